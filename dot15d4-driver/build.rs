@@ -7,15 +7,12 @@ fn main() {
     // (Variable, Type, Default value)
     // TODO: Set the default PAN ID to 0xffff once we implement association.
     let mut const_config: HashMap<&str, (&str, &str)> = HashMap::from([
-        ("MAC_MIN_BE", ("u8", "0")),
-        ("MAC_MAX_BE", ("u8", "8")),
-        ("MAC_MAX_CSMA_BACKOFFS", ("u8", "16")),
-        ("MAC_MAX_FRAME_RETRIES", ("u8", "3")),
         (
             "MAC_PAN_ID",
             ("PanId<[u8; 2]>", "PanId::new_owned([0xed, 0xfe])"),
         ),
         ("MAC_IMPLICIT_BROADCAST", ("bool", "false")),
+        ("PHY_CCA_MODE", ("CcaMode", "CcaMode::CarrierSense")),
     ]);
 
     // Make sure we get rerun if needed
@@ -27,7 +24,7 @@ fn main() {
     // Collect environment variables
     let mut data = String::new();
     // Write preamble
-    writeln!(data, "use crate::{{frame::PanId}};\n").unwrap();
+    writeln!(data, "use crate::{{config::CcaMode, frame::PanId}};\n").unwrap();
 
     for (var, value) in std::env::vars() {
         if let Some(name) = var.strip_prefix("DOT15D4_") {
@@ -0,0 +1,74 @@
+//! Wireshark extcap program sniffing with a radio co-processor attached over
+//! a serial line.
+//!
+//! Install the binary into the extcap directory of Wireshark (see "About
+//! Wireshark" > "Folders") and select the `dot15d4` interface. The serial
+//! port is expected to be configured already, e.g. with `stty raw`.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use dot15d4_driver::{
+    sniffer::{
+        extcap::{self, CaptureOptions, ExtcapRequest},
+        pcap::PcapWriter,
+        ChannelPlan, Sniffer,
+    },
+    socs::serial::SerialRadio,
+    test_clock::poll_ready,
+    time::Duration,
+};
+
+const INTERFACE: &str = "dot15d4";
+
+fn capture(options: CaptureOptions) -> io::Result<()> {
+    let device = options.device.ok_or(io::ErrorKind::NotFound)?;
+    let serial = OpenOptions::new().read(true).write(true).open(device)?;
+    let radio = SerialRadio::new(serial)?;
+    let plan = match options.channels[..] {
+        [channel] => ChannelPlan::Fixed(channel),
+        _ => ChannelPlan::Hopping {
+            channels: options.channels,
+            dwell: Duration::new(options.dwell_ms as i64 * 1_000),
+        },
+    };
+    let mut sniffer = Sniffer::new(radio, plan).map_err(|_| io::ErrorKind::Unsupported)?;
+
+    let output: Box<dyn Write> = if options.fifo == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(options.fifo)?)
+    };
+    let mut pcap = PcapWriter::new(output)?;
+    // Serial radio operations block until they complete.
+    poll_ready(sniffer.run(&mut pcap, None)).map_err(|error| io::Error::other(format!("{error:?}")))
+}
+
+fn main() -> ExitCode {
+    let request = match ExtcapRequest::parse(std::env::args().skip(1)) {
+        Ok(request) => request,
+        Err(error) => {
+            eprintln!("invalid arguments: {error:?}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut stdout = io::stdout();
+    let result = match request {
+        ExtcapRequest::Interfaces => {
+            extcap::write_interfaces(&mut stdout, INTERFACE, "IEEE 802.15.4 sniffer")
+        }
+        ExtcapRequest::Dlts { .. } => extcap::write_dlts(&mut stdout),
+        ExtcapRequest::Config { .. } => extcap::write_config(&mut stdout),
+        ExtcapRequest::Capture(options) => capture(options),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
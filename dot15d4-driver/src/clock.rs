@@ -0,0 +1,140 @@
+//! Monotonic clock and alarm consumed by the MAC engines.
+//!
+//! The MAC schedules its timeouts in nanoseconds against a [`MonotonicClock`]
+//! and an [`Alarm`] rather than against the ticks of a concrete timer:
+//!
+//! - Every [`RadioTimerApi`] implements both traits. Instants are converted
+//!   from timer ticks rounding down and alarms are scheduled rounding up to
+//!   the next tick, so that alarms never fire early.
+//! - Timers combining several peripherals implement the traits directly, e.g.
+//!   a low-power RTC that runs continuously together with a high-frequency
+//!   TIMER that is only started shortly before an alarm for precise timing.
+//! - The [`TestClock`](crate::test_clock::TestClock) that backs the host
+//!   simulator implements them through its radio timer implementation.
+//!
+//! Like [`RadioTimerApi`] the traits are implemented on types rather than on
+//! values: There is a single clock per radio.
+
+use core::future::Future;
+
+use crate::{
+    time::{Duration, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+/// A clock that never goes backwards, see the module documentation.
+pub trait MonotonicClock {
+    /// Returns the current instant with nanosecond nominal resolution.
+    ///
+    /// The actual resolution depends on the underlying timer.
+    fn now() -> Instant<Nanoseconds>;
+}
+
+/// A single alarm of a [`MonotonicClock`], see the module documentation.
+pub trait Alarm: MonotonicClock {
+    /// Schedules the alarm at the given instant, replacing any alarm that was
+    /// scheduled before.
+    ///
+    /// Implementations MAY fire late by up to their resolution but SHALL NOT
+    /// fire early. Instants in the past fire as soon as possible.
+    fn schedule(at: Instant<Nanoseconds>);
+
+    /// Cancels the scheduled alarm. An alarm that fired but was not awaited
+    /// yet is discarded.
+    ///
+    /// Clients cancel alarms they no longer wait for, e.g. a timeout that
+    /// lost a race against a reception, so that they don't fire spuriously
+    /// later on.
+    fn cancel();
+
+    /// Waits for the scheduled alarm and returns the instant at which it
+    /// fired.
+    ///
+    /// Implementations SHALL be cancellable.
+    fn wait() -> impl Future<Output = Instant<Nanoseconds>>;
+
+    /// Convenience method over [`Alarm::schedule()`] and [`Alarm::wait()`].
+    fn wait_until(at: Instant<Nanoseconds>) -> impl Future<Output = Instant<Nanoseconds>> {
+        Self::schedule(at);
+        Self::wait()
+    }
+
+    /// Waits for the given duration from now on.
+    fn wait_for(duration: Duration<Nanoseconds>) -> impl Future<Output = Instant<Nanoseconds>> {
+        Self::wait_until(<Self as MonotonicClock>::now().saturating_add(duration))
+    }
+}
+
+impl<Timer: RadioTimerApi> MonotonicClock for Timer {
+    fn now() -> Instant<Nanoseconds> {
+        <Timer as RadioTimerApi>::now().convert_into_rounding_down()
+    }
+}
+
+impl<Timer: RadioTimerApi> Alarm for Timer {
+    fn schedule(at: Instant<Nanoseconds>) {
+        Timer::schedule_alarm(at.convert_into_rounding_up());
+    }
+
+    fn cancel() {
+        Timer::cancel_alarm();
+    }
+
+    async fn wait() -> Instant<Nanoseconds> {
+        Timer::wait_for_alarm().await.convert_into_rounding_down()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use core::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{test_clock::TestClock, time::Hz};
+
+    #[test]
+    fn radio_timer() {
+        TestClock::reset();
+        TestClock::advance(Duration::new(3));
+        assert_eq!(<TestClock as MonotonicClock>::now(), Instant::new(3_000));
+
+        // Alarms are rounded up to the next microsecond.
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut alarm = pin!(TestClock::wait_until(Instant::new(4_001)));
+        assert!(alarm.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(5)));
+
+        TestClock::advance(Duration::new(1));
+        assert!(alarm.as_mut().poll(&mut cx).is_pending());
+        TestClock::advance(Duration::new(1));
+        assert_eq!(
+            alarm.as_mut().poll(&mut cx),
+            Poll::Ready(Instant::new(5_000))
+        );
+
+        let mut alarm = pin!(TestClock::wait_for(Duration::new(2_000)));
+        assert!(alarm.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(7)));
+    }
+
+    #[test]
+    fn cancel() {
+        TestClock::reset();
+        TestClock::schedule(Instant::new(1_000));
+        TestClock::cancel();
+        assert_eq!(TestClock::alarm(), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        // Scheduling the instant of a tick hits that very tick.
+        for tick in [1, 2, 3, 32_767, 32_768, 1 << 40] {
+            let at = Instant::<Hz<32_768>>::new(tick);
+            let ns: Instant<Nanoseconds> = at.convert_into_rounding_down();
+            assert_eq!(ns.convert_into_rounding_up::<Hz<32_768>>(), at);
+        }
+    }
+}
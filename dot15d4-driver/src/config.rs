@@ -1,112 +1,183 @@
-/// IEEE 802.15.4 channels
+/// A SUN channel plan, i.e. the channels of a SUN PHY in a frequency band
+/// (IEEE 802.15.4-2020, section 10.1.3.9).
+///
+/// The center frequency of channel `n` is ChanCenterFreq0 + `n` *
+/// ChanSpacing.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Channel {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SunChannelPlan {
+    /// ChanCenterFreq0, the center frequency of channel 0 in kHz.
+    pub center_frequency_0: u32,
+    /// ChanSpacing, the distance between the center frequencies of adjacent
+    /// channels in kHz.
+    pub channel_spacing: u32,
+    /// TotalNumChan, the number of channels.
+    pub num_channels: u16,
+}
+
+impl SunChannelPlan {
+    /// The 863-870 MHz band with 200 kHz channels (SUN FSK operating mode
+    /// #1).
+    pub const EU_863MHZ: Self = Self {
+        center_frequency_0: 863_125,
+        channel_spacing: 200,
+        num_channels: 34,
+    };
+
+    /// The 902-928 MHz band with 200 kHz channels (SUN FSK operating mode
+    /// #1).
+    pub const US_915MHZ: Self = Self {
+        center_frequency_0: 902_200,
+        channel_spacing: 200,
+        num_channels: 129,
+    };
+}
+
+/// The channel page and band of a [`Channel`] (IEEE 802.15.4-2020, section
+/// 10.1.3), i.e. the PHY and the channels available to it.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelPage {
+    /// O-QPSK in the 2450 MHz band, channels 11 to 26 of page 0.
+    #[default]
+    Oqpsk2450Mhz,
+    /// O-QPSK in the 868 MHz band, channel 0 of page 2.
+    Oqpsk868Mhz,
+    /// O-QPSK in the 915 MHz band, channels 1 to 10 of page 2.
+    Oqpsk915Mhz,
+    /// A SUN PHY with the given channel plan, page 9.
+    Sun(SunChannelPlan),
+}
+
+impl ChannelPage {
+    /// Return the number of the channel page.
+    pub const fn number(&self) -> u8 {
+        match self {
+            Self::Oqpsk2450Mhz => 0,
+            Self::Oqpsk868Mhz | Self::Oqpsk915Mhz => 2,
+            Self::Sun(_) => 9,
+        }
+    }
+
+    /// Return the lowest channel number of the band.
+    pub const fn first_channel(&self) -> u16 {
+        match self {
+            Self::Oqpsk2450Mhz => 11,
+            Self::Oqpsk868Mhz | Self::Sun(_) => 0,
+            Self::Oqpsk915Mhz => 1,
+        }
+    }
+
+    /// Return the highest channel number of the band.
+    pub const fn last_channel(&self) -> u16 {
+        match self {
+            Self::Oqpsk2450Mhz => 26,
+            Self::Oqpsk868Mhz => 0,
+            Self::Oqpsk915Mhz => 10,
+            Self::Sun(plan) => plan.num_channels.saturating_sub(1),
+        }
+    }
+
+    /// Return the number of channels of the band.
+    pub const fn num_channels(&self) -> u16 {
+        match self {
+            Self::Sun(plan) => plan.num_channels,
+            _ => self.last_channel() - self.first_channel() + 1,
+        }
+    }
+
+    /// Return the channel with the given number, `None` if the band has no
+    /// such channel.
+    pub const fn channel(self, number: u16) -> Option<Channel> {
+        if number < self.first_channel() || number > self.last_channel() {
+            return None;
+        }
+        // SUN channel plans may be empty.
+        if self.num_channels() == 0 {
+            return None;
+        }
+        Some(Channel { page: self, number })
+    }
+
+    /// Return an iterator over all channels of the band in ascending order.
+    pub fn channels(self) -> impl Iterator<Item = Channel> {
+        (self.first_channel()..=self.last_channel()).filter_map(move |number| self.channel(number))
+    }
+}
+
+/// An IEEE 802.15.4 channel, i.e. a channel number within a [`ChannelPage`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channel {
+    page: ChannelPage,
+    number: u16,
+}
+
+impl Channel {
     /// 2_405 MHz
-    _11,
+    pub const _11: Self = Self::oqpsk_2450mhz(11);
     /// 2_410 MHz
-    _12,
+    pub const _12: Self = Self::oqpsk_2450mhz(12);
     /// 2_415 MHz
-    _13,
+    pub const _13: Self = Self::oqpsk_2450mhz(13);
     /// 2_420 MHz
-    _14,
+    pub const _14: Self = Self::oqpsk_2450mhz(14);
     /// 2_425 MHz
-    _15,
+    pub const _15: Self = Self::oqpsk_2450mhz(15);
     /// 2_430 MHz
-    _16,
+    pub const _16: Self = Self::oqpsk_2450mhz(16);
     /// 2_435 MHz
-    _17,
+    pub const _17: Self = Self::oqpsk_2450mhz(17);
     /// 2_440 MHz
-    _18,
+    pub const _18: Self = Self::oqpsk_2450mhz(18);
     /// 2_445 MHz
-    _19,
+    pub const _19: Self = Self::oqpsk_2450mhz(19);
     /// 2_450 MHz
-    _20,
+    pub const _20: Self = Self::oqpsk_2450mhz(20);
     /// 2_455 MHz
-    _21,
+    pub const _21: Self = Self::oqpsk_2450mhz(21);
     /// 2_460 MHz
-    _22,
+    pub const _22: Self = Self::oqpsk_2450mhz(22);
     /// 2_465 MHz
-    _23,
+    pub const _23: Self = Self::oqpsk_2450mhz(23);
     /// 2_470 MHz
-    _24,
+    pub const _24: Self = Self::oqpsk_2450mhz(24);
     /// 2_475 MHz
-    _25,
+    pub const _25: Self = Self::oqpsk_2450mhz(25);
     /// 2_480 MHz
-    _26,
-}
+    pub const _26: Self = Self::oqpsk_2450mhz(26);
 
-impl TryFrom<u8> for Channel {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            11 => Ok(Channel::_11),
-            12 => Ok(Channel::_12),
-            13 => Ok(Channel::_13),
-            14 => Ok(Channel::_14),
-            15 => Ok(Channel::_15),
-            16 => Ok(Channel::_16),
-            17 => Ok(Channel::_17),
-            18 => Ok(Channel::_18),
-            19 => Ok(Channel::_19),
-            20 => Ok(Channel::_20),
-            21 => Ok(Channel::_21),
-            22 => Ok(Channel::_22),
-            23 => Ok(Channel::_23),
-            24 => Ok(Channel::_24),
-            25 => Ok(Channel::_25),
-            26 => Ok(Channel::_26),
-            _ => Err(()),
+    const fn oqpsk_2450mhz(number: u16) -> Self {
+        Self {
+            page: ChannelPage::Oqpsk2450Mhz,
+            number,
         }
     }
-}
 
-impl TryFrom<i32> for Channel {
-    type Error = ();
+    /// Creates the channel with the given number in the given band, `None` if
+    /// the band has no such channel.
+    pub const fn new(page: ChannelPage, number: u16) -> Option<Self> {
+        page.channel(number)
+    }
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            11 => Ok(Channel::_11),
-            12 => Ok(Channel::_12),
-            13 => Ok(Channel::_13),
-            14 => Ok(Channel::_14),
-            15 => Ok(Channel::_15),
-            16 => Ok(Channel::_16),
-            17 => Ok(Channel::_17),
-            18 => Ok(Channel::_18),
-            19 => Ok(Channel::_19),
-            20 => Ok(Channel::_20),
-            21 => Ok(Channel::_21),
-            22 => Ok(Channel::_22),
-            23 => Ok(Channel::_23),
-            24 => Ok(Channel::_24),
-            25 => Ok(Channel::_25),
-            26 => Ok(Channel::_26),
-            _ => Err(()),
-        }
+    /// Return the channel page and band of the channel.
+    pub const fn page(&self) -> ChannelPage {
+        self.page
+    }
+
+    /// Return the channel number.
+    pub const fn number(&self) -> u16 {
+        self.number
     }
-}
 
-impl From<Channel> for u8 {
-    fn from(ch: Channel) -> u8 {
-        match ch {
-            Channel::_11 => 11,
-            Channel::_12 => 12,
-            Channel::_13 => 13,
-            Channel::_14 => 14,
-            Channel::_15 => 15,
-            Channel::_16 => 16,
-            Channel::_17 => 17,
-            Channel::_18 => 18,
-            Channel::_19 => 19,
-            Channel::_20 => 20,
-            Channel::_21 => 21,
-            Channel::_22 => 22,
-            Channel::_23 => 23,
-            Channel::_24 => 24,
-            Channel::_25 => 25,
-            Channel::_26 => 26,
+    /// Return the center frequency of the channel in kHz.
+    pub const fn center_frequency(&self) -> u32 {
+        let number = self.number as u32;
+        match self.page {
+            ChannelPage::Oqpsk2450Mhz => 2_405_000 + 5_000 * (number - 11),
+            ChannelPage::Oqpsk868Mhz => 868_300,
+            ChannelPage::Oqpsk915Mhz => 906_000 + 2_000 * (number - 1),
+            ChannelPage::Sun(plan) => plan.center_frequency_0 + plan.channel_spacing * number,
         }
     }
 }
@@ -117,22 +188,74 @@ impl Default for Channel {
     }
 }
 
-// TODO: expose the other variants in `pac::CCAMODE_A`
-/// Clear Channel Assessment method
+impl TryFrom<u8> for Channel {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ChannelPage::Oqpsk2450Mhz.channel(value as u16).ok_or(())
+    }
+}
+
+impl TryFrom<i32> for Channel {
+    type Error = ();
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        u8::try_from(value).map_err(|_| ())?.try_into()
+    }
+}
+
+/// Clear Channel Assessment method, see IEEE 802.15.4-2024, section 10.2.8.
+///
+/// The CCA mode is a PHY-wide setting. It applies to all CCAs performed by the
+/// driver, i.e. to CSMA-CA as well as to TSCH CCA.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum CcaMode {
-    /// Carrier sense
-    CarrierSense,
-    /// Energy Detection / Energy Above Threshold
+    /// CCA mode 1: Energy above threshold.
     EnergyDetection {
-        /// Energy measurements above this value mean that the channel is assumed to be busy.
-        /// Note the measurement range is 0..0xFF - where 0 means that the received power was
-        /// less than 10 dB above the selected receiver sensitivity. This value is not given in d_bm(),
-        /// but can be converted. See the nrf52840 Product Specification Section 6.20.12.4
-        /// for details.
+        /// Energy measurements above this value mean that the channel is
+        /// assumed to be busy.
+        ///
+        /// Note: The unit of this value is driver-specific. On nRF devices,
+        ///       the measurement range is 0..0xFF - where 0 means that the
+        ///       received power was less than 10 dB above the selected
+        ///       receiver sensitivity. This value is not given in dBm, but can
+        ///       be converted. See the nrf52840 Product Specification Section
+        ///       6.20.12.4 for details.
+        ed_threshold: u8,
+    },
+    /// CCA mode 2: Carrier sense only.
+    #[default]
+    CarrierSense,
+    /// CCA mode 3 (logical AND): Carrier sense with energy above threshold,
+    /// i.e. the medium is reported busy only if a carrier is detected and the
+    /// energy is above the threshold.
+    CarrierSenseAndEnergyDetection {
+        /// See [`CcaMode::EnergyDetection`].
+        ed_threshold: u8,
+    },
+    /// CCA mode 3 (logical OR): Carrier sense or energy above threshold, i.e.
+    /// the medium is reported busy if either a carrier is detected or the
+    /// energy is above the threshold.
+    CarrierSenseOrEnergyDetection {
+        /// See [`CcaMode::EnergyDetection`].
         ed_threshold: u8,
     },
 }
 
+impl CcaMode {
+    /// The ED threshold used by this CCA mode or [`None`] if the CCA mode
+    /// does not involve energy detection.
+    pub const fn ed_threshold(&self) -> Option<u8> {
+        match self {
+            CcaMode::CarrierSense => None,
+            CcaMode::EnergyDetection { ed_threshold }
+            | CcaMode::CarrierSenseAndEnergyDetection { ed_threshold }
+            | CcaMode::CarrierSenseOrEnergyDetection { ed_threshold } => Some(*ed_threshold),
+        }
+    }
+}
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct RxConfig {
@@ -154,3 +277,42 @@ impl TxConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_pages() {
+        assert_eq!(Channel::_11.center_frequency(), 2_405_000);
+        assert_eq!(Channel::_26.center_frequency(), 2_480_000);
+        assert_eq!(Channel::try_from(15), Ok(Channel::_15));
+        assert_eq!(Channel::try_from(10u8), Err(()));
+        assert_eq!(ChannelPage::Oqpsk2450Mhz.channels().count(), 16);
+
+        let page = ChannelPage::Oqpsk915Mhz;
+        assert_eq!(page.number(), 2);
+        assert_eq!(page.channels().count(), 10);
+        assert_eq!(Channel::new(page, 10).unwrap().center_frequency(), 924_000);
+        assert_eq!(Channel::new(page, 11), None);
+        let channel = ChannelPage::Oqpsk868Mhz.channel(0).unwrap();
+        assert_eq!(channel.center_frequency(), 868_300);
+    }
+
+    #[test]
+    fn sun_channel_plans() {
+        let page = ChannelPage::Sun(SunChannelPlan::US_915MHZ);
+        assert_eq!(page.number(), 9);
+        assert_eq!(page.num_channels(), 129);
+        assert_eq!(page.channel(0).unwrap().center_frequency(), 902_200);
+        assert_eq!(page.channel(128).unwrap().center_frequency(), 927_800);
+        assert_eq!(page.channel(129), None);
+
+        let empty = ChannelPage::Sun(SunChannelPlan {
+            num_channels: 0,
+            ..SunChannelPlan::EU_863MHZ
+        });
+        assert_eq!(empty.channel(0), None);
+        assert_eq!(empty.channels().count(), 0);
+    }
+}
@@ -53,8 +53,14 @@ pub const MAC_UNIT_BACKOFF_PERIOD: Duration<SymbolsOQpsk250kB> =
 pub const MAC_SIFS: Duration<SymbolsOQpsk250kB> = Duration::new(12);
 /// LIFS: 40 symbols = 480µs
 pub const MAC_LIFS: Duration<SymbolsOQpsk250kB> = Duration::new(40);
+/// phyMaxFrameDuration: phySHRDuration + (aMaxPhyPacketSize + 1) *
+/// phySymbolsPerOctet = 10 + 128 * 2 symbols = 4256µs
+pub const PHY_MAX_FRAME_DURATION: Duration<SymbolsOQpsk250kB> = Duration::new(266);
 /// AIFS=1ms, for SUN PHY, LECIM PHY, TVWS PHY, SIFS otherwise
 pub const MAC_AIFS: Duration<SymbolsOQpsk250kB> = MAC_SIFS;
+/// macAckWaitDuration: aUnitBackoffPeriod + aTurnaroundTime + phySHRDuration +
+/// 6 * phySymbolsPerOctet = 20 + 12 + 10 + 12 symbols = 864µs
+pub const MAC_ACK_WAIT_DURATION: Duration<SymbolsOQpsk250kB> = Duration::new(54);
 
 /// O-QPSK Start of Frame Delimiter
 pub const DEFAULT_SFD: u8 = 0xA7;
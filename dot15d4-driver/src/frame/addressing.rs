@@ -1,17 +1,26 @@
 //! IEEE 802.15.4 addressing related fields.
-use core::{fmt::Debug, ops::Range};
+use core::{fmt::Debug, ops::Range, str::FromStr};
 
 use dot15d4_util::{Error, Result};
 
-use super::{FrameControl, FrameVersion};
+use super::{FrameControl, FrameError, FrameErrorKind, FrameVersion};
 
 const BROADCAST_ADDR_DATA: [u8; 2] = [0xff, 0xff];
 /// The broadcast PAN id.
 pub const BROADCAST_PAN_ID: PanId<&'static [u8]> = PanId(&BROADCAST_ADDR_DATA);
+/// The broadcast PAN.
+pub const BROADCAST_PAN: Pan = Pan(0xffff);
+
+/// The universal/local bit of the first octet of an EUI-64.
+const EUI64_LOCAL_BIT: u8 = 0x02;
+/// The individual/group bit of the first octet of an EUI-64.
+const EUI64_GROUP_BIT: u8 = 0x01;
 
 /// IEEE 802.15.4 addressing mode.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddressingMode {
     /// The address is absent.
     Absent = 0b00,
@@ -57,6 +66,15 @@ impl ShortAddress<[u8; 2]> {
     pub const fn new_owned(le_bytes: [u8; 2]) -> Self {
         Self(le_bytes)
     }
+
+    pub fn from_be_bytes(mut be_bytes: [u8; 2]) -> Self {
+        be_bytes.reverse();
+        Self(be_bytes)
+    }
+
+    pub fn from_u16(short_addr: u16) -> Self {
+        Self(short_addr.to_le_bytes())
+    }
 }
 
 impl<Bytes: AsRef<[u8]>> ShortAddress<Bytes> {
@@ -77,18 +95,16 @@ impl<Bytes: AsRef<[u8]>> ShortAddress<Bytes> {
         be_bytes
     }
 
-    pub fn from_be_bytes(mut be_bytes: [u8; 2]) -> ShortAddress<[u8; 2]> {
-        be_bytes.reverse();
-        ShortAddress::new(be_bytes)
-    }
-
     pub fn into_u16(&self) -> u16 {
         // Safety: Length was checked on instantiation.
         u16::from_le_bytes(self.0.as_ref().try_into().unwrap())
     }
 
-    pub fn from_u16(short_addr: u16) -> ShortAddress<[u8; 2]> {
-        ShortAddress::new(short_addr.to_le_bytes())
+    /// Return the IPv6 interface identifier derived from the short address
+    /// (RFC 4944, section 6), i.e. `0000:00ff:fe00:XXXX`.
+    pub fn to_interface_identifier(&self) -> [u8; 8] {
+        let [high, low] = self.into_be_bytes();
+        [0x00, 0x00, 0x00, 0xff, 0xfe, 0x00, high, low]
     }
 }
 
@@ -124,6 +140,30 @@ impl<Bytes: AsMut<[u8]>> AsMut<[u8]> for ShortAddress<Bytes> {
     }
 }
 
+impl FromStr for ShortAddress<[u8; 2]> {
+    type Err = Error;
+
+    /// Parses a short address in little-endian "aa:bb" notation.
+    fn from_str(s: &str) -> Result<Self> {
+        parse_le_bytes(s).map(Self::new_owned)
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for ShortAddress<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let bytes = self.as_ref();
+        write!(f, "{:02x}:{:02x}", bytes[0], bytes[1])
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Bytes: AsRef<[u8]>> defmt::Format for ShortAddress<Bytes> {
+    fn format(&self, f: defmt::Formatter) {
+        let bytes = self.as_ref();
+        defmt::write!(f, "{=u8:02x}:{=u8:02x}", bytes[0], bytes[1])
+    }
+}
+
 /// Extended address field.
 ///
 /// The internal representation is little-endian.
@@ -159,6 +199,40 @@ impl<Bytes: AsRef<[u8]>> ExtendedAddress<Bytes> {
         be_bytes.reverse();
         ExtendedAddress::new(be_bytes)
     }
+
+    /// Return the OUI, i.e. the first three octets of the EUI-64 in
+    /// big-endian notation.
+    pub fn oui(&self) -> [u8; 3] {
+        let be_bytes = self.into_be_bytes();
+        [be_bytes[0], be_bytes[1], be_bytes[2]]
+    }
+
+    /// Query whether the EUI-64 is universally administered, i.e. whether its
+    /// universal/local bit is cleared.
+    pub fn is_universal(&self) -> bool {
+        self.first_eui64_octet() & EUI64_LOCAL_BIT == 0
+    }
+
+    /// Query whether the EUI-64 identifies a group, i.e. whether its
+    /// individual/group bit is set.
+    pub fn is_group(&self) -> bool {
+        self.first_eui64_octet() & EUI64_GROUP_BIT != 0
+    }
+
+    /// Return the IPv6 interface identifier derived from the EUI-64 (RFC 4944,
+    /// section 6), i.e. the EUI-64 in big-endian notation with the
+    /// universal/local bit inverted.
+    pub fn to_interface_identifier(&self) -> [u8; 8] {
+        let mut iid = self.into_be_bytes();
+        iid[0] ^= EUI64_LOCAL_BIT;
+        iid
+    }
+
+    /// Return the first octet of the EUI-64 in big-endian notation, i.e. the
+    /// last octet on the air.
+    fn first_eui64_octet(&self) -> u8 {
+        self.as_ref()[7]
+    }
 }
 
 impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> ExtendedAddress<Bytes> {
@@ -169,10 +243,21 @@ impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> ExtendedAddress<Bytes> {
 
     pub fn set_be_bytes<SrcBytes: AsRef<[u8]>>(&mut self, be_bytes: SrcBytes) {
         debug_assert_eq!(be_bytes.as_ref().len(), 8, "invalid");
-        let mut be_bytes = <[u8; 2]>::try_from(be_bytes.as_ref()).expect("invalid");
+        let mut be_bytes = <[u8; 8]>::try_from(be_bytes.as_ref()).expect("invalid");
         be_bytes.reverse();
         self.as_mut().clone_from_slice(be_bytes.as_ref());
     }
+
+    /// Set or clear the universal/local bit of the EUI-64, see
+    /// [`ExtendedAddress::is_universal()`].
+    pub fn set_local(&mut self, local: bool) {
+        let octet = &mut self.as_mut()[7];
+        if local {
+            *octet |= EUI64_LOCAL_BIT;
+        } else {
+            *octet &= !EUI64_LOCAL_BIT;
+        }
+    }
 }
 
 impl<Bytes: AsRef<[u8]>> AsRef<[u8]> for ExtendedAddress<Bytes> {
@@ -189,6 +274,46 @@ impl<Bytes: AsMut<[u8]>> AsMut<[u8]> for ExtendedAddress<Bytes> {
     }
 }
 
+impl FromStr for ExtendedAddress<[u8; 8]> {
+    type Err = Error;
+
+    /// Parses an extended address in little-endian "aa:bb:cc:dd:ee:ff:00:11"
+    /// notation.
+    fn from_str(s: &str) -> Result<Self> {
+        parse_le_bytes(s).map(Self::new_owned)
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for ExtendedAddress<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let bytes = self.as_ref();
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Bytes: AsRef<[u8]>> defmt::Format for ExtendedAddress<Bytes> {
+    fn format(&self, f: defmt::Formatter) {
+        let bytes = self.as_ref();
+        defmt::write!(
+            f,
+            "{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}",
+            bytes[0],
+            bytes[1],
+            bytes[2],
+            bytes[3],
+            bytes[4],
+            bytes[5],
+            bytes[6],
+            bytes[7]
+        )
+    }
+}
+
 /// PAN id field.
 ///
 /// The internal representation is little-endian.
@@ -200,6 +325,15 @@ impl PanId<[u8; 2]> {
     pub const fn new_owned(le_bytes: [u8; 2]) -> Self {
         Self(le_bytes)
     }
+
+    pub fn from_be_bytes(mut be_bytes: [u8; 2]) -> Self {
+        be_bytes.reverse();
+        Self(be_bytes)
+    }
+
+    pub fn from_u16(pan_id: u16) -> Self {
+        Self(pan_id.to_le_bytes())
+    }
 }
 
 impl<Bytes: AsRef<[u8]>> PanId<Bytes> {
@@ -215,19 +349,10 @@ impl<Bytes: AsRef<[u8]>> PanId<Bytes> {
         be_bytes
     }
 
-    pub fn from_be_bytes(mut be_bytes: [u8; 2]) -> PanId<[u8; 2]> {
-        be_bytes.reverse();
-        PanId::new(be_bytes)
-    }
-
     pub fn into_u16(&self) -> u16 {
         // Safety: Length was checked on instantiation.
         u16::from_le_bytes(self.0.as_ref().try_into().unwrap())
     }
-
-    pub fn from_u16(pan_id: u16) -> PanId<[u8; 2]> {
-        PanId::new(pan_id.to_le_bytes())
-    }
 }
 
 impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> PanId<Bytes> {
@@ -262,7 +387,88 @@ impl<Bytes: AsMut<[u8]>> AsMut<[u8]> for PanId<Bytes> {
     }
 }
 
+impl FromStr for PanId<[u8; 2]> {
+    type Err = Error;
+
+    /// Parses a PAN ID from its hex value with optional "0x" prefix, e.g.
+    /// "0xabcd".
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.is_empty() || hex.len() > 4 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error);
+        }
+
+        u16::from_str_radix(hex, 16)
+            .map(PanId::from_u16)
+            .map_err(|_| Error)
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for PanId<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#06x}", self.into_u16())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Bytes: AsRef<[u8]>> defmt::Format for PanId<Bytes> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=u16:#06x}", self.into_u16())
+    }
+}
+
+/// A PAN identifier as a plain value, e.g. of the PIB, as opposed to the
+/// [`PanId`] field of a frame.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Pan(pub u16);
+
+impl Pan {
+    /// Query whether this is the broadcast PAN, see [`BROADCAST_PAN`].
+    pub const fn is_broadcast(&self) -> bool {
+        self.0 == BROADCAST_PAN.0
+    }
+
+    /// Return the PAN ID field of this PAN.
+    pub fn to_pan_id(&self) -> PanId<[u8; 2]> {
+        PanId::from_u16(self.0)
+    }
+}
+
+impl From<u16> for Pan {
+    fn from(pan_id: u16) -> Self {
+        Self(pan_id)
+    }
+}
+
+impl From<Pan> for u16 {
+    fn from(pan: Pan) -> Self {
+        pan.0
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> From<PanId<Bytes>> for Pan {
+    fn from(pan_id: PanId<Bytes>) -> Self {
+        Self(pan_id.into_u16())
+    }
+}
+
+impl core::fmt::Display for Pan {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#06x}", self.0)
+    }
+}
+
 /// An IEEE 802.15.4 address.
+///
+/// Like all addressing fields, addresses are represented in memory exactly as
+/// they appear on the air, i.e. in little-endian byte order. Readers and
+/// writers copy addresses verbatim so that parsing and re-emitting a frame
+/// preserves the byte order. Conversions from and to the big-endian notation
+/// of extended addresses (EUI-64) are explicit, see
+/// [`ExtendedAddress::into_be_bytes()`] and [`ExtendedAddress::from_be_bytes()`].
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum Address<Bytes> {
@@ -309,33 +515,56 @@ impl<Bytes> Address<Bytes> {
 impl Address<Vec<u8>> {
     /// Parse an address from a string.
     ///
-    /// The string is assumed to encode bytes in little-endian order.
+    /// The string is assumed to encode bytes in little-endian order, i.e. in
+    /// the format produced by the [`Display`](core::fmt::Display) impl. An
+    /// empty string or "absent" denotes an absent address.
     pub fn parse(address: &str) -> Result<Self> {
-        if address.is_empty() {
-            return Ok(Address::Absent);
-        }
-
-        let parts: std::vec::Vec<&str> = address.split(':').collect();
-        match parts.len() {
-            2 => {
-                let mut le_bytes = Vec::with_capacity(2);
-                for part in parts.iter() {
-                    le_bytes.push(u8::from_str_radix(part, 16).unwrap());
-                }
-                Ok(Address::Short(ShortAddress::new_unchecked(le_bytes)))
+        match address {
+            "" | "absent" => Ok(Address::Absent),
+            _ if address.split(':').count() == 2 => {
+                let short_address: ShortAddress<[u8; 2]> = address.parse()?;
+                Ok(Address::Short(ShortAddress::new_unchecked(
+                    short_address.0.to_vec(),
+                )))
             }
-            8 => {
-                let mut le_bytes = Vec::with_capacity(8);
-                for part in parts.iter() {
-                    le_bytes.push(u8::from_str_radix(part, 16).unwrap());
-                }
-                Ok(Address::Extended(ExtendedAddress::new_unchecked(le_bytes)))
+            _ => {
+                let extended_address: ExtendedAddress<[u8; 8]> = address.parse()?;
+                Ok(Address::Extended(ExtendedAddress::new_unchecked(
+                    extended_address.0.to_vec(),
+                )))
             }
-            _ => Err(Error),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl FromStr for Address<Vec<u8>> {
+    type Err = Error;
+
+    fn from_str(address: &str) -> Result<Self> {
+        Self::parse(address)
+    }
+}
+
+/// Parses colon-separated hex octets, e.g. "aa:bb".
+fn parse_le_bytes<const N: usize>(s: &str) -> Result<[u8; N]> {
+    let mut le_bytes = [0; N];
+    let mut parts = s.split(':');
+    for b in le_bytes.iter_mut() {
+        let part = parts.next().ok_or(Error)?;
+        if part.is_empty() || part.len() > 2 || !part.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error);
+        }
+        *b = u8::from_str_radix(part, 16).map_err(|_| Error)?;
+    }
+
+    if parts.next().is_some() {
+        return Err(Error);
+    }
+
+    Ok(le_bytes)
+}
+
 #[cfg(feature = "std")]
 impl From<Address<&[u8]>> for Address<Vec<u8>> {
     fn from(value: Address<&[u8]>) -> Self {
@@ -357,6 +586,34 @@ impl<Bytes: AsRef<[u8]>> Address<Bytes> {
         !self.is_broadcast()
     }
 
+    /// Query whether the address is an extended address of an individual
+    /// device, see [`ExtendedAddress::is_group()`].
+    pub fn is_extended_unicast(&self) -> bool {
+        match self {
+            Address::Extended(extended_address) => !extended_address.is_group(),
+            _ => false,
+        }
+    }
+
+    /// Return the OUI of an extended address, see [`ExtendedAddress::oui()`].
+    pub fn oui(&self) -> Option<[u8; 3]> {
+        match self {
+            Address::Extended(extended_address) => Some(extended_address.oui()),
+            _ => None,
+        }
+    }
+
+    /// Return the IPv6 interface identifier derived from the address for
+    /// 6LoWPAN, see [`ShortAddress::to_interface_identifier()`] and
+    /// [`ExtendedAddress::to_interface_identifier()`].
+    pub fn to_interface_identifier(&self) -> Option<[u8; 8]> {
+        match self {
+            Address::Absent => None,
+            Address::Short(short_address) => Some(short_address.to_interface_identifier()),
+            Address::Extended(extended_address) => Some(extended_address.to_interface_identifier()),
+        }
+    }
+
     /// Query whether this address is the broadcast address.
     pub fn is_broadcast(&self) -> bool {
         match self {
@@ -454,24 +711,25 @@ impl<Bytes: AsRef<[u8]>> core::fmt::Display for Address<Bytes> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Address::Absent => write!(f, "absent"),
-            Address::Short(bytes) => {
-                let bytes = bytes.as_ref();
-                write!(f, "{:02x}:{:02x}", bytes[0], bytes[1])
-            }
-            Address::Extended(bytes) => {
-                let bytes = bytes.as_ref();
-                write!(
-                    f,
-                    "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                    bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]
-                )
-            }
+            Address::Short(short_address) => write!(f, "{short_address}"),
+            Address::Extended(extended_address) => write!(f, "{extended_address}"),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Bytes: AsRef<[u8]>> defmt::Format for Address<Bytes> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Address::Absent => defmt::write!(f, "absent"),
+            Address::Short(short_address) => defmt::write!(f, "{}", short_address),
+            Address::Extended(extended_address) => defmt::write!(f, "{}", extended_address),
         }
     }
 }
 
 /// A reader/writer for the IEEE 802.15.4 Addressing Fields.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AddressingFields<Bytes> {
     dst_addr_offset: u8,
     src_pan_id_offset: u8,
@@ -510,17 +768,22 @@ impl<Bytes: AsRef<[u8]>> AddressingFields<Bytes> {
     ///
     /// # Errors
     ///
-    /// This function will check the length of the buffer to ensure it is large
-    /// enough to contain the addressing fields. If the buffer is too small, an
-    /// error will be returned.
-    pub fn new(le_bytes: Bytes, repr: AddressingRepr) -> Result<Self> {
-        let expected_len = repr.addressing_fields_length()? as usize;
-        if le_bytes.as_ref().len() != expected_len {
-            return Err(Error);
+    /// - [`FrameErrorKind::InvalidAddressingCombination`] if the addressing
+    ///   representation is invalid,
+    /// - [`FrameErrorKind::InvalidLength`] if the length of the buffer doesn't
+    ///   match the length of the addressing fields.
+    pub fn new(le_bytes: Bytes, repr: AddressingRepr) -> core::result::Result<Self, FrameError> {
+        let expected =
+            repr.addressing_fields_length()
+                .map_err(|_| FrameErrorKind::InvalidAddressingCombination)? as usize;
+        let got = le_bytes.as_ref().len();
+        if got != expected {
+            return Err(FrameErrorKind::InvalidLength { expected, got }.into());
         }
 
         // Safety: We checked the length of the given bytes buffer.
         unsafe { Self::new_unchecked(le_bytes, repr) }
+            .map_err(|_| FrameErrorKind::InvalidAddressingCombination.into())
     }
 
     /// Create a new [`AddressingFields`] reader/writer from a given
@@ -687,7 +950,7 @@ impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> AddressingFields<Bytes> {
         match addr.len() {
             0 => Some(Address::Absent),
             2 => Some(Address::Short(ShortAddress(addr))),
-            4 => Some(Address::Extended(ExtendedAddress(addr))),
+            8 => Some(Address::Extended(ExtendedAddress(addr))),
             // Safety: This is a guarantee of AddressingRepr.
             _ => unreachable!(),
         }
@@ -705,6 +968,9 @@ impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> AddressingFields<Bytes> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PanIdCompressionRepr {
     Yes,
     No,
@@ -712,6 +978,8 @@ pub enum PanIdCompressionRepr {
 } // 1 byte
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddressingRepr {
     pub(crate) dst: AddressingMode,
     pub(crate) src: AddressingMode,
@@ -719,6 +987,26 @@ pub struct AddressingRepr {
     pub(crate) pan_id_compression: PanIdCompressionRepr,
 } // 4 bytes
 
+/// Only generates known addressing modes as required by
+/// [`AddressingRepr::new()`].
+#[cfg(feature = "fuzz")]
+impl<'a> arbitrary::Arbitrary<'a> for AddressingRepr {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const KNOWN_MODES: [AddressingMode; 3] = [
+            AddressingMode::Absent,
+            AddressingMode::Short,
+            AddressingMode::Extended,
+        ];
+
+        Ok(Self::new(
+            *u.choose(&KNOWN_MODES)?,
+            *u.choose(&KNOWN_MODES)?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
 impl AddressingRepr {
     /// Instantiate a new addressing representation.
     ///
@@ -754,15 +1042,30 @@ impl AddressingRepr {
         Self::new(dst, src, pan_ids_equal, PanIdCompressionRepr::Legacy)
     }
 
-    /// Safety: The frame version and addressing modes must be known.
+    /// Derives the addressing representation from the frame control field.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::InvalidFrameVersion`] if the frame version is
+    ///   reserved,
+    /// - [`FrameErrorKind::InvalidAddressingCombination`] if the combination
+    ///   of addressing modes and PAN ID compression is not allowed.
     pub fn from_frame_control<Bytes: AsRef<[u8]>>(
         frame_control: FrameControl<Bytes>,
-    ) -> Result<Option<Self>> {
+    ) -> core::result::Result<Option<Self>, FrameError> {
         let dst = frame_control.dst_addressing_mode();
         let src = frame_control.src_addressing_mode();
+        if matches!(dst, AddressingMode::Unknown) || matches!(src, AddressingMode::Unknown) {
+            return Err(FrameErrorKind::InvalidAddressingCombination.into());
+        }
         let frame_version = frame_control.frame_version();
         let (pan_id_compression, pan_ids_equal) = match frame_version {
             FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
+                // Legacy frames without any addressing fields, e.g.
+                // acknowledgements.
+                if matches!(dst, AddressingMode::Absent) && matches!(src, AddressingMode::Absent) {
+                    return Ok(None);
+                }
                 let pan_id_compression = PanIdCompressionRepr::Legacy;
 
                 // See see IEEE 802.15.4-2024, section 7.2.2.6
@@ -811,11 +1114,14 @@ impl AddressingRepr {
 
                 (pan_id_compression, pan_ids_equal)
             }
-            FrameVersion::Unknown => return Err(Error),
+            FrameVersion::Unknown => return Err(FrameErrorKind::InvalidFrameVersion.into()),
         };
 
         let addressing = Self::new(dst, src, pan_ids_equal, pan_id_compression);
-        let addressing = if addressing.addressing_fields_length()? == 0 {
+        let addressing_fields_length = addressing
+            .addressing_fields_length()
+            .map_err(|_| FrameErrorKind::InvalidAddressingCombination)?;
+        let addressing = if addressing_fields_length == 0 {
             None
         } else {
             Some(addressing)
@@ -838,7 +1144,8 @@ impl AddressingRepr {
     /// Pan ID compression
     pub const fn pan_id_compression(&self) -> bool {
         match self.pan_id_compression {
-            PanIdCompressionRepr::Yes | PanIdCompressionRepr::No => true,
+            PanIdCompressionRepr::Yes => true,
+            PanIdCompressionRepr::No => false,
             PanIdCompressionRepr::Legacy => match (self.dst, self.src) {
                 (AddressingMode::Short, AddressingMode::Short)
                 | (AddressingMode::Short, AddressingMode::Extended)
@@ -850,6 +1157,12 @@ impl AddressingRepr {
         }
     }
 
+    /// PAN ID compression representation, [`PanIdCompressionRepr::Legacy`]
+    /// for frame versions before IEEE 802.15.4-2015.
+    pub const fn pan_id_compression_repr(&self) -> PanIdCompressionRepr {
+        self.pan_id_compression
+    }
+
     /// Destination [`AddressingMode`]
     pub const fn dst_addr_mode(&self) -> AddressingMode {
         self.dst
@@ -978,6 +1291,48 @@ mod tests {
         assert_eq!(Address::Extended(SOME_EXTENDED_ADDRESS).length(), 8);
     }
 
+    #[test]
+    fn from_frame_control_errors() {
+        // Reserved frame version.
+        let fc = [0x41, 0xb8];
+        assert_eq!(
+            AddressingRepr::from_frame_control(FrameControl::new_unchecked(&fc))
+                .unwrap_err()
+                .kind(),
+            FrameErrorKind::InvalidFrameVersion
+        );
+
+        // Reserved destination addressing mode.
+        let fc = [0x41, 0x94];
+        assert_eq!(
+            AddressingRepr::from_frame_control(FrameControl::new_unchecked(&fc))
+                .unwrap_err()
+                .kind(),
+            FrameErrorKind::InvalidAddressingCombination
+        );
+
+        // No addressing fields (IEEE 802.15.4-2006 acknowledgement).
+        let fc = [0x02, 0x10];
+        assert_eq!(
+            AddressingRepr::from_frame_control(FrameControl::new_unchecked(&fc)),
+            Ok(None)
+        );
+
+        // Short addresses with PAN ID compression (IEEE 802.15.4-2006).
+        let fc = [0x41, 0x98];
+        let repr = AddressingRepr::from_frame_control(FrameControl::new_unchecked(&fc))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            AddressingFields::new(&[0; 5][..], repr).unwrap_err().kind(),
+            FrameErrorKind::InvalidLength {
+                expected: 6,
+                got: 5
+            }
+        );
+        assert!(AddressingFields::new(&[0; 6][..], repr).is_ok());
+    }
+
     #[test]
     fn addressing_mode() {
         assert_eq!(AddressingMode::from(0b00), AddressingMode::Absent);
@@ -1105,6 +1460,187 @@ mod tests {
         check!((PanIdCompressionRepr::Yes, Absent, Absent, true) -> Some((true, Absent, false, Absent)));
     }
 
+    #[test]
+    fn be_bytes() {
+        let mut short_address = ShortAddress::new_owned([0; 2]);
+        short_address.set_be_bytes([0x12, 0x34]);
+        assert_eq!(short_address.as_ref(), &[0x34, 0x12]);
+        assert_eq!(short_address.into_be_bytes(), [0x12, 0x34]);
+
+        let be_bytes = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let mut extended_address = ExtendedAddress::new_owned([0; 8]);
+        extended_address.set_be_bytes(be_bytes);
+        assert_eq!(
+            extended_address.as_ref(),
+            &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(extended_address.into_be_bytes(), be_bytes);
+        assert_eq!(
+            ExtendedAddress::<[u8; 8]>::from_be_bytes(be_bytes),
+            extended_address
+        );
+    }
+
+    #[test]
+    fn eui64() {
+        // A universally administered EUI-64 of OUI 00:12:4b.
+        let mut extended_address = ExtendedAddress::<[u8; 8]>::from_be_bytes([
+            0x00, 0x12, 0x4b, 0x00, 0x01, 0x02, 0x03, 0x04,
+        ]);
+        assert_eq!(extended_address.oui(), [0x00, 0x12, 0x4b]);
+        assert!(extended_address.is_universal());
+        assert!(!extended_address.is_group());
+        assert_eq!(
+            extended_address.to_interface_identifier(),
+            [0x02, 0x12, 0x4b, 0x00, 0x01, 0x02, 0x03, 0x04]
+        );
+
+        extended_address.set_local(true);
+        assert!(!extended_address.is_universal());
+        assert_eq!(extended_address.into_be_bytes()[0], 0x02);
+        assert_eq!(extended_address.to_interface_identifier()[0], 0x00);
+        extended_address.set_local(false);
+        assert!(extended_address.is_universal());
+
+        let address = Address::Extended(extended_address);
+        assert!(address.is_extended_unicast());
+        assert_eq!(address.oui(), Some([0x00, 0x12, 0x4b]));
+        assert!(!Address::Extended(OTHER_EXTENDED_ADDRESS).is_extended_unicast());
+        assert!(!BROADCAST_ADDR.is_extended_unicast());
+        assert_eq!(BROADCAST_ADDR.oui(), None);
+
+        let short_address = Address::Short(ShortAddress::from_u16(0x1234));
+        assert_eq!(
+            short_address.to_interface_identifier(),
+            Some([0x00, 0x00, 0x00, 0xff, 0xfe, 0x00, 0x12, 0x34])
+        );
+        assert_eq!(Address::<&[u8]>::Absent.to_interface_identifier(), None);
+    }
+
+    #[test]
+    fn pan() {
+        assert!(BROADCAST_PAN.is_broadcast());
+        assert_eq!(Pan::from(BROADCAST_PAN_ID), BROADCAST_PAN);
+        let pan = Pan::from(0xabcd);
+        assert!(!pan.is_broadcast());
+        assert_eq!(pan.to_pan_id().as_ref(), &[0xcd, 0xab]);
+        assert_eq!(u16::from(pan), 0xabcd);
+        assert_eq!(pan.to_string(), "0xabcd");
+    }
+
+    #[test]
+    fn addressing_fields_round_trip() {
+        use AddressingMode::*;
+
+        const DST_PAN_ID: [u8; 2] = [0xcd, 0xab];
+        const SRC_PAN_ID: [u8; 2] = [0x34, 0x12];
+        const SHORT_ADDRESS: [u8; 2] = [0x01, 0x02];
+        const EXTENDED_ADDRESS: [u8; 8] = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+
+        let address = |mode| match mode {
+            Short => Address::Short(ShortAddress::new(&SHORT_ADDRESS[..])),
+            Extended => Address::Extended(ExtendedAddress::new(&EXTENDED_ADDRESS[..])),
+            _ => Address::Absent,
+        };
+
+        for compression in [
+            PanIdCompressionRepr::Legacy,
+            PanIdCompressionRepr::No,
+            PanIdCompressionRepr::Yes,
+        ] {
+            for dst in [Absent, Short, Extended] {
+                for src in [Absent, Short, Extended] {
+                    for pan_ids_equal in [false, true] {
+                        let repr = AddressingRepr::new(dst, src, pan_ids_equal, compression);
+                        let Ok(len) = repr.addressing_fields_length() else {
+                            continue;
+                        };
+                        let len = len as usize;
+
+                        let mut buffer = [0; 20];
+                        let mut fields = AddressingFields::new(&mut buffer[..len], repr).unwrap();
+                        if let Some(mut pan_id) = fields.dst_pan_id_mut() {
+                            pan_id.set_le_bytes(DST_PAN_ID);
+                        }
+                        fields
+                            .dst_address_mut()
+                            .unwrap()
+                            .set(&address(dst))
+                            .unwrap();
+                        if let Some(mut pan_id) = fields.src_pan_id_mut() {
+                            pan_id.set_le_bytes(SRC_PAN_ID);
+                        }
+                        fields
+                            .src_address_mut()
+                            .unwrap()
+                            .set(&address(src))
+                            .unwrap();
+
+                        let fields = AddressingFields::new(&buffer[..len], repr).unwrap();
+                        assert_eq!(fields.dst_address(), Some(address(dst)));
+                        assert_eq!(fields.src_address(), Some(address(src)));
+
+                        // The wire format is the in-memory representation
+                        // verbatim.
+                        let mut expected = Vec::new();
+                        if let Some(pan_id) = fields.dst_pan_id() {
+                            assert_eq!(pan_id.as_ref(), DST_PAN_ID);
+                            expected.extend_from_slice(&DST_PAN_ID);
+                        }
+                        expected.extend_from_slice(address(dst).as_le_bytes());
+                        if let Some(pan_id) = fields.src_pan_id() {
+                            assert_eq!(pan_id.as_ref(), SRC_PAN_ID);
+                            expected.extend_from_slice(&SRC_PAN_ID);
+                        }
+                        expected.extend_from_slice(address(src).as_le_bytes());
+                        assert_eq!(&buffer[..len], &expected[..]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_str_display_round_trip() {
+        for s in ["ff:fe", "00:01"] {
+            let short_address: ShortAddress<[u8; 2]> = s.parse().unwrap();
+            assert_eq!(short_address.to_string(), s);
+        }
+        assert_eq!(
+            "34:12".parse::<ShortAddress<[u8; 2]>>().unwrap().into_u16(),
+            0x1234
+        );
+
+        let s = "aa:bb:cc:dd:ee:ff:00:11";
+        let extended_address: ExtendedAddress<[u8; 8]> = s.parse().unwrap();
+        assert_eq!(
+            extended_address.as_ref(),
+            &[0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x00, 0x11]
+        );
+        assert_eq!(extended_address.to_string(), s);
+
+        for s in ["", "absent", "ff:fe", "aa:bb:cc:dd:ee:ff:00:11"] {
+            let address: Address<Vec<u8>> = s.parse().unwrap();
+            assert_eq!(address.to_string(), if s.is_empty() { "absent" } else { s });
+        }
+
+        let pan_id: PanId<[u8; 2]> = "0xabcd".parse().unwrap();
+        assert_eq!(pan_id.into_u16(), 0xabcd);
+        assert_eq!(pan_id.to_string(), "0xabcd");
+        assert_eq!("12".parse::<PanId<[u8; 2]>>().unwrap().into_u16(), 0x12);
+
+        for s in ["ff", "ff:", ":ff", "ff:fff", "gg:ff", "+f:ff", "ff:ff:ff"] {
+            assert!(s.parse::<ShortAddress<[u8; 2]>>().is_err(), "{s}");
+        }
+        assert!("aa:bb:cc:dd:ee:ff:00"
+            .parse::<ExtendedAddress<[u8; 8]>>()
+            .is_err());
+        assert!("aa:bb:cc".parse::<Address<Vec<u8>>>().is_err());
+        for s in ["", "0x", "0x12345", "-1", "xyz"] {
+            assert!(s.parse::<PanId<[u8; 2]>>().is_err(), "{s}");
+        }
+    }
+
     #[test]
     fn parse() {
         let mut addresses: Vec<(&'static str, Address<&[u8]>)> = vec![
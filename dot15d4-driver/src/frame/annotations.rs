@@ -0,0 +1,229 @@
+use crate::{
+    link_quality::LinkQuality,
+    time::{Instant, Microseconds},
+};
+
+/// Reception metadata of an incoming frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RxMetadata {
+    /// The time at which the RMARKER of the frame passed the local antenna.
+    pub timestamp: Option<Instant<Microseconds>>,
+    /// The link quality indicator reported by the driver, normalized with
+    /// [`normalize_lqi()`](crate::link_quality::normalize_lqi).
+    pub lqi: Option<u8>,
+    /// The received signal strength in dBm.
+    pub rssi: Option<i8>,
+}
+
+impl RxMetadata {
+    /// Return the link quality of the frame or [`None`] if the driver didn't
+    /// report the signal strength.
+    pub fn link_quality(&self) -> Option<LinkQuality> {
+        let rssi = self.rssi?;
+        Some(match self.lqi {
+            Some(lqi) => LinkQuality::new(rssi, lqi),
+            None => LinkQuality::from_rssi(rssi),
+        })
+    }
+}
+
+/// Security metadata of an incoming or outgoing frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityInfo {
+    /// The raw security level field of the auxiliary security header.
+    pub security_level: u8,
+    /// The key index, if the key identifier mode provides one.
+    pub key_index: Option<u8>,
+    /// The frame counter, unless frame counter suppression is active.
+    pub frame_counter: Option<u32>,
+}
+
+/// The command frame identifier of a MAC command frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacCommandKind(pub u8);
+
+/// TSCH slot metadata of an incoming or outgoing frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotInfo {
+    /// The absolute slot number of the timeslot.
+    pub asn: u64,
+    /// The handle of the slotframe the link belongs to.
+    pub slotframe_handle: u8,
+    /// The channel offset of the link.
+    pub channel_offset: u16,
+}
+
+/// A typed key into [`FrameAnnotations`].
+///
+/// The set of annotations is fixed. Keys are the annotation types themselves,
+/// i.e. [`RxMetadata`], [`SecurityInfo`], [`MacCommandKind`] and [`SlotInfo`].
+pub trait FrameAnnotation: Copy + private::Sealed {
+    #[doc(hidden)]
+    fn entry(annotations: &FrameAnnotations) -> &Option<Self>;
+
+    #[doc(hidden)]
+    fn entry_mut(annotations: &mut FrameAnnotations) -> &mut Option<Self>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! frame_annotation {
+    ($annotation:ty, $field:ident) => {
+        impl private::Sealed for $annotation {}
+
+        impl FrameAnnotation for $annotation {
+            fn entry(annotations: &FrameAnnotations) -> &Option<Self> {
+                &annotations.$field
+            }
+
+            fn entry_mut(annotations: &mut FrameAnnotations) -> &mut Option<Self> {
+                &mut annotations.$field
+            }
+        }
+    };
+}
+
+frame_annotation!(RxMetadata, rx_info);
+frame_annotation!(SecurityInfo, security_info);
+frame_annotation!(MacCommandKind, mac_command_kind);
+frame_annotation!(SlotInfo, slot_info);
+
+/// Typed metadata attached to a frame as it traverses the stack.
+///
+/// Annotations allow layers to pass on information about a frame (e.g. RX
+/// metadata from the driver to the MAC) without adding parameters to every
+/// function signature along the way.
+///
+/// Annotations are kept out of line, i.e. they travel next to rather than
+/// inside of frames, see [`Annotated`]. This keeps frames small on all
+/// paths that don't carry metadata.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameAnnotations {
+    rx_info: Option<RxMetadata>,
+    security_info: Option<SecurityInfo>,
+    mac_command_kind: Option<MacCommandKind>,
+    slot_info: Option<SlotInfo>,
+}
+
+impl FrameAnnotations {
+    /// Creates an empty set of annotations.
+    pub const fn new() -> Self {
+        Self {
+            rx_info: None,
+            security_info: None,
+            mac_command_kind: None,
+            slot_info: None,
+        }
+    }
+
+    /// Returns the annotation of the given type, if present.
+    pub fn get<Annotation: FrameAnnotation>(&self) -> Option<&Annotation> {
+        Annotation::entry(self).as_ref()
+    }
+
+    /// Returns the mutable annotation of the given type, if present.
+    pub fn get_mut<Annotation: FrameAnnotation>(&mut self) -> Option<&mut Annotation> {
+        Annotation::entry_mut(self).as_mut()
+    }
+
+    /// Sets the annotation of the given type and returns the previous value,
+    /// if any.
+    pub fn insert<Annotation: FrameAnnotation>(
+        &mut self,
+        annotation: Annotation,
+    ) -> Option<Annotation> {
+        Annotation::entry_mut(self).replace(annotation)
+    }
+
+    /// Removes the annotation of the given type and returns it, if present.
+    pub fn remove<Annotation: FrameAnnotation>(&mut self) -> Option<Annotation> {
+        Annotation::entry_mut(self).take()
+    }
+
+    /// Removes all annotations.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// A frame together with its annotations.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Annotated<Frame> {
+    /// The annotated frame.
+    pub frame: Frame,
+    /// The metadata attached to the frame.
+    pub annotations: FrameAnnotations,
+}
+
+impl<Frame> Annotated<Frame> {
+    /// Attaches an empty set of annotations to the given frame.
+    pub const fn new(frame: Frame) -> Self {
+        Self {
+            frame,
+            annotations: FrameAnnotations::new(),
+        }
+    }
+
+    /// Converts the annotated frame, e.g. a radio frame into an MPDU, and
+    /// carries the annotations along.
+    pub fn map<Other>(self, f: impl FnOnce(Frame) -> Other) -> Annotated<Other> {
+        Annotated {
+            frame: f(self.frame),
+            annotations: self.annotations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotations() {
+        let mut annotations = FrameAnnotations::new();
+        assert_eq!(annotations.get::<RxMetadata>(), None);
+
+        let rx_info = RxMetadata {
+            timestamp: Some(Instant::new(42)),
+            lqi: Some(0xff),
+            rssi: None,
+        };
+        assert_eq!(annotations.insert(rx_info), None);
+        assert_eq!(annotations.insert(MacCommandKind(0x01)), None);
+        assert_eq!(annotations.get::<RxMetadata>(), Some(&rx_info));
+        assert_eq!(annotations.get::<SlotInfo>(), None);
+
+        assert_eq!(rx_info.link_quality(), None);
+
+        annotations.get_mut::<RxMetadata>().unwrap().rssi = Some(-80);
+        assert_eq!(annotations.get::<RxMetadata>().unwrap().rssi, Some(-80));
+        assert_eq!(
+            annotations.get::<RxMetadata>().unwrap().link_quality(),
+            Some(LinkQuality::new(-80, 0xff))
+        );
+
+        assert_eq!(
+            annotations.insert(MacCommandKind(0x02)),
+            Some(MacCommandKind(0x01))
+        );
+        assert_eq!(
+            annotations.remove::<MacCommandKind>(),
+            Some(MacCommandKind(0x02))
+        );
+        assert_eq!(annotations.get::<MacCommandKind>(), None);
+
+        annotations.clear();
+        assert_eq!(annotations, FrameAnnotations::default());
+
+        let mut annotated = Annotated::new(0x2a_u8);
+        annotated.annotations.insert(MacCommandKind(0x01));
+        let annotated = annotated.map(u16::from);
+        assert_eq!(annotated.frame, 0x2a_u16);
+        assert_eq!(
+            annotated.annotations.get::<MacCommandKind>(),
+            Some(&MacCommandKind(0x01))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use core::fmt;
+
+use dot15d4_util::Error;
+
+/// The kind of failure encountered while parsing or building a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrameErrorKind {
+    /// The buffer is shorter than required by the field.
+    BufferTooShort {
+        /// The number of octets required.
+        needed: usize,
+        /// The number of octets available.
+        got: usize,
+    },
+    /// The buffer length does not match the exact length of the field.
+    InvalidLength {
+        /// The number of octets expected.
+        expected: usize,
+        /// The number of octets available.
+        got: usize,
+    },
+    /// The frame version is reserved.
+    InvalidFrameVersion,
+    /// The combination of addressing modes, frame version and PAN ID
+    /// compression is not allowed.
+    InvalidAddressingCombination,
+    /// The frame is secured but security is not supported.
+    SecurityNotSupported,
+    /// The frame contains IEs but IEs are not supported.
+    IesNotSupported,
+    /// The IE is well-formed but no typed representation is available for it.
+    UnknownIe {
+        /// The sub-ID of the IE.
+        sub_id: u8,
+        /// Whether the IE uses the long nested IE format.
+        is_long_format: bool,
+    },
+    /// The buffer is shorter than required by the IE content.
+    TruncatedIe,
+    /// The IE content is longer than expected or contains a value that is not
+    /// allowed.
+    MalformedIe,
+    /// The IE length announced in the IE header exceeds the remaining length
+    /// of the enclosing frame.
+    IeExceedsFrame {
+        /// The total length of the IE incl. header.
+        length: usize,
+        /// The number of octets remaining in the enclosing frame.
+        remaining: usize,
+    },
+    /// The IE length announced in the IE header exceeds the configured MTU.
+    IeExceedsMtu {
+        /// The total length of the IE incl. header.
+        length: usize,
+        /// The configured MTU.
+        mtu: u16,
+    },
+    /// The FCS of the frame does not match the FCS calculated over the MPDU.
+    InvalidFcs,
+}
+
+/// A detailed error produced while parsing or building a frame.
+///
+/// Carries the [`FrameErrorKind`] and, if known, the byte offset at which the
+/// failure was detected. Offsets are relative to the start of the buffer
+/// handed to the failing reader, e.g. the MPDU or an IE list.
+///
+/// Can be converted into the generic [`Error`] where the details are not
+/// needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FrameError {
+    kind: FrameErrorKind,
+    offset: Option<usize>,
+}
+
+impl FrameError {
+    /// Creates an error of the given kind without offset.
+    pub const fn new(kind: FrameErrorKind) -> Self {
+        Self { kind, offset: None }
+    }
+
+    /// Attaches the byte offset at which the failure was detected.
+    pub const fn at(self, offset: usize) -> Self {
+        Self {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    /// Shifts the offset by the given number of octets, e.g. when an error
+    /// produced by a nested reader is passed on by the enclosing reader.
+    ///
+    /// Errors without offset are attributed to the given offset.
+    pub const fn shifted_by(self, offset: usize) -> Self {
+        match self.offset {
+            Some(inner_offset) => self.at(offset + inner_offset),
+            None => self.at(offset),
+        }
+    }
+
+    /// The kind of failure.
+    pub const fn kind(&self) -> FrameErrorKind {
+        self.kind
+    }
+
+    /// The byte offset at which the failure was detected, if known.
+    pub const fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+}
+
+impl From<FrameErrorKind> for FrameError {
+    fn from(kind: FrameErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<FrameError> for Error {
+    fn from(_: FrameError) -> Self {
+        Error
+    }
+}
+
+impl fmt::Display for FrameErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameErrorKind::BufferTooShort { needed, got } => {
+                write!(f, "buffer too short (needed {needed}, got {got})")
+            }
+            FrameErrorKind::InvalidLength { expected, got } => {
+                write!(f, "invalid length (expected {expected}, got {got})")
+            }
+            FrameErrorKind::InvalidFrameVersion => write!(f, "invalid frame version"),
+            FrameErrorKind::InvalidAddressingCombination => {
+                write!(f, "invalid addressing combination")
+            }
+            FrameErrorKind::SecurityNotSupported => write!(f, "security not supported"),
+            FrameErrorKind::IesNotSupported => write!(f, "IEs not supported"),
+            FrameErrorKind::UnknownIe {
+                sub_id,
+                is_long_format,
+            } => write!(
+                f,
+                "unknown {} nested IE (sub-ID {:#04x})",
+                if *is_long_format { "long" } else { "short" },
+                sub_id
+            ),
+            FrameErrorKind::TruncatedIe => write!(f, "truncated IE"),
+            FrameErrorKind::MalformedIe => write!(f, "malformed IE"),
+            FrameErrorKind::IeExceedsFrame { length, remaining } => write!(
+                f,
+                "IE length ({length}) exceeds remaining frame length ({remaining})"
+            ),
+            FrameErrorKind::IeExceedsMtu { length, mtu } => {
+                write!(f, "IE length ({length}) exceeds MTU ({mtu})")
+            }
+            FrameErrorKind::InvalidFcs => write!(f, "invalid FCS"),
+        }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "{} at offset {offset}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
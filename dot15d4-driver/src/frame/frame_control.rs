@@ -1,11 +1,14 @@
 //! IEEE 802.15.4 Frame Control field access.
-use dot15d4_util::{Error, Result};
+use super::{AddressingMode, FrameError, FrameErrorKind};
 
-use super::AddressingMode;
+/// The length of the Frame Control field in octets.
+const FRAME_CONTROL_LEN: usize = 2;
 
 /// IEEE 802.15.4 frame type.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FrameType {
     /// Beacon frame.
     Beacon = 0b000,
@@ -43,6 +46,8 @@ impl From<u8> for FrameType {
 /// IEEE 802.15.4 frame version.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FrameVersion {
     /// IEEE 802.15.4-2003 frame version.
     Ieee802154_2003 = 0b00,
@@ -84,21 +89,25 @@ impl<Bytes: AsRef<[u8]>> FrameControl<Bytes> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the buffer is too short.
-    pub fn new(bytes: Bytes) -> Result<Self> {
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the buffer is too short.
+    pub fn new(bytes: Bytes) -> Result<Self, FrameError> {
         let fc = Self::new_unchecked(bytes);
-
-        if !fc.check_len() {
-            return Err(Error);
-        }
-
+        fc.check_len()?;
         Ok(fc)
     }
 
-    /// Returns `false` if the buffer is too short to contain the Frame Control
+    /// Checks that the buffer is long enough to contain the Frame Control
     /// field.
-    fn check_len(&self) -> bool {
-        self.bytes.as_ref().len() >= 2
+    fn check_len(&self) -> Result<(), FrameError> {
+        let got = self.bytes.as_ref().len();
+        if got < FRAME_CONTROL_LEN {
+            return Err(FrameErrorKind::BufferTooShort {
+                needed: FRAME_CONTROL_LEN,
+                got,
+            }
+            .into());
+        }
+        Ok(())
     }
 
     /// Create a new [`FrameControl`] reader/writer from a given buffer without
@@ -303,6 +312,29 @@ impl<Bytes: AsRef<[u8]>> core::fmt::Display for FrameControl<Bytes> {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<Bytes: AsRef<[u8]>> defmt::Format for FrameControl<Bytes> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "FrameControl {{ type: {}, security enabled: {=bool}, frame pending: {=bool}, \
+             ack request: {=bool}, pan id compression: {=bool}, \
+             sequence number suppression: {=bool}, information elements present: {=bool}, \
+             dst addressing mode: {}, src addressing mode: {}, frame version: {} }}",
+            self.frame_type(),
+            self.security_enabled(),
+            self.frame_pending(),
+            self.ack_request(),
+            self.pan_id_compression(),
+            self.sequence_number_suppression(),
+            self.information_elements_present(),
+            self.dst_addressing_mode(),
+            self.src_addressing_mode(),
+            self.frame_version(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,7 +342,10 @@ mod tests {
     #[test]
     fn bad_length() {
         let fc = [0x0];
-        assert!(FrameControl::new(&fc).is_err());
+        assert_eq!(
+            FrameControl::new(&fc).unwrap_err().kind(),
+            FrameErrorKind::BufferTooShort { needed: 2, got: 1 }
+        );
     }
 
     #[test]
@@ -8,12 +8,16 @@
 use core::fmt::Debug;
 
 mod addressing;
+mod annotations;
+mod error;
 mod frame_control;
 mod radio_frame;
 mod repr;
 mod utils;
 
 pub use addressing::*;
+pub use annotations::*;
+pub use error::*;
 pub use frame_control::*;
 pub use radio_frame::*;
 pub use repr::*;
@@ -12,13 +12,27 @@ use generic_array::ArrayLength;
 
 use crate::time::{Frequency, Instant};
 
+pub mod clock;
 pub mod config;
 pub mod const_config;
 pub mod constants;
 pub mod frame;
+pub mod link_quality;
+pub mod phy;
+pub mod radio;
+#[cfg(feature = "std")]
+pub mod simulator;
+#[cfg(feature = "std")]
+pub mod sniffer;
 pub mod socs;
 pub mod tasks;
+#[cfg(feature = "std")]
+pub mod test_clock;
 pub mod time;
+pub mod timer_queue;
+pub mod tx_descriptor;
+#[cfg(feature = "std")]
+pub mod virtual_radio;
 
 pub mod export {
     pub use generic_array::ArrayLength;
@@ -32,10 +46,58 @@ pub const MAX_DRIVER_OVERHEAD: usize = 2;
 /// Drivers for LECIM, TVWS and SUN PHYs may be configured with a 4-byte FCS, all
 pub type FcsFourBytes = u32;
 
+/// The reflected ANSI X3.66 CRC-32 generator polynomial.
+const CRC32_POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// Calculates the 4-octet FCS over the given MPDU (excluding the FCS), i.e.
+/// the ANSI X3.66 CRC-32 (IEEE 802.15.4-2024, section 7.2.11.2).
+pub const fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i] as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    !crc
+}
+
 /// Type allowed for [`DriverConfig::Fcs`]
 /// Most drivers/PHYs use two bytes.
 pub type FcsTwoBytes = u16;
 
+/// The reflected ITU-T CRC-16 generator polynomial.
+const CRC16_POLYNOMIAL: u16 = 0x8408;
+
+/// Calculates the 2-octet FCS over the given MPDU (excluding the FCS), i.e.
+/// the ITU-T CRC-16 (IEEE 802.15.4-2024, section 7.2.11.1).
+pub const fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i] as u16;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC16_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
 /// Type allowed for [`DriverConfig::Fcs`]
 /// Drivers that offload FCS (=CRC) checking to hardware will neither require
 /// nor include an FCS in the frame.
@@ -86,6 +148,12 @@ pub trait RadioTimerApi: Frequency + Sized {
 
     fn schedule_alarm(at: Instant<Self>);
 
+    /// Cancels the alarm programmed by [`RadioTimerApi::schedule_alarm()`].
+    /// An alarm that fired but was not awaited yet is discarded.
+    ///
+    /// Tasks SHALL NOT wait for a cancelled alarm.
+    fn cancel_alarm();
+
     /// Waits for the alarm programmed by [`RadioTimerApi::schedule_alarm()`].
     ///
     /// Implementations SHALL be cancellable.
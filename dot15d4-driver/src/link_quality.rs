@@ -0,0 +1,111 @@
+//! Link quality of received frames.
+//!
+//! Radios describe the quality of a received frame in different ways: the
+//! received signal strength (RSSI) in dBm, a link quality indicator (LQI) on a
+//! vendor-specific scale and ED values (IEEE 802.15.4-2020, sections 10.2.6
+//! and 10.2.7). [`LinkQuality`] normalizes them so that the MAC and upper
+//! layers compare links independently of the radio.
+//!
+//! Drivers scale their LQI to the full range of 0 to 255 with
+//! [`normalize_lqi()`]. Radios without an LQI derive it from the RSSI, see
+//! [`LinkQuality::from_rssi()`].
+
+/// The received power mapped to the lowest ED value in dBm, i.e. 10 dB above
+/// the sensitivity of -85 dBm required from the O-QPSK PHY (IEEE
+/// 802.15.4-2020, section 10.2.7).
+const ED_MIN_DBM: i16 = -75;
+
+/// The range of received power mapped linearly to the ED values in dB.
+const ED_RANGE_DB: i16 = 40;
+
+/// The quality of a received frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkQuality {
+    /// The received signal strength in dBm.
+    pub rssi: i8,
+    /// The link quality indicator, from 0 for the lowest to 255 for the
+    /// highest quality.
+    pub lqi: u8,
+    /// The ED value corresponding to the received signal strength.
+    pub ed: u8,
+}
+
+impl LinkQuality {
+    /// Creates the link quality of a frame received with the given signal
+    /// strength in dBm and normalized LQI.
+    pub const fn new(rssi: i8, lqi: u8) -> Self {
+        Self {
+            rssi,
+            lqi,
+            ed: ed_from_dbm(rssi),
+        }
+    }
+
+    /// Creates the link quality of a frame received with the given signal
+    /// strength in dBm by a radio without LQI.
+    ///
+    /// The LQI is derived from the signal strength like the ED value.
+    pub const fn from_rssi(rssi: i8) -> Self {
+        Self::new(rssi, ed_from_dbm(rssi))
+    }
+}
+
+/// Converts the given received power in dBm to an ED value.
+///
+/// The standard requires a linear mapping over at least 40 dB, starting 10 dB
+/// above the receiver sensitivity.
+pub const fn ed_from_dbm(dbm: i8) -> u8 {
+    let mut above_min = dbm as i16 - ED_MIN_DBM;
+    if above_min < 0 {
+        above_min = 0;
+    } else if above_min > ED_RANGE_DB {
+        above_min = ED_RANGE_DB;
+    }
+    (above_min * u8::MAX as i16 / ED_RANGE_DB) as u8
+}
+
+/// Scales the given LQI of a radio reporting values up to the given maximum to
+/// the range of 0 to 255.
+///
+/// Values above the maximum are saturated.
+pub const fn normalize_lqi(lqi: u8, max: u8) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    let lqi = if lqi > max { max } else { lqi };
+    (lqi as u16 * u8::MAX as u16 / max as u16) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn energy_detection_values() {
+        assert_eq!(ed_from_dbm(i8::MIN), 0);
+        assert_eq!(ed_from_dbm(-75), 0);
+        assert_eq!(ed_from_dbm(-60), 95);
+        assert_eq!(ed_from_dbm(-55), 127);
+        assert_eq!(ed_from_dbm(-35), 255);
+        assert_eq!(ed_from_dbm(10), 255);
+    }
+
+    #[test]
+    fn link_quality() {
+        assert_eq!(
+            LinkQuality::new(-60, 0xc0),
+            LinkQuality {
+                rssi: -60,
+                lqi: 0xc0,
+                ed: 95
+            }
+        );
+        assert_eq!(LinkQuality::from_rssi(-55).lqi, 127);
+
+        // A radio reporting LQIs up to 0x3f.
+        assert_eq!(normalize_lqi(0x3f, 0x3f), 0xff);
+        assert_eq!(normalize_lqi(0x20, 0x3f), 0x81);
+        assert_eq!(normalize_lqi(0x80, 0x3f), 0xff);
+    }
+}
@@ -0,0 +1,350 @@
+//! PHY-dependent timing parameters (IEEE 802.15.4-2020, section 11.3).
+//!
+//! The MAC timing (backoff periods, interframe spaces, ACK wait durations)
+//! is defined in symbols and PHY constants. [`PhyParameters`] captures these
+//! for a given PHY in microseconds so that the MAC does not depend on the
+//! symbol rate of the O-QPSK 2.4 GHz PHY the constants in
+//! [`crate::constants`] are given for.
+//!
+//! SUN PHYs may additionally switch between PHY modes of different data rates
+//! from frame to frame, see [`PhyMode`].
+//!
+//! [`air_time()`] returns the on-air duration of a frame on a given PHY, e.g.
+//! to check that frames fit into TSCH timeslots or to account for duty cycle
+//! limits.
+
+use crate::{
+    constants::{A_TURNAROUND_TIME, MAC_LIFS, MAC_SIFS, PHY_CCA_DURATION, PHY_MAX_FRAME_DURATION},
+    time::{Duration, Microseconds},
+};
+
+/// The number of symbols forming a SIFS (macSifsPeriod).
+const SIFS_SYMBOLS: i64 = MAC_SIFS.ticks();
+
+/// The number of symbols forming a LIFS (macLifsPeriod).
+const LIFS_SYMBOLS: i64 = MAC_LIFS.ticks();
+
+/// Timing parameters of a PHY, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyParameters {
+    /// The duration of a symbol.
+    pub symbol_duration: Duration<Microseconds>,
+    /// phySymbolsPerOctet
+    pub symbols_per_octet: u8,
+    /// aTurnaroundTime, the RX-to-TX or TX-to-RX turnaround time.
+    pub turnaround_time: Duration<Microseconds>,
+    /// phyCcaDuration, the time required to perform CCA detection.
+    pub cca_duration: Duration<Microseconds>,
+    /// phyShrDuration, the duration of the synchronization header.
+    pub shr_duration: Duration<Microseconds>,
+    /// The length of the PHR in octets.
+    pub phr_len: u8,
+    /// Whether PHR and PSDU are encoded with the rate 1/2 FEC of the SUN FSK
+    /// PHY.
+    pub fec: bool,
+    /// phyMaxFrameDuration, the duration of the longest possible PPDU.
+    pub max_frame_duration: Duration<Microseconds>,
+}
+
+impl PhyParameters {
+    /// The O-QPSK PHY at 250 kb/s, i.e. in the 2450 MHz band and, with
+    /// identical timing, in the 780 MHz and 915 MHz bands.
+    pub const OQPSK_2450MHZ: Self = Self {
+        symbol_duration: Duration::new(16),
+        symbols_per_octet: 2,
+        turnaround_time: A_TURNAROUND_TIME.convert_into_rounding_up(),
+        cca_duration: PHY_CCA_DURATION.convert_into_rounding_up(),
+        // 8 symbols preamble, 2 symbols SFD
+        shr_duration: Duration::new(10 * 16),
+        phr_len: 1,
+        fec: false,
+        max_frame_duration: PHY_MAX_FRAME_DURATION.convert_into_rounding_up(),
+    };
+
+    /// The O-QPSK PHY at 100 kb/s in the 868 MHz band (25 ksymbol/s).
+    pub const OQPSK_868MHZ: Self = Self {
+        symbol_duration: Duration::new(40),
+        symbols_per_octet: 2,
+        turnaround_time: Duration::new(12 * 40),
+        cca_duration: Duration::new(8 * 40),
+        // 8 symbols preamble, 2 symbols SFD
+        shr_duration: Duration::new(10 * 40),
+        phr_len: 1,
+        fec: false,
+        // 10 + (127 + 1) * 2 symbols
+        max_frame_duration: Duration::new(266 * 40),
+    };
+
+    /// The SUN FSK PHY at 50 kb/s (2-FSK operating mode #1) with the default
+    /// preamble of 8 octets.
+    pub const SUN_FSK_50KBPS: Self = Self {
+        symbol_duration: Duration::new(20),
+        symbols_per_octet: 8,
+        // SUN PHYs turn around within 1 ms.
+        turnaround_time: Duration::new(1_000),
+        cca_duration: Duration::new(8 * 20),
+        // 8 octets preamble, 2 octets SFD
+        shr_duration: Duration::new(10 * 8 * 20),
+        phr_len: 2,
+        fec: false,
+        // phyShrDuration + (aMaxPhyPacketSize + 2 octets PHR) * 8 symbols
+        max_frame_duration: Duration::new(10 * 8 * 20 + (2047 + 2) * 8 * 20),
+    };
+
+    /// The SUN FSK PHY in the given PHY mode with the default preamble of 8
+    /// octets.
+    ///
+    /// Returns [`None`] if the symbol duration is not a whole number of
+    /// microseconds, e.g. at 150 ksymbol/s.
+    pub const fn sun_fsk(mode: SunFskMode) -> Option<Self> {
+        if mode.symbol_rate == 0 || 1_000 % mode.symbol_rate != 0 {
+            return None;
+        }
+
+        let symbol_duration = (1_000 / mode.symbol_rate) as i64;
+        let symbols_per_octet = (8 / mode.bits_per_symbol()) as u8;
+        let mut phy = Self {
+            symbol_duration: Duration::new(symbol_duration),
+            symbols_per_octet,
+            // SUN PHYs turn around within 1 ms.
+            turnaround_time: Duration::new(1_000),
+            cca_duration: Duration::new(8 * symbol_duration),
+            // 8 octets preamble, 2 octets SFD
+            shr_duration: Duration::new(10 * symbols_per_octet as i64 * symbol_duration),
+            phr_len: 2,
+            fec: mode.fec,
+            max_frame_duration: Duration::new(0),
+        };
+        // aMaxPhyPacketSize
+        phy.max_frame_duration = air_time(2047, &phy);
+        Some(phy)
+    }
+
+    /// Return the duration of the given number of symbols.
+    pub const fn symbols(&self, symbols: i64) -> Duration<Microseconds> {
+        Duration::new(symbols * self.symbol_duration.ticks())
+    }
+
+    /// Return the duration of the given number of octets.
+    pub const fn octets(&self, octets: i64) -> Duration<Microseconds> {
+        self.symbols(octets * self.symbols_per_octet as i64)
+    }
+
+    /// Return aUnitBackoffPeriod, i.e. aTurnaroundTime + phyCcaDuration.
+    pub const fn unit_backoff_period(&self) -> Duration<Microseconds> {
+        Duration::new(self.turnaround_time.ticks() + self.cca_duration.ticks())
+    }
+
+    /// Return macSifsPeriod.
+    pub const fn sifs(&self) -> Duration<Microseconds> {
+        self.symbols(SIFS_SYMBOLS)
+    }
+
+    /// Return macLifsPeriod.
+    pub const fn lifs(&self) -> Duration<Microseconds> {
+        self.symbols(LIFS_SYMBOLS)
+    }
+
+    /// Return macAckWaitDuration, i.e. aUnitBackoffPeriod + aTurnaroundTime +
+    /// phyShrDuration + 6 * phySymbolsPerOctet.
+    pub const fn ack_wait_duration(&self) -> Duration<Microseconds> {
+        Duration::new(
+            self.unit_backoff_period().ticks()
+                + self.turnaround_time.ticks()
+                + self.shr_duration.ticks()
+                + self.octets(6).ticks(),
+        )
+    }
+}
+
+impl Default for PhyParameters {
+    fn default() -> Self {
+        Self::OQPSK_2450MHZ
+    }
+}
+
+/// Return the on-air duration of a frame, i.e. of its SHR, PHR and PSDU.
+///
+/// With FEC, PHR and PSDU are followed by tail and pad bits filling one octet
+/// (without interleaving) and encoded into twice as many octets.
+///
+/// * `frame_len` - Length of the PSDU in octets, i.e. of the MPDU including
+///   the FCS
+/// * `phy` - The PHY the frame is sent on
+pub const fn air_time(frame_len: usize, phy: &PhyParameters) -> Duration<Microseconds> {
+    let mut octets = phy.phr_len as i64 + frame_len as i64;
+    if phy.fec {
+        octets = 2 * (octets + 1);
+    }
+    Duration::new(phy.shr_duration.ticks() + phy.octets(octets).ticks())
+}
+
+/// The modulation of a SUN FSK PHY mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FskModulation {
+    /// Filtered 2-FSK, one bit per symbol.
+    Fsk2,
+    /// Filtered 4-FSK, two bits per symbol.
+    Fsk4,
+}
+
+/// A PHY mode of the SUN FSK PHY (IEEE 802.15.4-2020, section 19), i.e. one
+/// of the operating modes of a band or a mode described by a SUN FSK Generic
+/// PHY IE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SunFskMode {
+    /// The symbol rate in ksymbol/s.
+    pub symbol_rate: u16,
+    /// The modulation.
+    pub modulation: FskModulation,
+    /// Whether the PSDU is encoded with the rate 1/2 FEC.
+    pub fec: bool,
+}
+
+impl SunFskMode {
+    /// 2-FSK at 50 kb/s (operating mode #1 of the 863 MHz and 915 MHz bands).
+    pub const FSK_50KBPS: Self = Self::new(50, FskModulation::Fsk2, false);
+
+    /// 2-FSK at 100 kb/s (operating mode #2 of the 863 MHz band).
+    pub const FSK_100KBPS: Self = Self::new(100, FskModulation::Fsk2, false);
+
+    /// 2-FSK at 150 kb/s (operating mode #3 of the 863 MHz band).
+    pub const FSK_150KBPS: Self = Self::new(150, FskModulation::Fsk2, false);
+
+    /// Create a new PHY mode.
+    ///
+    /// * `symbol_rate` - Symbol rate in ksymbol/s
+    /// * `modulation` - Modulation
+    /// * `fec` - Whether the PSDU is encoded with the rate 1/2 FEC
+    pub const fn new(symbol_rate: u16, modulation: FskModulation, fec: bool) -> Self {
+        Self {
+            symbol_rate,
+            modulation,
+            fec,
+        }
+    }
+
+    /// Return the number of bits per symbol.
+    pub const fn bits_per_symbol(&self) -> u32 {
+        match self.modulation {
+            FskModulation::Fsk2 => 1,
+            FskModulation::Fsk4 => 2,
+        }
+    }
+
+    /// Return the data rate of the PSDU in b/s, taking the FEC into account.
+    pub const fn data_rate(&self) -> u32 {
+        let data_rate = self.symbol_rate as u32 * 1_000 * self.bits_per_symbol();
+        if self.fec {
+            data_rate / 2
+        } else {
+            data_rate
+        }
+    }
+}
+
+/// The PHY mode a frame is sent with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PhyMode {
+    /// The O-QPSK PHY of the current channel page.
+    #[default]
+    Oqpsk,
+    /// A SUN FSK PHY mode.
+    SunFsk(SunFskMode),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::{MAC_ACK_WAIT_DURATION, MAC_UNIT_BACKOFF_PERIOD},
+        time::SymbolsOQpsk250kB,
+    };
+
+    fn to_us(duration: Duration<SymbolsOQpsk250kB>) -> Duration<Microseconds> {
+        duration.convert_into_rounding_up()
+    }
+
+    #[test]
+    fn oqpsk_2450mhz() {
+        let phy = PhyParameters::OQPSK_2450MHZ;
+        assert_eq!(phy.unit_backoff_period(), to_us(MAC_UNIT_BACKOFF_PERIOD));
+        assert_eq!(phy.sifs(), to_us(MAC_SIFS));
+        assert_eq!(phy.lifs(), to_us(MAC_LIFS));
+        assert_eq!(phy.ack_wait_duration(), to_us(MAC_ACK_WAIT_DURATION));
+        assert_eq!(phy.max_frame_duration.ticks(), 4256);
+    }
+
+    #[test]
+    fn sub_ghz() {
+        let phy = PhyParameters::OQPSK_868MHZ;
+        // 20 symbols of 40µs each.
+        assert_eq!(phy.unit_backoff_period().ticks(), 800);
+        // 20 + 12 + 10 + 12 symbols.
+        assert_eq!(phy.ack_wait_duration().ticks(), 54 * 40);
+
+        let phy = PhyParameters::SUN_FSK_50KBPS;
+        assert_eq!(phy.unit_backoff_period().ticks(), 1_160);
+        assert_eq!(phy.octets(1).ticks(), 160);
+        // 1160µs + 1000µs + 80 symbols + 48 symbols of 20µs each.
+        assert_eq!(phy.ack_wait_duration().ticks(), 2_160 + 128 * 20);
+        assert_eq!(phy.lifs().ticks(), 800);
+    }
+
+    #[test]
+    fn air_times() {
+        // 5 octets SHR, 1 octet PHR and an Imm-Ack of 5 octets, 2 symbols
+        // of 16µs each per octet.
+        let phy = PhyParameters::OQPSK_2450MHZ;
+        assert_eq!(air_time(5, &phy).ticks(), 11 * 32);
+        for phy in [
+            PhyParameters::OQPSK_2450MHZ,
+            PhyParameters::OQPSK_868MHZ,
+            PhyParameters::SUN_FSK_50KBPS,
+        ] {
+            let max_frame_len = if phy.phr_len == 1 { 127 } else { 2047 };
+            assert_eq!(air_time(max_frame_len, &phy), phy.max_frame_duration);
+        }
+
+        // 10 octets SHR and 2 octets PHR at 160µs per octet.
+        let phy = PhyParameters::SUN_FSK_50KBPS;
+        assert_eq!(air_time(5, &phy).ticks(), 17 * 160);
+        assert_eq!(PhyParameters::sun_fsk(SunFskMode::FSK_50KBPS), Some(phy));
+
+        // PHR, PSDU and one octet of tail and pad bits are encoded at rate
+        // 1/2.
+        let mode = SunFskMode::new(50, FskModulation::Fsk2, true);
+        let phy = PhyParameters::sun_fsk(mode).unwrap();
+        assert_eq!(air_time(5, &phy).ticks(), (10 + 16) * 160);
+
+        // 4-FSK at 100 ksymbol/s, 4 symbols of 10µs each per octet.
+        let mode = SunFskMode::new(100, FskModulation::Fsk4, false);
+        let phy = PhyParameters::sun_fsk(mode).unwrap();
+        assert_eq!(air_time(5, &phy).ticks(), 17 * 40);
+
+        assert_eq!(PhyParameters::sun_fsk(SunFskMode::FSK_150KBPS), None);
+    }
+
+    #[test]
+    fn sun_fsk_modes() {
+        assert_eq!(SunFskMode::FSK_50KBPS.data_rate(), 50_000);
+        assert_eq!(SunFskMode::FSK_150KBPS.data_rate(), 150_000);
+
+        let mode = SunFskMode::new(100, FskModulation::Fsk4, false);
+        assert_eq!(mode.bits_per_symbol(), 2);
+        assert_eq!(mode.data_rate(), 200_000);
+        let mode = SunFskMode::new(100, FskModulation::Fsk4, true);
+        assert_eq!(mode.data_rate(), 100_000);
+
+        assert_eq!(PhyMode::default(), PhyMode::Oqpsk);
+    }
+}
@@ -0,0 +1,234 @@
+//! Hardware-independent contract of radio drivers.
+//!
+//! [`Radio`] exposes the operations the MAC engines build upon: scheduled
+//! transmission, reception within a window, clear channel assessment and
+//! energy detection, as well as the channel and transmit power settings.
+//! Optional hardware features are announced by [`RadioCapabilities`] so that
+//! the MAC can fall back to software where the hardware doesn't help.
+//!
+//! All operations are asynchronous and SHALL be cancellable, i.e. dropping a
+//! pending future returns the radio to an idle state.
+//!
+//! Frames are passed as MPDUs without FCS. Drivers calculate and check the FCS
+//! themselves, in hardware or software, and drop received frames with an
+//! invalid FCS.
+//!
+//! # Timestamps
+//!
+//! TSCH synchronization and CSL rely on knowing when exactly a frame was on
+//! air. Every transmitted and received frame is therefore timestamped with the
+//! instant at which its RMARKER, i.e. the end of the SFD, passed the antenna
+//! (IEEE 802.15.4-2020, section 6.5.4.1). Timestamps are given in nanoseconds
+//! of the radio clock, i.e. on the time base of [`Radio::Timer`] but
+//! independent of its resolution. Drivers SHALL capture them in hardware, e.g.
+//! with the SFD event of the radio, and compensate the delays of the radio
+//! pipeline so that they are accurate to
+//! [`RadioCapabilities::timestamp_resolution`].
+
+use core::future::Future;
+
+use crate::{
+    config::{CcaMode, Channel},
+    link_quality::LinkQuality,
+    phy::PhyMode,
+    time::{Duration, Frequency, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+/// The errors reported by [`Radio`] operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RadioError {
+    /// The instant at which the operation was to start had already passed
+    /// when the radio was ready.
+    TooLate,
+    /// The frame exceeds the size supported by the radio or the receive
+    /// buffer.
+    FrameTooLong,
+    /// The radio does not support the requested setting, e.g. the CCA mode.
+    Unsupported,
+    /// The hardware failed to execute the operation.
+    Hardware,
+}
+
+/// Optional features of a radio, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadioCapabilities {
+    /// Whether the radio acknowledges received frames requesting an Imm-Ack
+    /// by itself. Otherwise, the MAC transmits ACKs with
+    /// [`Radio::transmit_at()`].
+    pub hardware_ack: bool,
+    /// Whether hardware ACKs set the Frame Pending flag for the source
+    /// addresses published with [`Radio::set_frame_pending()`], e.g. the ACKs
+    /// of Data Request commands of devices with frames kept by a coordinator.
+    ///
+    /// Radios with hardware ACKs but without this feature SHALL leave the ACKs
+    /// of MAC commands to the MAC.
+    pub frame_pending: bool,
+    /// The accuracy of the timestamps of transmitted and received frames,
+    /// see the module documentation.
+    pub timestamp_resolution: Duration<Nanoseconds>,
+    /// The lowest transmit power in dBm.
+    pub min_tx_power: i8,
+    /// The highest transmit power in dBm.
+    pub max_tx_power: i8,
+}
+
+/// The time span in which [`Radio::receive()`] listens for a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RxWindow<Timer: Frequency> {
+    /// The instant at which the receiver is switched on or `None` to switch
+    /// it on immediately.
+    pub start: Option<Instant<Timer>>,
+    /// The instant at which the receiver is switched off unless a frame is
+    /// being received or `None` to listen until a frame is received.
+    pub end: Option<Instant<Timer>>,
+}
+
+impl<Timer: Frequency> RxWindow<Timer> {
+    /// Listen from now on until a frame is received.
+    pub const fn unbounded() -> Self {
+        Self {
+            start: None,
+            end: None,
+        }
+    }
+
+    /// Listen from now on until a frame is received or the given instant is
+    /// reached, whatever comes first.
+    pub const fn until(end: Instant<Timer>) -> Self {
+        Self {
+            start: None,
+            end: Some(end),
+        }
+    }
+
+    /// Listen between the given instants.
+    pub const fn between(start: Instant<Timer>, end: Instant<Timer>) -> Self {
+        Self {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+}
+
+/// The outcome of a transmission with [`Radio::transmit_at()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxInfo<Timer: Frequency> {
+    /// The instant at which the RMARKER of the frame was actually transmitted,
+    /// see the module documentation.
+    pub timestamp: Instant<Nanoseconds>,
+    /// The instant at which the last symbol of the frame was transmitted.
+    pub end: Instant<Timer>,
+}
+
+/// A frame received with [`Radio::receive()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RxInfo<Timer: Frequency> {
+    /// The length of the MPDU (without FCS) written to the buffer.
+    pub len: usize,
+    /// The received signal strength in dBm.
+    pub rssi: i8,
+    /// The link quality indicator (LQI), normalized with
+    /// [`normalize_lqi()`](crate::link_quality::normalize_lqi).
+    pub lqi: u8,
+    /// The instant at which the RMARKER of the frame was received, see the
+    /// module documentation.
+    pub timestamp: Instant<Nanoseconds>,
+    /// The instant at which the last symbol of the frame was received.
+    pub end: Instant<Timer>,
+}
+
+impl<Timer: Frequency> RxInfo<Timer> {
+    /// Return the link quality of the frame.
+    pub const fn link_quality(&self) -> LinkQuality {
+        LinkQuality::new(self.rssi, self.lqi)
+    }
+}
+
+/// The operations of a radio driver, see the module documentation.
+pub trait Radio {
+    /// The timer of the instants passed to and reported by the radio.
+    type Timer: RadioTimerApi;
+
+    /// Return the optional features of the radio.
+    fn capabilities(&self) -> RadioCapabilities;
+
+    /// Tune the radio to the given channel for all subsequent operations.
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError>;
+
+    /// Set the transmit power in dBm for all subsequent transmissions.
+    ///
+    /// Returns the transmit power actually configured, i.e. the nearest
+    /// supported value not exceeding the requested one or
+    /// [`RadioCapabilities::min_tx_power`].
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError>;
+
+    /// Set the PHY mode of all subsequent transmissions, e.g. to switch
+    /// between the data rates of a SUN PHY from frame to frame.
+    ///
+    /// Returns [`RadioError::Unsupported`] if the radio doesn't support the
+    /// mode. Radios that only support [`PhyMode::Oqpsk`] need not implement
+    /// this.
+    fn set_phy_mode(&mut self, mode: PhyMode) -> Result<(), RadioError> {
+        match mode {
+            PhyMode::Oqpsk => Ok(()),
+            PhyMode::SunFsk(_) => Err(RadioError::Unsupported),
+        }
+    }
+
+    /// Transmits the given MPDU (without FCS) so that its RMARKER passes the
+    /// antenna at the given instant or immediately if `None`.
+    ///
+    /// Returns [`RadioError::TooLate`] without transmitting if the instant
+    /// cannot be met within [`RadioCapabilities::timestamp_resolution`].
+    fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<Self::Timer>>,
+    ) -> impl Future<Output = Result<TxInfo<Self::Timer>, RadioError>>;
+
+    /// Receives the next frame within the given window into the given buffer.
+    ///
+    /// The window limits the RMARKER of the frame, i.e. a frame whose SFD was
+    /// detected before the end of the window is received completely.
+    ///
+    /// Returns `None` if no frame was received within the window.
+    fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<Self::Timer>,
+    ) -> impl Future<Output = Result<Option<RxInfo<Self::Timer>>, RadioError>>;
+
+    /// Performs a clear channel assessment with the given mode and returns
+    /// whether the channel is idle.
+    ///
+    /// Returns [`RadioError::Unsupported`] if the radio doesn't support the
+    /// mode.
+    fn cca(&mut self, mode: CcaMode) -> impl Future<Output = Result<bool, RadioError>>;
+
+    /// Measures the energy on the channel over the given duration and returns
+    /// the peak received power in dBm.
+    ///
+    /// The standard measurement lasts 8 symbol periods (IEEE 802.15.4-2020,
+    /// section 10.2.7). Radios measuring in fixed periods SHALL measure for at
+    /// least the given duration.
+    fn energy_detect(
+        &mut self,
+        duration: Duration<Self::Timer>,
+    ) -> impl Future<Output = Result<i8, RadioError>>;
+
+    /// Sets whether hardware ACKs of frames from the given source address set
+    /// the Frame Pending flag, see [`RadioCapabilities::frame_pending`].
+    ///
+    /// Returns [`RadioError::Unsupported`] if the radio doesn't control the
+    /// flag or has no room left for the address. The MAC then acknowledges
+    /// the frames of the device in software.
+    ///
+    /// * `src_address` - Short or extended address (little endian)
+    /// * `pending` - Whether a frame is pending for the device
+    fn set_frame_pending(&mut self, src_address: &[u8], pending: bool) -> Result<(), RadioError> {
+        let _ = (src_address, pending);
+        Err(RadioError::Unsupported)
+    }
+}
@@ -0,0 +1,306 @@
+//! Discrete-event simulation of several nodes on a shared virtual timeline.
+//!
+//! A [`Simulator`] runs one task per node, e.g. a MAC instance, against a
+//! [`VirtualRadio`] attached to a simulated [`VirtualMedium`]. In contrast to
+//! the default mode of the medium, radio operations wait for the
+//! [`TestClock`] instead of moving it forward. The simulator polls all tasks
+//! that can make progress and then moves the clock to the earliest alarm any
+//! task is waiting for. Time therefore passes in jumps from event to event
+//! and a simulation runs much faster than real time.
+//!
+//! Simulations are deterministic: Tasks are polled in the order in which they
+//! were spawned and all random decisions of the medium derive from
+//! [`VirtualMediumConfig::seed`]. Running the same simulation twice yields
+//! exactly the same timeline.
+//!
+//! The simulator runs on the [`TestClock`] of the current thread and resets it
+//! when created. Tasks SHALL only wait for the clock or their radio, i.e. they
+//! may not depend on futures woken by anything outside of the simulation.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Waker},
+};
+use std::{boxed::Box, sync::Arc, task::Wake, vec::Vec};
+
+use crate::{
+    test_clock::TestClock,
+    time::Instant,
+    virtual_radio::{VirtualMedium, VirtualMediumConfig, VirtualRadio},
+    RadioTimerApi,
+};
+
+/// Records whether a node was woken since it was last polled.
+#[derive(Debug, Default)]
+struct NodeWaker(AtomicBool);
+
+impl Wake for NodeWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The task of a node, `None` once it completed.
+struct SimNode {
+    task: Option<Pin<Box<dyn Future<Output = ()>>>>,
+    woken: Arc<NodeWaker>,
+}
+
+/// Runs several nodes on a shared virtual timeline, see the module
+/// documentation.
+pub struct Simulator {
+    medium: VirtualMedium,
+    nodes: Vec<SimNode>,
+}
+
+impl Simulator {
+    /// Creates a new simulation without nodes and resets the clock.
+    pub fn new(config: VirtualMediumConfig) -> Self {
+        TestClock::reset();
+        let medium = VirtualMedium::new(config);
+        medium.set_simulated();
+        Self {
+            medium,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Return the medium shared by all nodes.
+    pub fn medium(&self) -> &VirtualMedium {
+        &self.medium
+    }
+
+    /// Adds a node running the task returned by the given closure on a new
+    /// radio and returns its index.
+    ///
+    /// The task is first polled by the next call to [`Simulator::run_until()`].
+    pub fn spawn<F, Fut>(&mut self, node: F) -> usize
+    where
+        F: FnOnce(VirtualRadio) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let task = node(self.medium.radio());
+        let woken = Arc::new(NodeWaker::default());
+        woken.wake_by_ref();
+        self.nodes.push(SimNode {
+            task: Some(Box::pin(task)),
+            woken,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Return whether the task of the given node completed.
+    pub fn is_done(&self, node: usize) -> bool {
+        self.nodes[node].task.is_none()
+    }
+
+    /// Runs the simulation until all tasks completed or the clock reaches the
+    /// given instant, whatever comes first.
+    ///
+    /// Returns whether all tasks completed. Otherwise, the clock is left at
+    /// the given instant so that the simulation may be continued.
+    pub fn run_until(&mut self, end: Instant<TestClock>) -> bool {
+        loop {
+            self.poll_woken();
+            if self.nodes.iter().all(|node| node.task.is_none()) {
+                return true;
+            }
+
+            let now = TestClock::now();
+            match TestClock::next_alarm() {
+                Some(at) if at <= end => TestClock::advance(at.max(now) - now),
+                _ => {
+                    if end > now {
+                        TestClock::advance(end - now);
+                    }
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Polls the tasks of all woken nodes in order until none is woken
+    /// anymore.
+    fn poll_woken(&mut self) {
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for (id, node) in self.nodes.iter_mut().enumerate() {
+                if !node.woken.0.swap(false, Ordering::Relaxed) {
+                    continue;
+                }
+                let Some(task) = node.task.as_mut() else {
+                    continue;
+                };
+                progress = true;
+                TestClock::set_task(id);
+                let waker = Waker::from(node.woken.clone());
+                let mut cx = Context::from_waker(&waker);
+                if task.as_mut().poll(&mut cx).is_ready() {
+                    node.task = None;
+                }
+            }
+        }
+        TestClock::set_task(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        config::CcaMode,
+        radio::{Radio, RxWindow},
+        time::Duration,
+    };
+
+    /// Let one node answer each frame of another after 192µs (macSifsPeriod)
+    /// and return the start and round-trip time of all complete exchanges.
+    fn ping_pong(seed: u64) -> Vec<(u64, i64)> {
+        let mut simulator = Simulator::new(VirtualMediumConfig {
+            seed,
+            packet_loss: 0.3,
+            ..Default::default()
+        });
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let pings = log.clone();
+        simulator.spawn(|mut radio| async move {
+            let mut buffer = [0; 127];
+            for seq in 0..20u8 {
+                let start = TestClock::now();
+                let tx = radio.transmit_at(&[seq; 10], None).await.unwrap();
+                let deadline = tx.end + Duration::new(1_000);
+                let rx = radio
+                    .receive(&mut buffer, RxWindow::until(deadline))
+                    .await
+                    .unwrap();
+                if let Some(rx) = rx {
+                    assert_eq!(&buffer[..rx.len], &[seq; 5]);
+                    pings
+                        .borrow_mut()
+                        .push((start.tick(), (rx.end - start).ticks()));
+                }
+            }
+        });
+        simulator.spawn(|mut radio| async move {
+            let mut buffer = [0; 127];
+            loop {
+                let rx = radio
+                    .receive(&mut buffer, RxWindow::unbounded())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let seq = buffer[0];
+                let at = rx.end + Duration::new(192 + 160);
+                radio.transmit_at(&[seq; 5], Some(at)).await.unwrap();
+            }
+        });
+
+        // The responder never completes.
+        assert!(!simulator.run_until(Instant::new(1_000_000)));
+        assert!(simulator.is_done(0));
+        assert!(!simulator.is_done(1));
+        assert_eq!(TestClock::now().tick(), 1_000_000);
+        log.take()
+    }
+
+    #[test]
+    fn timeline() {
+        let log = ping_pong(1);
+        // Some frames are lost but not all.
+        assert!(!log.is_empty() && log.len() < 20);
+        // Ping: 160µs SHR and 13 octets of 32µs each, pong: 192µs of
+        // turnaround, 160µs SHR and 8 octets.
+        let exchange = 160 + 13 * 32 + 192 + 160 + 8 * 32;
+        assert!(log.iter().all(|(_, rtt)| *rtt == exchange));
+        assert!(log.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        assert_eq!(ping_pong(1), log);
+        assert_ne!(ping_pong(2), log);
+    }
+
+    #[test]
+    fn collision() {
+        let mut simulator = Simulator::new(VirtualMediumConfig::default());
+        let received = Rc::new(RefCell::new(Vec::new()));
+
+        for delay in [0, 100] {
+            simulator.spawn(move |mut radio| async move {
+                TestClock::wait_for_alarm_at(Instant::new(delay)).await;
+                radio.transmit_at(&[0; 10], None).await.unwrap();
+            });
+        }
+        let frames = received.clone();
+        simulator.spawn(move |mut radio| async move {
+            let mut buffer = [0; 127];
+            while let Some(rx) = radio
+                .receive(&mut buffer, RxWindow::until(Instant::new(5_000)))
+                .await
+                .unwrap()
+            {
+                frames.borrow_mut().push(rx.len);
+            }
+        });
+
+        assert!(simulator.run_until(Instant::new(10_000)));
+        assert!(received.borrow().is_empty());
+        assert_eq!(TestClock::now().tick(), 5_000);
+    }
+
+    #[test]
+    fn cca() {
+        let mut simulator = Simulator::new(VirtualMediumConfig::default());
+        let idle = Rc::new(RefCell::new(Vec::new()));
+
+        simulator.spawn(|mut radio| async move {
+            TestClock::wait_for_alarm_at(Instant::new(1_000)).await;
+            radio.transmit_at(&[0; 10], None).await.unwrap();
+        });
+        let results = idle.clone();
+        simulator.spawn(move |mut radio| async move {
+            for at in [500, 1_200, 2_000] {
+                TestClock::wait_for_alarm_at(Instant::new(at)).await;
+                let idle = radio.cca(CcaMode::CarrierSense).await.unwrap();
+                results.borrow_mut().push(idle);
+            }
+        });
+
+        assert!(simulator.run_until(Instant::new(10_000)));
+        // The transmission lasts from 1000µs to 1576µs.
+        assert_eq!(*idle.borrow(), [true, false, true]);
+        assert_eq!(TestClock::now().tick(), 2_128);
+    }
+
+    #[test]
+    fn energy_detection() {
+        let mut simulator = Simulator::new(VirtualMediumConfig::default());
+        let energy = Rc::new(RefCell::new(Vec::new()));
+
+        simulator.spawn(|mut radio| async move {
+            TestClock::wait_for_alarm_at(Instant::new(1_000)).await;
+            radio.transmit_at(&[0; 10], None).await.unwrap();
+        });
+        let results = energy.clone();
+        simulator.spawn(move |mut radio| async move {
+            for at in [500, 900, 2_000] {
+                TestClock::wait_for_alarm_at(Instant::new(at)).await;
+                let dbm = radio.energy_detect(Duration::new(200)).await.unwrap();
+                results.borrow_mut().push(dbm);
+            }
+        });
+
+        assert!(simulator.run_until(Instant::new(10_000)));
+        // The second measurement overlaps with the start of the transmission.
+        assert_eq!(*energy.borrow(), [-100, -60, -100]);
+        assert_eq!(TestClock::now().tick(), 2_200);
+    }
+}
@@ -0,0 +1,321 @@
+//! Wireshark extcap interface of the sniffer.
+//!
+//! Wireshark runs extcap programs with the following arguments to list their
+//! interfaces and options and to start a capture:
+//!
+//! | Arguments                                              | Output               |
+//! |--------------------------------------------------------|----------------------|
+//! | `--extcap-interfaces`                                  | [`write_interfaces()`] |
+//! | `--extcap-interface <name> --extcap-dlts`              | [`write_dlts()`]     |
+//! | `--extcap-interface <name> --extcap-config`            | [`write_config()`]   |
+//! | `--extcap-interface <name> --capture --fifo <path> ...` | pcap capture to the FIFO |
+//!
+//! The options of a capture are:
+//!
+//! - `--device <path>`: The device of the radio, e.g. the serial port of a
+//!   devkit running a radio co-processor.
+//! - `--channel <channel>`: The channel to listen on, 26 by default.
+//! - `--hop <channels>`: Hop over the given comma-separated channels instead.
+//! - `--dwell <ms>`: The time spent on each channel when hopping, 1s by
+//!   default.
+//!
+//! A FIFO of `-` stands for stdout, so that the program may be used without
+//! Wireshark as well, e.g. piped into `wireshark -k -i -`.
+
+use std::{
+    io::{self, Write},
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use super::pcap::LINKTYPE_IEEE802_15_4_TAP;
+use crate::config::Channel;
+
+/// The version of the extcap interface.
+const VERSION: &str = "0.1.0";
+
+/// The default time spent on each channel when hopping in milliseconds.
+pub const DEFAULT_DWELL_MS: u32 = 1_000;
+
+/// Options passed by Wireshark that take a value but are not used.
+const IGNORED_OPTIONS: [&str; 3] = [
+    "--extcap-capture-filter",
+    "--extcap-control-in",
+    "--extcap-control-out",
+];
+
+/// The errors reported by [`ExtcapRequest::parse()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtcapError {
+    /// The given argument is unknown.
+    UnknownArgument(String),
+    /// The given option requires a value.
+    MissingValue(String),
+    /// The value of the given option is invalid.
+    InvalidValue(String),
+    /// No interface was given or the request is incomplete.
+    MissingArgument(&'static str),
+}
+
+/// The options of a capture, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureOptions {
+    /// The interface to capture on.
+    pub interface: String,
+    /// The FIFO the capture is written to, `-` for stdout.
+    pub fifo: String,
+    /// The device of the radio.
+    pub device: Option<String>,
+    /// The channels to listen on. The sniffer hops if there are several.
+    pub channels: Vec<Channel>,
+    /// The time spent on each channel when hopping in milliseconds.
+    pub dwell_ms: u32,
+}
+
+/// A request of Wireshark, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtcapRequest {
+    /// List the interfaces.
+    Interfaces,
+    /// List the link types of the given interface.
+    Dlts { interface: String },
+    /// List the options of the given interface.
+    Config { interface: String },
+    /// Start a capture.
+    Capture(CaptureOptions),
+}
+
+impl ExtcapRequest {
+    /// Parses the given command line arguments, without the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, ExtcapError> {
+        let mut args = args.into_iter();
+        let mut interfaces = false;
+        let mut dlts = false;
+        let mut config = false;
+        let mut capture = false;
+        let mut interface = None;
+        let mut fifo = None;
+        let mut device = None;
+        let mut channel = None;
+        let mut hop = None;
+        let mut dwell_ms = DEFAULT_DWELL_MS;
+
+        while let Some(arg) = args.next() {
+            // Options are given as `--option value` or `--option=value`.
+            let (option, inline_value) = match arg.split_once('=') {
+                Some((option, value)) => (option.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ExtcapError::MissingValue(option.clone()))
+            };
+            match option.as_str() {
+                "--extcap-interfaces" => interfaces = true,
+                "--extcap-dlts" => dlts = true,
+                "--extcap-config" => config = true,
+                "--capture" => capture = true,
+                "--extcap-version" => {}
+                "--extcap-interface" => interface = Some(value()?),
+                "--fifo" => fifo = Some(value()?),
+                "--device" => device = Some(value()?),
+                "--channel" => channel = Some(parse_channel(&value()?)?),
+                "--hop" => {
+                    let channels = value()?
+                        .split(',')
+                        .map(parse_channel)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    hop = Some(channels);
+                }
+                "--dwell" => {
+                    let dwell = value()?;
+                    dwell_ms = dwell
+                        .parse()
+                        .ok()
+                        .filter(|dwell| *dwell > 0)
+                        .ok_or(ExtcapError::InvalidValue(dwell))?;
+                }
+                ignored if IGNORED_OPTIONS.contains(&ignored) => {
+                    value()?;
+                }
+                _ => return Err(ExtcapError::UnknownArgument(option)),
+            }
+        }
+
+        if interfaces {
+            return Ok(Self::Interfaces);
+        }
+        let interface = interface.ok_or(ExtcapError::MissingArgument("--extcap-interface"))?;
+        if dlts {
+            Ok(Self::Dlts { interface })
+        } else if config {
+            Ok(Self::Config { interface })
+        } else if capture {
+            let channels = hop.unwrap_or_else(|| [channel.unwrap_or_default()].to_vec());
+            Ok(Self::Capture(CaptureOptions {
+                interface,
+                fifo: fifo.ok_or(ExtcapError::MissingArgument("--fifo"))?,
+                device,
+                channels,
+                dwell_ms,
+            }))
+        } else {
+            Err(ExtcapError::MissingArgument("--capture"))
+        }
+    }
+}
+
+/// Parses a channel number.
+fn parse_channel(channel: &str) -> Result<Channel, ExtcapError> {
+    channel
+        .trim()
+        .parse::<u8>()
+        .ok()
+        .and_then(|channel| Channel::try_from(channel).ok())
+        .ok_or_else(|| ExtcapError::InvalidValue(channel.to_string()))
+}
+
+/// Writes the answer to `--extcap-interfaces` listing a single interface.
+///
+/// * `interface` - The name of the interface passed back by Wireshark
+/// * `display` - The description of the interface shown by Wireshark
+pub fn write_interfaces<W: Write>(
+    output: &mut W,
+    interface: &str,
+    display: &str,
+) -> io::Result<()> {
+    writeln!(output, "extcap {{version={VERSION}}}")?;
+    writeln!(
+        output,
+        "interface {{value={interface}}}{{display={display}}}"
+    )
+}
+
+/// Writes the answer to `--extcap-dlts`, i.e. the link type of the capture.
+pub fn write_dlts<W: Write>(output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "dlt {{number={LINKTYPE_IEEE802_15_4_TAP}}}{{name=IEEE802_15_4_TAP}}{{display=IEEE 802.15.4 TAP}}"
+    )
+}
+
+/// Writes the answer to `--extcap-config`, i.e. the options of a capture.
+pub fn write_config<W: Write>(output: &mut W) -> io::Result<()> {
+    writeln!(
+        output,
+        "arg {{number=0}}{{call=--device}}{{display=Device}}{{type=fileselect}}{{mustexist=true}}{{tooltip=The device of the radio, e.g. a serial port}}"
+    )?;
+    writeln!(
+        output,
+        "arg {{number=1}}{{call=--channel}}{{display=Channel}}{{type=integer}}{{range=11,26}}{{default={}}}{{tooltip=The channel to listen on}}",
+        Channel::default().number()
+    )?;
+    writeln!(
+        output,
+        "arg {{number=2}}{{call=--hop}}{{display=Hopping channels}}{{type=string}}{{tooltip=Comma-separated channels to hop over instead}}"
+    )?;
+    writeln!(
+        output,
+        "arg {{number=3}}{{call=--dwell}}{{display=Dwell time (ms)}}{{type=integer}}{{default={DEFAULT_DWELL_MS}}}{{tooltip=The time spent on each channel when hopping}}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<ExtcapRequest, ExtcapError> {
+        ExtcapRequest::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn requests() {
+        assert_eq!(
+            parse("--extcap-interfaces --extcap-version=4.2"),
+            Ok(ExtcapRequest::Interfaces)
+        );
+        assert_eq!(
+            parse("--extcap-interface dot15d4 --extcap-dlts"),
+            Ok(ExtcapRequest::Dlts {
+                interface: "dot15d4".into()
+            })
+        );
+        assert_eq!(
+            parse("--extcap-config --extcap-interface=dot15d4"),
+            Ok(ExtcapRequest::Config {
+                interface: "dot15d4".into()
+            })
+        );
+        assert_eq!(
+            parse(
+                "--capture --extcap-interface dot15d4 --fifo /tmp/fifo \
+                 --extcap-capture-filter x --device /dev/ttyACM0 --channel 15"
+            ),
+            Ok(ExtcapRequest::Capture(CaptureOptions {
+                interface: "dot15d4".into(),
+                fifo: "/tmp/fifo".into(),
+                device: Some("/dev/ttyACM0".into()),
+                channels: [Channel::_15].to_vec(),
+                dwell_ms: DEFAULT_DWELL_MS,
+            }))
+        );
+        let Ok(ExtcapRequest::Capture(options)) =
+            parse("--capture --extcap-interface dot15d4 --fifo - --hop 11,15,26 --dwell 250")
+        else {
+            panic!("not a capture");
+        };
+        assert_eq!(options.channels, [Channel::_11, Channel::_15, Channel::_26]);
+        assert_eq!(options.dwell_ms, 250);
+        assert_eq!(options.device, None);
+    }
+
+    #[test]
+    fn invalid_requests() {
+        assert_eq!(
+            parse("--extcap-dlts"),
+            Err(ExtcapError::MissingArgument("--extcap-interface"))
+        );
+        assert_eq!(
+            parse("--capture --extcap-interface dot15d4"),
+            Err(ExtcapError::MissingArgument("--fifo"))
+        );
+        assert_eq!(
+            parse("--capture --extcap-interface dot15d4 --fifo - --channel 27"),
+            Err(ExtcapError::InvalidValue("27".into()))
+        );
+        assert_eq!(
+            parse("--extcap-interface dot15d4 --fifo"),
+            Err(ExtcapError::MissingValue("--fifo".into()))
+        );
+        assert_eq!(
+            parse("--promiscuous"),
+            Err(ExtcapError::UnknownArgument("--promiscuous".into()))
+        );
+    }
+
+    #[test]
+    fn answers() {
+        let mut output = Vec::new();
+        write_interfaces(&mut output, "dot15d4", "IEEE 802.15.4 sniffer").unwrap();
+        write_dlts(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "extcap {version=0.1.0}",
+                "interface {value=dot15d4}{display=IEEE 802.15.4 sniffer}",
+                "dlt {number=283}{name=IEEE802_15_4_TAP}{display=IEEE 802.15.4 TAP}",
+            ]
+        );
+
+        let mut config = Vec::new();
+        write_config(&mut config).unwrap();
+        let config = String::from_utf8(config).unwrap();
+        assert_eq!(config.lines().count(), 4);
+        assert!(config
+            .contains("{call=--channel}{display=Channel}{type=integer}{range=11,26}{default=26}"));
+    }
+}
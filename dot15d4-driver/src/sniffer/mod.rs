@@ -0,0 +1,266 @@
+//! Sniffer turning a radio into a passive IEEE 802.15.4 capture device.
+//!
+//! A [`Sniffer`] keeps the receiver of a [`Radio`] switched on, either on a
+//! fixed channel or hopping over a list of channels, and writes all received
+//! frames to a pcap capture, see the `pcap` module. Frames are flushed one by
+//! one so that the capture may be streamed over a FIFO or stdout, e.g. to
+//! Wireshark through the extcap interface, see the `extcap` module.
+//!
+//! The radio doesn't filter frames by address or PAN, so the sniffer sees all
+//! frames with a valid FCS. It never transmits, in particular it doesn't
+//! acknowledge frames.
+//!
+//! Frames are timestamped with the radio clock. Timestamps are mapped to the
+//! system time when the sniffer is created.
+
+pub mod extcap;
+pub mod pcap;
+
+use std::{
+    io::{self, Write},
+    time::{self, SystemTime},
+    vec::Vec,
+};
+
+use self::pcap::PcapWriter;
+use crate::{
+    config::Channel,
+    constants::PHY_MAX_PACKET_SIZE_127,
+    radio::{Radio, RadioError, RxWindow},
+    time::{Duration, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+/// The channels a [`Sniffer`] listens on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelPlan<Timer: crate::time::Frequency> {
+    /// Listen on the given channel.
+    Fixed(Channel),
+    /// Listen on each of the given channels in turn for the given duration.
+    Hopping {
+        channels: Vec<Channel>,
+        dwell: Duration<Timer>,
+    },
+}
+
+/// The errors reported by [`Sniffer::run()`].
+#[derive(Debug)]
+pub enum SnifferError {
+    /// The radio failed.
+    Radio(RadioError),
+    /// The capture couldn't be written.
+    Io(io::Error),
+}
+
+impl From<RadioError> for SnifferError {
+    fn from(error: RadioError) -> Self {
+        Self::Radio(error)
+    }
+}
+
+impl From<io::Error> for SnifferError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Captures all frames received by a radio, see the module documentation.
+#[derive(Debug)]
+pub struct Sniffer<R: Radio> {
+    radio: R,
+    plan: ChannelPlan<R::Timer>,
+    /// The index of the current channel in a hopping plan.
+    index: usize,
+    /// The radio clock in nanoseconds when the sniffer was created.
+    origin: Instant<Nanoseconds>,
+    /// The system time when the sniffer was created.
+    origin_time: time::Duration,
+}
+
+impl<R: Radio> Sniffer<R> {
+    /// Creates a sniffer listening on the given channels.
+    ///
+    /// Returns [`RadioError::Unsupported`] if a hopping plan has no channels.
+    pub fn new(mut radio: R, plan: ChannelPlan<R::Timer>) -> Result<Self, RadioError> {
+        let channel = match &plan {
+            ChannelPlan::Fixed(channel) => *channel,
+            ChannelPlan::Hopping { channels, .. } => {
+                *channels.first().ok_or(RadioError::Unsupported)?
+            }
+        };
+        radio.set_channel(channel)?;
+        Ok(Self {
+            radio,
+            plan,
+            index: 0,
+            origin: R::Timer::now().convert_into_rounding_down(),
+            origin_time: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Return the channel the sniffer currently listens on.
+    pub fn channel(&self) -> Channel {
+        match &self.plan {
+            ChannelPlan::Fixed(channel) => *channel,
+            ChannelPlan::Hopping { channels, .. } => channels[self.index],
+        }
+    }
+
+    /// Writes all received frames to the given capture until the given
+    /// instant or forever if `None`.
+    pub async fn run<W: Write>(
+        &mut self,
+        pcap: &mut PcapWriter<W>,
+        end: Option<Instant<R::Timer>>,
+    ) -> Result<(), SnifferError> {
+        let mut buffer = [0; PHY_MAX_PACKET_SIZE_127];
+        let mut hop_at = match &self.plan {
+            ChannelPlan::Fixed(_) => None,
+            ChannelPlan::Hopping { dwell, .. } => Some(R::Timer::now() + *dwell),
+        };
+
+        loop {
+            let now = R::Timer::now();
+            if end.is_some_and(|end| now >= end) {
+                return Ok(());
+            }
+            if let (Some(at), ChannelPlan::Hopping { channels, dwell }) = (hop_at, &self.plan) {
+                if now >= at {
+                    self.index = (self.index + 1) % channels.len();
+                    self.radio.set_channel(channels[self.index])?;
+                    hop_at = Some(now + *dwell);
+                }
+            }
+
+            let window = match [hop_at, end].into_iter().flatten().min() {
+                Some(window_end) => RxWindow::until(window_end),
+                None => RxWindow::unbounded(),
+            };
+            let Some(rx) = self.radio.receive(&mut buffer, window).await? else {
+                continue;
+            };
+            pcap.write_frame(
+                self.system_time(rx.timestamp),
+                self.channel(),
+                rx.link_quality(),
+                &buffer[..rx.len],
+            )?;
+        }
+    }
+
+    /// Return the radio.
+    pub fn into_radio(self) -> R {
+        self.radio
+    }
+
+    /// Return the system time corresponding to the given timestamp of the
+    /// radio clock.
+    fn system_time(&self, timestamp: Instant<Nanoseconds>) -> time::Duration {
+        let elapsed = (timestamp - self.origin).ticks();
+        if elapsed >= 0 {
+            self.origin_time + time::Duration::from_nanos(elapsed as u64)
+        } else {
+            self.origin_time
+                .saturating_sub(time::Duration::from_nanos(elapsed.unsigned_abs()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{simulator::Simulator, test_clock::TestClock, virtual_radio::VirtualMediumConfig};
+
+    /// Sniffs with the given plan while frames are transmitted on channel 15
+    /// at 1ms and on channel 11 at 3ms, and returns the capture.
+    fn sniff(plan: ChannelPlan<TestClock>) -> Vec<u8> {
+        let mut simulator = Simulator::new(VirtualMediumConfig::default());
+        simulator.spawn(|mut radio| async move {
+            for (channel, at) in [(Channel::_15, 1_000), (Channel::_11, 3_000)] {
+                radio.set_channel(channel).unwrap();
+                TestClock::wait_for_alarm_at(Instant::new(at)).await;
+                radio
+                    .transmit_at(&[0x02, 0x10, channel.number() as u8], None)
+                    .await
+                    .unwrap();
+            }
+        });
+        let capture = Rc::new(RefCell::new(Vec::new()));
+        let output = capture.clone();
+        simulator.spawn(move |radio| async move {
+            let mut sniffer = Sniffer::new(radio, plan).unwrap();
+            let mut pcap = PcapWriter::new(Vec::new()).unwrap();
+            sniffer
+                .run(&mut pcap, Some(Instant::new(5_000)))
+                .await
+                .unwrap();
+            *output.borrow_mut() = pcap.into_inner();
+        });
+
+        assert!(simulator.run_until(Instant::new(10_000)));
+        capture.take()
+    }
+
+    /// Return the channel and MPDU of each record of the given capture.
+    fn records(capture: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut records = Vec::new();
+        let mut rest = &capture[24..];
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[8..12].try_into().unwrap()) as usize;
+            let (record, next) = rest[16..].split_at(len);
+            let tap_len = u16::from_le_bytes([record[2], record[3]]) as usize;
+            records.push((record[24], record[tap_len..].to_vec()));
+            rest = next;
+        }
+        records
+    }
+
+    #[test]
+    fn fixed_channel() {
+        let capture = sniff(ChannelPlan::Fixed(Channel::_15));
+        assert_eq!(records(&capture), [(15, [0x02, 0x10, 15].to_vec())]);
+    }
+
+    #[test]
+    fn channel_hopping() {
+        let capture = sniff(ChannelPlan::Hopping {
+            channels: [Channel::_15, Channel::_11].to_vec(),
+            dwell: Duration::new(2_000),
+        });
+        assert_eq!(
+            records(&capture),
+            [
+                (15, [0x02, 0x10, 15].to_vec()),
+                (11, [0x02, 0x10, 11].to_vec())
+            ]
+        );
+
+        // Both frames were received 2ms apart.
+        let timestamp = |record: &[u8]| {
+            let secs = u32::from_le_bytes(record[..4].try_into().unwrap()) as u64;
+            let nanos = u32::from_le_bytes(record[4..8].try_into().unwrap()) as u64;
+            secs * 1_000_000_000 + nanos
+        };
+        let first = &capture[24..];
+        let second = &first[16 + 39..];
+        assert_eq!(timestamp(second) - timestamp(first), 2_000_000);
+    }
+
+    #[test]
+    fn empty_hopping_plan() {
+        let simulator = Simulator::new(VirtualMediumConfig::default());
+        let plan = ChannelPlan::Hopping {
+            channels: Vec::new(),
+            dwell: Duration::new(2_000),
+        };
+        assert_eq!(
+            Sniffer::new(simulator.medium().radio(), plan).err(),
+            Some(RadioError::Unsupported)
+        );
+    }
+}
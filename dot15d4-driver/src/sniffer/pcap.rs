@@ -0,0 +1,157 @@
+//! Writer of pcap captures.
+//!
+//! Frames are written with the IEEE 802.15.4 TAP link type so that Wireshark
+//! shows the channel and link quality along with each frame. The TAP header
+//! precedes the MPDU and carries type-length-value fields, each padded to a
+//! multiple of four octets.
+//!
+//! Timestamps have nanosecond resolution.
+
+use std::{
+    io::{self, Write},
+    time::Duration,
+    vec::Vec,
+};
+
+use crate::{config::Channel, link_quality::LinkQuality};
+
+/// The magic number of pcap captures with nanosecond timestamps.
+const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+
+/// The link type of IEEE 802.15.4 frames preceded by a TAP header.
+pub const LINKTYPE_IEEE802_15_4_TAP: u32 = 283;
+
+/// The maximum length of a captured record, i.e. the TAP header and the
+/// largest MPDU.
+const SNAPLEN: u32 = 256;
+
+/// TLV carrying the type of the FCS included in the frame.
+const TLV_FCS_TYPE: u16 = 0;
+/// TLV carrying the received signal strength in dBm as `f32`.
+const TLV_RSS: u16 = 1;
+/// TLV carrying the channel number and page.
+const TLV_CHANNEL: u16 = 3;
+/// TLV carrying the link quality indicator.
+const TLV_LQI: u16 = 10;
+
+/// The FCS type of frames without FCS, as passed by radio drivers.
+const FCS_TYPE_NONE: u8 = 0;
+
+/// Writes frames to a pcap capture, e.g. a file or a FIFO read by Wireshark.
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Writes the header of the capture to the given writer.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&MAGIC_NANOSECONDS.to_le_bytes());
+        // Version 2.4.
+        header.extend_from_slice(&2u16.to_le_bytes());
+        header.extend_from_slice(&4u16.to_le_bytes());
+        // Timestamps are in UTC and accurate.
+        header.extend_from_slice(&0i32.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&SNAPLEN.to_le_bytes());
+        header.extend_from_slice(&LINKTYPE_IEEE802_15_4_TAP.to_le_bytes());
+        writer.write_all(&header)?;
+        writer.flush()?;
+        Ok(Self { writer })
+    }
+
+    /// Writes a received frame and flushes it to the writer so that it is
+    /// displayed right away.
+    ///
+    /// * `timestamp` - The time at which the frame was received since the
+    ///   Unix epoch
+    /// * `channel` - The channel on which the frame was received
+    /// * `link_quality` - The link quality of the frame
+    /// * `mpdu` - The MPDU without FCS
+    pub fn write_frame(
+        &mut self,
+        timestamp: Duration,
+        channel: Channel,
+        link_quality: LinkQuality,
+        mpdu: &[u8],
+    ) -> io::Result<()> {
+        let mut tap = Vec::with_capacity(36);
+        // Version 0, reserved and the length of the header filled in below.
+        tap.extend_from_slice(&[0, 0, 0, 0]);
+        push_tlv(&mut tap, TLV_FCS_TYPE, &[FCS_TYPE_NONE]);
+        push_tlv(&mut tap, TLV_RSS, &(link_quality.rssi as f32).to_le_bytes());
+        let mut channel_assignment = channel.number().to_le_bytes().to_vec();
+        channel_assignment.push(channel.page().number());
+        push_tlv(&mut tap, TLV_CHANNEL, &channel_assignment);
+        push_tlv(&mut tap, TLV_LQI, &[link_quality.lqi]);
+        let tap_len = tap.len() as u16;
+        tap[2..4].copy_from_slice(&tap_len.to_le_bytes());
+
+        let len = (tap.len() + mpdu.len()) as u32;
+        let mut record = Vec::with_capacity(16 + len as usize);
+        record.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+        record.extend_from_slice(&timestamp.subsec_nanos().to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&tap);
+        record.extend_from_slice(mpdu);
+        self.writer.write_all(&record)?;
+        self.writer.flush()
+    }
+
+    /// Return the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Appends a TLV with the given type and value to the given TAP header.
+fn push_tlv(tap: &mut Vec<u8>, tlv_type: u16, value: &[u8]) {
+    tap.extend_from_slice(&tlv_type.to_le_bytes());
+    tap.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    tap.extend_from_slice(value);
+    tap.resize(tap.len().next_multiple_of(4), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture() {
+        let mut pcap = PcapWriter::new(Vec::new()).unwrap();
+        pcap.write_frame(
+            Duration::new(1_700_000_000, 123),
+            Channel::_15,
+            LinkQuality::new(-60, 0xff),
+            &[0x02, 0x10, 0x2a],
+        )
+        .unwrap();
+        let capture = pcap.into_inner();
+
+        assert_eq!(
+            capture[..24],
+            [
+                0x4d, 0x3c, 0xb2, 0xa1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0x1b, 0x01,
+                0, 0
+            ]
+        );
+        let record = &capture[24..];
+        assert_eq!(record[..4], 1_700_000_000u32.to_le_bytes());
+        assert_eq!(record[4..8], 123u32.to_le_bytes());
+        // A TAP header of 36 octets and the MPDU.
+        assert_eq!(record[8..16], [39, 0, 0, 0, 39, 0, 0, 0]);
+        assert_eq!(
+            record[16..],
+            [
+                0, 0, 36, 0, // TAP header
+                0, 0, 1, 0, 0, 0, 0, 0, // FCS type
+                1, 0, 4, 0, 0, 0, 0x70, 0xc2, // RSS
+                3, 0, 3, 0, 15, 0, 0, 0, // Channel
+                10, 0, 1, 0, 0xff, 0, 0, 0, // LQI
+                0x02, 0x10, 0x2a,
+            ]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Radio timer of drivers running on a host operating system.
+//!
+//! The [`HostRadioTimer`] counts microseconds of the monotonic clock of the
+//! host. Its alarms block the calling thread: Drivers talking to radios
+//! through the operating system, e.g. over sockets or serial lines, block on
+//! I/O anyway and the MAC runs on a simple executor on a dedicated thread.
+
+use core::cell::Cell;
+use std::{sync::OnceLock, thread, time};
+
+use crate::{
+    time::{Frequency, Instant},
+    RadioTimerApi,
+};
+
+/// The host instant at which the radio clock started.
+static EPOCH: OnceLock<time::Instant> = OnceLock::new();
+
+std::thread_local! {
+    static ALARM: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Radio timer with microsecond resolution on top of the monotonic clock of
+/// the host, see the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct HostRadioTimer;
+
+impl HostRadioTimer {
+    /// Return the host instant corresponding to the given instant.
+    fn to_host(at: Instant<Self>) -> time::Instant {
+        *EPOCH.get_or_init(time::Instant::now) + time::Duration::from_micros(at.tick())
+    }
+
+    /// Blocks the calling thread until the given instant.
+    pub(crate) fn sleep_until(at: Instant<Self>) {
+        thread::sleep(Self::to_host(at).saturating_duration_since(time::Instant::now()));
+    }
+
+    /// Return the time left until the given instant in milliseconds, rounded
+    /// up, or zero if it passed already.
+    #[cfg(any(test, feature = "linux"))]
+    pub(crate) fn millis_until(at: Instant<Self>) -> u64 {
+        let left = Self::to_host(at).saturating_duration_since(time::Instant::now());
+        left.as_micros().div_ceil(1_000) as u64
+    }
+}
+
+impl Frequency for HostRadioTimer {
+    const FREQUENCY: u32 = 1_000_000;
+}
+
+impl RadioTimerApi for HostRadioTimer {
+    fn now() -> Instant<Self> {
+        let epoch = *EPOCH.get_or_init(time::Instant::now);
+        Instant::new(epoch.elapsed().as_micros() as u64)
+    }
+
+    fn schedule_alarm(at: Instant<Self>) {
+        ALARM.set(Some(at.tick()));
+    }
+
+    fn cancel_alarm() {
+        ALARM.set(None);
+    }
+
+    async fn wait_for_alarm() -> Instant<Self> {
+        // Safety: An alarm must be scheduled before waiting for it.
+        let at = Instant::new(ALARM.take().expect("no alarm scheduled"));
+        Self::sleep_until(at);
+        at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::time::Duration;
+
+    #[test]
+    fn alarm() {
+        let now = HostRadioTimer::now();
+        HostRadioTimer::schedule_alarm(now + Duration::new(2_000));
+        assert!((1..=2).contains(&HostRadioTimer::millis_until(now + Duration::new(2_000))));
+
+        let mut alarm = pin!(HostRadioTimer::wait_for_alarm());
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(at) = alarm.as_mut().poll(&mut cx) else {
+            panic!("alarms of the host timer block");
+        };
+        assert_eq!(at, now + Duration::new(2_000));
+        assert!(HostRadioTimer::now() >= at);
+        assert_eq!(HostRadioTimer::millis_until(at), 0);
+    }
+}
@@ -0,0 +1,304 @@
+//! Radio driver on top of a Linux IEEE 802.15.4 interface.
+//!
+//! [`LinuxRadio`] exchanges raw MPDUs with the kernel through an `AF_PACKET`
+//! socket and configures the PHY through nl802154. This allows running the MAC
+//! and frame stack on a desktop against real radios, e.g. an ATUSB or a
+//! wpanusb dongle, or against `mac802154_hwsim`.
+//!
+//! The MAC of this crate replaces the one of the kernel, so the radio binds to
+//! a monitor interface. Monitor interfaces pass all received frames up and
+//! transmit frames as they are, e.g.:
+//!
+//! ```sh
+//! iwpan dev wpan0 del
+//! iwpan phy phy0 interface add monitor0 type monitor
+//! ip link set monitor0 up
+//! ```
+//!
+//! Binding to the interface requires `CAP_NET_RAW`, configuring the PHY
+//! `CAP_NET_ADMIN`.
+//!
+//! # Limitations
+//!
+//! The kernel neither exposes the time at which frames were on air nor the
+//! link quality of received frames. Timestamps are derived from the host clock
+//! when the kernel hands frames over, with an accuracy in the order of a
+//! millisecond. This is good enough for CSMA-CA, scans and association but not
+//! for TSCH or CSL.
+//!
+//! CCA and energy detection are not accessible from user space either. The
+//! transceiver assesses the channel itself before each transmission, hence
+//! [`Radio::cca()`] reports an idle channel and [`Radio::energy_detect()`] is
+//! unsupported.
+//!
+//! All operations block the calling thread until they complete, like the
+//! alarms of the [`HostRadioTimer`]. The MAC is therefore meant to run on a
+//! simple executor on a dedicated thread.
+
+mod netlink;
+
+use std::{
+    ffi::CString,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use self::netlink::Nl802154;
+use super::host::HostRadioTimer;
+use crate::{
+    config::{CcaMode, Channel},
+    constants::{FCS_LEN, PHY_MAX_PACKET_SIZE_127},
+    crc16,
+    phy::{air_time, PhyParameters},
+    radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+    time::{Duration, Instant},
+    RadioTimerApi,
+};
+
+/// The protocol of IEEE 802.15.4 frames on packet sockets.
+const ETH_P_IEEE802154: u16 = 0x00f6;
+
+/// The packet type of frames transmitted by the host itself.
+const PACKET_OUTGOING: u8 = 4;
+
+/// The lowest transmit power tried by [`Radio::set_tx_power()`] in dBm.
+const MIN_TX_POWER: i8 = -30;
+
+/// The highest transmit power tried by [`Radio::set_tx_power()`] in dBm.
+const MAX_TX_POWER: i8 = 20;
+
+/// The signal strength reported for all received frames, see the module
+/// documentation.
+const UNKNOWN_RSSI: i8 = i8::MIN;
+
+/// A radio bound to a Linux monitor interface, see the module documentation.
+#[derive(Debug)]
+pub struct LinuxRadio {
+    socket: OwnedFd,
+    ifindex: u32,
+    netlink: Nl802154,
+    /// The timing of the O-QPSK PHY of the 2.4 GHz band.
+    phy: PhyParameters,
+}
+
+impl LinuxRadio {
+    /// Binds to the monitor interface with the given name, e.g. `monitor0`.
+    pub fn open(interface: &str) -> io::Result<Self> {
+        let name = CString::new(interface).map_err(|_| io::ErrorKind::InvalidInput)?;
+        // SAFETY: The name is a valid C string.
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let protocol = ETH_P_IEEE802154.to_be();
+        // SAFETY: The arguments are valid and the descriptor is owned below.
+        let fd = cvt(unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                protocol as i32,
+            )
+        })?;
+        // SAFETY: The descriptor was just opened and is not owned elsewhere.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: All-zero is a valid link-layer address.
+        let mut address: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+        address.sll_family = libc::AF_PACKET as u16;
+        address.sll_protocol = protocol;
+        address.sll_ifindex = ifindex as i32;
+        // SAFETY: The address is valid for the given length.
+        cvt(unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &address as *const libc::sockaddr_ll as *const libc::sockaddr,
+                core::mem::size_of::<libc::sockaddr_ll>() as u32,
+            )
+        })?;
+
+        Ok(Self {
+            socket,
+            ifindex,
+            netlink: Nl802154::connect()?,
+            phy: PhyParameters::default(),
+        })
+    }
+
+    /// Waits up to the given number of milliseconds for a frame, or forever
+    /// if `None`, and returns whether one is ready to be received.
+    fn poll(&self, timeout: Option<u64>) -> io::Result<bool> {
+        let mut fd = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.map_or(-1, |timeout| timeout.min(i32::MAX as u64) as i32);
+        // SAFETY: The descriptor set is valid for the given length.
+        let ready = cvt(unsafe { libc::poll(&mut fd, 1, timeout) })?;
+        Ok(ready > 0)
+    }
+
+    /// Receives the next frame including its FCS into the given buffer.
+    ///
+    /// Returns the length of the frame or `None` if the frame was transmitted
+    /// by the host itself.
+    fn recv(&self, buffer: &mut [u8], flags: i32) -> io::Result<Option<usize>> {
+        // SAFETY: All-zero is a valid link-layer address.
+        let mut address: libc::sockaddr_ll = unsafe { core::mem::zeroed() };
+        let mut address_len = core::mem::size_of::<libc::sockaddr_ll>() as u32;
+        // SAFETY: The buffer and address are valid for the given lengths.
+        let len = cvt(unsafe {
+            libc::recvfrom(
+                self.socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                flags,
+                &mut address as *mut libc::sockaddr_ll as *mut libc::sockaddr,
+                &mut address_len,
+            )
+        })? as usize;
+        Ok((address.sll_pkttype != PACKET_OUTGOING).then_some(len))
+    }
+
+    /// Discards all frames received so far.
+    fn drain(&self) -> io::Result<()> {
+        let mut buffer = [0; PHY_MAX_PACKET_SIZE_127];
+        loop {
+            match self.recv(&mut buffer, libc::MSG_DONTWAIT) {
+                Ok(_) => continue,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+impl Radio for LinuxRadio {
+    type Timer = HostRadioTimer;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        RadioCapabilities {
+            hardware_ack: false,
+            frame_pending: false,
+            timestamp_resolution: Duration::new(1_000_000),
+            min_tx_power: MIN_TX_POWER,
+            max_tx_power: MAX_TX_POWER,
+        }
+    }
+
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        let number = u8::try_from(channel.number()).map_err(|_| RadioError::Unsupported)?;
+        self.netlink
+            .set_channel(self.ifindex, channel.page().number(), number)
+            .map_err(|_| RadioError::Hardware)
+    }
+
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        // The kernel rejects powers the PHY doesn't support exactly.
+        let requested = dbm.clamp(MIN_TX_POWER, MAX_TX_POWER);
+        (MIN_TX_POWER..=requested)
+            .rev()
+            .chain(requested + 1..=MAX_TX_POWER)
+            .find(|dbm| {
+                self.netlink
+                    .set_tx_power(self.ifindex, *dbm as i32 * 100)
+                    .is_ok()
+            })
+            .ok_or(RadioError::Unsupported)
+    }
+
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<HostRadioTimer>>,
+    ) -> Result<TxInfo<HostRadioTimer>, RadioError> {
+        if mpdu.len() + FCS_LEN > PHY_MAX_PACKET_SIZE_127 {
+            return Err(RadioError::FrameTooLong);
+        }
+        let shr: Duration<HostRadioTimer> = self.phy.shr_duration.convert_into_rounding_up();
+        if let Some(at) = at {
+            if at < HostRadioTimer::now() + shr {
+                return Err(RadioError::TooLate);
+            }
+            HostRadioTimer::wait_for_alarm_at(at - shr).await;
+        }
+
+        let start = HostRadioTimer::now();
+        // SAFETY: The buffer is valid for the given length.
+        cvt(unsafe { libc::send(self.socket.as_raw_fd(), mpdu.as_ptr().cast(), mpdu.len(), 0) })
+            .map_err(|_| RadioError::Hardware)?;
+
+        let end = start + air_time(mpdu.len() + FCS_LEN, &self.phy).convert_into_rounding_up();
+        HostRadioTimer::wait_for_alarm_at(end).await;
+        Ok(TxInfo {
+            timestamp: (start + shr).convert_into_rounding_down(),
+            end,
+        })
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<HostRadioTimer>,
+    ) -> Result<Option<RxInfo<HostRadioTimer>>, RadioError> {
+        if let Some(start) = window.start {
+            HostRadioTimer::wait_for_alarm_at(start).await;
+            self.drain().map_err(|_| RadioError::Hardware)?;
+        }
+
+        let mut frame = [0; PHY_MAX_PACKET_SIZE_127];
+        loop {
+            let timeout = window.end.map(HostRadioTimer::millis_until);
+            if !self.poll(timeout).map_err(|_| RadioError::Hardware)? {
+                return Ok(None);
+            }
+            let Some(len) = self.recv(&mut frame, 0).map_err(|_| RadioError::Hardware)? else {
+                continue;
+            };
+            let end = HostRadioTimer::now();
+            let Some(mpdu_len) = len.checked_sub(FCS_LEN) else {
+                continue;
+            };
+            if crc16(&frame[..mpdu_len]).to_le_bytes() != frame[mpdu_len..len] {
+                continue;
+            }
+
+            buffer
+                .get_mut(..mpdu_len)
+                .ok_or(RadioError::FrameTooLong)?
+                .copy_from_slice(&frame[..mpdu_len]);
+            // The RMARKER preceded the PHR and PSDU.
+            let rmarker = end - self.phy.octets(1 + len as i64).convert_into_rounding_up();
+            return Ok(Some(RxInfo {
+                len: mpdu_len,
+                rssi: UNKNOWN_RSSI,
+                lqi: 0xff,
+                timestamp: rmarker.convert_into_rounding_down(),
+                end,
+            }));
+        }
+    }
+
+    async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+        let cca_duration = self.phy.cca_duration.convert_into_rounding_up();
+        HostRadioTimer::wait_for_alarm_at(HostRadioTimer::now() + cca_duration).await;
+        Ok(true)
+    }
+
+    async fn energy_detect(
+        &mut self,
+        _duration: Duration<HostRadioTimer>,
+    ) -> Result<i8, RadioError> {
+        Err(RadioError::Unsupported)
+    }
+}
+
+/// Converts the return value of a system call into a result.
+fn cvt<T: Default + PartialOrd>(ret: T) -> io::Result<T> {
+    if ret < T::default() {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
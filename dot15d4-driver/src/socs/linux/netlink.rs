@@ -0,0 +1,253 @@
+//! The few nl802154 commands needed to configure the PHY of an interface.
+//!
+//! nl802154 is a generic netlink family. Its numeric ID is assigned at runtime
+//! and resolved by name through the generic netlink controller.
+
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    vec::Vec,
+};
+
+use super::cvt;
+
+/// The length of the netlink message header.
+const NLMSG_HDRLEN: usize = 16;
+/// The length of the generic netlink message header.
+const GENL_HDRLEN: usize = 4;
+/// The length of the attribute header.
+const NLA_HDRLEN: usize = 4;
+/// The attribute type without the nested and byte order flags.
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_ACK: u16 = 0x04;
+const NLMSG_ERROR: u16 = 0x02;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL802154_CMD_SET_CHANNEL: u8 = 9;
+const NL802154_CMD_SET_TX_POWER: u8 = 12;
+const NL802154_ATTR_IFINDEX: u16 = 3;
+const NL802154_ATTR_PAGE: u16 = 7;
+const NL802154_ATTR_CHANNEL: u16 = 8;
+const NL802154_ATTR_TX_POWER: u16 = 11;
+
+/// A generic netlink socket talking to the nl802154 family.
+#[derive(Debug)]
+pub(super) struct Nl802154 {
+    socket: OwnedFd,
+    family: u16,
+    seq: u32,
+}
+
+impl Nl802154 {
+    /// Connects to the kernel and resolves the nl802154 family.
+    pub(super) fn connect() -> io::Result<Self> {
+        // SAFETY: The arguments are valid and the descriptor is owned below.
+        let fd = cvt(unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_GENERIC,
+            )
+        })?;
+        // SAFETY: The descriptor was just opened and is not owned elsewhere.
+        let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: An all-zero address is valid and denotes the kernel.
+        let mut kernel: libc::sockaddr_nl = unsafe { core::mem::zeroed() };
+        kernel.nl_family = libc::AF_NETLINK as u16;
+        // SAFETY: The address is valid for the given length.
+        cvt(unsafe {
+            libc::connect(
+                socket.as_raw_fd(),
+                &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+                core::mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        })?;
+
+        let mut netlink = Self {
+            socket,
+            family: GENL_ID_CTRL,
+            seq: 0,
+        };
+        let reply = netlink.request(
+            GENL_ID_CTRL,
+            CTRL_CMD_GETFAMILY,
+            &[(CTRL_ATTR_FAMILY_NAME, b"nl802154\0")],
+        )?;
+        netlink.family = attributes(&reply)
+            .find(|(ty, _)| *ty == CTRL_ATTR_FAMILY_ID)
+            .and_then(|(_, id)| Some(u16::from_ne_bytes(id.try_into().ok()?)))
+            .ok_or(io::ErrorKind::Unsupported)?;
+        Ok(netlink)
+    }
+
+    /// Tunes the PHY of the given interface to the given channel.
+    pub(super) fn set_channel(&mut self, ifindex: u32, page: u8, channel: u8) -> io::Result<()> {
+        self.request(
+            self.family,
+            NL802154_CMD_SET_CHANNEL,
+            &[
+                (NL802154_ATTR_IFINDEX, &ifindex.to_ne_bytes()),
+                (NL802154_ATTR_PAGE, &[page]),
+                (NL802154_ATTR_CHANNEL, &[channel]),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Sets the transmit power of the PHY of the given interface in mBm.
+    ///
+    /// The kernel only accepts powers supported exactly by the PHY.
+    pub(super) fn set_tx_power(&mut self, ifindex: u32, mbm: i32) -> io::Result<()> {
+        self.request(
+            self.family,
+            NL802154_CMD_SET_TX_POWER,
+            &[
+                (NL802154_ATTR_IFINDEX, &ifindex.to_ne_bytes()),
+                (NL802154_ATTR_TX_POWER, &mbm.to_ne_bytes()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Sends a request and waits for its acknowledgement.
+    ///
+    /// Returns the attributes of the reply, if any.
+    fn request(&mut self, family: u16, cmd: u8, attrs: &[(u16, &[u8])]) -> io::Result<Vec<u8>> {
+        self.seq = self.seq.wrapping_add(1);
+        let request = encode_request(family, self.seq, cmd, attrs);
+        // SAFETY: The buffer is valid for the given length.
+        cvt(unsafe {
+            libc::send(
+                self.socket.as_raw_fd(),
+                request.as_ptr().cast(),
+                request.len(),
+                0,
+            )
+        })?;
+
+        let mut buffer = [0u8; 8192];
+        let mut reply = Vec::new();
+        loop {
+            // SAFETY: The buffer is valid for the given length.
+            let len = cvt(unsafe {
+                libc::recv(
+                    self.socket.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            })? as usize;
+            for (ty, seq, payload) in messages(&buffer[..len]) {
+                if seq != self.seq {
+                    continue;
+                }
+                if ty != NLMSG_ERROR {
+                    reply = payload.get(GENL_HDRLEN..).unwrap_or_default().to_vec();
+                    continue;
+                }
+                let error = payload
+                    .get(..4)
+                    .map(|error| i32::from_ne_bytes(error.try_into().unwrap()))
+                    .ok_or(io::ErrorKind::InvalidData)?;
+                return match error {
+                    0 => Ok(reply),
+                    error => Err(io::Error::from_raw_os_error(-error)),
+                };
+            }
+        }
+    }
+}
+
+/// Encodes a generic netlink request with the given attributes.
+fn encode_request(family: u16, seq: u32, cmd: u8, attrs: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(64);
+    // The length is filled in below.
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&family.to_ne_bytes());
+    message.extend_from_slice(&(NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+    message.extend_from_slice(&seq.to_ne_bytes());
+    // The port ID is assigned by the kernel.
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    // Command, version and reserved.
+    message.extend_from_slice(&[cmd, 1, 0, 0]);
+    for (ty, value) in attrs {
+        message.extend_from_slice(&((NLA_HDRLEN + value.len()) as u16).to_ne_bytes());
+        message.extend_from_slice(&ty.to_ne_bytes());
+        message.extend_from_slice(value);
+        message.resize(message.len().next_multiple_of(4), 0);
+    }
+    let len = message.len() as u32;
+    message[..4].copy_from_slice(&len.to_ne_bytes());
+    message
+}
+
+/// Iterates over the type, sequence number and payload of the netlink
+/// messages in the given buffer.
+fn messages(mut buffer: &[u8]) -> impl Iterator<Item = (u16, u32, &[u8])> {
+    core::iter::from_fn(move || {
+        let len = u32::from_ne_bytes(buffer.get(..4)?.try_into().unwrap()) as usize;
+        let ty = u16::from_ne_bytes(buffer.get(4..6)?.try_into().unwrap());
+        let seq = u32::from_ne_bytes(buffer.get(8..12)?.try_into().unwrap());
+        let payload = buffer.get(NLMSG_HDRLEN..len)?;
+        buffer = buffer.get(len.next_multiple_of(4)..).unwrap_or_default();
+        Some((ty, seq, payload))
+    })
+}
+
+/// Iterates over the type and value of the attributes in the given buffer.
+fn attributes(mut buffer: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    core::iter::from_fn(move || {
+        let len = u16::from_ne_bytes(buffer.get(..2)?.try_into().unwrap()) as usize;
+        let ty = u16::from_ne_bytes(buffer.get(2..4)?.try_into().unwrap()) & NLA_TYPE_MASK;
+        let value = buffer.get(NLA_HDRLEN..len)?;
+        buffer = buffer.get(len.next_multiple_of(4)..).unwrap_or_default();
+        Some((ty, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request() {
+        let request = encode_request(
+            0x1c,
+            7,
+            NL802154_CMD_SET_CHANNEL,
+            &[
+                (NL802154_ATTR_IFINDEX, &3u32.to_ne_bytes()),
+                (NL802154_ATTR_CHANNEL, &[26]),
+            ],
+        );
+        assert_eq!(request.len(), NLMSG_HDRLEN + GENL_HDRLEN + 8 + 8);
+
+        let (ty, seq, payload) = messages(&request).next().unwrap();
+        assert_eq!((ty, seq), (0x1c, 7));
+        assert_eq!(payload[..GENL_HDRLEN], [NL802154_CMD_SET_CHANNEL, 1, 0, 0]);
+        let attrs: Vec<_> = attributes(&payload[GENL_HDRLEN..]).collect();
+        assert_eq!(
+            attrs,
+            [
+                (NL802154_ATTR_IFINDEX, &3u32.to_ne_bytes()[..]),
+                (NL802154_ATTR_CHANNEL, &[26][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_messages() {
+        let request = encode_request(GENL_ID_CTRL, 1, CTRL_CMD_GETFAMILY, &[]);
+        assert_eq!(messages(&request[..NLMSG_HDRLEN]).count(), 0);
+        // An attribute claiming to be shorter than its header ends the
+        // iteration.
+        assert_eq!(attributes(&[2, 0, 1, 0]).count(), 0);
+    }
+}
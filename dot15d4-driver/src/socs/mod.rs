@@ -1,2 +1,8 @@
+#[cfg(feature = "std")]
+pub mod host;
+#[cfg(feature = "linux")]
+pub mod linux;
 #[cfg(feature = "nrf")]
 pub mod nrf;
+#[cfg(feature = "serial")]
+pub mod serial;
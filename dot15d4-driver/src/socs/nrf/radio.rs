@@ -7,9 +7,11 @@
 
 use core::{
     cell::{RefCell, RefMut},
+    future::Future,
     num::NonZero,
     ops::Deref,
-    sync::atomic::{compiler_fence, Ordering},
+    pin::pin,
+    sync::atomic::{compiler_fence, AtomicI8, Ordering},
     task::{Context, Poll, Waker},
 };
 
@@ -34,18 +36,22 @@ use crate::trace::{
     TASK_TRANSITION_TO_RX, TASK_TRANSITION_TO_TX, TASK_TX_RUN, TASK_TX_SCHEDULE,
 };
 use crate::{
-    config::{CcaMode, Channel},
+    config::{CcaMode, Channel, ChannelPage},
+    const_config::PHY_CCA_MODE,
     constants::{
         DEFAULT_SFD, FCS_LEN, MAC_AIFS, MAC_LIFS, MAC_SIFS, PHY_HDR_LEN, PHY_MAX_PACKET_SIZE_127,
     },
-    frame::{AddressingFields, RadioFrame, RadioFrameSized},
+    frame::{AddressingFields, Annotated, RadioFrame, RadioFrameSized, RxMetadata},
+    link_quality::normalize_lqi,
+    phy::PhyParameters,
+    radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
     tasks::{
         ExternalRadioTransition, Ifs, OffResult, OffState, PreliminaryFrameInfo, RadioDriver,
         RadioState, RadioTaskError, RadioTransition, RxError, RxResult, RxState, SchedulingError,
         SelfRadioTransition, TaskOff, TaskRx, TaskTx, Timestamp, TxError, TxResult, TxState,
     },
-    time::{Duration, Microseconds},
-    DriverConfig, FcsNone, RadioDriverApi,
+    time::{Duration, Instant, Microseconds},
+    DriverConfig, FcsNone, RadioDriverApi, RadioTimerApi,
 };
 
 use super::NrfRadioTimer;
@@ -58,6 +64,36 @@ pub mod export {
     };
 }
 
+/// The highest LQI reported by the radio. Higher values are saturated.
+const MAX_LQI: u8 = 0x3f;
+
+/// The received power corresponding to an LQI of zero in dBm. The radio
+/// measures the LQI like the ED level, i.e. in dB above this power.
+const LQI_RSSI_OFFSET: i16 = -92;
+
+/// The received power corresponding to an ED sample of zero in dBm.
+const ED_RSSI_OFFSET: i16 = -93;
+
+/// The duration of a single ED measurement, i.e. 8 symbol periods.
+const ED_PERIOD: Duration<Microseconds> = Duration::new(128);
+
+/// The time it takes the radio to ramp up the receiver or transmitter.
+const RAMP_UP: Duration<Microseconds> = Duration::new(40);
+
+/// The PHY of the radio.
+const PHY: PhyParameters = PhyParameters::OQPSK_2450MHZ;
+
+/// The highest transmit power of the radio in dBm.
+#[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+const MAX_TX_POWER: i8 = 8;
+#[cfg(all(feature = "nrf52811", not(feature = "nrf5340-net")))]
+const MAX_TX_POWER: i8 = 4;
+#[cfg(feature = "nrf5340-net")]
+const MAX_TX_POWER: i8 = 0;
+
+/// The lowest transmit power of the radio in dBm.
+const MIN_TX_POWER: i8 = -40;
+
 struct RadioInterruptHandler;
 
 // TODO: Replace with a fast pseudo-executor that is able to poll all purely
@@ -144,6 +180,9 @@ impl NrfRadioDriver {
     }
 }
 
+/// The transmit power configured with [`RadioDriver::set_tx_power()`] in dBm.
+static TX_POWER: AtomicI8 = AtomicI8::new(0);
+
 impl<Task> RadioDriver<NrfRadioDriver, Task> {
     /// Convenience shortcut to access the radio registers.
     ///
@@ -167,6 +206,63 @@ impl<Task> RadioDriver<NrfRadioDriver, Task> {
 
         Self::radio().tifs.write(|w| w.tifs().variant(tifs_us));
     }
+
+    /// Sets the transmit power of the next frame, falling back to the
+    /// configured power.
+    ///
+    /// TXPOWER is sampled when the transmitter ramps up, so it may be written
+    /// while a previous frame is still on air.
+    fn set_frame_tx_power(tx_power: Option<i8>) {
+        Self::write_tx_power(tx_power.unwrap_or_else(|| TX_POWER.load(Ordering::Relaxed)));
+    }
+
+    /// Writes the given transmit power to the TXPOWER register.
+    ///
+    /// Powers not supported by the radio are rounded down to the next
+    /// supported power, powers below the lowest one are raised to it.
+    fn write_tx_power(mut power: i8) {
+        Self::radio().txpower.write(|w| loop {
+            break match power {
+                #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+                8 => w.txpower().pos8d_bm(),
+                #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+                7 => w.txpower().pos7d_bm(),
+                #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+                6 => w.txpower().pos6d_bm(),
+                #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+                5 => w.txpower().pos5d_bm(),
+                #[cfg(not(feature = "nrf5340-net"))]
+                4 => w.txpower().pos4d_bm(),
+                #[cfg(not(feature = "nrf5340-net"))]
+                3 => w.txpower().pos3d_bm(),
+                #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
+                2 => w.txpower().pos2d_bm(),
+                0 => w.txpower()._0d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -1 => w.txpower().neg1d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -2 => w.txpower().neg2d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -3 => w.txpower().neg3d_bm(),
+                -4 => w.txpower().neg4d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -5 => w.txpower().neg5d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -6 => w.txpower().neg6d_bm(),
+                #[cfg(feature = "nrf5340-net")]
+                -7 => w.txpower().neg7d_bm(),
+                -8 => w.txpower().neg8d_bm(),
+                -12 => w.txpower().neg12d_bm(),
+                -16 => w.txpower().neg16d_bm(),
+                -20 => w.txpower().neg20d_bm(),
+                i8::MIN..=-40 => w.txpower().neg40d_bm(),
+                _ => {
+                    power -= 1;
+                    continue;
+                }
+            };
+        });
+    }
 }
 
 /// RX bit counter event triggered after the frame control field (2 bytes) has
@@ -264,28 +360,12 @@ impl RadioDriver<NrfRadioDriver, TaskOff> {
 
         driver.set_sfd(DEFAULT_SFD);
         driver.set_tx_power(0);
-        driver.set_channel(Channel::_11);
-        driver.set_cca(CcaMode::CarrierSense);
+        OffState::set_channel(&mut driver, Channel::_11).expect("2450 MHz channel");
+        driver.set_cca_mode(PHY_CCA_MODE);
 
         driver
     }
 
-    /// Changes the Clear Channel Assessment method
-    pub fn set_cca(&mut self, cca: CcaMode) {
-        let r = Self::radio();
-        match cca {
-            CcaMode::CarrierSense => r.ccactrl.write(|w| w.ccamode().carrier_mode()),
-            CcaMode::EnergyDetection { ed_threshold } => {
-                // "[ED] is enabled by first configuring the field CCAMODE=EdMode in CCACTRL
-                // and writing the CCAEDTHRES field to a chosen value."
-                r.ccactrl.write(|w| {
-                    w.ccamode().ed_mode();
-                    w.ccaedthres().variant(ed_threshold)
-                });
-            }
-        }
-    }
-
     /// Changes the Start of Frame Delimiter (SFD)
     pub fn set_sfd(&mut self, sfd: u8) {
         Self::radio().sfd.write(|w| w.sfd().variant(sfd));
@@ -293,42 +373,8 @@ impl RadioDriver<NrfRadioDriver, TaskOff> {
 
     /// Changes the radio transmission power
     pub fn set_tx_power(&mut self, power: i8) {
-        Self::radio().txpower.write(|w| match power {
-            #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
-            8 => w.txpower().pos8d_bm(),
-            #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
-            7 => w.txpower().pos7d_bm(),
-            #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
-            6 => w.txpower().pos6d_bm(),
-            #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
-            5 => w.txpower().pos5d_bm(),
-            #[cfg(not(feature = "nrf5340-net"))]
-            4 => w.txpower().pos4d_bm(),
-            #[cfg(not(feature = "nrf5340-net"))]
-            3 => w.txpower().pos3d_bm(),
-            #[cfg(not(any(feature = "nrf52811", feature = "nrf5340-net")))]
-            2 => w.txpower().pos2d_bm(),
-            0 => w.txpower()._0d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -1 => w.txpower().neg1d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -2 => w.txpower().neg2d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -3 => w.txpower().neg3d_bm(),
-            -4 => w.txpower().neg4d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -5 => w.txpower().neg5d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -6 => w.txpower().neg6d_bm(),
-            #[cfg(feature = "nrf5340-net")]
-            -7 => w.txpower().neg7d_bm(),
-            -8 => w.txpower().neg8d_bm(),
-            -12 => w.txpower().neg12d_bm(),
-            -16 => w.txpower().neg16d_bm(),
-            -20 => w.txpower().neg20d_bm(),
-            -40 => w.txpower().neg40d_bm(),
-            _ => panic!("Invalid transmission power value"),
-        });
+        Self::write_tx_power(power);
+        TX_POWER.store(power, Ordering::Relaxed);
     }
 }
 
@@ -386,12 +432,39 @@ impl RadioState<TaskOff> for RadioDriver<NrfRadioDriver, TaskOff> {
 
 impl OffState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskOff> {
     /// Changes the default radio channel
-    fn set_channel(&mut self, channel: Channel) {
-        let channel: u8 = channel.into();
-        let frequency_offset = (channel - 10) * 5;
+    ///
+    /// Note: The radio only supports channels of the 2450 MHz band.
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        if channel.page() != ChannelPage::Oqpsk2450Mhz {
+            return Err(RadioError::Unsupported);
+        }
+        // The offset of the center frequency from 2400 MHz in MHz.
+        let frequency_offset = (channel.center_frequency() / 1_000 - 2_400) as u8;
         Self::radio()
             .frequency
             .write(|w| w.frequency().variant(frequency_offset).map().default());
+        Ok(())
+    }
+
+    /// Changes the Clear Channel Assessment method
+    fn set_cca_mode(&mut self, cca_mode: CcaMode) {
+        // "[ED] is enabled by first configuring the field CCAMODE=EdMode in
+        // CCACTRL and writing the CCAEDTHRES field to a chosen value."
+        Self::radio().ccactrl.write(|w| match cca_mode {
+            CcaMode::EnergyDetection { ed_threshold } => {
+                w.ccamode().ed_mode();
+                w.ccaedthres().variant(ed_threshold)
+            }
+            CcaMode::CarrierSense => w.ccamode().carrier_mode(),
+            CcaMode::CarrierSenseAndEnergyDetection { ed_threshold } => {
+                w.ccamode().carrier_and_ed_mode();
+                w.ccaedthres().variant(ed_threshold)
+            }
+            CcaMode::CarrierSenseOrEnergyDetection { ed_threshold } => {
+                w.ccamode().carrier_or_ed_mode();
+                w.ccaedthres().variant(ed_threshold)
+            }
+        });
     }
 
     fn schedule_rx(
@@ -450,6 +523,7 @@ impl OffState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskOff> {
         }
 
         let cca = tx_task.cca;
+        let tx_power = tx_task.tx_power;
         let packetptr = prepare_tx_frame(&mut tx_task.radio_frame);
         RadioTransition::new(
             self,
@@ -459,6 +533,7 @@ impl OffState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskOff> {
 
                 r.packetptr.write(|w| w.packetptr().variant(packetptr));
                 dma_start_fence();
+                Self::set_frame_tx_power(tx_power);
 
                 if cca {
                     r.shorts.write(|w| {
@@ -617,13 +692,21 @@ impl RadioState<TaskRx> for RadioDriver<NrfRadioDriver, TaskRx> {
                 // The CRC has been checked so the frame must have a non-zero
                 // size saved in the headroom of the nRF packet (PHY header).
                 let rx_task = self.task.take().unwrap();
+                let pdu = rx_task.radio_frame.pdu_ref();
                 let sdu_length_wo_fcs =
-                    NonZero::new(rx_task.radio_frame.pdu_ref()[0] as u16 - FCS_LEN as u16)
-                        .expect("invalid length");
-
-                Ok(RxResult::Frame(
-                    rx_task.radio_frame.with_size(sdu_length_wo_fcs),
-                ))
+                    NonZero::new(pdu[0] as u16 - FCS_LEN as u16).expect("invalid length");
+                // The radio overwrites the last octet of the FCS with the LQI.
+                let lqi = pdu[pdu[0] as usize];
+                let rssi = (LQI_RSSI_OFFSET + lqi as i16).clamp(i8::MIN as i16, i8::MAX as i16);
+
+                let mut radio_frame =
+                    Annotated::new(rx_task.radio_frame.with_size(sdu_length_wo_fcs));
+                radio_frame.annotations.insert(RxMetadata {
+                    timestamp: None,
+                    lqi: Some(normalize_lqi(lqi, MAX_LQI)),
+                    rssi: Some(rssi as i8),
+                });
+                Ok(RxResult::Frame(radio_frame))
             }
         } else {
             // Otherwise: Cancel the ongoing task and leave the receiver in idle
@@ -911,6 +994,7 @@ impl RxState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskRx> {
         }
 
         let cca = tx_task.cca;
+        let tx_power = tx_task.tx_power;
         // PACKETPTR is double buffered so we don't cause a race by setting it
         // while reception might still be ongoing.
         let packetptr = prepare_tx_frame(&mut tx_task.radio_frame);
@@ -922,6 +1006,7 @@ impl RxState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskRx> {
 
                 r.packetptr.write(|w| w.packetptr().variant(packetptr));
                 dma_start_fence();
+                Self::set_frame_tx_power(tx_power);
 
                 Self::set_ifs(ifs);
 
@@ -1205,6 +1290,7 @@ impl TxState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskTx> {
         }
 
         let cca = tx_task.cca;
+        let tx_power = tx_task.tx_power;
         let packetptr = prepare_tx_frame(&mut tx_task.radio_frame);
         RadioTransition::new(
             self,
@@ -1216,6 +1302,7 @@ impl TxState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskTx> {
 
                 r.packetptr.write(|w| w.packetptr().variant(packetptr));
                 dma_start_fence();
+                Self::set_frame_tx_power(tx_power);
 
                 Self::set_ifs(ifs);
 
@@ -1336,6 +1423,291 @@ impl TxState<NrfRadioDriver> for RadioDriver<NrfRadioDriver, TaskTx> {
     }
 }
 
+/// Operations of the [`Radio`] trait, e.g. to drive the MAC engines without
+/// the driver service.
+///
+/// Every operation starts and ends with the radio disabled, i.e. in the Off
+/// state. Operations are scheduled and timestamped in software with the radio
+/// timer, so timestamps are accurate to a tick of the RTC.
+impl Radio for RadioDriver<NrfRadioDriver, TaskOff> {
+    type Timer = NrfRadioTimer;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        RadioCapabilities {
+            hardware_ack: false,
+            frame_pending: false,
+            // One tick of the 32.768 kHz RTC.
+            timestamp_resolution: Duration::new(30_518),
+            min_tx_power: MIN_TX_POWER,
+            max_tx_power: MAX_TX_POWER,
+        }
+    }
+
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        OffState::set_channel(self, channel)
+    }
+
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        RadioDriver::set_tx_power(self, dbm);
+        // TXPOWER holds the rounded power in dBm.
+        Ok(Self::radio().txpower.read().txpower().bits() as i8)
+    }
+
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<NrfRadioTimer>>,
+    ) -> Result<TxInfo<NrfRadioTimer>, RadioError> {
+        let psdu_length = mpdu.len() + FCS_LEN;
+        if psdu_length > PHY_MAX_PACKET_SIZE_127 {
+            return Err(RadioError::FrameTooLong);
+        }
+        let mut packet = [0; PHY_HDR_LEN + PHY_MAX_PACKET_SIZE_127];
+        packet[0] = psdu_length as u8;
+        packet[PHY_HDR_LEN..PHY_HDR_LEN + mpdu.len()].copy_from_slice(mpdu);
+
+        // The RMARKER passes the antenna once the transmitter ramped up and
+        // the SHR was sent.
+        let lead: Duration<NrfRadioTimer> = (RAMP_UP + PHY.shr_duration).convert_into_rounding_up();
+        Self::disable().await;
+        if let Some(at) = at {
+            if at < NrfRadioTimer::now() + lead {
+                return Err(RadioError::TooLate);
+            }
+            NrfRadioTimer::wait_for_alarm_at(at - lead).await;
+        }
+
+        let guard = CancellationGuard::new(Self::cancel);
+        let r = Self::radio();
+        r.packetptr
+            .write(|w| w.packetptr().variant(packet.as_ptr() as u32));
+        dma_start_fence();
+        r.shorts.write(|w| {
+            w.txready_start().enabled();
+            w.end_disable().enabled()
+        });
+        let start = NrfRadioTimer::now();
+        r.tasks_txen.write(|w| w.tasks_txen().set_bit());
+
+        core::future::poll_fn(|cx| {
+            if r.events_end.read().events_end().bit_is_set() {
+                r.events_end.reset();
+                Poll::Ready(())
+            } else {
+                RadioInterruptHandler::arm(cx, |w| w.end().set_bit());
+                Poll::Pending
+            }
+        })
+        .await;
+        let end = NrfRadioTimer::now();
+
+        Self::disable().await;
+        guard.inactivate();
+        Ok(TxInfo {
+            timestamp: (start + lead).convert_into_rounding_down(),
+            end,
+        })
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<NrfRadioTimer>,
+    ) -> Result<Option<RxInfo<NrfRadioTimer>>, RadioError> {
+        let mut packet = [0; PHY_HDR_LEN + PHY_MAX_PACKET_SIZE_127];
+        let ramp_up: Duration<NrfRadioTimer> = RAMP_UP.convert_into_rounding_up();
+        Self::disable().await;
+        if let Some(start) = window.start {
+            if start > NrfRadioTimer::now() + ramp_up {
+                NrfRadioTimer::wait_for_alarm_at(start - ramp_up).await;
+            }
+        }
+
+        let guard = CancellationGuard::new(Self::cancel);
+        let r = Self::radio();
+        r.packetptr
+            .write(|w| w.packetptr().variant(packet.as_ptr() as u32));
+        dma_start_fence();
+        r.shorts.write(|w| w.rxready_start().enabled());
+        r.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+
+        let mut window_end = pin!(window.end.map(NrfRadioTimer::wait_for_alarm_at));
+        let mut window_ended = false;
+        let result = loop {
+            // Frames whose SFD was detected before the end of the window are
+            // received completely.
+            let frame_ended = core::future::poll_fn(|cx| {
+                if r.events_end.read().events_end().bit_is_set() {
+                    r.events_end.reset();
+                    return Poll::Ready(true);
+                }
+                if let Some(alarm) = window_end.as_mut().as_pin_mut() {
+                    if alarm.poll(cx).is_ready() {
+                        window_end.set(None);
+                        window_ended = true;
+                    }
+                }
+                if window_ended
+                    && r.events_framestart
+                        .read()
+                        .events_framestart()
+                        .bit_is_clear()
+                {
+                    return Poll::Ready(false);
+                }
+                RadioInterruptHandler::arm(cx, |w| w.end().set_bit());
+                Poll::Pending
+            })
+            .await;
+            if !frame_ended {
+                break Ok(None);
+            }
+
+            let end = NrfRadioTimer::now();
+            r.events_framestart.reset();
+            if r.events_crcerror.read().events_crcerror().bit_is_set() {
+                // Drop the frame and listen for the next one.
+                r.events_crcerror.reset();
+                r.tasks_start.write(|w| w.tasks_start().set_bit());
+                continue;
+            }
+            r.events_crcok.reset();
+            dma_end_fence();
+
+            let psdu_length = packet[0] as usize;
+            let Some(mpdu_length) = psdu_length.checked_sub(FCS_LEN) else {
+                r.tasks_start.write(|w| w.tasks_start().set_bit());
+                continue;
+            };
+            let Some(mpdu) = buffer.get_mut(..mpdu_length) else {
+                break Err(RadioError::FrameTooLong);
+            };
+            mpdu.copy_from_slice(&packet[PHY_HDR_LEN..PHY_HDR_LEN + mpdu_length]);
+            // The radio overwrites the last octet of the FCS with the LQI.
+            let lqi = packet[psdu_length];
+            let rssi = (LQI_RSSI_OFFSET + lqi as i16).clamp(i8::MIN as i16, i8::MAX as i16);
+            // The RMARKER preceded the PHR and PSDU.
+            let rmarker = end
+                - PHY
+                    .octets((PHY_HDR_LEN + psdu_length) as i64)
+                    .convert_into_rounding_up();
+            break Ok(Some(RxInfo {
+                len: mpdu_length,
+                rssi: rssi as i8,
+                lqi: normalize_lqi(lqi, MAX_LQI),
+                timestamp: rmarker.convert_into_rounding_down(),
+                end,
+            }));
+        };
+
+        Self::disable().await;
+        guard.inactivate();
+        result
+    }
+
+    async fn cca(&mut self, mode: CcaMode) -> Result<bool, RadioError> {
+        self.set_cca_mode(mode);
+        Self::disable().await;
+
+        let guard = CancellationGuard::new(Self::cancel);
+        let r = Self::radio();
+        r.shorts.write(|w| w.rxready_ccastart().enabled());
+        r.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+
+        let idle = core::future::poll_fn(|cx| {
+            if r.events_ccaidle.read().events_ccaidle().bit_is_set() {
+                Poll::Ready(true)
+            } else if r.events_ccabusy.read().events_ccabusy().bit_is_set() {
+                Poll::Ready(false)
+            } else {
+                RadioInterruptHandler::arm(cx, |w| {
+                    w.ccaidle().set_bit();
+                    w.ccabusy().set_bit()
+                });
+                Poll::Pending
+            }
+        })
+        .await;
+
+        Self::disable().await;
+        guard.inactivate();
+        Ok(idle)
+    }
+
+    async fn energy_detect(&mut self, duration: Duration<NrfRadioTimer>) -> Result<i8, RadioError> {
+        // The radio measures the peak over EDCNT + 1 periods of 8 symbols.
+        let duration: Duration<Microseconds> = duration.convert_into_rounding_up();
+        let periods = (duration.ticks() + ED_PERIOD.ticks() - 1) / ED_PERIOD.ticks();
+        let edcnt = periods.clamp(1, 0x20_0000) as u32 - 1;
+        Self::disable().await;
+
+        let guard = CancellationGuard::new(Self::cancel);
+        let r = Self::radio();
+        r.edcnt.write(|w| w.edcnt().variant(edcnt));
+        r.shorts.write(|w| w.rxready_edstart().enabled());
+        r.tasks_rxen.write(|w| w.tasks_rxen().set_bit());
+
+        core::future::poll_fn(|cx| {
+            if r.events_edend.read().events_edend().bit_is_set() {
+                Poll::Ready(())
+            } else {
+                RadioInterruptHandler::arm(cx, |w| w.edend().set_bit());
+                Poll::Pending
+            }
+        })
+        .await;
+        let sample = r.edsample.read().edlvl().bits();
+
+        Self::disable().await;
+        guard.inactivate();
+        Ok((ED_RSSI_OFFSET + sample as i16).clamp(i8::MIN as i16, i8::MAX as i16) as i8)
+    }
+}
+
+impl RadioDriver<NrfRadioDriver, TaskOff> {
+    /// Disables the radio, waits until it is disabled and clears the shorts
+    /// and events of the previous [`Radio`] operation.
+    async fn disable() {
+        let r = Self::radio();
+        r.shorts.reset();
+        match r.state.read().state().variant() {
+            Some(STATE_A::DISABLED) => {}
+            Some(STATE_A::TX_DISABLE | STATE_A::RX_DISABLE) => {}
+            _ => r.tasks_disable.write(|w| w.tasks_disable().set_bit()),
+        }
+
+        core::future::poll_fn(|cx| {
+            if let Some(STATE_A::DISABLED) = r.state.read().state().variant() {
+                Poll::Ready(())
+            } else {
+                RadioInterruptHandler::arm(cx, |w| w.disabled().set_bit());
+                Poll::Pending
+            }
+        })
+        .await;
+
+        r.events_disabled.reset();
+        r.events_rxready.reset();
+        r.events_txready.reset();
+        r.events_framestart.reset();
+        r.events_end.reset();
+        r.events_crcok.reset();
+        r.events_crcerror.reset();
+        r.events_ccaidle.reset();
+        r.events_ccabusy.reset();
+        r.events_edend.reset();
+    }
+
+    /// Aborts a cancelled [`Radio`] operation. The next operation waits until
+    /// the radio is disabled.
+    fn cancel() {
+        let r = Self::radio();
+        r.shorts.reset();
+        r.intenclr.write(|w| unsafe { w.bits(0xffff_ffff) });
+        r.tasks_disable.write(|w| w.tasks_disable().set_bit());
+    }
+}
+
 fn prepare_tx_frame(radio_frame: &mut RadioFrame<RadioFrameSized>) -> u32 {
     let sdu_length = radio_frame.sdu_wo_fcs_length().get() as u8 + FCS_LEN as u8;
     // Set PHY HDR.
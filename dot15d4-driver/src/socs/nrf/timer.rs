@@ -116,6 +116,13 @@ impl Alarms {
     fn get_and_clear_fired(&self) -> u64 {
         self.fired.replace(Self::OFF)
     }
+
+    /// Removes the pending, next and fired timeouts.
+    fn cancel(&self) {
+        self.pending.set(Self::OFF);
+        self.next.set(Self::OFF);
+        self.fired.set(Self::OFF);
+    }
 }
 
 struct RtcDriver {
@@ -305,6 +312,13 @@ impl RtcDriver {
         })
     }
 
+    fn cancel_alarm(&self) {
+        critical_section::with(|cs| {
+            Self::rtc().intenclr.write(|w| w.compare0().set_bit());
+            self.alarms.borrow(cs).cancel();
+        })
+    }
+
     async fn wait_for_alarm(&self) -> u64 {
         let cleanup_on_drop = CancellationGuard::new(|| {
             critical_section::with(|cs| {
@@ -376,6 +390,10 @@ impl RadioTimerApi for NrfRadioTimer {
         DRIVER.schedule_alarm(at.tick());
     }
 
+    fn cancel_alarm() {
+        DRIVER.cancel_alarm();
+    }
+
     async fn wait_for_alarm() -> Instant<Self> {
         Instant::new(DRIVER.wait_for_alarm().await)
     }
@@ -0,0 +1,131 @@
+//! HDLC-like framing of messages on a byte stream (RFC 1662, section 4).
+//!
+//! Each frame is delimited by flag bytes and carries the message followed by
+//! its FCS-16 in little-endian byte order. Flag and escape bytes within the
+//! frame are escaped.
+
+use std::vec::Vec;
+
+/// Delimits frames.
+const FLAG: u8 = 0x7e;
+/// Precedes escaped bytes.
+const ESCAPE: u8 = 0x7d;
+/// Toggled in escaped bytes.
+const ESCAPE_XOR: u8 = 0x20;
+
+/// The maximum length of a message, enough for any command or response.
+pub(super) const MAX_MESSAGE_LEN: usize = 256;
+
+/// Calculates the FCS-16 of the given bytes (RFC 1662, appendix C).
+fn fcs16(bytes: &[u8]) -> u16 {
+    !bytes.iter().fold(0xffff, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u16, |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Appends the frame carrying the given message to the given buffer.
+pub(super) fn encode(message: &[u8], buffer: &mut Vec<u8>) {
+    buffer.push(FLAG);
+    for byte in message.iter().chain(&fcs16(message).to_le_bytes()) {
+        if matches!(*byte, FLAG | ESCAPE) {
+            buffer.extend_from_slice(&[ESCAPE, byte ^ ESCAPE_XOR]);
+        } else {
+            buffer.push(*byte);
+        }
+    }
+    buffer.push(FLAG);
+}
+
+/// Extracts messages from a stream of frames.
+///
+/// Frames with an invalid FCS or exceeding [`MAX_MESSAGE_LEN`] are dropped.
+#[derive(Debug, Default)]
+pub(super) struct Decoder {
+    frame: Vec<u8>,
+    escaped: bool,
+    overflow: bool,
+}
+
+impl Decoder {
+    /// Feeds the next byte of the stream and returns the message completed by
+    /// it, if any.
+    pub(super) fn decode(&mut self, byte: u8) -> Option<Vec<u8>> {
+        match byte {
+            FLAG => {
+                let frame = core::mem::take(&mut self.frame);
+                let overflow = core::mem::take(&mut self.overflow);
+                self.escaped = false;
+                let message_len = frame.len().checked_sub(2)?;
+                let (message, fcs) = frame.split_at(message_len);
+                (!overflow && fcs == fcs16(message).to_le_bytes()).then(|| message.to_vec())
+            }
+            ESCAPE => {
+                self.escaped = true;
+                None
+            }
+            byte => {
+                let byte = if core::mem::take(&mut self.escaped) {
+                    byte ^ ESCAPE_XOR
+                } else {
+                    byte
+                };
+                if self.frame.len() < MAX_MESSAGE_LEN + 2 {
+                    self.frame.push(byte);
+                } else {
+                    self.overflow = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fcs() {
+        // The check value of the CRC-16/IBM-SDLC catalogue entry.
+        assert_eq!(fcs16(b"123456789"), 0x906e);
+    }
+
+    #[test]
+    fn framing() {
+        let message = [0x01, FLAG, 0x02, ESCAPE, 0x03];
+        let mut stream = Vec::new();
+        encode(&message, &mut stream);
+        assert_eq!(stream[..7], [FLAG, 0x01, ESCAPE, 0x5e, 0x02, ESCAPE, 0x5d]);
+        encode(&[0x04], &mut stream);
+
+        let mut decoder = Decoder::default();
+        let messages: Vec<_> = stream
+            .iter()
+            .filter_map(|byte| decoder.decode(*byte))
+            .collect();
+        assert_eq!(messages, [&message[..], &[0x04][..]]);
+    }
+
+    #[test]
+    fn corrupted_frames() {
+        let mut stream = Vec::new();
+        encode(&[0x01, 0x02], &mut stream);
+        stream[2] ^= 0x01;
+        // Line noise between frames.
+        stream.extend_from_slice(&[0x55; MAX_MESSAGE_LEN + 10]);
+        encode(&[0x03], &mut stream);
+
+        let mut decoder = Decoder::default();
+        let messages: Vec<_> = stream
+            .iter()
+            .filter_map(|byte| decoder.decode(*byte))
+            .collect();
+        assert_eq!(messages, [[0x03]]);
+    }
+}
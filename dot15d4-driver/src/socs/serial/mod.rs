@@ -0,0 +1,347 @@
+//! Host side of a radio co-processor attached over a serial line.
+//!
+//! [`SerialRadio`] forwards all radio operations to a devkit running a radio
+//! co-processor firmware, e.g. over a UART or USB CDC ACM. This allows running
+//! the MAC and frame stack on a PC while the radio lives on the devkit. The
+//! serial line can be any byte stream implementing [`Read`] and [`Write`],
+//! e.g. a TTY opened as a file and configured with `stty`.
+//!
+//! Commands and responses are framed with HDLC-like framing, see the `hdlc`
+//! module, and encoded as described in the `protocol` module.
+//!
+//! # Time
+//!
+//! The co-processor schedules operations and timestamps frames with its own
+//! radio clock. The driver maps instants of the [`HostRadioTimer`] to the clock
+//! of the co-processor with an offset measured when connecting, accurate to
+//! half the round-trip time of a command. The offset is not corrected for
+//! drift, long-running applications call [`SerialRadio::synchronize()`] from
+//! time to time.
+//!
+//! All operations block the calling thread until the co-processor responds,
+//! like the alarms of the [`HostRadioTimer`].
+
+mod hdlc;
+mod protocol;
+
+use std::{
+    io::{self, Read, Write},
+    vec::Vec,
+};
+
+use self::protocol::{Command, Response};
+use super::host::HostRadioTimer;
+use crate::{
+    config::{CcaMode, Channel, ChannelPage},
+    constants::{FCS_LEN, PHY_MAX_PACKET_SIZE_127},
+    radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+    time::{Duration, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+/// A radio co-processor attached over a serial line, see the module
+/// documentation.
+#[derive(Debug)]
+pub struct SerialRadio<T: Read + Write> {
+    transport: T,
+    decoder: hdlc::Decoder,
+    capabilities: RadioCapabilities,
+    /// The clock of the co-processor minus the host clock in microseconds.
+    offset: i64,
+}
+
+impl<T: Read + Write> SerialRadio<T> {
+    /// Connects to the co-processor on the given serial line.
+    pub fn new(transport: T) -> io::Result<Self> {
+        let mut radio = Self {
+            transport,
+            decoder: hdlc::Decoder::default(),
+            capabilities: RadioCapabilities {
+                hardware_ack: false,
+                frame_pending: false,
+                timestamp_resolution: Duration::new(0),
+                min_tx_power: 0,
+                max_tx_power: 0,
+            },
+            offset: 0,
+        };
+        radio.capabilities = match radio.request(Command::GetCapabilities)? {
+            Response::Capabilities(capabilities) => capabilities,
+            _ => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        radio.synchronize()?;
+        Ok(radio)
+    }
+
+    /// Measures the offset between the clocks of the host and the
+    /// co-processor, see the module documentation.
+    pub fn synchronize(&mut self) -> io::Result<()> {
+        let sent = HostRadioTimer::now();
+        let Response::Time(device) = self.request(Command::GetTime)? else {
+            return Err(io::ErrorKind::InvalidData.into());
+        };
+        let received = HostRadioTimer::now();
+        let host = sent.tick() + (received - sent).ticks() as u64 / 2;
+        self.offset = device as i64 - host as i64;
+        Ok(())
+    }
+
+    /// Return the serial line.
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Return the instant of the co-processor corresponding to the given
+    /// instant.
+    fn to_device(&self, at: Instant<HostRadioTimer>) -> u64 {
+        (at.tick() as i64 + self.offset) as u64
+    }
+
+    /// Return the instant corresponding to the given instant of the
+    /// co-processor.
+    fn to_host(&self, at: u64) -> Instant<HostRadioTimer> {
+        Instant::new((at as i64 - self.offset) as u64)
+    }
+
+    /// Return the timestamp corresponding to the given timestamp of the
+    /// co-processor.
+    fn to_host_timestamp(&self, timestamp: u64) -> Instant<Nanoseconds> {
+        Instant::new((timestamp as i64 - self.offset * 1_000) as u64)
+    }
+
+    /// Sends the given command and waits for its response.
+    fn request(&mut self, command: Command) -> io::Result<Response> {
+        let mut message = Vec::with_capacity(hdlc::MAX_MESSAGE_LEN);
+        command.encode(&mut message);
+        let mut frame = Vec::with_capacity(2 * message.len() + 4);
+        hdlc::encode(&message, &mut frame);
+        self.transport.write_all(&frame)?;
+        self.transport.flush()?;
+
+        let mut buffer = [0; 64];
+        loop {
+            let len = match self.transport.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(len) => len,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            // Commands are answered one at a time, so no further message
+            // follows the response.
+            let message = buffer[..len]
+                .iter()
+                .find_map(|byte| self.decoder.decode(*byte));
+            if let Some(message) = message {
+                let response = Response::decode(&message);
+                return response.ok_or_else(|| io::ErrorKind::InvalidData.into());
+            }
+        }
+    }
+
+    /// Sends the given command and maps transport and protocol errors to
+    /// [`RadioError::Hardware`].
+    fn execute(&mut self, command: Command) -> Result<Response, RadioError> {
+        match self.request(command) {
+            Ok(Response::Error(error)) => Err(error),
+            Ok(response) => Ok(response),
+            Err(_) => Err(RadioError::Hardware),
+        }
+    }
+}
+
+impl<T: Read + Write> Radio for SerialRadio<T> {
+    type Timer = HostRadioTimer;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        self.capabilities
+    }
+
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        // The co-processor only supports channels of the 2450 MHz band.
+        if channel.page() != ChannelPage::Oqpsk2450Mhz {
+            return Err(RadioError::Unsupported);
+        }
+        match self.execute(Command::SetChannel(channel.number() as u8))? {
+            Response::Done => Ok(()),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        match self.execute(Command::SetTxPower(dbm))? {
+            Response::TxPower(dbm) => Ok(dbm),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<HostRadioTimer>>,
+    ) -> Result<TxInfo<HostRadioTimer>, RadioError> {
+        if mpdu.len() + FCS_LEN > PHY_MAX_PACKET_SIZE_127 {
+            return Err(RadioError::FrameTooLong);
+        }
+        let at = at.map(|at| self.to_device(at));
+        match self.execute(Command::Transmit { at, mpdu })? {
+            Response::Transmitted { timestamp, end } => Ok(TxInfo {
+                timestamp: self.to_host_timestamp(timestamp),
+                end: self.to_host(end),
+            }),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<HostRadioTimer>,
+    ) -> Result<Option<RxInfo<HostRadioTimer>>, RadioError> {
+        let start = window.start.map(|start| self.to_device(start));
+        let end = window.end.map(|end| self.to_device(end));
+        match self.execute(Command::Receive { start, end })? {
+            Response::Received {
+                timestamp,
+                end,
+                rssi,
+                lqi,
+                mpdu,
+            } => {
+                buffer
+                    .get_mut(..mpdu.len())
+                    .ok_or(RadioError::FrameTooLong)?
+                    .copy_from_slice(&mpdu);
+                Ok(Some(RxInfo {
+                    len: mpdu.len(),
+                    rssi,
+                    lqi,
+                    timestamp: self.to_host_timestamp(timestamp),
+                    end: self.to_host(end),
+                }))
+            }
+            Response::Timeout => Ok(None),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+
+    async fn cca(&mut self, mode: CcaMode) -> Result<bool, RadioError> {
+        match self.execute(Command::Cca(mode))? {
+            Response::Cca(idle) => Ok(idle),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+
+    async fn energy_detect(
+        &mut self,
+        duration: Duration<HostRadioTimer>,
+    ) -> Result<i8, RadioError> {
+        let duration = duration.ticks().clamp(0, u32::MAX as i64) as u32;
+        match self.execute(Command::EnergyDetect(duration))? {
+            Response::Energy(dbm) => Ok(dbm),
+            _ => Err(RadioError::Hardware),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::test_clock::poll_ready;
+
+    /// A co-processor replaying the given responses and recording the
+    /// commands.
+    struct Loopback {
+        responses: VecDeque<Vec<u8>>,
+        written: Vec<u8>,
+        pending: VecDeque<u8>,
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.pending.is_empty() {
+                let Some(response) = self.responses.pop_front() else {
+                    return Ok(0);
+                };
+                let mut frame = Vec::new();
+                hdlc::encode(&response, &mut frame);
+                self.pending.extend(frame);
+            }
+            let len = buffer.len().min(self.pending.len());
+            for (byte, pending) in buffer.iter_mut().zip(self.pending.drain(..len)) {
+                *byte = pending;
+            }
+            Ok(len)
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buffer);
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn serial_radio() {
+        // The co-processor clock is 1s ahead.
+        let device_now = HostRadioTimer::now().tick() + 1_000_000;
+        let mut time = [0x82].to_vec();
+        time.extend_from_slice(&device_now.to_le_bytes());
+        let mut transmitted = [0x85].to_vec();
+        transmitted.extend_from_slice(&(device_now * 1_000 + 5_160_000).to_le_bytes());
+        transmitted.extend_from_slice(&(device_now + 5_320).to_le_bytes());
+        let responses = [
+            [0x81, 0, 0xe8, 0x03, 0, 0, 0xec, 0x08].to_vec(),
+            time,
+            [0x83].to_vec(),
+            transmitted,
+            [0x87].to_vec(),
+            [0xff, 3].to_vec(),
+        ];
+        let mut radio = SerialRadio::new(Loopback {
+            responses: responses.into(),
+            written: Vec::new(),
+            pending: VecDeque::new(),
+        })
+        .unwrap();
+        assert_eq!(radio.capabilities().max_tx_power, 8);
+        // The round trip of the loopback takes far less than 1ms.
+        assert!((radio.offset - 1_000_000).abs() < 1_000);
+
+        assert_eq!(radio.set_channel(Channel::_15), Ok(()));
+        let at = HostRadioTimer::now() + Duration::new(5_160);
+        let tx = poll_ready(radio.transmit_at(&[0x02, 0x10], Some(at))).unwrap();
+        let host_now = device_now as i64 - radio.offset;
+        assert_eq!(tx.end.tick() as i64, host_now + 5_320);
+        assert_eq!(tx.timestamp.tick() as i64, (host_now + 5_160) * 1_000);
+
+        let mut buffer = [0; 127];
+        let window = RxWindow::until(HostRadioTimer::now());
+        assert_eq!(poll_ready(radio.receive(&mut buffer, window)), Ok(None));
+        assert_eq!(
+            poll_ready(radio.energy_detect(Duration::new(128))),
+            Err(RadioError::Unsupported)
+        );
+        // The serial line was closed.
+        assert_eq!(
+            poll_ready(radio.cca(CcaMode::CarrierSense)),
+            Err(RadioError::Hardware)
+        );
+
+        let mut decoder = hdlc::Decoder::default();
+        let commands: Vec<_> = radio
+            .into_inner()
+            .written
+            .iter()
+            .filter_map(|byte| decoder.decode(*byte))
+            .map(|command| command[0])
+            .collect();
+        assert_eq!(commands, [0x01, 0x02, 0x03, 0x05, 0x06, 0x08, 0x07]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Messages exchanged with the radio co-processor.
+//!
+//! Each command of the host is answered by exactly one response of the
+//! co-processor. A message starts with its tag followed by its fields.
+//! Multi-byte fields are little-endian. Instants are given in microseconds and
+//! timestamps in nanoseconds of the radio clock of the co-processor, an
+//! instant of `u64::MAX` stands for "immediately" respectively "never".
+//! Durations are given in microseconds as well.
+//!
+//! | Command          | Tag    | Fields                          | Response             |
+//! |------------------|--------|---------------------------------|----------------------|
+//! | Get capabilities | `0x01` |                                 | Capabilities         |
+//! | Get time         | `0x02` |                                 | Time                 |
+//! | Set channel      | `0x03` | channel: u8                     | Done                 |
+//! | Set TX power     | `0x04` | dBm: i8                         | TX power             |
+//! | Transmit         | `0x05` | RMARKER: u64, MPDU without FCS  | Transmitted          |
+//! | Receive          | `0x06` | start: u64, end: u64            | Received or Timeout  |
+//! | CCA              | `0x07` | mode: u8, ED threshold: u8      | CCA                  |
+//! | Energy detection | `0x08` | duration: u32                   | Energy               |
+//!
+//! | Response     | Tag    | Fields                                                         |
+//! |--------------|--------|----------------------------------------------------------------|
+//! | Capabilities | `0x81` | hardware ACK: u8, timestamp resolution: u32, min/max dBm: i8   |
+//! | Time         | `0x82` | now: u64                                                       |
+//! | Done         | `0x83` |                                                                |
+//! | TX power     | `0x84` | dBm: i8                                                        |
+//! | Transmitted  | `0x85` | timestamp: u64, end: u64                                       |
+//! | Received     | `0x86` | timestamp: u64, end: u64, RSSI: i8, LQI: u8, MPDU without FCS |
+//! | Timeout      | `0x87` |                                                                |
+//! | CCA          | `0x88` | idle: u8                                                       |
+//! | Energy       | `0x89` | peak dBm: i8                                                   |
+//! | Error        | `0xff` | error: u8                                                      |
+//!
+//! CCA modes are numbered as in the standard: 1 for energy detection, 2 for
+//! carrier sense, 3 for carrier sense and energy detection and 4 for carrier
+//! sense or energy detection. Errors are numbered in the order of
+//! [`RadioError`], starting at 1.
+
+use std::vec::Vec;
+
+use crate::{
+    config::CcaMode,
+    radio::{RadioCapabilities, RadioError},
+    time::Duration,
+};
+
+/// Stands for "immediately" or "never" in instants.
+const UNSET: u64 = u64::MAX;
+
+/// A command of the host.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Command<'a> {
+    GetCapabilities,
+    GetTime,
+    SetChannel(u8),
+    SetTxPower(i8),
+    Transmit {
+        at: Option<u64>,
+        mpdu: &'a [u8],
+    },
+    Receive {
+        start: Option<u64>,
+        end: Option<u64>,
+    },
+    Cca(CcaMode),
+    EnergyDetect(u32),
+}
+
+impl Command<'_> {
+    /// Appends the command to the given buffer.
+    pub(super) fn encode(&self, buffer: &mut Vec<u8>) {
+        match *self {
+            Command::GetCapabilities => buffer.push(0x01),
+            Command::GetTime => buffer.push(0x02),
+            Command::SetChannel(channel) => buffer.extend_from_slice(&[0x03, channel]),
+            Command::SetTxPower(dbm) => buffer.extend_from_slice(&[0x04, dbm as u8]),
+            Command::Transmit { at, mpdu } => {
+                buffer.push(0x05);
+                buffer.extend_from_slice(&at.unwrap_or(UNSET).to_le_bytes());
+                buffer.extend_from_slice(mpdu);
+            }
+            Command::Receive { start, end } => {
+                buffer.push(0x06);
+                buffer.extend_from_slice(&start.unwrap_or(UNSET).to_le_bytes());
+                buffer.extend_from_slice(&end.unwrap_or(UNSET).to_le_bytes());
+            }
+            Command::Cca(mode) => {
+                let mode_id = match mode {
+                    CcaMode::EnergyDetection { .. } => 1,
+                    CcaMode::CarrierSense => 2,
+                    CcaMode::CarrierSenseAndEnergyDetection { .. } => 3,
+                    CcaMode::CarrierSenseOrEnergyDetection { .. } => 4,
+                };
+                let ed_threshold = mode.ed_threshold().unwrap_or(0);
+                buffer.extend_from_slice(&[0x07, mode_id, ed_threshold]);
+            }
+            Command::EnergyDetect(duration) => {
+                buffer.push(0x08);
+                buffer.extend_from_slice(&duration.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// A response of the co-processor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Response {
+    Capabilities(RadioCapabilities),
+    Time(u64),
+    Done,
+    TxPower(i8),
+    Transmitted {
+        timestamp: u64,
+        end: u64,
+    },
+    Received {
+        timestamp: u64,
+        end: u64,
+        rssi: i8,
+        lqi: u8,
+        mpdu: Vec<u8>,
+    },
+    Timeout,
+    Cca(bool),
+    Energy(i8),
+    Error(RadioError),
+}
+
+impl Response {
+    /// Decodes the given message.
+    ///
+    /// Returns `None` if the message is malformed.
+    pub(super) fn decode(message: &[u8]) -> Option<Self> {
+        let (tag, fields) = message.split_first()?;
+        let u64_at = |offset: usize| {
+            let bytes = fields.get(offset..offset + 8)?;
+            Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        let response = match (tag, fields) {
+            (0x81, [hardware_ack, resolution @ .., min, max]) if resolution.len() == 4 => {
+                Response::Capabilities(RadioCapabilities {
+                    hardware_ack: *hardware_ack != 0,
+                    frame_pending: false,
+                    timestamp_resolution: Duration::new(u32::from_le_bytes(
+                        resolution.try_into().unwrap(),
+                    ) as i64),
+                    min_tx_power: *min as i8,
+                    max_tx_power: *max as i8,
+                })
+            }
+            (0x82, _) if fields.len() == 8 => Response::Time(u64_at(0)?),
+            (0x83, []) => Response::Done,
+            (0x84, [dbm]) => Response::TxPower(*dbm as i8),
+            (0x85, _) if fields.len() == 16 => Response::Transmitted {
+                timestamp: u64_at(0)?,
+                end: u64_at(8)?,
+            },
+            (0x86, _) if fields.len() >= 18 => Response::Received {
+                timestamp: u64_at(0)?,
+                end: u64_at(8)?,
+                rssi: fields[16] as i8,
+                lqi: fields[17],
+                mpdu: fields[18..].to_vec(),
+            },
+            (0x87, []) => Response::Timeout,
+            (0x88, [idle]) => Response::Cca(*idle != 0),
+            (0x89, [dbm]) => Response::Energy(*dbm as i8),
+            (0xff, [error]) => Response::Error(match error {
+                1 => RadioError::TooLate,
+                2 => RadioError::FrameTooLong,
+                3 => RadioError::Unsupported,
+                _ => RadioError::Hardware,
+            }),
+            _ => return None,
+        };
+        Some(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(command: Command) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        command.encode(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(encode(Command::SetTxPower(-4)), [0x04, 0xfc]);
+        assert_eq!(
+            encode(Command::Transmit {
+                at: None,
+                mpdu: &[0x02, 0x10]
+            }),
+            [0x05, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02, 0x10]
+        );
+        assert_eq!(
+            encode(Command::Receive {
+                start: Some(0x0102),
+                end: None
+            })[..10],
+            [0x06, 0x02, 0x01, 0, 0, 0, 0, 0, 0, 0xff]
+        );
+        assert_eq!(
+            encode(Command::Cca(CcaMode::CarrierSenseOrEnergyDetection {
+                ed_threshold: 0x2a
+            })),
+            [0x07, 4, 0x2a]
+        );
+        assert_eq!(encode(Command::EnergyDetect(128)), [0x08, 0x80, 0, 0, 0]);
+    }
+
+    #[test]
+    fn responses() {
+        assert_eq!(
+            Response::decode(&[0x81, 1, 0xe8, 0x03, 0, 0, 0xec, 0x08]),
+            Some(Response::Capabilities(RadioCapabilities {
+                hardware_ack: true,
+                frame_pending: false,
+                timestamp_resolution: Duration::new(1_000),
+                min_tx_power: -20,
+                max_tx_power: 8,
+            }))
+        );
+        let mut received = [0u8; 21];
+        received[0] = 0x86;
+        received[1] = 0x10;
+        received[9] = 0x20;
+        received[17..].copy_from_slice(&[0xc4, 0xff, 0x02, 0x10]);
+        assert_eq!(
+            Response::decode(&received),
+            Some(Response::Received {
+                timestamp: 0x10,
+                end: 0x20,
+                rssi: -60,
+                lqi: 0xff,
+                mpdu: [0x02, 0x10].to_vec(),
+            })
+        );
+        assert_eq!(Response::decode(&[0x89, 0xa6]), Some(Response::Energy(-90)));
+        assert_eq!(
+            Response::decode(&[0xff, 1]),
+            Some(Response::Error(RadioError::TooLate))
+        );
+
+        // Truncated or unknown messages.
+        assert_eq!(Response::decode(&[]), None);
+        assert_eq!(Response::decode(&[0x82, 0, 0]), None);
+        assert_eq!(Response::decode(&[0x83, 0]), None);
+        assert_eq!(Response::decode(&[0x42]), None);
+    }
+}
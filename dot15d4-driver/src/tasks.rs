@@ -3,9 +3,14 @@
 use core::{convert::Infallible, future::Future, marker::PhantomData};
 
 use crate::{
-    config::Channel,
-    constants::A_MAX_SIFS_FRAME_SIZE,
-    frame::{AddressingFields, FrameControl, RadioFrame, RadioFrameSized, RadioFrameUnsized},
+    config::{CcaMode, Channel},
+    constants::{A_MAX_SIFS_FRAME_SIZE, FCS_LEN},
+    frame::{
+        AddressingFields, Annotated, FrameControl, RadioFrame, RadioFrameSized, RadioFrameUnsized,
+    },
+    radio::RadioError,
+    tx_descriptor::{TxCancelled, TxDescriptor},
+    DriverConfig,
 };
 
 /// Tasks can be scheduled as fast as possible ("best effort") or at a
@@ -84,6 +89,15 @@ pub trait RadioTask {
     /// This type SHALL be the never type (i.e. "Infallible") if starting the
     /// task cannot fail.
     type Error;
+
+    /// Commits the task right before the transition to it is programmed.
+    ///
+    /// Fails if the client cancelled the task, see [`crate::tx_descriptor`].
+    /// The transition is rolled back in this case, see
+    /// [`CompletedRadioTransition::Rollback`].
+    fn commit(&mut self) -> Result<(), TxCancelled> {
+        Ok(())
+    }
 }
 
 /// Task: switch to low energy state
@@ -140,8 +154,9 @@ pub struct TaskRx {
 pub enum RxResult {
     /// A valid frame was successfully received and acknowledged if requested.
     Frame(
-        /// received radio frame
-        RadioFrame<RadioFrameSized>,
+        /// received radio frame and the reception metadata reported by the
+        /// driver
+        Annotated<RadioFrame<RadioFrameSized>>,
     ),
     /// A new task was scheduled before a frame was received.
     RxWindowEnded(
@@ -219,6 +234,15 @@ pub struct TaskTx {
 
     /// whether CCA is to be performed as a precondition to send out the frame
     pub cca: bool,
+
+    /// transmit power of the frame in dBm or [`None`] to use the power
+    /// configured in the driver, subsequent frames SHALL NOT be affected,
+    /// unsupported powers SHALL be rounded down to the next supported power
+    pub tx_power: Option<i8>,
+
+    /// optional descriptor that allows clients to cancel the task, see
+    /// [`crate::tx_descriptor`] for the cancellation semantics
+    pub cancellation: Option<TxDescriptor>,
 }
 /// TX task result
 #[derive(Debug, PartialEq, Eq)]
@@ -242,10 +266,23 @@ pub enum TxError {
         /// The radio frame that could not be sent.
         RadioFrame<RadioFrameSized>,
     ),
+    /// The task was cancelled by the client before the driver committed to
+    /// the transmission.
+    Cancelled(
+        /// The radio frame that was not sent.
+        RadioFrame<RadioFrameSized>,
+    ),
 }
 impl RadioTask for TaskTx {
     type Result = TxResult;
     type Error = TxError;
+
+    fn commit(&mut self) -> Result<(), TxCancelled> {
+        match self.cancellation.take() {
+            Some(tx_descriptor) => tx_descriptor.commit(),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Currently just a placeholder - may report more specific scheduling errors
@@ -474,8 +511,17 @@ pub trait OffState<RadioDriverImpl>: RadioState<TaskOff> {
     /// Set the default radio channel.
     ///
     /// This channel will be used for Rx and Tx if no task-specific channel was
-    /// set.
-    fn set_channel(&mut self, channel: Channel);
+    /// set. If the hardware does not support the channel, then the driver
+    /// SHALL return [`RadioError::Unsupported`] and keep the previous channel.
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError>;
+
+    /// Set the Clear Channel Assessment mode and ED threshold.
+    ///
+    /// The CCA mode SHALL be used for all TX tasks with the cca flag set until
+    /// it is changed again. Drivers SHALL NOT rely on hardware reset defaults
+    /// but apply the given mode. If the hardware does not support the
+    /// requested mode, then the driver SHALL panic.
+    fn set_cca_mode(&mut self, cca_mode: CcaMode);
 
     /// Schedules a transition to the RX state.
     fn schedule_rx(
@@ -518,6 +564,8 @@ pub enum Ifs {
 }
 
 impl Ifs {
+    /// Return the interframe space following a frame with the given MPDU
+    /// length, i.e. the frame length from the PHY header, including the FCS.
     pub fn from_mpdu_length(mpdu_length: u16) -> Ifs {
         if mpdu_length <= A_MAX_SIFS_FRAME_SIZE {
             Ifs::Sifs
@@ -525,6 +573,21 @@ impl Ifs {
             Ifs::Lifs
         }
     }
+
+    /// Return the interframe space following a frame with the given MPDU
+    /// length excluding the FCS.
+    ///
+    /// The IFS depends on the length of the frame on air, i.e. including the
+    /// FCS of the driver configuration. Use this when the FCS may be offloaded
+    /// to the driver and is therefore not part of the frame buffer. Drivers
+    /// offloading the FCS are assumed to send a 2-byte FCS.
+    pub fn from_mpdu_length_wo_fcs<Config: DriverConfig>(mpdu_length_wo_fcs: u16) -> Ifs {
+        let fcs_length = match size_of::<Config::Fcs>() {
+            0 => FCS_LEN,
+            fcs_length => fcs_length,
+        };
+        Self::from_mpdu_length(mpdu_length_wo_fcs + fcs_length as u16)
+    }
 }
 
 /// Generic characterization of the "Receiver ON" (RX) state.
@@ -791,12 +854,13 @@ pub trait ExternalRadioTransition<RadioDriverImpl, ThisTask: RadioTask, NextTask
     /// Switching to the new state SHALL include completing the previous task,
     /// executing the transition behavior as well as `exit()` and `transition()`
     /// in the following order:
-    /// 1. transition: on_scheduled() - non-blocking
-    /// 2. source radio state: run() - blocking
-    /// 3. transition: on_task_complete() - non-blocking
-    /// 4. source radio state: exit() - non-blocking
-    /// 5. target radio state: transition() - blocking
-    /// 6. transition: cleanup() - non-blocking
+    /// 1. target task: commit() - non-blocking
+    /// 2. transition: on_scheduled() - non-blocking
+    /// 3. source radio state: run() - blocking
+    /// 4. transition: on_task_complete() - non-blocking
+    /// 5. source radio state: exit() - non-blocking
+    /// 6. target radio state: transition() - blocking
+    /// 7. transition: cleanup() - non-blocking
     ///
     /// Note that the task result is known once run() finishes but will only be
     /// returned to the radio task scheduler once this method finishes, see the
@@ -826,6 +890,18 @@ where
     async fn execute_transition(
         mut self,
     ) -> CompletedRadioTransition<RadioDriverImpl, ThisTask, NextTask> {
+        if self.next_task.commit().is_err() {
+            #[cfg(feature = "rtos-trace")]
+            rtos_trace::trace::task_exec_end();
+
+            return CompletedRadioTransition::Rollback(
+                self.from_radio,
+                RadioTaskError::Scheduling(SchedulingError),
+                None,
+                self.next_task,
+            );
+        }
+
         if let Err(scheduling_error) = (self.on_scheduled)() {
             #[cfg(feature = "rtos-trace")]
             rtos_trace::trace::task_exec_end();
@@ -926,10 +1002,11 @@ pub trait SelfRadioTransition<RadioDriverImpl, ThisTask: RadioTask, NextTask: Ra
     /// Switching to the new state SHALL include completing the previous task,
     /// executing the full transition behavior but NOT `exit()` or
     /// `transition()` in the following order:
-    /// 1. transition: on_scheduled() - non-blocking
-    /// 2. source radio state: run() - blocking
-    /// 3. transition: on_task_complete() - non-blocking
-    /// 4. transition: cleanup() - non-blocking
+    /// 1. target task: commit() - non-blocking
+    /// 2. transition: on_scheduled() - non-blocking
+    /// 3. source radio state: run() - blocking
+    /// 4. transition: on_task_complete() - non-blocking
+    /// 5. transition: cleanup() - non-blocking
     ///
     /// Note that - other than for external transitions - the task result will
     /// be available synchronously after task completion. This is due to the
@@ -960,6 +1037,15 @@ where
     async fn execute_transition(
         mut self,
     ) -> CompletedRadioTransition<RadioDriverImpl, ThisTask, NextTask> {
+        if self.next_task.commit().is_err() {
+            return CompletedRadioTransition::Rollback(
+                self.from_radio,
+                RadioTaskError::Scheduling(SchedulingError),
+                None,
+                self.next_task,
+            );
+        }
+
         if let Err(scheduling_error) = (self.on_scheduled)() {
             return CompletedRadioTransition::Rollback(
                 self.from_radio,
@@ -1034,7 +1120,9 @@ pub enum CompletedRadioTransition<RadioDriverImpl, PrevTask: RadioTask, ThisTask
     /// The scheduled transition to the next task could not be executed and was
     /// rolled back to the previous transition state. This happens if any of the
     /// source state's methods involved in task execution and transition - up to
-    /// and including the source state's `exit()` method - returns an error.
+    /// and including the source state's `exit()` method - returns an error or
+    /// if the next task was cancelled before it could be committed, see
+    /// [`RadioTask::commit()`].
     ///
     /// Note: The previous task may or may not have produced a result in this
     ///       case. If the result is `None` then the previous task SHALL remain
@@ -1062,3 +1150,43 @@ pub enum CompletedRadioTransition<RadioDriverImpl, PrevTask: RadioTask, ThisTask
         RadioTaskError<ThisTask>,
     ),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::PHY_MAX_PACKET_SIZE_127, export::U, test_clock::TestClock, FcsFourBytes,
+        FcsNone, FcsTwoBytes,
+    };
+
+    struct Config<Fcs>(PhantomData<Fcs>);
+
+    impl<Fcs: Copy + core::fmt::Debug> DriverConfig for Config<Fcs> {
+        type Headroom = U<0>;
+        type Tailroom = U<0>;
+        type MaxSduLength = U<PHY_MAX_PACKET_SIZE_127>;
+        type Fcs = Fcs;
+        type Timer = TestClock;
+    }
+
+    #[test]
+    fn ifs_from_mpdu_length_wo_fcs() {
+        // Frames of up to aMaxSifsFrameSize (18) octets on air get SIFS.
+        for (mpdu_length_wo_fcs, ifs) in [(16, Ifs::Sifs), (17, Ifs::Lifs)] {
+            assert_eq!(
+                Ifs::from_mpdu_length_wo_fcs::<Config<FcsTwoBytes>>(mpdu_length_wo_fcs),
+                ifs
+            );
+            assert_eq!(
+                Ifs::from_mpdu_length_wo_fcs::<Config<FcsNone>>(mpdu_length_wo_fcs),
+                ifs
+            );
+        }
+        for (mpdu_length_wo_fcs, ifs) in [(14, Ifs::Sifs), (15, Ifs::Lifs), (16, Ifs::Lifs)] {
+            assert_eq!(
+                Ifs::from_mpdu_length_wo_fcs::<Config<FcsFourBytes>>(mpdu_length_wo_fcs),
+                ifs
+            );
+        }
+    }
+}
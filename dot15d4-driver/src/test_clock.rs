@@ -0,0 +1,237 @@
+//! A mock radio timer for unit tests.
+//!
+//! The [`TestClock`] does not advance on its own. Tests move it forward
+//! explicitly with [`TestClock::advance()`] so that timeouts, backoffs and
+//! retries can be verified exhaustively and deterministically without
+//! actually waiting.
+//!
+//! The clock state is thread-local: Each test runs against its own clock as
+//! long as it polls all futures from the test thread.
+//!
+//! Each task has its own alarm so that several tasks, e.g. the nodes of a
+//! [`Simulator`](crate::simulator::Simulator), may wait concurrently. The task
+//! to which alarms belong is selected with [`TestClock::set_task()`] before
+//! polling it. Tests with a single task don't need to care.
+//!
+//! [`TestClock::block_on()`] runs a future to completion, moving the clock to
+//! the next alarm whenever the future waits. [`poll_ready()`] runs futures
+//! that never wait, e.g. operations of radios that block.
+
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    time::{Duration, Frequency, Instant},
+    RadioTimerApi,
+};
+
+#[derive(Debug, Default)]
+struct Alarm {
+    at: Option<u64>,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug, Default)]
+struct TestClockState {
+    now: u64,
+    /// The task being polled.
+    task: usize,
+    /// The alarm of each task.
+    alarms: Vec<Alarm>,
+}
+
+impl TestClockState {
+    /// Return the alarm of the task being polled.
+    fn alarm_mut(&mut self) -> &mut Alarm {
+        if self.alarms.len() <= self.task {
+            self.alarms.resize_with(self.task + 1, Alarm::default);
+        }
+        &mut self.alarms[self.task]
+    }
+}
+
+std::thread_local! {
+    static TEST_CLOCK: RefCell<TestClockState> = RefCell::new(TestClockState::default());
+}
+
+/// Mock radio timer with microsecond resolution, see the module
+/// documentation.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct TestClock;
+
+impl Frequency for TestClock {
+    const FREQUENCY: u32 = 1_000_000;
+}
+
+impl TestClock {
+    /// Resets the clock of the current thread to zero and removes all pending
+    /// alarms.
+    pub fn reset() {
+        TEST_CLOCK.with_borrow_mut(|state| *state = TestClockState::default());
+    }
+
+    /// Moves the clock forward by the given duration and wakes all pending
+    /// alarms that expired.
+    pub fn advance(duration: Duration<TestClock>) {
+        debug_assert!(duration.ticks() >= 0);
+        let wakers = TEST_CLOCK.with_borrow_mut(|state| {
+            state.now += duration.ticks() as u64;
+            let now = state.now;
+            state
+                .alarms
+                .iter_mut()
+                .filter(|alarm| alarm.at.is_some_and(|at| at <= now))
+                .filter_map(|alarm| alarm.waker.take())
+                .collect::<Vec<_>>()
+        });
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns the alarm currently scheduled by the task being polled, if any.
+    pub fn alarm() -> Option<Instant<TestClock>> {
+        TEST_CLOCK.with_borrow_mut(|state| state.alarm_mut().at.map(Instant::new))
+    }
+
+    /// Selects the task to which subsequently scheduled and awaited alarms
+    /// belong, see the module documentation.
+    pub fn set_task(task: usize) {
+        TEST_CLOCK.with_borrow_mut(|state| state.task = task);
+    }
+
+    /// Returns the earliest alarm that a task is waiting for, if any.
+    pub fn next_alarm() -> Option<Instant<TestClock>> {
+        TEST_CLOCK.with_borrow(|state| {
+            state
+                .alarms
+                .iter()
+                .filter(|alarm| alarm.waker.is_some())
+                .filter_map(|alarm| alarm.at)
+                .min()
+                .map(Instant::new)
+        })
+    }
+
+    /// Runs the given future on the current thread, advancing the clock to
+    /// the next alarm whenever it is pending.
+    ///
+    /// Panics if the future is pending without waiting for an alarm.
+    pub fn block_on<T>(future: impl Future<Output = T>) -> T {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => {
+                    let alarm = Self::next_alarm().expect("blocked without alarm");
+                    Self::advance(alarm - Self::now());
+                }
+            }
+        }
+    }
+}
+
+impl RadioTimerApi for TestClock {
+    fn now() -> Instant<Self> {
+        TEST_CLOCK.with_borrow(|state| Instant::new(state.now))
+    }
+
+    fn schedule_alarm(at: Instant<Self>) {
+        TEST_CLOCK.with_borrow_mut(|state| state.alarm_mut().at = Some(at.tick()));
+    }
+
+    fn cancel_alarm() {
+        TEST_CLOCK.with_borrow_mut(|state| *state.alarm_mut() = Alarm::default());
+    }
+
+    async fn wait_for_alarm() -> Instant<Self> {
+        poll_fn(|cx| {
+            TEST_CLOCK.with_borrow_mut(|state| {
+                let now = state.now;
+                let alarm = state.alarm_mut();
+                // Safety: An alarm must be scheduled before waiting for it.
+                let at = alarm.at.expect("no alarm scheduled");
+                if at <= now {
+                    *alarm = Alarm::default();
+                    Poll::Ready(Instant::new(at))
+                } else {
+                    alarm.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+        })
+        .await
+    }
+}
+
+/// Polls the given future once and returns its output.
+///
+/// Panics if the future is pending.
+pub fn poll_ready<T>(future: impl Future<Output = T>) -> T {
+    let mut cx = Context::from_waker(Waker::noop());
+    match pin!(future).poll(&mut cx) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the future must not wait"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alarm() {
+        TestClock::reset();
+        assert_eq!(TestClock::now().tick(), 0);
+
+        let mut alarm = pin!(TestClock::wait_for_alarm_at(Instant::new(100)));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(100)));
+        assert!(alarm.as_mut().poll(&mut cx).is_pending());
+
+        TestClock::advance(Duration::new(99));
+        assert!(alarm.as_mut().poll(&mut cx).is_pending());
+
+        TestClock::advance(Duration::new(1));
+        assert_eq!(alarm.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(TestClock::now().tick(), 100);
+        assert_eq!(TestClock::alarm(), None);
+    }
+
+    #[test]
+    fn concurrent_alarms() {
+        TestClock::reset();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        TestClock::set_task(1);
+        let mut first = pin!(TestClock::wait_for_alarm_at(Instant::new(200)));
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        TestClock::set_task(2);
+        let mut second = pin!(TestClock::wait_for_alarm_at(Instant::new(100)));
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::next_alarm(), Some(Instant::new(100)));
+
+        TestClock::advance(Duration::new(100));
+        assert_eq!(second.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(TestClock::next_alarm(), Some(Instant::new(200)));
+        TestClock::set_task(1);
+        assert_eq!(TestClock::alarm(), Some(Instant::new(200)));
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn block_on() {
+        TestClock::reset();
+        let at = TestClock::block_on(async {
+            TestClock::wait_for_alarm_at(Instant::new(100)).await;
+            TestClock::wait_for_alarm_at(Instant::new(250)).await;
+            TestClock::now()
+        });
+        assert_eq!(at, Instant::new(250));
+    }
+}
@@ -2,6 +2,16 @@
 //!
 //! - [`Instant`] is used to represent a point in time.
 //! - [`Duration`] is used to represent a duration of time.
+//!
+//! Both count ticks of a [`Frequency`] given as a type parameter, e.g.
+//! [`Nanoseconds`] for sub-microsecond timeslot alignment, the radio timer
+//! of a driver (see [`Timer`]) or an arbitrary tick rate through [`Hz`].
+//! Arithmetic is exact within a frequency. Conversions between frequencies
+//! are explicit and state their rounding direction.
+//!
+//! The arithmetic operators panic on overflow in debug builds like integer
+//! arithmetic does. Use the `checked_*` and `saturating_*` variants where
+//! overflow is possible, e.g. when adding to [`Instant::NEVER`].
 
 use core::marker::PhantomData;
 
@@ -12,6 +22,7 @@ pub trait Frequency {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Milliseconds;
 
 impl Frequency for Milliseconds {
@@ -19,6 +30,7 @@ impl Frequency for Milliseconds {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Microseconds;
 
 impl Frequency for Microseconds {
@@ -26,6 +38,7 @@ impl Frequency for Microseconds {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Nanoseconds;
 
 impl Frequency for Nanoseconds {
@@ -33,6 +46,7 @@ impl Frequency for Nanoseconds {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct SymbolsOQpsk250kB;
 
 impl Frequency for SymbolsOQpsk250kB {
@@ -40,29 +54,48 @@ impl Frequency for SymbolsOQpsk250kB {
     const FREQUENCY: u32 = 62_500;
 }
 
+/// A tick rate given in Hertz, e.g. `Hz<32_768>` for a low-frequency RTC.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Hz<const FREQUENCY: u32>;
+
+impl<const FREQUENCY: u32> Frequency for Hz<FREQUENCY> {
+    const FREQUENCY: u32 = FREQUENCY;
+}
+
 // The radio timer's high-precision timer frequency in Hertz.
 pub const fn timer_frequency<F: Frequency>() -> u32 {
     <F as Frequency>::FREQUENCY
 }
 
 /// Converts ticks between different frequencies while rounding down.
+///
+/// The intermediate product is calculated with 128 bits as e.g. microseconds
+/// would overflow in nanoseconds after a few hours otherwise.
 const fn convert_rounding_down(ticks: u64, from_frequency: u32, to_frequency: u32) -> u64 {
-    (ticks * to_frequency as u64) / from_frequency as u64
+    ((ticks as u128 * to_frequency as u128) / from_frequency as u128) as u64
 }
 
-/// Converts ticks between different frequencies while rounding up.
+/// Converts ticks between different frequencies while rounding up, see
+/// [`convert_rounding_down()`].
 const fn convert_rounding_up(ticks: u64, from_frequency: u32, to_frequency: u32) -> u64 {
-    (ticks * to_frequency as u64).div_ceil(from_frequency as u64)
+    (ticks as u128 * to_frequency as u128).div_ceil(from_frequency as u128) as u64
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent, bound = "")
+)]
 pub struct Instant<F: Frequency> {
     tick: u64, // in high-precision radio timer ticks
+    #[cfg_attr(feature = "serde", serde(skip))]
     frequency: PhantomData<F>,
 }
 
 impl<F: Frequency> Instant<F> {
+    pub const ZERO: Self = Self::new(0);
     pub const NEVER: Self = Self::new(u64::MAX);
 
     pub const fn new(tick: u64) -> Self {
@@ -117,17 +150,114 @@ impl<F: Frequency> Instant<F> {
     pub const fn tick(&self) -> u64 {
         self.tick
     }
+
+    /// Creates an [`Instant`] from microseconds since the epoch of the
+    /// frequency's clock, rounding up to a tick.
+    pub const fn from_micros(micros: u64) -> Self {
+        Instant::<Microseconds>::new(micros).convert_into_rounding_up()
+    }
+
+    /// Creates an [`Instant`] from nanoseconds since the epoch of the
+    /// frequency's clock, rounding up to a tick.
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Instant::<Nanoseconds>::new(nanos).convert_into_rounding_up()
+    }
+
+    /// Returns the microseconds since the epoch, rounded down.
+    pub const fn as_micros(&self) -> u64 {
+        self.convert_into_rounding_down::<Microseconds>().tick
+    }
+
+    /// Returns the nanoseconds since the epoch, rounded down.
+    pub const fn as_nanos(&self) -> u64 {
+        self.convert_into_rounding_down::<Nanoseconds>().tick
+    }
+
+    /// Returns the instant after the given duration or `None` on overflow.
+    pub const fn checked_add(&self, duration: Duration<F>) -> Option<Self> {
+        let tick = if duration.ticks >= 0 {
+            self.tick.checked_add(duration.ticks as u64)
+        } else {
+            self.tick.checked_sub(duration.ticks.unsigned_abs())
+        };
+        match tick {
+            Some(tick) => Some(Self::new(tick)),
+            None => None,
+        }
+    }
+
+    /// Returns the instant before the given duration or `None` on overflow.
+    pub const fn checked_sub(&self, duration: Duration<F>) -> Option<Self> {
+        match duration.checked_neg() {
+            Some(duration) => self.checked_add(duration),
+            // The negation of the most negative duration is out of range but
+            // may still be subtracted.
+            None => match self.tick.checked_add(duration.ticks.unsigned_abs()) {
+                Some(tick) => Some(Self::new(tick)),
+                None => None,
+            },
+        }
+    }
+
+    /// Returns the instant after the given duration, saturating at
+    /// [`Instant::ZERO`] and [`Instant::NEVER`].
+    pub const fn saturating_add(&self, duration: Duration<F>) -> Self {
+        match self.checked_add(duration) {
+            Some(instant) => instant,
+            None if duration.ticks < 0 => Self::ZERO,
+            None => Self::NEVER,
+        }
+    }
+
+    /// Returns the instant before the given duration, saturating at
+    /// [`Instant::ZERO`] and [`Instant::NEVER`].
+    pub const fn saturating_sub(&self, duration: Duration<F>) -> Self {
+        match self.checked_sub(duration) {
+            Some(instant) => instant,
+            None if duration.ticks > 0 => Self::ZERO,
+            None => Self::NEVER,
+        }
+    }
+
+    /// Returns the duration elapsed from the given instant to this one,
+    /// negative if the given instant is later, or `None` if the duration
+    /// doesn't fit.
+    pub const fn checked_duration_since(&self, earlier: Self) -> Option<Duration<F>> {
+        let ticks = self.tick as i128 - earlier.tick as i128;
+        if ticks > i64::MAX as i128 || ticks < i64::MIN as i128 {
+            None
+        } else {
+            Some(Duration::new(ticks as i64))
+        }
+    }
+
+    /// Returns the duration elapsed from the given instant to this one,
+    /// saturating at [`Duration::MIN`] and [`Duration::MAX`].
+    pub const fn saturating_duration_since(&self, earlier: Self) -> Duration<F> {
+        match self.checked_duration_since(earlier) {
+            Some(duration) => duration,
+            None if self.tick > earlier.tick => Duration::MAX,
+            None => Duration::MIN,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent, bound = "")
+)]
 pub struct Duration<F: Frequency> {
     ticks: i64, // in high-precision radio timer ticks
+    #[cfg_attr(feature = "serde", serde(skip))]
     frequency: PhantomData<F>,
 }
 
 impl<F: Frequency> Duration<F> {
     pub const ZERO: Self = Self::new(0);
+    pub const MAX: Self = Self::new(i64::MAX);
+    pub const MIN: Self = Self::new(i64::MIN);
 
     pub const fn new(ticks: i64) -> Self {
         Self {
@@ -189,8 +319,135 @@ impl<F: Frequency> Duration<F> {
     pub const fn ticks(&self) -> i64 {
         self.ticks
     }
+
+    /// Creates a [`Duration`] from microseconds, rounding away from zero to
+    /// a tick so that timeouts never expire early.
+    pub const fn from_micros(micros: i64) -> Self {
+        Duration::<Microseconds>::new(micros).convert_into_rounding_up()
+    }
+
+    /// Creates a [`Duration`] from milliseconds, rounding away from zero to
+    /// a tick, see [`Duration::from_micros()`].
+    pub const fn from_millis(millis: i64) -> Self {
+        Duration::<Milliseconds>::new(millis).convert_into_rounding_up()
+    }
+
+    /// Creates a [`Duration`] from nanoseconds, rounding away from zero to
+    /// a tick, see [`Duration::from_micros()`].
+    pub const fn from_nanos(nanos: i64) -> Self {
+        Duration::<Nanoseconds>::new(nanos).convert_into_rounding_up()
+    }
+
+    /// Returns the duration in whole microseconds, rounded toward zero.
+    pub const fn as_micros(&self) -> i64 {
+        self.convert_into_rounding_down::<Microseconds>().ticks
+    }
+
+    /// Returns the duration in whole milliseconds, rounded toward zero.
+    pub const fn as_millis(&self) -> i64 {
+        self.convert_into_rounding_down::<Milliseconds>().ticks
+    }
+
+    /// Returns the duration in whole nanoseconds, rounded toward zero.
+    pub const fn as_nanos(&self) -> i64 {
+        self.convert_into_rounding_down::<Nanoseconds>().ticks
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.ticks < 0
+    }
+
+    /// Returns the absolute duration, saturating at [`Duration::MAX`].
+    pub const fn abs(&self) -> Self {
+        Self::new(self.ticks.saturating_abs())
+    }
+
+    pub const fn checked_add(&self, rhs: Self) -> Option<Self> {
+        match self.ticks.checked_add(rhs.ticks) {
+            Some(ticks) => Some(Self::new(ticks)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(&self, rhs: Self) -> Option<Self> {
+        match self.ticks.checked_sub(rhs.ticks) {
+            Some(ticks) => Some(Self::new(ticks)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_mul(&self, rhs: i64) -> Option<Self> {
+        match self.ticks.checked_mul(rhs) {
+            Some(ticks) => Some(Self::new(ticks)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_neg(&self) -> Option<Self> {
+        match self.ticks.checked_neg() {
+            Some(ticks) => Some(Self::new(ticks)),
+            None => None,
+        }
+    }
+
+    pub const fn saturating_add(&self, rhs: Self) -> Self {
+        Self::new(self.ticks.saturating_add(rhs.ticks))
+    }
+
+    pub const fn saturating_sub(&self, rhs: Self) -> Self {
+        Self::new(self.ticks.saturating_sub(rhs.ticks))
+    }
+
+    pub const fn saturating_mul(&self, rhs: i64) -> Self {
+        Self::new(self.ticks.saturating_mul(rhs))
+    }
+}
+
+// Note: The traits below are implemented manually as deriving them would
+//       require the frequency marker to implement them, too.
+macro_rules! impl_tick_traits {
+    ($type:ident, $field:ident) => {
+        impl<F: Frequency> Clone for $type<F> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<F: Frequency> Copy for $type<F> {}
+
+        impl<F: Frequency> PartialEq for $type<F> {
+            fn eq(&self, other: &Self) -> bool {
+                self.$field == other.$field
+            }
+        }
+
+        impl<F: Frequency> Eq for $type<F> {}
+
+        impl<F: Frequency> PartialOrd for $type<F> {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<F: Frequency> Ord for $type<F> {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.$field.cmp(&other.$field)
+            }
+        }
+
+        impl<F: Frequency> core::fmt::Debug for $type<F> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($type))
+                    .field(stringify!($field), &self.$field)
+                    .finish()
+            }
+        }
+    };
 }
 
+impl_tick_traits!(Instant, tick);
+impl_tick_traits!(Duration, ticks);
+
 // Note: Instants cannot be added, multiplied, divided or negated. The
 //       difference between instances is defined and yields a duration.
 impl<F: Frequency> core::ops::Sub for Instant<F> {
@@ -316,6 +573,30 @@ impl<F: Frequency> core::fmt::Display for Duration<F> {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl<F: Frequency> defmt::Format for Instant<F> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{=u64}us (tick {=u64})",
+            self.convert_into_rounding_down::<Microseconds>().tick(),
+            self.tick
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<F: Frequency> defmt::Format for Duration<F> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{=i64}us ({=i64} ticks)",
+            self.convert_into_rounding_down::<Microseconds>().ticks(),
+            self.ticks
+        )
+    }
+}
+
 pub type Timer<RadioDriverImpl> = <RadioDriverImpl as DriverConfig>::Timer;
 
 pub fn now<Timer: RadioTimerApi>() -> Instant<Timer> {
@@ -345,6 +626,12 @@ mod tests {
         let b: Instant<Nanoseconds> = a.convert_into_rounding_down();
         assert_eq!(b.tick(), 100_000);
         assert_eq!(b.convert_into_rounding_down::<Microseconds>().tick(), 100);
+
+        // A day in nanoseconds exceeds 64 bits when multiplied by the frequency.
+        let day = Instant::<Microseconds>::new(86_400_000_000);
+        let day_ns: Instant<Nanoseconds> = day.convert_into_rounding_up();
+        assert_eq!(day_ns.tick(), 86_400_000_000_000);
+        assert_eq!(day_ns.convert_into_rounding_down::<Microseconds>(), day);
     }
 
     #[test]
@@ -401,6 +688,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn micros() {
+        type Rtc = Hz<32_768>;
+
+        // Constructors round up to a tick, accessors round down.
+        assert_eq!(Duration::<Rtc>::from_micros(100).ticks(), 4);
+        assert_eq!(Duration::<Rtc>::from_micros(-100).ticks(), -4);
+        assert_eq!(Duration::<Rtc>::from_millis(1_000).ticks(), 32_768);
+        assert_eq!(Duration::<Rtc>::new(4).as_micros(), 122);
+        assert_eq!(Duration::<Rtc>::new(-4).as_micros(), -122);
+        assert_eq!(Duration::<Nanoseconds>::from_micros(3).ticks(), 3_000);
+        assert_eq!(Duration::<Nanoseconds>::new(2_999).as_micros(), 2);
+        assert_eq!(Duration::<Microseconds>::from_nanos(1).ticks(), 1);
+        assert_eq!(Duration::<Microseconds>::new(7).as_nanos(), 7_000);
+        assert_eq!(Duration::<Microseconds>::new(7_999).as_millis(), 7);
+
+        assert_eq!(Instant::<Rtc>::from_micros(1_000_000).tick(), 32_768);
+        assert_eq!(Instant::<Rtc>::from_nanos(1).tick(), 1);
+        assert_eq!(Instant::<Rtc>::new(1).as_micros(), 30);
+        assert_eq!(Instant::<Rtc>::new(1).as_nanos(), 30_517);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let a = Instant::<Nanoseconds>::new(100);
+        assert_eq!(a.checked_add(Duration::new(-100)), Some(Instant::ZERO));
+        assert_eq!(a.checked_add(Duration::new(-101)), None);
+        assert_eq!(a.checked_sub(Duration::new(100)), Some(Instant::ZERO));
+        assert_eq!(
+            a.checked_sub(Duration::MIN),
+            Some(Instant::new(100 + (1 << 63)))
+        );
+        assert_eq!(
+            Instant::<Nanoseconds>::NEVER.checked_add(Duration::new(1)),
+            None
+        );
+        assert_eq!(
+            a.checked_duration_since(Instant::new(150)),
+            Some(Duration::new(-50))
+        );
+        assert_eq!(
+            Instant::<Nanoseconds>::NEVER.checked_duration_since(a),
+            None
+        );
+
+        let b = Duration::<Nanoseconds>::new(100);
+        assert_eq!(b.checked_add(Duration::MAX), None);
+        assert_eq!(b.checked_sub(Duration::MIN), None);
+        assert_eq!(b.checked_mul(-3), Some(Duration::new(-300)));
+        assert_eq!(b.checked_mul(i64::MAX), None);
+        assert_eq!(Duration::<Nanoseconds>::MIN.checked_neg(), None);
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        let a = Instant::<Nanoseconds>::new(100);
+        assert_eq!(a.saturating_add(Duration::new(-101)), Instant::ZERO);
+        assert_eq!(a.saturating_sub(Duration::new(101)), Instant::ZERO);
+        assert_eq!(
+            Instant::NEVER.saturating_add(Duration::new(1)),
+            Instant::<Nanoseconds>::NEVER
+        );
+        assert_eq!(a.saturating_sub(Duration::MIN).tick(), 100 + (1 << 63));
+        assert_eq!(
+            Instant::NEVER.saturating_duration_since(a),
+            Duration::<Nanoseconds>::MAX
+        );
+        assert_eq!(a.saturating_duration_since(Instant::NEVER), Duration::MIN);
+
+        let b = Duration::<Nanoseconds>::new(100);
+        assert_eq!(b.saturating_add(Duration::MAX), Duration::MAX);
+        assert_eq!(b.saturating_sub(Duration::MIN), Duration::MAX);
+        assert_eq!((-b).saturating_mul(i64::MAX), Duration::MIN);
+        assert_eq!(Duration::<Nanoseconds>::MIN.abs(), Duration::MAX);
+        assert!((-b).is_negative());
+    }
+
     #[test]
     #[cfg(feature = "std")]
     fn formatting() {
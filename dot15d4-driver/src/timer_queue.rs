@@ -0,0 +1,452 @@
+//! Multiplexes many concurrent timeouts onto a single [`Alarm`].
+//!
+//! The MAC has many timeouts pending at the same time, e.g. ACK waits,
+//! interframe spacings, keep-alives, the expiry of indirect transmissions and
+//! scan durations, but radio timers offer few compare channels. A
+//! [`TimerQueue`] keeps all pending [`Timeout`]s ordered by deadline and only
+//! programs the earliest one into the alarm.
+//!
+//! The queue is intrusive: Each node lives inside of the pinned [`Timeout`]
+//! future that waits for it, so the queue needs no allocation and has no
+//! capacity limit. Nodes are organized as a pairing heap: Inserting a timeout
+//! takes constant time, removing the earliest or a cancelled timeout takes
+//! amortized logarithmic time.
+//!
+//! Like the other synchronization primitives of this project, the queue is not
+//! synchronized across threads. All timeouts and [`TimerQueue::run()`] must be
+//! polled by a single executor (thread).
+
+use core::{
+    cell::Cell,
+    future::{poll_fn, Future},
+    marker::{PhantomData, PhantomPinned},
+    pin::Pin,
+    ptr::NonNull,
+    task::{Context, Poll, Waker},
+};
+
+use dot15d4_util::sync::select;
+
+use crate::{
+    clock::Alarm,
+    time::{Duration, Instant, Nanoseconds},
+};
+
+type Link = Cell<Option<NonNull<Node>>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeState {
+    /// The timeout has not been polled yet.
+    Idle,
+    /// The node is part of the heap.
+    Queued,
+    /// The deadline expired and the node was removed from the heap.
+    Expired,
+}
+
+/// A pairing heap node, see the module documentation.
+struct Node {
+    deadline: Instant<Nanoseconds>,
+    state: Cell<NodeState>,
+    waker: Cell<Option<Waker>>,
+    /// The first child.
+    child: Link,
+    /// The next sibling.
+    next: Link,
+    /// The previous sibling or the parent of the first child.
+    prev: Link,
+    _pinned: PhantomPinned,
+}
+
+/// Queue of pending timeouts, see the module documentation.
+pub struct TimerQueue<A: Alarm> {
+    root: Link,
+    /// Set whenever the earliest deadline moved forward.
+    rescheduled: Cell<bool>,
+    /// The waker of [`TimerQueue::run()`].
+    runner: Cell<Option<Waker>>,
+    _alarm: PhantomData<A>,
+}
+
+impl<A: Alarm> Default for TimerQueue<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Alarm> TimerQueue<A> {
+    pub const fn new() -> Self {
+        Self {
+            root: Cell::new(None),
+            rescheduled: Cell::new(false),
+            runner: Cell::new(None),
+            _alarm: PhantomData,
+        }
+    }
+
+    /// Returns a future that resolves once the given deadline expired.
+    ///
+    /// The timeout joins the queue when it is first polled and leaves it when
+    /// it expires or is dropped.
+    pub fn timeout_at(&self, deadline: Instant<Nanoseconds>) -> Timeout<'_, A> {
+        Timeout {
+            queue: self,
+            node: Node {
+                deadline,
+                state: Cell::new(NodeState::Idle),
+                waker: Cell::new(None),
+                child: Cell::new(None),
+                next: Cell::new(None),
+                prev: Cell::new(None),
+                _pinned: PhantomPinned,
+            },
+        }
+    }
+
+    /// Returns a future that resolves once the given duration elapsed from now
+    /// on.
+    pub fn timeout_after(&self, duration: Duration<Nanoseconds>) -> Timeout<'_, A> {
+        self.timeout_at(A::now().saturating_add(duration))
+    }
+
+    /// Returns the earliest pending deadline, if any.
+    pub fn next_deadline(&self) -> Option<Instant<Nanoseconds>> {
+        // Safety: Queued nodes are pinned and remove themselves when dropped.
+        self.root
+            .get()
+            .map(|root| unsafe { root.as_ref() }.deadline)
+    }
+
+    /// Returns whether no timeout is pending.
+    pub fn is_empty(&self) -> bool {
+        self.root.get().is_none()
+    }
+
+    /// Drives the alarm: Programs the earliest pending deadline and wakes all
+    /// timeouts that expired.
+    ///
+    /// Must be polled concurrently with all timeouts of the queue. The queue
+    /// requires exclusive access to the alarm.
+    pub async fn run(&self) -> ! {
+        loop {
+            self.rescheduled.set(false);
+            self.expire(A::now());
+            match self.next_deadline() {
+                Some(deadline) => {
+                    A::schedule(deadline);
+                    select(A::wait(), self.wait_for_reschedule()).await;
+                }
+                None => {
+                    A::cancel();
+                    self.wait_for_reschedule().await;
+                }
+            }
+        }
+    }
+
+    fn wait_for_reschedule(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            if self.rescheduled.replace(false) {
+                Poll::Ready(())
+            } else {
+                self.runner.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Removes all nodes whose deadline expired and wakes their tasks.
+    fn expire(&self, now: Instant<Nanoseconds>) {
+        while let Some(root) = self.root.get() {
+            // Safety: Queued nodes are pinned and remove themselves when
+            // dropped.
+            let node = unsafe { root.as_ref() };
+            if node.deadline > now {
+                break;
+            }
+            self.remove(node);
+            node.state.set(NodeState::Expired);
+            if let Some(waker) = node.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn insert(&self, node: &Node) {
+        let node = NonNull::from(node);
+        let root = match self.root.get() {
+            Some(root) => Self::meld(root, node),
+            None => node,
+        };
+        if root == node {
+            self.rescheduled.set(true);
+            if let Some(waker) = self.runner.take() {
+                waker.wake();
+            }
+        }
+        self.root.set(Some(root));
+    }
+
+    fn remove(&self, node: &Node) {
+        let children = Self::merge_pairs(node.child.take());
+        if self.root.get() == Some(NonNull::from(node)) {
+            self.root.set(children);
+        } else {
+            // Safety: Non-root nodes have a previous sibling or a parent.
+            let prev = unsafe { node.prev.get().unwrap_unchecked().as_ref() };
+            let next = node.next.take();
+            if prev.child.get() == Some(NonNull::from(node)) {
+                prev.child.set(next);
+            } else {
+                prev.next.set(next);
+            }
+            if let Some(next) = next {
+                unsafe { next.as_ref() }.prev.set(node.prev.get());
+            }
+            if let Some(children) = children {
+                // Safety: Removing a non-root node leaves the root in place.
+                let root = unsafe { self.root.get().unwrap_unchecked() };
+                self.root.set(Some(Self::meld(root, children)));
+            }
+        }
+        node.prev.set(None);
+        node.next.set(None);
+    }
+
+    /// Melds two heaps, returns the new root. Clears the siblings of the root.
+    fn meld(a: NonNull<Node>, b: NonNull<Node>) -> NonNull<Node> {
+        // Safety: Both nodes are pinned.
+        let (parent, child) = unsafe {
+            if b.as_ref().deadline < a.as_ref().deadline {
+                (b, a)
+            } else {
+                (a, b)
+            }
+        };
+        let (parent_ref, child_ref) = unsafe { (parent.as_ref(), child.as_ref()) };
+        let first = parent_ref.child.get();
+        if let Some(first) = first {
+            unsafe { first.as_ref() }.prev.set(Some(child));
+        }
+        child_ref.next.set(first);
+        child_ref.prev.set(Some(parent));
+        parent_ref.child.set(Some(child));
+        parent_ref.next.set(None);
+        parent_ref.prev.set(None);
+        parent
+    }
+
+    /// Melds a list of siblings in two passes, returns the new root.
+    fn merge_pairs(first: Option<NonNull<Node>>) -> Option<NonNull<Node>> {
+        // First pass: Meld pairs from left to right, stack the results.
+        let mut pairs = None;
+        let mut current = first;
+        while let Some(a) = current {
+            // Safety: All siblings are pinned.
+            let pair = match unsafe { a.as_ref() }.next.get() {
+                Some(b) => {
+                    current = unsafe { b.as_ref() }.next.get();
+                    Self::meld(a, b)
+                }
+                None => {
+                    current = None;
+                    a
+                }
+            };
+            unsafe { pair.as_ref() }.next.set(pairs);
+            pairs = Some(pair);
+        }
+
+        // Second pass: Meld the stacked pairs from right to left.
+        let mut root = None;
+        while let Some(pair) = pairs {
+            pairs = unsafe { pair.as_ref() }.next.take();
+            root = Some(match root {
+                Some(root) => Self::meld(root, pair),
+                None => pair,
+            });
+        }
+        if let Some(root) = root {
+            unsafe { root.as_ref() }.prev.set(None);
+        }
+        root
+    }
+}
+
+/// A future that resolves once its deadline expired, see
+/// [`TimerQueue::timeout_at()`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Timeout<'queue, A: Alarm> {
+    queue: &'queue TimerQueue<A>,
+    node: Node,
+}
+
+impl<A: Alarm> Timeout<'_, A> {
+    /// Returns the deadline of the timeout.
+    pub fn deadline(&self) -> Instant<Nanoseconds> {
+        self.node.deadline
+    }
+}
+
+impl<A: Alarm> Future for Timeout<'_, A> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: The node is never moved out of the pinned timeout.
+        let this = self.into_ref().get_ref();
+        let node = &this.node;
+        match node.state.get() {
+            NodeState::Expired => Poll::Ready(()),
+            NodeState::Idle if node.deadline <= A::now() => {
+                node.state.set(NodeState::Expired);
+                Poll::Ready(())
+            }
+            NodeState::Idle => {
+                node.waker.set(Some(cx.waker().clone()));
+                node.state.set(NodeState::Queued);
+                this.queue.insert(node);
+                Poll::Pending
+            }
+            NodeState::Queued => {
+                node.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<A: Alarm> Drop for Timeout<'_, A> {
+    fn drop(&mut self) {
+        if self.node.state.get() == NodeState::Queued {
+            self.queue.remove(&self.node);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use core::pin::pin;
+
+    use super::*;
+    use crate::{clock::MonotonicClock, test_clock::TestClock};
+
+    fn poll<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    fn advance_micros(micros: i64) {
+        TestClock::advance(Duration::new(micros));
+    }
+
+    #[test]
+    fn expires_in_order() {
+        TestClock::reset();
+        let queue = TimerQueue::<TestClock>::new();
+        let mut runner = pin!(queue.run());
+        assert!(poll(runner.as_mut()).is_pending());
+        assert_eq!(TestClock::alarm(), None);
+
+        let deadlines = [5, 3, 8, 3, 1, 13, 2, 21];
+        let mut timeouts: Vec<_> = deadlines
+            .iter()
+            .map(|&micros| Box::pin(queue.timeout_at(Instant::from_micros(micros))))
+            .collect();
+        for timeout in &mut timeouts {
+            assert!(poll(timeout.as_mut()).is_pending());
+        }
+        assert_eq!(queue.next_deadline(), Some(Instant::from_micros(1)));
+
+        let mut expired = Vec::new();
+        for _ in 0..21 {
+            assert!(poll(runner.as_mut()).is_pending());
+            assert_eq!(
+                TestClock::alarm(),
+                queue
+                    .next_deadline()
+                    .map(|at| at.convert_into_rounding_up())
+            );
+            advance_micros(1);
+            assert!(poll(runner.as_mut()).is_pending());
+            let now = <TestClock as MonotonicClock>::now();
+            for (idx, timeout) in timeouts.iter_mut().enumerate() {
+                if !expired.contains(&idx) && poll(timeout.as_mut()).is_ready() {
+                    assert_eq!(timeout.deadline(), now);
+                    expired.push(idx);
+                }
+            }
+        }
+        assert_eq!(expired.len(), deadlines.len());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn reschedules_earlier_deadlines() {
+        TestClock::reset();
+        let queue = TimerQueue::<TestClock>::new();
+        let mut runner = pin!(queue.run());
+
+        let mut late = pin!(queue.timeout_at(Instant::from_micros(10)));
+        assert!(poll(late.as_mut()).is_pending());
+        assert!(poll(runner.as_mut()).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(10)));
+
+        let mut early = pin!(queue.timeout_after(Duration::from_micros(4)));
+        assert!(poll(early.as_mut()).is_pending());
+        assert!(poll(runner.as_mut()).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(4)));
+
+        advance_micros(4);
+        assert!(poll(runner.as_mut()).is_pending());
+        assert!(poll(early.as_mut()).is_ready());
+        assert!(poll(late.as_mut()).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(10)));
+    }
+
+    #[test]
+    fn cancels_dropped_timeouts() {
+        TestClock::reset();
+        let queue = TimerQueue::<TestClock>::new();
+        let mut runner = pin!(queue.run());
+
+        let mut timeouts: Vec<_> = (1..=64)
+            .map(|micros| Box::pin(queue.timeout_at(Instant::from_micros(micros * 7 % 65))))
+            .collect();
+        for timeout in &mut timeouts {
+            assert!(poll(timeout.as_mut()).is_pending());
+        }
+        // Expire a few to build up a multi-level heap, then drop every other
+        // timeout.
+        advance_micros(3);
+        assert!(poll(runner.as_mut()).is_pending());
+        let mut remaining: Vec<_> = timeouts
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, timeout)| (idx % 2 == 0).then_some(timeout))
+            .filter(|timeout| timeout.deadline() > Instant::from_micros(3))
+            .collect();
+        remaining.sort_by_key(|timeout| timeout.deadline());
+
+        for mut timeout in remaining {
+            let deadline = timeout.deadline();
+            assert_eq!(queue.next_deadline(), Some(deadline));
+            TestClock::advance(
+                (deadline - <TestClock as MonotonicClock>::now()).convert_into_rounding_up(),
+            );
+            assert!(poll(runner.as_mut()).is_pending());
+            assert!(poll(timeout.as_mut()).is_ready());
+        }
+        assert!(queue.is_empty());
+        assert!(poll(runner.as_mut()).is_pending());
+        assert_eq!(TestClock::alarm(), None);
+    }
+
+    #[test]
+    fn expired_deadline() {
+        TestClock::reset();
+        advance_micros(5);
+        let queue = TimerQueue::<TestClock>::new();
+        let mut timeout = pin!(queue.timeout_at(Instant::from_micros(5)));
+        assert!(poll(timeout.as_mut()).is_ready());
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,262 @@
+//! Cancellation of scheduled TX tasks.
+//!
+//! Clients MAY attach a [`TxDescriptor`] to a [`TaskTx`] to be able to cancel
+//! the transmission after the task has been handed over to the driver, e.g.
+//! when an ACK to a duplicate frame is no longer needed or when a TSCH slot
+//! needs to be aborted due to loss of synchronization.
+//!
+//! Descriptors are allocated from a small static pool. Each allocation
+//! produces a pair of handles: the [`TxDescriptor`] travels with the task to
+//! the driver, the [`TxCancellationHandle`] stays with the client.
+//!
+//! # Cancellation semantics
+//!
+//! Each descriptor goes through the following states:
+//!
+//! ```notrust
+//! Scheduled --commit()--> Committed --release()--> Free
+//!     |
+//!     +------cancel()---> Cancelled --release()--> Free
+//! ```
+//!
+//! - Drivers SHALL call [`TxDescriptor::commit()`] at the latest point in time
+//!   at which the transmission can still be aborted without anything being
+//!   put on air (the "point of no return"). Drivers that pre-program timed
+//!   transmissions in hardware SHALL commit when the transmission is
+//!   pre-programmed. Radio transitions commit the task right before they
+//!   program the transmission, see [`RadioTask::commit()`], unless the driver
+//!   took the descriptor from the task to commit it later.
+//! - If [`TxCancellationHandle::cancel()`] wins the race, then the driver SHALL
+//!   NOT send the frame and SHALL return the frame to the client with
+//!   [`TxError::Cancelled`].
+//! - If the driver committed first, then the transmission proceeds and the task
+//!   completes with its regular result (which MAY still be an error, e.g. CCA
+//!   busy). Cancellation reports [`TxCancellationResult::TooLate`] in this
+//!   case so that clients know that they will receive a regular result.
+//! - A task result is always reported exactly once, whether the task was
+//!   cancelled or not.
+//! - The driver SHALL release the descriptor as soon as it committed or
+//!   reported the cancellation. Cancellation handles of released descriptors
+//!   become stale and report [`TxCancellationResult::TooLate`], i.e.
+//!   cancellation has no effect.
+//! - Dropping a descriptor releases it, so that tasks dropped on an error
+//!   path cannot leak descriptors from the pool.
+//!
+//! [`TaskTx`]: crate::tasks::TaskTx
+//! [`RadioTask::commit()`]: crate::tasks::RadioTask::commit
+//! [`TxError::Cancelled`]: crate::tasks::TxError::Cancelled
+
+use core::cell::RefCell;
+
+use critical_section::{with as with_cs, Mutex};
+
+/// The max number of TX tasks that can be cancellable at the same time.
+pub const TX_DESCRIPTOR_POOL_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxDescriptorState {
+    Free,
+    Scheduled,
+    Committed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TxDescriptorSlot {
+    /// Incremented whenever the slot is released so that stale handles can be
+    /// recognized. Wide enough not to wrap during the lifetime of a handle.
+    generation: u32,
+    state: TxDescriptorState,
+}
+
+static TX_DESCRIPTOR_POOL: Mutex<RefCell<[TxDescriptorSlot; TX_DESCRIPTOR_POOL_SIZE]>> =
+    Mutex::new(RefCell::new(
+        [TxDescriptorSlot {
+            generation: 0,
+            state: TxDescriptorState::Free,
+        }; TX_DESCRIPTOR_POOL_SIZE],
+    ));
+
+/// Runs the given closure on the slot referenced by the given handle data if
+/// the slot has not been released in the meantime.
+fn with_slot<R>(
+    slot: u8,
+    generation: u32,
+    f: impl FnOnce(&mut TxDescriptorSlot) -> R,
+) -> Option<R> {
+    with_cs(|cs| {
+        let mut pool = TX_DESCRIPTOR_POOL.borrow_ref_mut(cs);
+        let slot = &mut pool[slot as usize];
+        if slot.generation != generation || slot.state == TxDescriptorState::Free {
+            return None;
+        }
+        Some(f(slot))
+    })
+}
+
+/// Allocates a TX descriptor from the static pool.
+///
+/// Returns [`None`] if all descriptors are in use.
+pub fn allocate_tx_descriptor() -> Option<(TxDescriptor, TxCancellationHandle)> {
+    with_cs(|cs| {
+        let mut pool = TX_DESCRIPTOR_POOL.borrow_ref_mut(cs);
+        let (slot_idx, slot) = pool
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.state == TxDescriptorState::Free)?;
+        slot.state = TxDescriptorState::Scheduled;
+
+        let slot_idx = slot_idx as u8;
+        let generation = slot.generation;
+        Some((
+            TxDescriptor {
+                slot: slot_idx,
+                generation,
+            },
+            TxCancellationHandle {
+                slot: slot_idx,
+                generation,
+            },
+        ))
+    })
+}
+
+/// Returned when trying to commit a TX task that has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxCancelled;
+
+/// Driver-side reference to a cancellable TX task, see the module
+/// documentation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TxDescriptor {
+    slot: u8,
+    generation: u32,
+}
+
+impl TxDescriptor {
+    /// Marks the transmission as no longer cancellable.
+    ///
+    /// Committing an already committed descriptor is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TxCancelled`] if the task was cancelled before it could be
+    /// committed. The frame SHALL NOT be sent in this case.
+    pub fn commit(&self) -> Result<(), TxCancelled> {
+        with_slot(self.slot, self.generation, |slot| match slot.state {
+            TxDescriptorState::Scheduled | TxDescriptorState::Committed => {
+                slot.state = TxDescriptorState::Committed;
+                Ok(())
+            }
+            TxDescriptorState::Cancelled => Err(TxCancelled),
+            // Safety: Free slots are filtered out by with_slot().
+            TxDescriptorState::Free => unreachable!(),
+        })
+        // Safety: The descriptor is only released when it is consumed or
+        //         dropped.
+        .unwrap()
+    }
+
+    /// Returns the descriptor to the pool.
+    ///
+    /// Dropping the descriptor has the same effect, this makes the point of
+    /// release explicit.
+    pub fn release(self) {
+        drop(self)
+    }
+}
+
+impl Drop for TxDescriptor {
+    fn drop(&mut self) {
+        let _ = with_slot(self.slot, self.generation, |slot| {
+            slot.generation = slot.generation.wrapping_add(1);
+            slot.state = TxDescriptorState::Free;
+        });
+    }
+}
+
+/// The outcome of a cancellation request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxCancellationResult {
+    /// The TX task was cancelled before it was committed. It will complete
+    /// with [`TxError::Cancelled`](crate::tasks::TxError::Cancelled).
+    Cancelled,
+    /// The driver already committed to the transmission or released the
+    /// descriptor. Cancellation had no effect.
+    TooLate,
+}
+
+/// Client-side handle to cancel a TX task, see the module documentation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TxCancellationHandle {
+    slot: u8,
+    generation: u32,
+}
+
+impl TxCancellationHandle {
+    /// Tries to cancel the TX task.
+    ///
+    /// Cancelling an already cancelled task reports
+    /// [`TxCancellationResult::Cancelled`] again.
+    pub fn cancel(&self) -> TxCancellationResult {
+        with_slot(self.slot, self.generation, |slot| match slot.state {
+            TxDescriptorState::Scheduled | TxDescriptorState::Cancelled => {
+                slot.state = TxDescriptorState::Cancelled;
+                TxCancellationResult::Cancelled
+            }
+            TxDescriptorState::Committed => TxCancellationResult::TooLate,
+            // Safety: Free slots are filtered out by with_slot().
+            TxDescriptorState::Free => unreachable!(),
+        })
+        .unwrap_or(TxCancellationResult::TooLate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Note: All scenarios run in a single test as they share the static pool.
+    #[test]
+    fn cancellation() {
+        // Cancellation before commit.
+        let (descriptor, handle) = allocate_tx_descriptor().unwrap();
+        assert_eq!(handle.cancel(), TxCancellationResult::Cancelled);
+        assert_eq!(handle.cancel(), TxCancellationResult::Cancelled);
+        assert_eq!(descriptor.commit(), Err(TxCancelled));
+        descriptor.release();
+        assert_eq!(handle.cancel(), TxCancellationResult::TooLate);
+
+        // Cancellation after commit.
+        let (descriptor, handle) = allocate_tx_descriptor().unwrap();
+        assert_eq!(descriptor.commit(), Ok(()));
+        assert_eq!(handle.cancel(), TxCancellationResult::TooLate);
+        assert_eq!(descriptor.commit(), Ok(()));
+        descriptor.release();
+        assert_eq!(handle.cancel(), TxCancellationResult::TooLate);
+
+        // Dropped descriptors are released.
+        let (descriptor, handle) = allocate_tx_descriptor().unwrap();
+        drop(descriptor);
+        assert_eq!(handle.cancel(), TxCancellationResult::TooLate);
+
+        // Pool exhaustion and stale handles of re-used slots.
+        let mut descriptors: [Option<(TxDescriptor, TxCancellationHandle)>;
+            TX_DESCRIPTOR_POOL_SIZE] = Default::default();
+        for descriptor in descriptors.iter_mut() {
+            *descriptor = allocate_tx_descriptor();
+            assert!(descriptor.is_some());
+        }
+        assert!(allocate_tx_descriptor().is_none());
+        let (descriptor, stale_handle) = descriptors[0].take().unwrap();
+        descriptor.release();
+        let (descriptor, handle) = allocate_tx_descriptor().unwrap();
+        assert_eq!(stale_handle.cancel(), TxCancellationResult::TooLate);
+        assert_eq!(handle.cancel(), TxCancellationResult::Cancelled);
+        descriptor.release();
+        drop(descriptors);
+        let descriptors: [Option<(TxDescriptor, TxCancellationHandle)>; TX_DESCRIPTOR_POOL_SIZE] =
+            core::array::from_fn(|_| allocate_tx_descriptor());
+        assert!(descriptors.iter().all(Option::is_some));
+    }
+}
@@ -0,0 +1,547 @@
+//! A virtual radio for host-side tests.
+//!
+//! Several [`VirtualRadio`]s attached to the same [`VirtualMedium`] exchange
+//! frames in memory. A frame transmitted by one radio is delivered to all
+//! other radios tuned to the same channel, subject to the propagation delay
+//! and packet loss of the medium. Frames overlapping in time on the same
+//! channel collide and are not received at all. Deliveries are buffered per
+//! radio and handed out by [`Radio::receive()`] if their RMARKER lies within
+//! the receive window, i.e. a radio receives frames as if its receiver had
+//! been on all the time.
+//!
+//! Time is provided by the [`TestClock`] of the current thread. By default,
+//! the radios move the clock forward themselves, e.g. to the end of a
+//! transmission or of an empty receive window, so that radio operations never
+//! wait on the clock. On the medium of a
+//! [`Simulator`](crate::simulator::Simulator), they wait for the clock
+//! instead so that the operations of several radios interleave on a shared
+//! timeline. A receive without window end always waits until a frame is
+//! delivered.
+//!
+//! Failures are injected deterministically: Random decisions, i.e. packet
+//! loss, CCA-busy injection and timestamp jitter, are drawn from a
+//! pseudo-random generator seeded by [`VirtualMediumConfig::seed`].
+
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    pin::pin,
+    task::{Poll, Waker},
+};
+use std::{collections::VecDeque, rc::Rc, vec::Vec};
+
+use crate::{
+    config::{CcaMode, Channel},
+    constants::{FCS_LEN, PHY_MAX_PACKET_SIZE_127},
+    phy::{air_time, PhyParameters},
+    radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+    test_clock::TestClock,
+    time::{Duration, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+/// The lowest transmit power of a virtual radio in dBm.
+const MIN_TX_POWER: i8 = -20;
+
+/// The highest transmit power of a virtual radio in dBm.
+const MAX_TX_POWER: i8 = 8;
+
+/// The energy measured on an idle channel in dBm.
+const NOISE_FLOOR: i8 = -100;
+
+/// The properties of a [`VirtualMedium`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualMediumConfig {
+    /// The timing parameters of the PHY, defining the airtime of frames.
+    pub phy: PhyParameters,
+    /// The delay between transmission and reception of a frame.
+    pub propagation_delay: Duration<Nanoseconds>,
+    /// The probability that a receiver misses a frame, from 0 to 1.
+    pub packet_loss: f32,
+    /// The probability that a CCA finds the channel busy although it is idle,
+    /// from 0 to 1.
+    pub cca_busy: f32,
+    /// The maximum deviation of a timestamp from the actual RMARKER, in both
+    /// directions.
+    pub timestamp_jitter: Duration<Nanoseconds>,
+    /// The received signal strength of all frames in dBm.
+    pub rssi: i8,
+    /// The seed of the random decisions.
+    pub seed: u64,
+}
+
+impl Default for VirtualMediumConfig {
+    fn default() -> Self {
+        Self {
+            phy: PhyParameters::default(),
+            propagation_delay: Duration::new(0),
+            packet_loss: 0.0,
+            cca_busy: 0.0,
+            timestamp_jitter: Duration::new(0),
+            rssi: -60,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+}
+
+/// A frame on its way to a receiver.
+#[derive(Debug)]
+struct Delivery {
+    mpdu: Vec<u8>,
+    /// The instant at which the RMARKER reaches the receiver.
+    rmarker: Instant<Nanoseconds>,
+    /// The instant at which the last symbol reaches the receiver.
+    end: Instant<TestClock>,
+    /// Whether the frame overlapped with another frame.
+    collided: bool,
+}
+
+/// The state of a radio attached to the medium.
+#[derive(Debug, Default)]
+struct Node {
+    channel: Channel,
+    inbox: VecDeque<Delivery>,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+struct MediumState {
+    config: VirtualMediumConfig,
+    /// Whether the radios wait for the clock, see the module documentation.
+    simulated: bool,
+    rng: XorShift,
+    nodes: Vec<Node>,
+    /// The channels occupied by transmissions and until when.
+    airtime: Vec<(Channel, Instant<TestClock>)>,
+}
+
+impl MediumState {
+    /// Return whether a transmission occupies the given channel.
+    fn is_busy(&mut self, channel: Channel) -> bool {
+        let now = TestClock::now();
+        self.airtime.retain(|(_, end)| *end > now);
+        self.airtime
+            .iter()
+            .any(|(occupied, _)| *occupied == channel)
+    }
+
+    /// Return the given RMARKER shifted by a random jitter.
+    fn jitter(&mut self, rmarker: Instant<Nanoseconds>) -> Instant<Nanoseconds> {
+        let max = self.config.timestamp_jitter.ticks();
+        if max == 0 {
+            return rmarker;
+        }
+        let jitter = (self.rng.next_u64() % (2 * max as u64 + 1)) as i64 - max;
+        rmarker + Duration::new(jitter)
+    }
+}
+
+/// The medium connecting virtual radios, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct VirtualMedium {
+    state: Rc<RefCell<MediumState>>,
+}
+
+impl VirtualMedium {
+    /// Creates a new medium without radios.
+    pub fn new(config: VirtualMediumConfig) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(MediumState {
+                config,
+                simulated: false,
+                rng: XorShift::new(config.seed),
+                nodes: Vec::new(),
+                airtime: Vec::new(),
+            })),
+        }
+    }
+
+    /// Let the radios wait for the clock instead of moving it forward, see
+    /// the module documentation.
+    pub(crate) fn set_simulated(&self) {
+        self.state.borrow_mut().simulated = true;
+    }
+
+    /// Attaches a new radio tuned to the default channel.
+    pub fn radio(&self) -> VirtualRadio {
+        let mut state = self.state.borrow_mut();
+        state.nodes.push(Node::default());
+        VirtualRadio {
+            id: state.nodes.len() - 1,
+            medium: self.state.clone(),
+            tx_power: 0,
+            injected_cca_busy: 0,
+        }
+    }
+}
+
+/// A radio attached to a [`VirtualMedium`], see the module documentation.
+#[derive(Debug)]
+pub struct VirtualRadio {
+    id: usize,
+    medium: Rc<RefCell<MediumState>>,
+    tx_power: i8,
+    injected_cca_busy: usize,
+}
+
+impl VirtualRadio {
+    /// Let the given number of subsequent CCAs find the channel busy.
+    pub fn inject_cca_busy(&mut self, count: usize) {
+        self.injected_cca_busy += count;
+    }
+
+    /// Return the current transmit power in dBm.
+    pub fn tx_power(&self) -> i8 {
+        self.tx_power
+    }
+
+    /// Return the number of frames delivered to the radio but not received
+    /// yet.
+    pub fn pending(&self) -> usize {
+        self.medium.borrow().nodes[self.id].inbox.len()
+    }
+
+    /// Lets time pass until the given instant, see the module documentation.
+    async fn wait_until(&self, at: Instant<TestClock>) {
+        let now = TestClock::now();
+        if at <= now {
+            return;
+        }
+        if self.medium.borrow().simulated {
+            TestClock::wait_for_alarm_at(at).await;
+        } else {
+            TestClock::advance(at - now);
+        }
+    }
+
+    /// Puts the given frame on the medium and delivers it to all nodes on the
+    /// same channel. Returns the jittered RMARKER timestamp of the frame.
+    fn deliver(
+        &self,
+        mpdu: &[u8],
+        start: Instant<TestClock>,
+        end: Instant<TestClock>,
+        rmarker: Instant<Nanoseconds>,
+    ) -> Instant<Nanoseconds> {
+        let mut state = self.medium.borrow_mut();
+        let channel = state.nodes[self.id].channel;
+        let collided = state.is_busy(channel);
+        state.airtime.push((channel, end));
+        let delay = state.config.propagation_delay;
+        for id in 0..state.nodes.len() {
+            if id == self.id || state.nodes[id].channel != channel {
+                continue;
+            }
+            let lost = state.rng.probability() < state.config.packet_loss;
+            let node = &mut state.nodes[id];
+            if collided {
+                for delivery in node
+                    .inbox
+                    .iter_mut()
+                    .filter(|delivery| delivery.end > start)
+                {
+                    delivery.collided = true;
+                }
+            }
+            if lost {
+                continue;
+            }
+            node.inbox.push_back(Delivery {
+                mpdu: mpdu.to_vec(),
+                rmarker: rmarker + delay,
+                end: end + delay.convert_into_rounding_up(),
+                collided,
+            });
+            if let Some(waker) = node.waker.take() {
+                waker.wake();
+            }
+        }
+        state.jitter(rmarker)
+    }
+
+    /// Waits for the next delivery whose RMARKER lies within the given window
+    /// and returns the instant at which it ends.
+    ///
+    /// Returns `None` if the window ends without delivery.
+    async fn next_delivery(
+        &self,
+        start: Option<Instant<TestClock>>,
+        end: Option<Instant<TestClock>>,
+    ) -> Option<Instant<TestClock>> {
+        let start = start.map(|start| start.convert_into_rounding_down::<Nanoseconds>());
+        let end_ns = end.map(|end| end.convert_into_rounding_down::<Nanoseconds>());
+        let simulated = self.medium.borrow().simulated;
+        if let (true, Some(end)) = (simulated, end) {
+            TestClock::schedule_alarm(end);
+        }
+        let mut alarm = pin!(TestClock::wait_for_alarm());
+
+        poll_fn(|cx| {
+            let mut state = self.medium.borrow_mut();
+            let node = &mut state.nodes[self.id];
+            while let Some(delivery) = node.inbox.front() {
+                if start.is_some_and(|start| delivery.rmarker < start) {
+                    node.inbox.pop_front();
+                    continue;
+                }
+                if end_ns.is_some_and(|end| delivery.rmarker > end) {
+                    break;
+                }
+                return Poll::Ready(Some(delivery.end));
+            }
+            node.waker = Some(cx.waker().clone());
+            match end {
+                None => Poll::Pending,
+                Some(_) if !simulated || alarm.as_mut().poll(cx).is_ready() => Poll::Ready(None),
+                Some(_) => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+impl Radio for VirtualRadio {
+    type Timer = TestClock;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        RadioCapabilities {
+            hardware_ack: false,
+            frame_pending: false,
+            timestamp_resolution: Duration::new(1_000),
+            min_tx_power: MIN_TX_POWER,
+            max_tx_power: MAX_TX_POWER,
+        }
+    }
+
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        self.medium.borrow_mut().nodes[self.id].channel = channel;
+        Ok(())
+    }
+
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        self.tx_power = dbm.clamp(MIN_TX_POWER, MAX_TX_POWER);
+        Ok(self.tx_power)
+    }
+
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<TestClock>>,
+    ) -> Result<TxInfo<TestClock>, RadioError> {
+        if mpdu.len() + FCS_LEN > PHY_MAX_PACKET_SIZE_127 {
+            return Err(RadioError::FrameTooLong);
+        }
+
+        let phy = self.medium.borrow().config.phy;
+        let shr: Duration<TestClock> = phy.shr_duration.convert_into_rounding_up();
+        let now = TestClock::now();
+        let start = match at {
+            Some(at) if at < now + shr => return Err(RadioError::TooLate),
+            Some(at) => at - shr,
+            None => now,
+        };
+        let end = start + air_time(mpdu.len() + FCS_LEN, &phy).convert_into_rounding_up();
+        let rmarker: Instant<Nanoseconds> = (start + shr).convert_into_rounding_down();
+        self.wait_until(start).await;
+
+        let timestamp = self.deliver(mpdu, start, end, rmarker);
+
+        self.wait_until(end).await;
+        Ok(TxInfo { timestamp, end })
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<TestClock>,
+    ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+        if let Some(start) = window.start {
+            self.wait_until(start).await;
+        }
+
+        loop {
+            let Some(end) = self.next_delivery(window.start, window.end).await else {
+                if let Some(end) = window.end {
+                    self.wait_until(end).await;
+                }
+                return Ok(None);
+            };
+            // Frames starting until the end of this one may still collide.
+            self.wait_until(end).await;
+
+            let mut state = self.medium.borrow_mut();
+            // Safety: Deliveries are only removed by the receiving radio.
+            let delivery = state.nodes[self.id].inbox.pop_front().unwrap();
+            if delivery.collided {
+                continue;
+            }
+            buffer
+                .get_mut(..delivery.mpdu.len())
+                .ok_or(RadioError::FrameTooLong)?
+                .copy_from_slice(&delivery.mpdu);
+            return Ok(Some(RxInfo {
+                len: delivery.mpdu.len(),
+                rssi: state.config.rssi,
+                lqi: 0xff,
+                timestamp: state.jitter(delivery.rmarker),
+                end: delivery.end,
+            }));
+        }
+    }
+
+    async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+        let (busy, cca_duration) = {
+            let mut state = self.medium.borrow_mut();
+            let channel = state.nodes[self.id].channel;
+            let busy = state.is_busy(channel) || state.rng.probability() < state.config.cca_busy;
+            (
+                busy,
+                state.config.phy.cca_duration.convert_into_rounding_up(),
+            )
+        };
+        let injected = self.injected_cca_busy > 0;
+        self.injected_cca_busy = self.injected_cca_busy.saturating_sub(1);
+
+        self.wait_until(TestClock::now() + cca_duration).await;
+        Ok(!busy && !injected)
+    }
+
+    async fn energy_detect(&mut self, duration: Duration<TestClock>) -> Result<i8, RadioError> {
+        let channel = self.medium.borrow().nodes[self.id].channel;
+        let busy_at_start = self.medium.borrow_mut().is_busy(channel);
+        self.wait_until(TestClock::now() + duration).await;
+
+        let mut state = self.medium.borrow_mut();
+        Ok(if busy_at_start || state.is_busy(channel) {
+            state.config.rssi
+        } else {
+            NOISE_FLOOR
+        })
+    }
+}
+
+/// A xorshift64* pseudo-random generator, good enough to inject failures.
+#[derive(Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero.
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Return a number uniformly distributed in [0, 1).
+    fn probability(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::test_clock::poll_ready;
+
+    #[test]
+    fn transmit_and_receive() {
+        TestClock::reset();
+        let medium = VirtualMedium::new(VirtualMediumConfig {
+            propagation_delay: Duration::new(300),
+            ..Default::default()
+        });
+        let mut a = medium.radio();
+        let mut b = medium.radio();
+        let mut c = medium.radio();
+        c.set_channel(Channel::_11).unwrap();
+
+        // 160µs SHR, 5 octets of 32µs each.
+        let tx = poll_ready(a.transmit_at(&[0x02, 0x10], Some(Instant::new(1_160)))).unwrap();
+        assert_eq!(tx.timestamp, Instant::new(1_160_000));
+        assert_eq!(tx.end, Instant::new(1_160 + 160));
+        assert_eq!(TestClock::now(), tx.end);
+        assert_eq!(
+            poll_ready(a.transmit_at(&[0x02, 0x10], Some(Instant::new(1_200)))),
+            Err(RadioError::TooLate)
+        );
+        assert_eq!(
+            poll_ready(a.transmit_at(&[0; 126], None)),
+            Err(RadioError::FrameTooLong)
+        );
+
+        let mut buffer = [0; 127];
+        let rx = poll_ready(b.receive(&mut buffer, RxWindow::until(Instant::new(5_000))))
+            .unwrap()
+            .unwrap();
+        assert_eq!(buffer[..rx.len], [0x02, 0x10]);
+        assert_eq!(rx.timestamp, Instant::new(1_160_300));
+        assert_eq!(rx.end, Instant::new(1_321));
+        assert_eq!(c.pending(), 0);
+
+        assert_eq!(
+            poll_ready(b.receive(&mut buffer, RxWindow::until(Instant::new(5_000)))),
+            Ok(None)
+        );
+        assert_eq!(TestClock::now(), Instant::new(5_000));
+
+        // Frames before the receive window are missed.
+        poll_ready(a.transmit_at(&[0x02, 0x10], None)).unwrap();
+        let window = RxWindow::between(Instant::new(6_000), Instant::new(7_000));
+        assert_eq!(poll_ready(b.receive(&mut buffer, window)), Ok(None));
+    }
+
+    #[test]
+    fn unbounded_receive() {
+        TestClock::reset();
+        let medium = VirtualMedium::new(VirtualMediumConfig::default());
+        let mut a = medium.radio();
+        let mut b = medium.radio();
+
+        let mut buffer = [0; 127];
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut receive = pin!(b.receive(&mut buffer, RxWindow::unbounded()));
+        assert!(receive.as_mut().poll(&mut cx).is_pending());
+        poll_ready(a.transmit_at(&[0x02, 0x10, 0x07], None)).unwrap();
+        assert!(matches!(
+            receive.as_mut().poll(&mut cx),
+            Poll::Ready(Ok(Some(RxInfo { len: 3, .. })))
+        ));
+    }
+
+    #[test]
+    fn failure_injection() {
+        TestClock::reset();
+        let medium = VirtualMedium::new(VirtualMediumConfig {
+            packet_loss: 1.0,
+            timestamp_jitter: Duration::new(500),
+            ..Default::default()
+        });
+        let mut a = medium.radio();
+        let b = medium.radio();
+
+        let tx = poll_ready(a.transmit_at(&[0x02, 0x10], Some(Instant::new(1_000)))).unwrap();
+        let deviation = (tx.timestamp - Instant::new(1_000_000)).ticks();
+        assert!((-500..=500).contains(&deviation));
+        assert_eq!(b.pending(), 0);
+
+        assert_eq!(poll_ready(a.cca(CcaMode::CarrierSense)), Ok(true));
+        a.inject_cca_busy(2);
+        assert_eq!(poll_ready(a.cca(CcaMode::CarrierSense)), Ok(false));
+        assert_eq!(poll_ready(a.cca(CcaMode::CarrierSense)), Ok(false));
+        assert_eq!(poll_ready(a.cca(CcaMode::CarrierSense)), Ok(true));
+
+        assert_eq!(a.set_tx_power(20), Ok(MAX_TX_POWER));
+        assert_eq!(a.tx_power(), MAX_TX_POWER);
+    }
+}
@@ -0,0 +1,107 @@
+//! Decoding of frames given as text.
+
+/// Decodes the given hex or base64 encoded frame.
+///
+/// Hex digits may be separated by whitespace or colons and prefixed with
+/// `0x`. Text that is valid hex is decoded as hex unless `base64` is set.
+///
+/// Returns `None` if the text is neither valid hex nor base64.
+pub fn decode(text: &str, base64: bool) -> Option<Vec<u8>> {
+    if !base64 {
+        if let Some(frame) = decode_hex(text) {
+            return Some(frame);
+        }
+    }
+    decode_base64(text)
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b':')
+        .collect();
+    if digits.is_empty()
+        || !digits.len().is_multiple_of(2)
+        || !digits.iter().all(u8::is_ascii_hexdigit)
+    {
+        return None;
+    }
+    let digit = |c: u8| (c as char).to_digit(16).unwrap() as u8;
+    Some(
+        digits
+            .chunks_exact(2)
+            .map(|pair| (digit(pair[0]) << 4) | digit(pair[1]))
+            .collect(),
+    )
+}
+
+/// Decodes the standard or URL-safe base64 alphabet with optional padding.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut frame = Vec::new();
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    let digits = text.trim().trim_end_matches('=');
+    for c in digits.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            frame.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    // A single digit left over doesn't encode a whole octet.
+    (!frame.is_empty() && bit_count < 6).then_some(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex() {
+        let frame = Some([0x41, 0x88, 0x2a].to_vec());
+        assert_eq!(decode("41882a", false), frame);
+        assert_eq!(decode("0x41882A\n", false), frame);
+        assert_eq!(decode("41 88 2a", false), frame);
+        assert_eq!(decode("41:88:2a", false), frame);
+    }
+
+    #[test]
+    fn base64() {
+        let frame = Some([0x41, 0x88, 0x2a].to_vec());
+        assert_eq!(decode("QYgq", false), frame);
+        assert_eq!(
+            decode("QYgqAQ==", false),
+            Some([0x41, 0x88, 0x2a, 0x01].to_vec())
+        );
+        assert_eq!(
+            decode("QYgqAQ", false),
+            Some([0x41, 0x88, 0x2a, 0x01].to_vec())
+        );
+
+        // Valid hex is decoded as base64 if requested.
+        assert_eq!(decode("deadbeef", false).unwrap().len(), 4);
+        assert_eq!(decode("deadbeef", true).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode("", false), None);
+        assert_eq!(decode("QYgqA", false), None);
+        assert_eq!(decode("41 88 2a!", false), None);
+    }
+}
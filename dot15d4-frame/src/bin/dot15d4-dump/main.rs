@@ -0,0 +1,318 @@
+//! Decodes IEEE 802.15.4 frames and prints all their fields.
+//!
+//! ```sh
+//! dot15d4-dump [OPTIONS] [FRAME | FILE]...
+//! ```
+//!
+//! Frames are given as hex or base64 encoded MPDUs, one per argument or, if no
+//! frame is given, one per line on stdin. Arguments naming a file are read as
+//! pcap captures, e.g. saved by Wireshark or written by the sniffer of the
+//! `dot15d4-driver` crate.
+//!
+//! The options are:
+//!
+//! - `--fcs`: Encoded frames end with a 2-octet FCS. It is checked and
+//!   stripped before decoding.
+//! - `--base64`: Decode frames as base64 even if they are valid hex.
+//! - `--type <type>`: Only print frames of the given type, one of `beacon`,
+//!   `data`, `ack`, `command`, `multipurpose`, `fragment` or `extended`.
+//! - `--address <address>`: Only print frames from or to the given address,
+//!   written as printed by the tool, e.g. `34:12` or `01:02:03:04:05:06:07:08`.
+//! - `--summary`: Print a single line per frame instead of all fields.
+//!
+//! Filters may be given several times, a frame is printed if it matches any
+//! of the types and any of the addresses. Malformed frames only match if no
+//! filter is given.
+
+mod input;
+mod pcap;
+
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use dot15d4_driver::frame::FrameType;
+use dot15d4_frame::{
+    fcs::check_fcs,
+    fields::{dissect, FrameSummary},
+};
+
+use self::pcap::PcapReader;
+
+/// The length of the FCS of encoded frames given with `--fcs`.
+const FCS_LEN: usize = 2;
+
+/// The command line options, see the module documentation.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    fcs: bool,
+    base64: bool,
+    summary: bool,
+    frame_types: Vec<FrameType>,
+    addresses: Vec<String>,
+    inputs: Vec<String>,
+}
+
+impl Options {
+    /// Parses the given command line arguments, without the program name.
+    fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} requires a value"));
+            match arg.as_str() {
+                "--fcs" => options.fcs = true,
+                "--base64" => options.base64 = true,
+                "--summary" => options.summary = true,
+                "--type" => {
+                    let name = value()?;
+                    let frame_type =
+                        parse_frame_type(&name).ok_or_else(|| format!("unknown type {name}"))?;
+                    options.frame_types.push(frame_type);
+                }
+                "--address" => options.addresses.push(value()?.to_lowercase()),
+                option if option.starts_with("--") => {
+                    return Err(format!("unknown option {option}"))
+                }
+                _ => options.inputs.push(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    /// Returns whether the frame with the given summary passes the filters.
+    fn matches(&self, summary: Option<&FrameSummary>) -> bool {
+        let Some(summary) = summary else {
+            return self.frame_types.is_empty() && self.addresses.is_empty();
+        };
+        let type_matches =
+            self.frame_types.is_empty() || self.frame_types.contains(&summary.frame_type);
+        let address_matches = self.addresses.is_empty()
+            || [summary.dst_address, summary.src_address]
+                .iter()
+                .any(|address| self.addresses.contains(&address.to_string()));
+        type_matches && address_matches
+    }
+}
+
+/// Parses the name of a frame type as given to `--type`.
+fn parse_frame_type(name: &str) -> Option<FrameType> {
+    let frame_type = match name {
+        "beacon" => FrameType::Beacon,
+        "data" => FrameType::Data,
+        "ack" => FrameType::Ack,
+        "command" => FrameType::MacCommand,
+        "multipurpose" => FrameType::Multipurpose,
+        "fragment" => FrameType::FragmentOrFrak,
+        "extended" => FrameType::Extended,
+        _ => return None,
+    };
+    Some(frame_type)
+}
+
+/// Prints the given frame if it passes the filters.
+///
+/// * `label` - Identifies the frame in the output, e.g. its number
+/// * `frame` - The MPDU followed by an FCS of the given length
+fn print_frame<W: Write>(
+    output: &mut W,
+    options: &Options,
+    label: &str,
+    frame: &[u8],
+    fcs_len: usize,
+) -> io::Result<()> {
+    let Some(mpdu_len) = frame.len().checked_sub(fcs_len) else {
+        return writeln!(output, "{label}: frame shorter than its FCS");
+    };
+    let (mpdu, fcs) = frame.split_at(mpdu_len);
+    let summary = FrameSummary::parse(mpdu);
+    if !options.matches(summary.as_ref().ok()) {
+        return Ok(());
+    }
+
+    let fcs_note = match fcs_len {
+        0 => "",
+        _ if check_fcs(mpdu, fcs).is_ok() => "",
+        _ => ", invalid FCS",
+    };
+    if options.summary {
+        match summary {
+            Ok(summary) => writeln!(output, "{label}{fcs_note}: {summary}"),
+            Err(error) => writeln!(output, "{label}{fcs_note}: malformed frame: {error}"),
+        }
+    } else {
+        writeln!(output, "{label} ({} bytes{fcs_note})", mpdu.len())?;
+        writeln!(output, "{}", dissect(mpdu))
+    }
+}
+
+/// Prints the frames of the given pcap capture.
+fn print_capture<W: Write>(output: &mut W, options: &Options, path: &str) -> io::Result<()> {
+    let data = fs::read(path)?;
+    let reader =
+        PcapReader::new(&data).map_err(|error| io::Error::other(format!("{path}: {error}")))?;
+    for (idx, record) in reader.enumerate() {
+        let record = record.map_err(|error| io::Error::other(format!("{path}: {error}")))?;
+        let label = format!(
+            "{path} #{} at {}.{:09}",
+            idx + 1,
+            record.timestamp.as_secs(),
+            record.timestamp.subsec_nanos()
+        );
+        print_frame(output, options, &label, record.frame, record.fcs_len)?;
+    }
+    Ok(())
+}
+
+/// Prints the given encoded frame.
+fn print_encoded<W: Write>(
+    output: &mut W,
+    options: &Options,
+    label: &str,
+    text: &str,
+) -> io::Result<()> {
+    let frame = input::decode(text, options.base64)
+        .ok_or_else(|| io::Error::other(format!("{label}: invalid hex or base64 frame")))?;
+    let fcs_len = if options.fcs { FCS_LEN } else { 0 };
+    print_frame(output, options, label, &frame, fcs_len)
+}
+
+fn run(options: &Options) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if options.inputs.is_empty() {
+        for (idx, line) in io::stdin().lock().lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            print_encoded(&mut stdout, options, &format!("#{}", idx + 1), &line)?;
+        }
+        return Ok(());
+    }
+
+    for (idx, input) in options.inputs.iter().enumerate() {
+        if Path::new(input).is_file() {
+            print_capture(&mut stdout, options, input)?;
+        } else {
+            print_encoded(&mut stdout, options, &format!("#{}", idx + 1), input)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("invalid arguments: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        // The output was closed, e.g. when piped into `head`.
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn output(options: &Options, frame: &[u8], fcs_len: usize) -> String {
+        let mut output = Vec::new();
+        print_frame(&mut output, options, "#1", frame, fcs_len).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    const DATA_FRAME: [u8; 12] = [
+        0x61, 0x88, // frame control
+        0x2a, // sequence number
+        0xcd, 0xab, // dst PAN ID
+        0x02, 0x00, // dst address
+        0x01, 0x00, // src address
+        0x11, 0x22, 0x33, // payload
+    ];
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            parse(&["--fcs", "--type", "data", "--address", "AB:CD", "41882a"]),
+            Ok(Options {
+                fcs: true,
+                frame_types: [FrameType::Data].to_vec(),
+                addresses: ["ab:cd".to_string()].to_vec(),
+                inputs: ["41882a".to_string()].to_vec(),
+                ..Options::default()
+            })
+        );
+        assert!(parse(&["--type", "frak"]).is_err());
+        assert!(parse(&["--address"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+
+    #[test]
+    fn filters() {
+        let summary = FrameSummary::parse(&DATA_FRAME).unwrap();
+        assert!(Options::default().matches(Some(&summary)));
+        assert!(Options::default().matches(None));
+
+        let options = parse(&["--type", "beacon", "--type", "data"]).unwrap();
+        assert!(options.matches(Some(&summary)));
+        assert!(!options.matches(None));
+        let options = parse(&["--type", "ack"]).unwrap();
+        assert!(!options.matches(Some(&summary)));
+
+        let options = parse(&["--address", "01:00"]).unwrap();
+        assert!(options.matches(Some(&summary)));
+        let options = parse(&["--address", "03:00"]).unwrap();
+        assert!(!options.matches(Some(&summary)));
+    }
+
+    #[test]
+    fn print() {
+        let options = parse(&["--summary"]).unwrap();
+        assert_eq!(
+            output(&options, &DATA_FRAME, 0),
+            "#1: Data #42 0xabcd 02:00 <- 01:00 ies 0+0 payload 3\n"
+        );
+
+        let mut frame = DATA_FRAME.to_vec();
+        frame.extend_from_slice(&[0x00, 0x00]);
+        assert!(output(&options, &frame, 2).starts_with("#1, invalid FCS: Data #42"));
+
+        let output = output(&Options::default(), &DATA_FRAME, 0);
+        assert!(output.starts_with("#1 (12 bytes)\nFrame Control: 0x8861\n"));
+        assert!(output.ends_with("Payload: 3 bytes\n  0000: 11 22 33\n\n"));
+    }
+
+    #[test]
+    fn print_ies() {
+        let options = parse(&["--summary"]).unwrap();
+        let enhanced_beacon = input::decode(
+            "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00",
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            output(&options, &enhanced_beacon, 0),
+            "#1: Beacon 0xabcd ff:ff <- 01:00:01:00:01:00:01:00 ies 0+1 payload 0\n"
+        );
+        let enhanced_ack = input::decode("022e37cdab0200020002000200020fe18f", false).unwrap();
+        assert_eq!(
+            output(&options, &enhanced_ack, 0),
+            "#1: Ack #55 0xabcd 02:00:02:00:02:00:02:00 <- absent ies 1+0 payload 0\n"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Reader of pcap captures of IEEE 802.15.4 frames.
+//!
+//! Captures with microsecond or nanosecond timestamps in either byte order
+//! are supported with the following link types:
+//!
+//! - `LINKTYPE_IEEE802_15_4_WITHFCS` (195): MPDUs followed by a 2-octet FCS,
+//! - `LINKTYPE_IEEE802_15_4_NOFCS` (230): MPDUs without FCS,
+//! - `LINKTYPE_IEEE802_15_4_TAP` (283): MPDUs preceded by a TAP header that
+//!   announces the FCS type, as written by the sniffer of the
+//!   `dot15d4-driver` crate.
+//!
+//! The pcapng format is not supported, Wireshark converts captures with
+//! "File" > "Save As".
+
+use std::{fmt, time::Duration};
+
+/// The magic number of pcap captures with microsecond timestamps.
+const MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+/// The magic number of pcap captures with nanosecond timestamps.
+const MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+
+const FILE_HEADER_LEN: usize = 24;
+const RECORD_HEADER_LEN: usize = 16;
+const TAP_HEADER_LEN: usize = 4;
+const TLV_HEADER_LEN: usize = 4;
+
+const LINKTYPE_IEEE802_15_4_WITHFCS: u32 = 195;
+const LINKTYPE_IEEE802_15_4_NOFCS: u32 = 230;
+const LINKTYPE_IEEE802_15_4_TAP: u32 = 283;
+
+/// TLV carrying the type of the FCS included in the frame.
+const TLV_FCS_TYPE: u16 = 0;
+
+/// The errors reported while reading a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcapError {
+    /// The file is not a pcap capture.
+    NotPcap,
+    /// The capture contains frames of the given unsupported link type.
+    UnsupportedLinkType(u32),
+    /// The capture ends within a record.
+    Truncated,
+    /// The TAP header of the record at the given offset is malformed.
+    MalformedTap(usize),
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcapError::NotPcap => write!(f, "not a pcap capture"),
+            PcapError::UnsupportedLinkType(link_type) => {
+                write!(f, "unsupported link type {link_type}")
+            }
+            PcapError::Truncated => write!(f, "truncated capture"),
+            PcapError::MalformedTap(offset) => {
+                write!(f, "malformed TAP header at offset {offset}")
+            }
+        }
+    }
+}
+
+/// A captured frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// The time at which the frame was captured since the Unix epoch.
+    pub timestamp: Duration,
+    /// The MPDU, followed by the FCS if any.
+    pub frame: &'a [u8],
+    /// The length of the FCS at the end of the frame.
+    pub fcs_len: usize,
+}
+
+/// An iterator over the frames of a capture.
+#[derive(Debug)]
+pub struct PcapReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+    nanoseconds: bool,
+    link_type: u32,
+}
+
+impl<'a> PcapReader<'a> {
+    /// Reads the header of the given capture.
+    pub fn new(data: &'a [u8]) -> Result<Self, PcapError> {
+        let header = data.get(..FILE_HEADER_LEN).ok_or(PcapError::NotPcap)?;
+        let magic = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let (big_endian, nanoseconds) = match magic {
+            MAGIC_MICROSECONDS => (false, false),
+            MAGIC_NANOSECONDS => (false, true),
+            _ if magic.swap_bytes() == MAGIC_MICROSECONDS => (true, false),
+            _ if magic.swap_bytes() == MAGIC_NANOSECONDS => (true, true),
+            _ => return Err(PcapError::NotPcap),
+        };
+        let mut reader = Self {
+            data,
+            offset: FILE_HEADER_LEN,
+            big_endian,
+            nanoseconds,
+            link_type: 0,
+        };
+        // The upper bits of the link type field carry the FCS length of some
+        // link types, they don't apply to IEEE 802.15.4.
+        reader.link_type = reader.u32_at(20) & 0x0fff_ffff;
+        match reader.link_type {
+            LINKTYPE_IEEE802_15_4_WITHFCS
+            | LINKTYPE_IEEE802_15_4_NOFCS
+            | LINKTYPE_IEEE802_15_4_TAP => Ok(reader),
+            link_type => Err(PcapError::UnsupportedLinkType(link_type)),
+        }
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        let bytes = self.data[offset..offset + 4].try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn record(&mut self) -> Result<Record<'a>, PcapError> {
+        if self.data.len() < self.offset + RECORD_HEADER_LEN {
+            return Err(PcapError::Truncated);
+        }
+        let seconds = self.u32_at(self.offset) as u64;
+        let fraction = self.u32_at(self.offset + 4);
+        let nanoseconds = if self.nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1_000)
+        };
+        let len = self.u32_at(self.offset + 8) as usize;
+        let start = self.offset + RECORD_HEADER_LEN;
+        let data = self
+            .data
+            .get(start..start + len)
+            .ok_or(PcapError::Truncated)?;
+        self.offset = start + len;
+
+        let (frame, fcs_len) = match self.link_type {
+            LINKTYPE_IEEE802_15_4_WITHFCS => (data, 2),
+            LINKTYPE_IEEE802_15_4_NOFCS => (data, 0),
+            _ => tap_frame(data).ok_or(PcapError::MalformedTap(start))?,
+        };
+        Ok(Record {
+            timestamp: Duration::new(seconds, nanoseconds),
+            frame,
+            fcs_len,
+        })
+    }
+}
+
+impl<'a> Iterator for PcapReader<'a> {
+    type Item = Result<Record<'a>, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.data.len() {
+            return None;
+        }
+        let record = self.record();
+        if record.is_err() {
+            self.offset = self.data.len();
+        }
+        Some(record)
+    }
+}
+
+/// Splits the given TAP record into the frame and the length of its FCS.
+fn tap_frame(data: &[u8]) -> Option<(&[u8], usize)> {
+    let header = data.get(..TAP_HEADER_LEN)?;
+    let header_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+    let tlvs = data.get(TAP_HEADER_LEN..header_len)?;
+
+    // Frames have a 2-octet FCS unless announced otherwise.
+    let mut fcs_len = 2;
+    let mut offset = 0;
+    while offset < tlvs.len() {
+        let tlv = tlvs.get(offset..offset + TLV_HEADER_LEN)?;
+        let tlv_type = u16::from_le_bytes([tlv[0], tlv[1]]);
+        let value_len = u16::from_le_bytes([tlv[2], tlv[3]]) as usize;
+        let value = tlvs.get(offset + TLV_HEADER_LEN..offset + TLV_HEADER_LEN + value_len)?;
+        if tlv_type == TLV_FCS_TYPE {
+            fcs_len = match value.first()? {
+                0 => 0,
+                1 => 2,
+                2 => 4,
+                _ => return None,
+            };
+        }
+        offset += (TLV_HEADER_LEN + value_len).next_multiple_of(4);
+    }
+
+    Some((&data[header_len..], fcs_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(magic: [u8; 4], link_type: [u8; 4], records: &[&[u8]]) -> Vec<u8> {
+        let mut capture = magic.to_vec();
+        capture.extend_from_slice(&[0; 16]);
+        capture.extend_from_slice(&link_type);
+        for record in records {
+            capture.extend_from_slice(record);
+        }
+        capture
+    }
+
+    #[test]
+    fn little_endian_with_fcs() {
+        let record = [
+            0x10, 0, 0, 0, // seconds
+            0x20, 0, 0, 0, // microseconds
+            5, 0, 0, 0, // captured length
+            5, 0, 0, 0, // original length
+            0x02, 0x10, 0x2a, 0x50, 0x7c, // MPDU and FCS
+        ];
+        let data = capture([0xd4, 0xc3, 0xb2, 0xa1], [195, 0, 0, 0], &[&record]);
+
+        let records: Vec<_> = PcapReader::new(&data).unwrap().collect();
+        assert_eq!(
+            records,
+            [Ok(Record {
+                timestamp: Duration::new(0x10, 0x20 * 1_000),
+                frame: &record[16..],
+                fcs_len: 2,
+            })]
+        );
+    }
+
+    #[test]
+    fn big_endian_without_fcs() {
+        let record = [
+            0, 0, 0, 0x10, // seconds
+            0, 0, 0, 0x20, // nanoseconds
+            0, 0, 0, 3, // captured length
+            0, 0, 0, 3, // original length
+            0x02, 0x10, 0x2a, // MPDU
+        ];
+        let data = capture(
+            [0xa1, 0xb2, 0x3c, 0x4d],
+            [0, 0, 0, 230],
+            &[&record, &record],
+        );
+
+        let mut reader = PcapReader::new(&data).unwrap();
+        let record = reader.next().unwrap().unwrap();
+        assert_eq!(record.timestamp, Duration::new(0x10, 0x20));
+        assert_eq!(record.frame, [0x02, 0x10, 0x2a]);
+        assert_eq!(record.fcs_len, 0);
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn tap() {
+        let record = [
+            1, 0, 0, 0, // seconds
+            0, 0, 0, 0, // nanoseconds
+            31, 0, 0, 0, // captured length
+            31, 0, 0, 0, // original length
+            0, 0, 28, 0, // TAP header
+            0, 0, 1, 0, 0, 0, 0, 0, // FCS type: none
+            3, 0, 3, 0, 15, 0, 0, 0, // channel
+            10, 0, 1, 0, 0xff, 0, 0, 0, // LQI
+            0x02, 0x10, 0x2a, // MPDU
+        ];
+        let data = capture([0x4d, 0x3c, 0xb2, 0xa1], [0x1b, 0x01, 0, 0], &[&record]);
+
+        let record = PcapReader::new(&data).unwrap().next().unwrap().unwrap();
+        assert_eq!(record.frame, [0x02, 0x10, 0x2a]);
+        assert_eq!(record.fcs_len, 0);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(PcapReader::new(&[0; 24]).unwrap_err(), PcapError::NotPcap);
+        assert_eq!(
+            PcapReader::new(&capture([0xd4, 0xc3, 0xb2, 0xa1], [1, 0, 0, 0], &[])).unwrap_err(),
+            PcapError::UnsupportedLinkType(1)
+        );
+
+        let truncated = [0; 12];
+        let data = capture([0xd4, 0xc3, 0xb2, 0xa1], [195, 0, 0, 0], &[&truncated]);
+        let mut reader = PcapReader::new(&data).unwrap();
+        assert_eq!(reader.next(), Some(Err(PcapError::Truncated)));
+        assert_eq!(reader.next(), None);
+
+        // The TAP header announces more octets than recorded.
+        let record = [0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 4, 0, 0, 0, 0, 0, 8, 0];
+        let data = capture([0xd4, 0xc3, 0xb2, 0xa1], [0x1b, 0x01, 0, 0], &[&record]);
+        let mut reader = PcapReader::new(&data).unwrap();
+        assert_eq!(reader.next(), Some(Err(PcapError::MalformedTap(40))));
+    }
+}
@@ -0,0 +1,132 @@
+//! Frame Check Sequence (FCS) calculation (IEEE 802.15.4-2024, section
+//! 7.2.11).
+//!
+//! Two FCS variants are supported:
+//!
+//! - The 2-octet FCS used by most PHYs: the ITU-T CRC-16 with generator
+//!   polynomial `x^16 + x^12 + x^5 + 1`, zero initial remainder and LSB-first
+//!   processing.
+//! - The 4-octet FCS optionally used by LECIM, TVWS and SUN PHYs: the ANSI
+//!   X3.66 CRC-32 with generator polynomial `0x04C11DB7`, all-ones initial
+//!   remainder and final complement.
+//!
+//! Both are transmitted in little-endian byte order directly after the MAC
+//! payload.
+//!
+//! Which variant applies is decided by the driver configuration (see
+//! [`DriverConfig::Fcs`]). Drivers that offload FCS handling to hardware (see
+//! [`FcsNone`](dot15d4_driver::FcsNone)) neither require nor include an FCS in
+//! the frame.
+
+pub use dot15d4_driver::{crc16, crc32};
+use dot15d4_driver::{DriverConfig, FcsFourBytes, FcsTwoBytes};
+use dot15d4_util::{Error, Result};
+
+use crate::{FrameError, FrameErrorKind};
+
+/// Returns the length of the FCS that the framework has to calculate for the
+/// given driver configuration, zero if FCS handling is offloaded.
+pub const fn fcs_length<Config: DriverConfig>() -> usize {
+    size_of::<Config::Fcs>()
+}
+
+/// Calculates the FCS over the given MPDU (excluding the FCS) and writes it
+/// into the given FCS buffer.
+///
+/// The FCS variant is selected by the length of the FCS buffer. An empty FCS
+/// buffer is accepted and left untouched.
+///
+/// # Errors
+///
+/// Returns an error if the FCS buffer is neither empty nor of the length of
+/// one of the supported FCS variants.
+pub fn write_fcs(mpdu_wo_fcs: &[u8], fcs: &mut [u8]) -> Result<()> {
+    match fcs.len() {
+        0 => {}
+        len if len == size_of::<FcsTwoBytes>() => {
+            fcs.copy_from_slice(&crc16(mpdu_wo_fcs).to_le_bytes());
+        }
+        len if len == size_of::<FcsFourBytes>() => {
+            fcs.copy_from_slice(&crc32(mpdu_wo_fcs).to_le_bytes());
+        }
+        _ => return Err(Error),
+    }
+
+    Ok(())
+}
+
+/// Verifies the FCS of the given MPDU (excluding the FCS).
+///
+/// The FCS variant is selected by the length of the given FCS. An empty FCS is
+/// considered valid as FCS checking has been offloaded in that case.
+///
+/// # Errors
+///
+/// Returns [`FrameErrorKind::InvalidFcs`] if the FCS does not match and
+/// [`FrameErrorKind::InvalidLength`] if the FCS length is not supported.
+pub fn check_fcs(mpdu_wo_fcs: &[u8], fcs: &[u8]) -> core::result::Result<(), FrameError> {
+    let is_valid = match fcs.len() {
+        0 => true,
+        len if len == size_of::<FcsTwoBytes>() => {
+            crc16(mpdu_wo_fcs).to_le_bytes().as_slice() == fcs
+        }
+        len if len == size_of::<FcsFourBytes>() => {
+            crc32(mpdu_wo_fcs).to_le_bytes().as_slice() == fcs
+        }
+        len => {
+            return Err(FrameErrorKind::InvalidLength {
+                expected: size_of::<FcsTwoBytes>(),
+                got: len,
+            }
+            .into())
+        }
+    };
+
+    if !is_valid {
+        return Err(FrameErrorKind::InvalidFcs.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK_INPUT: &[u8] = b"123456789";
+
+    #[test]
+    fn crc16_check_value() {
+        assert_eq!(crc16(CHECK_INPUT), 0x2189);
+        assert_eq!(crc16(&[]), 0);
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(CHECK_INPUT), 0xcbf4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn write_and_check() {
+        let mpdu = [0x41, 0x88, 0x01, 0xcd, 0xab, 0xff, 0xff, 0x34, 0x12];
+
+        let mut fcs = [0; 2];
+        write_fcs(&mpdu, &mut fcs).unwrap();
+        assert!(check_fcs(&mpdu, &fcs).is_ok());
+
+        let mut fcs = [0; 4];
+        write_fcs(&mpdu, &mut fcs).unwrap();
+        assert!(check_fcs(&mpdu, &fcs).is_ok());
+
+        fcs[0] ^= 1;
+        assert_eq!(
+            check_fcs(&mpdu, &fcs).unwrap_err().kind(),
+            FrameErrorKind::InvalidFcs
+        );
+
+        assert!(check_fcs(&mpdu, &[]).is_ok());
+        assert!(check_fcs(&mpdu, &[0; 3]).is_err());
+        assert!(write_fcs(&mpdu, &mut [0; 3]).is_err());
+    }
+}
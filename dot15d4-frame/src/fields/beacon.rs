@@ -0,0 +1,847 @@
+//! Beacon frame payload field access (IEEE 802.15.4-2024, section 7.3.1).
+//!
+//! The MAC payload of a (non-enhanced) beacon frame is structured as follows:
+//!
+//! ```notrust
+//! +---------------------+------------+-------------------------+----------------+
+//! | Superframe spec.    | GTS fields | Pending address fields  | Beacon payload |
+//! | (2B)                | (1-23B)    | (1-71B)                 | (variable)     |
+//! +---------------------+------------+-------------------------+----------------+
+//! ```
+//!
+//! The Superframe Specification field is structured as follows:
+//!
+//! ```notrust
+//! +--------------+-----------------+-----------------+-----+----------+-------------+-------------+
+//! | Beacon order | Superframe ord. | Final CAP slot  | BLE | Reserved | PAN coord.  | Assoc.      |
+//! | (bits 0-3)   | (bits 4-7)      | (bits 8-11)     | (12)| (13)     | (14)        | permit (15) |
+//! +--------------+-----------------+-----------------+-----+----------+-------------+-------------+
+//! ```
+//!
+//! The GTS fields consist of a GTS specification (1B), GTS directions (0/1B,
+//! only present if at least one GTS descriptor is present) and a GTS list of
+//! 3-octet GTS descriptors:
+//!
+//! ```notrust
+//! GTS specification:             GTS descriptor:
+//! +-------------+----------+--------+   +----------------+-----------------+-------------+
+//! | Desc. count | Reserved | Permit |   | Short address  | Starting slot   | Length      |
+//! | (bits 0-2)  | (3-6)    | (7)    |   | (2B)           | (bits 0-3)      | (bits 4-7)  |
+//! +-------------+----------+--------+   +----------------+-----------------+-------------+
+//! ```
+//!
+//! The pending address fields consist of a pending address specification (1B)
+//! followed by the list of short addresses and then the list of extended
+//! addresses:
+//!
+//! ```notrust
+//! +------------------+----------+---------------------+----------+
+//! | Num. short addr. | Reserved | Num. extended addr. | Reserved |
+//! | (bits 0-2)       | (3)      | (bits 4-6)          | (7)      |
+//! +------------------+----------+---------------------+----------+
+//! ```
+
+use dot15d4_driver::frame::{ExtendedAddress, FrameError, FrameErrorKind, ShortAddress};
+use dot15d4_util::{Error, Result};
+
+/// The length of the Superframe Specification field in octets.
+pub const SUPERFRAME_SPECIFICATION_LEN: usize = 2;
+
+/// The max number of GTS descriptors in a beacon.
+pub const MAX_GTS_DESCRIPTORS: usize = 7;
+
+/// The max number of short and extended addresses each in a beacon's pending
+/// address list.
+pub const MAX_PENDING_ADDRESSES: usize = 7;
+
+/// The beacon order and superframe order of a non-beacon-enabled PAN.
+pub const NON_BEACON_ENABLED_ORDER: u8 = 15;
+
+const ORDER_MASK: u8 = 0b1111;
+const SUPERFRAME_ORDER_SHIFT: u8 = 4;
+const BATTERY_LIFE_EXTENSION: u8 = 0b0001_0000;
+const PAN_COORDINATOR: u8 = 0b0100_0000;
+const ASSOCIATION_PERMIT: u8 = 0b1000_0000;
+
+const GTS_DESCRIPTOR_COUNT_MASK: u8 = 0b0000_0111;
+const GTS_PERMIT: u8 = 0b1000_0000;
+const GTS_DIRECTIONS_MASK: u8 = 0b0111_1111;
+const GTS_DESCRIPTOR_LEN: usize = 3;
+const GTS_LENGTH_SHIFT: u8 = 4;
+
+const NUM_SHORT_ADDRESSES_MASK: u8 = 0b0000_0111;
+const NUM_EXTENDED_ADDRESSES_SHIFT: u8 = 4;
+const NUM_EXTENDED_ADDRESSES_MASK: u8 = 0b0000_0111;
+const SHORT_ADDRESS_LEN: usize = 2;
+const EXTENDED_ADDRESS_LEN: usize = 8;
+
+/// Calculates the length of the GTS fields with the given number of GTS
+/// descriptors.
+pub const fn gts_fields_length(descriptor_count: u8) -> u16 {
+    debug_assert!(descriptor_count as usize <= MAX_GTS_DESCRIPTORS);
+    if descriptor_count == 0 {
+        1
+    } else {
+        2 + descriptor_count as u16 * GTS_DESCRIPTOR_LEN as u16
+    }
+}
+
+/// Calculates the length of the pending address fields with the given number
+/// of pending short and extended addresses.
+pub const fn pending_address_fields_length(
+    num_short_addresses: u8,
+    num_extended_addresses: u8,
+) -> u16 {
+    debug_assert!(num_short_addresses as usize <= MAX_PENDING_ADDRESSES);
+    debug_assert!(num_extended_addresses as usize <= MAX_PENDING_ADDRESSES);
+    1 + num_short_addresses as u16 * SHORT_ADDRESS_LEN as u16
+        + num_extended_addresses as u16 * EXTENDED_ADDRESS_LEN as u16
+}
+
+/// Calculates the length of all beacon fields preceding the beacon payload.
+///
+/// Add the length of the beacon payload to obtain the frame payload length of
+/// a beacon frame.
+pub const fn beacon_fields_length(
+    gts_descriptor_count: u8,
+    num_short_addresses: u8,
+    num_extended_addresses: u8,
+) -> u16 {
+    SUPERFRAME_SPECIFICATION_LEN as u16
+        + gts_fields_length(gts_descriptor_count)
+        + pending_address_fields_length(num_short_addresses, num_extended_addresses)
+}
+
+fn check_min_len(bytes: &[u8], needed: usize) -> core::result::Result<(), FrameError> {
+    if bytes.len() < needed {
+        return Err(FrameErrorKind::BufferTooShort {
+            needed,
+            got: bytes.len(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// A reader/writer for the Superframe Specification field of a beacon.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SuperframeSpecification<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> SuperframeSpecification<Bytes> {
+    /// Create a new [`SuperframeSpecification`] reader/writer from a given
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is not exactly two octets long.
+    pub fn new(bytes: Bytes) -> core::result::Result<Self, FrameError> {
+        let len = bytes.as_ref().len();
+        if len != SUPERFRAME_SPECIFICATION_LEN {
+            return Err(FrameErrorKind::InvalidLength {
+                expected: SUPERFRAME_SPECIFICATION_LEN,
+                got: len,
+            }
+            .into());
+        }
+
+        Ok(Self::new_unchecked(bytes))
+    }
+
+    /// Create a new [`SuperframeSpecification`] reader/writer from a given
+    /// buffer without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// The beacon order (BO), i.e. the beacon interval is
+    /// `aBaseSuperframeDuration * 2^BO`. A value of 15 denotes a
+    /// non-beacon-enabled PAN.
+    pub fn beacon_order(&self) -> u8 {
+        self.bytes.as_ref()[0] & ORDER_MASK
+    }
+
+    /// The superframe order (SO), i.e. the active portion of the superframe
+    /// lasts `aBaseSuperframeDuration * 2^SO`.
+    pub fn superframe_order(&self) -> u8 {
+        (self.bytes.as_ref()[0] >> SUPERFRAME_ORDER_SHIFT) & ORDER_MASK
+    }
+
+    /// The final superframe slot used by the contention access period (CAP).
+    pub fn final_cap_slot(&self) -> u8 {
+        self.bytes.as_ref()[1] & ORDER_MASK
+    }
+
+    /// Returns `true` if frames transmitted to the beacon device during the
+    /// CAP are required to start within `macBattLifeExtPeriods` after the
+    /// inter-frame spacing following the beacon.
+    pub fn battery_life_extension(&self) -> bool {
+        self.bytes.as_ref()[1] & BATTERY_LIFE_EXTENSION != 0
+    }
+
+    /// Returns `true` if the beacon is transmitted by the PAN coordinator.
+    pub fn pan_coordinator(&self) -> bool {
+        self.bytes.as_ref()[1] & PAN_COORDINATOR != 0
+    }
+
+    /// Returns `true` if the coordinator accepts association to the PAN.
+    pub fn association_permit(&self) -> bool {
+        self.bytes.as_ref()[1] & ASSOCIATION_PERMIT != 0
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> SuperframeSpecification<Bytes> {
+    /// Sets the beacon order (0-15).
+    pub fn set_beacon_order(&mut self, beacon_order: u8) {
+        debug_assert!(beacon_order <= ORDER_MASK);
+        let b = &mut self.bytes.as_mut()[0];
+        *b = (*b & !ORDER_MASK) | (beacon_order & ORDER_MASK);
+    }
+
+    /// Sets the superframe order (0-15).
+    pub fn set_superframe_order(&mut self, superframe_order: u8) {
+        debug_assert!(superframe_order <= ORDER_MASK);
+        let b = &mut self.bytes.as_mut()[0];
+        *b = (*b & ORDER_MASK) | ((superframe_order & ORDER_MASK) << SUPERFRAME_ORDER_SHIFT);
+    }
+
+    /// Sets the final CAP slot (0-15).
+    pub fn set_final_cap_slot(&mut self, final_cap_slot: u8) {
+        debug_assert!(final_cap_slot <= ORDER_MASK);
+        let b = &mut self.bytes.as_mut()[1];
+        *b = (*b & !ORDER_MASK) | (final_cap_slot & ORDER_MASK);
+    }
+
+    /// Sets the battery life extension (BLE) flag.
+    pub fn set_battery_life_extension(&mut self, battery_life_extension: bool) {
+        self.set_flag(BATTERY_LIFE_EXTENSION, battery_life_extension);
+    }
+
+    /// Sets the PAN coordinator flag.
+    pub fn set_pan_coordinator(&mut self, pan_coordinator: bool) {
+        self.set_flag(PAN_COORDINATOR, pan_coordinator);
+    }
+
+    /// Sets the association permit flag.
+    pub fn set_association_permit(&mut self, association_permit: bool) {
+        self.set_flag(ASSOCIATION_PERMIT, association_permit);
+    }
+
+    fn set_flag(&mut self, flag: u8, value: bool) {
+        let b = &mut self.bytes.as_mut()[1];
+        if value {
+            *b |= flag;
+        } else {
+            *b &= !flag;
+        }
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for SuperframeSpecification<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(f, "beacon order: {}", self.beacon_order())?;
+        writeln!(
+            f,
+            "{:indent$}superframe order: {}",
+            "",
+            self.superframe_order()
+        )?;
+        writeln!(f, "{:indent$}final cap slot: {}", "", self.final_cap_slot())?;
+        writeln!(
+            f,
+            "{:indent$}battery life extension: {}",
+            "",
+            self.battery_life_extension() as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}pan coordinator: {}",
+            "",
+            self.pan_coordinator() as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}association permit: {}",
+            "",
+            self.association_permit() as usize
+        )
+    }
+}
+
+/// The direction of a guaranteed time slot as seen from the device owning the
+/// GTS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GtsDirection {
+    /// The device transmits during the GTS.
+    Transmit,
+    /// The device receives during the GTS.
+    Receive,
+}
+
+/// A GTS descriptor as announced in a beacon's GTS list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GtsDescriptor {
+    /// The short address of the device the GTS is allocated to.
+    pub short_address: ShortAddress<[u8; 2]>,
+    /// The superframe slot at which the GTS begins (0-15).
+    pub starting_slot: u8,
+    /// The number of contiguous superframe slots of the GTS (0-15).
+    pub length: u8,
+    /// The direction of the GTS.
+    pub direction: GtsDirection,
+}
+
+/// A reader/writer for the GTS fields of a beacon, i.e. the GTS specification,
+/// GTS directions and GTS list.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GtsFields<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> GtsFields<Bytes> {
+    /// Create a new [`GtsFields`] reader/writer from a given buffer.
+    ///
+    /// The buffer may extend beyond the GTS fields, see [`GtsFields::length`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the GTS list
+    /// announced in the GTS specification.
+    pub fn new(bytes: Bytes) -> core::result::Result<Self, FrameError> {
+        check_min_len(bytes.as_ref(), 1)?;
+        let gts_fields = Self::new_unchecked(bytes);
+        check_min_len(gts_fields.bytes.as_ref(), gts_fields.length() as usize)?;
+        Ok(gts_fields)
+    }
+
+    /// Create a new [`GtsFields`] reader/writer from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// The length of the GTS fields as announced by the GTS specification.
+    pub fn length(&self) -> u16 {
+        gts_fields_length(self.descriptor_count())
+    }
+
+    /// The number of GTS descriptors in the GTS list.
+    pub fn descriptor_count(&self) -> u8 {
+        self.bytes.as_ref()[0] & GTS_DESCRIPTOR_COUNT_MASK
+    }
+
+    /// Returns `true` if the PAN coordinator accepts GTS requests.
+    pub fn gts_permit(&self) -> bool {
+        self.bytes.as_ref()[0] & GTS_PERMIT != 0
+    }
+
+    /// The GTS directions mask, bit `i` is set if the `i`-th GTS descriptor is
+    /// a receive-only GTS. Zero if the GTS list is empty.
+    pub fn directions_mask(&self) -> u8 {
+        if self.descriptor_count() == 0 {
+            return 0;
+        }
+
+        self.bytes.as_ref()[1] & GTS_DIRECTIONS_MASK
+    }
+
+    /// Returns an iterator over the GTS descriptors in the GTS list.
+    pub fn descriptors(&self) -> impl Iterator<Item = GtsDescriptor> + '_ {
+        let directions_mask = self.directions_mask();
+        let list_len = self.descriptor_count() as usize * GTS_DESCRIPTOR_LEN;
+        let list = match self.descriptor_count() {
+            0 => &[][..],
+            _ => &self.bytes.as_ref()[2..2 + list_len],
+        };
+
+        list.chunks_exact(GTS_DESCRIPTOR_LEN)
+            .enumerate()
+            .map(move |(i, descriptor)| GtsDescriptor {
+                short_address: ShortAddress::new_owned([descriptor[0], descriptor[1]]),
+                starting_slot: descriptor[2] & ORDER_MASK,
+                length: descriptor[2] >> GTS_LENGTH_SHIFT,
+                direction: if directions_mask & (1 << i) != 0 {
+                    GtsDirection::Receive
+                } else {
+                    GtsDirection::Transmit
+                },
+            })
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> GtsFields<Bytes> {
+    /// Writes the complete GTS fields.
+    ///
+    /// The buffer must have been sized with [`gts_fields_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are too many descriptors, a starting slot or
+    /// length does not fit into four bits or the buffer does not match the
+    /// length of the GTS fields.
+    pub fn emit(&mut self, gts_permit: bool, descriptors: &[GtsDescriptor]) -> Result<()> {
+        if descriptors.len() > MAX_GTS_DESCRIPTORS
+            || descriptors
+                .iter()
+                .any(|d| d.starting_slot > ORDER_MASK || d.length > ORDER_MASK)
+        {
+            return Err(Error);
+        }
+
+        let bytes = self.bytes.as_mut();
+        if bytes.len() != gts_fields_length(descriptors.len() as u8) as usize {
+            return Err(Error);
+        }
+
+        bytes[0] = descriptors.len() as u8;
+        if gts_permit {
+            bytes[0] |= GTS_PERMIT;
+        }
+
+        if descriptors.is_empty() {
+            return Ok(());
+        }
+
+        let mut directions_mask = 0;
+        for (i, (descriptor, dst)) in descriptors
+            .iter()
+            .zip(bytes[2..].chunks_exact_mut(GTS_DESCRIPTOR_LEN))
+            .enumerate()
+        {
+            if descriptor.direction == GtsDirection::Receive {
+                directions_mask |= 1 << i;
+            }
+            dst[..2].copy_from_slice(descriptor.short_address.as_ref());
+            dst[2] = descriptor.starting_slot | (descriptor.length << GTS_LENGTH_SHIFT);
+        }
+        bytes[1] = directions_mask;
+
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for GtsFields<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(f, "gts permit: {}", self.gts_permit() as usize)?;
+        for descriptor in self.descriptors() {
+            writeln!(
+                f,
+                "{:indent$}gts: {:#06x}, slot {}, length {}, {:?}",
+                "",
+                descriptor.short_address.into_u16(),
+                descriptor.starting_slot,
+                descriptor.length,
+                descriptor.direction
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A reader/writer for the pending address fields of a beacon, i.e. the
+/// pending address specification and address list.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PendingAddressFields<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> PendingAddressFields<Bytes> {
+    /// Create a new [`PendingAddressFields`] reader/writer from a given
+    /// buffer.
+    ///
+    /// The buffer may extend beyond the pending address fields, see
+    /// [`PendingAddressFields::length`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the addresses
+    /// announced in the pending address specification.
+    pub fn new(bytes: Bytes) -> core::result::Result<Self, FrameError> {
+        check_min_len(bytes.as_ref(), 1)?;
+        let pending_address_fields = Self::new_unchecked(bytes);
+        check_min_len(
+            pending_address_fields.bytes.as_ref(),
+            pending_address_fields.length() as usize,
+        )?;
+        Ok(pending_address_fields)
+    }
+
+    /// Create a new [`PendingAddressFields`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// The length of the pending address fields as announced by the pending
+    /// address specification.
+    pub fn length(&self) -> u16 {
+        pending_address_fields_length(self.num_short_addresses(), self.num_extended_addresses())
+    }
+
+    /// The number of pending short addresses.
+    pub fn num_short_addresses(&self) -> u8 {
+        self.bytes.as_ref()[0] & NUM_SHORT_ADDRESSES_MASK
+    }
+
+    /// The number of pending extended addresses.
+    pub fn num_extended_addresses(&self) -> u8 {
+        (self.bytes.as_ref()[0] >> NUM_EXTENDED_ADDRESSES_SHIFT) & NUM_EXTENDED_ADDRESSES_MASK
+    }
+
+    /// Returns an iterator over the short addresses of devices for which the
+    /// coordinator has pending data.
+    pub fn short_addresses(&self) -> impl Iterator<Item = ShortAddress<&[u8]>> {
+        let end = 1 + self.num_short_addresses() as usize * SHORT_ADDRESS_LEN;
+        self.bytes.as_ref()[1..end]
+            .chunks_exact(SHORT_ADDRESS_LEN)
+            .map(ShortAddress::new)
+    }
+
+    /// Returns an iterator over the extended addresses of devices for which
+    /// the coordinator has pending data.
+    pub fn extended_addresses(&self) -> impl Iterator<Item = ExtendedAddress<&[u8]>> {
+        let start = 1 + self.num_short_addresses() as usize * SHORT_ADDRESS_LEN;
+        let end = start + self.num_extended_addresses() as usize * EXTENDED_ADDRESS_LEN;
+        self.bytes.as_ref()[start..end]
+            .chunks_exact(EXTENDED_ADDRESS_LEN)
+            .map(ExtendedAddress::new)
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> PendingAddressFields<Bytes> {
+    /// Writes the complete pending address fields.
+    ///
+    /// The buffer must have been sized with
+    /// [`pending_address_fields_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are too many addresses or the buffer does not
+    /// match the length of the pending address fields.
+    pub fn emit<Short: AsRef<[u8]>, Extended: AsRef<[u8]>>(
+        &mut self,
+        short_addresses: &[ShortAddress<Short>],
+        extended_addresses: &[ExtendedAddress<Extended>],
+    ) -> Result<()> {
+        if short_addresses.len() > MAX_PENDING_ADDRESSES
+            || extended_addresses.len() > MAX_PENDING_ADDRESSES
+        {
+            return Err(Error);
+        }
+
+        let bytes = self.bytes.as_mut();
+        if bytes.len()
+            != pending_address_fields_length(
+                short_addresses.len() as u8,
+                extended_addresses.len() as u8,
+            ) as usize
+        {
+            return Err(Error);
+        }
+
+        bytes[0] = short_addresses.len() as u8
+            | ((extended_addresses.len() as u8) << NUM_EXTENDED_ADDRESSES_SHIFT);
+
+        let mut offset = 1;
+        for short_address in short_addresses {
+            bytes[offset..offset + SHORT_ADDRESS_LEN].copy_from_slice(short_address.as_ref());
+            offset += SHORT_ADDRESS_LEN;
+        }
+        for extended_address in extended_addresses {
+            bytes[offset..offset + EXTENDED_ADDRESS_LEN].copy_from_slice(extended_address.as_ref());
+            offset += EXTENDED_ADDRESS_LEN;
+        }
+
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for PendingAddressFields<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "pending addresses: {} short, {} extended",
+            self.num_short_addresses(),
+            self.num_extended_addresses()
+        )?;
+        for short_address in self.short_addresses() {
+            writeln!(f, "{:indent$}{:#06x}", "", short_address.into_u16())?;
+        }
+        for extended_address in self.extended_addresses() {
+            writeln!(f, "{:indent$}{:02x?}", "", extended_address.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+/// A reader for the MAC payload of a (non-enhanced) beacon frame.
+///
+/// Splits the frame payload into the Superframe Specification, GTS fields,
+/// pending address fields and the beacon payload.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BeaconFields<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> BeaconFields<Bytes> {
+    /// Create a new [`BeaconFields`] reader from a beacon frame payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame payload is too short to contain the
+    /// fields announced in the GTS and pending address specifications. The
+    /// error offset is relative to the start of the frame payload.
+    pub fn new(bytes: Bytes) -> core::result::Result<Self, FrameError> {
+        let payload = bytes.as_ref();
+        check_min_len(payload, SUPERFRAME_SPECIFICATION_LEN + 1)?;
+
+        let gts_offset = SUPERFRAME_SPECIFICATION_LEN;
+        let gts_fields =
+            GtsFields::new(&payload[gts_offset..]).map_err(|e| e.shifted_by(gts_offset))?;
+
+        let pending_offset = gts_offset + gts_fields.length() as usize;
+        check_min_len(payload, pending_offset + 1)?;
+        PendingAddressFields::new(&payload[pending_offset..])
+            .map_err(|e| e.shifted_by(pending_offset))?;
+
+        Ok(Self::new_unchecked(bytes))
+    }
+
+    /// Create a new [`BeaconFields`] reader from a beacon frame payload
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the Superframe Specification field.
+    pub fn superframe_specification(&self) -> SuperframeSpecification<&[u8]> {
+        SuperframeSpecification::new_unchecked(&self.bytes.as_ref()[..SUPERFRAME_SPECIFICATION_LEN])
+    }
+
+    /// Returns the GTS fields.
+    pub fn gts_fields(&self) -> GtsFields<&[u8]> {
+        let gts_fields =
+            GtsFields::new_unchecked(&self.bytes.as_ref()[SUPERFRAME_SPECIFICATION_LEN..]);
+        let end = gts_fields.length() as usize;
+        GtsFields::new_unchecked(&gts_fields.into_inner()[..end])
+    }
+
+    fn pending_address_fields_offset(&self) -> usize {
+        SUPERFRAME_SPECIFICATION_LEN + self.gts_fields().length() as usize
+    }
+
+    /// Returns the pending address fields.
+    pub fn pending_address_fields(&self) -> PendingAddressFields<&[u8]> {
+        let pending_address_fields = PendingAddressFields::new_unchecked(
+            &self.bytes.as_ref()[self.pending_address_fields_offset()..],
+        );
+        let end = pending_address_fields.length() as usize;
+        PendingAddressFields::new_unchecked(&pending_address_fields.into_inner()[..end])
+    }
+
+    /// Returns the beacon payload following the beacon fields.
+    pub fn beacon_payload(&self) -> &[u8] {
+        let offset =
+            self.pending_address_fields_offset() + self.pending_address_fields().length() as usize;
+        &self.bytes.as_ref()[offset..]
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for BeaconFields<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        write!(f, "{}", self.superframe_specification())?;
+        write!(f, "{:indent$}{:indent$}", "", self.gts_fields())?;
+        write!(f, "{:indent$}{:indent$}", "", self.pending_address_fields())?;
+        writeln!(
+            f,
+            "{:indent$}beacon payload: {:02x?}",
+            "",
+            self.beacon_payload()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn superframe_specification() {
+        let bytes = [0xf5, 0b1101_1010];
+        let spec = SuperframeSpecification::new(&bytes).unwrap();
+        assert_eq!(spec.beacon_order(), 5);
+        assert_eq!(spec.superframe_order(), 15);
+        assert_eq!(spec.final_cap_slot(), 10);
+        assert!(spec.battery_life_extension());
+        assert!(spec.pan_coordinator());
+        assert!(spec.association_permit());
+
+        let mut bytes = [0; 2];
+        let mut spec = SuperframeSpecification::new_unchecked(&mut bytes);
+        spec.set_beacon_order(5);
+        spec.set_superframe_order(15);
+        spec.set_final_cap_slot(10);
+        spec.set_battery_life_extension(true);
+        spec.set_pan_coordinator(true);
+        spec.set_association_permit(true);
+        spec.set_pan_coordinator(false);
+        assert_eq!(bytes, [0xf5, 0b1001_1010]);
+
+        assert!(SuperframeSpecification::new(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn gts_fields() {
+        let bytes = [0b1000_0010, 0b0000_0010, 0x34, 0x12, 0x29, 0x78, 0x56, 0x1c];
+        let gts_fields = GtsFields::new(&bytes).unwrap();
+        assert!(gts_fields.gts_permit());
+        assert_eq!(gts_fields.descriptor_count(), 2);
+        assert_eq!(gts_fields.length(), 8);
+
+        let mut descriptors = gts_fields.descriptors();
+        let descriptor = descriptors.next().unwrap();
+        assert_eq!(descriptor.short_address.into_u16(), 0x1234);
+        assert_eq!(descriptor.starting_slot, 9);
+        assert_eq!(descriptor.length, 2);
+        assert_eq!(descriptor.direction, GtsDirection::Transmit);
+        let descriptor = descriptors.next().unwrap();
+        assert_eq!(descriptor.short_address.into_u16(), 0x5678);
+        assert_eq!(descriptor.starting_slot, 12);
+        assert_eq!(descriptor.length, 1);
+        assert_eq!(descriptor.direction, GtsDirection::Receive);
+        assert!(descriptors.next().is_none());
+
+        let mut emitted = [0; 8];
+        let mut descriptors = gts_fields.descriptors();
+        let descriptors = [descriptors.next().unwrap(), descriptors.next().unwrap()];
+        GtsFields::new_unchecked(&mut emitted)
+            .emit(true, &descriptors)
+            .unwrap();
+        assert_eq!(emitted, bytes);
+
+        let mut emitted = [0; 1];
+        GtsFields::new_unchecked(&mut emitted)
+            .emit(false, &[])
+            .unwrap();
+        assert_eq!(emitted, [0]);
+        assert_eq!(GtsFields::new(&emitted).unwrap().descriptors().count(), 0);
+
+        // Truncated GTS list.
+        assert!(GtsFields::new(&bytes[..7]).is_err());
+        assert!(GtsFields::new(&[]).is_err());
+    }
+
+    #[test]
+    fn pending_address_fields() {
+        let bytes = [
+            0b0001_0001,
+            0x34,
+            0x12,
+            0x01,
+            0x02,
+            0x03,
+            0x04,
+            0x05,
+            0x06,
+            0x07,
+            0x08,
+        ];
+        let pending = PendingAddressFields::new(&bytes).unwrap();
+        assert_eq!(pending.num_short_addresses(), 1);
+        assert_eq!(pending.num_extended_addresses(), 1);
+        assert_eq!(pending.length(), 11);
+        assert_eq!(pending.short_addresses().next().unwrap().into_u16(), 0x1234);
+        assert_eq!(
+            pending.extended_addresses().next().unwrap().as_ref(),
+            &bytes[3..]
+        );
+
+        let mut emitted = [0; 11];
+        PendingAddressFields::new_unchecked(&mut emitted)
+            .emit(
+                &[ShortAddress::from_u16(0x1234)],
+                &[ExtendedAddress::new(&bytes[3..])],
+            )
+            .unwrap();
+        assert_eq!(emitted, bytes);
+
+        let short_addresses = [ShortAddress::from_u16(0); MAX_PENDING_ADDRESSES + 1];
+        let mut emitted = [0; 17];
+        assert!(PendingAddressFields::new_unchecked(&mut emitted)
+            .emit::<_, [u8; 8]>(&short_addresses, &[])
+            .is_err());
+
+        // Truncated address list.
+        assert!(PendingAddressFields::new(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn beacon_fields() {
+        let payload = [
+            0xff,
+            0b1100_1111, // superframe specification
+            0b1000_0001,
+            0b0000_0001,
+            0x34,
+            0x12,
+            0x29, // GTS fields
+            0b0000_0001,
+            0x78,
+            0x56, // pending address fields
+            0xaa,
+            0xbb, // beacon payload
+        ];
+        assert_eq!(beacon_fields_length(1, 1, 0), 10);
+
+        let beacon = BeaconFields::new(&payload).unwrap();
+        let spec = beacon.superframe_specification();
+        assert_eq!(spec.beacon_order(), NON_BEACON_ENABLED_ORDER);
+        assert!(spec.association_permit());
+        assert_eq!(beacon.gts_fields().descriptor_count(), 1);
+        assert_eq!(
+            beacon
+                .pending_address_fields()
+                .short_addresses()
+                .next()
+                .unwrap()
+                .into_u16(),
+            0x5678
+        );
+        assert_eq!(beacon.beacon_payload(), &[0xaa, 0xbb]);
+
+        let err = BeaconFields::new(&payload[..9]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            FrameErrorKind::BufferTooShort { needed: 3, got: 2 }
+        );
+        assert_eq!(err.offset(), Some(7));
+
+        let err = BeaconFields::new(&payload[..5]).unwrap_err();
+        assert_eq!(err.offset(), Some(2));
+    }
+}
@@ -0,0 +1,758 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use dot15d4_driver::frame::{AddressingFields, AddressingRepr, FrameControl, FrameType};
+
+use super::summary::{
+    field, ie_header, next_ie, FRAME_CONTROL_LEN, HEADER_TERMINATION_IE_1, HEADER_TERMINATION_IE_2,
+    PAYLOAD_TERMINATION_IE,
+};
+use super::{
+    BeaconFields, NestedIeRepr, NestedIes, TschSlotframeAndLink, TschSynchronization,
+    VendorSpecific, IE_HEADER_LEN, NESTED_IE_HEADER_LEN, TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID,
+    TSCH_SYNCHRONIZATION_IE_SUB_ID, VENDOR_SPECIFIC_HEADER_IE_ELEMENT_ID,
+    VENDOR_SPECIFIC_PAYLOAD_IE_GROUP_ID,
+};
+use crate::{FrameError, FrameErrorKind};
+
+const TIME_CORRECTION_IE_ELEMENT_ID: u8 = 0x1e;
+const MLME_IE_GROUP_ID: u8 = 0x1;
+const CHANNEL_HOPPING_IE_SUB_ID: u8 = 0x9;
+
+/// The number of payload octets per line of the hexdump.
+const HEXDUMP_LINE_LEN: usize = 16;
+
+const SECURITY_LEVELS: [&str; 8] = [
+    "None",
+    "MIC-32",
+    "MIC-64",
+    "MIC-128",
+    "ENC",
+    "ENC-MIC-32",
+    "ENC-MIC-64",
+    "ENC-MIC-128",
+];
+
+/// A field of a dissected frame, see [`dissect()`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldNode {
+    /// The name of the field.
+    pub name: String,
+    /// The decoded value of the field, if it is not only made up of
+    /// sub-fields.
+    pub value: Option<String>,
+    /// The octets of the MPDU covered by the field.
+    pub range: Range<usize>,
+    /// The sub-fields.
+    pub children: Vec<FieldNode>,
+}
+
+impl FieldNode {
+    fn new(name: impl Into<String>, range: Range<usize>) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+            range,
+            children: Vec::new(),
+        }
+    }
+
+    fn with_value(mut self, value: impl fmt::Display) -> Self {
+        self.value = Some(value.to_string());
+        self
+    }
+
+    /// Adds the lines of the given `Display` output as sub-fields, see
+    /// [`FieldNode::from_line()`].
+    fn with_display_fields(mut self, display: impl fmt::Display) -> Self {
+        let range = self.range.clone();
+        self.children.extend(
+            display
+                .to_string()
+                .lines()
+                .map(|line| Self::from_line(line, range.clone())),
+        );
+        self
+    }
+
+    /// Creates a field from the given `Display` output. The first line names
+    /// the field, the following lines are its sub-fields.
+    fn from_display(display: impl fmt::Display, range: Range<usize>) -> Self {
+        let text = display.to_string();
+        let mut lines = text.lines();
+        let mut node = Self::from_line(lines.next().unwrap_or_default(), range.clone());
+        node.children
+            .extend(lines.map(|line| Self::from_line(line, range.clone())));
+        node
+    }
+
+    /// Creates a field from a `name: value` line of a `Display` output.
+    fn from_line(line: &str, range: Range<usize>) -> Self {
+        let line = line.trim();
+        match line.split_once(": ") {
+            Some((name, value)) => Self::new(name, range).with_value(value),
+            None => Self::new(line, range),
+        }
+    }
+
+    /// Returns the first sub-field with the given name.
+    pub fn child(&self, name: &str) -> Option<&FieldNode> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Returns the sub-field at the given path of field names.
+    pub fn find(&self, path: &[&str]) -> Option<&FieldNode> {
+        path.iter().try_fold(self, |node, name| node.child(name))
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.name, indent = 2 * depth)?;
+        if let Some(value) = &self.value {
+            write!(f, ": {value}")?;
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for FieldNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// The structured breakdown of an MPDU produced by [`dissect()`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FrameTree {
+    /// The top-level fields in the order of the MPDU: frame control,
+    /// sequence number, addressing fields, auxiliary security header, header
+    /// and payload IEs, payload and MIC.
+    pub fields: Vec<FieldNode>,
+    /// The error that stopped the dissection, if the MPDU is malformed. The
+    /// fields preceding the malformed field are dissected nevertheless.
+    pub error: Option<FrameError>,
+}
+
+impl FrameTree {
+    /// Returns the field at the given path of field names, e.g.
+    /// `["Frame Control", "type"]`.
+    pub fn find(&self, path: &[&str]) -> Option<&FieldNode> {
+        let (name, path) = path.split_first()?;
+        self.fields
+            .iter()
+            .find(|field| field.name == *name)?
+            .find(path)
+    }
+
+    /// Returns the value of the field at the given path of field names.
+    pub fn value(&self, path: &[&str]) -> Option<&str> {
+        self.find(path)?.value.as_deref()
+    }
+}
+
+impl fmt::Display for FrameTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in &self.fields {
+            write!(f, "{field}")?;
+        }
+        if let Some(error) = self.error {
+            writeln!(f, "error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Dissects the given MPDU into a tree of all its fields, e.g. to print
+/// captured frames or to assert on the decoded structure of built frames.
+///
+/// The MPDU must not contain the FCS. Unlike [`FrameSummary::parse()`] the
+/// dissection does not fail on malformed frames. It stops at the first
+/// malformed field and records the error in [`FrameTree::error`], see
+/// [`FrameSummary::parse()`] for the possible errors.
+///
+/// Payload IEs of encrypted frames are part of the payload.
+///
+/// [`FrameSummary::parse()`]: super::FrameSummary::parse
+pub fn dissect(mpdu: &[u8]) -> FrameTree {
+    let mut fields = Vec::new();
+    let error = dissect_fields(mpdu, &mut fields).err();
+    FrameTree { fields, error }
+}
+
+fn dissect_fields(mpdu: &[u8], fields: &mut Vec<FieldNode>) -> Result<(), FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    let raw_frame_control = u16::from_le_bytes([mpdu[0], mpdu[1]]);
+    fields.push(
+        FieldNode::from_display(&frame_control, 0..FRAME_CONTROL_LEN)
+            .with_value(format_args!("{raw_frame_control:#06x}")),
+    );
+    let mut offset = FRAME_CONTROL_LEN;
+
+    if !frame_control.sequence_number_suppression() {
+        let seq_nr = field(mpdu, offset, 1)?[0];
+        fields.push(FieldNode::new("sequence number", offset..offset + 1).with_value(seq_nr));
+        offset += 1;
+    }
+
+    if let Some(addressing) = AddressingRepr::from_frame_control(frame_control.clone())? {
+        let length = addressing
+            .addressing_fields_length()
+            .map_err(|_| FrameErrorKind::InvalidAddressingCombination)?
+            as usize;
+        let addressing_fields = AddressingFields::new(field(mpdu, offset, length)?, addressing)
+            .map_err(|e| e.shifted_by(offset))?;
+        if length > 0 {
+            fields.push(FieldNode::from_display(
+                addressing_fields,
+                offset..offset + length,
+            ));
+        }
+        offset += length;
+    }
+
+    let mut mic_length = 0;
+    let mut encrypted = false;
+    if frame_control.security_enabled() {
+        let (node, level) = auxiliary_security_header(mpdu, offset)?;
+        offset = node.range.end;
+        fields.push(node);
+
+        mic_length = match level & 0b11 {
+            0 => 0,
+            1 => 4,
+            2 => 8,
+            _ => 16,
+        };
+        encrypted = level & 0b100 != 0;
+    }
+
+    let end = mpdu.len().saturating_sub(mic_length);
+    if offset > end {
+        return Err(FrameError::from(FrameErrorKind::BufferTooShort {
+            needed: offset + mic_length,
+            got: mpdu.len(),
+        })
+        .at(offset));
+    }
+
+    if frame_control.information_elements_present() {
+        let mut header_ies = FieldNode::new("Header IEs", offset..offset);
+        let result = dissect_header_ies(mpdu, &mut offset, end, &mut header_ies);
+        header_ies.range.end = offset;
+        fields.push(header_ies);
+        let payload_ies_present = result?;
+
+        if payload_ies_present && !encrypted {
+            let mut payload_ies = FieldNode::new("Payload IEs", offset..offset);
+            let result = dissect_payload_ies(mpdu, &mut offset, end, &mut payload_ies);
+            payload_ies.range.end = offset;
+            fields.push(payload_ies);
+            result?;
+        }
+    }
+
+    if offset < end {
+        let payload = &mpdu[offset..end];
+        let mut node = FieldNode::new("Payload", offset..end)
+            .with_value(format_args!("{} bytes", payload.len()));
+        // Enhanced beacons carry IEs instead of beacon fields.
+        let legacy_beacon = frame_control.frame_type() == FrameType::Beacon
+            && !frame_control.information_elements_present();
+        if legacy_beacon && !encrypted {
+            if let Ok(beacon_fields) = BeaconFields::new(payload) {
+                node.children.push(
+                    FieldNode::new("Beacon Fields", offset..end).with_display_fields(beacon_fields),
+                );
+            }
+        }
+        for (idx, line) in payload.chunks(HEXDUMP_LINE_LEN).enumerate() {
+            let start = offset + idx * HEXDUMP_LINE_LEN;
+            node.children.push(
+                FieldNode::new(
+                    format!("{:04x}", idx * HEXDUMP_LINE_LEN),
+                    start..start + line.len(),
+                )
+                .with_value(Hex(line)),
+            );
+        }
+        fields.push(node);
+    }
+
+    if mic_length > 0 {
+        fields.push(FieldNode::new("MIC", end..mpdu.len()).with_value(Hex(&mpdu[end..])));
+    }
+
+    Ok(())
+}
+
+/// Dissects the auxiliary security header at the given offset.
+///
+/// Returns the header and the security level.
+fn auxiliary_security_header(mpdu: &[u8], offset: usize) -> Result<(FieldNode, u8), FrameError> {
+    let security_control = field(mpdu, offset, 1)?[0];
+    let level = security_control & 0b111;
+    let key_id_mode = (security_control >> 3) & 0b11;
+    let key_id_length = match key_id_mode {
+        0 => 0,
+        1 => 1,
+        2 => 5,
+        _ => 9,
+    };
+    let frame_counter_suppressed = security_control & (1 << 5) != 0;
+    let frame_counter_length = if frame_counter_suppressed { 0 } else { 4 };
+    let length = 1 + frame_counter_length + key_id_length;
+    let header = field(mpdu, offset, length)?;
+
+    let mut node = FieldNode::new("Auxiliary Security Header", offset..offset + length);
+    let mut security_control_node = FieldNode::new("Security Control", offset..offset + 1)
+        .with_value(format_args!("{security_control:#04x}"));
+    security_control_node.children.extend([
+        FieldNode::new("security level", offset..offset + 1).with_value(format_args!(
+            "{level} ({})",
+            SECURITY_LEVELS[level as usize]
+        )),
+        FieldNode::new("key id mode", offset..offset + 1).with_value(key_id_mode),
+        FieldNode::new("frame counter suppression", offset..offset + 1)
+            .with_value(frame_counter_suppressed as usize),
+    ]);
+    node.children.push(security_control_node);
+
+    let mut field_offset = 1;
+    if !frame_counter_suppressed {
+        let frame_counter = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+        node.children.push(
+            FieldNode::new("frame counter", offset + 1..offset + 5).with_value(frame_counter),
+        );
+        field_offset += frame_counter_length;
+    }
+    if key_id_length > 1 {
+        let key_source = &header[field_offset..field_offset + key_id_length - 1];
+        let start = offset + field_offset;
+        node.children.push(
+            FieldNode::new("key source", start..start + key_source.len())
+                .with_value(Hex(key_source)),
+        );
+        field_offset += key_source.len();
+    }
+    if key_id_length > 0 {
+        let start = offset + field_offset;
+        node.children
+            .push(FieldNode::new("key index", start..start + 1).with_value(header[field_offset]));
+    }
+
+    Ok((node, level))
+}
+
+/// Dissects the header IEs starting at the given offset into the given node.
+///
+/// Returns whether payload IEs follow.
+fn dissect_header_ies(
+    mpdu: &[u8],
+    offset: &mut usize,
+    end: usize,
+    node: &mut FieldNode,
+) -> Result<bool, FrameError> {
+    while *offset < end {
+        let header = ie_header(mpdu, *offset, end)?;
+        let element_id = ((header >> 7) & 0xff) as u8;
+        let start = *offset;
+        *offset = next_ie(start, (header & 0x7f) as usize, end)?;
+        let range = start..*offset;
+        let content = &mpdu[start + IE_HEADER_LEN..*offset];
+        match element_id {
+            HEADER_TERMINATION_IE_1 => {
+                node.children
+                    .push(FieldNode::new("Header Termination 1", range));
+                return Ok(true);
+            }
+            HEADER_TERMINATION_IE_2 => {
+                node.children
+                    .push(FieldNode::new("Header Termination 2", range));
+                return Ok(false);
+            }
+            TIME_CORRECTION_IE_ELEMENT_ID if content.len() == 2 => {
+                let time_correction = u16::from_le_bytes([content[0], content[1]]);
+                // The time synchronization information is a 12-bit signed
+                // integer.
+                let time_sync_info = ((time_correction << 4) as i16) >> 4;
+                let nack = time_correction & (1 << 15) != 0;
+                let mut ie = FieldNode::new("Time Correction", range.clone());
+                ie.children.extend([
+                    FieldNode::new("time sync info", range.clone())
+                        .with_value(format_args!("{time_sync_info} us")),
+                    FieldNode::new("nack", range).with_value(nack as usize),
+                ]);
+                node.children.push(ie);
+            }
+            VENDOR_SPECIFIC_HEADER_IE_ELEMENT_ID => {
+                node.children.push(vendor_specific(content, range));
+            }
+            _ => node.children.push(
+                FieldNode::new(format!("Unknown (element ID {element_id:#04x})"), range)
+                    .with_value(Hex(content)),
+            ),
+        }
+    }
+    Ok(false)
+}
+
+/// Dissects the payload IEs starting at the given offset into the given node.
+fn dissect_payload_ies(
+    mpdu: &[u8],
+    offset: &mut usize,
+    end: usize,
+    node: &mut FieldNode,
+) -> Result<(), FrameError> {
+    while *offset < end {
+        let header = ie_header(mpdu, *offset, end)?;
+        let group_id = ((header >> 11) & 0xf) as u8;
+        let start = *offset;
+        *offset = next_ie(start, (header & 0x7ff) as usize, end)?;
+        let range = start..*offset;
+        let content = &mpdu[start + IE_HEADER_LEN..*offset];
+        match group_id {
+            PAYLOAD_TERMINATION_IE => {
+                node.children
+                    .push(FieldNode::new("Payload Termination", range));
+                break;
+            }
+            MLME_IE_GROUP_ID => {
+                let mut mlme = FieldNode::new("MLME", range);
+                let result = dissect_nested_ies(content, start + IE_HEADER_LEN, &mut mlme);
+                node.children.push(mlme);
+                result?;
+            }
+            VENDOR_SPECIFIC_PAYLOAD_IE_GROUP_ID => {
+                node.children.push(vendor_specific(content, range));
+            }
+            _ => node.children.push(
+                FieldNode::new(format!("Unknown (group ID {group_id:#03x})"), range)
+                    .with_value(Hex(content)),
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Dissects the nested IEs of an MLME IE with the given content starting at
+/// the given offset into the given node.
+fn dissect_nested_ies(
+    content: &[u8],
+    offset: usize,
+    node: &mut FieldNode,
+) -> Result<(), FrameError> {
+    let mut start = offset;
+    for nested_ie in NestedIes::new(content) {
+        let nested_ie = nested_ie.map_err(|e| e.shifted_by(offset))?;
+        let range = start..start + nested_ie.total_length();
+        start = range.end;
+        let malformed = || FrameError::from(FrameErrorKind::MalformedIe).at(range.start);
+        let content_range = range.start + NESTED_IE_HEADER_LEN..range.end;
+
+        let ie = match (nested_ie.is_long_format(), nested_ie.sub_id()) {
+            (false, TSCH_SYNCHRONIZATION_IE_SUB_ID) => {
+                let sync =
+                    TschSynchronization::new(nested_ie.content()).map_err(|_| malformed())?;
+                let mut ie = FieldNode::new("TSCH Synchronization", range.clone());
+                ie.children.extend([
+                    FieldNode::new("asn", content_range.start..content_range.end - 1)
+                        .with_value(sync.asn()),
+                    FieldNode::new("join metric", content_range.end - 1..content_range.end)
+                        .with_value(sync.join_metric()),
+                ]);
+                ie
+            }
+            (false, TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID) => {
+                let slotframe_and_link =
+                    TschSlotframeAndLink::new(nested_ie.content()).map_err(|_| malformed())?;
+                slotframe_and_link_ie(&slotframe_and_link, range.clone())
+            }
+            (true, CHANNEL_HOPPING_IE_SUB_ID) => {
+                let content = nested_ie.content();
+                let mut ie = FieldNode::new("Channel Hopping", range.clone());
+                let Some((&hopping_sequence_id, rest)) = content.split_first() else {
+                    return Err(FrameError::from(FrameErrorKind::TruncatedIe).at(range.start));
+                };
+                ie.children.push(
+                    FieldNode::new(
+                        "hopping sequence id",
+                        content_range.start..content_range.start + 1,
+                    )
+                    .with_value(hopping_sequence_id),
+                );
+                if !rest.is_empty() {
+                    ie.children.push(
+                        FieldNode::new("content", content_range.start + 1..content_range.end)
+                            .with_value(Hex(rest)),
+                    );
+                }
+                ie
+            }
+            _ => {
+                let repr = NestedIeRepr::parse_or_unknown(nested_ie)
+                    .map_err(|e| e.shifted_by(range.start))?;
+                FieldNode::from_display(repr, range.clone())
+            }
+        };
+        node.children.push(ie);
+    }
+    Ok(())
+}
+
+fn slotframe_and_link_ie(
+    slotframe_and_link: &TschSlotframeAndLink<&[u8]>,
+    range: Range<usize>,
+) -> FieldNode {
+    let mut ie = FieldNode::new("TSCH Slotframe and Link", range.clone());
+    ie.children.push(
+        FieldNode::new("number of slotframes", range.clone())
+            .with_value(slotframe_and_link.number_of_slotframes()),
+    );
+    for descriptor in slotframe_and_link.slotframe_descriptors() {
+        let mut slotframe = FieldNode::new("Slotframe", range.clone());
+        slotframe.children.extend([
+            FieldNode::new("handle", range.clone()).with_value(descriptor.handle()),
+            FieldNode::new("size", range.clone()).with_value(descriptor.size()),
+        ]);
+        for link in descriptor.links() {
+            let mut link_node = FieldNode::new("Link", range.clone());
+            link_node.children.extend([
+                FieldNode::new("timeslot", range.clone()).with_value(link.timeslot()),
+                FieldNode::new("channel offset", range.clone()).with_value(link.channel_offset()),
+                FieldNode::new("link options", range.clone()).with_value(link.link_options()),
+            ]);
+            slotframe.children.push(link_node);
+        }
+        ie.children.push(slotframe);
+    }
+    ie
+}
+
+fn vendor_specific(content: &[u8], range: Range<usize>) -> FieldNode {
+    match VendorSpecific::new(content) {
+        Ok(vendor_specific) => {
+            FieldNode::new("Vendor Specific", range).with_display_fields(vendor_specific)
+        }
+        Err(_) => FieldNode::new("Vendor Specific", range).with_value(Hex(content)),
+    }
+}
+
+/// Formats octets as space-separated hex digits.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, byte) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enhanced_beacon() {
+        let mpdu = [
+            0x40, 0xeb, // frame control
+            0xcd, 0xab, // dst PAN ID
+            0xff, 0xff, // dst address
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // src address
+            0x00, 0x3f, // header termination IE 1
+            0x1a, 0x88, // MLME payload IE
+            0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, // TSCH synchronization IE
+            0x01, 0x1c, 0x00, // TSCH timeslot IE
+            0x01, 0xc8, 0x00, // channel hopping IE
+            0x0a, 0x1b, 0x01, 0x00, 0x65, 0x00, 0x01, // TSCH slotframe and link IE
+            0x00, 0x00, 0x00, 0x00, 0x0f, // link
+        ];
+
+        let tree = dissect(&mpdu);
+        assert_eq!(tree.error, None);
+        assert_eq!(tree.value(&["Frame Control"]), Some("0xeb40"));
+        assert_eq!(tree.value(&["Frame Control", "type"]), Some("Beacon"));
+        assert_eq!(
+            tree.value(&["Frame Control", "information elements present"]),
+            Some("1")
+        );
+        assert_eq!(tree.find(&["sequence number"]), None);
+        assert_eq!(tree.find(&["Addressing Fields"]).unwrap().range, 2..14);
+        assert_eq!(
+            tree.value(&["Addressing Fields", "dst pan id"]),
+            Some("abcd")
+        );
+
+        let header_ies = tree.find(&["Header IEs"]).unwrap();
+        assert_eq!(header_ies.range, 14..16);
+        assert_eq!(header_ies.children[0].name, "Header Termination 1");
+
+        let mlme = tree.find(&["Payload IEs", "MLME"]).unwrap();
+        assert_eq!(mlme.range, 16..mpdu.len());
+        let names: Vec<_> = mlme.children.iter().map(|ie| ie.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "TSCH Synchronization",
+                "TSCH Timeslot",
+                "Channel Hopping",
+                "TSCH Slotframe and Link"
+            ]
+        );
+        assert_eq!(
+            mlme.find(&["TSCH Synchronization", "asn"]).unwrap().value,
+            Some("14".into())
+        );
+        assert_eq!(
+            mlme.find(&["TSCH Synchronization", "join metric"])
+                .unwrap()
+                .range,
+            25..26
+        );
+        assert_eq!(
+            mlme.find(&["Channel Hopping", "hopping sequence id"])
+                .unwrap()
+                .value,
+            Some("0".into())
+        );
+        let link = mlme
+            .find(&["TSCH Slotframe and Link", "Slotframe", "Link"])
+            .unwrap();
+        assert_eq!(link.child("timeslot").unwrap().value, Some("0".into()));
+        assert_eq!(
+            link.child("link options").unwrap().value,
+            Some("Tx | Rx | Shared | TimeKeeping".into())
+        );
+        assert_eq!(tree.find(&["Payload"]), None);
+    }
+
+    #[test]
+    fn secured_data_frame() {
+        let mpdu = [
+            0x69, 0xaa, // frame control
+            0x2a, // sequence number
+            0xcd, 0xab, // dst PAN ID
+            0x02, 0x00, // dst address
+            0x01, 0x00, // src address
+            0x0d, // security control: ENC-MIC-32, key ID mode 1
+            0x05, 0x00, 0x00, 0x00, // frame counter
+            0x03, // key index
+            0x00, 0x3f, // header termination IE 1
+            0x0f, 0x88, 0x11, 0x22, // encrypted payload IEs and payload
+            0xa1, 0xa2, 0xa3, 0xa4, // MIC
+        ];
+
+        let tree = dissect(&mpdu);
+        assert_eq!(tree.error, None);
+        assert_eq!(tree.value(&["sequence number"]), Some("42"));
+        assert_eq!(
+            tree.value(&[
+                "Auxiliary Security Header",
+                "Security Control",
+                "security level"
+            ]),
+            Some("5 (ENC-MIC-32)")
+        );
+        assert_eq!(
+            tree.value(&["Auxiliary Security Header", "frame counter"]),
+            Some("5")
+        );
+        assert_eq!(
+            tree.find(&["Auxiliary Security Header", "key index"])
+                .unwrap()
+                .range,
+            14..15
+        );
+        assert_eq!(
+            tree.find(&["Header IEs"]).unwrap().children[0].name,
+            "Header Termination 1"
+        );
+        // Payload IEs of encrypted frames are part of the payload.
+        assert_eq!(tree.find(&["Payload IEs"]), None);
+        assert_eq!(tree.value(&["Payload"]), Some("4 bytes"));
+        assert_eq!(tree.value(&["Payload", "0000"]), Some("0f 88 11 22"));
+        assert_eq!(tree.value(&["MIC"]), Some("a1 a2 a3 a4"));
+    }
+
+    #[test]
+    fn enhanced_ack() {
+        let mpdu = [
+            0x02, 0x2a, // frame control
+            0x07, // sequence number
+            0xcd, 0xab, // dst PAN ID
+            0x01, 0x00, // dst address
+            0x02, 0x0f, 0xfe, 0x8f, // time correction IE: -2us, NACK
+        ];
+
+        let tree = dissect(&mpdu);
+        assert_eq!(tree.error, None);
+        assert_eq!(tree.value(&["Frame Control", "type"]), Some("Ack"));
+        let time_correction = tree.find(&["Header IEs", "Time Correction"]).unwrap();
+        assert_eq!(time_correction.range, 7..11);
+        assert_eq!(
+            time_correction.child("time sync info").unwrap().value,
+            Some("-2 us".into())
+        );
+        assert_eq!(
+            time_correction.child("nack").unwrap().value,
+            Some("1".into())
+        );
+
+        assert_eq!(
+            tree.to_string(),
+            "Frame Control: 0x2a02\n\
+             \x20 type: Ack\n\
+             \x20 security enabled: 0\n\
+             \x20 frame pending: 0\n\
+             \x20 ack request: 0\n\
+             \x20 pan id compression: 0\n\
+             \x20 sequence number suppression: 0\n\
+             \x20 information elements present: 1\n\
+             \x20 dst addressing mode: Short\n\
+             \x20 src addressing mode: Absent\n\
+             \x20 frame version: Ieee802154\n\
+             sequence number: 7\n\
+             Addressing Fields\n\
+             \x20 dst pan id: abcd\n\
+             \x20 dst address: 01:00\n\
+             \x20 src address: absent\n\
+             Header IEs\n\
+             \x20 Time Correction\n\
+             \x20   time sync info: -2 us\n\
+             \x20   nack: 1\n"
+        );
+    }
+
+    #[test]
+    fn malformed_frame() {
+        // A header IE exceeding the frame.
+        let tree = dissect(&[0x01, 0x23, 0x02, 0x09, 0x00]);
+        assert_eq!(tree.value(&["Frame Control", "type"]), Some("Data"));
+        assert_eq!(tree.find(&["sequence number"]), None);
+        assert_eq!(tree.find(&["Addressing Fields"]), None);
+        assert!(tree.find(&["Header IEs"]).unwrap().children.is_empty());
+        let error = tree.error.unwrap();
+        assert_eq!(
+            error.kind(),
+            FrameErrorKind::IeExceedsFrame {
+                length: 4,
+                remaining: 3
+            }
+        );
+        assert_eq!(error.offset(), Some(2));
+        assert!(tree
+            .to_string()
+            .ends_with("error: IE length (4) exceeds remaining frame length (3) at offset 2\n"));
+    }
+}
@@ -155,8 +155,10 @@ impl MpduFieldRanges<MpduWithAddressing> {
     pub(crate) const fn without_security(&self) -> MpduFieldRanges<MpduWithSecurity> {
         #[cfg(feature = "security")]
         return self.next_state(self.offset_aux_sec_hdr.unwrap().get(), None);
-        #[cfg(not(feature = "security"))]
-        return self.next_state(self.offset_addressing.get(), None);
+        #[cfg(all(not(feature = "security"), feature = "ies"))]
+        return self.next_state(self.offset_ies.unwrap().get(), None);
+        #[cfg(not(any(feature = "security", feature = "ies")))]
+        return self.next_state(self.offset_frame_payload.unwrap().get() as u8, None);
     }
 
     const fn next_state(
@@ -173,7 +175,7 @@ impl MpduFieldRanges<MpduWithAddressing> {
             offset_frame_control: self.offset_frame_control,
             offset_addressing: self.offset_addressing,
             #[cfg(feature = "security")]
-            offset_aux_sec_hdr: as_nz_u8(next_offset),
+            offset_aux_sec_hdr: self.offset_aux_sec_hdr,
             #[cfg(feature = "security")]
             length_mic: _length_mic,
             #[cfg(feature = "ies")]
@@ -224,11 +226,15 @@ impl MpduFieldRanges<MpduWithSecurity> {
         ies: IeListRepr,
         mpdu_length_wo_fcs: u16,
     ) -> Result<MpduFieldRanges<MpduWithAllFields>> {
-        let mpdu_less_ies_and_payload_length = self.last_offset();
-        if mpdu_less_ies_and_payload_length > mpdu_length_wo_fcs {
+        let offset_ies = self.last_offset();
+        let offset_mpdu_end = match self.offset_mpdu_end(mpdu_length_wo_fcs) {
+            Ok(offset_mpdu_end) => offset_mpdu_end,
+            Err(e) => return Err(e),
+        };
+        if offset_ies > offset_mpdu_end {
             return Err(Error);
         }
-        let mpdu_ies_and_payload_length = mpdu_length_wo_fcs - mpdu_less_ies_and_payload_length;
+        let mpdu_ies_and_payload_length = offset_mpdu_end - offset_ies;
         let (ies_length, frame_payload_length) =
             match ies.ies_and_frame_payload_length(mpdu_ies_and_payload_length) {
                 Ok(frame_payload_length) => frame_payload_length,
@@ -260,22 +266,37 @@ impl MpduFieldRanges<MpduWithSecurity> {
         &self,
         mpdu_length_wo_fcs: u16,
     ) -> Result<MpduFieldRanges<MpduWithAllFields>> {
-        let mpdu_less_payload_length = self.last_offset();
-        if mpdu_less_payload_length > mpdu_length_wo_fcs {
+        let offset_frame_payload = self.last_offset();
+        let offset_mpdu_end = match self.offset_mpdu_end(mpdu_length_wo_fcs) {
+            Ok(offset_mpdu_end) => offset_mpdu_end,
+            Err(e) => return Err(e),
+        };
+        if offset_frame_payload > offset_mpdu_end {
             return Err(Error);
         }
-        let frame_payload_length = mpdu_length_wo_fcs - mpdu_less_payload_length;
+        let frame_payload_length = offset_mpdu_end - offset_frame_payload;
         Ok(self.next_state::<Config>(0, frame_payload_length))
     }
 
-    const fn last_offset(&self) -> u16 {
+    /// Converts the given MPDU length into the buffer offset of the first
+    /// byte after the MPDU (w/o FCS).
+    ///
+    /// Note: All other offsets are relative to the start of the buffer, not
+    ///       to the start of the MPDU.
+    const fn offset_mpdu_end(&self, mpdu_length_wo_fcs: u16) -> Result<u16> {
+        match (self.offset_frame_control as u16).checked_add(mpdu_length_wo_fcs) {
+            Some(offset_mpdu_end) => Ok(offset_mpdu_end),
+            None => Err(Error),
+        }
+    }
+
+    /// The buffer offset of the first byte after the MAC header fields
+    /// preceding the IEs (or the frame payload if IEs are disabled).
+    pub(crate) const fn last_offset(&self) -> u16 {
         #[cfg(feature = "ies")]
-        let last_offset = self.offset_ies.unwrap().get();
-        #[cfg(all(feature = "security", not(feature = "ies")))]
-        let last_offset = self.offset_aux_sec_hdr.unwrap().get();
-        #[cfg(not(any(feature = "security", feature = "ies")))]
-        let last_offset = self.offset_addressing.get();
-        last_offset as u16
+        return self.offset_ies.unwrap().get() as u16;
+        #[cfg(not(feature = "ies"))]
+        return self.offset_frame_payload.unwrap().get();
     }
 
     const fn next_state<Config: DriverConfig>(
@@ -409,7 +430,7 @@ impl MpduFieldRanges<MpduWithAllFields> {
     /// frame does not have a payload.
     pub(crate) const fn offset_frame_payload_end(&self) -> u16 {
         #[cfg(feature = "security")]
-        return self.offset_fcs.unwrap().get() - self.length_mic.unwrap().get() as u16;
+        return self.offset_fcs.unwrap().get() - self.mic_length();
         #[cfg(not(feature = "security"))]
         return self.offset_fcs.unwrap().get();
     }
@@ -430,7 +451,7 @@ impl MpduFieldRanges<MpduWithAllFields> {
         #[cfg(feature = "security")]
         return {
             let next_offset = self.offset_fcs.unwrap().get() as usize;
-            let offset_mic = next_offset - self.length_mic.unwrap().get() as usize;
+            let offset_mic = next_offset - self.mic_length() as usize;
             if offset_mic == next_offset {
                 None
             } else {
@@ -441,6 +462,15 @@ impl MpduFieldRanges<MpduWithAllFields> {
         return None;
     }
 
+    /// The length of the MIC, zero if the frame is not secured.
+    #[cfg(feature = "security")]
+    const fn mic_length(&self) -> u16 {
+        match self.length_mic {
+            Some(length_mic) => length_mic.get() as u16,
+            None => 0,
+        }
+    }
+
     /// The buffer range containing the FCS.
     pub(crate) const fn range_fcs(&self) -> Option<Range<usize>> {
         let offset_fcs = self.offset_fcs.unwrap().get() as usize;
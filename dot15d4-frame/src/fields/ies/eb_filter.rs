@@ -0,0 +1,307 @@
+//! Enhanced Beacon Filter IE field access (IEEE 802.15.4-2024, section
+//! 10.3.5.21).
+//!
+//! ```notrust
+//! +---------------------------+--------------+----------------+-----------------------+
+//! | EB Filter descriptor (1B) | LQ (0/1B)    | Percent (0/1B) | PIB attr. IDs (0-3B)  |
+//! +---------------------------+--------------+----------------+-----------------------+
+//! ```
+//!
+//! The EB filter descriptor is structured as follows:
+//!
+//! ```notrust
+//! +-----------------+------------+-----------------+------------------------+----------+
+//! | Permit joining  | Include LQ | Include percent | Num attr. ID list len  | Reserved |
+//! | on (bit 0)      | (bit 1)    | (bit 2)         | (bits 3-4)             | (5-7)    |
+//! +-----------------+------------+-----------------+------------------------+----------+
+//! ```
+
+use dot15d4_util::{Error, Result};
+
+/// The nested IE sub-ID of the Enhanced Beacon Filter IE (short format).
+pub const EB_FILTER_IE_SUB_ID: u8 = 0x1e;
+
+/// The max length of the PIB attribute ID list in octets.
+pub const EB_FILTER_MAX_ATTRIBUTE_ID_LIST_LEN: usize = 3;
+
+const PERMIT_JOINING_ON: u8 = 0b0000_0001;
+const INCLUDE_LINK_QUALITY: u8 = 0b0000_0010;
+const INCLUDE_PERCENT: u8 = 0b0000_0100;
+const ATTRIBUTE_ID_LIST_LEN_SHIFT: u8 = 3;
+const ATTRIBUTE_ID_LIST_LEN_MASK: u8 = 0b11;
+
+/// Calculates the content length of an Enhanced Beacon Filter IE with the given
+/// optional fields.
+pub const fn eb_filter_content_length(
+    include_link_quality: bool,
+    include_percent: bool,
+    attribute_id_list_len: u8,
+) -> u16 {
+    debug_assert!(attribute_id_list_len as usize <= EB_FILTER_MAX_ATTRIBUTE_ID_LIST_LEN);
+    1 + include_link_quality as u16 + include_percent as u16 + attribute_id_list_len as u16
+}
+
+/// A reader/writer for the content of an Enhanced Beacon Filter IE.
+///
+/// Devices use this IE in Enhanced Beacon Request commands to solicit
+/// Enhanced Beacons only from coordinators matching the filter.
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnhancedBeaconFilter<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> EnhancedBeaconFilter<Bytes> {
+    /// Create a new [`EnhancedBeaconFilter`] reader/writer from a given
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the fields
+    /// announced in the EB filter descriptor.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let eb_filter = Self::new_unchecked(bytes);
+
+        if !eb_filter.check_len() {
+            return Err(Error);
+        }
+
+        Ok(eb_filter)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the IE content.
+    fn check_len(&self) -> bool {
+        let bytes = self.bytes.as_ref();
+        !bytes.is_empty() && bytes.len() >= self.length() as usize
+    }
+
+    /// Create a new [`EnhancedBeaconFilter`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    fn descriptor(&self) -> u8 {
+        self.bytes.as_ref()[0]
+    }
+
+    /// The length of the IE content as announced by the EB filter descriptor.
+    pub fn length(&self) -> u16 {
+        eb_filter_content_length(
+            self.descriptor() & INCLUDE_LINK_QUALITY != 0,
+            self.descriptor() & INCLUDE_PERCENT != 0,
+            self.attribute_id_list_len(),
+        )
+    }
+
+    /// Returns `true` if only devices that currently permit joining shall
+    /// respond.
+    pub fn permit_joining_on(&self) -> bool {
+        self.descriptor() & PERMIT_JOINING_ON != 0
+    }
+
+    /// Returns the minimal link quality a device must have measured on the
+    /// EB request to respond, if present.
+    pub fn link_quality(&self) -> Option<u8> {
+        if self.descriptor() & INCLUDE_LINK_QUALITY == 0 {
+            return None;
+        }
+
+        Some(self.bytes.as_ref()[1])
+    }
+
+    /// Returns the probability (0-100) with which a device shall respond, if
+    /// present.
+    pub fn percent_filter(&self) -> Option<u8> {
+        if self.descriptor() & INCLUDE_PERCENT == 0 {
+            return None;
+        }
+
+        let offset = 1 + self.link_quality().is_some() as usize;
+        Some(self.bytes.as_ref()[offset])
+    }
+
+    fn attribute_id_list_len(&self) -> u8 {
+        (self.descriptor() >> ATTRIBUTE_ID_LIST_LEN_SHIFT) & ATTRIBUTE_ID_LIST_LEN_MASK
+    }
+
+    /// Returns the PIB attribute ID list, i.e. a bitmap of PIB attributes that
+    /// responding devices are requested to include in their Enhanced Beacon.
+    pub fn attribute_id_list(&self) -> &[u8] {
+        let offset =
+            1 + self.link_quality().is_some() as usize + self.percent_filter().is_some() as usize;
+        &self.bytes.as_ref()[offset..offset + self.attribute_id_list_len() as usize]
+    }
+
+    /// Decides whether a device shall respond to an Enhanced Beacon Request
+    /// containing this filter.
+    ///
+    /// * `permit_joining` - Whether the device currently permits joining.
+    /// * `link_quality` - The link quality measured on the EB request.
+    /// * `random_percent` - A random value in the range 0..100 drawn by the
+    ///   device for this request.
+    pub fn should_respond(
+        &self,
+        permit_joining: bool,
+        link_quality: u8,
+        random_percent: u8,
+    ) -> bool {
+        debug_assert!(random_percent < 100);
+
+        if self.permit_joining_on() && !permit_joining {
+            return false;
+        }
+
+        if let Some(min_link_quality) = self.link_quality() {
+            if link_quality < min_link_quality {
+                return false;
+            }
+        }
+
+        if let Some(percent) = self.percent_filter() {
+            if random_percent >= percent {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> EnhancedBeaconFilter<Bytes> {
+    /// Writes the complete IE content.
+    ///
+    /// The buffer must have been sized with
+    /// [`eb_filter_content_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the attribute ID list is too long or the buffer
+    /// does not match the content length.
+    pub fn emit(
+        &mut self,
+        permit_joining_on: bool,
+        link_quality: Option<u8>,
+        percent_filter: Option<u8>,
+        attribute_id_list: &[u8],
+    ) -> Result<()> {
+        if attribute_id_list.len() > EB_FILTER_MAX_ATTRIBUTE_ID_LIST_LEN
+            || percent_filter.is_some_and(|percent| percent > 100)
+        {
+            return Err(Error);
+        }
+
+        let content_length = eb_filter_content_length(
+            link_quality.is_some(),
+            percent_filter.is_some(),
+            attribute_id_list.len() as u8,
+        ) as usize;
+
+        let bytes = self.bytes.as_mut();
+        if bytes.len() != content_length {
+            return Err(Error);
+        }
+
+        let mut descriptor = (attribute_id_list.len() as u8) << ATTRIBUTE_ID_LIST_LEN_SHIFT;
+        if permit_joining_on {
+            descriptor |= PERMIT_JOINING_ON;
+        }
+
+        let mut offset = 1;
+        if let Some(link_quality) = link_quality {
+            descriptor |= INCLUDE_LINK_QUALITY;
+            bytes[offset] = link_quality;
+            offset += 1;
+        }
+        if let Some(percent_filter) = percent_filter {
+            descriptor |= INCLUDE_PERCENT;
+            bytes[offset] = percent_filter;
+            offset += 1;
+        }
+        bytes[0] = descriptor;
+        bytes[offset..].copy_from_slice(attribute_id_list);
+
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for EnhancedBeaconFilter<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "permit joining on: {}",
+            self.permit_joining_on() as usize
+        )?;
+        if let Some(link_quality) = self.link_quality() {
+            writeln!(f, "{:indent$}link quality: {}", "", link_quality)?;
+        }
+        if let Some(percent_filter) = self.percent_filter() {
+            writeln!(f, "{:indent$}percent filter: {}", "", percent_filter)?;
+        }
+        writeln!(
+            f,
+            "{:indent$}attribute ids: {:?}",
+            "",
+            self.attribute_id_list()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let content = [0b0001_0111, 0x80, 50, 0b0000_0011, 0b1000_0000];
+        let eb_filter = EnhancedBeaconFilter::new(&content).unwrap();
+        assert!(eb_filter.permit_joining_on());
+        assert_eq!(eb_filter.link_quality(), Some(0x80));
+        assert_eq!(eb_filter.percent_filter(), Some(50));
+        assert_eq!(eb_filter.attribute_id_list(), &[0b0000_0011, 0b1000_0000]);
+        assert_eq!(eb_filter.length(), 5);
+
+        let content = [0b0000_0100, 50];
+        let eb_filter = EnhancedBeaconFilter::new(&content).unwrap();
+        assert!(!eb_filter.permit_joining_on());
+        assert_eq!(eb_filter.link_quality(), None);
+        assert_eq!(eb_filter.percent_filter(), Some(50));
+        assert!(eb_filter.attribute_id_list().is_empty());
+
+        // Truncated attribute ID list.
+        let content = [0b0001_0000, 0x01];
+        assert!(EnhancedBeaconFilter::new(&content).is_err());
+        assert!(EnhancedBeaconFilter::new(&[]).is_err());
+    }
+
+    #[test]
+    fn emit() {
+        let mut content = [0; 4];
+        let mut eb_filter = EnhancedBeaconFilter::new_unchecked(&mut content);
+        eb_filter
+            .emit(true, Some(0x80), None, &[0x01, 0x02])
+            .unwrap();
+        assert_eq!(content, [0b0001_0011, 0x80, 0x01, 0x02]);
+
+        let mut content = [0; 2];
+        let mut eb_filter = EnhancedBeaconFilter::new_unchecked(&mut content);
+        assert!(eb_filter.emit(false, None, Some(101), &[]).is_err());
+        assert!(eb_filter.emit(false, None, None, &[]).is_err());
+        assert!(eb_filter.emit(false, None, None, &[0; 4]).is_err());
+    }
+
+    #[test]
+    fn should_respond() {
+        let content = [0b0000_0111, 0x80, 50];
+        let eb_filter = EnhancedBeaconFilter::new(&content).unwrap();
+        assert!(eb_filter.should_respond(true, 0x80, 49));
+        assert!(!eb_filter.should_respond(false, 0x80, 49));
+        assert!(!eb_filter.should_respond(true, 0x7f, 49));
+        assert!(!eb_filter.should_respond(true, 0x80, 50));
+    }
+}
@@ -0,0 +1,387 @@
+//! Header and payload IE lists (IEEE 802.15.4-2024, sections 7.4.2 and
+//! 7.4.3).
+//!
+//! ```notrust
+//! Header IE:
+//! +----------------+--------------------+-------------+
+//! | Length (0-6)   | Element ID (7-14)  | Type=0 (15) |
+//! +----------------+--------------------+-------------+
+//!
+//! Payload IE:
+//! +----------------+--------------------+-------------+
+//! | Length (0-10)  | Group ID (11-14)   | Type=1 (15) |
+//! +----------------+--------------------+-------------+
+//! ```
+//!
+//! [`HeaderIes`] and [`PayloadIes`] stream over the IE lists of an MPDU
+//! without interpreting the IE content. Unknown IEs are yielded like known
+//! ones, so that a malformed vendor specific IE only affects the reader of its
+//! content but not the other IEs of the frame.
+//!
+//! Iteration stops cleanly at a termination IE, which is not yielded. The
+//! bytes following it, i.e. the payload IEs or the frame payload, are
+//! available from `remaining()`. Iteration ends after an IE whose length
+//! exceeds the list as the position of subsequent IEs cannot be determined.
+//! Errors carry the offset of the malformed IE relative to the start of the
+//! IE list.
+
+use crate::{FrameError, FrameErrorKind};
+
+use super::{
+    decode_vendor_specific, NestedIes, VendorIeCodec, VendorIeKind,
+    VENDOR_SPECIFIC_HEADER_IE_ELEMENT_ID, VENDOR_SPECIFIC_PAYLOAD_IE_GROUP_ID,
+};
+
+/// The length of header and payload IE headers in octets.
+pub const IE_HEADER_LEN: usize = 2;
+
+/// The element ID of the Header Termination 1 IE, i.e. payload IEs follow.
+pub const HEADER_TERMINATION_1_IE_ELEMENT_ID: u8 = 0x7e;
+
+/// The element ID of the Header Termination 2 IE, i.e. the frame payload
+/// follows.
+pub const HEADER_TERMINATION_2_IE_ELEMENT_ID: u8 = 0x7f;
+
+/// The group ID of the MLME payload IE which carries nested IEs.
+pub const MLME_PAYLOAD_IE_GROUP_ID: u8 = 0x1;
+
+/// The group ID of the Payload Termination IE.
+pub const PAYLOAD_TERMINATION_IE_GROUP_ID: u8 = 0xf;
+
+const TYPE_PAYLOAD: u16 = 1 << 15;
+const HEADER_LENGTH_MASK: u16 = 0x7f;
+const HEADER_ELEMENT_ID_SHIFT: u16 = 7;
+const HEADER_ELEMENT_ID_MASK: u16 = 0xff;
+const PAYLOAD_LENGTH_MASK: u16 = 0x7ff;
+const PAYLOAD_GROUP_ID_SHIFT: u16 = 11;
+const PAYLOAD_GROUP_ID_MASK: u16 = 0xf;
+
+/// A header IE of a [`HeaderIes`] list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderIe<'ie> {
+    element_id: u8,
+    content: &'ie [u8],
+}
+
+impl<'ie> HeaderIe<'ie> {
+    /// Returns the element ID of the IE.
+    pub const fn element_id(&self) -> u8 {
+        self.element_id
+    }
+
+    /// Returns the IE content.
+    pub const fn content(&self) -> &'ie [u8] {
+        self.content
+    }
+
+    /// Decodes the content of a Vendor Specific header IE with the given
+    /// codec.
+    ///
+    /// Returns [`None`] for other header IEs and for Vendor Specific header
+    /// IEs carrying a different OUI than the one handled by the codec.
+    pub fn decode_vendor<Codec: VendorIeCodec>(
+        &self,
+    ) -> Option<dot15d4_util::Result<Codec::Ie<'ie>>> {
+        if self.element_id != VENDOR_SPECIFIC_HEADER_IE_ELEMENT_ID {
+            return None;
+        }
+
+        decode_vendor_specific::<Codec>(VendorIeKind::Header, self.content)
+    }
+}
+
+/// A payload IE of a [`PayloadIes`] list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadIe<'ie> {
+    group_id: u8,
+    content: &'ie [u8],
+}
+
+impl<'ie> PayloadIe<'ie> {
+    /// Returns the group ID of the IE.
+    pub const fn group_id(&self) -> u8 {
+        self.group_id
+    }
+
+    /// Returns the IE content.
+    pub const fn content(&self) -> &'ie [u8] {
+        self.content
+    }
+
+    /// Returns an iterator over the nested IEs of an MLME payload IE, `None`
+    /// for payload IEs of other groups.
+    pub const fn nested_ies(&self) -> Option<NestedIes<'ie>> {
+        if self.group_id == MLME_PAYLOAD_IE_GROUP_ID {
+            Some(NestedIes::new(self.content))
+        } else {
+            None
+        }
+    }
+
+    /// Decodes the content of a Vendor Specific payload IE with the given
+    /// codec.
+    ///
+    /// Returns [`None`] for other payload IEs and for Vendor Specific payload
+    /// IEs carrying a different OUI than the one handled by the codec.
+    pub fn decode_vendor<Codec: VendorIeCodec>(
+        &self,
+    ) -> Option<dot15d4_util::Result<Codec::Ie<'ie>>> {
+        if self.group_id != VENDOR_SPECIFIC_PAYLOAD_IE_GROUP_ID {
+            return None;
+        }
+
+        decode_vendor_specific::<Codec>(VendorIeKind::Payload, self.content)
+    }
+}
+
+/// Splits the IE at the start of the given list into its header and content.
+fn split_ie(bytes: &[u8], payload: bool) -> Result<(u16, &[u8], &[u8]), FrameError> {
+    let Some(header) = bytes.get(..IE_HEADER_LEN) else {
+        return Err(FrameErrorKind::TruncatedIe.into());
+    };
+    let header = u16::from_le_bytes([header[0], header[1]]);
+    // The type bit is checked first, the length of an IE of the wrong type
+    // is meaningless.
+    if (header & TYPE_PAYLOAD != 0) != payload {
+        return Err(FrameErrorKind::MalformedIe.into());
+    }
+    let length_mask = if payload {
+        PAYLOAD_LENGTH_MASK
+    } else {
+        HEADER_LENGTH_MASK
+    };
+    let length = IE_HEADER_LEN + (header & length_mask) as usize;
+    if length > bytes.len() {
+        return Err(FrameErrorKind::IeExceedsFrame {
+            length,
+            remaining: bytes.len(),
+        }
+        .into());
+    }
+    let (ie, remaining) = bytes.split_at(length);
+    Ok((header, &ie[IE_HEADER_LEN..], remaining))
+}
+
+/// An iterator over the header IEs of an MPDU, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct HeaderIes<'ie> {
+    bytes: &'ie [u8],
+    offset: usize,
+    payload_ies_follow: bool,
+    terminated: bool,
+}
+
+impl<'ie> HeaderIes<'ie> {
+    /// Create a new iterator over the header IEs at the start of the given
+    /// buffer, i.e. the MPDU following the auxiliary security header up to
+    /// the MIC.
+    pub const fn new(bytes: &'ie [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            payload_ies_follow: false,
+            terminated: false,
+        }
+    }
+
+    /// Returns `true` once the Header Termination 1 IE was reached, i.e. the
+    /// remaining bytes start with payload IEs.
+    pub const fn payload_ies_follow(&self) -> bool {
+        self.payload_ies_follow
+    }
+
+    /// Returns the bytes following the IEs iterated so far, i.e. the payload
+    /// IEs or the frame payload once the iteration ended at a termination IE.
+    pub const fn remaining(&self) -> &'ie [u8] {
+        self.bytes
+    }
+
+    /// Returns the offset of the remaining bytes relative to the start of the
+    /// IE list.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'ie> Iterator for HeaderIes<'ie> {
+    type Item = Result<HeaderIe<'ie>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.terminated || self.bytes.is_empty() {
+            return None;
+        }
+
+        let (header, content, remaining) = match split_ie(self.bytes, false) {
+            Ok(ie) => ie,
+            Err(e) => {
+                self.bytes = &[];
+                return Some(Err(e.at(self.offset)));
+            }
+        };
+        self.offset += self.bytes.len() - remaining.len();
+        self.bytes = remaining;
+
+        match ((header >> HEADER_ELEMENT_ID_SHIFT) & HEADER_ELEMENT_ID_MASK) as u8 {
+            HEADER_TERMINATION_1_IE_ELEMENT_ID => {
+                self.payload_ies_follow = true;
+                self.terminated = true;
+                None
+            }
+            HEADER_TERMINATION_2_IE_ELEMENT_ID => {
+                self.terminated = true;
+                None
+            }
+            element_id => Some(Ok(HeaderIe {
+                element_id,
+                content,
+            })),
+        }
+    }
+}
+
+/// An iterator over the payload IEs of an MPDU, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct PayloadIes<'ie> {
+    bytes: &'ie [u8],
+    offset: usize,
+    terminated: bool,
+}
+
+impl<'ie> PayloadIes<'ie> {
+    /// Create a new iterator over the payload IEs at the start of the given
+    /// buffer, e.g. [`HeaderIes::remaining()`] after the Header Termination 1
+    /// IE.
+    pub const fn new(bytes: &'ie [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            terminated: false,
+        }
+    }
+
+    /// Returns the bytes following the IEs iterated so far, i.e. the frame
+    /// payload once the iteration ended at the Payload Termination IE.
+    pub const fn remaining(&self) -> &'ie [u8] {
+        self.bytes
+    }
+
+    /// Returns the offset of the remaining bytes relative to the start of the
+    /// IE list.
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'ie> Iterator for PayloadIes<'ie> {
+    type Item = Result<PayloadIe<'ie>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.terminated || self.bytes.is_empty() {
+            return None;
+        }
+
+        let (header, content, remaining) = match split_ie(self.bytes, true) {
+            Ok(ie) => ie,
+            Err(e) => {
+                self.bytes = &[];
+                return Some(Err(e.at(self.offset)));
+            }
+        };
+        self.offset += self.bytes.len() - remaining.len();
+        self.bytes = remaining;
+
+        match ((header >> PAYLOAD_GROUP_ID_SHIFT) & PAYLOAD_GROUP_ID_MASK) as u8 {
+            PAYLOAD_TERMINATION_IE_GROUP_ID => {
+                self.terminated = true;
+                None
+            }
+            group_id => Some(Ok(PayloadIe { group_id, content })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_ies() {
+        let bytes = [
+            0x02, 0x0f, 0x14, 0x00, // Time Correction
+            0x01, 0x00, 0xaa, // Vendor Specific with truncated OUI
+            0x00, 0x3f, // Header Termination 1
+            0x00, 0x88, // MLME
+        ];
+        let mut header_ies = HeaderIes::new(&bytes);
+        let time_correction = header_ies.next().unwrap().unwrap();
+        assert_eq!(time_correction.element_id(), 0x1e);
+        assert_eq!(time_correction.content(), &[0x14, 0x00]);
+        let vendor = header_ies.next().unwrap().unwrap();
+        assert_eq!(vendor.element_id(), 0x00);
+        assert_eq!(vendor.content(), &[0xaa]);
+        assert_eq!(header_ies.next(), None);
+        assert_eq!(header_ies.next(), None);
+        assert!(header_ies.payload_ies_follow());
+        assert_eq!(header_ies.remaining(), &[0x00, 0x88]);
+        assert_eq!(header_ies.offset(), 9);
+
+        // Header Termination 2 is followed by the frame payload.
+        let bytes = [0x80, 0x3f, 0x11, 0x22];
+        let mut header_ies = HeaderIes::new(&bytes);
+        assert_eq!(header_ies.next(), None);
+        assert!(!header_ies.payload_ies_follow());
+        assert_eq!(header_ies.remaining(), &[0x11, 0x22]);
+    }
+
+    #[test]
+    fn malformed_header_ies() {
+        let bytes = [0x02, 0x0f, 0x14, 0x00, 0x05, 0x00, 0xaa];
+        let mut header_ies = HeaderIes::new(&bytes);
+        assert!(matches!(header_ies.next(), Some(Ok(_))));
+        assert_eq!(
+            header_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::IeExceedsFrame {
+                length: 7,
+                remaining: 3
+            })
+            .at(4)))
+        );
+        assert_eq!(header_ies.next(), None);
+
+        let mut header_ies = HeaderIes::new(&[0x00]);
+        assert_eq!(
+            header_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::TruncatedIe).at(0)))
+        );
+        assert_eq!(header_ies.next(), None);
+    }
+
+    #[test]
+    fn payload_ies() {
+        let bytes = [
+            0x03, 0x90, 0x01, 0x02, 0x03, // Vendor Specific
+            0x03, 0x88, 0x01, 0x1c, 0x00, // MLME with TSCH Timeslot
+            0x00, 0xf8, // Payload Termination
+            0x11,
+        ];
+        let mut payload_ies = PayloadIes::new(&bytes);
+        let vendor = payload_ies.next().unwrap().unwrap();
+        assert_eq!(vendor.group_id(), 0x2);
+        assert!(vendor.nested_ies().is_none());
+        let mlme = payload_ies.next().unwrap().unwrap();
+        assert_eq!(mlme.group_id(), MLME_PAYLOAD_IE_GROUP_ID);
+        let nested_ie = mlme.nested_ies().unwrap().next().unwrap().unwrap();
+        assert_eq!(nested_ie.sub_id(), 0x1c);
+        assert_eq!(payload_ies.next(), None);
+        assert_eq!(payload_ies.next(), None);
+        assert_eq!(payload_ies.remaining(), &[0x11]);
+
+        // A header IE in the payload IE list.
+        let mut payload_ies = PayloadIes::new(&[0x00, 0x3f]);
+        assert_eq!(
+            payload_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::MalformedIe).at(0)))
+        );
+        assert_eq!(payload_ies.next(), None);
+    }
+}
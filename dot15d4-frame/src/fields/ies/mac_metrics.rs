@@ -0,0 +1,312 @@
+//! MAC Metrics IE and All MAC Metrics IE field access (IEEE 802.15.4-2024).
+//!
+//! The MAC Metrics IE carries a single metric:
+//!
+//! ```notrust
+//! +----------------+-------------------+
+//! | Metric ID (1B) | Metric count (4B) |
+//! +----------------+-------------------+
+//! ```
+//!
+//! The All MAC Metrics IE carries the counts of all metrics in the order of
+//! their metric IDs:
+//!
+//! ```notrust
+//! +----------------------+-----+----------------------+
+//! | Metric count 0 (4B)  | ... | Metric count 9 (4B)  |
+//! +----------------------+-----+----------------------+
+//! ```
+
+use dot15d4_util::{Error, Result};
+
+/// The nested IE sub-ID of the MAC Metrics IE (short format).
+pub const MAC_METRICS_IE_SUB_ID: u8 = 0x1f;
+
+/// The nested IE sub-ID of the All MAC Metrics IE (short format).
+pub const ALL_MAC_METRICS_IE_SUB_ID: u8 = 0x20;
+
+/// The content length of the MAC Metrics IE in octets.
+pub const MAC_METRICS_CONTENT_LEN: u16 = 5;
+
+/// The number of metrics carried by the All MAC Metrics IE.
+pub const NUM_MAC_METRICS: usize = 10;
+
+/// The content length of the All MAC Metrics IE in octets.
+pub const ALL_MAC_METRICS_CONTENT_LEN: u16 = NUM_MAC_METRICS as u16 * METRIC_COUNT_LEN as u16;
+
+const METRIC_COUNT_LEN: usize = 4;
+
+/// MAC metrics as identified by the metric ID.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MacMetric {
+    /// macCounterOctets: Number of octets sent and received.
+    CounterOctets = 0x00,
+    /// macRetryCount: Number of frames that were successfully sent after one
+    /// retry.
+    RetryCount = 0x01,
+    /// macMultipleRetryCount: Number of frames that were successfully sent
+    /// after more than one retry.
+    MultipleRetryCount = 0x02,
+    /// macTxFailCount: Number of frames that could not be sent after the max
+    /// number of retries.
+    TxFailCount = 0x03,
+    /// macTxSuccessCount: Number of frames that were successfully sent.
+    TxSuccessCount = 0x04,
+    /// macFcsErrorCount: Number of received frames that were discarded due
+    /// to an FCS error.
+    FcsErrorCount = 0x05,
+    /// macSecurityFailureCount: Number of received frames that were
+    /// discarded due to a security error.
+    SecurityFailureCount = 0x06,
+    /// macDuplicateFrameCount: Number of received frames that were discarded
+    /// as duplicates.
+    DuplicateFrameCount = 0x07,
+    /// macRxSuccessCount: Number of frames that were successfully received.
+    RxSuccessCount = 0x08,
+    /// macNackCount: Number of NACKs received.
+    NackCount = 0x09,
+    /// Unknown metric ID.
+    Unknown,
+}
+
+impl From<u8> for MacMetric {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::CounterOctets,
+            0x01 => Self::RetryCount,
+            0x02 => Self::MultipleRetryCount,
+            0x03 => Self::TxFailCount,
+            0x04 => Self::TxSuccessCount,
+            0x05 => Self::FcsErrorCount,
+            0x06 => Self::SecurityFailureCount,
+            0x07 => Self::DuplicateFrameCount,
+            0x08 => Self::RxSuccessCount,
+            0x09 => Self::NackCount,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// A reader/writer for the content of a MAC Metrics IE.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MacMetrics<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> MacMetrics<Bytes> {
+    /// Create a new [`MacMetrics`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let mac_metrics = Self::new_unchecked(bytes);
+
+        if !mac_metrics.check_len() {
+            return Err(Error);
+        }
+
+        Ok(mac_metrics)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the IE content.
+    fn check_len(&self) -> bool {
+        self.bytes.as_ref().len() >= MAC_METRICS_CONTENT_LEN as usize
+    }
+
+    /// Create a new [`MacMetrics`] reader/writer from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the metric carried by this IE.
+    pub fn metric(&self) -> MacMetric {
+        MacMetric::from(self.bytes.as_ref()[0])
+    }
+
+    /// Returns the count of the metric.
+    pub fn count(&self) -> u32 {
+        let b = &self.bytes.as_ref()[1..MAC_METRICS_CONTENT_LEN as usize];
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> MacMetrics<Bytes> {
+    /// Set the metric carried by this IE.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metric is unknown.
+    pub fn set_metric(&mut self, metric: MacMetric) -> Result<()> {
+        if metric == MacMetric::Unknown {
+            return Err(Error);
+        }
+
+        self.bytes.as_mut()[0] = metric as u8;
+        Ok(())
+    }
+
+    /// Set the count of the metric.
+    pub fn set_count(&mut self, count: u32) {
+        self.bytes.as_mut()[1..MAC_METRICS_CONTENT_LEN as usize]
+            .copy_from_slice(&count.to_le_bytes());
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for MacMetrics<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{:?}: {}", self.metric(), self.count())
+    }
+}
+
+/// A reader/writer for the content of an All MAC Metrics IE.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AllMacMetrics<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> AllMacMetrics<Bytes> {
+    /// Create a new [`AllMacMetrics`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let all_mac_metrics = Self::new_unchecked(bytes);
+
+        if !all_mac_metrics.check_len() {
+            return Err(Error);
+        }
+
+        Ok(all_mac_metrics)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the IE content.
+    fn check_len(&self) -> bool {
+        self.bytes.as_ref().len() >= ALL_MAC_METRICS_CONTENT_LEN as usize
+    }
+
+    /// Create a new [`AllMacMetrics`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the count of the given metric.
+    ///
+    /// Returns [`None`] for unknown metrics.
+    pub fn count(&self, metric: MacMetric) -> Option<u32> {
+        let offset = Self::offset(metric)?;
+        let b = &self.bytes.as_ref()[offset..offset + METRIC_COUNT_LEN];
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Returns an iterator over all metrics and their counts.
+    pub fn iter(&self) -> impl Iterator<Item = (MacMetric, u32)> + '_ {
+        (0..NUM_MAC_METRICS as u8).map(|metric_id| {
+            let metric = MacMetric::from(metric_id);
+            // Safety: All metric IDs in this range are known.
+            (metric, self.count(metric).unwrap())
+        })
+    }
+
+    fn offset(metric: MacMetric) -> Option<usize> {
+        if metric == MacMetric::Unknown {
+            return None;
+        }
+
+        Some(metric as usize * METRIC_COUNT_LEN)
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> AllMacMetrics<Bytes> {
+    /// Set the count of the given metric.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metric is unknown.
+    pub fn set_count(&mut self, metric: MacMetric, count: u32) -> Result<()> {
+        let offset = Self::offset(metric).ok_or(Error)?;
+        self.bytes.as_mut()[offset..offset + METRIC_COUNT_LEN]
+            .copy_from_slice(&count.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for AllMacMetrics<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        for (idx, (metric, count)) in self.iter().enumerate() {
+            if idx == 0 {
+                writeln!(f, "{metric:?}: {count}")?;
+            } else {
+                writeln!(f, "{:indent$}{metric:?}: {count}", "")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mac_metrics() {
+        let content = [0x01, 0x78, 0x56, 0x34, 0x12];
+        let mac_metrics = MacMetrics::new(&content).unwrap();
+        assert_eq!(mac_metrics.metric(), MacMetric::RetryCount);
+        assert_eq!(mac_metrics.count(), 0x1234_5678);
+        assert!(MacMetrics::new(&content[..4]).is_err());
+
+        let mut content = [0; MAC_METRICS_CONTENT_LEN as usize];
+        let mut mac_metrics = MacMetrics::new(&mut content).unwrap();
+        mac_metrics.set_metric(MacMetric::NackCount).unwrap();
+        mac_metrics.set_count(42);
+        assert!(mac_metrics.set_metric(MacMetric::Unknown).is_err());
+        assert_eq!(content, [0x09, 42, 0, 0, 0]);
+    }
+
+    #[test]
+    fn all_mac_metrics() {
+        let mut content = [0; ALL_MAC_METRICS_CONTENT_LEN as usize];
+        let mut all_mac_metrics = AllMacMetrics::new(&mut content).unwrap();
+        all_mac_metrics
+            .set_count(MacMetric::CounterOctets, 0x0102_0304)
+            .unwrap();
+        all_mac_metrics.set_count(MacMetric::NackCount, 7).unwrap();
+        assert!(all_mac_metrics.set_count(MacMetric::Unknown, 1).is_err());
+        assert_eq!(content[..4], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(content[36..], [7, 0, 0, 0]);
+
+        let all_mac_metrics = AllMacMetrics::new(&content).unwrap();
+        assert_eq!(
+            all_mac_metrics.count(MacMetric::CounterOctets),
+            Some(0x0102_0304)
+        );
+        assert_eq!(all_mac_metrics.count(MacMetric::RetryCount), Some(0));
+        assert_eq!(all_mac_metrics.count(MacMetric::NackCount), Some(7));
+        assert_eq!(all_mac_metrics.count(MacMetric::Unknown), None);
+        assert_eq!(all_mac_metrics.iter().count(), NUM_MAC_METRICS);
+        assert_eq!(
+            all_mac_metrics.iter().last(),
+            Some((MacMetric::NackCount, 7))
+        );
+
+        assert!(AllMacMetrics::new(&content[..39]).is_err());
+    }
+}
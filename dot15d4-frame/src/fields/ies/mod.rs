@@ -1,3 +1,15 @@
+mod eb_filter;
+mod list;
+mod mac_metrics;
+mod nested;
+mod sun;
 mod tsch;
+mod vendor;
 
+pub use eb_filter::*;
+pub use list::*;
+pub use mac_metrics::*;
+pub use nested::*;
+pub use sun::*;
 pub use tsch::*;
+pub use vendor::*;
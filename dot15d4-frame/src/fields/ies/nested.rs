@@ -0,0 +1,973 @@
+//! Nested IE field access (IEEE 802.15.4-2024, section 7.4.4.1).
+//!
+//! Nested IEs are carried inside the MLME payload IE. They come in a short and
+//! a long format:
+//!
+//! ```notrust
+//! Short format:
+//! +----------------+---------------+-------------+
+//! | Length (0-7)   | Sub-ID (8-14) | Type=0 (15) |
+//! +----------------+---------------+-------------+
+//!
+//! Long format:
+//! +----------------+---------------+-------------+
+//! | Length (0-10)  | Sub-ID (11-14)| Type=1 (15) |
+//! +----------------+---------------+-------------+
+//! ```
+
+use crate::{FrameError, FrameErrorKind};
+
+use super::{
+    decode_vendor_specific, AllMacMetrics, EnhancedBeaconFilter, MacMetric, MacMetrics,
+    ModeSwitchParameter, SunFskGenericPhy, TschTimeslot, VendorIeCodec, VendorIeKind,
+    VendorSpecific, ALL_MAC_METRICS_CONTENT_LEN, ALL_MAC_METRICS_IE_SUB_ID, EB_FILTER_IE_SUB_ID,
+    MAC_METRICS_CONTENT_LEN, MAC_METRICS_IE_SUB_ID, MODE_SWITCH_PARAMETER_CONTENT_LEN,
+    MODE_SWITCH_PARAMETER_IE_SUB_ID, SUN_FSK_GENERIC_PHY_CONTENT_LEN,
+    SUN_FSK_GENERIC_PHY_IE_SUB_ID, TSCH_TIMESLOT_IE_SUB_ID, VENDOR_SPECIFIC_NESTED_IE_SUB_ID,
+};
+
+/// The length of the nested IE header in octets.
+pub const NESTED_IE_HEADER_LEN: usize = 2;
+
+const TYPE_LONG: u16 = 1 << 15;
+const SHORT_LENGTH_MASK: u16 = 0xff;
+const SHORT_SUB_ID_SHIFT: u16 = 8;
+const SHORT_SUB_ID_MASK: u16 = 0x7f;
+const LONG_LENGTH_MASK: u16 = 0x7ff;
+const LONG_SUB_ID_SHIFT: u16 = 11;
+const LONG_SUB_ID_MASK: u16 = 0xf;
+
+/// A reader for a nested IE including its header.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NestedIe<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> NestedIe<Bytes> {
+    /// Create a new [`NestedIe`] reader from a given buffer.
+    ///
+    /// The buffer may extend beyond the IE, e.g. when it contains a list of
+    /// nested IEs.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::TruncatedIe`] if the buffer is too short to contain the
+    ///   IE header,
+    /// - [`FrameErrorKind::IeExceedsFrame`] if the buffer is too short to contain
+    ///   the content length announced in the header.
+    pub fn new(bytes: Bytes) -> Result<Self, FrameError> {
+        if bytes.as_ref().len() < NESTED_IE_HEADER_LEN {
+            return Err(FrameErrorKind::TruncatedIe.into());
+        }
+
+        let nested_ie = Self::new_unchecked(bytes);
+        nested_ie.check_len()?;
+
+        Ok(nested_ie)
+    }
+
+    /// Create a new [`NestedIe`] reader from a given buffer and additionally
+    /// check that the IE fits into the given MTU.
+    ///
+    /// Long format IEs may announce up to 2046 content octets which exceeds
+    /// the MTU of most PHYs. Such IEs can be rejected early even if the
+    /// buffer happens to be large enough.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::IeExceedsMtu`] if the IE length exceeds the MTU
+    /// and any of the errors returned by [`NestedIe::new()`].
+    pub fn new_with_mtu(bytes: Bytes, mtu: u16) -> Result<Self, FrameError> {
+        if bytes.as_ref().len() < NESTED_IE_HEADER_LEN {
+            return Err(FrameErrorKind::TruncatedIe.into());
+        }
+
+        let nested_ie = Self::new_unchecked(bytes);
+        nested_ie.check_mtu(mtu)?;
+        nested_ie.check_len()?;
+
+        Ok(nested_ie)
+    }
+
+    /// Checks that the buffer is long enough to contain the IE content.
+    fn check_len(&self) -> Result<(), FrameError> {
+        let length = self.total_length();
+        let remaining = self.bytes.as_ref().len();
+        if length > remaining {
+            return Err(FrameErrorKind::IeExceedsFrame { length, remaining }.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that the IE fits into the given MTU.
+    fn check_mtu(&self, mtu: u16) -> Result<(), FrameError> {
+        let length = self.total_length();
+        if length > mtu as usize {
+            return Err(FrameErrorKind::IeExceedsMtu { length, mtu }.into());
+        }
+        Ok(())
+    }
+
+    /// Create a new [`NestedIe`] reader from a given buffer without length
+    /// checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    fn header(&self) -> u16 {
+        let b = &self.bytes.as_ref()[..NESTED_IE_HEADER_LEN];
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    /// Returns `true` if the IE uses the long format.
+    pub fn is_long_format(&self) -> bool {
+        self.header() & TYPE_LONG != 0
+    }
+
+    /// Returns the sub-ID of the IE.
+    pub fn sub_id(&self) -> u8 {
+        let header = self.header();
+        if self.is_long_format() {
+            ((header >> LONG_SUB_ID_SHIFT) & LONG_SUB_ID_MASK) as u8
+        } else {
+            ((header >> SHORT_SUB_ID_SHIFT) & SHORT_SUB_ID_MASK) as u8
+        }
+    }
+
+    /// Returns the content length of the IE as announced in its header.
+    pub fn length(&self) -> u16 {
+        let header = self.header();
+        if self.is_long_format() {
+            header & LONG_LENGTH_MASK
+        } else {
+            header & SHORT_LENGTH_MASK
+        }
+    }
+
+    /// Returns the length of the IE including its header.
+    pub fn total_length(&self) -> usize {
+        NESTED_IE_HEADER_LEN + self.length() as usize
+    }
+
+    /// Returns the IE content.
+    pub fn content(&self) -> &[u8] {
+        &self.bytes.as_ref()[NESTED_IE_HEADER_LEN..self.total_length()]
+    }
+}
+
+impl<'ie> NestedIe<&'ie [u8]> {
+    /// Returns the IE content with the lifetime of the underlying buffer.
+    pub fn into_content(self) -> &'ie [u8] {
+        let total_length = self.total_length();
+        &self.bytes[NESTED_IE_HEADER_LEN..total_length]
+    }
+
+    /// Decodes the content of a Vendor Specific nested IE with the given
+    /// codec.
+    ///
+    /// Returns [`None`] for other nested IEs and for Vendor Specific nested
+    /// IEs carrying a different OUI than the one handled by the codec.
+    pub fn decode_vendor<Codec: VendorIeCodec>(
+        self,
+    ) -> Option<dot15d4_util::Result<Codec::Ie<'ie>>> {
+        if !self.is_long_format() || self.sub_id() != VENDOR_SPECIFIC_NESTED_IE_SUB_ID {
+            return None;
+        }
+
+        decode_vendor_specific::<Codec>(VendorIeKind::Nested, self.into_content())
+    }
+}
+
+/// An iterator over a list of nested IEs, e.g. the content of an MLME payload
+/// IE.
+///
+/// Iteration ends after an IE whose length exceeds the list as the position of
+/// subsequent IEs cannot be determined. IEs that fit into the list but exceed
+/// the MTU are reported and skipped. Errors carry the offset of the malformed
+/// IE relative to the start of the IE list.
+#[derive(Debug, Clone)]
+pub struct NestedIes<'ie> {
+    bytes: &'ie [u8],
+    offset: usize,
+    mtu: Option<u16>,
+}
+
+impl<'ie> NestedIes<'ie> {
+    /// Create a new iterator over the nested IEs contained in the given
+    /// buffer.
+    pub const fn new(bytes: &'ie [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            mtu: None,
+        }
+    }
+
+    /// Additionally reject IEs that do not fit into the given MTU, see
+    /// [`NestedIe::new_with_mtu()`].
+    pub const fn with_mtu(self, mtu: u16) -> Self {
+        Self {
+            mtu: Some(mtu),
+            ..self
+        }
+    }
+}
+
+impl<'ie> Iterator for NestedIes<'ie> {
+    type Item = Result<NestedIe<&'ie [u8]>, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+
+        let nested_ie = match self.mtu {
+            Some(mtu) => NestedIe::new_with_mtu(self.bytes, mtu),
+            None => NestedIe::new(self.bytes),
+        };
+
+        match nested_ie {
+            Ok(nested_ie) => {
+                let total_length = nested_ie.total_length();
+                let (ie_bytes, remaining_bytes) = self.bytes.split_at(total_length);
+                self.bytes = remaining_bytes;
+                self.offset += total_length;
+                Some(Ok(NestedIe::new_unchecked(ie_bytes)))
+            }
+            Err(e) => {
+                let skipped = match e.kind() {
+                    FrameErrorKind::IeExceedsMtu { length, .. } if length <= self.bytes.len() => {
+                        length
+                    }
+                    _ => self.bytes.len(),
+                };
+                let offset = self.offset;
+                self.bytes = &self.bytes[skipped..];
+                self.offset += skipped;
+                Some(Err(e.shifted_by(offset)))
+            }
+        }
+    }
+}
+
+/// Typed representation of a nested IE's content.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NestedIeRepr<'ie> {
+    TschTimeslot(TschTimeslot<&'ie [u8]>),
+    EnhancedBeaconFilter(EnhancedBeaconFilter<&'ie [u8]>),
+    MacMetrics(MacMetrics<&'ie [u8]>),
+    AllMacMetrics(AllMacMetrics<&'ie [u8]>),
+    VendorSpecific(VendorSpecific<&'ie [u8]>),
+    SunFskGenericPhy(SunFskGenericPhy<&'ie [u8]>),
+    ModeSwitchParameter(ModeSwitchParameter<&'ie [u8]>),
+    /// An IE without typed representation. Its content is passed on
+    /// uninterpreted.
+    Unknown {
+        sub_id: u8,
+        is_long_format: bool,
+        content: &'ie [u8],
+    },
+}
+
+impl<'ie> NestedIeRepr<'ie> {
+    /// Parses the given nested IE into its typed representation.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::UnknownIe`] if no typed representation is available for
+    ///   the IE,
+    /// - [`FrameErrorKind::TruncatedIe`] if the IE content is shorter than required,
+    /// - [`FrameErrorKind::MalformedIe`] if the IE content is longer than expected
+    ///   or contains a value that is not allowed.
+    pub fn parse(nested_ie: NestedIe<&'ie [u8]>) -> Result<Self, FrameError> {
+        let sub_id = nested_ie.sub_id();
+        let is_long_format = nested_ie.is_long_format();
+        let content = nested_ie.into_content();
+
+        let repr = match (is_long_format, sub_id) {
+            (false, TSCH_TIMESLOT_IE_SUB_ID) => {
+                // The TSCH Timeslot IE has several fixed length variants.
+                let timeslot =
+                    TschTimeslot::new(content).map_err(|_| FrameErrorKind::MalformedIe)?;
+                NestedIeRepr::TschTimeslot(timeslot)
+            }
+            (false, EB_FILTER_IE_SUB_ID) => {
+                let eb_filter =
+                    EnhancedBeaconFilter::new(content).map_err(|_| FrameErrorKind::TruncatedIe)?;
+                if eb_filter.length() as usize != content.len()
+                    || eb_filter
+                        .percent_filter()
+                        .is_some_and(|percent| percent > 100)
+                {
+                    return Err(FrameErrorKind::MalformedIe.into());
+                }
+                NestedIeRepr::EnhancedBeaconFilter(eb_filter)
+            }
+            (false, MAC_METRICS_IE_SUB_ID) => {
+                check_fixed_len(content, MAC_METRICS_CONTENT_LEN)?;
+                let mac_metrics = MacMetrics::new_unchecked(content);
+                if mac_metrics.metric() == MacMetric::Unknown {
+                    return Err(FrameErrorKind::MalformedIe.into());
+                }
+                NestedIeRepr::MacMetrics(mac_metrics)
+            }
+            (false, ALL_MAC_METRICS_IE_SUB_ID) => {
+                check_fixed_len(content, ALL_MAC_METRICS_CONTENT_LEN)?;
+                NestedIeRepr::AllMacMetrics(AllMacMetrics::new_unchecked(content))
+            }
+            (false, SUN_FSK_GENERIC_PHY_IE_SUB_ID) => {
+                check_fixed_len(content, SUN_FSK_GENERIC_PHY_CONTENT_LEN)?;
+                let generic_phy = SunFskGenericPhy::new_unchecked(content);
+                if generic_phy.has_reserved_bits()
+                    || generic_phy.symbol_rate() == 0
+                    || generic_phy.num_channels() == 0
+                {
+                    return Err(FrameErrorKind::MalformedIe.into());
+                }
+                NestedIeRepr::SunFskGenericPhy(generic_phy)
+            }
+            (false, MODE_SWITCH_PARAMETER_IE_SUB_ID) => {
+                check_fixed_len(content, MODE_SWITCH_PARAMETER_CONTENT_LEN)?;
+                let mode_switch = ModeSwitchParameter::new_unchecked(content);
+                if mode_switch.has_reserved_bits() {
+                    return Err(FrameErrorKind::MalformedIe.into());
+                }
+                NestedIeRepr::ModeSwitchParameter(mode_switch)
+            }
+            (true, VENDOR_SPECIFIC_NESTED_IE_SUB_ID) => NestedIeRepr::VendorSpecific(
+                VendorSpecific::new(content).map_err(|_| FrameErrorKind::TruncatedIe)?,
+            ),
+            _ => {
+                return Err(FrameErrorKind::UnknownIe {
+                    sub_id,
+                    is_long_format,
+                }
+                .into())
+            }
+        };
+
+        Ok(repr)
+    }
+
+    /// Like [`NestedIeRepr::parse()`] but returns [`NestedIeRepr::Unknown`]
+    /// rather than an error for IEs without typed representation so that
+    /// iteration can continue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content of a known IE is malformed.
+    pub fn parse_or_unknown(nested_ie: NestedIe<&'ie [u8]>) -> Result<Self, FrameError> {
+        let content = nested_ie.bytes;
+        match Self::parse(NestedIe::new_unchecked(content)) {
+            Err(e) => match e.kind() {
+                FrameErrorKind::UnknownIe {
+                    sub_id,
+                    is_long_format,
+                } => Ok(NestedIeRepr::Unknown {
+                    sub_id,
+                    is_long_format,
+                    content: NestedIe::new_unchecked(content).into_content(),
+                }),
+                _ => Err(e),
+            },
+            result => result,
+        }
+    }
+}
+
+impl core::fmt::Display for NestedIeRepr<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0) + 2;
+        match self {
+            NestedIeRepr::TschTimeslot(timeslot) => {
+                writeln!(f, "TSCH Timeslot")?;
+                write!(f, "{:indent$}{:indent$}", "", timeslot.timeslot_timings())
+            }
+            NestedIeRepr::EnhancedBeaconFilter(eb_filter) => {
+                writeln!(f, "Enhanced Beacon Filter")?;
+                write!(f, "{:indent$}{:indent$}", "", eb_filter)
+            }
+            NestedIeRepr::MacMetrics(mac_metrics) => {
+                writeln!(f, "MAC Metrics")?;
+                write!(f, "{:indent$}{:indent$}", "", mac_metrics)
+            }
+            NestedIeRepr::AllMacMetrics(all_mac_metrics) => {
+                writeln!(f, "All MAC Metrics")?;
+                write!(f, "{:indent$}{:indent$}", "", all_mac_metrics)
+            }
+            NestedIeRepr::VendorSpecific(vendor_specific) => {
+                writeln!(f, "Vendor Specific")?;
+                write!(f, "{:indent$}{:indent$}", "", vendor_specific)
+            }
+            NestedIeRepr::SunFskGenericPhy(generic_phy) => {
+                writeln!(f, "SUN FSK Generic PHY")?;
+                write!(f, "{:indent$}{:indent$}", "", generic_phy)
+            }
+            NestedIeRepr::ModeSwitchParameter(mode_switch) => {
+                writeln!(f, "Mode Switch Parameter")?;
+                write!(f, "{:indent$}{:indent$}", "", mode_switch)
+            }
+            NestedIeRepr::Unknown {
+                sub_id,
+                is_long_format,
+                content,
+            } => {
+                let format = if *is_long_format { "long" } else { "short" };
+                writeln!(f, "Unknown ({format} format, sub-ID {sub_id:#04x})")?;
+                writeln!(f, "{:indent$}content: {:x?}", "", content)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for NestedIeRepr<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            NestedIeRepr::TschTimeslot(timeslot) => {
+                defmt::write!(f, "TschTimeslot({})", timeslot.timeslot_timings())
+            }
+            NestedIeRepr::EnhancedBeaconFilter(eb_filter) => defmt::write!(
+                f,
+                "EnhancedBeaconFilter {{ permit joining on: {=bool}, link quality: {}, \
+                 percent filter: {}, attribute ids: {=[u8]} }}",
+                eb_filter.permit_joining_on(),
+                eb_filter.link_quality(),
+                eb_filter.percent_filter(),
+                eb_filter.attribute_id_list(),
+            ),
+            NestedIeRepr::MacMetrics(mac_metrics) => defmt::write!(
+                f,
+                "MacMetrics({}: {=u32})",
+                mac_metrics.metric(),
+                mac_metrics.count()
+            ),
+            NestedIeRepr::AllMacMetrics(all_mac_metrics) => {
+                defmt::write!(f, "AllMacMetrics {{");
+                for (metric, count) in all_mac_metrics.iter() {
+                    defmt::write!(f, " {}: {=u32}", metric, count);
+                }
+                defmt::write!(f, " }}");
+            }
+            NestedIeRepr::VendorSpecific(vendor_specific) => defmt::write!(
+                f,
+                "VendorSpecific {{ oui: {=[u8]:x}, content: {=[u8]:x} }}",
+                &vendor_specific.oui()[..],
+                vendor_specific.content(),
+            ),
+            NestedIeRepr::SunFskGenericPhy(generic_phy) => defmt::write!(
+                f,
+                "SunFskGenericPhy {{ phy id: {=u8}, channel 0: {=u32} kHz, spacing: {=u16} kHz, \
+                 channels: {=u16}, symbol rate: {=u16} ksymbol/s, modulation: {}, fec: {=bool}, \
+                 modulation index: {=u8} }}",
+                generic_phy.phy_id(),
+                generic_phy.center_frequency_0(),
+                generic_phy.channel_spacing(),
+                generic_phy.num_channels(),
+                generic_phy.symbol_rate(),
+                generic_phy.modulation(),
+                generic_phy.fec(),
+                generic_phy.modulation_index(),
+            ),
+            NestedIeRepr::ModeSwitchParameter(mode_switch) => defmt::write!(
+                f,
+                "ModeSwitchParameter {{ entry: {=u8}, phy id: {=u8}, settling delay: {=u16} us }}",
+                mode_switch.entry(),
+                mode_switch.phy_id(),
+                mode_switch.settling_delay(),
+            ),
+            NestedIeRepr::Unknown {
+                sub_id,
+                is_long_format,
+                content,
+            } => defmt::write!(
+                f,
+                "Unknown {{ sub id: {=u8:#04x}, long format: {=bool}, content: {=[u8]:x} }}",
+                *sub_id,
+                *is_long_format,
+                content,
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NestedIeRepr<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStructVariant;
+
+        const NAME: &str = "NestedIeRepr";
+
+        match self {
+            NestedIeRepr::TschTimeslot(timeslot) => serializer.serialize_newtype_variant(
+                NAME,
+                0,
+                "TschTimeslot",
+                &timeslot.timeslot_timings(),
+            ),
+            NestedIeRepr::EnhancedBeaconFilter(eb_filter) => {
+                let mut sv =
+                    serializer.serialize_struct_variant(NAME, 1, "EnhancedBeaconFilter", 4)?;
+                sv.serialize_field("permit_joining_on", &eb_filter.permit_joining_on())?;
+                sv.serialize_field("link_quality", &eb_filter.link_quality())?;
+                sv.serialize_field("percent_filter", &eb_filter.percent_filter())?;
+                sv.serialize_field("attribute_ids", eb_filter.attribute_id_list())?;
+                sv.end()
+            }
+            NestedIeRepr::MacMetrics(mac_metrics) => {
+                let mut sv = serializer.serialize_struct_variant(NAME, 2, "MacMetrics", 2)?;
+                sv.serialize_field("metric", &mac_metrics.metric())?;
+                sv.serialize_field("count", &mac_metrics.count())?;
+                sv.end()
+            }
+            NestedIeRepr::AllMacMetrics(all_mac_metrics) => {
+                struct Counts<'a, 'ie>(&'a AllMacMetrics<&'ie [u8]>);
+
+                impl serde::Serialize for Counts<'_, '_> {
+                    fn serialize<S: serde::Serializer>(
+                        &self,
+                        serializer: S,
+                    ) -> Result<S::Ok, S::Error> {
+                        serializer.collect_map(self.0.iter())
+                    }
+                }
+
+                serializer.serialize_newtype_variant(
+                    NAME,
+                    3,
+                    "AllMacMetrics",
+                    &Counts(all_mac_metrics),
+                )
+            }
+            NestedIeRepr::VendorSpecific(vendor_specific) => {
+                let mut sv = serializer.serialize_struct_variant(NAME, 4, "VendorSpecific", 2)?;
+                sv.serialize_field("oui", &vendor_specific.oui())?;
+                sv.serialize_field("content", vendor_specific.content())?;
+                sv.end()
+            }
+            NestedIeRepr::SunFskGenericPhy(generic_phy) => {
+                let mut sv = serializer.serialize_struct_variant(NAME, 5, "SunFskGenericPhy", 8)?;
+                sv.serialize_field("phy_id", &generic_phy.phy_id())?;
+                sv.serialize_field("center_frequency_0", &generic_phy.center_frequency_0())?;
+                sv.serialize_field("channel_spacing", &generic_phy.channel_spacing())?;
+                sv.serialize_field("num_channels", &generic_phy.num_channels())?;
+                sv.serialize_field("symbol_rate", &generic_phy.symbol_rate())?;
+                sv.serialize_field("modulation", &generic_phy.modulation())?;
+                sv.serialize_field("fec", &generic_phy.fec())?;
+                sv.serialize_field("modulation_index", &generic_phy.modulation_index())?;
+                sv.end()
+            }
+            NestedIeRepr::ModeSwitchParameter(mode_switch) => {
+                let mut sv =
+                    serializer.serialize_struct_variant(NAME, 6, "ModeSwitchParameter", 3)?;
+                sv.serialize_field("entry", &mode_switch.entry())?;
+                sv.serialize_field("phy_id", &mode_switch.phy_id())?;
+                sv.serialize_field("settling_delay", &mode_switch.settling_delay())?;
+                sv.end()
+            }
+            NestedIeRepr::Unknown {
+                sub_id,
+                is_long_format,
+                content,
+            } => {
+                let mut sv = serializer.serialize_struct_variant(NAME, 7, "Unknown", 3)?;
+                sv.serialize_field("sub_id", sub_id)?;
+                sv.serialize_field("is_long_format", is_long_format)?;
+                sv.serialize_field("content", content)?;
+                sv.end()
+            }
+        }
+    }
+}
+
+fn check_fixed_len(content: &[u8], expected_len: u16) -> Result<(), FrameError> {
+    match content.len().cmp(&(expected_len as usize)) {
+        core::cmp::Ordering::Less => Err(FrameErrorKind::TruncatedIe.into()),
+        core::cmp::Ordering::Equal => Ok(()),
+        core::cmp::Ordering::Greater => Err(FrameErrorKind::MalformedIe.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_ie_header() {
+        // Short format: TSCH Timeslot IE with ID only.
+        let bytes = [0x01, 0x1c, 0x00];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert!(!nested_ie.is_long_format());
+        assert_eq!(nested_ie.sub_id(), TSCH_TIMESLOT_IE_SUB_ID);
+        assert_eq!(nested_ie.length(), 1);
+        assert_eq!(nested_ie.content(), &[0x00]);
+
+        // Long format: Vendor Specific IE.
+        let bytes = [0x04, 0xc0, 0x00, 0x12, 0x4b, 0xff];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert!(nested_ie.is_long_format());
+        assert_eq!(nested_ie.sub_id(), VENDOR_SPECIFIC_NESTED_IE_SUB_ID);
+        assert_eq!(nested_ie.length(), 4);
+
+        assert_eq!(
+            NestedIe::new(&bytes[..5]),
+            Err(FrameErrorKind::IeExceedsFrame {
+                length: 6,
+                remaining: 5
+            }
+            .into())
+        );
+        assert_eq!(
+            NestedIe::new(&bytes[..1]),
+            Err(FrameErrorKind::TruncatedIe.into())
+        );
+    }
+
+    #[test]
+    fn oversized_long_format_ie() {
+        // Long format Vendor Specific IE announcing the max content length of
+        // 2046 octets within a 127 octet frame.
+        let mut frame = [0; 127];
+        frame[..2].copy_from_slice(&(0xc000u16 | 0x7fe).to_le_bytes());
+        frame[2..5].copy_from_slice(&[0x00, 0x12, 0x4b]);
+
+        assert_eq!(
+            NestedIe::new(&frame[..]),
+            Err(FrameErrorKind::IeExceedsFrame {
+                length: 2048,
+                remaining: 127
+            }
+            .into())
+        );
+        assert_eq!(
+            NestedIe::new_with_mtu(&frame[..], 127),
+            Err(FrameErrorKind::IeExceedsMtu {
+                length: 2048,
+                mtu: 127
+            }
+            .into())
+        );
+
+        // The IE fits into the buffer but not into the MTU.
+        let mut buffer = [0; 200];
+        buffer[..2].copy_from_slice(&(0xc000u16 | 150).to_le_bytes());
+        assert!(NestedIe::new(&buffer[..]).is_ok());
+        assert_eq!(
+            NestedIe::new_with_mtu(&buffer[..], 127),
+            Err(FrameErrorKind::IeExceedsMtu {
+                length: 152,
+                mtu: 127
+            }
+            .into())
+        );
+        assert!(NestedIe::new_with_mtu(&buffer[..], 200).is_ok());
+
+        // Iteration continues after an IE exceeding the MTU but not the
+        // buffer.
+        buffer[152..155].copy_from_slice(&[0x01, 0x1c, 0x00]);
+        let mut nested_ies = NestedIes::new(&buffer[..155]).with_mtu(127);
+        assert_eq!(
+            nested_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::IeExceedsMtu {
+                length: 152,
+                mtu: 127
+            })
+            .at(0)))
+        );
+        assert_eq!(nested_ies.next().unwrap().unwrap().sub_id(), 0x1c);
+        assert_eq!(nested_ies.next(), None);
+
+        // Iteration stops at the oversized IE.
+        let mut frame = [0; 10];
+        frame[..3].copy_from_slice(&[0x01, 0x1c, 0x00]);
+        frame[3..5].copy_from_slice(&(0xc000u16 | 0x7fe).to_le_bytes());
+        let mut nested_ies = NestedIes::new(&frame).with_mtu(127);
+        assert!(matches!(nested_ies.next(), Some(Ok(_))));
+        assert_eq!(
+            nested_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::IeExceedsMtu {
+                length: 2048,
+                mtu: 127
+            })
+            .at(3)))
+        );
+        assert_eq!(nested_ies.next(), None);
+    }
+
+    #[test]
+    fn parse() {
+        let bytes = [0x01, 0x1c, 0x00];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert!(matches!(
+            NestedIeRepr::parse(nested_ie),
+            Ok(NestedIeRepr::TschTimeslot(_))
+        ));
+
+        // TSCH Synchronization IE has no typed representation (yet).
+        let bytes = [0x01, 0x1a, 0x00];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::UnknownIe {
+                sub_id: 0x1a,
+                is_long_format: false
+            }
+            .into())
+        );
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse_or_unknown(nested_ie),
+            Ok(NestedIeRepr::Unknown {
+                sub_id: 0x1a,
+                is_long_format: false,
+                content: &[0x00]
+            })
+        );
+
+        // MAC Metrics IE with unknown metric ID.
+        let bytes = [0x05, 0x1f, 0xff, 0x00, 0x00, 0x00, 0x00];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::MalformedIe.into())
+        );
+
+        // Truncated MAC Metrics IE.
+        let bytes = [0x04, 0x1f, 0x01, 0x00, 0x00, 0x00];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::TruncatedIe.into())
+        );
+    }
+
+    #[test]
+    fn parse_sun_phy_ies() {
+        // SUN FSK Generic PHY IE: 863.125 MHz, 200 kHz spacing, 34 channels,
+        // 2-FSK at 100 ksymbol/s with FEC.
+        let mut bytes = [
+            0x0d, 0x23, 0x02, 0x95, 0x2b, 0x0d, 0x00, 0xc8, 0x00, 0x22, 0x00, 0x64, 0x00, 0x02,
+            0x32,
+        ];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        let Ok(NestedIeRepr::SunFskGenericPhy(generic_phy)) = NestedIeRepr::parse(nested_ie) else {
+            panic!("expected a SUN FSK Generic PHY IE");
+        };
+        assert_eq!(generic_phy.phy_id(), 2);
+        assert_eq!(generic_phy.center_frequency_0(), 863_125);
+        assert_eq!(generic_phy.data_rate(), 50_000);
+
+        // Reserved modulation bits.
+        bytes[13] = 0x04;
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::MalformedIe.into())
+        );
+
+        // Mode Switch Parameter IE: entry 1 switches to PHY ID 2 after 500 µs.
+        let mut bytes = [0x04, 0x24, 0x01, 0x02, 0xf4, 0x01];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        let Ok(NestedIeRepr::ModeSwitchParameter(mode_switch)) = NestedIeRepr::parse(nested_ie)
+        else {
+            panic!("expected a Mode Switch Parameter IE");
+        };
+        assert_eq!(mode_switch.entry(), 1);
+        assert_eq!(mode_switch.phy_id(), 2);
+        assert_eq!(mode_switch.settling_delay(), 500);
+
+        // Reserved entry bits.
+        bytes[2] = 0x05;
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::MalformedIe.into())
+        );
+
+        // Truncated Mode Switch Parameter IE.
+        let bytes = [0x03, 0x24, 0x01, 0x02, 0xf4];
+        let nested_ie = NestedIe::new(&bytes[..]).unwrap();
+        assert_eq!(
+            NestedIeRepr::parse(nested_ie),
+            Err(FrameErrorKind::TruncatedIe.into())
+        );
+    }
+
+    #[test]
+    fn iterate() {
+        let bytes = [
+            0x01, 0x1a, 0x00, // unknown
+            0x01, 0x1c, 0x00, // TSCH Timeslot
+            0x05, 0x1f, 0x01, // truncated
+        ];
+        let mut nested_ies = NestedIes::new(&bytes);
+        assert!(matches!(
+            NestedIeRepr::parse_or_unknown(nested_ies.next().unwrap().unwrap()),
+            Ok(NestedIeRepr::Unknown { sub_id: 0x1a, .. })
+        ));
+        assert!(matches!(
+            NestedIeRepr::parse_or_unknown(nested_ies.next().unwrap().unwrap()),
+            Ok(NestedIeRepr::TschTimeslot(_))
+        ));
+        assert_eq!(
+            nested_ies.next(),
+            Some(Err(FrameError::new(FrameErrorKind::IeExceedsFrame {
+                length: 7,
+                remaining: 3
+            })
+            .at(6)))
+        );
+        assert_eq!(nested_ies.next(), None);
+    }
+
+    /// Regression test derived from fuzzing: readers must not panic on
+    /// arbitrary input.
+    #[test]
+    fn arbitrary_input() {
+        // xorshift32, deterministic so that failures can be reproduced.
+        let mut state = 0x2545_f491u32;
+        let mut next_byte = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        };
+
+        let mut bytes = [0; 64];
+        for _ in 0..10_000 {
+            let len = next_byte() as usize % (bytes.len() + 1);
+            bytes.iter_mut().for_each(|b| *b = next_byte());
+            let bytes = &bytes[..len];
+
+            for nested_ie in NestedIes::new(bytes).chain(NestedIes::new(bytes).with_mtu(32)) {
+                let Ok(nested_ie) = nested_ie else {
+                    continue;
+                };
+                assert!(nested_ie.content().len() < len);
+                match NestedIeRepr::parse_or_unknown(nested_ie) {
+                    Ok(NestedIeRepr::TschTimeslot(timeslot)) => {
+                        let _ = timeslot.timeslot_timings();
+                    }
+                    Ok(NestedIeRepr::EnhancedBeaconFilter(eb_filter)) => {
+                        let _ = eb_filter.attribute_id_list();
+                        let _ = eb_filter.should_respond(true, 0xff, 0);
+                    }
+                    Ok(NestedIeRepr::MacMetrics(mac_metrics)) => {
+                        let _ = mac_metrics.count();
+                    }
+                    Ok(NestedIeRepr::AllMacMetrics(all_mac_metrics)) => {
+                        let _ = all_mac_metrics.iter().count();
+                    }
+                    Ok(NestedIeRepr::VendorSpecific(vendor_specific)) => {
+                        let _ = vendor_specific.content();
+                    }
+                    Ok(NestedIeRepr::SunFskGenericPhy(generic_phy)) => {
+                        let _ = generic_phy.data_rate();
+                    }
+                    Ok(NestedIeRepr::ModeSwitchParameter(mode_switch)) => {
+                        let _ = mode_switch.settling_delay();
+                    }
+                    Ok(NestedIeRepr::Unknown { .. }) | Err(_) => {}
+                }
+            }
+
+            // Readers validate their length on construction.
+            if let Ok(timeslot) = TschTimeslot::new(bytes) {
+                let _ = timeslot.timeslot_timings();
+            }
+            if let Ok(eb_filter) = EnhancedBeaconFilter::new(bytes) {
+                let _ = eb_filter.percent_filter();
+                let _ = eb_filter.attribute_id_list();
+            }
+            if let Ok(mac_metrics) = MacMetrics::new(bytes) {
+                let _ = (mac_metrics.metric(), mac_metrics.count());
+            }
+            if let Ok(all_mac_metrics) = AllMacMetrics::new(bytes) {
+                let _ = all_mac_metrics.iter().count();
+            }
+            if let Ok(vendor_specific) = VendorSpecific::new(bytes) {
+                let _ = (vendor_specific.oui(), vendor_specific.content());
+            }
+            if let Ok(generic_phy) = SunFskGenericPhy::new(bytes) {
+                let _ = (generic_phy.modulation(), generic_phy.data_rate());
+            }
+            if let Ok(mode_switch) = ModeSwitchParameter::new(bytes) {
+                let _ = (mode_switch.entry(), mode_switch.settling_delay());
+            }
+        }
+    }
+
+    /// A fixed-capacity [`core::fmt::Write`] sink so that formatting can be
+    /// exercised without an allocator.
+    struct FixedBuf<const N: usize> {
+        buf: [u8; N],
+        len: usize,
+    }
+
+    impl<const N: usize> FixedBuf<N> {
+        fn new() -> Self {
+            Self {
+                buf: [0; N],
+                len: 0,
+            }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.buf[..self.len]).unwrap()
+        }
+    }
+
+    impl<const N: usize> core::fmt::Write for FixedBuf<N> {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let end = self.len + s.len();
+            self.buf
+                .get_mut(self.len..end)
+                .ok_or(core::fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn formatting() {
+        use core::fmt::Write;
+
+        let bytes = [0x04, 0xc0, 0x00, 0x12, 0x4b, 0xff];
+        let repr = NestedIeRepr::parse(NestedIe::new(&bytes[..]).unwrap()).unwrap();
+        let mut out = FixedBuf::<64>::new();
+        write!(out, "{repr}").unwrap();
+        assert_eq!(
+            out.as_str(),
+            "Vendor Specific\n  oui: 00-12-4b\n  content: [ff]\n"
+        );
+
+        let bytes = [
+            0x0d, 0x23, 0x02, 0x95, 0x2b, 0x0d, 0x00, 0xc8, 0x00, 0x22, 0x00, 0x64, 0x00, 0x02,
+            0x32,
+        ];
+        let repr = NestedIeRepr::parse(NestedIe::new(&bytes[..]).unwrap()).unwrap();
+        let mut out = FixedBuf::<160>::new();
+        write!(out, "{repr}").unwrap();
+        assert_eq!(
+            out.as_str(),
+            "SUN FSK Generic PHY\n  PHY ID: 2\n  \
+             channel 0: 863125 kHz, spacing: 200 kHz, channels: 34\n  \
+             Fsk2, 100 ksymbol/s, modulation index: 0.50, FEC: 1\n"
+        );
+
+        let bytes = [0x01, 0x7e, 0xaa];
+        let repr = NestedIeRepr::parse_or_unknown(NestedIe::new(&bytes[..]).unwrap()).unwrap();
+        let mut out = FixedBuf::<64>::new();
+        write!(out, "{repr}").unwrap();
+        assert_eq!(
+            out.as_str(),
+            "Unknown (short format, sub-ID 0x7e)\n  content: [aa]\n"
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! SUN FSK Generic PHY IE and Mode Switch Parameter IE field access (IEEE
+//! 802.15.4-2024).
+//!
+//! The SUN FSK Generic PHY IE describes a PHY mode of the SUN FSK PHY beyond
+//! the standard operating modes, i.e. its channel plan, data rate and FEC:
+//!
+//! ```notrust
+//! +-------------+---------------------------+-----------------------+
+//! | PHY ID (1B) | ChanCenterFreq0 (4B, kHz) | ChanSpacing (2B, kHz) |
+//! +-------------+---------------------------+-----------------------+
+//! +--------------------+------------------------------+
+//! | TotalNumChan (2B)  | Symbol rate (2B, ksymbol/s)  |
+//! +--------------------+------------------------------+
+//! +----------------------------------+------------------------------+
+//! | Modulation (bit 0), FEC (bit 1)  | Modulation index (1B, 1/100) |
+//! +----------------------------------+------------------------------+
+//! ```
+//!
+//! The Mode Switch Parameter IE assigns one of the PHY modes to a Mode Switch
+//! Parameter Entry, so that the mode switch PHR of a frame can announce it
+//! with two bits:
+//!
+//! ```notrust
+//! +--------------------------+-------------+-----------------------------+
+//! | Entry (bits 0-1 of 1B)   | PHY ID (1B) | Settling delay (2B, µs)     |
+//! +--------------------------+-------------+-----------------------------+
+//! ```
+
+use dot15d4_driver::phy::{FskModulation, SunFskMode};
+use dot15d4_util::{Error, Result};
+
+/// The nested IE sub-ID of the SUN FSK Generic PHY IE (short format).
+pub const SUN_FSK_GENERIC_PHY_IE_SUB_ID: u8 = 0x23;
+
+/// The nested IE sub-ID of the Mode Switch Parameter IE (short format).
+pub const MODE_SWITCH_PARAMETER_IE_SUB_ID: u8 = 0x24;
+
+/// The content length of the SUN FSK Generic PHY IE in octets.
+pub const SUN_FSK_GENERIC_PHY_CONTENT_LEN: u16 = 13;
+
+/// The content length of the Mode Switch Parameter IE in octets.
+pub const MODE_SWITCH_PARAMETER_CONTENT_LEN: u16 = 4;
+
+/// The highest Mode Switch Parameter Entry.
+pub const MAX_MODE_SWITCH_ENTRY: u8 = 0b11;
+
+const MODULATION_4_FSK: u8 = 1 << 0;
+const FEC: u8 = 1 << 1;
+
+/// A reader/writer for the content of a SUN FSK Generic PHY IE.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SunFskGenericPhy<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> SunFskGenericPhy<Bytes> {
+    /// Create a new [`SunFskGenericPhy`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let generic_phy = Self::new_unchecked(bytes);
+
+        if !generic_phy.check_len() {
+            return Err(Error);
+        }
+
+        Ok(generic_phy)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the IE content.
+    fn check_len(&self) -> bool {
+        self.bytes.as_ref().len() >= SUN_FSK_GENERIC_PHY_CONTENT_LEN as usize
+    }
+
+    /// Create a new [`SunFskGenericPhy`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the ID by which Mode Switch Parameter IEs refer to the PHY
+    /// mode.
+    pub fn phy_id(&self) -> u8 {
+        self.bytes.as_ref()[0]
+    }
+
+    /// Returns ChanCenterFreq0, the center frequency of channel 0 in kHz.
+    pub fn center_frequency_0(&self) -> u32 {
+        let b = &self.bytes.as_ref()[1..5];
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    /// Returns ChanSpacing, the distance between the center frequencies of
+    /// adjacent channels in kHz.
+    pub fn channel_spacing(&self) -> u16 {
+        let b = &self.bytes.as_ref()[5..7];
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    /// Returns TotalNumChan, the number of channels.
+    pub fn num_channels(&self) -> u16 {
+        let b = &self.bytes.as_ref()[7..9];
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    /// Returns the symbol rate in ksymbol/s.
+    pub fn symbol_rate(&self) -> u16 {
+        let b = &self.bytes.as_ref()[9..11];
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    /// Returns the modulation.
+    pub fn modulation(&self) -> FskModulation {
+        if self.bytes.as_ref()[11] & MODULATION_4_FSK != 0 {
+            FskModulation::Fsk4
+        } else {
+            FskModulation::Fsk2
+        }
+    }
+
+    /// Returns whether the PSDU is encoded with the rate 1/2 FEC.
+    pub fn fec(&self) -> bool {
+        self.bytes.as_ref()[11] & FEC != 0
+    }
+
+    /// Returns whether reserved bits of the modulation field are set.
+    pub fn has_reserved_bits(&self) -> bool {
+        self.bytes.as_ref()[11] & !(MODULATION_4_FSK | FEC) != 0
+    }
+
+    /// Returns the modulation index in hundredths, e.g. 100 for a modulation
+    /// index of 1.0.
+    pub fn modulation_index(&self) -> u8 {
+        self.bytes.as_ref()[12]
+    }
+
+    /// Returns the described PHY mode, e.g. to send frames with it.
+    pub fn phy_mode(&self) -> SunFskMode {
+        SunFskMode::new(self.symbol_rate(), self.modulation(), self.fec())
+    }
+
+    /// Returns the data rate of the PSDU in b/s, taking the FEC into account.
+    pub fn data_rate(&self) -> u32 {
+        self.phy_mode().data_rate()
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> SunFskGenericPhy<Bytes> {
+    /// Set the ID by which Mode Switch Parameter IEs refer to the PHY mode.
+    pub fn set_phy_id(&mut self, phy_id: u8) {
+        self.bytes.as_mut()[0] = phy_id;
+    }
+
+    /// Set ChanCenterFreq0 in kHz.
+    pub fn set_center_frequency_0(&mut self, center_frequency_0: u32) {
+        self.bytes.as_mut()[1..5].copy_from_slice(&center_frequency_0.to_le_bytes());
+    }
+
+    /// Set ChanSpacing in kHz.
+    pub fn set_channel_spacing(&mut self, channel_spacing: u16) {
+        self.bytes.as_mut()[5..7].copy_from_slice(&channel_spacing.to_le_bytes());
+    }
+
+    /// Set TotalNumChan.
+    pub fn set_num_channels(&mut self, num_channels: u16) {
+        self.bytes.as_mut()[7..9].copy_from_slice(&num_channels.to_le_bytes());
+    }
+
+    /// Set the symbol rate in ksymbol/s.
+    pub fn set_symbol_rate(&mut self, symbol_rate: u16) {
+        self.bytes.as_mut()[9..11].copy_from_slice(&symbol_rate.to_le_bytes());
+    }
+
+    /// Set the symbol rate, modulation and FEC of the described PHY mode.
+    pub fn set_phy_mode(&mut self, phy_mode: SunFskMode) {
+        self.set_symbol_rate(phy_mode.symbol_rate);
+        self.set_modulation(phy_mode.modulation, phy_mode.fec);
+    }
+
+    /// Set the modulation and whether the PSDU is encoded with the rate 1/2
+    /// FEC.
+    pub fn set_modulation(&mut self, modulation: FskModulation, fec: bool) {
+        let mut value = 0;
+        if modulation == FskModulation::Fsk4 {
+            value |= MODULATION_4_FSK;
+        }
+        if fec {
+            value |= FEC;
+        }
+        self.bytes.as_mut()[11] = value;
+    }
+
+    /// Set the modulation index in hundredths.
+    pub fn set_modulation_index(&mut self, modulation_index: u8) {
+        self.bytes.as_mut()[12] = modulation_index;
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for SunFskGenericPhy<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(f, "PHY ID: {}", self.phy_id())?;
+        writeln!(
+            f,
+            "{:indent$}channel 0: {} kHz, spacing: {} kHz, channels: {}",
+            "",
+            self.center_frequency_0(),
+            self.channel_spacing(),
+            self.num_channels()
+        )?;
+        writeln!(
+            f,
+            "{:indent$}{:?}, {} ksymbol/s, modulation index: {}.{:02}, FEC: {}",
+            "",
+            self.modulation(),
+            self.symbol_rate(),
+            self.modulation_index() / 100,
+            self.modulation_index() % 100,
+            self.fec() as u8
+        )
+    }
+}
+
+/// A reader/writer for the content of a Mode Switch Parameter IE.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ModeSwitchParameter<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> ModeSwitchParameter<Bytes> {
+    /// Create a new [`ModeSwitchParameter`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let mode_switch = Self::new_unchecked(bytes);
+
+        if !mode_switch.check_len() {
+            return Err(Error);
+        }
+
+        Ok(mode_switch)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the IE content.
+    fn check_len(&self) -> bool {
+        self.bytes.as_ref().len() >= MODE_SWITCH_PARAMETER_CONTENT_LEN as usize
+    }
+
+    /// Create a new [`ModeSwitchParameter`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the Mode Switch Parameter Entry announced by mode switch PHRs.
+    pub fn entry(&self) -> u8 {
+        self.bytes.as_ref()[0] & MAX_MODE_SWITCH_ENTRY
+    }
+
+    /// Returns whether reserved bits of the entry field are set.
+    pub fn has_reserved_bits(&self) -> bool {
+        self.bytes.as_ref()[0] & !MAX_MODE_SWITCH_ENTRY != 0
+    }
+
+    /// Returns the PHY ID of the new PHY mode, see
+    /// [`SunFskGenericPhy::phy_id()`].
+    pub fn phy_id(&self) -> u8 {
+        self.bytes.as_ref()[1]
+    }
+
+    /// Returns the time in µs the receiver needs to switch to the new PHY
+    /// mode after the mode switch PPDU.
+    pub fn settling_delay(&self) -> u16 {
+        let b = &self.bytes.as_ref()[2..4];
+        u16::from_le_bytes([b[0], b[1]])
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> ModeSwitchParameter<Bytes> {
+    /// Set the Mode Switch Parameter Entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry exceeds [`MAX_MODE_SWITCH_ENTRY`].
+    pub fn set_entry(&mut self, entry: u8) -> Result<()> {
+        if entry > MAX_MODE_SWITCH_ENTRY {
+            return Err(Error);
+        }
+
+        self.bytes.as_mut()[0] = entry;
+        Ok(())
+    }
+
+    /// Set the PHY ID of the new PHY mode.
+    pub fn set_phy_id(&mut self, phy_id: u8) {
+        self.bytes.as_mut()[1] = phy_id;
+    }
+
+    /// Set the settling delay in µs.
+    pub fn set_settling_delay(&mut self, settling_delay: u16) {
+        self.bytes.as_mut()[2..4].copy_from_slice(&settling_delay.to_le_bytes());
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for ModeSwitchParameter<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "entry: {}, PHY ID: {}, settling delay: {} µs",
+            self.entry(),
+            self.phy_id(),
+            self.settling_delay()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_fsk_generic_phy() {
+        let mut content = [0; SUN_FSK_GENERIC_PHY_CONTENT_LEN as usize];
+        let mut generic_phy = SunFskGenericPhy::new(&mut content).unwrap();
+        generic_phy.set_phy_id(2);
+        generic_phy.set_center_frequency_0(863_125);
+        generic_phy.set_channel_spacing(200);
+        generic_phy.set_num_channels(34);
+        generic_phy.set_symbol_rate(100);
+        generic_phy.set_modulation(FskModulation::Fsk2, true);
+        generic_phy.set_modulation_index(50);
+        assert_eq!(
+            content,
+            [0x02, 0x95, 0x2b, 0x0d, 0x00, 0xc8, 0x00, 0x22, 0x00, 0x64, 0x00, 0x02, 0x32]
+        );
+
+        let generic_phy = SunFskGenericPhy::new(&content).unwrap();
+        assert_eq!(generic_phy.phy_id(), 2);
+        assert_eq!(generic_phy.center_frequency_0(), 863_125);
+        assert_eq!(generic_phy.channel_spacing(), 200);
+        assert_eq!(generic_phy.num_channels(), 34);
+        assert_eq!(generic_phy.modulation(), FskModulation::Fsk2);
+        assert!(generic_phy.fec());
+        assert!(!generic_phy.has_reserved_bits());
+        assert_eq!(generic_phy.modulation_index(), 50);
+        // 100 ksymbol/s with rate 1/2 FEC.
+        assert_eq!(generic_phy.data_rate(), 50_000);
+
+        content[11] = 0x01;
+        let generic_phy = SunFskGenericPhy::new(&content).unwrap();
+        assert_eq!(generic_phy.modulation(), FskModulation::Fsk4);
+        assert_eq!(generic_phy.data_rate(), 200_000);
+        assert_eq!(
+            generic_phy.phy_mode(),
+            SunFskMode::new(100, FskModulation::Fsk4, false)
+        );
+
+        assert!(SunFskGenericPhy::new(&content[..12]).is_err());
+    }
+
+    #[test]
+    fn mode_switch_parameter() {
+        let mut content = [0; MODE_SWITCH_PARAMETER_CONTENT_LEN as usize];
+        let mut mode_switch = ModeSwitchParameter::new(&mut content).unwrap();
+        mode_switch.set_entry(3).unwrap();
+        mode_switch.set_phy_id(2);
+        mode_switch.set_settling_delay(500);
+        assert!(mode_switch.set_entry(4).is_err());
+        assert_eq!(content, [0x03, 0x02, 0xf4, 0x01]);
+
+        let mode_switch = ModeSwitchParameter::new(&content).unwrap();
+        assert_eq!(mode_switch.entry(), 3);
+        assert_eq!(mode_switch.phy_id(), 2);
+        assert_eq!(mode_switch.settling_delay(), 500);
+        assert!(!mode_switch.has_reserved_bits());
+        assert!(ModeSwitchParameter::new(&content[..3]).is_err());
+    }
+}
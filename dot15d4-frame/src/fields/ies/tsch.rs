@@ -6,7 +6,194 @@
 
 use bitflags::bitflags;
 
-use dot15d4_driver::time::{Duration, Microseconds};
+use dot15d4_driver::{
+    constants::PHY_MAX_PACKET_SIZE_127,
+    phy::{air_time, PhyParameters},
+    time::{Duration, Microseconds},
+};
+use dot15d4_util::{Error, Result};
+
+/// The nested IE sub-ID of the TSCH Synchronization IE (short format).
+pub const TSCH_SYNCHRONIZATION_IE_SUB_ID: u8 = 0x1a;
+
+/// The nested IE sub-ID of the TSCH Slotframe and Link IE (short format).
+pub const TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID: u8 = 0x1b;
+
+/// The nested IE sub-ID of the TSCH Timeslot IE (short format).
+pub const TSCH_TIMESLOT_IE_SUB_ID: u8 = 0x1c;
+
+/// The absolute slot number (ASN), i.e. the number of timeslots that elapsed
+/// since the start of the network.
+///
+/// The ASN is encoded as a 5-byte unsigned integer on the wire. All arithmetic
+/// therefore wraps around at 2^40.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Asn(u64);
+
+impl Asn {
+    /// The length of the ASN on the wire.
+    pub const LEN: usize = 5;
+
+    /// The largest ASN that can be encoded.
+    pub const MAX: Asn = Asn(0xff_ffff_ffff);
+
+    /// Creates an ASN from the given value.
+    ///
+    /// Returns `None` if the value cannot be encoded in 5 bytes.
+    pub const fn new(asn: u64) -> Option<Self> {
+        if asn > Self::MAX.0 {
+            None
+        } else {
+            Some(Self(asn))
+        }
+    }
+
+    /// Returns the ASN as integer.
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// Decodes the ASN from its little-endian wire encoding.
+    pub const fn from_le_bytes(bytes: [u8; Self::LEN]) -> Self {
+        let [b0, b1, b2, b3, b4] = bytes;
+        Self(u64::from_le_bytes([b0, b1, b2, b3, b4, 0, 0, 0]))
+    }
+
+    /// Encodes the ASN in little-endian byte order.
+    pub const fn to_le_bytes(&self) -> [u8; Self::LEN] {
+        let [b0, b1, b2, b3, b4, ..] = self.0.to_le_bytes();
+        [b0, b1, b2, b3, b4]
+    }
+
+    /// Adds the given number of timeslots, wrapping around at the largest ASN.
+    pub const fn wrapping_add(self, timeslots: u64) -> Self {
+        // Both operands are masked to 40 bits so the sum cannot overflow.
+        Self((self.0 + (timeslots & Self::MAX.0)) & Self::MAX.0)
+    }
+
+    /// Returns the offset of the timeslot designated by this ASN within a
+    /// slotframe of the given size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slotframe size is zero.
+    pub const fn slot_offset(&self, slotframe_size: u16) -> u16 {
+        (self.0 % slotframe_size as u64) as u16
+    }
+
+    /// Returns the index into the hopping sequence of the channel to be used
+    /// by a link with the given channel offset in the timeslot designated by
+    /// this ASN (IEEE 802.15.4-2024, section 6.2.6.3).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hopping sequence length is zero.
+    pub const fn channel_hopping_index(
+        &self,
+        channel_offset: u16,
+        hopping_sequence_length: u16,
+    ) -> u16 {
+        ((self.0 + channel_offset as u64) % hopping_sequence_length as u64) as u16
+    }
+}
+
+impl core::fmt::Display for Asn {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A reader for the content of a TSCH Synchronization IE (figure 7-61 in IEEE
+/// 802.15.4-2024).
+///
+/// ```notrust
+/// +-----+-------------+
+/// | ASN | Join metric |
+/// +-----+-------------+
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct TschSynchronization<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> TschSynchronization<Bytes> {
+    /// Create a new [`TschSynchronization`] reader from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match
+    /// [`TschSynchronizationRepr::CONTENT_LEN`].
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        if bytes.as_ref().len() != TschSynchronizationRepr::CONTENT_LEN as usize {
+            return Err(Error);
+        }
+
+        Ok(Self::new_unchecked(bytes))
+    }
+
+    /// Create a new [`TschSynchronization`] reader from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the absolute slot number.
+    pub fn asn(&self) -> Asn {
+        let b = self.bytes.as_ref();
+        Asn::from_le_bytes([b[0], b[1], b[2], b[3], b[4]])
+    }
+
+    /// Return the join metric.
+    pub fn join_metric(&self) -> u8 {
+        self.bytes.as_ref()[Asn::LEN]
+    }
+}
+
+/// A writable representation of the content of a TSCH Synchronization IE.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TschSynchronizationRepr {
+    /// The absolute slot number.
+    pub asn: Asn,
+    /// The join metric.
+    pub join_metric: u8,
+}
+
+impl TschSynchronizationRepr {
+    /// The content length of a TSCH Synchronization IE.
+    pub const CONTENT_LEN: u16 = 6;
+
+    /// Writes the content of a TSCH Synchronization IE.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match
+    /// [`TschSynchronizationRepr::CONTENT_LEN`].
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<()> {
+        if buffer.len() != Self::CONTENT_LEN as usize {
+            return Err(Error);
+        }
+
+        buffer[..Asn::LEN].copy_from_slice(&self.asn.to_le_bytes());
+        buffer[Asn::LEN] = self.join_metric;
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> From<&TschSynchronization<Bytes>> for TschSynchronizationRepr {
+    fn from(synchronization: &TschSynchronization<Bytes>) -> Self {
+        Self {
+            asn: synchronization.asn(),
+            join_metric: synchronization.join_metric(),
+        }
+    }
+}
 
 /// TSCH timeslot timings (figure 6-30 in IEEE 802.15.4-2020).
 ///
@@ -18,6 +205,9 @@ use dot15d4_driver::time::{Duration, Microseconds};
 /// +----+------------+-----+-----------+-----------+--------------+--------------+---------+----------+-------+---------+--------+------------------+
 /// ```
 #[derive(Debug)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TschTimeslotTimings {
     id: u8,
     /// Offset from the start of the timeslot to the start of the CCA in
@@ -232,6 +422,288 @@ impl core::fmt::Display for TschTimeslotTimings {
     }
 }
 
+/// An inconsistency of [`TschTimeslotTimings`], see
+/// [`TschTimeslotTimings::validate()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimeslotTimingViolation {
+    /// A timing is negative.
+    NegativeTiming,
+    /// The CCA is shorter than phyCcaDuration or doesn't leave the RX/TX
+    /// turnaround time before the TX offset.
+    Cca,
+    /// The RX/TX turnaround time, RX ACK delay or TX ACK delay is shorter
+    /// than aTurnaroundTime.
+    Turnaround,
+    /// The max TX is shorter than the longest frame of 127 octets.
+    MaxTx,
+    /// The RX wait doesn't cover the TX offset.
+    RxWait,
+    /// The ACK wait doesn't cover the TX ACK delay.
+    AckWait,
+    /// The ACK may end after the timeslot.
+    TimeslotLength,
+    /// The guard time doesn't cover the clock drift between two
+    /// synchronizations.
+    GuardTime,
+}
+
+impl TschTimeslotTimings {
+    /// Check that the timings are consistent with each other and with the
+    /// given PHY.
+    ///
+    /// The receiver of a frame listens for [`rx_wait()`] from [`rx_offset()`]
+    /// on, i.e. up to a guard time before and after the expected start of the
+    /// frame at [`tx_offset()`]. The guard time must cover the drift of both
+    /// clocks since the last synchronization. The ACK of the longest frame
+    /// must end within the timeslot, i.e. at the latest [`tx_offset()`] +
+    /// [`max_tx()`] + [`rx_ack_delay()`] + [`ack_wait()`] + [`max_ack()`].
+    ///
+    /// * `phy` - The timing parameters of the PHY
+    /// * `clock_accuracy_ppm` - The accuracy of the clocks of both devices in
+    ///   parts per million
+    /// * `sync_interval` - The max time between two synchronizations with the
+    ///   time source, e.g. the keep-alive period
+    ///
+    /// [`rx_wait()`]: TschTimeslotTimings::rx_wait
+    /// [`rx_offset()`]: TschTimeslotTimings::rx_offset
+    /// [`tx_offset()`]: TschTimeslotTimings::tx_offset
+    /// [`max_tx()`]: TschTimeslotTimings::max_tx
+    /// [`rx_ack_delay()`]: TschTimeslotTimings::rx_ack_delay
+    /// [`ack_wait()`]: TschTimeslotTimings::ack_wait
+    /// [`max_ack()`]: TschTimeslotTimings::max_ack
+    pub fn validate(
+        &self,
+        phy: &PhyParameters,
+        clock_accuracy_ppm: u16,
+        sync_interval: Duration<Microseconds>,
+    ) -> core::result::Result<(), TimeslotTimingViolation> {
+        let timings = [
+            self.cca_offset,
+            self.cca,
+            self.rx_tx,
+            self.tx_offset,
+            self.max_tx,
+            self.rx_ack_delay,
+            self.ack_wait,
+            self.rx_offset,
+            self.rx_wait,
+            self.tx_ack_delay,
+            self.max_ack,
+            self.timeslot_length,
+        ];
+        if timings.iter().any(|timing| timing.ticks() < 0) {
+            return Err(TimeslotTimingViolation::NegativeTiming);
+        }
+
+        // Both clocks drift.
+        let drift = sync_interval.ticks() * 2 * clock_accuracy_ppm as i64 / 1_000_000;
+        let guard_time =
+            (self.tx_offset - self.rx_offset).min(self.rx_offset + self.rx_wait - self.tx_offset);
+        let ack_end = self.tx_offset
+            + self.max_tx
+            + (self.rx_ack_delay + self.ack_wait).max(self.tx_ack_delay)
+            + self.max_ack;
+
+        if self.cca < phy.cca_duration || self.cca_offset + self.cca + self.rx_tx > self.tx_offset {
+            Err(TimeslotTimingViolation::Cca)
+        } else if self.rx_tx.min(self.rx_ack_delay).min(self.tx_ack_delay) < phy.turnaround_time {
+            Err(TimeslotTimingViolation::Turnaround)
+        } else if self.max_tx < air_time(PHY_MAX_PACKET_SIZE_127, phy) {
+            Err(TimeslotTimingViolation::MaxTx)
+        } else if guard_time.ticks() < 0 {
+            Err(TimeslotTimingViolation::RxWait)
+        } else if self.tx_ack_delay < self.rx_ack_delay
+            || self.tx_ack_delay > self.rx_ack_delay + self.ack_wait
+        {
+            Err(TimeslotTimingViolation::AckWait)
+        } else if ack_end > self.timeslot_length {
+            Err(TimeslotTimingViolation::TimeslotLength)
+        } else if guard_time.ticks() < drift {
+            Err(TimeslotTimingViolation::GuardTime)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl TschTimeslotTimings {
+    /// The content length of a TSCH Timeslot IE that only carries the timeslot
+    /// ID.
+    pub const SHORT_CONTENT_LEN: u16 = 1;
+    /// The content length of a TSCH Timeslot IE with 2-byte max TX and
+    /// timeslot length fields.
+    pub const FULL_CONTENT_LEN: u16 = 25;
+    /// The content length of a TSCH Timeslot IE with 3-byte max TX and
+    /// timeslot length fields.
+    pub const FULL_CONTENT_LEN_LONG: u16 = 27;
+
+    /// Returns `true` if the max TX or timeslot length exceed 65535µs and
+    /// therefore require the 3-byte encoding.
+    pub fn requires_long_format(&self) -> bool {
+        self.max_tx.ticks() > u16::MAX as i64 || self.timeslot_length.ticks() > u16::MAX as i64
+    }
+
+    /// The content length of a TSCH Timeslot IE carrying these timings.
+    pub fn content_length(&self) -> u16 {
+        if self.requires_long_format() {
+            Self::FULL_CONTENT_LEN_LONG
+        } else {
+            Self::FULL_CONTENT_LEN
+        }
+    }
+
+    /// Writes the timings as the content of a full TSCH Timeslot IE.
+    ///
+    /// Chooses the long format with 3-byte max TX and timeslot length fields
+    /// automatically if required. The buffer must have been sized with
+    /// [`TschTimeslotTimings::content_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer does not match the content length or
+    /// if a timing does not fit into its field.
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<()> {
+        if buffer.len() != self.content_length() as usize {
+            return Err(Error);
+        }
+
+        let is_long = self.requires_long_format();
+
+        buffer[0] = self.id;
+        let mut offset = 1;
+        for duration in [
+            self.cca_offset,
+            self.cca,
+            self.tx_offset,
+            self.rx_offset,
+            self.rx_ack_delay,
+            self.tx_ack_delay,
+            self.rx_wait,
+            self.ack_wait,
+            self.rx_tx,
+            self.max_ack,
+        ] {
+            let value = u16::try_from(duration.ticks()).map_err(|_| Error)?;
+            buffer[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            offset += 2;
+        }
+
+        for duration in [self.max_tx, self.timeslot_length] {
+            if is_long {
+                let value = u32::try_from(duration.ticks()).map_err(|_| Error)?;
+                if value > 0xff_ffff {
+                    return Err(Error);
+                }
+                buffer[offset..offset + 3].copy_from_slice(&value.to_le_bytes()[..3]);
+                offset += 3;
+            } else {
+                let value = u16::try_from(duration.ticks()).map_err(|_| Error)?;
+                buffer[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+                offset += 2;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A reader for the content of a TSCH Timeslot IE.
+///
+/// The IE either only carries the timeslot ID (default timings) or the full
+/// set of timings with 2-byte (25 bytes total) or 3-byte (27 bytes total) max
+/// TX and timeslot length fields.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TschTimeslot<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> TschTimeslot<Bytes> {
+    /// Create a new [`TschTimeslot`] reader from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match any of the TSCH
+    /// Timeslot IE formats.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let timeslot = Self::new_unchecked(bytes);
+
+        if !timeslot.check_len() {
+            return Err(Error);
+        }
+
+        Ok(timeslot)
+    }
+
+    /// Returns `false` if the buffer length doesn't match any of the TSCH
+    /// Timeslot IE formats.
+    fn check_len(&self) -> bool {
+        matches!(
+            self.bytes.as_ref().len() as u16,
+            TschTimeslotTimings::SHORT_CONTENT_LEN
+                | TschTimeslotTimings::FULL_CONTENT_LEN
+                | TschTimeslotTimings::FULL_CONTENT_LEN_LONG
+        )
+    }
+
+    /// Create a new [`TschTimeslot`] reader from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the timeslot ID.
+    pub fn id(&self) -> u8 {
+        self.bytes.as_ref()[0]
+    }
+
+    /// Returns the timeslot timings.
+    ///
+    /// If the IE only carries the timeslot ID, then the default timings are
+    /// returned.
+    pub fn timeslot_timings(&self) -> TschTimeslotTimings {
+        let b = self.bytes.as_ref();
+        if b.len() == TschTimeslotTimings::SHORT_CONTENT_LEN as usize {
+            return TschTimeslotTimings::new(self.id(), TschTimeslotTimings::DEFAULT_GUARD_TIME);
+        }
+
+        let is_long = b.len() == TschTimeslotTimings::FULL_CONTENT_LEN_LONG as usize;
+        let short =
+            |offset: usize| Duration::new(u16::from_le_bytes([b[offset], b[offset + 1]]) as i64);
+        let max_tx_and_timeslot_length = |offset: usize| {
+            if is_long {
+                Duration::new(
+                    u32::from_le_bytes([b[offset], b[offset + 1], b[offset + 2], 0]) as i64,
+                )
+            } else {
+                short(offset)
+            }
+        };
+        let field_len = if is_long { 3 } else { 2 };
+
+        TschTimeslotTimings {
+            id: self.id(),
+            cca_offset: short(1),
+            cca: short(3),
+            tx_offset: short(5),
+            rx_offset: short(7),
+            rx_ack_delay: short(9),
+            tx_ack_delay: short(11),
+            rx_wait: short(13),
+            ack_wait: short(15),
+            rx_tx: short(17),
+            max_ack: short(19),
+            max_tx: max_tx_and_timeslot_length(21),
+            timeslot_length: max_tx_and_timeslot_length(21 + field_len),
+        }
+    }
+}
+
 bitflags! {
     /// TSCH link options bitfield.
     /// ```notrust
@@ -239,7 +711,7 @@ bitflags! {
     /// | Tx | Rx | Shared | Time keeping | Priority | Reserved |
     /// +----+----+--------+--------------+----------+----------+
     /// ```
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, PartialEq, Eq)]
     pub struct TschLinkOption: u8 {
         /// Transmit.
         const Tx = 0b0000_0001;
@@ -265,3 +737,612 @@ impl core::fmt::Display for TschLinkOption {
         bitflags::parser::to_writer(self, f)
     }
 }
+
+/// The length of the slotframe descriptor fields preceding the link
+/// information fields (handle, size and number of links).
+pub const SLOTFRAME_DESCRIPTOR_HEADER_LEN: usize = 4;
+
+/// The length of a link information field (timeslot, channel offset and link
+/// options).
+pub const LINK_INFORMATION_LEN: usize = 5;
+
+/// A reader for the content of a TSCH Slotframe and Link IE (figure 7-62 in
+/// IEEE 802.15.4-2024).
+///
+/// ```notrust
+/// +----------------------+------------------------+
+/// | Number of slotframes | Slotframe descriptors  |
+/// +----------------------+------------------------+
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct TschSlotframeAndLink<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> TschSlotframeAndLink<Bytes> {
+    /// Create a new [`TschSlotframeAndLink`] reader from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match the length
+    /// announced by the slotframe descriptors.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let slotframe_and_link = Self::new_unchecked(bytes);
+
+        if !slotframe_and_link.check_len() {
+            return Err(Error);
+        }
+
+        Ok(slotframe_and_link)
+    }
+
+    /// Returns `false` if the buffer length doesn't match the length announced
+    /// by the slotframe descriptors.
+    fn check_len(&self) -> bool {
+        let b = self.bytes.as_ref();
+        let Some((&number_of_slotframes, mut descriptors)) = b.split_first() else {
+            return false;
+        };
+
+        for _ in 0..number_of_slotframes {
+            match SlotframeDescriptor::new(descriptors) {
+                Ok(descriptor) => descriptors = &descriptors[descriptor.length()..],
+                Err(_) => return false,
+            }
+        }
+
+        descriptors.is_empty()
+    }
+
+    /// Create a new [`TschSlotframeAndLink`] reader from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the number of slotframes.
+    pub fn number_of_slotframes(&self) -> u8 {
+        self.bytes.as_ref()[0]
+    }
+
+    /// Returns an iterator over the slotframe descriptors.
+    pub fn slotframe_descriptors(&self) -> SlotframeDescriptorIterator<'_> {
+        SlotframeDescriptorIterator {
+            bytes: &self.bytes.as_ref()[1..],
+            remaining: self.number_of_slotframes(),
+        }
+    }
+}
+
+/// An iterator over the slotframe descriptors of a TSCH Slotframe and Link IE.
+#[derive(Debug)]
+pub struct SlotframeDescriptorIterator<'f> {
+    bytes: &'f [u8],
+    remaining: u8,
+}
+
+impl<'f> Iterator for SlotframeDescriptorIterator<'f> {
+    type Item = SlotframeDescriptor<&'f [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let descriptor = SlotframeDescriptor::new(self.bytes).ok()?;
+        let (descriptor, rest) = self.bytes.split_at(descriptor.length());
+        self.bytes = rest;
+        self.remaining -= 1;
+        Some(SlotframeDescriptor::new_unchecked(descriptor))
+    }
+}
+
+/// A reader for a slotframe descriptor (figure 7-63 in IEEE 802.15.4-2024).
+///
+/// ```notrust
+/// +------------------+----------------+-----------------+------------------+
+/// | Slotframe handle | Slotframe size | Number of links | Link information |
+/// +------------------+----------------+-----------------+------------------+
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct SlotframeDescriptor<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> SlotframeDescriptor<Bytes> {
+    /// Create a new [`SlotframeDescriptor`] reader from a given buffer.
+    ///
+    /// The buffer may extend beyond the descriptor, e.g. when it contains a
+    /// list of descriptors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the descriptor
+    /// including all link information fields.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let descriptor = Self::new_unchecked(bytes);
+
+        let len = descriptor.bytes.as_ref().len();
+        if len < SLOTFRAME_DESCRIPTOR_HEADER_LEN || len < descriptor.length() {
+            return Err(Error);
+        }
+
+        Ok(descriptor)
+    }
+
+    /// Create a new [`SlotframeDescriptor`] reader from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the slotframe handle.
+    pub fn handle(&self) -> u8 {
+        self.bytes.as_ref()[0]
+    }
+
+    /// Return the slotframe size.
+    pub fn size(&self) -> u16 {
+        let b = self.bytes.as_ref();
+        u16::from_le_bytes([b[1], b[2]])
+    }
+
+    /// Return the number of links.
+    pub fn number_of_links(&self) -> u8 {
+        self.bytes.as_ref()[3]
+    }
+
+    /// Return the length of the descriptor including all link information
+    /// fields.
+    pub fn length(&self) -> usize {
+        SLOTFRAME_DESCRIPTOR_HEADER_LEN + self.number_of_links() as usize * LINK_INFORMATION_LEN
+    }
+
+    /// Returns an iterator over the link information fields.
+    pub fn links(&self) -> impl Iterator<Item = LinkDescriptor<&[u8]>> {
+        self.bytes.as_ref()[SLOTFRAME_DESCRIPTOR_HEADER_LEN..self.length()]
+            .chunks_exact(LINK_INFORMATION_LEN)
+            .map(LinkDescriptor::new_unchecked)
+    }
+}
+
+/// A reader for a link information field (figure 7-64 in IEEE
+/// 802.15.4-2024).
+///
+/// ```notrust
+/// +----------+----------------+--------------+
+/// | Timeslot | Channel offset | Link options |
+/// +----------+----------------+--------------+
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct LinkDescriptor<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> LinkDescriptor<Bytes> {
+    /// Create a new [`LinkDescriptor`] reader from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match the length of a
+    /// link information field.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        if bytes.as_ref().len() != LINK_INFORMATION_LEN {
+            return Err(Error);
+        }
+
+        Ok(Self::new_unchecked(bytes))
+    }
+
+    /// Create a new [`LinkDescriptor`] reader from a given buffer without
+    /// length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Return the timeslot of the link.
+    pub fn timeslot(&self) -> u16 {
+        let b = self.bytes.as_ref();
+        u16::from_le_bytes([b[0], b[1]])
+    }
+
+    /// Return the channel offset of the link.
+    pub fn channel_offset(&self) -> u16 {
+        let b = self.bytes.as_ref();
+        u16::from_le_bytes([b[2], b[3]])
+    }
+
+    /// Return the link options.
+    pub fn link_options(&self) -> TschLinkOption {
+        TschLinkOption::from_bits_truncate(self.bytes.as_ref()[4])
+    }
+}
+
+/// A writable representation of a link information field.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LinkDescriptorRepr {
+    /// The timeslot of the link.
+    pub timeslot: u16,
+    /// The channel offset of the link.
+    pub channel_offset: u16,
+    /// The link options.
+    pub link_options: TschLinkOption,
+}
+
+impl LinkDescriptorRepr {
+    /// Writes the link information field into the given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match
+    /// [`LINK_INFORMATION_LEN`].
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<()> {
+        if buffer.len() != LINK_INFORMATION_LEN {
+            return Err(Error);
+        }
+
+        buffer[0..2].copy_from_slice(&self.timeslot.to_le_bytes());
+        buffer[2..4].copy_from_slice(&self.channel_offset.to_le_bytes());
+        buffer[4] = self.link_options.bits();
+        Ok(())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> From<&LinkDescriptor<Bytes>> for LinkDescriptorRepr {
+    fn from(link: &LinkDescriptor<Bytes>) -> Self {
+        Self {
+            timeslot: link.timeslot(),
+            channel_offset: link.channel_offset(),
+            link_options: link.link_options(),
+        }
+    }
+}
+
+/// A writable representation of a slotframe descriptor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SlotframeDescriptorRepr<'links> {
+    /// The slotframe handle.
+    pub handle: u8,
+    /// The slotframe size in timeslots.
+    pub size: u16,
+    /// The advertised links of the slotframe.
+    pub links: &'links [LinkDescriptorRepr],
+}
+
+impl SlotframeDescriptorRepr<'_> {
+    /// The length of the descriptor including all link information fields.
+    pub const fn length(&self) -> usize {
+        SLOTFRAME_DESCRIPTOR_HEADER_LEN + self.links.len() * LINK_INFORMATION_LEN
+    }
+
+    /// Writes the slotframe descriptor into the given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer length doesn't match
+    /// [`SlotframeDescriptorRepr::length()`] or if the slotframe advertises
+    /// more than 255 links.
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<()> {
+        if buffer.len() != self.length() {
+            return Err(Error);
+        }
+
+        buffer[0] = self.handle;
+        buffer[1..3].copy_from_slice(&self.size.to_le_bytes());
+        buffer[3] = u8::try_from(self.links.len()).map_err(|_| Error)?;
+
+        let link_fields =
+            buffer[SLOTFRAME_DESCRIPTOR_HEADER_LEN..].chunks_exact_mut(LINK_INFORMATION_LEN);
+        for (link, buffer) in self.links.iter().zip(link_fields) {
+            link.emit(buffer)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A writable representation of the content of a TSCH Slotframe and Link IE.
+///
+/// Slotframe descriptors and their links are borrowed so that a coordinator
+/// can advertise its schedule without copying it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TschSlotframeAndLinkRepr<'repr> {
+    /// The advertised slotframes.
+    pub slotframes: &'repr [SlotframeDescriptorRepr<'repr>],
+}
+
+impl TschSlotframeAndLinkRepr<'_> {
+    /// The content length of a TSCH Slotframe and Link IE advertising these
+    /// slotframes.
+    pub const fn content_length(&self) -> usize {
+        let mut content_length = 1;
+        let mut sf_idx = 0;
+        while sf_idx < self.slotframes.len() {
+            content_length += self.slotframes[sf_idx].length();
+            sf_idx += 1;
+        }
+        content_length
+    }
+
+    /// Writes the content of a TSCH Slotframe and Link IE.
+    ///
+    /// The buffer must have been sized with
+    /// [`TschSlotframeAndLinkRepr::content_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer does not match the content length or
+    /// if more than 255 slotframes or links per slotframe are given.
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<()> {
+        if buffer.len() != self.content_length() {
+            return Err(Error);
+        }
+
+        buffer[0] = u8::try_from(self.slotframes.len()).map_err(|_| Error)?;
+
+        let mut offset = 1;
+        for slotframe in self.slotframes {
+            let length = slotframe.length();
+            slotframe.emit(&mut buffer[offset..offset + length])?;
+            offset += length;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asn() {
+        let asn = Asn::new(0xab_1234_5678).unwrap();
+        assert_eq!(asn.to_le_bytes(), [0x78, 0x56, 0x34, 0x12, 0xab]);
+        assert_eq!(Asn::from_le_bytes(asn.to_le_bytes()), asn);
+        assert!(Asn::new(Asn::MAX.as_u64() + 1).is_none());
+
+        assert_eq!(Asn::MAX.wrapping_add(1), Asn::default());
+        assert_eq!(asn.wrapping_add(u64::MAX).as_u64(), 0xab_1234_5677);
+
+        let asn = Asn::new(4242).unwrap();
+        assert_eq!(asn.slot_offset(101), 4242 % 101);
+        assert_eq!(asn.channel_hopping_index(3, 16), (4242 + 3) % 16);
+        assert_eq!(Asn::MAX.slot_offset(7), (0xff_ffff_ffff_u64 % 7) as u16);
+    }
+
+    #[test]
+    fn synchronization_roundtrip() {
+        let repr = TschSynchronizationRepr {
+            asn: Asn::new(0x0e).unwrap(),
+            join_metric: 1,
+        };
+
+        let mut buffer = [0; TschSynchronizationRepr::CONTENT_LEN as usize];
+        repr.emit(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x0e, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        assert!(repr.emit(&mut [0; 5]).is_err());
+
+        let synchronization = TschSynchronization::new(&buffer).unwrap();
+        assert_eq!(synchronization.asn().as_u64(), 0x0e);
+        assert_eq!(synchronization.join_metric(), 1);
+        assert_eq!(TschSynchronizationRepr::from(&synchronization), repr);
+
+        assert!(TschSynchronization::new([0; 5]).is_err());
+    }
+
+    #[test]
+    fn timeslot_timings_roundtrip() {
+        let timings = TschTimeslotTimings::default();
+        assert!(!timings.requires_long_format());
+        let mut buffer = [0; TschTimeslotTimings::FULL_CONTENT_LEN as usize];
+        timings.emit(&mut buffer).unwrap();
+        assert_eq!(buffer[21..], [0xa0, 0x10, 0x10, 0x27]);
+
+        let parsed = TschTimeslot::new(&buffer).unwrap().timeslot_timings();
+        assert_eq!(parsed.id(), 0);
+        assert_eq!(parsed.rx_offset(), timings.rx_offset());
+        assert_eq!(parsed.max_tx(), timings.max_tx());
+        assert_eq!(parsed.timeslot_length(), timings.timeslot_length());
+    }
+
+    #[test]
+    fn validate_timeslot_timings() {
+        let phy = PhyParameters::OQPSK_2450MHZ;
+        let validate =
+            |timings: &TschTimeslotTimings| timings.validate(&phy, 20, Duration::new(10_000_000));
+        let with = |modify: fn(&mut TschTimeslotTimings)| {
+            let mut timings = TschTimeslotTimings::default();
+            modify(&mut timings);
+            validate(&timings)
+        };
+
+        let timings = TschTimeslotTimings::default();
+        assert_eq!(validate(&timings), Ok(()));
+        // Both clocks drift apart by 2400µs within a minute.
+        assert_eq!(
+            timings.validate(&phy, 20, Duration::new(60_000_000)),
+            Err(TimeslotTimingViolation::GuardTime)
+        );
+        // SUN PHYs need longer CCAs and turnaround times.
+        assert_eq!(
+            timings.validate(
+                &PhyParameters::SUN_FSK_50KBPS,
+                20,
+                Duration::new(10_000_000)
+            ),
+            Err(TimeslotTimingViolation::Cca)
+        );
+
+        assert_eq!(
+            with(|timings| timings.set_cca_offset(Duration::new(-1))),
+            Err(TimeslotTimingViolation::NegativeTiming)
+        );
+        assert_eq!(
+            with(|timings| timings.set_rx_tx(Duration::new(100))),
+            Err(TimeslotTimingViolation::Turnaround)
+        );
+        assert_eq!(
+            with(|timings| timings.set_max_tx(Duration::new(4_000))),
+            Err(TimeslotTimingViolation::MaxTx)
+        );
+        assert_eq!(
+            with(|timings| timings.set_rx_offset(Duration::new(2_200))),
+            Err(TimeslotTimingViolation::RxWait)
+        );
+        assert_eq!(
+            with(|timings| timings.set_tx_ack_delay(Duration::new(1_300))),
+            Err(TimeslotTimingViolation::AckWait)
+        );
+        assert_eq!(
+            with(|timings| timings.set_timeslot_length(Duration::new(9_000))),
+            Err(TimeslotTimingViolation::TimeslotLength)
+        );
+    }
+
+    #[test]
+    fn long_timeslot_timings() {
+        let mut timings = TschTimeslotTimings::new(1, TschTimeslotTimings::DEFAULT_GUARD_TIME);
+        timings.set_max_tx(Duration::new(70_000));
+        timings.set_timeslot_length(Duration::new(100_000));
+        assert!(timings.requires_long_format());
+        assert_eq!(
+            timings.content_length(),
+            TschTimeslotTimings::FULL_CONTENT_LEN_LONG
+        );
+
+        let mut buffer = [0; TschTimeslotTimings::FULL_CONTENT_LEN as usize];
+        assert!(timings.emit(&mut buffer).is_err());
+
+        let mut buffer = [0; TschTimeslotTimings::FULL_CONTENT_LEN_LONG as usize];
+        timings.emit(&mut buffer).unwrap();
+        assert_eq!(buffer[21..], [0x70, 0x11, 0x01, 0xa0, 0x86, 0x01]);
+
+        let parsed = TschTimeslot::new(&buffer).unwrap().timeslot_timings();
+        assert_eq!(parsed.id(), 1);
+        assert_eq!(parsed.max_ack(), timings.max_ack());
+        assert_eq!(parsed.max_tx().ticks(), 70_000);
+        assert_eq!(parsed.timeslot_length().ticks(), 100_000);
+
+        timings.set_timeslot_length(Duration::new(0x100_0000));
+        assert!(timings.emit(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn short_timeslot() {
+        let timeslot = TschTimeslot::new([0]).unwrap();
+        assert_eq!(timeslot.id(), 0);
+        assert_eq!(
+            timeslot.timeslot_timings().timeslot_length().ticks(),
+            10_000
+        );
+
+        assert!(TschTimeslot::new([0; 2]).is_err());
+    }
+
+    #[test]
+    fn slotframe_and_link_roundtrip() {
+        const LINKS: [LinkDescriptorRepr; 2] = [
+            LinkDescriptorRepr {
+                timeslot: 0,
+                channel_offset: 0,
+                link_options: TschLinkOption::Tx
+                    .union(TschLinkOption::Rx)
+                    .union(TschLinkOption::Shared)
+                    .union(TschLinkOption::TimeKeeping),
+            },
+            LinkDescriptorRepr {
+                timeslot: 0x0102,
+                channel_offset: 3,
+                link_options: TschLinkOption::Rx,
+            },
+        ];
+        let slotframes = [
+            SlotframeDescriptorRepr {
+                handle: 0,
+                size: 101,
+                links: &LINKS,
+            },
+            SlotframeDescriptorRepr {
+                handle: 1,
+                size: 7,
+                links: &[],
+            },
+        ];
+        let repr = TschSlotframeAndLinkRepr {
+            slotframes: &slotframes,
+        };
+
+        // The length must be consistent with the IE representation used to
+        // size frames.
+        let (_, nested_ie_len) =
+            crate::repr::IeRepr::TschSlotframeAndLinkNestedIe(&[2, 0]).length();
+        assert_eq!(repr.content_length(), 19);
+        if cfg!(feature = "ies") {
+            assert_eq!(repr.content_length() + 2, nested_ie_len as usize);
+        }
+
+        let mut buffer = [0; 19];
+        repr.emit(&mut buffer).unwrap();
+        assert_eq!(
+            buffer,
+            [
+                0x02, // number of slotframes
+                0x00, 0x65, 0x00, 0x02, // slotframe 0
+                0x00, 0x00, 0x00, 0x00, 0x0f, // link 0
+                0x02, 0x01, 0x03, 0x00, 0x02, // link 1
+                0x01, 0x07, 0x00, 0x00, // slotframe 1
+            ]
+        );
+        assert!(repr.emit(&mut buffer[..18]).is_err());
+
+        let slotframe_and_link = TschSlotframeAndLink::new(&buffer).unwrap();
+        assert_eq!(slotframe_and_link.number_of_slotframes(), 2);
+        let mut descriptors = slotframe_and_link.slotframe_descriptors();
+        for expected in slotframes {
+            let descriptor = descriptors.next().unwrap();
+            assert_eq!(descriptor.handle(), expected.handle);
+            assert_eq!(descriptor.size(), expected.size);
+            assert_eq!(descriptor.number_of_links() as usize, expected.links.len());
+            assert!(descriptor
+                .links()
+                .map(|link| LinkDescriptorRepr::from(&link))
+                .eq(expected.links.iter().copied()));
+        }
+        assert!(descriptors.next().is_none());
+
+        assert!(TschSlotframeAndLink::new(&buffer[..18]).is_err());
+        assert!(TschSlotframeAndLink::new(&[]).is_err());
+        assert!(TschSlotframeAndLink::new(&[0, 0]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn timeslot_timings_serde() {
+        let mut timings = TschTimeslotTimings::new(1, TschTimeslotTimings::DEFAULT_GUARD_TIME);
+        timings.set_max_tx(Duration::new(70_000));
+
+        let json = serde_json::to_string(&timings).unwrap();
+        assert!(json.contains(r#""max_tx":70000"#));
+
+        let parsed: TschTimeslotTimings = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.id(), 1);
+        assert_eq!(parsed.max_tx(), timings.max_tx());
+        assert_eq!(parsed.timeslot_length(), timings.timeslot_length());
+    }
+}
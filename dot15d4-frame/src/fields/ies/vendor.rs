@@ -0,0 +1,333 @@
+//! Vendor Specific IE field access (IEEE 802.15.4-2024, section 7.4).
+//!
+//! Vendor Specific header, payload and nested IEs share the same content
+//! structure:
+//!
+//! ```notrust
+//! +--------------+-------------------------+
+//! | Vendor OUI   | Vendor specific content |
+//! +--------------+-------------------------+
+//! ```
+//!
+//! Downstream crates implement [`VendorIeCodec`] to decode and encode the
+//! vendor specific content of IEs carrying their OUI instead of dealing with
+//! opaque byte slices. IEs yielded while streaming over IE lists are decoded
+//! directly, see [`HeaderIe::decode_vendor()`](super::HeaderIe::decode_vendor),
+//! [`PayloadIe::decode_vendor()`](super::PayloadIe::decode_vendor) and
+//! [`NestedIe::decode_vendor()`](super::NestedIe::decode_vendor).
+
+use dot15d4_util::{Error, Result};
+
+/// The element ID of the Vendor Specific header IE.
+pub const VENDOR_SPECIFIC_HEADER_IE_ELEMENT_ID: u8 = 0x00;
+
+/// The group ID of the Vendor Specific payload IE.
+pub const VENDOR_SPECIFIC_PAYLOAD_IE_GROUP_ID: u8 = 0x2;
+
+/// The nested IE sub-ID of the Vendor Specific nested IE (long format).
+pub const VENDOR_SPECIFIC_NESTED_IE_SUB_ID: u8 = 0x8;
+
+/// The length of the vendor OUI in octets.
+pub const VENDOR_OUI_LEN: usize = 3;
+
+/// The IE type a vendor specific IE was found in or will be written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VendorIeKind {
+    /// Vendor Specific header IE.
+    Header,
+    /// Vendor Specific payload IE.
+    Payload,
+    /// Vendor Specific nested IE.
+    Nested,
+}
+
+/// A decoder/encoder for vendor specific IE content.
+///
+/// Each codec is keyed by the vendor OUI. Codecs only ever see the content
+/// following the OUI.
+pub trait VendorIeCodec {
+    /// The vendor OUI of IEs handled by this codec.
+    const OUI: [u8; VENDOR_OUI_LEN];
+
+    /// The structured representation of the vendor specific content.
+    type Ie<'ie>;
+
+    /// Decodes the vendor specific content of an IE of the given kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is malformed.
+    fn decode(kind: VendorIeKind, content: &[u8]) -> Result<Self::Ie<'_>>;
+
+    /// Returns the length of the encoded vendor specific content (w/o OUI).
+    fn content_length(kind: VendorIeKind, ie: &Self::Ie<'_>) -> u16;
+
+    /// Encodes the vendor specific content of an IE of the given kind.
+    ///
+    /// The buffer has been sized with [`VendorIeCodec::content_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the IE cannot be encoded.
+    fn encode(kind: VendorIeKind, ie: &Self::Ie<'_>, content: &mut [u8]) -> Result<()>;
+}
+
+/// Calculates the content length of a Vendor Specific IE (incl. OUI) that
+/// carries the given vendor specific IE.
+pub fn vendor_specific_content_length<Codec: VendorIeCodec>(
+    kind: VendorIeKind,
+    ie: &Codec::Ie<'_>,
+) -> u16 {
+    VENDOR_OUI_LEN as u16 + Codec::content_length(kind, ie)
+}
+
+/// Decodes the content of a Vendor Specific IE (incl. OUI) of the given kind
+/// with the given codec.
+///
+/// Returns [`None`] if the IE carries a different OUI than the one handled by
+/// the codec.
+pub(crate) fn decode_vendor_specific<'ie, Codec: VendorIeCodec>(
+    kind: VendorIeKind,
+    content: &'ie [u8],
+) -> Option<Result<Codec::Ie<'ie>>> {
+    let vendor_specific = match VendorSpecific::new(content) {
+        Ok(vendor_specific) => vendor_specific,
+        Err(e) => return Some(Err(e)),
+    };
+    if vendor_specific.oui() != Codec::OUI {
+        return None;
+    }
+
+    Some(Codec::decode(kind, &content[VENDOR_OUI_LEN..]))
+}
+
+/// A reader/writer for the content of a Vendor Specific header, payload or
+/// nested IE.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VendorSpecific<Bytes> {
+    bytes: Bytes,
+}
+
+impl<Bytes: AsRef<[u8]>> VendorSpecific<Bytes> {
+    /// Create a new [`VendorSpecific`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the vendor OUI.
+    pub fn new(bytes: Bytes) -> Result<Self> {
+        let vendor_specific = Self::new_unchecked(bytes);
+
+        if !vendor_specific.check_len() {
+            return Err(Error);
+        }
+
+        Ok(vendor_specific)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the vendor OUI.
+    fn check_len(&self) -> bool {
+        self.bytes.as_ref().len() >= VENDOR_OUI_LEN
+    }
+
+    /// Create a new [`VendorSpecific`] reader/writer from a given buffer
+    /// without length checking.
+    pub const fn new_unchecked(bytes: Bytes) -> Self {
+        Self { bytes }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> Bytes {
+        self.bytes
+    }
+
+    /// Returns the vendor OUI.
+    pub fn oui(&self) -> [u8; VENDOR_OUI_LEN] {
+        let b = &self.bytes.as_ref()[..VENDOR_OUI_LEN];
+        [b[0], b[1], b[2]]
+    }
+
+    /// Returns the vendor specific content following the OUI.
+    pub fn content(&self) -> &[u8] {
+        &self.bytes.as_ref()[VENDOR_OUI_LEN..]
+    }
+
+    /// Decodes the vendor specific content with the given codec.
+    ///
+    /// Returns [`None`] if the IE carries a different OUI than the one handled
+    /// by the codec.
+    pub fn decode<Codec: VendorIeCodec>(
+        &self,
+        kind: VendorIeKind,
+    ) -> Option<Result<Codec::Ie<'_>>> {
+        decode_vendor_specific::<Codec>(kind, self.bytes.as_ref())
+    }
+}
+
+impl<Bytes: AsRef<[u8]> + AsMut<[u8]>> VendorSpecific<Bytes> {
+    /// Set the vendor OUI.
+    pub fn set_oui(&mut self, oui: [u8; VENDOR_OUI_LEN]) {
+        self.bytes.as_mut()[..VENDOR_OUI_LEN].copy_from_slice(&oui);
+    }
+
+    /// Returns the mutable vendor specific content following the OUI.
+    pub fn content_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes.as_mut()[VENDOR_OUI_LEN..]
+    }
+
+    /// Writes the OUI and the encoded vendor specific IE.
+    ///
+    /// The buffer must have been sized with
+    /// [`vendor_specific_content_length()`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer does not match the content length or
+    /// the codec fails to encode the IE.
+    pub fn emit<Codec: VendorIeCodec>(
+        &mut self,
+        kind: VendorIeKind,
+        ie: &Codec::Ie<'_>,
+    ) -> Result<()> {
+        if self.bytes.as_ref().len() != vendor_specific_content_length::<Codec>(kind, ie) as usize {
+            return Err(Error);
+        }
+
+        self.set_oui(Codec::OUI);
+        Codec::encode(kind, ie, self.content_mut())
+    }
+}
+
+impl<Bytes: AsRef<[u8]>> core::fmt::Display for VendorSpecific<Bytes> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let [oui0, oui1, oui2] = self.oui();
+        writeln!(f, "oui: {oui0:02x}-{oui1:02x}-{oui2:02x}")?;
+        writeln!(f, "{:indent$}content: {:x?}", "", self.content())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A vendor IE carrying a single little-endian u16 counter.
+    struct CounterCodec;
+
+    impl VendorIeCodec for CounterCodec {
+        const OUI: [u8; VENDOR_OUI_LEN] = [0x00, 0x12, 0x4b];
+
+        type Ie<'ie> = u16;
+
+        fn decode(_kind: VendorIeKind, content: &[u8]) -> Result<u16> {
+            match content {
+                [lsb, msb] => Ok(u16::from_le_bytes([*lsb, *msb])),
+                _ => Err(Error),
+            }
+        }
+
+        fn content_length(_kind: VendorIeKind, _ie: &u16) -> u16 {
+            2
+        }
+
+        fn encode(_kind: VendorIeKind, ie: &u16, content: &mut [u8]) -> Result<()> {
+            content.copy_from_slice(&ie.to_le_bytes());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn decode() {
+        let content = [0x00, 0x12, 0x4b, 0x34, 0x12];
+        let vendor_specific = VendorSpecific::new(&content).unwrap();
+        assert_eq!(vendor_specific.oui(), [0x00, 0x12, 0x4b]);
+        assert_eq!(vendor_specific.content(), &[0x34, 0x12]);
+        assert!(matches!(
+            vendor_specific.decode::<CounterCodec>(VendorIeKind::Payload),
+            Some(Ok(0x1234))
+        ));
+
+        let content = [0x00, 0x12, 0x4b, 0x34];
+        let vendor_specific = VendorSpecific::new(&content).unwrap();
+        assert!(matches!(
+            vendor_specific.decode::<CounterCodec>(VendorIeKind::Header),
+            Some(Err(_))
+        ));
+
+        let content = [0xac, 0xde, 0x48, 0x34, 0x12];
+        let vendor_specific = VendorSpecific::new(&content).unwrap();
+        assert!(vendor_specific
+            .decode::<CounterCodec>(VendorIeKind::Nested)
+            .is_none());
+
+        assert!(VendorSpecific::new(&content[..2]).is_err());
+    }
+
+    #[test]
+    fn decode_from_ie_lists() {
+        use super::super::{HeaderIes, NestedIes, PayloadIes};
+
+        let bytes = [
+            0x02, 0x0f, 0x14, 0x00, // Time Correction
+            0x05, 0x00, 0x00, 0x12, 0x4b, 0x34, 0x12, // Vendor Specific
+            0x01, 0x00, 0xaa, // Vendor Specific with truncated OUI
+        ];
+        let mut header_ies = HeaderIes::new(&bytes);
+        let time_correction = header_ies.next().unwrap().unwrap();
+        assert!(time_correction.decode_vendor::<CounterCodec>().is_none());
+        let vendor = header_ies.next().unwrap().unwrap();
+        assert!(matches!(
+            vendor.decode_vendor::<CounterCodec>(),
+            Some(Ok(0x1234))
+        ));
+        let vendor = header_ies.next().unwrap().unwrap();
+        assert!(matches!(
+            vendor.decode_vendor::<CounterCodec>(),
+            Some(Err(_))
+        ));
+
+        let bytes = [
+            0x05, 0x90, 0xac, 0xde, 0x48, 0x34, 0x12, // Vendor Specific, other OUI
+            0x05, 0x90, 0x00, 0x12, 0x4b, 0x34, 0x12, // Vendor Specific
+        ];
+        let mut payload_ies = PayloadIes::new(&bytes);
+        let vendor = payload_ies.next().unwrap().unwrap();
+        assert!(vendor.decode_vendor::<CounterCodec>().is_none());
+        let vendor = payload_ies.next().unwrap().unwrap();
+        assert!(matches!(
+            vendor.decode_vendor::<CounterCodec>(),
+            Some(Ok(0x1234))
+        ));
+
+        let bytes = [
+            0x01, 0x1a, 0x00, // TSCH Synchronization (short format)
+            0x05, 0xc0, 0x00, 0x12, 0x4b, 0x34, 0x12, // Vendor Specific
+        ];
+        let mut nested_ies = NestedIes::new(&bytes);
+        let sync = nested_ies.next().unwrap().unwrap();
+        assert!(sync.decode_vendor::<CounterCodec>().is_none());
+        let vendor = nested_ies.next().unwrap().unwrap();
+        assert!(matches!(
+            vendor.decode_vendor::<CounterCodec>(),
+            Some(Ok(0x1234))
+        ));
+    }
+
+    #[test]
+    fn emit() {
+        let len = vendor_specific_content_length::<CounterCodec>(VendorIeKind::Header, &0x1234);
+        assert_eq!(len, 5);
+
+        let mut content = [0; 5];
+        let mut vendor_specific = VendorSpecific::new_unchecked(&mut content);
+        vendor_specific
+            .emit::<CounterCodec>(VendorIeKind::Header, &0x1234)
+            .unwrap();
+        assert_eq!(content, [0x00, 0x12, 0x4b, 0x34, 0x12]);
+
+        let mut content = [0; 4];
+        let mut vendor_specific = VendorSpecific::new_unchecked(&mut content);
+        assert!(vendor_specific
+            .emit::<CounterCodec>(VendorIeKind::Header, &0x1234)
+            .is_err());
+    }
+}
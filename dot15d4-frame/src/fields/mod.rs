@@ -21,9 +21,17 @@
 //! both directions - including critical validations and conversions - saving
 //! code size on small embedded devices.
 
+mod beacon;
+#[cfg(feature = "alloc")]
+mod dissect;
 mod field_ranges;
 mod ies;
 mod mpdu;
+mod summary;
 
+pub use beacon::*;
+#[cfg(feature = "alloc")]
+pub use dissect::*;
 pub use ies::*;
 pub use mpdu::*;
+pub use summary::*;
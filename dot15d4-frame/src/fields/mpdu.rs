@@ -1,3 +1,5 @@
+use core::ops::Range;
+
 use dot15d4_driver::{
     frame::{
         AddressingFields, AddressingMode, AddressingRepr, FrameControl, FrameType, FrameVersion,
@@ -16,8 +18,8 @@ use crate::repr::IeListRepr;
 use crate::{
     mpdu::MpduFrame,
     repr::{MpduRepr, SeqNrRepr},
-    MpduParsedUpToAddressing, MpduParsedUpToSecurity, MpduWithAddressing, MpduWithAllFields,
-    MpduWithFrameControl, MpduWithIes, MpduWithSecurity,
+    FrameError, FrameErrorKind, MpduParsedUpToAddressing, MpduParsedUpToSecurity,
+    MpduWithAddressing, MpduWithAllFields, MpduWithFrameControl, MpduWithIes, MpduWithSecurity,
 };
 
 use super::field_ranges::MpduFieldRanges;
@@ -45,27 +47,41 @@ impl MpduFrame {
     }
 
     /// Reads the sequence number field.
+    ///
+    /// Returns [`None`] if the sequence number is suppressed or the MPDU is too
+    /// short to contain it.
     pub fn sequence_number(&self) -> Option<u8> {
         if self.frame_control().sequence_number_suppression() {
             return None;
         }
 
-        // Safety: We now know for sure that a sequence number is present.
         let mpdu_field_ranges = MpduFieldRanges::new(self.offset, SeqNrRepr::Yes);
-        Some(self.buffer[mpdu_field_ranges.offset_seq_nr().get() as usize])
+        let offset_seq_nr = mpdu_field_ranges.offset_seq_nr().get() as usize;
+        if offset_seq_nr >= self.pdu_end_wo_fcs() {
+            return None;
+        }
+
+        self.buffer.get(offset_seq_nr).copied()
     }
 
     /// Writes the sequence number field.
     ///
-    /// Safety: Requires the sequence number suppression field of the frame
-    ///         control field to be false.
+    /// # Errors
+    ///
+    /// Returns an error if the sequence number is suppressed or the MPDU is too
+    /// short to contain it. Suppression is decided when the MPDU is built, see
+    /// [`crate::repr::MpduRepr::with_frame_control()`].
     pub fn set_sequence_number(&mut self, seq_nr: u8) -> SimplifiedResult<()> {
         if self.frame_control().sequence_number_suppression() {
             return Err(Error);
         }
 
         let mpdu_field_ranges = MpduFieldRanges::new(self.offset, SeqNrRepr::Yes);
-        self.buffer[mpdu_field_ranges.offset_seq_nr().get() as usize] = seq_nr;
+        let offset_seq_nr = mpdu_field_ranges.offset_seq_nr().get() as usize;
+        if offset_seq_nr >= self.pdu_end_wo_fcs() {
+            return Err(Error);
+        }
+        *self.buffer.get_mut(offset_seq_nr).ok_or(Error)? = seq_nr;
 
         Ok(())
     }
@@ -88,6 +104,17 @@ impl MpduFrame {
         }
     }
 
+    /// Like [`MpduFrame::reader()`] but rejects MPDUs with invalid FCS.
+    ///
+    /// Use this on the Rx path of drivers that do not check the FCS in
+    /// hardware. See [`MpduFrame::check_fcs()`] for details.
+    pub fn reader_with_valid_fcs<Config: DriverConfig>(
+        &self,
+    ) -> Result<MpduParser<&MpduFrame, MpduWithFrameControl>, FrameError> {
+        self.check_fcs::<Config>()?;
+        Ok(self.reader())
+    }
+
     /// Initializes a partially parsed MPDU with read-only access to the frame
     /// control and sequence number fields from an unparsed MPDU.
     ///
@@ -270,7 +297,17 @@ impl<ReadOnlyMpdu: AsRef<MpduFrame>, State> MpduParser<ReadOnlyMpdu, State> {
     /// TODO: This is part of the incoming frame procedure. Verify that all
     ///       checks are properly executed here.
     pub fn is_valid(&self) -> bool {
-        self.frame_control().is_valid()
+        // The MPDU must at least contain the frame control and sequence
+        // number fields.
+        let pdu_end_wo_fcs = self.mpdu.as_ref().pdu_end_wo_fcs();
+        if self.mpdu_field_ranges.range_frame_control().end > pdu_end_wo_fcs {
+            return false;
+        }
+
+        let frame_control = self.frame_control();
+        let offset_addressing = self.mpdu_field_ranges.offset_seq_nr().get() as usize
+            + !frame_control.sequence_number_suppression() as usize;
+        offset_addressing <= pdu_end_wo_fcs && frame_control.is_valid()
     }
 }
 
@@ -335,21 +372,28 @@ impl<State> MpduParser<MpduFrame, State> {
 impl<ReadOnlyMpdu: AsRef<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithFrameControl> {
     /// Parses the frame control field to identify the addressing configuration
     /// of the MPDU.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame control field contains an invalid
+    /// addressing configuration or the MPDU is too short to contain the
+    /// addressing fields.
     pub fn parse_addressing(
         self,
-    ) -> SimplifiedResult<MpduParser<ReadOnlyMpdu, MpduWithAddressing>> {
-        let addressing = match AddressingRepr::from_frame_control(self.frame_control()) {
-            Ok(result) => result,
-            Err(_) => return Err(Error),
-        };
+    ) -> Result<MpduParser<ReadOnlyMpdu, MpduWithAddressing>, FrameError> {
+        let addressing =
+            AddressingRepr::from_frame_control(self.frame_control()).map_err(|e| e.at(0))?;
         let mpdu_field_ranges = if let Some(addressing) = addressing {
             match self.mpdu_field_ranges.with_addressing(addressing) {
                 Ok(result) => result,
-                Err(_) => return Err(Error),
+                Err(_) => {
+                    return Err(FrameError::new(FrameErrorKind::InvalidAddressingCombination).at(0))
+                }
             }
         } else {
             self.mpdu_field_ranges.without_addressing()
         };
+        check_within_mpdu(self.mpdu.as_ref(), mpdu_field_ranges.range_addressing())?;
 
         Ok(MpduParser {
             mpdu_field_ranges,
@@ -364,21 +408,28 @@ impl<ReadWriteMpdu: AsRef<MpduFrame> + AsMut<MpduFrame>>
 {
     /// Parses the frame control field to identify the addressing configuration
     /// of the MPDU.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the frame control field contains an invalid
+    /// addressing configuration or the MPDU is too short to contain the
+    /// addressing fields.
     pub fn parse_addressing_mut(
         self,
-    ) -> SimplifiedResult<MpduParser<ReadWriteMpdu, MpduWithAddressing>> {
-        let addressing = match AddressingRepr::from_frame_control(self.frame_control()) {
-            Ok(result) => result,
-            Err(_) => return Err(Error),
-        };
+    ) -> Result<MpduParser<ReadWriteMpdu, MpduWithAddressing>, FrameError> {
+        let addressing =
+            AddressingRepr::from_frame_control(self.frame_control()).map_err(|e| e.at(0))?;
         let mpdu_field_ranges = if let Some(addressing) = addressing {
             match self.mpdu_field_ranges.with_addressing(addressing) {
                 Ok(result) => result,
-                Err(_) => return Err(Error),
+                Err(_) => {
+                    return Err(FrameError::new(FrameErrorKind::InvalidAddressingCombination).at(0))
+                }
             }
         } else {
             self.mpdu_field_ranges.without_addressing()
         };
+        check_within_mpdu(self.mpdu.as_ref(), mpdu_field_ranges.range_addressing())?;
 
         Ok(MpduParser {
             mpdu_field_ranges,
@@ -391,14 +442,21 @@ impl<ReadWriteMpdu: AsRef<MpduFrame> + AsMut<MpduFrame>>
 impl<ReadOnlyMpdu: AsRef<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithAddressing> {
     /// Parses the frame control field to identify the security configuration of
     /// the MPDU.
-    pub fn parse_security(self) -> MpduParser<ReadOnlyMpdu, MpduWithSecurity> {
+    ///
+    /// # Errors
+    ///
+    /// Secured frames are not supported, yet, and will be rejected with
+    /// [`FrameErrorKind::SecurityNotSupported`].
+    pub fn parse_security(self) -> Result<MpduParser<ReadOnlyMpdu, MpduWithSecurity>, FrameError> {
         // TODO: implement
-        debug_assert!(!self.frame_control().security_enabled());
+        if self.frame_control().security_enabled() {
+            return Err(FrameError::new(FrameErrorKind::SecurityNotSupported).at(0));
+        }
 
-        MpduParser {
+        Ok(MpduParser {
             mpdu_field_ranges: self.mpdu_field_ranges.without_security(),
             mpdu: self.mpdu,
-        }
+        })
     }
 }
 
@@ -406,19 +464,37 @@ impl<ReadOnlyMpdu: AsRef<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithAddressing
 impl<ReadOnlyMpdu: AsRef<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithSecurity> {
     /// Parses the frame control and information element fields to identify the
     /// information elements of the MPDU.
+    ///
+    /// # Errors
+    ///
+    /// Frames containing IEs are not supported, yet, and will be rejected with
+    /// [`FrameErrorKind::IesNotSupported`]. Returns
+    /// [`FrameErrorKind::BufferTooShort`] if the MPDU is too short to contain
+    /// the MAC header.
     pub fn parse_ies<Config: DriverConfig>(
         self,
-    ) -> SimplifiedResult<MpduParser<ReadOnlyMpdu, MpduWithAllFields>> {
+    ) -> Result<MpduParser<ReadOnlyMpdu, MpduWithAllFields>, FrameError> {
         // TODO: implement
-        debug_assert!(!self.frame_control().information_elements_present());
+        if self.frame_control().information_elements_present() {
+            return Err(FrameError::new(FrameErrorKind::IesNotSupported).at(0));
+        }
 
-        let mpdu_length_wo_fcs = self.mpdu.as_ref().pdu_length_wo_fcs();
+        let mpdu = self.mpdu.as_ref();
+        let mpdu_length_wo_fcs = mpdu.pdu_length_wo_fcs();
         let mpdu_field_ranges = match self
             .mpdu_field_ranges
             .without_ies_with_mpdu_length::<Config>(mpdu_length_wo_fcs)
         {
             Ok(result) => result,
-            Err(_) => return Err(Error),
+            Err(_) => {
+                let offset_mpdu = mpdu.offset as usize;
+                let needed = self.mpdu_field_ranges.last_offset() as usize - offset_mpdu;
+                return Err(FrameError::new(FrameErrorKind::BufferTooShort {
+                    needed,
+                    got: mpdu_length_wo_fcs as usize,
+                })
+                .at(mpdu_length_wo_fcs as usize));
+            }
         };
 
         Ok(MpduParser {
@@ -443,7 +519,11 @@ impl<ReadOnlyMpdu: AsRef<MpduFrame>, State: MpduParsedUpToAddressing>
         //         frame control field.
         let addressing_fields = unsafe {
             AddressingFields::new_unchecked(
-                &self.mpdu.as_ref().buffer[range_addressing],
+                self.mpdu
+                    .as_ref()
+                    .buffer
+                    .get(range_addressing)
+                    .ok_or(Error)?,
                 addressing_repr,
             )?
         };
@@ -469,7 +549,11 @@ impl<ReadWriteMpdu: AsRef<MpduFrame> + AsMut<MpduFrame>, State: MpduParsedUpToAd
         //         frame control field.
         let addressing_fields = unsafe {
             AddressingFields::new_unchecked(
-                &mut self.mpdu.as_mut().buffer[range_addressing],
+                self.mpdu
+                    .as_mut()
+                    .buffer
+                    .get_mut(range_addressing)
+                    .ok_or(Error)?,
                 addressing_repr,
             )?
         };
@@ -490,7 +574,10 @@ impl<'mpdu, State: MpduParsedUpToAddressing> MpduParser<&'mpdu MpduFrame, State>
         // Safety: Addressing representation and range are both synced with the
         //         frame control field.
         let addressing_fields = unsafe {
-            AddressingFields::new_unchecked(&self.mpdu.buffer[range_addressing], addressing_repr)?
+            AddressingFields::new_unchecked(
+                self.mpdu.buffer.get(range_addressing).ok_or(Error)?,
+                addressing_repr,
+            )?
         };
 
         Ok(Some(addressing_fields))
@@ -508,11 +595,17 @@ impl<ReadOnlyMpdu: AsRef<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithAllFields>
     // TODO: Add access to IEs.
 
     pub fn frame_payload(&self) -> Option<&[u8]> {
-        Some(&self.mpdu.as_ref().buffer[self.mpdu_field_ranges.range_frame_payload()?])
+        self.mpdu
+            .as_ref()
+            .buffer
+            .get(self.mpdu_field_ranges.range_frame_payload()?)
     }
 
     pub fn fcs(&self) -> Option<&[u8]> {
-        Some(&self.mpdu.as_ref().buffer[self.mpdu_field_ranges.range_fcs()?])
+        self.mpdu
+            .as_ref()
+            .buffer
+            .get(self.mpdu_field_ranges.range_fcs()?)
     }
 }
 
@@ -521,11 +614,30 @@ impl<ReadOnlyMpdu: AsMut<MpduFrame>> MpduParser<ReadOnlyMpdu, MpduWithAllFields>
     // TODO: Add access to IEs.
 
     pub fn frame_payload_mut(&mut self) -> Option<&mut [u8]> {
-        Some(&mut self.mpdu.as_mut().buffer[self.mpdu_field_ranges.range_frame_payload()?])
+        let range_frame_payload = self.mpdu_field_ranges.range_frame_payload()?;
+        self.mpdu.as_mut().buffer.get_mut(range_frame_payload)
     }
 
     pub fn fcs_mut(&mut self) -> Option<&mut [u8]> {
-        Some(&mut self.mpdu.as_mut().buffer[self.mpdu_field_ranges.range_fcs()?])
+        let range_fcs = self.mpdu_field_ranges.range_fcs()?;
+        self.mpdu.as_mut().buffer.get_mut(range_fcs)
+    }
+}
+
+/// Rejects MPDUs that are too short to contain the given field.
+///
+/// The error offset points to the start of the field within the MPDU.
+fn check_within_mpdu(mpdu: &MpduFrame, range: Option<Range<usize>>) -> Result<(), FrameError> {
+    match range {
+        Some(range) if range.end > mpdu.pdu_end_wo_fcs() => {
+            let offset_mpdu = mpdu.offset as usize;
+            Err(FrameError::new(FrameErrorKind::BufferTooShort {
+                needed: range.end - offset_mpdu,
+                got: mpdu.pdu_length_wo_fcs() as usize,
+            })
+            .at(range.start - offset_mpdu))
+        }
+        _ => Ok(()),
     }
 }
 
@@ -0,0 +1,394 @@
+use dot15d4_driver::frame::{
+    Address, AddressingFields, AddressingRepr, FrameControl, FrameType, FrameVersion, PanId,
+};
+
+use super::IE_HEADER_LEN;
+use crate::{FrameError, FrameErrorKind};
+
+pub(super) const FRAME_CONTROL_LEN: usize = 2;
+
+pub(super) const HEADER_TERMINATION_IE_1: u8 = 0x7e;
+pub(super) const HEADER_TERMINATION_IE_2: u8 = 0x7f;
+pub(super) const PAYLOAD_TERMINATION_IE: u8 = 0xf;
+
+/// A lightweight summary of an MPDU, intended for compact logging.
+///
+/// The summary is produced in a single pass over the MPDU without allocation
+/// and without the full parser typestate machinery. It only contains the
+/// information typically needed to follow frame exchanges in a log: frame
+/// type, sequence number, addressing, number of IEs and payload length.
+///
+/// The RSSI and LQI are not part of the MPDU. They remain empty after parsing
+/// and may be set from the reception metadata of the radio driver, see
+/// [`FrameSummary::with_rssi()`] and [`FrameSummary::with_lqi()`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FrameSummary<'frame> {
+    /// The frame type.
+    pub frame_type: FrameType,
+    /// The frame version.
+    pub frame_version: FrameVersion,
+    /// The sequence number, if not suppressed.
+    pub sequence_number: Option<u8>,
+    /// The destination PAN ID, if not elided.
+    pub dst_pan_id: Option<PanId<&'frame [u8]>>,
+    /// The destination address.
+    pub dst_address: Address<&'frame [u8]>,
+    /// The source PAN ID, if not elided.
+    pub src_pan_id: Option<PanId<&'frame [u8]>>,
+    /// The source address.
+    pub src_address: Address<&'frame [u8]>,
+    /// Whether the frame is secured.
+    pub security_enabled: bool,
+    /// The number of header IEs, not counting termination IEs.
+    pub header_ie_count: u8,
+    /// The number of payload IEs, not counting termination IEs. Payload IEs of
+    /// encrypted frames cannot be counted, they are part of the payload
+    /// length instead.
+    pub payload_ie_count: u8,
+    /// The length of the frame payload (excluding IEs and MIC).
+    pub payload_length: u16,
+    /// The received signal strength in dBm, if known.
+    pub rssi: Option<i8>,
+    /// The link quality indicator, if known.
+    pub lqi: Option<u8>,
+}
+
+impl<'frame> FrameSummary<'frame> {
+    /// Summarizes the given MPDU.
+    ///
+    /// The MPDU must not contain the FCS.
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::BufferTooShort`] if the MPDU is shorter than
+    ///   announced by its header fields,
+    /// - [`FrameErrorKind::InvalidFrameVersion`] or
+    ///   [`FrameErrorKind::InvalidAddressingCombination`] if the addressing
+    ///   fields cannot be derived from the frame control field,
+    /// - [`FrameErrorKind::TruncatedIe`] or [`FrameErrorKind::IeExceedsFrame`]
+    ///   if an IE is truncated.
+    pub fn parse(mpdu: &'frame [u8]) -> Result<Self, FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        let mut offset = FRAME_CONTROL_LEN;
+
+        let sequence_number = if frame_control.sequence_number_suppression() {
+            None
+        } else {
+            let seq_nr = field(mpdu, offset, 1)?[0];
+            offset += 1;
+            Some(seq_nr)
+        };
+
+        let mut summary = Self {
+            frame_type: frame_control.frame_type(),
+            frame_version: frame_control.frame_version(),
+            sequence_number,
+            dst_pan_id: None,
+            dst_address: Address::Absent,
+            src_pan_id: None,
+            src_address: Address::Absent,
+            security_enabled: frame_control.security_enabled(),
+            header_ie_count: 0,
+            payload_ie_count: 0,
+            payload_length: 0,
+            rssi: None,
+            lqi: None,
+        };
+
+        if let Some(addressing) = AddressingRepr::from_frame_control(frame_control.clone())? {
+            let length = addressing
+                .addressing_fields_length()
+                .map_err(|_| FrameErrorKind::InvalidAddressingCombination)?
+                as usize;
+            let addressing_fields = AddressingFields::new(field(mpdu, offset, length)?, addressing)
+                .map_err(|e| e.shifted_by(offset))?;
+            summary.dst_pan_id = addressing_fields.clone().into_dst_pan_id();
+            summary.src_pan_id = addressing_fields.clone().into_src_pan_id();
+            summary.dst_address = addressing_fields
+                .clone()
+                .into_dst_address()
+                .unwrap_or(Address::Absent);
+            summary.src_address = addressing_fields
+                .into_src_address()
+                .unwrap_or(Address::Absent);
+            offset += length;
+        }
+
+        let mut mic_length = 0;
+        let mut encrypted = false;
+        if summary.security_enabled {
+            let security_control = field(mpdu, offset, 1)?[0];
+            let security_level = security_control & 0b111;
+            let key_id_length = match (security_control >> 3) & 0b11 {
+                0 => 0,
+                1 => 1,
+                2 => 5,
+                _ => 9,
+            };
+            let frame_counter_suppressed = security_control & (1 << 5) != 0;
+            let frame_counter_length = if frame_counter_suppressed { 0 } else { 4 };
+            let aux_sec_header_length = 1 + frame_counter_length + key_id_length;
+            field(mpdu, offset, aux_sec_header_length)?;
+            offset += aux_sec_header_length;
+
+            mic_length = match security_level & 0b11 {
+                0 => 0,
+                1 => 4,
+                2 => 8,
+                _ => 16,
+            };
+            encrypted = security_level & 0b100 != 0;
+        }
+
+        let end = mpdu.len().saturating_sub(mic_length);
+        if offset > end {
+            return Err(FrameError::from(FrameErrorKind::BufferTooShort {
+                needed: offset + mic_length,
+                got: mpdu.len(),
+            })
+            .at(offset));
+        }
+
+        if frame_control.information_elements_present() {
+            let mut payload_ies_present = false;
+            while offset < end {
+                let header = ie_header(mpdu, offset, end)?;
+                let element_id = ((header >> 7) & 0xff) as u8;
+                offset = next_ie(offset, (header & 0x7f) as usize, end)?;
+                match element_id {
+                    HEADER_TERMINATION_IE_1 => {
+                        payload_ies_present = true;
+                        break;
+                    }
+                    HEADER_TERMINATION_IE_2 => break,
+                    _ => summary.header_ie_count = summary.header_ie_count.saturating_add(1),
+                }
+            }
+
+            if payload_ies_present && !encrypted {
+                while offset < end {
+                    let header = ie_header(mpdu, offset, end)?;
+                    let group_id = ((header >> 11) & 0xf) as u8;
+                    offset = next_ie(offset, (header & 0x7ff) as usize, end)?;
+                    if group_id == PAYLOAD_TERMINATION_IE {
+                        break;
+                    }
+                    summary.payload_ie_count = summary.payload_ie_count.saturating_add(1);
+                }
+            }
+        }
+
+        summary.payload_length = (end - offset) as u16;
+        Ok(summary)
+    }
+
+    /// Sets the received signal strength in dBm.
+    pub const fn with_rssi(mut self, rssi: i8) -> Self {
+        self.rssi = Some(rssi);
+        self
+    }
+
+    /// Sets the link quality indicator.
+    pub const fn with_lqi(mut self, lqi: u8) -> Self {
+        self.lqi = Some(lqi);
+        self
+    }
+}
+
+/// Returns the field at the given offset or an error if the MPDU is too short.
+pub(super) fn field(mpdu: &[u8], offset: usize, length: usize) -> Result<&[u8], FrameError> {
+    mpdu.get(offset..offset + length).ok_or_else(|| {
+        FrameError::from(FrameErrorKind::BufferTooShort {
+            needed: offset + length,
+            got: mpdu.len(),
+        })
+        .at(offset)
+    })
+}
+
+/// Reads the IE header at the given offset.
+pub(super) fn ie_header(mpdu: &[u8], offset: usize, end: usize) -> Result<u16, FrameError> {
+    if offset + IE_HEADER_LEN > end {
+        return Err(FrameError::from(FrameErrorKind::TruncatedIe).at(offset));
+    }
+    Ok(u16::from_le_bytes([mpdu[offset], mpdu[offset + 1]]))
+}
+
+/// Returns the offset of the IE following the IE at the given offset.
+pub(super) fn next_ie(
+    offset: usize,
+    content_length: usize,
+    end: usize,
+) -> Result<usize, FrameError> {
+    let length = IE_HEADER_LEN + content_length;
+    if offset + length > end {
+        return Err(FrameError::from(FrameErrorKind::IeExceedsFrame {
+            length,
+            remaining: end - offset,
+        })
+        .at(offset));
+    }
+    Ok(offset + length)
+}
+
+impl core::fmt::Display for FrameSummary<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.frame_type)?;
+        if let Some(seq_nr) = self.sequence_number {
+            write!(f, " #{seq_nr}")?;
+        }
+        if let Some(dst_pan_id) = self.dst_pan_id {
+            write!(f, " {dst_pan_id}")?;
+        }
+        write!(f, " {} <-", self.dst_address)?;
+        if let Some(src_pan_id) = self.src_pan_id {
+            write!(f, " {src_pan_id}")?;
+        }
+        write!(f, " {}", self.src_address)?;
+        if self.security_enabled {
+            write!(f, " sec")?;
+        }
+        write!(
+            f,
+            " ies {}+{} payload {}",
+            self.header_ie_count, self.payload_ie_count, self.payload_length
+        )?;
+        if let Some(rssi) = self.rssi {
+            write!(f, " rssi {rssi}")?;
+        }
+        if let Some(lqi) = self.lqi {
+            write!(f, " lqi {lqi}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for FrameSummary<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.frame_type);
+        if let Some(seq_nr) = self.sequence_number {
+            defmt::write!(f, " #{=u8}", seq_nr);
+        }
+        if let Some(dst_pan_id) = self.dst_pan_id {
+            defmt::write!(f, " {}", dst_pan_id);
+        }
+        defmt::write!(f, " {} <-", self.dst_address);
+        if let Some(src_pan_id) = self.src_pan_id {
+            defmt::write!(f, " {}", src_pan_id);
+        }
+        defmt::write!(f, " {}", self.src_address);
+        if self.security_enabled {
+            defmt::write!(f, " sec");
+        }
+        defmt::write!(
+            f,
+            " ies {=u8}+{=u8} payload {=u16}",
+            self.header_ie_count,
+            self.payload_ie_count,
+            self.payload_length
+        );
+        if let Some(rssi) = self.rssi {
+            defmt::write!(f, " rssi {=i8}", rssi);
+        }
+        if let Some(lqi) = self.lqi {
+            defmt::write!(f, " lqi {=u8}", lqi);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_driver::frame::{ExtendedAddress, ShortAddress};
+
+    use super::*;
+
+    #[test]
+    fn enhanced_beacon() {
+        let mpdu = [
+            0x40, 0xeb, // frame control
+            0xcd, 0xab, // dst PAN ID
+            0xff, 0xff, // dst address
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // src address
+            0x00, 0x3f, // header termination IE 1
+            0x11, 0x88, // MLME payload IE
+            0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, // TSCH synchronization IE
+            0x01, 0x1c, 0x00, // TSCH timeslot IE
+            0x01, 0xc8, 0x00, // channel hopping IE
+            0x01, 0x1b, 0x00, // TSCH slotframe and link IE
+        ];
+
+        let summary = FrameSummary::parse(&mpdu).unwrap().with_rssi(-40);
+        assert_eq!(summary.frame_type, FrameType::Beacon);
+        assert_eq!(summary.frame_version, FrameVersion::Ieee802154);
+        assert_eq!(summary.sequence_number, None);
+        assert_eq!(summary.dst_pan_id, Some(PanId::new(&mpdu[2..4])));
+        assert_eq!(
+            summary.dst_address,
+            Address::Short(ShortAddress::new(&mpdu[4..6]))
+        );
+        assert_eq!(summary.src_pan_id, None);
+        assert_eq!(
+            summary.src_address,
+            Address::Extended(ExtendedAddress::new(&mpdu[6..14]))
+        );
+        assert!(!summary.security_enabled);
+        assert_eq!(summary.header_ie_count, 0);
+        assert_eq!(summary.payload_ie_count, 1);
+        assert_eq!(summary.payload_length, 0);
+        assert_eq!(summary.rssi, Some(-40));
+        assert_eq!(summary.lqi, None);
+    }
+
+    #[test]
+    fn data_frame() {
+        let mpdu = [
+            0x61, 0x88, // frame control
+            0x2a, // sequence number
+            0xcd, 0xab, // dst PAN ID
+            0x02, 0x00, // dst address
+            0x01, 0x00, // src address
+            0x11, 0x22, 0x33, // payload
+        ];
+
+        let summary = FrameSummary::parse(&mpdu).unwrap();
+        assert_eq!(summary.frame_type, FrameType::Data);
+        assert_eq!(summary.sequence_number, Some(0x2a));
+        assert_eq!(summary.dst_pan_id, Some(PanId::new(&mpdu[3..5])));
+        assert_eq!(summary.src_pan_id, None);
+        assert_eq!(
+            summary.src_address,
+            Address::Short(ShortAddress::new(&mpdu[7..9]))
+        );
+        assert_eq!(summary.header_ie_count, 0);
+        assert_eq!(summary.payload_ie_count, 0);
+        assert_eq!(summary.payload_length, 3);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            FrameSummary::parse(&[0x61]).unwrap_err().kind(),
+            FrameErrorKind::BufferTooShort { needed: 2, got: 1 }
+        );
+
+        // Truncated addressing fields.
+        let err = FrameSummary::parse(&[0x61, 0x88, 0x2a, 0xcd, 0xab]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            FrameErrorKind::BufferTooShort { needed: 9, got: 5 }
+        );
+        assert_eq!(err.offset(), Some(3));
+
+        // Header IE exceeding the frame.
+        let err = FrameSummary::parse(&[0x01, 0x23, 0x02, 0x09, 0x00]).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            FrameErrorKind::IeExceedsFrame {
+                length: 4,
+                remaining: 3
+            }
+        );
+        assert_eq!(err.offset(), Some(2));
+    }
+}
@@ -2,10 +2,19 @@
 #![cfg_attr(feature = "strict", deny(warnings))]
 #![allow(dead_code)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+// Required by the derived `Arbitrary` implementations.
+#[cfg(feature = "fuzz")]
+extern crate std;
+
+pub mod fcs;
 pub mod fields;
 pub mod mpdu;
 pub mod repr;
 
+pub use dot15d4_driver::frame::{FrameError, FrameErrorKind};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct MpduNoFields;
 
@@ -59,9 +68,9 @@ mod test {
     #[cfg(feature = "security")]
     use crate::repr::{KeyIdRepr, SecurityLevelRepr, SecurityRepr};
     use crate::{
-        mpdu::imm_ack_frame,
+        mpdu::{imm_ack_frame, MpduFrame},
         repr::{MpduRepr, SeqNrRepr},
-        MpduWithIes,
+        FrameError, FrameErrorKind, MpduWithIes,
     };
     struct FakeRadioTimer;
     impl Frequency for FakeRadioTimer {
@@ -76,6 +85,10 @@ mod test {
             todo!()
         }
 
+        fn cancel_alarm() {
+            todo!()
+        }
+
         async fn wait_for_alarm() -> Instant<Self> {
             todo!()
         }
@@ -175,6 +188,44 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_mpdu_repr_length() {
+        const MPDU_REPR: MpduRepr<'static, MpduWithIes> = MpduRepr::new()
+            .with_frame_control(SeqNrRepr::Yes)
+            .with_addressing(AddressingRepr::new(
+                AddressingMode::Short,
+                AddressingMode::Short,
+                true,
+                PanIdCompressionRepr::Yes,
+            ))
+            .without_security()
+            .without_ies();
+
+        const PAYLOAD_LENGTH: u16 = 5;
+        // Frame control, sequence number, dst PAN ID and short addresses.
+        const MPDU_LENGTH_WO_FCS: u16 = 2 + 1 + 2 + 2 + 2 + PAYLOAD_LENGTH;
+        assert_eq!(
+            MPDU_REPR.mpdu_length_wo_fcs(PAYLOAD_LENGTH).unwrap().get(),
+            MPDU_LENGTH_WO_FCS
+        );
+        assert_eq!(
+            MPDU_REPR
+                .mpdu_length::<FakeDriverConfig>(PAYLOAD_LENGTH)
+                .unwrap()
+                .get(),
+            MPDU_LENGTH_WO_FCS + 2
+        );
+        // Headroom and tailroom of the driver are included.
+        assert_eq!(
+            MPDU_REPR
+                .min_buffer_size::<FakeDriverConfig>(PAYLOAD_LENGTH)
+                .unwrap(),
+            1 + MPDU_LENGTH_WO_FCS as usize + 2 + 2
+        );
+
+        assert!(MPDU_REPR.mpdu_length_wo_fcs(u16::MAX).is_err());
+    }
+
     #[test]
     fn test_imm_ack_frame() {
         const IMM_ACK_LEN: u8 = 3;
@@ -214,6 +265,186 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_mpdu_parser_with_headroom() {
+        static BUFFER: ConstStaticCell<[u8; 32]> = ConstStaticCell::new([0; 32]);
+        let buffer = BufferToken::new(BUFFER.take());
+
+        // Data frame (2006), PAN ID compression, short addresses, 3 bytes of
+        // payload.
+        const MPDU: [u8; 12] = [
+            0x41, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33,
+        ];
+        const HEADROOM: u8 = 1;
+        let mut mpdu = MpduFrame::new(
+            buffer,
+            HEADROOM,
+            NonZeroU16::new(MPDU.len() as u16).unwrap(),
+        );
+        mpdu.pdu_mut_wo_fcs().copy_from_slice(&MPDU);
+        assert_eq!(mpdu.sequence_number(), Some(0x2a));
+
+        let reader = mpdu
+            .reader()
+            .parse_addressing()
+            .unwrap()
+            .parse_security()
+            .unwrap()
+            .parse_ies::<FakeDriverConfig>()
+            .unwrap();
+        assert_eq!(reader.frame_payload(), Some(&MPDU[9..]));
+
+        // Truncated addressing fields must be rejected.
+        let mut mpdu = MpduFrame::new(mpdu.into_buffer(), HEADROOM, NonZeroU16::new(8).unwrap());
+        assert_eq!(
+            mpdu.reader().parse_addressing().err(),
+            Some(FrameError::new(FrameErrorKind::BufferTooShort { needed: 9, got: 8 }).at(3))
+        );
+        assert!(mpdu.writer().parse_addressing_mut().is_err());
+
+        // Truncated sequence number.
+        let mpdu = MpduFrame::new(mpdu.into_buffer(), HEADROOM, NonZeroU16::new(2).unwrap());
+        assert_eq!(mpdu.sequence_number(), None);
+        assert!(!mpdu.reader().is_valid());
+
+        unsafe {
+            mpdu.into_buffer().consume();
+        }
+    }
+
+    #[test]
+    fn test_mpdu_parser_seq_nr_suppression() {
+        static BUFFER: ConstStaticCell<[u8; 16]> = ConstStaticCell::new([0; 16]);
+        let buffer = BufferToken::new(BUFFER.take());
+
+        // Data frame (2015), sequence number suppressed, PAN ID compression,
+        // short addresses, 3 bytes of payload.
+        const MPDU: [u8; 11] = [
+            0x41, 0xa9, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33,
+        ];
+        const HEADROOM: u8 = 1;
+        let mut mpdu = MpduFrame::new(
+            buffer,
+            HEADROOM,
+            NonZeroU16::new(MPDU.len() as u16).unwrap(),
+        );
+        mpdu.pdu_mut_wo_fcs().copy_from_slice(&MPDU);
+        assert_eq!(mpdu.sequence_number(), None);
+        assert!(mpdu.set_sequence_number(0x2a).is_err());
+        assert_eq!(*mpdu.pdu_mut_wo_fcs(), MPDU);
+
+        let reader = mpdu.reader();
+        assert!(reader.is_valid());
+        let reader = reader.parse_addressing().unwrap();
+        let addressing_fields = reader.addressing_fields().unwrap().unwrap();
+        assert_eq!(addressing_fields.dst_pan_id().unwrap().into_u16(), 0xabcd);
+        let reader = reader
+            .parse_security()
+            .unwrap()
+            .parse_ies::<FakeDriverConfig>()
+            .unwrap();
+        assert_eq!(reader.frame_payload(), Some(&MPDU[8..]));
+
+        unsafe {
+            mpdu.into_buffer().consume();
+        }
+    }
+
+    #[test]
+    fn test_mpdu_fcs() {
+        static BUFFER: ConstStaticCell<[u8; 16]> = ConstStaticCell::new([0; 16]);
+        let buffer = BufferToken::new(BUFFER.take());
+
+        const MPDU: [u8; 12] = [
+            0x41, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33,
+        ];
+        const HEADROOM: u8 = 1;
+        let mut mpdu = MpduFrame::new(
+            buffer,
+            HEADROOM,
+            NonZeroU16::new(MPDU.len() as u16).unwrap(),
+        );
+        mpdu.pdu_mut_wo_fcs().copy_from_slice(&MPDU);
+        assert_eq!(
+            mpdu.check_fcs::<FakeDriverConfig>().err().map(|e| e.kind()),
+            Some(FrameErrorKind::InvalidFcs)
+        );
+
+        mpdu.write_fcs::<FakeDriverConfig>().unwrap();
+        assert!(mpdu.check_fcs::<FakeDriverConfig>().is_ok());
+        assert!(mpdu
+            .reader_with_valid_fcs::<FakeDriverConfig>()
+            .unwrap()
+            .parse_addressing()
+            .is_ok());
+
+        // Corrupt the payload.
+        mpdu.pdu_mut_wo_fcs()[9] ^= 0xff;
+        assert_eq!(
+            mpdu.reader_with_valid_fcs::<FakeDriverConfig>()
+                .err()
+                .and_then(|e| e.offset()),
+            Some(MPDU.len())
+        );
+
+        // No room for the FCS.
+        let mpdu = MpduFrame::new(mpdu.into_buffer(), HEADROOM, NonZeroU16::new(14).unwrap());
+        assert_eq!(
+            mpdu.check_fcs::<FakeDriverConfig>().err().map(|e| e.kind()),
+            Some(FrameErrorKind::BufferTooShort {
+                needed: 16,
+                got: 15
+            })
+        );
+
+        unsafe {
+            mpdu.into_buffer().consume();
+        }
+    }
+
+    /// Regression test derived from fuzzing: parsing must not panic on
+    /// arbitrary MPDUs.
+    #[test]
+    fn test_mpdu_parser_arbitrary_input() {
+        const BUFFER_LEN: usize = 1 + PHY_MAX_PACKET_SIZE_127 + 2;
+        static BUFFER: ConstStaticCell<[u8; BUFFER_LEN]> = ConstStaticCell::new([0; BUFFER_LEN]);
+        let mut buffer = BufferToken::new(BUFFER.take());
+
+        // xorshift32, deterministic so that failures can be reproduced.
+        let mut state = 0x9e37_79b9u32;
+        let mut next_byte = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        };
+
+        for _ in 0..10_000 {
+            buffer.iter_mut().for_each(|b| *b = next_byte());
+            let length_wo_fcs = (next_byte() as u16 % PHY_MAX_PACKET_SIZE_127 as u16) + 1;
+            let mpdu = MpduFrame::new(buffer, 1, NonZeroU16::new(length_wo_fcs).unwrap());
+
+            let reader = mpdu.reader();
+            let _ = reader.is_valid();
+            let _ = reader.sequence_number();
+            if let Ok(reader) = reader.parse_addressing() {
+                let _ = reader.addressing_fields();
+                if let Ok(reader) = reader.parse_security() {
+                    if let Ok(reader) = reader.parse_ies::<FakeDriverConfig>() {
+                        let _ = reader.frame_payload();
+                        let _ = reader.fcs();
+                    }
+                }
+            }
+
+            buffer = mpdu.into_buffer();
+        }
+
+        unsafe {
+            buffer.consume();
+        }
+    }
+
     fn round_to_alignment(size: usize, alignment: usize) -> usize {
         assert!(alignment > 0 && ((alignment & (alignment - 1)) == 0));
 
@@ -44,6 +44,10 @@ pub const BEACON_FRAME_REPR: MpduRepr<MpduWithSecurity> = mpdu_repr()
 ///       efficient than instantiating an IE list and payload slice just to move
 ///       (copy) it into the function and copy it once again into the buffer
 ///       verbatim.
+///
+/// The beacon payload length covers the complete MAC payload, i.e. it must
+/// include the superframe specification, GTS and pending address fields, see
+/// [`beacon_fields_length()`](crate::fields::beacon_fields_length).
 pub async fn beacon_frame<'ies, Config: DriverConfig, const ALLOCATOR_BACKLOG: usize>(
     ies: Option<IeReprList<'ies, IeRepr<'ies>>>,
     beacon_payload_length: u16,
@@ -67,7 +71,7 @@ where
     match beacon_frame_repr.into_parsed_mpdu::<Config>(
         FrameVersion::Ieee802154_2006,
         FrameType::Beacon,
-        0,
+        beacon_payload_length,
         buffer,
     ) {
         Ok(result) => Ok(result),
@@ -0,0 +1,240 @@
+use core::ops::{Deref, DerefMut};
+
+use dot15d4_driver::frame::FrameControl;
+
+use crate::{FrameError, FrameErrorKind, MpduWithAllFields};
+
+use super::FrameBuilder;
+
+/// An owned, fixed-capacity buffer holding a single MPDU (without FCS).
+///
+/// The buffer tracks the length of the MPDU it contains and dereferences to
+/// that part of the buffer only, so that all field readers/writers can be
+/// instantiated directly on it, e.g. `FrameControl::new(&*buffer)`.
+///
+/// In contrast to [`super::MpduFrame`], which borrows its memory from a
+/// buffer allocator, the frame buffer owns its memory. It can be created in
+/// const context and is therefore suitable for static frame pools and queues.
+#[derive(Clone)]
+pub struct FrameBuffer<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> FrameBuffer<N> {
+    /// Creates an empty, zeroed frame buffer.
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Creates a frame buffer containing a copy of the given MPDU.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the MPDU exceeds the
+    /// capacity of the buffer.
+    pub fn from_slice(mpdu: &[u8]) -> Result<Self, FrameError> {
+        let mut buffer = Self::new();
+        buffer.set_len(mpdu.len())?;
+        buffer.copy_from_slice(mpdu);
+        Ok(buffer)
+    }
+
+    /// Creates a frame buffer containing the MPDU emitted by the given frame
+    /// builder.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the MPDU exceeds the
+    /// capacity of the buffer.
+    pub fn from_builder(builder: &FrameBuilder<'_, MpduWithAllFields>) -> Result<Self, FrameError> {
+        let mut buffer = Self::new();
+        buffer.len = builder.emit(&mut buffer.bytes)?;
+        Ok(buffer)
+    }
+
+    /// Returns the capacity of the buffer.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the length of the contained MPDU.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the buffer contains no MPDU.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Sets the length of the contained MPDU.
+    ///
+    /// Use this after writing an MPDU of yet unknown length into
+    /// [`FrameBuffer::as_mut_capacity()`], e.g. when receiving a frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the length exceeds the
+    /// capacity of the buffer.
+    pub fn set_len(&mut self, len: usize) -> Result<(), FrameError> {
+        if len > N {
+            return Err(FrameErrorKind::BufferTooShort {
+                needed: len,
+                got: N,
+            }
+            .into());
+        }
+        self.len = len;
+        Ok(())
+    }
+
+    /// Empties the buffer so that it can be re-used.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Provides mutable access to the full capacity of the buffer independently
+    /// of the length of the contained MPDU.
+    pub fn as_mut_capacity(&mut self) -> &mut [u8; N] {
+        &mut self.bytes
+    }
+
+    /// Provides read-only access to the [`FrameControl`] field.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the contained MPDU is too
+    /// short to contain the frame control field.
+    pub fn frame_control(&self) -> Result<FrameControl<&[u8]>, FrameError> {
+        FrameControl::new(&self[..])
+    }
+
+    /// Provides mutable access to the [`FrameControl`] field.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the contained MPDU is too
+    /// short to contain the frame control field.
+    pub fn frame_control_mut(&mut self) -> Result<FrameControl<&mut [u8]>, FrameError> {
+        FrameControl::new(&mut self[..])
+    }
+}
+
+impl<const N: usize> Default for FrameBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for FrameBuffer<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes[..self.len]
+    }
+}
+
+impl<const N: usize> DerefMut for FrameBuffer<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.bytes[..self.len]
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FrameBuffer<N> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const N: usize> AsMut<[u8]> for FrameBuffer<N> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl<const N: usize> PartialEq for FrameBuffer<N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<const N: usize> Eq for FrameBuffer<N> {}
+
+impl<const N: usize> core::fmt::Debug for FrameBuffer<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FrameBuffer")
+            .field("mpdu", &&**self)
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, FrameType, PanId, ShortAddress},
+    };
+    use static_cell::ConstStaticCell;
+
+    use super::*;
+
+    type Mpdu = FrameBuffer<PHY_MAX_PACKET_SIZE_127>;
+
+    #[test]
+    fn frame_buffer() {
+        static POOL: ConstStaticCell<[Mpdu; 2]> = ConstStaticCell::new([Mpdu::new(), Mpdu::new()]);
+
+        let buffer = &mut POOL.take()[0];
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), PHY_MAX_PACKET_SIZE_127);
+
+        // Imm-Ack.
+        buffer.as_mut_capacity()[..3].copy_from_slice(&[0x02, 0x10, 0x56]);
+        buffer.set_len(3).unwrap();
+        assert_eq!(&buffer[..], &[0x02, 0x10, 0x56]);
+        assert_eq!(buffer.frame_control().unwrap().frame_type(), FrameType::Ack);
+
+        buffer.frame_control_mut().unwrap().set_frame_pending(true);
+        assert_eq!(buffer[0], 0x12);
+
+        assert_eq!(
+            buffer.set_len(PHY_MAX_PACKET_SIZE_127 + 1),
+            Err(FrameErrorKind::BufferTooShort {
+                needed: PHY_MAX_PACKET_SIZE_127 + 1,
+                got: PHY_MAX_PACKET_SIZE_127
+            }
+            .into())
+        );
+
+        buffer.clear();
+        assert!(buffer.frame_control().is_err());
+        assert!(FrameBuffer::<2>::from_slice(&[0; 3]).is_err());
+    }
+
+    #[test]
+    fn from_builder() {
+        let builder = FrameBuilder::new(FrameType::Data)
+            .with_sequence_number(1)
+            .with_addressing(
+                Some((
+                    PanId::from_u16(0xabcd),
+                    Address::Short(ShortAddress::new(&[0x02, 0x00][..])),
+                )),
+                None,
+            )
+            .without_security()
+            .without_ies()
+            .with_payload(&[0x11]);
+
+        let buffer = Mpdu::from_builder(&builder).unwrap();
+        assert_eq!(buffer.len(), builder.mpdu_length_wo_fcs());
+        assert_eq!(buffer, Mpdu::from_slice(&buffer).unwrap());
+
+        assert!(FrameBuffer::<4>::from_builder(&builder).is_err());
+    }
+}
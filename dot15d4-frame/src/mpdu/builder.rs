@@ -0,0 +1,490 @@
+use core::marker::PhantomData;
+
+use dot15d4_driver::frame::{
+    Address, AddressingFields, AddressingMode, AddressingRepr, FrameControl, FrameType,
+    FrameVersion, PanId, PanIdCompressionRepr,
+};
+
+use crate::{
+    FrameError, FrameErrorKind, MpduWithAddressing, MpduWithAllFields, MpduWithFrameControl,
+    MpduWithIes, MpduWithSecurity,
+};
+
+const FRAME_CONTROL_LEN: usize = 2;
+
+/// The PAN ID and address of the source or destination of a frame.
+type PanIdAndAddress<'f> = (PanId<[u8; 2]>, Address<&'f [u8]>);
+
+/// Builds an MPDU from scratch and emits it into a caller-supplied buffer.
+///
+/// Fields must be given in the order in which they appear in the frame, i.e.
+/// frame type → addressing → security → IEs → payload. The order is enforced
+/// at compile time through the builder's state.
+///
+/// The frame control field is derived from the given fields: frame version,
+/// addressing modes, PAN ID compression, sequence number suppression and IE
+/// present flags are all calculated when emitting the frame. The frame version
+/// is IEEE 802.15.4-2006 unless features of later versions (IEs, sequence
+/// number suppression) are used or it is requested explicitly with
+/// [`FrameBuilder::with_enhanced_frame_version()`].
+///
+/// The builder emits the MPDU without FCS. Use [`crate::fcs::write_fcs()`] if
+/// the FCS is not offloaded to the driver or hardware.
+///
+/// In contrast to [`crate::repr::MpduRepr`], which only describes the structure
+/// of a frame so that a buffer of the right size can be allocated, the builder
+/// holds the field content and is meant for buffers that are managed by the
+/// caller.
+#[derive(Debug)]
+pub struct FrameBuilder<'f, State> {
+    frame_type: FrameType,
+    seq_nr: Option<u8>,
+    ack_request: bool,
+    frame_pending: bool,
+    enhanced: bool,
+    dst: Option<PanIdAndAddress<'f>>,
+    src: Option<PanIdAndAddress<'f>>,
+    ies: &'f [u8],
+    payload: &'f [u8],
+    state: PhantomData<State>,
+}
+
+impl<'f, State> FrameBuilder<'f, State> {
+    fn into_state<NextState>(self) -> FrameBuilder<'f, NextState> {
+        FrameBuilder {
+            frame_type: self.frame_type,
+            seq_nr: self.seq_nr,
+            ack_request: self.ack_request,
+            frame_pending: self.frame_pending,
+            enhanced: self.enhanced,
+            dst: self.dst,
+            src: self.src,
+            ies: self.ies,
+            payload: self.payload,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<'f> FrameBuilder<'f, MpduWithFrameControl> {
+    /// Starts building a frame of the given type.
+    ///
+    /// The sequence number is suppressed unless it is set with
+    /// [`FrameBuilder::with_sequence_number()`].
+    pub const fn new(frame_type: FrameType) -> Self {
+        Self {
+            frame_type,
+            seq_nr: None,
+            ack_request: false,
+            frame_pending: false,
+            enhanced: false,
+            dst: None,
+            src: None,
+            ies: &[],
+            payload: &[],
+            state: PhantomData,
+        }
+    }
+
+    /// Sets the sequence number.
+    pub const fn with_sequence_number(mut self, seq_nr: u8) -> Self {
+        self.seq_nr = Some(seq_nr);
+        self
+    }
+
+    /// Sets the acknowledgment request flag.
+    pub const fn with_ack_request(mut self, ack_request: bool) -> Self {
+        self.ack_request = ack_request;
+        self
+    }
+
+    /// Sets the frame pending flag.
+    pub const fn with_frame_pending(mut self, frame_pending: bool) -> Self {
+        self.frame_pending = frame_pending;
+        self
+    }
+
+    /// Uses the IEEE 802.15.4-2015 frame version even if no features of later
+    /// versions are used, e.g. for Enh-Ack frames.
+    pub const fn with_enhanced_frame_version(mut self) -> Self {
+        self.enhanced = true;
+        self
+    }
+
+    /// Sets the destination and source PAN IDs and addresses.
+    ///
+    /// Absent addresses are treated as if they had not been given. PAN IDs
+    /// will be elided as far as the frame version permits.
+    ///
+    /// Note: Frames with two extended addresses cannot convey distinct PAN IDs
+    ///       in IEEE 802.15.4-2015 and later. Only the destination PAN ID is
+    ///       included in that case.
+    pub fn with_addressing(
+        mut self,
+        dst: Option<PanIdAndAddress<'f>>,
+        src: Option<PanIdAndAddress<'f>>,
+    ) -> FrameBuilder<'f, MpduWithAddressing> {
+        self.dst = dst.filter(|(_, address)| !address.is_absent());
+        self.src = src.filter(|(_, address)| !address.is_absent());
+        self.into_state()
+    }
+
+    /// Builds a frame without addressing fields, e.g. an Imm-Ack frame.
+    pub fn without_addressing(self) -> FrameBuilder<'f, MpduWithAddressing> {
+        self.into_state()
+    }
+}
+
+impl<'f> FrameBuilder<'f, MpduWithAddressing> {
+    // TODO: Add with_security() once the auxiliary security header can be
+    //       written.
+
+    /// Builds an unsecured frame.
+    pub fn without_security(self) -> FrameBuilder<'f, MpduWithSecurity> {
+        self.into_state()
+    }
+}
+
+impl<'f> FrameBuilder<'f, MpduWithSecurity> {
+    /// Sets the IE list, i.e. header and payload IEs in their encoded form
+    /// including any termination IEs.
+    #[cfg(feature = "ies")]
+    pub fn with_ies(mut self, ies: &'f [u8]) -> FrameBuilder<'f, MpduWithIes> {
+        self.ies = ies;
+        self.into_state()
+    }
+
+    /// Builds a frame without IEs.
+    pub fn without_ies(self) -> FrameBuilder<'f, MpduWithIes> {
+        self.into_state()
+    }
+}
+
+impl<'f> FrameBuilder<'f, MpduWithIes> {
+    /// Sets the frame payload.
+    pub fn with_payload(mut self, payload: &'f [u8]) -> FrameBuilder<'f, MpduWithAllFields> {
+        self.payload = payload;
+        self.into_state()
+    }
+
+    /// Builds a frame without payload.
+    pub fn without_payload(self) -> FrameBuilder<'f, MpduWithAllFields> {
+        self.into_state()
+    }
+}
+
+impl FrameBuilder<'_, MpduWithAllFields> {
+    /// The frame version derived from the given fields.
+    pub fn frame_version(&self) -> FrameVersion {
+        if self.enhanced || self.seq_nr.is_none() || !self.ies.is_empty() {
+            FrameVersion::Ieee802154
+        } else {
+            FrameVersion::Ieee802154_2006
+        }
+    }
+
+    /// The addressing representation derived from the given fields, [`None`]
+    /// if the frame has no addressing fields.
+    fn addressing_repr(&self) -> Option<AddressingRepr> {
+        let (dst_mode, dst_pan_id) = match self.dst {
+            Some((pan_id, address)) => (AddressingMode::from(address), Some(pan_id)),
+            None => (AddressingMode::Absent, None),
+        };
+        let (src_mode, src_pan_id) = match self.src {
+            Some((pan_id, address)) => (AddressingMode::from(address), Some(pan_id)),
+            None => (AddressingMode::Absent, None),
+        };
+
+        if self.dst.is_none() && self.src.is_none() {
+            return None;
+        }
+
+        let pan_ids_equal = dst_pan_id.is_some() && dst_pan_id == src_pan_id;
+        let pan_id_compression = match self.frame_version() {
+            FrameVersion::Ieee802154 if pan_ids_equal => PanIdCompressionRepr::Yes,
+            FrameVersion::Ieee802154 => PanIdCompressionRepr::No,
+            _ => PanIdCompressionRepr::Legacy,
+        };
+
+        Some(AddressingRepr::new(
+            dst_mode,
+            src_mode,
+            pan_ids_equal,
+            pan_id_compression,
+        ))
+    }
+
+    fn addressing_fields_length(&self) -> usize {
+        // Note: The addressing representation is derived from known addressing
+        //       modes, all combinations of which can be represented.
+        self.addressing_repr().map_or(0, |addressing| {
+            addressing.addressing_fields_length().unwrap_or(0) as usize
+        })
+    }
+
+    /// The length of the MPDU excluding the FCS.
+    pub fn mpdu_length_wo_fcs(&self) -> usize {
+        FRAME_CONTROL_LEN
+            + self.seq_nr.is_some() as usize
+            + self.addressing_fields_length()
+            + self.ies.len()
+            + self.payload.len()
+    }
+
+    /// Emits the MPDU (excluding the FCS) into the given buffer.
+    ///
+    /// Returns the length of the emitted MPDU.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::BufferTooShort`] if the buffer cannot hold the
+    /// MPDU.
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<usize, FrameError> {
+        let mpdu_length = self.mpdu_length_wo_fcs();
+        if buffer.len() < mpdu_length {
+            return Err(FrameErrorKind::BufferTooShort {
+                needed: mpdu_length,
+                got: buffer.len(),
+            }
+            .into());
+        }
+
+        let addressing = self.addressing_repr();
+
+        // All frame control fields shall default to zero.
+        buffer[..FRAME_CONTROL_LEN].fill(0);
+        let mut fc = FrameControl::new_unchecked(&mut buffer[..FRAME_CONTROL_LEN]);
+        fc.set_frame_type(self.frame_type);
+        fc.set_frame_version(self.frame_version());
+        fc.set_frame_pending(self.frame_pending);
+        fc.set_ack_request(self.ack_request);
+        fc.set_pan_id_compression(addressing.is_some_and(|a| a.pan_id_compression()));
+        fc.set_sequence_number_suppression(self.seq_nr.is_none());
+        fc.set_information_elements_present(!self.ies.is_empty());
+        if let Some(addressing) = &addressing {
+            fc.set_dst_addressing_mode(addressing.dst_addr_mode());
+            fc.set_src_addressing_mode(addressing.src_addr_mode());
+        }
+        let mut offset = FRAME_CONTROL_LEN;
+
+        if let Some(seq_nr) = self.seq_nr {
+            buffer[offset] = seq_nr;
+            offset += 1;
+        }
+
+        if let Some(addressing) = addressing {
+            let addressing_fields_length = self.addressing_fields_length();
+            let mut addressing_fields = AddressingFields::new(
+                &mut buffer[offset..offset + addressing_fields_length],
+                addressing,
+            )
+            .map_err(|e| e.at(offset))?;
+
+            if let (Some(mut dst_pan_id), Some((pan_id, _))) =
+                (addressing_fields.dst_pan_id_mut(), self.dst)
+            {
+                dst_pan_id.set_le_bytes(pan_id);
+            }
+            if let (Some(mut dst_address), Some((_, address))) =
+                (addressing_fields.dst_address_mut(), self.dst)
+            {
+                // Safety: The addressing mode was derived from the address.
+                let _ = dst_address.set(&address);
+            }
+            if let (Some(mut src_pan_id), Some((pan_id, _))) =
+                (addressing_fields.src_pan_id_mut(), self.src)
+            {
+                src_pan_id.set_le_bytes(pan_id);
+            }
+            if let (Some(mut src_address), Some((_, address))) =
+                (addressing_fields.src_address_mut(), self.src)
+            {
+                // Safety: The addressing mode was derived from the address.
+                let _ = src_address.set(&address);
+            }
+
+            offset += addressing_fields_length;
+        }
+
+        buffer[offset..offset + self.ies.len()].copy_from_slice(self.ies);
+        offset += self.ies.len();
+
+        buffer[offset..offset + self.payload.len()].copy_from_slice(self.payload);
+        offset += self.payload.len();
+
+        debug_assert_eq!(offset, mpdu_length);
+        Ok(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_driver::frame::{ExtendedAddress, ShortAddress};
+
+    use super::*;
+
+    #[test]
+    fn data_frame_2006() {
+        let pan_id = PanId::from_u16(0xabcd);
+        let frame = FrameBuilder::new(FrameType::Data)
+            .with_sequence_number(0x2a)
+            .with_addressing(
+                Some((pan_id, Address::Short(ShortAddress::new(&[0x02, 0x00][..])))),
+                Some((pan_id, Address::Short(ShortAddress::new(&[0x01, 0x00][..])))),
+            )
+            .without_security()
+            .without_ies()
+            .with_payload(&[0x11, 0x22, 0x33]);
+        assert_eq!(frame.frame_version(), FrameVersion::Ieee802154_2006);
+
+        let mut buffer = [0xff; 16];
+        assert_eq!(frame.emit(&mut buffer), Ok(12));
+        assert_eq!(
+            buffer[..12],
+            [0x41, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33]
+        );
+
+        assert_eq!(
+            frame.emit(&mut buffer[..11]).err().map(|e| e.kind()),
+            Some(FrameErrorKind::BufferTooShort {
+                needed: 12,
+                got: 11
+            })
+        );
+    }
+
+    #[test]
+    fn distinct_pan_ids() {
+        let frame = FrameBuilder::new(FrameType::Data)
+            .with_sequence_number(1)
+            .with_ack_request(true)
+            .with_addressing(
+                Some((
+                    PanId::from_u16(0xabcd),
+                    Address::Short(ShortAddress::new(&[0x02, 0x00][..])),
+                )),
+                Some((
+                    PanId::from_u16(0x1234),
+                    Address::Extended(ExtendedAddress::new(&[0x01; 8][..])),
+                )),
+            )
+            .without_security()
+            .without_ies()
+            .without_payload();
+
+        let mut buffer = [0; 32];
+        let len = frame.emit(&mut buffer).unwrap();
+        assert_eq!(len, 2 + 1 + 2 + 2 + 2 + 8);
+
+        let fc = FrameControl::new(&buffer[..2]).unwrap();
+        assert!(fc.ack_request());
+        assert!(!fc.pan_id_compression());
+        assert_eq!(fc.src_addressing_mode(), AddressingMode::Extended);
+
+        let addressing = AddressingRepr::from_frame_control(fc).unwrap().unwrap();
+        let addressing_fields = AddressingFields::new(&buffer[3..len], addressing).unwrap();
+        assert_eq!(addressing_fields.dst_pan_id().unwrap().into_u16(), 0xabcd);
+        assert_eq!(addressing_fields.src_pan_id().unwrap().into_u16(), 0x1234);
+        assert_eq!(
+            addressing_fields.src_address(),
+            Some(Address::Extended(ExtendedAddress::new(&[0x01; 8][..])))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ies")]
+    fn frame_2015() {
+        // Header termination IE 2.
+        const IES: [u8; 2] = [0x00, 0x3f];
+
+        let pan_id = PanId::from_u16(0xabcd);
+        let frame = FrameBuilder::new(FrameType::Data)
+            .with_addressing(
+                Some((pan_id, Address::Short(ShortAddress::new(&[0x02, 0x00][..])))),
+                Some((pan_id, Address::Short(ShortAddress::new(&[0x01, 0x00][..])))),
+            )
+            .without_security()
+            .with_ies(&IES)
+            .with_payload(&[0x11]);
+        assert_eq!(frame.frame_version(), FrameVersion::Ieee802154);
+
+        let mut buffer = [0; 16];
+        let len = frame.emit(&mut buffer).unwrap();
+        assert_eq!(len, 2 + 2 + 2 + 2 + IES.len() + 1);
+
+        let fc = FrameControl::new(&buffer[..2]).unwrap();
+        assert_eq!(fc.frame_version(), FrameVersion::Ieee802154);
+        assert!(fc.sequence_number_suppression());
+        assert!(fc.information_elements_present());
+        assert!(fc.pan_id_compression());
+        assert_eq!(
+            buffer[2..len],
+            [0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x00, 0x3f, 0x11]
+        );
+    }
+
+    #[test]
+    fn parse_emit_round_trip() {
+        // Data frame (2006), extended addresses, distinct PAN IDs.
+        const MPDU: [u8; 24] = [
+            0x21, 0xdc, 0x07, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x34,
+            0x12, 0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11, 0xaa,
+        ];
+
+        let fc = FrameControl::new(&MPDU[..2]).unwrap();
+        let addressing = AddressingRepr::from_frame_control(fc).unwrap().unwrap();
+        let fields = AddressingFields::new(&MPDU[3..23], addressing).unwrap();
+        let pan_id = |pan_id: PanId<&[u8]>| PanId::from_u16(pan_id.into_u16());
+
+        let frame = FrameBuilder::new(FrameType::Data)
+            .with_sequence_number(MPDU[2])
+            .with_ack_request(true)
+            .with_addressing(
+                Some((
+                    pan_id(fields.dst_pan_id().unwrap()),
+                    fields.dst_address().unwrap(),
+                )),
+                Some((
+                    pan_id(fields.src_pan_id().unwrap()),
+                    fields.src_address().unwrap(),
+                )),
+            )
+            .without_security()
+            .without_ies()
+            .with_payload(&MPDU[23..]);
+
+        let mut buffer = [0; 24];
+        assert_eq!(frame.emit(&mut buffer), Ok(MPDU.len()));
+        assert_eq!(buffer, MPDU);
+    }
+
+    #[test]
+    fn imm_ack() {
+        let frame = FrameBuilder::new(FrameType::Ack)
+            .with_sequence_number(0x56)
+            .without_addressing()
+            .without_security()
+            .without_ies()
+            .without_payload();
+
+        let mut buffer = [0; 3];
+        assert_eq!(frame.emit(&mut buffer), Ok(3));
+        assert_eq!(buffer, [0x02, 0x10, 0x56]);
+    }
+
+    #[test]
+    fn enh_ack() {
+        let frame = FrameBuilder::new(FrameType::Ack)
+            .with_sequence_number(0x56)
+            .with_enhanced_frame_version()
+            .without_addressing()
+            .without_security()
+            .without_ies()
+            .without_payload();
+        assert_eq!(frame.frame_version(), FrameVersion::Ieee802154);
+
+        let mut buffer = [0; 3];
+        assert_eq!(frame.emit(&mut buffer), Ok(3));
+        assert_eq!(buffer, [0x02, 0x20, 0x56]);
+    }
+}
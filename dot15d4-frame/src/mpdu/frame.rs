@@ -8,6 +8,12 @@ use dot15d4_driver::{
 use dot15d4_util::{
     allocator::{BufferToken, IntoBuffer},
     frame::FramePdu,
+    Error, Result,
+};
+
+use crate::{
+    fcs::{check_fcs, fcs_length, write_fcs},
+    FrameError, FrameErrorKind,
 };
 
 /// An unparsed MPDU.
@@ -45,11 +51,17 @@ impl MpduFrame {
         self.length_wo_fcs.get()
     }
 
+    /// The buffer offset of the first byte after the MPDU (w/o FCS).
+    pub(crate) fn pdu_end_wo_fcs(&self) -> usize {
+        self.offset as usize + self.pdu_length_wo_fcs() as usize
+    }
+
     fn pdu_range_wo_fcs(&self) -> Range<usize> {
         self.offset as usize..(self.offset as usize + self.pdu_length_wo_fcs() as usize)
     }
 
-    fn pdu_ref_wo_fcs(&self) -> &[u8] {
+    /// The MPDU of the frame without any FCS.
+    pub fn pdu_ref_wo_fcs(&self) -> &[u8] {
         &self.buffer[self.pdu_range_wo_fcs()]
     }
 
@@ -58,6 +70,52 @@ impl MpduFrame {
         &mut self.buffer[pdu_range]
     }
 
+    /// The buffer range of the FCS that the framework has to handle for the
+    /// given driver configuration. Empty if FCS handling is offloaded.
+    fn fcs_range<Config: DriverConfig>(&self) -> Range<usize> {
+        let offset_fcs = self.pdu_end_wo_fcs();
+        offset_fcs..offset_fcs + fcs_length::<Config>()
+    }
+
+    /// Verifies the FCS of the MPDU.
+    ///
+    /// Always succeeds if FCS handling is offloaded to the driver or hardware
+    /// as frames with invalid FCS will then have been dropped before reaching
+    /// the MAC.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FrameErrorKind::InvalidFcs`] if the FCS does not match the
+    /// MPDU or [`FrameErrorKind::BufferTooShort`] if the buffer is too short to
+    /// contain the FCS. The error offset points to the FCS.
+    pub fn check_fcs<Config: DriverConfig>(&self) -> core::result::Result<(), FrameError> {
+        let offset_fcs = self.pdu_length_wo_fcs() as usize;
+        let Some(fcs) = self.buffer.get(self.fcs_range::<Config>()) else {
+            return Err(FrameError::new(FrameErrorKind::BufferTooShort {
+                needed: self.pdu_length::<Config>() as usize,
+                got: self.buffer.len().saturating_sub(self.offset as usize),
+            })
+            .at(offset_fcs));
+        };
+
+        check_fcs(self.pdu_ref_wo_fcs(), fcs).map_err(|e| e.at(offset_fcs))
+    }
+
+    /// Calculates the FCS over the MPDU and appends it to the MPDU.
+    ///
+    /// Does nothing if FCS handling is offloaded to the driver or hardware.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short to contain the FCS.
+    pub fn write_fcs<Config: DriverConfig>(&mut self) -> Result<()> {
+        let range_fcs = self.fcs_range::<Config>();
+        let pdu_range_wo_fcs = self.pdu_range_wo_fcs();
+        let (pdu, tail) = self.buffer.split_at_mut(range_fcs.start);
+        let fcs = tail.get_mut(..range_fcs.len()).ok_or(Error)?;
+        write_fcs(&pdu[pdu_range_wo_fcs], fcs)
+    }
+
     /// Produces an unparsed MPDU from a radio frame.
     pub fn from_radio_frame(radio_frame: RadioFrame<RadioFrameSized>) -> Self {
         let offset = radio_frame.headroom_length();
@@ -72,10 +130,14 @@ impl MpduFrame {
     /// Converts an MPDU into a radio frame.
     ///
     /// Calculates the driver-specific FCS if required.
-    pub fn into_radio_frame<Config: DriverConfig>(self) -> RadioFrame<RadioFrameSized> {
+    pub fn into_radio_frame<Config: DriverConfig>(mut self) -> RadioFrame<RadioFrameSized> {
         debug_assert_eq!(self.offset, <Config::Headroom as Unsigned>::U8);
 
-        // TODO: Calculate the FCS if required.
+        // Buffers are allocated for the sized radio frame, so they always
+        // have room for the FCS.
+        let result = self.write_fcs::<Config>();
+        debug_assert!(result.is_ok());
+
         // Safety: The length must be set for a sized MPDU.
         RadioFrame::new::<Config>(self.buffer).with_size(self.length_wo_fcs)
     }
@@ -1,7 +1,11 @@
 mod ack;
 mod beacon;
+mod buffer;
+mod builder;
 mod frame;
 
 pub use ack::*;
 pub use beacon::*;
+pub use buffer::*;
+pub use builder::*;
 pub use frame::*;
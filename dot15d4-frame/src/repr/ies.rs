@@ -2,7 +2,15 @@ use const_for::const_for;
 
 use dot15d4_util::{Error, Result};
 
+use crate::fields::{
+    eb_filter_content_length, TschTimeslotTimings, ALL_MAC_METRICS_CONTENT_LEN,
+    MAC_METRICS_CONTENT_LEN, MODE_SWITCH_PARAMETER_CONTENT_LEN, SUN_FSK_GENERIC_PHY_CONTENT_LEN,
+};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IeRepr<'ie> {
     TimeCorrectionHeaderIe,
     ReducedChannelHoppingNestedIe,
@@ -11,6 +19,12 @@ pub enum IeRepr<'ie> {
     TschSlotframeAndLinkNestedIe(&'ie [u8]), // for each slotframe descriptor: number of links
     ReducedTschTimeslotNestedIe,
     FullTschTimeslotNestedIe,
+    FullTschTimeslotNestedIeLong, // with 3-byte max TX and timeslot length fields
+    EnhancedBeaconFilterNestedIe(bool, bool, u8), // include link quality, include percent filter, attribute ID list length
+    MacMetricsNestedIe,
+    AllMacMetricsNestedIe,
+    SunFskGenericPhyNestedIe,
+    ModeSwitchParameterNestedIe,
 } // 12 bytes
   // TODO: Consider removing IEs based on the supported protocol to reduce size to
   //       1 byte for protocols that don't require parameterized IE config.
@@ -51,7 +65,26 @@ impl IeRepr<'_> {
                     (0, content_len)
                 }
                 IeRepr::ReducedTschTimeslotNestedIe => (0, 1),
-                IeRepr::FullTschTimeslotNestedIe => (0, 25),
+                IeRepr::FullTschTimeslotNestedIe => (0, TschTimeslotTimings::FULL_CONTENT_LEN),
+                IeRepr::FullTschTimeslotNestedIeLong => {
+                    (0, TschTimeslotTimings::FULL_CONTENT_LEN_LONG)
+                }
+                IeRepr::EnhancedBeaconFilterNestedIe(
+                    include_link_quality,
+                    include_percent,
+                    attribute_id_list_len,
+                ) => (
+                    0,
+                    eb_filter_content_length(
+                        *include_link_quality,
+                        *include_percent,
+                        *attribute_id_list_len,
+                    ),
+                ),
+                IeRepr::MacMetricsNestedIe => (0, MAC_METRICS_CONTENT_LEN),
+                IeRepr::AllMacMetricsNestedIe => (0, ALL_MAC_METRICS_CONTENT_LEN),
+                IeRepr::SunFskGenericPhyNestedIe => (0, SUN_FSK_GENERIC_PHY_CONTENT_LEN),
+                IeRepr::ModeSwitchParameterNestedIe => (0, MODE_SWITCH_PARAMETER_CONTENT_LEN),
             };
 
             if header_ie_content_len > 0 {
@@ -68,8 +101,11 @@ impl IeRepr<'_> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IeReprWithTermination<'ie> {
-    NonTerminationIe(IeRepr<'ie>),
+    NonTerminationIe(#[cfg_attr(feature = "serde", serde(borrow))] IeRepr<'ie>),
 
     // Termination IEs will be generated synthetically when building a frame and
     // will be parsed from incoming frames.
@@ -83,6 +119,8 @@ pub enum IeReprWithTermination<'ie> {
 /// The list is generic over the implementation of the IE representation so that
 /// it can accept both, a list including or excluding termination IEs.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IeReprList<'ies, IeRepr>(&'ies [IeRepr]);
 
 impl<'ies, IeRepr> IeReprList<'ies, IeRepr> {
@@ -256,6 +294,8 @@ impl IeReprList<'_, IeReprWithTermination<'_>> {
 ///
 /// Provides functionality required both, on incoming and outgoing MPDUs.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum IeListRepr<'ies> {
     Empty,
     WithTerminationIes(IeReprList<'ies, IeReprWithTermination<'ies>>),
@@ -28,6 +28,7 @@ use super::seq_nr::SeqNrRepr;
 /// The MPDU representation is fully const compatible so that MPDU
 /// configurations can be prepared at compile time.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(bound = ""))]
 pub struct MpduRepr<'repr, State> {
     pub(crate) seq_nr: SeqNrRepr,
     pub(crate) addressing: Option<AddressingRepr>,
@@ -35,6 +36,7 @@ pub struct MpduRepr<'repr, State> {
     pub(crate) security: Option<SecurityRepr>,
     #[cfg(feature = "ies")]
     pub(crate) ies: IeListRepr<'repr>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub(crate) state: PhantomData<&'repr State>, // Lifetime reference required in case IEs are disabled.
 }
 
@@ -178,14 +180,42 @@ impl<'repr> MpduRepr<'repr, MpduWithIes> {
             }
         }
 
-        len += frame_payload_length;
+        len = match len.checked_add(frame_payload_length) {
+            Some(len) => len,
+            None => return Err(Error),
+        };
 
-        // Safety: The above calculation will always yield a non-wrapped,
-        //         non-zero u16.
+        // Safety: The frame control field is always present, so the length is
+        //         non-zero.
         Ok(unsafe { NonZero::new_unchecked(len) })
     }
 
-    /// Calculates the min zero-copy buffer length required to hold this MPDU.
+    /// Calculate the MPDU (=PSDU) length including the FCS given the frame
+    /// payload length.
+    ///
+    /// Compare the result against [`RadioFrameRepr::max_sdu_length()`] to find
+    /// out whether the frame fits into the PHY's MTU before building it. The
+    /// result is also the basis for calculating the frame's Tx duration.
+    ///
+    /// Note: The FCS is only included if it is calculated by the framework,
+    ///       i.e. not if it is offloaded to the driver or hardware (see
+    ///       [`dot15d4_driver::FcsNone`]).
+    ///
+    /// Validates addressing for consistency.
+    pub const fn mpdu_length<Config: DriverConfig>(
+        &self,
+        frame_payload_length: u16,
+    ) -> Result<NonZero<u16>> {
+        match self.mpdu_length_wo_fcs(frame_payload_length) {
+            Ok(mpdu_length_wo_fcs) => {
+                Ok(RadioFrameRepr::<Config, RadioFrameSized>::new(mpdu_length_wo_fcs).sdu_length())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Calculates the min zero-copy buffer length required to hold this MPDU
+    /// including the driver's headroom and tailroom.
     ///
     /// This is convenient when building outgoing frames from scratch.
     ///
@@ -201,8 +231,7 @@ impl<'repr> MpduRepr<'repr, MpduWithIes> {
             Ok(mpdu_size_wo_fcs) => Ok(RadioFrameRepr::<Config, RadioFrameSized>::new(
                 mpdu_size_wo_fcs,
             )
-            .sdu_length()
-            .get() as usize),
+            .pdu_length() as usize),
             Err(e) => Err(e),
         }
     }
@@ -1,4 +1,6 @@
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SecurityLevelRepr {
     Mic32,
     Mic64,
@@ -9,6 +11,18 @@ pub enum SecurityLevelRepr {
 } // 1 byte
 
 impl SecurityLevelRepr {
+    /// Value of the security level subfield of the security control field.
+    pub const fn value(&self) -> u8 {
+        match self {
+            SecurityLevelRepr::Mic32 => 1,
+            SecurityLevelRepr::Mic64 => 2,
+            SecurityLevelRepr::Mic128 => 3,
+            SecurityLevelRepr::EncMic32 => 5,
+            SecurityLevelRepr::EncMic64 => 6,
+            SecurityLevelRepr::EncMic128 => 7,
+        }
+    }
+
     /// MIC length in bytes.
     pub const fn mic_length(&self) -> u16 {
         match self {
@@ -20,6 +34,8 @@ impl SecurityLevelRepr {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyIdRepr {
     Implicit,
     SourceNone,
@@ -39,6 +55,8 @@ impl KeyIdRepr {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SecurityRepr {
     tsch_mode: bool,
     security_level: SecurityLevelRepr,
@@ -58,6 +76,20 @@ impl SecurityRepr {
         }
     }
 
+    /// Whether the frame counter is suppressed and the ASN is used in the
+    /// nonce instead (TSCH mode).
+    pub const fn tsch_mode(&self) -> bool {
+        self.tsch_mode
+    }
+
+    pub const fn security_level(&self) -> SecurityLevelRepr {
+        self.security_level
+    }
+
+    pub const fn key_id(&self) -> KeyIdRepr {
+        self.key_id
+    }
+
     pub const fn aux_sec_header_length(&self) -> u16 {
         const SECURITY_CONTROL_LENGTH: u16 = 1;
 
@@ -0,0 +1,13 @@
+//! Helpers shared by the integration tests.
+
+/// The length of the FCS at the end of frames, unless stated otherwise.
+pub const FCS_LEN: usize = 2;
+
+/// Decodes the given string of hex digits.
+pub fn decode_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len().is_multiple_of(2), "odd number of hex digits");
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).expect("invalid hex digits"))
+        .collect()
+}
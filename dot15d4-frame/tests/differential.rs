@@ -0,0 +1,261 @@
+//! Differential tests against Wireshark.
+//!
+//! Each file in `tests/reference` holds frames dissected by Wireshark, i.e.
+//! the output of `tshark -r capture.pcap -T json -x`. The fields of the frame
+//! control field and the addressing fields decoded by Wireshark are compared
+//! with the ones decoded by [`FrameControl`] and [`FrameSummary`]. Fields
+//! unknown to either side are skipped. All disagreements are reported at once.
+//!
+//! To check a capture without adding it to the repository, point
+//! `DOT15D4_REFERENCE` to its dissection:
+//!
+//! ```sh
+//! tshark -r capture.pcap -T json -x > capture.json
+//! DOT15D4_REFERENCE=capture.json cargo test -p dot15d4-frame --test differential
+//! ```
+//!
+//! Captures with and without FCS are supported, the FCS is stripped if valid.
+
+use dot15d4_driver::frame::{Address, FrameControl};
+use dot15d4_frame::{fcs::check_fcs, fields::FrameSummary};
+use serde_json::Value;
+
+mod common;
+
+use common::{decode_hex, FCS_LEN};
+
+/// The environment variable naming an additional dissection to check.
+const REFERENCE_VAR: &str = "DOT15D4_REFERENCE";
+
+/// Searches the given layer and its subtrees for the given field.
+fn find<'v>(layer: &'v Value, field: &str) -> Option<&'v str> {
+    let object = layer.as_object()?;
+    if let Some(value) = object.get(field) {
+        return value.as_str();
+    }
+    object.values().find_map(|value| find(value, field))
+}
+
+/// Parses a number as formatted by Wireshark, i.e. decimal or hexadecimal.
+fn number(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses a boolean as formatted by Wireshark.
+fn boolean(value: &str) -> Option<bool> {
+    match value {
+        "1" | "True" | "true" => Some(true),
+        "0" | "False" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Formats an extended address like Wireshark, i.e. big endian.
+fn eui64(le_bytes: &[u8]) -> String {
+    le_bytes
+        .iter()
+        .rev()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Records a disagreement if Wireshark decoded the given field differently.
+fn compare(
+    disagreements: &mut Vec<String>,
+    field: &str,
+    ours: Option<String>,
+    theirs: Option<String>,
+) {
+    match (ours, theirs) {
+        (_, None) => {}
+        (Some(ours), Some(theirs)) if ours == theirs => {}
+        (ours, Some(theirs)) => disagreements.push(format!(
+            "{field}\n  wireshark: {theirs}\n  ours:      {}",
+            ours.as_deref().unwrap_or("absent")
+        )),
+    }
+}
+
+/// Collects the disagreements of a single frame with its dissection.
+fn check_packet(packet: &Value) -> Result<(), Vec<String>> {
+    let layers = &packet["_source"]["layers"];
+    let raw = layers["frame_raw"][0]
+        .as_str()
+        .expect("missing \"frame_raw\", run tshark with -x");
+    let Some(wpan) = layers.get("wpan") else {
+        // Not an IEEE 802.15.4 frame.
+        return Ok(());
+    };
+
+    let frame = decode_hex(raw);
+    let mpdu = match frame.split_at_checked(frame.len().saturating_sub(FCS_LEN)) {
+        Some((mpdu, fcs)) if check_fcs(mpdu, fcs).is_ok() => mpdu,
+        _ => &frame[..],
+    };
+
+    let mut disagreements = Vec::new();
+    let number_field = |field: &str| find(wpan, field).and_then(number).map(|n| n.to_string());
+    let boolean_field = |field: &str| find(wpan, field).and_then(boolean).map(|b| b.to_string());
+
+    let frame_control = match FrameControl::new(mpdu) {
+        Ok(frame_control) => frame_control,
+        Err(error) => return Err(vec![format!("malformed frame: {error}")]),
+    };
+    for (field, ours) in [
+        ("wpan.frame_type", frame_control.frame_type() as u8),
+        (
+            "wpan.dst_addr_mode",
+            frame_control.dst_addressing_mode() as u8,
+        ),
+        (
+            "wpan.src_addr_mode",
+            frame_control.src_addressing_mode() as u8,
+        ),
+        ("wpan.version", frame_control.frame_version() as u8),
+    ] {
+        compare(
+            &mut disagreements,
+            field,
+            Some(ours.to_string()),
+            number_field(field),
+        );
+    }
+    for (field, ours) in [
+        ("wpan.security", frame_control.security_enabled()),
+        ("wpan.pending", frame_control.frame_pending()),
+        ("wpan.ack_request", frame_control.ack_request()),
+        (
+            "wpan.pan_id_compression",
+            frame_control.pan_id_compression(),
+        ),
+        (
+            "wpan.seqno_suppression",
+            frame_control.sequence_number_suppression(),
+        ),
+        (
+            "wpan.ie_present",
+            frame_control.information_elements_present(),
+        ),
+    ] {
+        compare(
+            &mut disagreements,
+            field,
+            Some(ours.to_string()),
+            boolean_field(field),
+        );
+    }
+
+    let summary = match FrameSummary::parse(mpdu) {
+        Ok(summary) => summary,
+        Err(error) => {
+            disagreements.push(format!("malformed frame: {error}"));
+            return Err(disagreements);
+        }
+    };
+    compare(
+        &mut disagreements,
+        "wpan.seq_no",
+        summary.sequence_number.map(|seq_nr| seq_nr.to_string()),
+        number_field("wpan.seq_no"),
+    );
+    for (field, pan_id) in [
+        ("wpan.dst_pan", summary.dst_pan_id),
+        ("wpan.src_pan", summary.src_pan_id),
+    ] {
+        compare(
+            &mut disagreements,
+            field,
+            pan_id.map(|pan_id| pan_id.into_u16().to_string()),
+            number_field(field),
+        );
+    }
+    for (short_field, extended_field, address) in [
+        ("wpan.dst16", "wpan.dst64", summary.dst_address),
+        ("wpan.src16", "wpan.src64", summary.src_address),
+    ] {
+        let (short, extended) = match address {
+            Address::Short(address) => (Some(address.into_u16().to_string()), None),
+            Address::Extended(address) => (None, Some(eui64(address.as_ref()))),
+            _ => (None, None),
+        };
+        compare(
+            &mut disagreements,
+            short_field,
+            short,
+            number_field(short_field),
+        );
+        compare(
+            &mut disagreements,
+            extended_field,
+            extended,
+            find(wpan, extended_field).map(str::to_lowercase),
+        );
+    }
+
+    if disagreements.is_empty() {
+        Ok(())
+    } else {
+        Err(disagreements)
+    }
+}
+
+/// Checks all frames of the given dissection and reports all disagreements at
+/// once.
+fn check_reference(file: &str, json: &str) {
+    let packets: Value = serde_json::from_str(json).expect("invalid JSON");
+    let packets = packets.as_array().expect("expected an array of packets");
+    assert!(!packets.is_empty(), "{file} contains no packets");
+
+    let failures: Vec<String> = packets
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, packet)| {
+            check_packet(packet)
+                .err()
+                .map(|errors| format!("{file}: packet {}: {}", idx + 1, errors.join("\n")))
+        })
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+#[test]
+fn wireshark() {
+    check_reference("wireshark.json", include_str!("reference/wireshark.json"));
+}
+
+#[test]
+fn external_reference() {
+    let Some(path) = std::env::var_os(REFERENCE_VAR) else {
+        return;
+    };
+    let json = std::fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("cannot read {}: {error}", path.to_string_lossy()));
+    check_reference(&path.to_string_lossy(), &json);
+}
+
+#[test]
+fn detects_disagreements() {
+    let mut packet: Value = serde_json::json!({
+        "_source": {
+            "layers": {
+                "frame_raw": ["02002a", 0, 3, 0, 1],
+                "wpan": {
+                    "wpan.fcf_tree": { "wpan.frame_type": "0x0002" },
+                    "wpan.seq_no": "42",
+                },
+            },
+        },
+    });
+    assert_eq!(check_packet(&packet), Ok(()));
+
+    packet["_source"]["layers"]["wpan"]["wpan.seq_no"] = "43".into();
+    packet["_source"]["layers"]["wpan"]["wpan.dst16"] = "0xffff".into();
+    let errors = check_packet(&packet).unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(errors[0].starts_with("wpan.seq_no"), "{errors:?}");
+    assert!(errors[1].ends_with("ours:      absent"), "{errors:?}");
+}
@@ -0,0 +1,135 @@
+//! Golden test vectors of realistic traffic.
+//!
+//! Each file in `tests/vectors` holds a list of frames, i.e. hex encoded
+//! MPDUs followed by their FCS, together with their expected [`FrameSummary`]
+//! and their expected [`dissect()`] output, one line per array entry. The FCS
+//! is 2 octets long unless the file sets `fcs_length`, e.g. to 4 for the
+//! Wi-SUN FAN frames of SUN PHYs.
+//!
+//! The frames are not captures. The `annex-c.*` frames are the examples of
+//! IEEE 802.15.4-2006, Annex C, with an FCS appended. All others were
+//! assembled by hand from the frame formats of the specifications named in
+//! their descriptions. They pin the output of the parser, the differential
+//! tests compare it with Wireshark.
+//!
+//! To add a vector, append it to the file of its kind. Parser changes that
+//! alter the decoded structure of a frame show up as a diff of the tree.
+
+use dot15d4_frame::{
+    fcs::{check_fcs, write_fcs},
+    fields::{dissect, FrameSummary},
+};
+use serde_json::Value;
+
+mod common;
+
+use common::{decode_hex, FCS_LEN};
+
+fn string<'v>(vector: &'v Value, key: &str) -> &'v str {
+    vector[key]
+        .as_str()
+        .unwrap_or_else(|| panic!("missing string {key:?}"))
+}
+
+/// Checks a single vector whose frame ends with an FCS of the given length,
+/// returns a description of the first mismatch.
+fn check_vector(vector: &Value, fcs_len: usize) -> Result<(), String> {
+    let frame = decode_hex(string(vector, "frame"));
+    let (mpdu, fcs) = frame.split_at(frame.len() - fcs_len);
+    check_fcs(mpdu, fcs).map_err(|error| format!("invalid FCS: {error}"))?;
+
+    let summary = match FrameSummary::parse(mpdu) {
+        Ok(summary) => summary.to_string(),
+        Err(error) => format!("malformed frame: {error}"),
+    };
+    let expected_summary = string(vector, "summary");
+    if summary != expected_summary {
+        return Err(format!(
+            "summary\n  expected: {expected_summary}\n  got:      {summary}"
+        ));
+    }
+
+    let tree = dissect(mpdu).to_string();
+    let expected_tree: Vec<&str> = vector["tree"]
+        .as_array()
+        .expect("missing array \"tree\"")
+        .iter()
+        .map(|line| line.as_str().expect("tree lines must be strings"))
+        .collect();
+    let lines: Vec<&str> = tree.lines().collect();
+    if lines != expected_tree {
+        let idx = lines
+            .iter()
+            .zip(&expected_tree)
+            .position(|(line, expected)| line != expected)
+            .unwrap_or(lines.len().min(expected_tree.len()));
+        return Err(format!(
+            "tree line {}\n  expected: {:?}\n  got:      {:?}\nfull tree:\n{tree}",
+            idx + 1,
+            expected_tree.get(idx),
+            lines.get(idx)
+        ));
+    }
+    Ok(())
+}
+
+/// Checks all vectors of the given file and reports all mismatches at once.
+fn check_vectors(file: &str, json: &str) {
+    let vectors: Value = serde_json::from_str(json).expect("invalid JSON");
+    let fcs_len = vectors["fcs_length"]
+        .as_u64()
+        .map_or(FCS_LEN, |fcs_len| fcs_len as usize);
+    let vectors = vectors["vectors"].as_array().expect("missing \"vectors\"");
+    assert!(!vectors.is_empty(), "{file} contains no vectors");
+
+    let failures: Vec<String> = vectors
+        .iter()
+        .filter_map(|vector| {
+            check_vector(vector, fcs_len)
+                .err()
+                .map(|error| format!("{file}: {}: {error}", string(vector, "name")))
+        })
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+macro_rules! golden {
+    ($name:ident) => {
+        #[test]
+        fn $name() {
+            check_vectors(
+                concat!(stringify!($name), ".json"),
+                include_str!(concat!("vectors/", stringify!($name), ".json")),
+            );
+        }
+    };
+}
+
+golden!(enhanced_beacons);
+golden!(acks);
+golden!(secured_frames);
+golden!(sixp);
+golden!(wisun);
+
+#[test]
+fn detects_mismatches() {
+    let vector = serde_json::json!({
+        "name": "imm-ack",
+        "frame": "02002a50",
+        "summary": "Ack #42 absent <- absent ies 0+0 payload 0",
+        "tree": ["Frame Control: 0x0002"],
+    });
+    let error = check_vector(&vector, FCS_LEN).unwrap_err();
+    assert!(error.starts_with("invalid FCS"), "{error}");
+
+    let mut vector = vector;
+    vector["frame"] = format!("02002a{}", fcs_hex(&[0x02, 0x00, 0x2a])).into();
+    let error = check_vector(&vector, FCS_LEN).unwrap_err();
+    assert!(error.starts_with("tree line 2"), "{error}");
+}
+
+fn fcs_hex(mpdu: &[u8]) -> String {
+    let mut fcs = [0; FCS_LEN];
+    write_fcs(mpdu, &mut fcs).unwrap();
+    fcs.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -3,7 +3,7 @@
 //! This module provides the upper half of the communication pipe towards IEEE
 //! 802.15.4 radio drivers.
 
-use core::cell::Cell;
+use core::{cell::Cell, future::Future};
 
 use crate::{
     mac::{
@@ -19,17 +19,19 @@ use crate::{
 };
 
 use self::{
+    const_config::PHY_CCA_MODE,
     constants::MAC_AIFS,
     frame::{
-        is_frame_valid_and_for_us, RadioFrame, RadioFrameRepr, RadioFrameSized, RadioFrameUnsized,
+        is_frame_valid_and_for_us, Annotated, RadioFrame, RadioFrameRepr, RadioFrameSized,
+        RadioFrameUnsized,
     },
     tasks::{
         CompletedRadioTransition, ExternalRadioTransition, Ifs, OffResult, OffState, RadioDriver,
         RadioTask, RadioTaskError, RxError, RxResult, RxState, SelfRadioTransition,
-        TaskOff as RadioTaskOff, TaskRx as RadioTaskRx, TaskTx as RadioTaskTx, Timestamp, TxResult,
-        TxState,
+        TaskOff as RadioTaskOff, TaskRx as RadioTaskRx, TaskTx as RadioTaskTx, Timestamp, TxError,
+        TxResult, TxState,
     },
-    time::{timer_frequency, Duration, SymbolsOQpsk250kB},
+    time::{timer_frequency, Duration, Frequency, SymbolsOQpsk250kB},
 };
 
 pub use dot15d4_driver::*;
@@ -249,8 +251,41 @@ enum DriverState<RadioDriverImpl> {
     Off(RadioDriver<RadioDriverImpl, RadioTaskOff>),
 }
 
+/// IFS starts after the reception of the last symbol of the previous PPDU (END
+/// event) and ends with the first symbol of the next PPDU, i.e. the first
+/// symbol of the SHR's preamble in the case of the O-QPSK PHY.
+///
+/// The radio driver polls for the FRAMESTART event which is emitted after
+/// receiving the SHR and PHY header (PHR) when the last symbol of the PHR has
+/// been received.
+///
+/// The SHR consists of 8 symbols preamble and 1 byte SFD (2 symbols). The PHR
+/// is 1 byte (2 symbols).
+///
+/// The ACK timeout counting from the END event until the FRAMESTART event
+/// therefore consists of:
+/// t_ACK = 12 symbols (MAC_AIFS) + 10 symbols (SHR) + 2 symbols (PHR)
+///       = 24 symbols = 384µs.
+const fn driver_rx_ack_timeout<Timer: Frequency>() -> Duration<Timer> {
+    // Note: We cannot use addition of durations as they are non-const.
+    assert!(MAC_AIFS.frequency() == timer_frequency::<SymbolsOQpsk250kB>());
+    Duration::<SymbolsOQpsk250kB>::new(MAC_AIFS.ticks() + 10 + 2).convert_into_rounding_up()
+}
+
+/// Resolves when the ACK timeout started at the current instant expires.
+///
+/// Note: This is just a rough estimate with some safety margin for now.
+///       Precise timing requires timestamp and RX window support in the
+///       driver.
+fn rx_ack_timeout<Timer: RadioTimerApi>() -> impl Future<Output = ()> {
+    Timer::wait_for_alarm_at(Timer::now() + driver_rx_ack_timeout::<Timer>())
+}
+
 /// Structure managing a given driver implementation. Knows about and manages
 /// individual driver capabilities and exposes a unified API to the MAC service.
+///
+/// Internal: Run the driver service via [`crate::Device::run()`] instead.
+#[doc(hidden)]
 pub struct DriverService<'svc, RadioDriverImpl> {
     /// The current radio driver state.
     driver_state: Cell<Option<DriverState<RadioDriverImpl>>>,
@@ -271,34 +306,19 @@ where
     RadioDriver<RadioDriverImpl, RadioTaskRx>: RxState<RadioDriverImpl> + RadioDriverApi,
     RadioDriver<RadioDriverImpl, RadioTaskTx>: TxState<RadioDriverImpl> + RadioDriverApi,
 {
-    /// IFS starts after the reception of the last symbol of the previous PPDU
-    /// (END event) and ends with the first symbol of the next PPDU, i.e. the
-    /// first symbol of the SHR's preamble in the case of the O-QPSK PHY.
-    ///
-    /// The radio driver polls for the FRAMESTART event which is emitted after
-    /// receiving the SHR and PHY header (PHR) when the last symbol of the PHR
-    /// has been received.
-    ///
-    /// The SHR consists of 8 symbols preamble and 1 byte SFD (2 symbols). The
-    /// PHR is 1 byte (2 symbols).
-    ///
-    /// The ACK timeout counting from the END event until the FRAMESTART event
-    /// therefore consists of:
-    /// t_ACK = 12 symbols (MAC_AIFS) + 10 symbols (SHR) + 2 symbols (PHR)
-    ///       = 24 symbols = 384µs.
-    const DRIVER_RX_ACK_TIMEOUT: Duration<RadioDriverImpl::Timer> = {
-        // Note: We cannot use addition of durations as they are non-const.
-        assert!(MAC_AIFS.frequency() == timer_frequency::<SymbolsOQpsk250kB>());
-        Duration::<SymbolsOQpsk250kB>::new(MAC_AIFS.ticks() + 10 + 2).convert_into_rounding_up()
-    };
-
     /// Creates a new [`DriverService`] instance wrapping the given driver
     /// implementation.
+    ///
+    /// The driver is configured with the statically configured CCA mode so
+    /// that CSMA-CA and TSCH CCA behave consistently across drivers
+    /// independently of driver reset defaults.
     pub fn new(
-        driver: RadioDriver<RadioDriverImpl, RadioTaskOff>,
+        mut driver: RadioDriver<RadioDriverImpl, RadioTaskOff>,
         driver_service_receiver: DriverRequestReceiver<'svc>,
         buffer_allocator: MacBufferAllocator,
     ) -> Self {
+        driver.set_cca_mode(PHY_CCA_MODE);
+
         Self {
             driver_state: Cell::new(Some(DriverState::Off(driver))),
             request_receiver: driver_service_receiver,
@@ -342,6 +362,15 @@ where
         )
     }
 
+    /// Sends the frame of a TX task that was cancelled before the driver
+    /// committed to the transmission back to the client.
+    fn report_cancelled_tx_task(&self, response_token: ResponseToken, tx_task: RadioTaskTx) {
+        let tx_task_error: RadioTaskError<RadioTaskTx> =
+            RadioTaskError::Task(TxError::Cancelled(tx_task.radio_frame));
+        self.request_receiver
+            .received(response_token, tx_task_error.into());
+    }
+
     /// Run the main driver service event loop.
     pub async fn run(&self) -> ! {
         let mut consumer_token = self
@@ -393,6 +422,7 @@ where
     ///
     /// If the radio was turned off: Returns the driver in the off state and no
     /// response token.
+    #[allow(clippy::unnecessary_unwrap)]
     async fn try_receive_frame(
         &self,
         mut rx_driver: RadioDriver<RadioDriverImpl, RadioTaskRx>,
@@ -470,6 +500,8 @@ where
             at: Timestamp::BestEffort,
             radio_frame: tx_ack_frame,
             cca: false,
+            tx_power: None,
+            cancellation: None,
         };
 
         match rx_driver
@@ -521,7 +553,7 @@ where
     /// response token.
     async fn receive_frame(
         &self,
-        rx_driver: RadioDriver<RadioDriverImpl, RadioTaskRx>,
+        mut rx_driver: RadioDriver<RadioDriverImpl, RadioTaskRx>,
         rx_ack_info: Option<(RadioFrame<RadioFrameSized>, u8)>,
         prev_task_response_token: ResponseToken,
         next_task_ifs: Ifs,
@@ -535,7 +567,10 @@ where
             if let Some((tx_radio_frame, rx_task_ack_seq_nr)) = rx_ack_info {
                 // Expect RX ACK frame
                 let (tx_result, recovered_rx_frame) = match rx_task_result {
-                    RxResult::Frame(rx_ack_frame) => {
+                    RxResult::Frame(Annotated {
+                        frame: rx_ack_frame,
+                        ..
+                    }) => {
                         // TODO: Support enhanced ACK.
                         const ACK_FC_MASK: u16 = !0x1000; // Frame version 2003 or 2006
                         const ACK_FC: u16 = 0x0002; // Frame type ACK, other flags all zero
@@ -573,126 +608,149 @@ where
             }
         }
 
-        let next_request = self
+        let mut next_request = self
             .request_receiver
             .try_receive_request(&TaskDirection::Any);
-        match next_request {
-            Some((next_response_token, next_request)) => match next_request {
-                DrvSvcRequest::Tx(tx_task) => {
-                    let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
-                    let tx_task_ifs = Ifs::from_mpdu_length(tx_task.radio_frame.sdu_length().get());
-                    match rx_driver
-                        .schedule_tx(tx_task, next_task_ifs, false)
-                        .execute_transition()
-                        .await
-                    {
-                        CompletedRadioTransition::Entered(transition_result) => {
-                            let rx_task_result = transition_result.prev_task_result;
-                            handle_rx_task_result(
-                                self,
-                                prev_task_response_token,
-                                rx_task_result,
-                                rx_ack_info,
-                            );
-
-                            let tx_driver = transition_result.this_state;
-                            (
-                                DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
-                                Some(next_response_token),
-                            )
-                        }
-                        CompletedRadioTransition::Fallback(transition_result, tx_task_error) => {
-                            let rx_task_result = transition_result.prev_task_result;
-                            handle_rx_task_result(
-                                self,
-                                prev_task_response_token,
-                                rx_task_result,
-                                rx_ack_info,
-                            );
+        loop {
+            break match next_request {
+                Some((next_response_token, request)) => match request {
+                    DrvSvcRequest::Tx(tx_task) => {
+                        let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
+                        let tx_task_ifs = Ifs::from_mpdu_length_wo_fcs::<RadioDriverImpl>(
+                            tx_task.radio_frame.sdu_wo_fcs_length().get(),
+                        );
+                        match rx_driver
+                            .schedule_tx(tx_task, next_task_ifs, false)
+                            .execute_transition()
+                            .await
+                        {
+                            CompletedRadioTransition::Entered(transition_result) => {
+                                let rx_task_result = transition_result.prev_task_result;
+                                handle_rx_task_result(
+                                    self,
+                                    prev_task_response_token,
+                                    rx_task_result,
+                                    rx_ack_info,
+                                );
+
+                                let tx_driver = transition_result.this_state;
+                                (
+                                    DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
+                                    Some(next_response_token),
+                                )
+                            }
+                            CompletedRadioTransition::Fallback(
+                                transition_result,
+                                tx_task_error,
+                            ) => {
+                                let rx_task_result = transition_result.prev_task_result;
+                                handle_rx_task_result(
+                                    self,
+                                    prev_task_response_token,
+                                    rx_task_result,
+                                    rx_ack_info,
+                                );
 
-                            self.request_receiver
-                                .received(next_response_token, tx_task_error.into());
+                                self.request_receiver
+                                    .received(next_response_token, tx_task_error.into());
 
-                            let off_driver = transition_result.this_state;
-                            (DriverState::Off(off_driver), None)
+                                let off_driver = transition_result.this_state;
+                                (DriverState::Off(off_driver), None)
+                            }
+                            // The transition was programmed to not roll back on
+                            // CRC error, i.e. the TX task was cancelled. Proceed
+                            // with the next request.
+                            CompletedRadioTransition::Rollback(
+                                recovered_rx_driver,
+                                _,
+                                _,
+                                cancelled_tx_task,
+                            ) => {
+                                self.report_cancelled_tx_task(
+                                    next_response_token,
+                                    cancelled_tx_task,
+                                );
+                                rx_driver = recovered_rx_driver;
+                                next_request = self
+                                    .request_receiver
+                                    .try_receive_request(&TaskDirection::Any);
+                                continue;
+                            }
                         }
-                        // Safety: The transition was programmed to not roll
-                        //         back on CRC error.
-                        CompletedRadioTransition::Rollback(..) => unreachable!(),
                     }
-                }
-                DrvSvcRequest::Rx(rx_task) => {
-                    // We're already receiving another request and are
-                    // therefore guaranteed to make progress. Therefore
-                    // scheduling RX back-to-back is ok.
-                    match rx_driver
-                        .schedule_rx(rx_task, false)
-                        .execute_transition()
-                        .await
-                    {
-                        CompletedRadioTransition::Entered(transition_result) => {
-                            let rx_task_result = transition_result.prev_task_result;
-                            handle_rx_task_result(
-                                self,
-                                prev_task_response_token,
-                                rx_task_result,
-                                rx_ack_info,
-                            );
-
-                            let rx_driver = transition_result.this_state;
-                            (DriverState::Rx(rx_driver), Some(next_response_token))
+                    DrvSvcRequest::Rx(rx_task) => {
+                        // We're already receiving another request and are
+                        // therefore guaranteed to make progress. Therefore
+                        // scheduling RX back-to-back is ok.
+                        match rx_driver
+                            .schedule_rx(rx_task, false)
+                            .execute_transition()
+                            .await
+                        {
+                            CompletedRadioTransition::Entered(transition_result) => {
+                                let rx_task_result = transition_result.prev_task_result;
+                                handle_rx_task_result(
+                                    self,
+                                    prev_task_response_token,
+                                    rx_task_result,
+                                    rx_ack_info,
+                                );
+
+                                let rx_driver = transition_result.this_state;
+                                (DriverState::Rx(rx_driver), Some(next_response_token))
+                            }
+                            // Safety: The transition task was programmed to not
+                            //         roll back on CRC error.
+                            CompletedRadioTransition::Rollback(..) => unreachable!(),
+                            // Safety: Scheduling RX cannot fall back.
+                            CompletedRadioTransition::Fallback(..) => unreachable!(),
                         }
-                        // Safety: The transition task was programmed to not
-                        //         roll back on CRC error.
-                        CompletedRadioTransition::Rollback(..) => unreachable!(),
-                        // Safety: Scheduling RX cannot fall back.
-                        CompletedRadioTransition::Fallback(..) => unreachable!(),
                     }
-                }
-            },
-            None => match rx_driver
-                .schedule_off(
-                    RadioTaskOff {
-                        at: Timestamp::BestEffort,
-                    },
-                    true,
-                )
-                .execute_transition()
-                .await
-            {
-                CompletedRadioTransition::Entered(transition_result) => {
-                    let rx_task_result = transition_result.prev_task_result;
-                    handle_rx_task_result(
-                        self,
-                        prev_task_response_token,
-                        rx_task_result,
-                        rx_ack_info,
-                    );
+                },
+                None => match rx_driver
+                    .schedule_off(
+                        RadioTaskOff {
+                            at: Timestamp::BestEffort,
+                        },
+                        true,
+                    )
+                    .execute_transition()
+                    .await
+                {
+                    CompletedRadioTransition::Entered(transition_result) => {
+                        let rx_task_result = transition_result.prev_task_result;
+                        handle_rx_task_result(
+                            self,
+                            prev_task_response_token,
+                            rx_task_result,
+                            rx_ack_info,
+                        );
 
-                    let off_driver = transition_result.this_state;
-                    (DriverState::Off(off_driver), None)
-                }
-                CompletedRadioTransition::Rollback(
-                    recovered_rx_driver,
-                    rx_task_error,
-                    rx_task_result,
-                    .., // It is safe to drop the off task.
-                ) => {
-                    debug_assert!(matches!(
+                        let off_driver = transition_result.this_state;
+                        (DriverState::Off(off_driver), None)
+                    }
+                    CompletedRadioTransition::Rollback(
+                        recovered_rx_driver,
                         rx_task_error,
-                        RadioTaskError::Task(RxError::CrcError)
-                    ));
-                    debug_assert!(rx_task_result.is_none());
-
-                    // We rolled back to the previous Rx task
-                    (
-                        DriverState::Rx(recovered_rx_driver),
-                        Some(prev_task_response_token),
-                    )
-                }
-                // Safety: Switching the radio off is infallible.
-                CompletedRadioTransition::Fallback(..) => unreachable!(),
-            },
+                        rx_task_result,
+                        .., // It is safe to drop the off task.
+                    ) => {
+                        debug_assert!(matches!(
+                            rx_task_error,
+                            RadioTaskError::Task(RxError::CrcError)
+                        ));
+                        debug_assert!(rx_task_result.is_none());
+
+                        // We rolled back to the previous Rx task
+                        (
+                            DriverState::Rx(recovered_rx_driver),
+                            Some(prev_task_response_token),
+                        )
+                    }
+                    // Safety: Switching the radio off is infallible.
+                    CompletedRadioTransition::Fallback(..) => unreachable!(),
+                },
+            };
         }
     }
 
@@ -725,7 +783,11 @@ where
             CompletedRadioTransition::Entered(transition_result) => {
                 let rx_task_result = transition_result.prev_task_result;
                 let recovered_rx_frame = match rx_task_result {
-                    RxResult::Frame(invalid_frame) | RxResult::FilteredFrame(invalid_frame) => {
+                    RxResult::Frame(Annotated {
+                        frame: invalid_frame,
+                        ..
+                    })
+                    | RxResult::FilteredFrame(invalid_frame) => {
                         invalid_frame.forget_size::<RadioDriverImpl>()
                     }
                     RxResult::RxWindowEnded(recovered_rx_frame)
@@ -761,10 +823,10 @@ where
     /// ended without receiving a frame and the TX request scheduled.
     async fn end_rx_window(
         &self,
-        rx_driver: RadioDriver<RadioDriverImpl, RadioTaskRx>,
+        mut rx_driver: RadioDriver<RadioDriverImpl, RadioTaskRx>,
         prev_task_response_token: ResponseToken,
         rx_ack_info: Option<RadioFrame<RadioFrameSized>>,
-        next_request: Option<(ResponseToken, DrvSvcRequest)>,
+        mut next_request: Option<(ResponseToken, DrvSvcRequest)>,
     ) -> (DriverState<RadioDriverImpl>, Option<ResponseToken>) {
         fn handle_rx_task_result<RadioDriverImpl: DriverConfig>(
             this: &DriverService<'_, RadioDriverImpl>,
@@ -778,7 +840,10 @@ where
             //
             // Note: Well timed protocols should not experience this situation.
             let rx_radio_frame = match rx_task_result {
-                RxResult::Frame(radio_frame) | RxResult::FilteredFrame(radio_frame) => {
+                RxResult::Frame(Annotated {
+                    frame: radio_frame, ..
+                })
+                | RxResult::FilteredFrame(radio_frame) => {
                     radio_frame.forget_size::<RadioDriverImpl>()
                 }
                 RxResult::RxWindowEnded(radio_frame) | RxResult::CrcError(radio_frame) => {
@@ -800,95 +865,122 @@ where
             }
         }
 
-        match next_request {
-            Some((tx_task_response_token, DrvSvcRequest::Tx(tx_task))) => {
-                let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
-                let tx_task_ifs = Ifs::from_mpdu_length(tx_task.radio_frame.sdu_length().get());
-                match rx_driver
-                    .schedule_tx(tx_task, Ifs::None, false)
-                    .execute_transition()
-                    .await
-                {
-                    CompletedRadioTransition::Entered(transition_result) => {
-                        let rx_task_result = transition_result.prev_task_result;
-                        handle_rx_task_result::<RadioDriverImpl>(
-                            self,
-                            rx_task_result,
-                            rx_ack_info,
-                            prev_task_response_token,
-                        );
+        loop {
+            break match next_request {
+                Some((tx_task_response_token, DrvSvcRequest::Tx(tx_task))) => {
+                    let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
+                    let tx_task_ifs = Ifs::from_mpdu_length_wo_fcs::<RadioDriverImpl>(
+                        tx_task.radio_frame.sdu_wo_fcs_length().get(),
+                    );
+                    match rx_driver
+                        .schedule_tx(tx_task, Ifs::None, false)
+                        .execute_transition()
+                        .await
+                    {
+                        CompletedRadioTransition::Entered(transition_result) => {
+                            let rx_task_result = transition_result.prev_task_result;
+                            handle_rx_task_result::<RadioDriverImpl>(
+                                self,
+                                rx_task_result,
+                                rx_ack_info,
+                                prev_task_response_token,
+                            );
 
-                        let tx_driver = transition_result.this_state;
-                        (
-                            DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
-                            Some(tx_task_response_token),
-                        )
-                    }
-                    // Fallback to "off" state due to CCA busy when trying to schedule
-                    // the Tx task.
-                    CompletedRadioTransition::Fallback(transition_result, tx_task_error) => {
-                        let rx_task_result = transition_result.prev_task_result;
-                        handle_rx_task_result::<RadioDriverImpl>(
-                            self,
-                            rx_task_result,
-                            rx_ack_info,
-                            prev_task_response_token,
-                        );
+                            let tx_driver = transition_result.this_state;
+                            (
+                                DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
+                                Some(tx_task_response_token),
+                            )
+                        }
+                        // Fallback to "off" state due to CCA busy when trying to schedule
+                        // the Tx task.
+                        CompletedRadioTransition::Fallback(transition_result, tx_task_error) => {
+                            let rx_task_result = transition_result.prev_task_result;
+                            handle_rx_task_result::<RadioDriverImpl>(
+                                self,
+                                rx_task_result,
+                                rx_ack_info,
+                                prev_task_response_token,
+                            );
 
-                        // Report CCA busy as result of the tx task.
-                        self.request_receiver
-                            .received(tx_task_response_token, tx_task_error.into());
+                            // Report CCA busy as result of the tx task.
+                            self.request_receiver
+                                .received(tx_task_response_token, tx_task_error.into());
 
-                        let off_driver = transition_result.this_state;
-                        (DriverState::Off(off_driver), None)
+                            let off_driver = transition_result.this_state;
+                            (DriverState::Off(off_driver), None)
+                        }
+                        // The transition was programmed not to roll back, i.e.
+                        // the TX task was cancelled.
+                        CompletedRadioTransition::Rollback(
+                            recovered_rx_driver,
+                            _,
+                            _,
+                            cancelled_tx_task,
+                        ) => {
+                            self.report_cancelled_tx_task(
+                                tx_task_response_token,
+                                cancelled_tx_task,
+                            );
+                            if rx_ack_info.is_none() {
+                                // Continue the RX window.
+                                return (
+                                    DriverState::Rx(recovered_rx_driver),
+                                    Some(prev_task_response_token),
+                                );
+                            }
+
+                            // End the RX ACK window.
+                            rx_driver = recovered_rx_driver;
+                            next_request = None;
+                            continue;
+                        }
                     }
-                    // Safety: The transition was programmed not to roll back.
-                    CompletedRadioTransition::Rollback(..) => unreachable!(),
                 }
-            }
-            Some((rx_task_response_token, DrvSvcRequest::Rx(rx_task))) => {
-                let tx_task_result = if let Some(tx_radio_frame) = rx_ack_info {
-                    TxResult::Nack(tx_radio_frame)
-                } else {
-                    // Safety: We only ever end an RX window with another RX task
-                    //         after an RX ACK window timed out.
-                    unreachable!()
-                };
-
-                // Continue the ongoing reception and recover the temporary
-                // frame from the incoming RX task instead.
-                self.temporary_rx_frame.set(Some(rx_task.radio_frame));
-
-                self.request_receiver
-                    .received(prev_task_response_token, tx_task_result.into());
-                (DriverState::Rx(rx_driver), Some(rx_task_response_token))
-            }
-            None => {
-                let off_task = RadioTaskOff {
-                    at: Timestamp::BestEffort,
-                };
-                match rx_driver
-                    .schedule_off(off_task, false)
-                    .execute_transition()
-                    .await
-                {
-                    CompletedRadioTransition::Entered(transition_result) => {
-                        let rx_task_result = transition_result.prev_task_result;
-                        handle_rx_task_result::<RadioDriverImpl>(
-                            self,
-                            rx_task_result,
-                            rx_ack_info,
-                            prev_task_response_token,
-                        );
+                Some((rx_task_response_token, DrvSvcRequest::Rx(rx_task))) => {
+                    let tx_task_result = if let Some(tx_radio_frame) = rx_ack_info {
+                        TxResult::Nack(tx_radio_frame)
+                    } else {
+                        // Safety: We only ever end an RX window with another RX task
+                        //         after an RX ACK window timed out.
+                        unreachable!()
+                    };
+
+                    // Continue the ongoing reception and recover the temporary
+                    // frame from the incoming RX task instead.
+                    self.temporary_rx_frame.set(Some(rx_task.radio_frame));
+
+                    self.request_receiver
+                        .received(prev_task_response_token, tx_task_result.into());
+                    (DriverState::Rx(rx_driver), Some(rx_task_response_token))
+                }
+                None => {
+                    let off_task = RadioTaskOff {
+                        at: Timestamp::BestEffort,
+                    };
+                    match rx_driver
+                        .schedule_off(off_task, false)
+                        .execute_transition()
+                        .await
+                    {
+                        CompletedRadioTransition::Entered(transition_result) => {
+                            let rx_task_result = transition_result.prev_task_result;
+                            handle_rx_task_result::<RadioDriverImpl>(
+                                self,
+                                rx_task_result,
+                                rx_ack_info,
+                                prev_task_response_token,
+                            );
 
-                        let off_driver = transition_result.this_state;
-                        (DriverState::Off(off_driver), None)
+                            let off_driver = transition_result.this_state;
+                            (DriverState::Off(off_driver), None)
+                        }
+                        // Safety: Switching the driver off from an RX state
+                        //         w/o rollback should be infallible.
+                        _ => unreachable!(),
                     }
-                    // Safety: Switching the driver off from an RX state
-                    //         w/o rollback should be infallible.
-                    _ => unreachable!(),
                 }
-            }
+            };
         }
     }
 
@@ -902,7 +994,7 @@ where
     /// response token.
     async fn send_frame(
         &self,
-        tx_driver: RadioDriver<RadioDriverImpl, RadioTaskTx>,
+        mut tx_driver: RadioDriver<RadioDriverImpl, RadioTaskTx>,
         tx_task_response_token: Option<ResponseToken>,
         ack_seq_nr: Option<u8>,
         next_task_ifs: Ifs,
@@ -938,67 +1030,117 @@ where
                 .await;
         }
 
-        let next_request = self
+        let mut next_request = self
             .request_receiver
             .try_receive_request(&TaskDirection::Any);
-        match next_request {
-            Some((next_response_token, next_request)) => match next_request {
-                DrvSvcRequest::Tx(tx_task) => {
-                    let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
-                    let tx_task_ifs = Ifs::from_mpdu_length(tx_task.radio_frame.sdu_length().get());
-                    match tx_driver
-                        .schedule_tx(tx_task, next_task_ifs)
-                        .execute_transition()
-                        .await
-                    {
-                        CompletedRadioTransition::Entered(transition_result) => {
-                            let tx_task_result = transition_result.prev_task_result;
-                            handle_tx_task_result(
-                                self,
-                                tx_task_response_token,
-                                tx_task_result,
-                                ack_seq_nr,
-                            )
-                            .await;
-
-                            let tx_driver = transition_result.this_state;
-                            (
-                                DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
-                                Some(next_response_token),
-                            )
-                        }
-                        CompletedRadioTransition::Fallback(transition_result, tx_task_error) => {
-                            let tx_task_result = transition_result.prev_task_result;
-
-                            if let Some(tx_task_response_token) = tx_task_response_token {
-                                // External request: send back the result.
-                                self.request_receiver
-                                    .received(tx_task_response_token, tx_task_result.into());
-                            } else {
-                                // Tx ACK: recover the pre-allocated ACK frame.
-                                match tx_task_result {
-                                    TxResult::Sent(radio_frame) => {
-                                        self.tx_ack_frame.set(Some(radio_frame));
+        loop {
+            break match next_request {
+                Some((next_response_token, request)) => match request {
+                    DrvSvcRequest::Tx(tx_task) => {
+                        let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
+                        let tx_task_ifs = Ifs::from_mpdu_length_wo_fcs::<RadioDriverImpl>(
+                            tx_task.radio_frame.sdu_wo_fcs_length().get(),
+                        );
+                        match tx_driver
+                            .schedule_tx(tx_task, next_task_ifs)
+                            .execute_transition()
+                            .await
+                        {
+                            CompletedRadioTransition::Entered(transition_result) => {
+                                let tx_task_result = transition_result.prev_task_result;
+                                handle_tx_task_result(
+                                    self,
+                                    tx_task_response_token,
+                                    tx_task_result,
+                                    ack_seq_nr,
+                                )
+                                .await;
+
+                                let tx_driver = transition_result.this_state;
+                                (
+                                    DriverState::Tx(tx_driver, tx_task_ack_seq_nr, tx_task_ifs),
+                                    Some(next_response_token),
+                                )
+                            }
+                            CompletedRadioTransition::Fallback(
+                                transition_result,
+                                tx_task_error,
+                            ) => {
+                                let tx_task_result = transition_result.prev_task_result;
+
+                                if let Some(tx_task_response_token) = tx_task_response_token {
+                                    // External request: send back the result.
+                                    self.request_receiver
+                                        .received(tx_task_response_token, tx_task_result.into());
+                                } else {
+                                    // Tx ACK: recover the pre-allocated ACK frame.
+                                    match tx_task_result {
+                                        TxResult::Sent(radio_frame) => {
+                                            self.tx_ack_frame.set(Some(radio_frame));
+                                        }
+                                        // Safety: Ack frames don't ask for ACK.
+                                        TxResult::Nack(_) => unreachable!(),
                                     }
-                                    // Safety: Ack frames don't ask for ACK.
-                                    TxResult::Nack(_) => unreachable!(),
                                 }
-                            }
 
-                            // Send back the result of the failed transition.
-                            self.request_receiver
-                                .received(next_response_token, tx_task_error.into());
+                                // Send back the result of the failed transition.
+                                self.request_receiver
+                                    .received(next_response_token, tx_task_error.into());
 
-                            let off_driver = transition_result.this_state;
-                            (DriverState::Off(off_driver), None)
+                                let off_driver = transition_result.this_state;
+                                (DriverState::Off(off_driver), None)
+                            }
+                            // The TX task doesn't roll back, i.e. the next TX task
+                            // was cancelled. Proceed with the next request.
+                            CompletedRadioTransition::Rollback(
+                                recovered_tx_driver,
+                                _,
+                                _,
+                                cancelled_tx_task,
+                            ) => {
+                                self.report_cancelled_tx_task(
+                                    next_response_token,
+                                    cancelled_tx_task,
+                                );
+                                tx_driver = recovered_tx_driver;
+                                next_request = self
+                                    .request_receiver
+                                    .try_receive_request(&TaskDirection::Any);
+                                continue;
+                            }
                         }
-                        // Safety: The TX task doesn't roll back.
-                        CompletedRadioTransition::Rollback(..) => unreachable!(),
                     }
-                }
-                DrvSvcRequest::Rx(rx_task) => {
+                    DrvSvcRequest::Rx(rx_task) => {
+                        match tx_driver
+                            .schedule_rx(rx_task, next_task_ifs)
+                            .execute_transition()
+                            .await
+                        {
+                            CompletedRadioTransition::Entered(transition_result) => {
+                                let tx_task_result = transition_result.prev_task_result;
+                                handle_tx_task_result(
+                                    self,
+                                    tx_task_response_token,
+                                    tx_task_result,
+                                    ack_seq_nr,
+                                )
+                                .await;
+
+                                let rx_driver = transition_result.this_state;
+                                (DriverState::Rx(rx_driver), Some(next_response_token))
+                            }
+                            // Safety: The TX task doesn't roll back.
+                            CompletedRadioTransition::Rollback(..) => unreachable!(),
+                            // Safety: Scheduling an RX task doesn't fall back.
+                            CompletedRadioTransition::Fallback(..) => unreachable!(),
+                        }
+                    }
+                },
+                None => {
                     match tx_driver
-                        .schedule_rx(rx_task, next_task_ifs)
+                        .schedule_off(RadioTaskOff {
+                            at: Timestamp::BestEffort,
+                        })
                         .execute_transition()
                         .await
                     {
@@ -1012,42 +1154,15 @@ where
                             )
                             .await;
 
-                            let rx_driver = transition_result.this_state;
-                            (DriverState::Rx(rx_driver), Some(next_response_token))
+                            let off_driver = transition_result.this_state;
+                            (DriverState::Off(off_driver), None)
                         }
-                        // Safety: The TX task doesn't roll back.
-                        CompletedRadioTransition::Rollback(..) => unreachable!(),
-                        // Safety: Scheduling an RX task doesn't fall back.
-                        CompletedRadioTransition::Fallback(..) => unreachable!(),
+                        // Safety: Switching the driver off from a TX state should
+                        //         be infallible.
+                        _ => unreachable!(),
                     }
                 }
-            },
-            None => {
-                match tx_driver
-                    .schedule_off(RadioTaskOff {
-                        at: Timestamp::BestEffort,
-                    })
-                    .execute_transition()
-                    .await
-                {
-                    CompletedRadioTransition::Entered(transition_result) => {
-                        let tx_task_result = transition_result.prev_task_result;
-                        handle_tx_task_result(
-                            self,
-                            tx_task_response_token,
-                            tx_task_result,
-                            ack_seq_nr,
-                        )
-                        .await;
-
-                        let off_driver = transition_result.this_state;
-                        (DriverState::Off(off_driver), None)
-                    }
-                    // Safety: Switching the driver off from a TX state should
-                    //         be infallible.
-                    _ => unreachable!(),
-                }
-            }
+            };
         }
     }
 
@@ -1085,14 +1200,11 @@ where
             CompletedRadioTransition::Fallback(..) => unreachable!(),
         };
 
-        // Note: This is just a rough estimate with some safety margin for now.
-        //       Precise timing requires timestamp and RX window support in the
-        //       driver.
-        let timeout = RadioDriverImpl::Timer::wait_for_alarm_at(
-            RadioDriverImpl::Timer::now() + Self::DRIVER_RX_ACK_TIMEOUT,
-        );
+        let timeout = rx_ack_timeout::<RadioDriverImpl::Timer>();
 
-        let next_task_ifs = Ifs::from_mpdu_length(tx_radio_frame.sdu_length().get());
+        let next_task_ifs = Ifs::from_mpdu_length_wo_fcs::<RadioDriverImpl>(
+            tx_radio_frame.sdu_wo_fcs_length().get(),
+        );
         match select(rx_driver.frame_started(), timeout).await {
             Either::First(_) => {
                 // Receive and validate the incoming frame.
@@ -1137,7 +1249,9 @@ where
             match next_request {
                 DrvSvcRequest::Tx(tx_task) => {
                     let tx_task_ack_seq_nr = tx_task.radio_frame.ack_seq_num();
-                    let tx_task_ifs = Ifs::from_mpdu_length(tx_task.radio_frame.sdu_length().get());
+                    let tx_task_ifs = Ifs::from_mpdu_length_wo_fcs::<RadioDriverImpl>(
+                        tx_task.radio_frame.sdu_wo_fcs_length().get(),
+                    );
                     match off_driver.schedule_tx(tx_task).execute_transition().await {
                         CompletedRadioTransition::Entered(transition_result) => {
                             let tx_driver = transition_result.this_state;
@@ -1155,8 +1269,20 @@ where
                             off_driver = transition_result.this_state;
                             continue;
                         }
-                        // Safety: The Off task doesn't roll back.
-                        CompletedRadioTransition::Rollback(..) => unreachable!(),
+                        // The Off task doesn't roll back, i.e. the TX task was
+                        // cancelled.
+                        CompletedRadioTransition::Rollback(
+                            recovered_off_driver,
+                            _,
+                            _,
+                            cancelled_tx_task,
+                        ) => {
+                            self.report_cancelled_tx_task(next_response_token, cancelled_tx_task);
+
+                            // Wait for the next request.
+                            off_driver = recovered_off_driver;
+                            continue;
+                        }
                     }
                 }
                 DrvSvcRequest::Rx(rx_task) => {
@@ -1175,3 +1301,37 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::driver::{test_clock::TestClock, time::Microseconds};
+
+    #[test]
+    fn rx_ack_timeout_expires_after_384us() {
+        TestClock::reset();
+        TestClock::advance(Duration::new(1_000));
+
+        let mut timeout = pin!(rx_ack_timeout::<TestClock>());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(timeout.as_mut().poll(&mut cx).is_pending());
+
+        TestClock::advance(Duration::new(383));
+        assert!(timeout.as_mut().poll(&mut cx).is_pending());
+
+        TestClock::advance(Duration::new(1));
+        assert_eq!(timeout.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn rx_ack_timeout_rounds_up_to_timer_ticks() {
+        assert_eq!(driver_rx_ack_timeout::<Microseconds>().ticks(), 384);
+        assert_eq!(driver_rx_ack_timeout::<TestClock>().ticks(), 384);
+    }
+}
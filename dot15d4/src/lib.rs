@@ -1,6 +1,9 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 pub mod driver;
 pub mod mac;
+pub mod prelude;
+#[cfg(feature = "smoltcp")]
+pub mod smoltcp;
 
 use dot15d4_driver::{
     tasks::{
@@ -0,0 +1,744 @@
+//! Acknowledgement generation (IEEE 802.15.4-2020, section 6.7.4).
+//!
+//! Frames of version IEEE 802.15.4-2003 and 2006 are acknowledged with an
+//! Imm-Ack that merely echoes the sequence number. Frames of version IEEE
+//! 802.15.4-2015 and later are acknowledged with an Enh-Ack addressed to the
+//! sender of the acknowledged frame that may carry header IEs, e.g. the Time
+//! Correction IE required by TSCH or the CSL IE of a CSL receiver.
+//!
+//! An [`AckIePolicy`] decides per requester which of these header IEs are
+//! embedded and may add further IEs, e.g. vendor specific IEs, see
+//! [`ack_frame_with_policy()`] and [`EnhAckPolicy`].
+//!
+//! The ACK is sent [`AckDelay`] after the end of the acknowledged frame, i.e.
+//! AIFS outside of TSCH and macTsTxAckDelay in TSCH timeslots.
+//!
+//! On the sending side, [`AckMatcher`] recognizes the ACK of a transmitted
+//! frame.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::{MAC_AIFS, PHY_MAX_PACKET_SIZE_127},
+        frame::{
+            AddressingFields, AddressingRepr, FrameControl, FrameType, FrameVersion, PanId,
+            BROADCAST_PAN_ID,
+        },
+        time::{Duration, Frequency, Instant, Microseconds},
+    },
+    mac::frame::{
+        fields::TschTimeslotTimings,
+        mpdu::{FrameBuffer, FrameBuilder},
+        FrameError, FrameErrorKind,
+    },
+};
+
+use super::csl::CslIe;
+
+const FRAME_CONTROL_LEN: usize = 2;
+
+/// The length of header IE headers.
+const IE_HEADER_LEN: usize = 2;
+
+/// The element IDs of the Header Termination IEs.
+const HEADER_TERMINATION_IE_1: u16 = 0x7e;
+const HEADER_TERMINATION_IE_2: u16 = 0x7f;
+
+/// The element ID of the Time Correction header IE.
+const TIME_CORRECTION_IE_ELEMENT_ID: u16 = 0x1e;
+
+/// The length of the content of the Time Correction IE.
+const TIME_CORRECTION_CONTENT_LEN: usize = 2;
+
+/// The length of the Time Correction IE including its header.
+const TIME_CORRECTION_IE_LEN: usize = IE_HEADER_LEN + TIME_CORRECTION_CONTENT_LEN;
+
+/// The largest time correction that can be conveyed in microseconds.
+const MAX_TIME_CORRECTION: i64 = 2047;
+
+/// The max length of the header IEs of an Enh-Ack.
+const ACK_IES_MAX_LEN: usize = 64;
+
+/// An MPDU containing an Imm-Ack or Enh-Ack.
+pub type AckFrame = FrameBuffer<PHY_MAX_PACKET_SIZE_127>;
+
+/// The content of a Time Correction IE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeCorrection {
+    /// Difference between the expected and the actual reception time of the
+    /// acknowledged frame, limited to ±2047µs.
+    pub correction: Duration<Microseconds>,
+    /// Whether the acknowledged frame is rejected (NACK).
+    pub nack: bool,
+}
+
+impl TimeCorrection {
+    /// Decodes the content of a Time Correction IE.
+    pub fn from_bytes(bytes: [u8; TIME_CORRECTION_CONTENT_LEN]) -> Self {
+        let raw = u16::from_le_bytes(bytes);
+        // Sign extend the 12-bit correction.
+        let correction = ((raw << 4) as i16) >> 4;
+        Self {
+            correction: Duration::new(correction as i64),
+            nack: raw & 0x8000 != 0,
+        }
+    }
+
+    /// Encodes the content of a Time Correction IE. Corrections exceeding the
+    /// range of the IE are saturated.
+    pub fn to_bytes(self) -> [u8; TIME_CORRECTION_CONTENT_LEN] {
+        let correction = self
+            .correction
+            .ticks()
+            .clamp(-MAX_TIME_CORRECTION, MAX_TIME_CORRECTION) as u16
+            & 0x0fff;
+        let nack = if self.nack { 0x8000 } else { 0 };
+        (correction | nack).to_le_bytes()
+    }
+}
+
+/// Decides which header IEs are embedded in the Enh-Ack to a requester, see
+/// the module documentation.
+pub trait AckIePolicy {
+    /// Return whether the Enh-Ack to the given requester carries the Time
+    /// Correction IE.
+    ///
+    /// * `requester` - Source address of the acknowledged frame (little
+    ///   endian), empty if it has none
+    fn time_correction(&self, requester: &[u8]) -> bool;
+
+    /// Return whether the Enh-Ack to the given requester carries the CSL IE.
+    ///
+    /// * `requester` - Source address of the acknowledged frame (little
+    ///   endian), empty if it has none
+    fn csl(&self, requester: &[u8]) -> bool;
+
+    /// Write further header IEs for the given requester to the given buffer
+    /// and return their length.
+    ///
+    /// * `requester` - Source address of the acknowledged frame (little
+    ///   endian), empty if it has none
+    /// * `buffer` - The space left for header IEs in the Enh-Ack
+    fn emit_ies(&mut self, _requester: &[u8], _buffer: &mut [u8]) -> usize {
+        0
+    }
+}
+
+/// Embeds all header IEs given to [`ack_frame_with_csl()`].
+struct EmbedAll;
+
+impl AckIePolicy for EmbedAll {
+    fn time_correction(&self, _requester: &[u8]) -> bool {
+        true
+    }
+
+    fn csl(&self, _requester: &[u8]) -> bool {
+        true
+    }
+}
+
+/// Callback writing no vendor specific IEs, see [`EnhAckPolicy::new()`].
+pub type NoVendorIes = fn(&[u8], &mut [u8]) -> usize;
+
+/// The [`AckIePolicy`] of a device depending on its mode of operation.
+pub struct EnhAckPolicy<VendorIes = NoVendorIes> {
+    /// Whether TSCH is on. Every Enh-Ack then carries the Time Correction IE,
+    /// so that the neighbors using this device as time parent stay
+    /// synchronized.
+    pub tsch: bool,
+    /// Whether CSL is on. Every Enh-Ack then carries the CSL IE, so that the
+    /// requesters learn the sampling schedule of this device.
+    pub csl: bool,
+    /// Writes vendor specific header IEs for a requester.
+    vendor_ies: VendorIes,
+}
+
+impl EnhAckPolicy {
+    /// Creates a new [`EnhAckPolicy`] without vendor specific IEs.
+    ///
+    /// * `tsch` - Whether TSCH is on
+    /// * `csl` - Whether CSL is on
+    pub fn new(tsch: bool, csl: bool) -> Self {
+        Self::with_vendor_ies(tsch, csl, |_, _| 0)
+    }
+}
+
+impl<VendorIes: FnMut(&[u8], &mut [u8]) -> usize> EnhAckPolicy<VendorIes> {
+    /// Creates a new [`EnhAckPolicy`] embedding the vendor specific IEs
+    /// written by the given callback.
+    ///
+    /// * `tsch` - Whether TSCH is on
+    /// * `csl` - Whether CSL is on
+    /// * `vendor_ies` - Writes the header IEs for the given requester to the
+    ///   given buffer and returns their length, see
+    ///   [`AckIePolicy::emit_ies()`]
+    pub fn with_vendor_ies(tsch: bool, csl: bool, vendor_ies: VendorIes) -> Self {
+        Self {
+            tsch,
+            csl,
+            vendor_ies,
+        }
+    }
+}
+
+impl<VendorIes: FnMut(&[u8], &mut [u8]) -> usize> AckIePolicy for EnhAckPolicy<VendorIes> {
+    fn time_correction(&self, _requester: &[u8]) -> bool {
+        self.tsch
+    }
+
+    fn csl(&self, _requester: &[u8]) -> bool {
+        self.csl
+    }
+
+    fn emit_ies(&mut self, requester: &[u8], buffer: &mut [u8]) -> usize {
+        (self.vendor_ies)(requester, buffer)
+    }
+}
+
+/// The delay between the end of a received frame and the start of its ACK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckDelay {
+    /// AIFS, used outside of TSCH.
+    Aifs,
+    /// macTsTxAckDelay of the TSCH timeslot template.
+    TxAckDelay(Duration<Microseconds>),
+}
+
+impl AckDelay {
+    /// Return the ACK delay of the given TSCH timeslot template.
+    pub fn tsch(timings: &TschTimeslotTimings) -> Self {
+        Self::TxAckDelay(timings.tx_ack_delay())
+    }
+
+    /// Return the instant at which the transmission of the ACK must start.
+    ///
+    /// * `rx_end` - Instant at which the reception of the acknowledged frame
+    ///   ended
+    pub fn ack_start<Timer: Frequency>(&self, rx_end: Instant<Timer>) -> Instant<Timer> {
+        let delay: Duration<Timer> = match self {
+            Self::Aifs => MAC_AIFS.convert_into_rounding_up(),
+            Self::TxAckDelay(delay) => delay.convert_into_rounding_up(),
+        };
+        rx_end + delay
+    }
+}
+
+/// Build the ACK of a received frame, see the module documentation.
+///
+/// Returns `None` if the frame doesn't request an acknowledgement.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+/// * `time_correction` - Content of the Time Correction IE to include in an
+///   Enh-Ack, ignored for Imm-Acks
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::InvalidFrameVersion`] if the frame version is reserved,
+/// - [`FrameErrorKind::SecurityNotSupported`] if an Enh-Ack is required for a
+///   secured frame,
+/// - [`FrameErrorKind::IesNotSupported`] if a Time Correction IE is given
+///   without IE support,
+/// - any other error if the frame is truncated.
+pub fn ack_frame(
+    mpdu: &[u8],
+    time_correction: Option<TimeCorrection>,
+) -> Result<Option<AckFrame>, FrameError> {
+    ack_frame_with_csl(mpdu, time_correction, None)
+}
+
+/// Build the ACK of a received frame like [`ack_frame()`], including the given
+/// CSL IE in an Enh-Ack.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+/// * `time_correction` - Content of the Time Correction IE to include in an
+///   Enh-Ack, ignored for Imm-Acks
+/// * `csl` - Content of the CSL IE to include in an Enh-Ack, ignored for
+///   Imm-Acks
+pub fn ack_frame_with_csl(
+    mpdu: &[u8],
+    time_correction: Option<TimeCorrection>,
+    csl: Option<CslIe>,
+) -> Result<Option<AckFrame>, FrameError> {
+    ack_frame_with_policy(mpdu, &mut EmbedAll, time_correction, csl)
+}
+
+/// Build the ACK of a received frame like [`ack_frame()`], embedding the
+/// header IEs of an Enh-Ack as decided by the given policy.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+/// * `policy` - Decides which header IEs to embed in an Enh-Ack
+/// * `time_correction` - Content of the Time Correction IE, if known
+/// * `csl` - Content of the CSL IE, if CSL is on
+pub fn ack_frame_with_policy<Policy: AckIePolicy + ?Sized>(
+    mpdu: &[u8],
+    policy: &mut Policy,
+    time_correction: Option<TimeCorrection>,
+    csl: Option<CslIe>,
+) -> Result<Option<AckFrame>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if !frame_control.ack_request() || frame_control.frame_type() == FrameType::Ack {
+        return Ok(None);
+    }
+
+    match frame_control.frame_version() {
+        FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
+            let seq_nr = field(mpdu, FRAME_CONTROL_LEN, 1)?[0];
+            let builder = FrameBuilder::new(FrameType::Ack)
+                .with_sequence_number(seq_nr)
+                .without_addressing()
+                .without_security()
+                .without_ies()
+                .without_payload();
+            AckFrame::from_builder(&builder).map(Some)
+        }
+        FrameVersion::Ieee802154 => {
+            enh_ack_frame(mpdu, frame_control, policy, time_correction, csl).map(Some)
+        }
+        FrameVersion::Unknown => Err(FrameErrorKind::InvalidFrameVersion.into()),
+    }
+}
+
+/// Build the Enh-Ack of a received frame of version IEEE 802.15.4-2015 or
+/// later.
+///
+/// The sequence number is copied unless it is suppressed in the acknowledged
+/// frame. The source address of the acknowledged frame becomes the destination
+/// address of the Enh-Ack. The source address is elided as the sender of the
+/// acknowledged frame knows from whom it expects the ACK.
+fn enh_ack_frame<Policy: AckIePolicy + ?Sized>(
+    mpdu: &[u8],
+    frame_control: FrameControl<&[u8]>,
+    policy: &mut Policy,
+    time_correction: Option<TimeCorrection>,
+    csl: Option<CslIe>,
+) -> Result<AckFrame, FrameError> {
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    let dst = header.addressing_fields.and_then(|addressing_fields| {
+        let pan_id = addressing_fields
+            .dst_pan_id()
+            .or(addressing_fields.src_pan_id())
+            .unwrap_or(BROADCAST_PAN_ID)
+            .into_u16();
+        addressing_fields
+            .into_src_address()
+            .map(|address| (PanId::from_u16(pan_id), address))
+    });
+
+    let requester = dst
+        .as_ref()
+        .map_or(&[][..], |(_, address)| address.as_le_bytes());
+    let time_correction = time_correction.filter(|_| policy.time_correction(requester));
+    let csl = csl.filter(|_| policy.csl(requester));
+
+    let mut ies = [0; ACK_IES_MAX_LEN];
+    let mut ies_len = match time_correction {
+        Some(time_correction) => {
+            let header = (TIME_CORRECTION_IE_ELEMENT_ID << 7) | TIME_CORRECTION_CONTENT_LEN as u16;
+            ies[..IE_HEADER_LEN].copy_from_slice(&header.to_le_bytes());
+            ies[IE_HEADER_LEN..TIME_CORRECTION_IE_LEN].copy_from_slice(&time_correction.to_bytes());
+            TIME_CORRECTION_IE_LEN
+        }
+        None => 0,
+    };
+    if let Some(csl) = csl {
+        ies_len += csl.emit(&mut ies[ies_len..]);
+    }
+    let buffer = &mut ies[ies_len..];
+    ies_len += policy.emit_ies(requester, buffer).min(buffer.len());
+
+    let builder = FrameBuilder::new(FrameType::Ack).with_enhanced_frame_version();
+    let builder = match header.seq_nr {
+        Some(seq_nr) => builder.with_sequence_number(seq_nr),
+        None => builder,
+    };
+    let builder = builder.with_addressing(dst, None).without_security();
+    #[cfg(feature = "ies")]
+    let builder = builder.with_ies(&ies[..ies_len]);
+    #[cfg(not(feature = "ies"))]
+    let builder = if ies_len > 0 {
+        return Err(FrameErrorKind::IesNotSupported.into());
+    } else {
+        builder.without_ies()
+    };
+    AckFrame::from_builder(&builder.without_payload())
+}
+
+/// The ACK received for a transmitted frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceivedAck {
+    /// Whether the sender of the ACK has frames pending for the receiver,
+    /// e.g. in response to a Data Request command.
+    pub frame_pending: bool,
+    /// The content of the Time Correction IE of an Enh-Ack, if present.
+    pub time_correction: Option<TimeCorrection>,
+    /// The content of the CSL IE of an Enh-Ack, if present.
+    pub csl: Option<CslIe>,
+}
+
+impl ReceivedAck {
+    /// Return whether the receiver rejected the frame.
+    pub fn is_nack(&self) -> bool {
+        self.time_correction
+            .is_some_and(|time_correction| time_correction.nack)
+    }
+}
+
+/// Recognizes the ACK of a transmitted frame among received frames.
+///
+/// Imm-Acks are matched by sequence number. Enh-Acks are matched by sequence
+/// number and, if addressed, by their destination address, which must be the
+/// source address of the transmitted frame. The Enh-Ack of a frame without
+/// sequence number must therefore be addressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AckMatcher {
+    /// Whether an Enh-Ack is expected.
+    enhanced: bool,
+    /// The sequence number of the transmitted frame.
+    seq_nr: Option<u8>,
+    /// The source address of the transmitted frame (little endian), empty if
+    /// absent.
+    src_address: heapless::Vec<u8, 8>,
+}
+
+impl AckMatcher {
+    /// Creates the matcher for the ACK of the given frame.
+    ///
+    /// Returns `None` if the frame doesn't request an acknowledgement.
+    ///
+    /// * `mpdu` - Transmitted MPDU (without FCS)
+    pub fn new(mpdu: &[u8]) -> Result<Option<Self>, FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        if !frame_control.ack_request() {
+            return Ok(None);
+        }
+        let enhanced = match frame_control.frame_version() {
+            FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => false,
+            FrameVersion::Ieee802154 => true,
+            FrameVersion::Unknown => return Err(FrameErrorKind::InvalidFrameVersion.into()),
+        };
+
+        let header = MacHeader::parse(mpdu, frame_control)?;
+        let src_address = header
+            .addressing_fields
+            .and_then(|addressing_fields| addressing_fields.into_src_address())
+            // Safety: Addresses never exceed 8 bytes.
+            .map(|address| heapless::Vec::from_slice(address.as_le_bytes()).unwrap())
+            .unwrap_or_default();
+        Ok(Some(Self {
+            enhanced,
+            seq_nr: header.seq_nr,
+            src_address,
+        }))
+    }
+
+    /// Return the content of the given frame if it is the expected ACK.
+    ///
+    /// * `mpdu` - Received MPDU (without FCS)
+    pub fn matches(&self, mpdu: &[u8]) -> Option<ReceivedAck> {
+        let frame_control = FrameControl::new(mpdu).ok()?;
+        let enhanced = frame_control.frame_version() == FrameVersion::Ieee802154;
+        if frame_control.frame_type() != FrameType::Ack
+            || enhanced != self.enhanced
+            || frame_control.security_enabled()
+        {
+            return None;
+        }
+        let ies_present = frame_control.information_elements_present();
+        let frame_pending = frame_control.frame_pending();
+
+        let header = MacHeader::parse(mpdu, frame_control).ok()?;
+        if header.seq_nr != self.seq_nr {
+            return None;
+        }
+        match header
+            .addressing_fields
+            .as_ref()
+            .and_then(|addressing_fields| addressing_fields.dst_address())
+        {
+            Some(address) if *address.as_le_bytes() != *self.src_address => return None,
+            None if self.seq_nr.is_none() => return None,
+            _ => {}
+        }
+
+        let (time_correction, csl) = if ies_present {
+            (
+                find_header_ie(mpdu, header.end, TIME_CORRECTION_IE_ELEMENT_ID)
+                    .and_then(|content| content.try_into().ok())
+                    .map(TimeCorrection::from_bytes),
+                find_header_ie(mpdu, header.end, CslIe::ELEMENT_ID).and_then(CslIe::from_bytes),
+            )
+        } else {
+            (None, None)
+        };
+        Some(ReceivedAck {
+            frame_pending,
+            time_correction,
+            csl,
+        })
+    }
+}
+
+/// The sequence number and addressing fields of a frame.
+pub(super) struct MacHeader<'frame> {
+    pub(super) seq_nr: Option<u8>,
+    pub(super) addressing_fields: Option<AddressingFields<&'frame [u8]>>,
+    /// The offset of the first byte following the addressing fields.
+    pub(super) end: usize,
+}
+
+impl<'frame> MacHeader<'frame> {
+    pub(super) fn parse(
+        mpdu: &'frame [u8],
+        frame_control: FrameControl<&[u8]>,
+    ) -> Result<Self, FrameError> {
+        let mut offset = FRAME_CONTROL_LEN;
+        let seq_nr = if frame_control.sequence_number_suppression() {
+            None
+        } else {
+            offset += 1;
+            Some(field(mpdu, FRAME_CONTROL_LEN, 1)?[0])
+        };
+
+        let addressing_fields = match AddressingRepr::from_frame_control(frame_control)? {
+            Some(addressing) => {
+                let length = addressing
+                    .addressing_fields_length()
+                    .map_err(|_| FrameErrorKind::InvalidAddressingCombination)?
+                    as usize;
+                let addressing_fields =
+                    AddressingFields::new(field(mpdu, offset, length)?, addressing)
+                        .map_err(|e| e.shifted_by(offset))?;
+                offset += length;
+                Some(addressing_fields)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            seq_nr,
+            addressing_fields,
+            end: offset,
+        })
+    }
+}
+
+/// Search the header IEs starting at the given offset for the IE with the
+/// given element ID and return its content.
+pub(super) fn find_header_ie(mpdu: &[u8], mut offset: usize, element_id: u16) -> Option<&[u8]> {
+    while let Some(header) = mpdu.get(offset..offset + IE_HEADER_LEN) {
+        let header = u16::from_le_bytes([header[0], header[1]]);
+        let length = (header & 0x7f) as usize;
+        let start = offset + IE_HEADER_LEN;
+        let content = mpdu.get(start..start + length)?;
+        match (header >> 7) & 0xff {
+            id if id == element_id => return Some(content),
+            HEADER_TERMINATION_IE_1 | HEADER_TERMINATION_IE_2 => return None,
+            _ => offset = start + length,
+        }
+    }
+    None
+}
+
+/// Returns the field at the given offset or an error if the MPDU is too short.
+pub(in crate::mac) fn field(
+    mpdu: &[u8],
+    offset: usize,
+    length: usize,
+) -> Result<&[u8], FrameError> {
+    mpdu.get(offset..offset + length).ok_or_else(|| {
+        FrameErrorKind::BufferTooShort {
+            needed: offset + length,
+            got: mpdu.len(),
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::frame::{Address, ExtendedAddress, ShortAddress};
+
+    use super::*;
+
+    const SRC_ADDRESS: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    /// Data frame (2006) requesting an ACK.
+    const DATA_2006: [u8; 12] = [
+        0x61, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33,
+    ];
+
+    /// Build a data frame (2015) requesting an ACK.
+    fn data_2015(seq_nr: Option<u8>, src_address: &[u8; 8]) -> FrameBuffer<32> {
+        let pan_id = PanId::from_u16(0xabcd);
+        let builder = FrameBuilder::new(FrameType::Data);
+        let builder = match seq_nr {
+            Some(seq_nr) => builder.with_sequence_number(seq_nr),
+            None => builder,
+        };
+        let builder = builder
+            .with_ack_request(true)
+            .with_enhanced_frame_version()
+            .with_addressing(
+                Some((pan_id, Address::Short(ShortAddress::new(&[0x02, 0x00][..])))),
+                Some((
+                    pan_id,
+                    Address::Extended(ExtendedAddress::new(&src_address[..])),
+                )),
+            )
+            .without_security()
+            .without_ies()
+            .with_payload(&[0x11]);
+        FrameBuffer::from_builder(&builder).unwrap()
+    }
+
+    #[test]
+    fn imm_ack() {
+        let ack = ack_frame(&DATA_2006, None).unwrap().unwrap();
+        assert_eq!(&ack[..], &[0x02, 0x10, 0x2a]);
+
+        // Without ACK request.
+        let mut mpdu = DATA_2006;
+        mpdu[0] &= !0x20;
+        assert_eq!(ack_frame(&mpdu, None), Ok(None));
+    }
+
+    #[test]
+    #[cfg(feature = "ies")]
+    fn enh_ack() {
+        let mpdu = data_2015(Some(9), &SRC_ADDRESS);
+        let time_correction = TimeCorrection {
+            correction: Duration::new(-5),
+            nack: false,
+        };
+        let ack = ack_frame(&mpdu, Some(time_correction)).unwrap().unwrap();
+        let fc = ack.frame_control().unwrap();
+        assert_eq!(fc.frame_type(), FrameType::Ack);
+        assert_eq!(fc.frame_version(), FrameVersion::Ieee802154);
+        assert!(fc.information_elements_present());
+        assert_eq!(
+            ack[2..],
+            [
+                0x09, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x02, 0x0f, 0xfb,
+                0x0f
+            ]
+        );
+
+        let ack = ack_frame(&mpdu, None).unwrap().unwrap();
+        assert!(!ack.frame_control().unwrap().information_elements_present());
+        assert_eq!(ack.len(), 2 + 1 + 2 + 8);
+    }
+
+    #[test]
+    #[cfg(feature = "ies")]
+    fn ack_matcher() {
+        let matcher = AckMatcher::new(&DATA_2006).unwrap().unwrap();
+        let plain_ack = ReceivedAck {
+            frame_pending: false,
+            time_correction: None,
+            csl: None,
+        };
+        assert_eq!(matcher.matches(&[0x02, 0x10, 0x2a]), Some(plain_ack));
+        assert_eq!(matcher.matches(&[0x02, 0x10, 0x2b]), None);
+        assert!(matcher.matches(&[0x12, 0x10, 0x2a]).unwrap().frame_pending);
+        assert_eq!(matcher.matches(&DATA_2006), None);
+
+        let mpdu = data_2015(Some(9), &SRC_ADDRESS);
+        let matcher = AckMatcher::new(&mpdu).unwrap().unwrap();
+        let time_correction = TimeCorrection {
+            correction: Duration::new(20),
+            nack: true,
+        };
+        let ack = ack_frame(&mpdu, Some(time_correction)).unwrap().unwrap();
+        let received = matcher.matches(&ack).unwrap();
+        assert_eq!(received.time_correction, Some(time_correction));
+        assert!(received.is_nack());
+        assert_eq!(matcher.matches(&[0x02, 0x10, 0x09]), None);
+
+        // Without sequence numbers, Enh-Acks are matched by address.
+        let mpdu = data_2015(None, &SRC_ADDRESS);
+        let matcher = AckMatcher::new(&mpdu).unwrap().unwrap();
+        let ack = ack_frame(&mpdu, None).unwrap().unwrap();
+        assert_eq!(matcher.matches(&ack), Some(plain_ack));
+        let other_ack = ack_frame(&data_2015(None, &[0x01; 8]), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(matcher.matches(&other_ack), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ies")]
+    fn enh_ack_policy() {
+        let mpdu = data_2015(Some(9), &SRC_ADDRESS);
+        let time_correction = TimeCorrection {
+            correction: Duration::new(-5),
+            nack: false,
+        };
+
+        // Outside of TSCH, the Time Correction IE is left out.
+        let mut policy = EnhAckPolicy::new(false, false);
+        let ack = ack_frame_with_policy(&mpdu, &mut policy, Some(time_correction), None)
+            .unwrap()
+            .unwrap();
+        assert!(!ack.frame_control().unwrap().information_elements_present());
+
+        let mut policy = EnhAckPolicy::new(true, false);
+        let ack = ack_frame_with_policy(&mpdu, &mut policy, Some(time_correction), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(ack[ack.len() - 4..], [0x02, 0x0f, 0xfb, 0x0f]);
+
+        // Vendor specific IEs follow the Time Correction IE.
+        let mut policy = EnhAckPolicy::with_vendor_ies(true, false, |requester, buffer| {
+            assert_eq!(requester, SRC_ADDRESS);
+            buffer[..3].copy_from_slice(&[0x01, 0x00, 0xaa]);
+            3
+        });
+        let ack = ack_frame_with_policy(&mpdu, &mut policy, Some(time_correction), None)
+            .unwrap()
+            .unwrap();
+        let received = AckMatcher::new(&mpdu).unwrap().unwrap().matches(&ack);
+        assert_eq!(received.unwrap().time_correction, Some(time_correction));
+        assert_eq!(
+            ack[ack.len() - 7..],
+            [0x02, 0x0f, 0xfb, 0x0f, 0x01, 0x00, 0xaa]
+        );
+    }
+
+    #[test]
+    fn time_correction() {
+        for (correction, nack, bytes) in [
+            (-5, false, [0xfb, 0x0f]),
+            (100, true, [0x64, 0x80]),
+            (5000, false, [0xff, 0x07]),
+            (-5000, true, [0x01, 0x88]),
+        ] {
+            let time_correction = TimeCorrection {
+                correction: Duration::new(correction),
+                nack,
+            };
+            assert_eq!(time_correction.to_bytes(), bytes);
+            assert_eq!(
+                TimeCorrection::from_bytes(bytes).correction.ticks(),
+                correction.clamp(-MAX_TIME_CORRECTION, MAX_TIME_CORRECTION)
+            );
+            assert_eq!(TimeCorrection::from_bytes(bytes).nack, nack);
+        }
+    }
+
+    #[test]
+    fn ack_delay() {
+        let rx_end = Instant::<Microseconds>::new(1_000);
+        // 12 symbols of 16µs each.
+        assert_eq!(AckDelay::Aifs.ack_start(rx_end), Instant::new(1_192));
+
+        let timings = TschTimeslotTimings::default();
+        assert_eq!(
+            AckDelay::tsch(&timings).ack_start(rx_end),
+            rx_end + timings.tx_ack_delay()
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! MAC statistics for health monitoring.
+//!
+//! The MAC sublayer counts transmissions, receptions and failures in the
+//! global [`MAC_COUNTERS`]. Counters are updated atomically and may be read or
+//! reset by the application at any time, e.g. from another task or an
+//! interrupt handler.
+//!
+//! Counting is enabled by the `counters` feature. Without it, all counters are
+//! zero-sized, updates compile to nothing and reading a counter always yields
+//! zero.
+//!
+//! Counters wrap around on overflow.
+
+#[cfg(feature = "counters")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::driver::frame::FrameType;
+
+/// The counters of the MAC sublayer.
+pub static MAC_COUNTERS: MacCounters = MacCounters::new();
+
+/// A single event counter, see the module documentation.
+#[derive(Debug, Default)]
+pub struct Counter {
+    #[cfg(feature = "counters")]
+    value: AtomicU32,
+}
+
+impl Counter {
+    /// Creates a new counter starting at zero.
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "counters")]
+            value: AtomicU32::new(0),
+        }
+    }
+
+    /// Count an event.
+    #[inline]
+    pub fn increment(&self) {
+        #[cfg(feature = "counters")]
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Return the number of events counted since the last reset.
+    #[cfg(feature = "counters")]
+    #[inline]
+    pub fn get(&self) -> u32 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of events counted since the last reset.
+    #[cfg(not(feature = "counters"))]
+    #[inline]
+    pub fn get(&self) -> u32 {
+        0
+    }
+
+    /// Reset the counter to zero.
+    #[inline]
+    pub fn reset(&self) {
+        #[cfg(feature = "counters")]
+        self.value.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Statistics of the MAC sublayer, see the module documentation.
+#[derive(Debug, Default)]
+pub struct MacCounters {
+    /// Frames transmitted and acknowledged if requested.
+    pub tx_success: Counter,
+    /// Frames not transmitted because the channel was busy.
+    pub tx_channel_access_failure: Counter,
+    /// Frames not acknowledged after all retransmissions.
+    pub tx_no_ack: Counter,
+    /// Frames not transmitted because they exceeded the duty cycle budget of
+    /// their band.
+    pub tx_duty_cycle_exceeded: Counter,
+    /// Received beacons.
+    pub rx_beacon: Counter,
+    /// Received data frames, including duplicates.
+    pub rx_data: Counter,
+    /// Received acknowledgements.
+    pub rx_ack: Counter,
+    /// Received MAC commands.
+    pub rx_command: Counter,
+    /// Received frames of any other type.
+    pub rx_other: Counter,
+    /// Clear channel assessments that found the channel busy.
+    pub cca_busy: Counter,
+    /// Transmission attempts whose acknowledgement didn't arrive in time.
+    pub ack_timeouts: Counter,
+    /// Received frames dropped as duplicates.
+    pub duplicate_drops: Counter,
+    /// Received frames dropped because they couldn't be unsecured.
+    pub security_failures: Counter,
+    /// Received frames dropped as replays, i.e. their frame counter or ASN was
+    /// already seen from the sender.
+    pub replay_drops: Counter,
+    /// Frames dropped because a queue was full, i.e. received frames that the
+    /// upper layer didn't ingest in time or transmissions exceeding the
+    /// transmit or transaction queue.
+    pub queue_overflows: Counter,
+}
+
+impl MacCounters {
+    /// Creates new counters starting at zero.
+    pub const fn new() -> Self {
+        Self {
+            tx_success: Counter::new(),
+            tx_channel_access_failure: Counter::new(),
+            tx_no_ack: Counter::new(),
+            tx_duty_cycle_exceeded: Counter::new(),
+            rx_beacon: Counter::new(),
+            rx_data: Counter::new(),
+            rx_ack: Counter::new(),
+            rx_command: Counter::new(),
+            rx_other: Counter::new(),
+            cca_busy: Counter::new(),
+            ack_timeouts: Counter::new(),
+            duplicate_drops: Counter::new(),
+            security_failures: Counter::new(),
+            replay_drops: Counter::new(),
+            queue_overflows: Counter::new(),
+        }
+    }
+
+    /// Count a received frame of the given type.
+    pub fn count_rx(&self, frame_type: FrameType) {
+        match frame_type {
+            FrameType::Beacon => self.rx_beacon.increment(),
+            FrameType::Data => self.rx_data.increment(),
+            FrameType::Ack => self.rx_ack.increment(),
+            FrameType::MacCommand => self.rx_command.increment(),
+            _ => self.rx_other.increment(),
+        }
+    }
+
+    /// Return the total number of received frames.
+    pub fn rx_total(&self) -> u32 {
+        [
+            &self.rx_beacon,
+            &self.rx_data,
+            &self.rx_ack,
+            &self.rx_command,
+            &self.rx_other,
+        ]
+        .iter()
+        .fold(0u32, |total, counter| total.wrapping_add(counter.get()))
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        for counter in [
+            &self.tx_success,
+            &self.tx_channel_access_failure,
+            &self.tx_no_ack,
+            &self.tx_duty_cycle_exceeded,
+            &self.rx_beacon,
+            &self.rx_data,
+            &self.rx_ack,
+            &self.rx_command,
+            &self.rx_other,
+            &self.cca_busy,
+            &self.ack_timeouts,
+            &self.duplicate_drops,
+            &self.security_failures,
+            &self.replay_drops,
+            &self.queue_overflows,
+        ] {
+            counter.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "counters")]
+    fn counters() {
+        // Use local counters as the global ones are updated by other tests.
+        let counters = MacCounters::new();
+        counters.tx_success.increment();
+        counters.tx_success.increment();
+        counters.count_rx(FrameType::Data);
+        counters.count_rx(FrameType::Beacon);
+        counters.count_rx(FrameType::Multipurpose);
+        assert_eq!(counters.tx_success.get(), 2);
+        assert_eq!(counters.rx_data.get(), 1);
+        assert_eq!(counters.rx_beacon.get(), 1);
+        assert_eq!(counters.rx_other.get(), 1);
+        assert_eq!(counters.rx_total(), 3);
+
+        counters.reset();
+        assert_eq!(counters.tx_success.get(), 0);
+        assert_eq!(counters.rx_total(), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "counters"))]
+    fn disabled_counters() {
+        assert_eq!(core::mem::size_of::<MacCounters>(), 0);
+        MAC_COUNTERS.tx_success.increment();
+        assert_eq!(MAC_COUNTERS.tx_success.get(), 0);
+    }
+}
@@ -0,0 +1,966 @@
+//! Coordinated sampled listening (IEEE 802.15.4-2020, section 6.12.2).
+//!
+//! CSL is a low-power mode that, unlike TSCH, needs no network-wide schedule.
+//! A [`CslReceiver`] samples the channel once every macCslPeriod for a short
+//! window and keeps its radio off otherwise. It announces when it samples next
+//! in the CSL IE of its Enh-Acks, see [`CslReceiver::ack_frame()`], and of the
+//! frames it sends.
+//!
+//! A [`CslTransmitter`] learns the phase of a receiver from these IEs, see
+//! [`CslPeer`], and starts timed transmissions shortly after the next sample
+//! of the receiver. The start is delayed by a CSL unit, as phases are rounded
+//! down, and by a guard time covering the drift of both clocks since the phase
+//! was learned. Without a known phase, or once the guard time no longer fits
+//! into the sample window, the frame is preceded by a wake-up sequence:
+//! back-to-back wake-up frames that cover a whole period, each with a
+//! Rendezvous Time IE announcing the start of the frame.
+//!
+//! CSL phases, periods and rendezvous times are given in units of 10 symbols.
+//! Phases are measured from the start of the frame carrying the CSL IE,
+//! rendezvous times from the end of the wake-up frame.
+//!
+//! Note: Wake-up frames are multipurpose frames with a long frame control
+//! field, without security and source address.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::FCS_LEN,
+        frame::FrameControl,
+        phy::{air_time, PhyParameters},
+        radio::Radio,
+        time::{Duration, Frequency, Instant, Microseconds},
+        RadioTimerApi,
+    },
+    mac::frame::{FrameError, FrameErrorKind},
+};
+
+use super::{
+    ack::{ack_frame_with_csl, find_header_ie, AckDelay, AckFrame, MacHeader, TimeCorrection},
+    csma::{CsmaConfig, CsmaConfigError},
+    radio::MacRadio,
+    retransmission::{Retransmissions, TxReport},
+};
+
+/// The length of header IE headers.
+const IE_HEADER_LEN: usize = 2;
+
+/// The length of the content of a CSL IE without Rendezvous Time field.
+const CSL_IE_CONTENT_LEN: usize = 4;
+
+/// The length of the content of a CSL IE with Rendezvous Time field.
+const CSL_IE_FULL_CONTENT_LEN: usize = 6;
+
+/// The max length of a CSL IE including its header.
+pub const CSL_IE_MAX_LEN: usize = IE_HEADER_LEN + CSL_IE_FULL_CONTENT_LEN;
+
+/// The element ID of the Rendezvous Time header IE.
+const RENDEZVOUS_TIME_IE_ELEMENT_ID: u16 = 0x1d;
+
+/// The length of the content of a Rendezvous Time IE without Wake-up Interval
+/// field.
+const RENDEZVOUS_TIME_CONTENT_LEN: usize = 2;
+
+/// The frame type of multipurpose frames.
+const MULTIPURPOSE_FRAME_TYPE: u8 = 0b101;
+
+/// The number of symbols forming a CSL unit.
+const CSL_UNIT_SYMBOLS: i64 = 10;
+
+/// The content of a CSL IE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CslIe {
+    /// The time from the start of the frame carrying the IE until the next
+    /// sample of the sender.
+    pub phase: u16,
+    /// macCslPeriod of the sender, 0 if CSL is off.
+    pub period: u16,
+    /// The time until the start of the frame announced by a wake-up frame.
+    pub rendezvous_time: Option<u16>,
+}
+
+impl CslIe {
+    /// The element ID of the CSL header IE.
+    pub const ELEMENT_ID: u16 = 0x1a;
+
+    /// Decodes the content of a CSL IE.
+    pub fn from_bytes(content: &[u8]) -> Option<Self> {
+        let field = |idx: usize| u16::from_le_bytes([content[idx], content[idx + 1]]);
+        let rendezvous_time = match content.len() {
+            CSL_IE_CONTENT_LEN => None,
+            CSL_IE_FULL_CONTENT_LEN => Some(field(4)),
+            _ => return None,
+        };
+        Some(Self {
+            phase: field(0),
+            period: field(2),
+            rendezvous_time,
+        })
+    }
+
+    /// Encodes the IE including its header into the given buffer and returns
+    /// its length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is too short.
+    pub fn emit(&self, buffer: &mut [u8]) -> usize {
+        let content_len = match self.rendezvous_time {
+            Some(_) => CSL_IE_FULL_CONTENT_LEN,
+            None => CSL_IE_CONTENT_LEN,
+        };
+        let header = (Self::ELEMENT_ID << 7) | content_len as u16;
+        buffer[..2].copy_from_slice(&header.to_le_bytes());
+        buffer[2..4].copy_from_slice(&self.phase.to_le_bytes());
+        buffer[4..6].copy_from_slice(&self.period.to_le_bytes());
+        if let Some(rendezvous_time) = self.rendezvous_time {
+            buffer[6..8].copy_from_slice(&rendezvous_time.to_le_bytes());
+        }
+        IE_HEADER_LEN + content_len
+    }
+}
+
+/// Configuration of CSL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CslConfig {
+    /// The timing of the PHY.
+    pub phy: PhyParameters,
+    /// macCslPeriod, the sample period of the receiver, 0 if CSL is off.
+    pub period: u16,
+    /// macCslMaxPeriod, the longest sample period of the receivers in the
+    /// PAN. Wake-up sequences to receivers with unknown phase cover it.
+    pub max_period: u16,
+    /// The time the receiver listens at each sample. Must fit two wake-up
+    /// frames and exceed a CSL unit.
+    pub sample_window: Duration<Microseconds>,
+    /// The accuracy of the clocks of all devices in parts per million.
+    pub clock_accuracy_ppm: u16,
+    /// The uncertainty of timed transmissions regardless of clock drift, e.g.
+    /// due to the ramp-up of the radio.
+    pub min_guard_time: Duration<Microseconds>,
+}
+
+impl Default for CslConfig {
+    fn default() -> Self {
+        Self {
+            phy: PhyParameters::default(),
+            period: 0,
+            // 500ms
+            max_period: 3125,
+            sample_window: Duration::new(2_000),
+            clock_accuracy_ppm: 20,
+            min_guard_time: Duration::new(100),
+        }
+    }
+}
+
+impl CslConfig {
+    /// Return the duration of a CSL unit of 10 symbols.
+    pub const fn unit(&self) -> Duration<Microseconds> {
+        self.phy.symbols(CSL_UNIT_SYMBOLS)
+    }
+
+    /// Return the duration of the given number of CSL units.
+    pub const fn units(&self, units: u16) -> Duration<Microseconds> {
+        Duration::new(self.unit().ticks() * units as i64)
+    }
+
+    /// Return the on-air duration of a frame.
+    ///
+    /// * `mpdu_len` - Length of the MPDU in octets, excluding the FCS
+    pub const fn frame_duration(&self, mpdu_len: usize) -> Duration<Microseconds> {
+        air_time(mpdu_len + FCS_LEN, &self.phy)
+    }
+
+    /// Return the guard time of a timed transmission to a peer whose phase was
+    /// learned the given time ago.
+    pub const fn guard_time(&self, elapsed: Duration<Microseconds>) -> Duration<Microseconds> {
+        // Both clocks drift.
+        let drift = elapsed.ticks() * 2 * self.clock_accuracy_ppm as i64 / 1_000_000;
+        Duration::new(self.min_guard_time.ticks() + drift)
+    }
+}
+
+/// Return the first instant at or after `after` that lies a multiple of the
+/// given period after `anchor`.
+pub(super) fn next_sample<Timer: Frequency>(
+    anchor: Instant<Timer>,
+    period: Duration<Microseconds>,
+    after: Instant<Timer>,
+) -> Instant<Timer> {
+    if after <= anchor {
+        return anchor;
+    }
+    let elapsed = (after - anchor)
+        .convert_into_rounding_up::<Microseconds>()
+        .ticks();
+    let periods = (elapsed + period.ticks() - 1) / period.ticks();
+    let sample = anchor
+        + Duration::<Microseconds>::new(periods * period.ticks()).convert_into_rounding_down();
+    if sample < after {
+        // The conversion rounded the sample before `after`.
+        anchor
+            + Duration::<Microseconds>::new((periods + 1) * period.ticks())
+                .convert_into_rounding_down()
+    } else {
+        sample
+    }
+}
+
+/// Return the number of CSL units in the given duration, rounded down and
+/// saturated.
+fn to_units(config: &CslConfig, duration: Duration<Microseconds>) -> u16 {
+    (duration.ticks() / config.unit().ticks()).clamp(0, u16::MAX as i64) as u16
+}
+
+/// The receiving side of CSL, see the module documentation.
+pub struct CslReceiver<Timer: RadioTimerApi> {
+    config: CslConfig,
+    /// An instant at which the channel is sampled.
+    anchor: Instant<Timer>,
+}
+
+impl<Timer: RadioTimerApi> CslReceiver<Timer> {
+    /// Creates a new [`CslReceiver`] sampling every macCslPeriod from the
+    /// given instant on.
+    ///
+    /// Returns `None` if macCslPeriod is 0.
+    pub fn new(config: &CslConfig, anchor: Instant<Timer>) -> Option<Self> {
+        (config.period > 0).then_some(Self {
+            config: *config,
+            anchor,
+        })
+    }
+
+    /// Return the first sample at or after the given instant.
+    pub fn next_sample(&self, after: Instant<Timer>) -> Instant<Timer> {
+        next_sample(self.anchor, self.config.units(self.config.period), after)
+    }
+
+    /// Return the CSL IE to include in a frame starting at the given instant.
+    ///
+    /// The phase is rounded down so that the peer expects the sample early
+    /// rather than late.
+    pub fn csl_ie(&self, frame_start: Instant<Timer>) -> CslIe {
+        let sample = self.next_sample(frame_start);
+        CslIe {
+            phase: to_units(
+                &self.config,
+                (sample - frame_start).convert_into_rounding_down(),
+            ),
+            period: self.config.period,
+            rendezvous_time: None,
+        }
+    }
+
+    /// Build the ACK of a received frame like [`ack_frame_with_csl()`],
+    /// including a CSL IE in an Enh-Ack.
+    ///
+    /// * `mpdu` - Received MPDU (without FCS)
+    /// * `rx_end` - Instant at which the reception of the frame ended
+    /// * `time_correction` - Content of the Time Correction IE to include in an
+    ///   Enh-Ack
+    pub fn ack_frame(
+        &self,
+        mpdu: &[u8],
+        rx_end: Instant<Timer>,
+        time_correction: Option<TimeCorrection>,
+    ) -> Result<Option<AckFrame>, FrameError> {
+        let csl = self.csl_ie(AckDelay::Aifs.ack_start(rx_end));
+        ack_frame_with_csl(mpdu, time_correction, Some(csl))
+    }
+
+    /// Samples the channel until a frame is received into the given buffer
+    /// and returns the instant at which the reception ended.
+    ///
+    /// Wake-up frames are not returned: The receiver sleeps until the
+    /// announced rendezvous and receives the frame following them.
+    ///
+    /// * `radio` - The radio receiving the frame
+    /// * `frame` - Buffer receiving the frame
+    pub async fn receive<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        frame: &mut AckFrame,
+    ) -> Instant<Timer> {
+        let sample_window: Duration<Timer> = self.config.sample_window.convert_into_rounding_up();
+        let mut listen_at = self.next_sample(Timer::now());
+        loop {
+            if listen_at > Timer::now() {
+                Timer::wait_for_alarm_at(listen_at).await;
+            }
+            let rx_end = radio
+                .receive_until(frame, listen_at + sample_window)
+                .await
+                .map(|rx| rx.end);
+            match rx_end.map(|rx_end| (rx_end, rendezvous_time(frame))) {
+                Some((rx_end, None)) => return rx_end,
+                Some((rx_end, Some(rendezvous_time))) => {
+                    listen_at = rx_end
+                        + self
+                            .config
+                            .units(rendezvous_time)
+                            .convert_into_rounding_down()
+                }
+                None => listen_at = self.next_sample(Timer::now()),
+            }
+        }
+    }
+}
+
+/// The sampling schedule of a CSL receiver as learned by a transmitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CslPeer<Timer: Frequency> {
+    period: Duration<Microseconds>,
+    /// The sample announced by the last CSL IE of the peer.
+    sample: Instant<Timer>,
+}
+
+impl<Timer: Frequency> CslPeer<Timer> {
+    /// Learns the schedule of a peer from a CSL IE it sent.
+    ///
+    /// Returns `None` if CSL is off at the peer.
+    ///
+    /// * `config` - The CSL configuration
+    /// * `ie` - The received CSL IE
+    /// * `frame_start` - Instant at which the frame carrying the IE started
+    pub fn from_ie(config: &CslConfig, ie: CslIe, frame_start: Instant<Timer>) -> Option<Self> {
+        (ie.period > 0).then(|| Self {
+            period: config.units(ie.period),
+            sample: frame_start + config.units(ie.phase).convert_into_rounding_down(),
+        })
+    }
+
+    /// Return the first sample of the peer at or after the given instant.
+    pub fn next_sample(&self, after: Instant<Timer>) -> Instant<Timer> {
+        next_sample(self.sample, self.period, after)
+    }
+}
+
+/// How a frame is transmitted to a CSL receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CslTxPlan<Timer: Frequency> {
+    /// The frame is transmitted at the given instant, shortly after a sample
+    /// of the receiver.
+    Timed(Instant<Timer>),
+    /// A wake-up sequence starts immediately and the frame is transmitted at
+    /// the given rendezvous.
+    WakeupSequence(Instant<Timer>),
+}
+
+/// The transmitting side of CSL, see the module documentation.
+pub struct CslTransmitter<Timer: RadioTimerApi> {
+    config: CslConfig,
+    /// Transmits single attempts, retransmissions wait for the next sample.
+    retransmissions: Retransmissions<Timer>,
+    max_frame_retries: u8,
+}
+
+impl<Timer: RadioTimerApi> CslTransmitter<Timer> {
+    /// Creates a new [`CslTransmitter`].
+    ///
+    /// * `config` - The CSL configuration
+    /// * `csma_config` - The configuration providing macMaxFrameRetries,
+    ///   macAckWaitDuration and the interframe spaces
+    pub fn new(config: &CslConfig, csma_config: &CsmaConfig) -> Result<Self, CsmaConfigError> {
+        let single_attempt = CsmaConfig {
+            max_frame_retries: 0,
+            ..*csma_config
+        };
+        Ok(Self {
+            config: *config,
+            retransmissions: Retransmissions::new(&single_attempt)?,
+            max_frame_retries: csma_config.max_frame_retries,
+        })
+    }
+
+    /// Plans the transmission of a frame to a receiver, see the module
+    /// documentation.
+    ///
+    /// * `peer` - The schedule of the receiver, if known
+    /// * `after` - The earliest instant at which the transmission may start
+    pub fn plan(&self, peer: Option<&CslPeer<Timer>>, after: Instant<Timer>) -> CslTxPlan<Timer> {
+        let shr_duration = self.config.phy.shr_duration;
+        if let Some(peer) = peer {
+            let sample = peer.next_sample(after);
+            let guard_time = self
+                .config
+                .guard_time((sample - peer.sample).convert_into_rounding_up());
+            // The frame must start within the sample window even if the
+            // clocks drifted apart in either direction.
+            let delay = self.config.unit() + guard_time;
+            if delay.ticks() + guard_time.ticks() + shr_duration.ticks()
+                <= self.config.sample_window.ticks()
+            {
+                return CslTxPlan::Timed(sample + delay.convert_into_rounding_up());
+            }
+        }
+        let period = match peer {
+            Some(peer) => peer.period,
+            None => self.config.units(self.config.max_period),
+        };
+        CslTxPlan::WakeupSequence(
+            after + (period + self.config.sample_window).convert_into_rounding_up(),
+        )
+    }
+
+    /// Transmits the given frame to a CSL receiver and retransmits it at the
+    /// following samples until it is acknowledged or macMaxFrameRetries
+    /// retransmissions failed.
+    ///
+    /// The schedule of the receiver is updated from the CSL IEs of its ACKs.
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `peer` - The schedule of the receiver, if known
+    /// * `mpdu` - The MPDU (without FCS) to transmit
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::InvalidAddressingCombination`] if a wake-up sequence
+    ///   is required but the frame has no destination address,
+    /// - any error of [`Retransmissions::transmit()`].
+    pub async fn transmit<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        peer: &mut Option<CslPeer<Timer>>,
+        mpdu: &[u8],
+    ) -> Result<TxReport<Timer>, FrameError> {
+        let mut previous: Option<TxReport<Timer>> = None;
+        loop {
+            let after = previous
+                .as_ref()
+                .map_or_else(Timer::now, |report| report.ifs_end);
+            let tx_at = match self.plan(peer.as_ref(), after) {
+                CslTxPlan::Timed(tx_at) => tx_at,
+                CslTxPlan::WakeupSequence(rendezvous) => {
+                    self.wakeup_sequence(radio, mpdu, rendezvous).await?;
+                    rendezvous
+                }
+            };
+            if tx_at > Timer::now() {
+                Timer::wait_for_alarm_at(tx_at).await;
+            }
+
+            let mut attempt_report = self.retransmissions.transmit(radio, mpdu).await?;
+            // Safety: Single attempts report exactly one attempt.
+            let attempt = attempt_report.attempts.pop().unwrap();
+            if let Some(csl) = attempt.ack.and_then(|ack| ack.csl) {
+                let ack_start = AckDelay::Aifs.ack_start(attempt.end);
+                *peer = CslPeer::from_ie(&self.config, csl, ack_start);
+            }
+            let ifs_end = attempt_report.ifs_end;
+            let mut report = previous.take().unwrap_or(attempt_report);
+            // Safety: The number of attempts is limited to macMaxFrameRetries
+            // + 1.
+            let _ = report.attempts.push(attempt);
+            report.ifs_end = ifs_end;
+            if report.is_success() || report.retries() >= self.max_frame_retries {
+                return Ok(report);
+            }
+            previous = Some(report);
+        }
+    }
+
+    /// Transmits wake-up frames back to back until the next one would end
+    /// after the given rendezvous.
+    async fn wakeup_sequence<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        mpdu: &[u8],
+        rendezvous: Instant<Timer>,
+    ) -> Result<(), FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        let addressing_fields = MacHeader::parse(mpdu, frame_control)?
+            .addressing_fields
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+        let dst_address = addressing_fields
+            .dst_address()
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+        let dst_pan_id = addressing_fields
+            .dst_pan_id()
+            .map(|pan_id| pan_id.into_u16());
+
+        let mut frame = wakeup_frame(dst_pan_id, dst_address.as_le_bytes(), 0)?;
+        let duration: Duration<Timer> = self
+            .config
+            .frame_duration(frame.len())
+            .convert_into_rounding_up();
+        loop {
+            let end = Timer::now() + duration;
+            if end > rendezvous {
+                return Ok(());
+            }
+            let rendezvous_time = to_units(
+                &self.config,
+                (rendezvous - end).convert_into_rounding_down(),
+            );
+            set_rendezvous_time(&mut frame, rendezvous_time);
+            radio.transmit_now(&frame).await;
+        }
+    }
+}
+
+/// Build a wake-up frame, see the module documentation.
+///
+/// * `dst_pan_id` - PAN ID of the receiver, if present in the announced frame
+/// * `dst_address` - Address of the receiver (little endian)
+/// * `rendezvous_time` - Time from the end of the wake-up frame until the start
+///   of the announced frame
+pub fn wakeup_frame(
+    dst_pan_id: Option<u16>,
+    dst_address: &[u8],
+    rendezvous_time: u16,
+) -> Result<AckFrame, FrameError> {
+    let dst_addressing_mode: u16 = match dst_address.len() {
+        2 => 0b10,
+        8 => 0b11,
+        _ => return Err(FrameErrorKind::InvalidAddressingCombination.into()),
+    };
+    let mut frame_control = MULTIPURPOSE_FRAME_TYPE as u16
+        // Long frame control
+        | 1 << 3
+        | dst_addressing_mode << 4
+        // Sequence number suppression
+        | 1 << 10
+        // IEs present
+        | 1 << 15;
+    if dst_pan_id.is_some() {
+        frame_control |= 1 << 8;
+    }
+
+    let mut mpdu = [0; 2 + 2 + 8 + IE_HEADER_LEN + RENDEZVOUS_TIME_CONTENT_LEN];
+    let mut len = 0;
+    let mut append = |bytes: &[u8]| {
+        mpdu[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+    };
+    append(&frame_control.to_le_bytes());
+    if let Some(dst_pan_id) = dst_pan_id {
+        append(&dst_pan_id.to_le_bytes());
+    }
+    append(dst_address);
+    let header = (RENDEZVOUS_TIME_IE_ELEMENT_ID << 7) | RENDEZVOUS_TIME_CONTENT_LEN as u16;
+    append(&header.to_le_bytes());
+    append(&rendezvous_time.to_le_bytes());
+    AckFrame::from_slice(&mpdu[..len])
+}
+
+/// Update the rendezvous time of a wake-up frame.
+fn set_rendezvous_time(frame: &mut AckFrame, rendezvous_time: u16) {
+    let len = frame.len();
+    frame[len - RENDEZVOUS_TIME_CONTENT_LEN..].copy_from_slice(&rendezvous_time.to_le_bytes());
+}
+
+/// Return the rendezvous time announced by the given frame if it is a wake-up
+/// frame.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+pub fn rendezvous_time(mpdu: &[u8]) -> Option<u16> {
+    let frame_control = u16::from_le_bytes(mpdu.get(..2)?.try_into().ok()?);
+    let long_frame_control = frame_control & (1 << 3) != 0;
+    let security_enabled = frame_control & (1 << 9) != 0;
+    let ies_present = frame_control & (1 << 15) != 0;
+    if frame_control & 0b111 != MULTIPURPOSE_FRAME_TYPE as u16
+        || !long_frame_control
+        || security_enabled
+        || !ies_present
+    {
+        return None;
+    }
+    let address_len = |mode: u16| match mode & 0b11 {
+        0b10 => 2,
+        0b11 => 8,
+        _ => 0,
+    };
+    let mut offset = 2 + address_len(frame_control >> 4) + address_len(frame_control >> 6);
+    if frame_control & (1 << 8) != 0 {
+        offset += 2;
+    }
+    if frame_control & (1 << 10) == 0 {
+        offset += 1;
+    }
+    let content = find_header_ie(mpdu, offset, RENDEZVOUS_TIME_IE_ELEMENT_ID)?;
+    Some(u16::from_le_bytes(content.get(..2)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            frame::{Address, ExtendedAddress, FrameType, PanId},
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+        },
+        mac::{
+            frame::mpdu::{FrameBuffer, FrameBuilder},
+            test_helpers::{mac_radio, received, timed_out, transmitted, TEST_CAPABILITIES},
+        },
+    };
+
+    const DST_ADDRESS: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    /// Build a data frame (2015) requesting an ACK.
+    fn data_frame(seq_nr: u8) -> FrameBuffer<32> {
+        let pan_id = PanId::from_u16(0xabcd);
+        let builder = FrameBuilder::new(FrameType::Data)
+            .with_sequence_number(seq_nr)
+            .with_ack_request(true)
+            .with_enhanced_frame_version()
+            .with_addressing(
+                Some((
+                    pan_id,
+                    Address::Extended(ExtendedAddress::new(&DST_ADDRESS[..])),
+                )),
+                None,
+            )
+            .without_security()
+            .without_ies()
+            .with_payload(&[0x11]);
+        FrameBuffer::from_builder(&builder).unwrap()
+    }
+
+    /// A CSL receiver on the air: Frames are received if they start within a
+    /// sample window or at an announced rendezvous.
+    struct CslRadio {
+        config: CslConfig,
+        receiver: CslReceiver<TestClock>,
+        /// The rendezvous announced by a received wake-up frame.
+        rendezvous: Option<Instant<TestClock>>,
+        /// The ACK to a frame received by the receiver.
+        ack: Option<(Instant<TestClock>, AckFrame)>,
+        /// The number of wake-up frames.
+        wakeup_frames: usize,
+    }
+
+    impl CslRadio {
+        fn new(config: &CslConfig) -> Self {
+            Self {
+                config: *config,
+                receiver: CslReceiver::new(config, Instant::new(10_000)).unwrap(),
+                rendezvous: None,
+                ack: None,
+                wakeup_frames: 0,
+            }
+        }
+
+        fn is_listening(&self, at: Instant<TestClock>) -> bool {
+            let window = self.config.sample_window.convert_into_rounding_down();
+            let sample = self.receiver.next_sample(at - window);
+            let rendezvous = self.rendezvous.is_some_and(|rendezvous| {
+                at >= rendezvous - self.config.unit().convert_into_rounding_down()
+                    && at <= rendezvous + window
+            });
+            (at >= sample && at <= sample + window) || rendezvous
+        }
+    }
+
+    impl Radio for CslRadio {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            let start = TestClock::now();
+            let duration = self.config.frame_duration(mpdu.len());
+            TestClock::advance(duration.convert_into_rounding_up());
+            let end = TestClock::now();
+            if self.is_listening(start) {
+                match rendezvous_time(mpdu) {
+                    Some(units) => {
+                        self.wakeup_frames += 1;
+                        self.rendezvous =
+                            Some(end + self.config.units(units).convert_into_rounding_down());
+                    }
+                    None => {
+                        let ack = self.receiver.ack_frame(mpdu, end, None).unwrap().unwrap();
+                        self.ack = Some((AckDelay::Aifs.ack_start(end), ack));
+                    }
+                }
+            } else if rendezvous_time(mpdu).is_some() {
+                self.wakeup_frames += 1;
+            }
+            transmitted(start)
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            match self.ack.take() {
+                Some((ack_start, ack)) if Some(ack_start) <= window.end => {
+                    let duration = self.config.frame_duration(ack.len());
+                    TestClock::advance(ack_start - TestClock::now());
+                    TestClock::advance(duration.convert_into_rounding_up());
+                    received(buffer, &ack)
+                }
+                _ => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    fn config() -> CslConfig {
+        CslConfig {
+            // 100ms
+            period: 625,
+            max_period: 625,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn csl_ie() {
+        let ie = CslIe {
+            phase: 0x1234,
+            period: 0x0271,
+            rendezvous_time: None,
+        };
+        let mut buffer = [0; CSL_IE_MAX_LEN];
+        assert_eq!(ie.emit(&mut buffer), 6);
+        assert_eq!(buffer[..6], [0x04, 0x0d, 0x34, 0x12, 0x71, 0x02]);
+        assert_eq!(CslIe::from_bytes(&buffer[2..6]), Some(ie));
+
+        let ie = CslIe {
+            rendezvous_time: Some(7),
+            ..ie
+        };
+        assert_eq!(ie.emit(&mut buffer), 8);
+        assert_eq!(buffer[..2], [0x06, 0x0d]);
+        assert_eq!(CslIe::from_bytes(&buffer[2..]), Some(ie));
+        assert_eq!(CslIe::from_bytes(&buffer[2..7]), None);
+    }
+
+    #[test]
+    fn receiver_schedule() {
+        let config = config();
+        assert!(CslReceiver::<TestClock>::new(&CslConfig::default(), Instant::new(0)).is_none());
+        let receiver = CslReceiver::<TestClock>::new(&config, Instant::new(1_000)).unwrap();
+        assert_eq!(receiver.next_sample(Instant::new(0)), Instant::new(1_000));
+        assert_eq!(
+            receiver.next_sample(Instant::new(1_000)),
+            Instant::new(1_000)
+        );
+        assert_eq!(
+            receiver.next_sample(Instant::new(1_001)),
+            Instant::new(101_000)
+        );
+
+        // The phase is rounded down to units of 160µs.
+        let ie = receiver.csl_ie(Instant::new(100_000));
+        assert_eq!((ie.phase, ie.period), (6, 625));
+        let peer = CslPeer::<TestClock>::from_ie(&config, ie, Instant::new(100_000)).unwrap();
+        assert_eq!(
+            peer.next_sample(Instant::new(100_000)),
+            Instant::new(100_960)
+        );
+        assert_eq!(
+            peer.next_sample(Instant::new(100_961)),
+            Instant::new(200_960)
+        );
+    }
+
+    #[test]
+    fn wakeup_frames() {
+        let frame = wakeup_frame(Some(0xabcd), &DST_ADDRESS, 0x1234).unwrap();
+        assert_eq!(
+            frame[..],
+            [
+                0x3d, 0x85, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x82, 0x0e,
+                0x34, 0x12
+            ]
+        );
+        assert_eq!(rendezvous_time(&frame), Some(0x1234));
+
+        let frame = wakeup_frame(None, &[0x02, 0x00], 5).unwrap();
+        assert_eq!(frame.len(), 8);
+        assert_eq!(rendezvous_time(&frame), Some(5));
+        assert_eq!(rendezvous_time(&data_frame(1)), None);
+        assert!(wakeup_frame(None, &[], 5).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ies")]
+    fn transmit() {
+        TestClock::reset();
+        let config = config();
+        let transmitter =
+            CslTransmitter::<TestClock>::new(&config, &CsmaConfig::default()).unwrap();
+        let mut radio = mac_radio(CslRadio::new(&config));
+        let mut peer = None;
+
+        // The phase is unknown, so a wake-up sequence precedes the frame.
+        TestClock::advance(Duration::new(50_000));
+        let report =
+            TestClock::block_on(transmitter.transmit(&mut radio, &mut peer, &data_frame(1)))
+                .unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.retries(), 0);
+        assert!(radio.radio_mut().wakeup_frames > 100);
+        // 100ms + sample window after the start of the sequence
+        assert_eq!(report.attempts[0].start, Instant::new(152_000));
+        let peer_sample = peer.unwrap().next_sample(TestClock::now());
+        assert!(
+            radio.radio_mut().receiver.next_sample(TestClock::now()) - peer_sample
+                < Duration::new(160)
+        );
+
+        // The phase was learned from the ACK: Timed transmissions follow.
+        radio.radio_mut().wakeup_frames = 0;
+        TestClock::advance(Duration::new(1_000_000));
+        let report =
+            TestClock::block_on(transmitter.transmit(&mut radio, &mut peer, &data_frame(2)))
+                .unwrap();
+        assert!(report.is_success());
+        assert_eq!(radio.radio_mut().wakeup_frames, 0);
+        let start = report.attempts[0].start;
+        let sample = radio
+            .radio_mut()
+            .receiver
+            .next_sample(start - Duration::new(1_000));
+        assert!(start > sample && start - sample < Duration::new(1_000));
+    }
+
+    /// A reception expected at the given instant: either a frame received
+    /// after the given delay or a timeout.
+    type ScriptedRx<'a> = (u64, Option<(i64, &'a [u8])>);
+
+    /// Radio expecting receptions at the given instants.
+    struct ScriptedRadio<'a> {
+        rx: core::slice::Iter<'a, ScriptedRx<'a>>,
+    }
+
+    impl Radio for ScriptedRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            unreachable!()
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            let (listen_at, rx) = self.rx.next().unwrap();
+            assert_eq!(TestClock::now(), Instant::new(*listen_at));
+            match rx {
+                Some((delay, mpdu)) => {
+                    TestClock::advance(Duration::new(*delay));
+                    received(buffer, mpdu)
+                }
+                None => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    #[test]
+    fn receive() {
+        TestClock::reset();
+        TestClock::advance(Duration::new(5_000));
+        let receiver = CslReceiver::<TestClock>::new(&config(), Instant::new(10_000)).unwrap();
+        let wakeup = wakeup_frame(None, &[0x02, 0x00], 10).unwrap();
+        let data = data_frame(1);
+        let rx = [
+            (10_000, None),
+            (110_000, Some((500, &wakeup[..]))),
+            // 10 units after the end of the wake-up frame
+            (112_100, Some((1_000, &data[..]))),
+        ];
+        let mut radio = mac_radio(ScriptedRadio { rx: rx.iter() });
+        let mut frame = AckFrame::new();
+        let rx_end = TestClock::block_on(receiver.receive(&mut radio, &mut frame));
+        assert_eq!(rx_end, Instant::new(113_100));
+        assert_eq!(frame[..], data[..]);
+    }
+
+    #[test]
+    fn plan() {
+        let config = config();
+        let transmitter =
+            CslTransmitter::<TestClock>::new(&config, &CsmaConfig::default()).unwrap();
+        let peer = CslPeer::<TestClock>::from_ie(
+            &config,
+            CslIe {
+                phase: 0,
+                period: 625,
+                rendezvous_time: None,
+            },
+            Instant::new(0),
+        )
+        .unwrap();
+
+        // The guard time grows with the time since the phase was learned.
+        assert_eq!(
+            transmitter.plan(Some(&peer), Instant::new(1)),
+            CslTxPlan::Timed(Instant::new(100_000 + 160 + 100 + 4))
+        );
+        assert_eq!(
+            transmitter.plan(Some(&peer), Instant::new(10_000_001)),
+            CslTxPlan::Timed(Instant::new(10_100_000 + 160 + 100 + 404))
+        );
+
+        // Until it doesn't fit into the sample window anymore.
+        assert_eq!(
+            transmitter.plan(Some(&peer), Instant::new(100_000_000)),
+            CslTxPlan::WakeupSequence(Instant::new(100_000_000 + 100_000 + 2_000))
+        );
+        assert_eq!(
+            transmitter.plan(None, Instant::new(0)),
+            CslTxPlan::WakeupSequence(Instant::new(102_000))
+        );
+    }
+}
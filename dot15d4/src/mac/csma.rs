@@ -0,0 +1,794 @@
+//! CSMA-CA backoff timing (IEEE 802.15.4-2024, section 6.2.5.1).
+//!
+//! Backoff durations are derived from the unit backoff period in radio timer
+//! ticks when constructing [`Csma`], so that the backoff hot path requires
+//! neither frequency conversions nor multiplications. The durations for the
+//! [`MAC_UNIT_BACKOFF_PERIOD`] of the O-QPSK 2.4 GHz PHY are precomputed at
+//! compile time ([`CsmaBackoff`]).
+//!
+//! [`CsmaMac`] runs the complete unslotted CSMA-CA algorithm including
+//! retransmissions on a [`MacRadio`]. Time-critical
+//! frames may bypass it, see [`TxParameters`].
+
+use core::marker::PhantomData;
+
+use rand_core::RngCore;
+
+use crate::driver::{
+    constants::{FCS_LEN, MAC_UNIT_BACKOFF_PERIOD},
+    phy::PhyParameters,
+    radio::Radio,
+    time::{Duration, Frequency, Microseconds},
+    RadioTimerApi,
+};
+
+use super::{counters::MAC_COUNTERS, mcps::data::TxParameters, radio::MacRadio};
+
+/// The max value of macMaxBe allowed by the standard.
+pub const MAX_BE: u8 = 8;
+
+/// The min value of macMaxBe allowed by the standard.
+pub const MIN_MAX_BE: u8 = 3;
+
+/// The max value of macMaxCsmaBackoffs allowed by the standard.
+pub const MAX_CSMA_BACKOFFS: u8 = 5;
+
+/// The max value of macMaxFrameRetries allowed by the standard.
+pub const MAX_FRAME_RETRIES: u8 = 7;
+
+const NUM_BE_VALUES: usize = MAX_BE as usize + 1;
+
+/// Precomputed backoff durations for a given radio timer.
+pub struct CsmaBackoff<Timer: Frequency>(PhantomData<Timer>);
+
+impl<Timer: Frequency> CsmaBackoff<Timer> {
+    /// The duration of a single unit backoff period in timer ticks.
+    pub const UNIT_BACKOFF_PERIOD: Duration<Timer> =
+        MAC_UNIT_BACKOFF_PERIOD.convert_into_rounding_up();
+
+    /// `BACKOFF_PERIOD_DURATIONS[be]` contains the duration of 2^BE unit
+    /// backoff periods.
+    ///
+    /// Any random backoff of 0 to 2^BE - 1 unit backoff periods can be
+    /// composed by adding up the entries corresponding to the bits set in the
+    /// number of backoff periods.
+    const BACKOFF_PERIOD_DURATIONS: [Duration<Timer>; NUM_BE_VALUES] = {
+        let mut durations = [Duration::ZERO; NUM_BE_VALUES];
+        let mut be = 0;
+        while be < NUM_BE_VALUES {
+            // Note: We cannot use multiplication of durations as it is
+            //       non-const.
+            durations[be] = Duration::new(Self::UNIT_BACKOFF_PERIOD.ticks() << be);
+            be += 1;
+        }
+        durations
+    };
+}
+
+/// Compose the duration of the given number of backoff periods from the
+/// durations of 2^BE unit backoff periods.
+///
+/// Only the lower `be` bits of `backoff_periods` are taken into account, i.e.
+/// the result is always in the range of 0 to 2^BE - 1 unit backoff periods.
+///
+/// The calculation takes a constant number of steps independently of the
+/// number of backoff periods.
+const fn compose_backoff<Timer: Frequency>(
+    backoff_period_durations: &[Duration<Timer>; NUM_BE_VALUES],
+    be: u8,
+    backoff_periods: u32,
+) -> Duration<Timer> {
+    debug_assert!(be <= MAX_BE);
+    let mut ticks = 0;
+    let mut bit = 0;
+    while bit < be as usize {
+        let is_set = ((backoff_periods >> bit) & 1) as i64;
+        ticks += backoff_period_durations[bit].ticks() & -is_set;
+        bit += 1;
+    }
+    Duration::new(ticks)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsmaConfigError {
+    /// macMinBe exceeds macMaxBe.
+    MinBe,
+    /// macMaxBe is out of the range 3 to 8.
+    MaxBe,
+    /// macMaxCsmaBackoffs is out of the range 0 to 5.
+    MaxCsmaBackoffs,
+    /// macMaxFrameRetries is out of the range 0 to 7.
+    MaxFrameRetries,
+    /// The unit backoff period is not positive.
+    UnitBackoffPeriod,
+    /// The SIFS is not positive or exceeds the LIFS.
+    Ifs,
+    /// The FCS is neither 2 nor 4 octets long.
+    FcsLen,
+}
+
+/// Configuration of the CSMA-CA algorithm (IEEE 802.15.4-2020, table 8-94).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsmaConfig {
+    /// The minimum value of the backoff exponent (macMinBe).
+    pub min_be: u8,
+    /// The maximum value of the backoff exponent (macMaxBe).
+    pub max_be: u8,
+    /// The maximum number of backoffs before declaring a channel access
+    /// failure (macMaxCsmaBackoffs).
+    pub max_csma_backoffs: u8,
+    /// The maximum number of retries after a transmission failure
+    /// (macMaxFrameRetries).
+    pub max_frame_retries: u8,
+    /// The basic time period of the backoffs.
+    pub unit_backoff_period: Duration<Microseconds>,
+    /// The interframe space following frames of up to
+    /// [`A_MAX_SIFS_FRAME_SIZE`](crate::driver::constants::A_MAX_SIFS_FRAME_SIZE)
+    /// octets.
+    pub sifs: Duration<Microseconds>,
+    /// The interframe space following longer frames.
+    pub lifs: Duration<Microseconds>,
+    /// The length of the FCS in octets, 4 if macFcsType selects the CRC-32
+    /// of SUN PHYs, otherwise 2. Frames are shorter than on air by this
+    /// length when choosing the interframe space.
+    pub fcs_len: u8,
+    /// The time to wait for an ACK after a transmission
+    /// (macAckWaitDuration).
+    pub ack_wait_duration: Duration<Microseconds>,
+    /// The duration of the longest possible frame (phyMaxFrameDuration).
+    pub max_frame_duration: Duration<Microseconds>,
+}
+
+impl Default for CsmaConfig {
+    fn default() -> Self {
+        Self::for_phy(&PhyParameters::default())
+    }
+}
+
+impl CsmaConfig {
+    /// Creates the default configuration with the timing of the given PHY.
+    pub fn for_phy(phy: &PhyParameters) -> Self {
+        Self {
+            min_be: 3,
+            max_be: 5,
+            max_csma_backoffs: 4,
+            max_frame_retries: 3,
+            unit_backoff_period: phy.unit_backoff_period(),
+            sifs: phy.sifs(),
+            lifs: phy.lifs(),
+            fcs_len: FCS_LEN as u8,
+            ack_wait_duration: phy.ack_wait_duration(),
+            max_frame_duration: phy.max_frame_duration,
+        }
+    }
+
+    /// Check that all parameters lie within the ranges allowed by the
+    /// standard.
+    pub fn validate(&self) -> Result<(), CsmaConfigError> {
+        if !(MIN_MAX_BE..=MAX_BE).contains(&self.max_be) {
+            Err(CsmaConfigError::MaxBe)
+        } else if self.min_be > self.max_be {
+            Err(CsmaConfigError::MinBe)
+        } else if self.max_csma_backoffs > MAX_CSMA_BACKOFFS {
+            Err(CsmaConfigError::MaxCsmaBackoffs)
+        } else if self.max_frame_retries > MAX_FRAME_RETRIES {
+            Err(CsmaConfigError::MaxFrameRetries)
+        } else if self.unit_backoff_period.ticks() <= 0 {
+            Err(CsmaConfigError::UnitBackoffPeriod)
+        } else if self.sifs.ticks() <= 0 || self.sifs > self.lifs {
+            Err(CsmaConfigError::Ifs)
+        } else if !matches!(self.fcs_len, 2 | 4) {
+            Err(CsmaConfigError::FcsLen)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Return macMaxFrameTotalWaitTime, i.e. the max time to wait for a frame
+    /// announced with the Frame Pending flag: the longest possible CSMA-CA
+    /// procedure of the sender plus phyMaxFrameDuration.
+    pub fn max_frame_total_wait_time(&self) -> Duration<Microseconds> {
+        let m = self
+            .max_be
+            .saturating_sub(self.min_be)
+            .min(self.max_csma_backoffs);
+        let backoff_periods = (0..m).map(|k| 1 << (self.min_be + k)).sum::<i64>()
+            + ((1 << self.max_be) - 1) * (self.max_csma_backoffs - m) as i64;
+        Duration::new(
+            backoff_periods * self.unit_backoff_period.ticks() + self.max_frame_duration.ticks(),
+        )
+    }
+}
+
+/// The CSMA-CA algorithm for a given radio timer, see the module
+/// documentation.
+pub struct Csma<Timer: Frequency> {
+    config: CsmaConfig,
+    /// `backoff_period_durations[be]` contains the duration of 2^BE unit
+    /// backoff periods.
+    backoff_period_durations: [Duration<Timer>; NUM_BE_VALUES],
+    /// Number of backoffs of the current transmission (NB).
+    nb: u8,
+    /// Current backoff exponent (BE).
+    be: u8,
+}
+
+impl<Timer: Frequency> Csma<Timer> {
+    /// Creates a new [`Csma`] from a validated configuration.
+    ///
+    /// * `config` - The configuration of the algorithm
+    pub fn new(config: CsmaConfig) -> Result<Self, CsmaConfigError> {
+        config.validate()?;
+        let backoff_period_durations =
+            if config.unit_backoff_period == CsmaBackoff::<Microseconds>::UNIT_BACKOFF_PERIOD {
+                CsmaBackoff::<Timer>::BACKOFF_PERIOD_DURATIONS
+            } else {
+                let unit_backoff_period = config
+                    .unit_backoff_period
+                    .convert_into_rounding_up::<Timer>()
+                    .ticks();
+                let mut backoff_period_durations = [Duration::ZERO; NUM_BE_VALUES];
+                for (be, duration) in backoff_period_durations.iter_mut().enumerate() {
+                    *duration = Duration::new(unit_backoff_period << be);
+                }
+                backoff_period_durations
+            };
+        Ok(Self {
+            config,
+            backoff_period_durations,
+            nb: 0,
+            be: config.min_be,
+        })
+    }
+
+    /// Return the configuration of the algorithm.
+    pub fn config(&self) -> &CsmaConfig {
+        &self.config
+    }
+
+    /// Reset the algorithm before the transmission of a new frame.
+    pub fn start(&mut self) {
+        self.nb = 0;
+        self.be = self.config.min_be;
+    }
+
+    /// Draws a random backoff duration for the current backoff exponent.
+    pub fn random_backoff_duration<Rng: RngCore>(&self, rng: &mut Rng) -> Duration<Timer> {
+        compose_backoff(&self.backoff_period_durations, self.be, rng.next_u32())
+    }
+
+    /// Account for a busy channel and return whether another backoff may be
+    /// attempted, otherwise the transmission fails with a channel access
+    /// failure.
+    pub fn on_channel_busy(&mut self) -> bool {
+        self.nb += 1;
+        self.be = (self.be + 1).min(self.config.max_be);
+        self.nb <= self.config.max_csma_backoffs
+    }
+}
+
+/// The outcome of a transmission with [`CsmaMac::transmit_with()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxOutcome {
+    /// The frame was transmitted and acknowledged if requested.
+    Success {
+        /// Number of retransmissions before the frame was acknowledged.
+        retries: u8,
+    },
+    /// The channel was busy for more than macMaxCsmaBackoffs backoffs.
+    ChannelAccessFailure,
+    /// The frame was not acknowledged after macMaxFrameRetries retries.
+    NoAck,
+    /// The frame, or its retransmission, exceeded the duty cycle budget of
+    /// its band.
+    DutyCycleExceeded,
+}
+
+/// Transmits frames with the unslotted CSMA-CA algorithm (IEEE
+/// 802.15.4-2020, section 6.2.5.1) and retransmits them until they are
+/// acknowledged.
+pub struct CsmaMac<Timer: RadioTimerApi, Rng: RngCore> {
+    csma: Csma<Timer>,
+    rng: Rng,
+}
+
+impl<Timer: RadioTimerApi, Rng: RngCore> CsmaMac<Timer, Rng> {
+    /// Creates a new [`CsmaMac`].
+    ///
+    /// * `config` - The configuration of the algorithm
+    /// * `rng` - Source of the random backoffs
+    pub fn new(config: CsmaConfig, rng: Rng) -> Result<Self, CsmaConfigError> {
+        Ok(Self {
+            csma: Csma::new(config)?,
+            rng,
+        })
+    }
+
+    /// Return the configuration of the algorithm.
+    pub fn config(&self) -> &CsmaConfig {
+        self.csma.config()
+    }
+
+    /// Transmits the given frame with the given parameters.
+    ///
+    /// Frames bypassing CSMA-CA are transmitted once, immediately or after a
+    /// single CCA, see [`TxParameters`]. Every attempt must fit into the duty
+    /// cycle budget of the radio, see [`MacRadio::acquire_air_time()`].
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `frame` - The frame to transmit
+    /// * `parameters` - The transmission parameters of the frame
+    pub async fn transmit_with<R: Radio<Timer = Timer>>(
+        &mut self,
+        radio: &mut MacRadio<R>,
+        frame: &[u8],
+        parameters: &TxParameters,
+    ) -> TxOutcome {
+        if !parameters.csma {
+            if !radio.acquire_air_time(frame, parameters).await {
+                MAC_COUNTERS.tx_duty_cycle_exceeded.increment();
+                return TxOutcome::DutyCycleExceeded;
+            }
+            if parameters.cca && !radio.clear_channel().await {
+                MAC_COUNTERS.cca_busy.increment();
+                MAC_COUNTERS.tx_channel_access_failure.increment();
+                return TxOutcome::ChannelAccessFailure;
+            }
+            if radio.transmit_acked(frame, parameters).await {
+                MAC_COUNTERS.tx_success.increment();
+                return TxOutcome::Success { retries: 0 };
+            }
+            MAC_COUNTERS.ack_timeouts.increment();
+            MAC_COUNTERS.tx_no_ack.increment();
+            return TxOutcome::NoAck;
+        }
+
+        let mut retries = 0;
+        loop {
+            if !radio.acquire_air_time(frame, parameters).await {
+                MAC_COUNTERS.tx_duty_cycle_exceeded.increment();
+                return TxOutcome::DutyCycleExceeded;
+            }
+            if !self.access_channel(radio).await {
+                MAC_COUNTERS.tx_channel_access_failure.increment();
+                return TxOutcome::ChannelAccessFailure;
+            }
+            if radio.transmit_acked(frame, parameters).await {
+                MAC_COUNTERS.tx_success.increment();
+                return TxOutcome::Success { retries };
+            }
+            MAC_COUNTERS.ack_timeouts.increment();
+            if retries >= self.csma.config().max_frame_retries {
+                MAC_COUNTERS.tx_no_ack.increment();
+                return TxOutcome::NoAck;
+            }
+            retries += 1;
+        }
+    }
+
+    /// Backs off until the channel is found idle and return whether it was
+    /// before the maximum number of backoffs was exceeded.
+    async fn access_channel<R: Radio<Timer = Timer>>(&mut self, radio: &mut MacRadio<R>) -> bool {
+        self.csma.start();
+        loop {
+            let backoff = self.csma.random_backoff_duration(&mut self.rng);
+            Timer::wait_for_alarm_at(Timer::now() + backoff).await;
+            if radio.clear_channel().await {
+                return true;
+            }
+            MAC_COUNTERS.cca_busy.increment();
+            if !self.csma.on_channel_busy() {
+                return false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+            time::{Instant, Microseconds, SymbolsOQpsk250kB},
+        },
+        mac::{
+            regulatory::{DutyCycleBudget, DutyCycleLimit, DutyCyclePolicy},
+            test_helpers::{
+                mac_radio, received, timed_out, transmitted, FixedRng, TEST_CAPABILITIES,
+            },
+        },
+    };
+
+    type Backoff = CsmaBackoff<Microseconds>;
+
+    #[test]
+    fn backoff_period_durations() {
+        // 20 symbols of 16µs each.
+        assert_eq!(Backoff::UNIT_BACKOFF_PERIOD.ticks(), 320);
+        assert_eq!(Backoff::BACKOFF_PERIOD_DURATIONS[0].ticks(), 320);
+        assert_eq!(Backoff::BACKOFF_PERIOD_DURATIONS[3].ticks(), 8 * 320);
+        assert_eq!(
+            Backoff::BACKOFF_PERIOD_DURATIONS[MAX_BE as usize].ticks(),
+            256 * 320
+        );
+        assert_eq!(
+            CsmaBackoff::<SymbolsOQpsk250kB>::BACKOFF_PERIOD_DURATIONS[5].ticks(),
+            32 * MAC_UNIT_BACKOFF_PERIOD.ticks()
+        );
+
+        // The precomputed durations apply to the O-QPSK 2.4 GHz PHY.
+        let csma = Csma::<Microseconds>::new(CsmaConfig::default()).unwrap();
+        assert_eq!(
+            csma.backoff_period_durations,
+            Backoff::BACKOFF_PERIOD_DURATIONS
+        );
+        let config = CsmaConfig::for_phy(&PhyParameters::OQPSK_868MHZ);
+        let csma = Csma::<Microseconds>::new(config).unwrap();
+        assert_eq!(csma.backoff_period_durations[3].ticks(), 8 * 800);
+    }
+
+    #[test]
+    fn backoff_duration() {
+        for be in 0..=MAX_BE {
+            for backoff_periods in [0, 1, 2, 5, 0x7f, 0xff, u32::MAX] {
+                let expected_periods = backoff_periods & ((1 << be) - 1);
+                let duration =
+                    compose_backoff(&Backoff::BACKOFF_PERIOD_DURATIONS, be, backoff_periods);
+                assert_eq!(duration.ticks(), expected_periods as i64 * 320);
+                assert!(duration.ticks() <= ((1 << be) - 1) * 320);
+            }
+        }
+    }
+
+    #[test]
+    fn config_validation() {
+        assert_eq!(CsmaConfig::default().validate(), Ok(()));
+
+        let invalid = [
+            (
+                CsmaConfig {
+                    max_be: 9,
+                    ..Default::default()
+                },
+                CsmaConfigError::MaxBe,
+            ),
+            (
+                CsmaConfig {
+                    min_be: 6,
+                    ..Default::default()
+                },
+                CsmaConfigError::MinBe,
+            ),
+            (
+                CsmaConfig {
+                    max_csma_backoffs: 16,
+                    ..Default::default()
+                },
+                CsmaConfigError::MaxCsmaBackoffs,
+            ),
+            (
+                CsmaConfig {
+                    max_frame_retries: 8,
+                    ..Default::default()
+                },
+                CsmaConfigError::MaxFrameRetries,
+            ),
+            (
+                CsmaConfig {
+                    unit_backoff_period: Duration::ZERO,
+                    ..Default::default()
+                },
+                CsmaConfigError::UnitBackoffPeriod,
+            ),
+            (
+                CsmaConfig {
+                    sifs: PhyParameters::OQPSK_2450MHZ.lifs(),
+                    lifs: PhyParameters::OQPSK_2450MHZ.sifs(),
+                    ..Default::default()
+                },
+                CsmaConfigError::Ifs,
+            ),
+            (
+                CsmaConfig {
+                    fcs_len: 0,
+                    ..Default::default()
+                },
+                CsmaConfigError::FcsLen,
+            ),
+        ];
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error));
+            assert!(Csma::<Microseconds>::new(config).is_err());
+        }
+    }
+
+    #[test]
+    fn max_frame_total_wait_time() {
+        // ((2^3 + 2^4) + (2^5 - 1) * 2) * 20 + 266 symbols of 16µs each.
+        assert_eq!(
+            CsmaConfig::default().max_frame_total_wait_time(),
+            Duration::new(1986 * 16)
+        );
+
+        let config = CsmaConfig {
+            min_be: 0,
+            max_be: 3,
+            max_csma_backoffs: 2,
+            ..Default::default()
+        };
+        // (2^0 + 2^1) * 20 + 266 symbols of 16µs each.
+        assert_eq!(config.max_frame_total_wait_time(), Duration::new(326 * 16));
+
+        // 2 * 20 + 266 symbols of 40µs each.
+        let config = CsmaConfig {
+            min_be: 1,
+            max_be: 3,
+            max_csma_backoffs: 1,
+            ..CsmaConfig::for_phy(&PhyParameters::OQPSK_868MHZ)
+        };
+        assert_eq!(config.max_frame_total_wait_time(), Duration::new(306 * 40));
+    }
+
+    #[test]
+    fn csma_backoffs() {
+        let config = CsmaConfig {
+            min_be: 2,
+            max_be: 3,
+            max_csma_backoffs: 2,
+            ..Default::default()
+        };
+        let mut csma = Csma::<Microseconds>::new(config).unwrap();
+        assert_eq!(csma.be, 2);
+        assert_eq!(
+            compose_backoff(&csma.backoff_period_durations, 2, u32::MAX).ticks(),
+            3 * 320
+        );
+
+        assert!(csma.on_channel_busy());
+        assert_eq!(csma.be, 3);
+        assert!(csma.on_channel_busy());
+        assert_eq!(csma.be, 3);
+        assert!(!csma.on_channel_busy());
+
+        csma.start();
+        assert_eq!(csma.be, 2);
+    }
+
+    /// Data frame (2006) with sequence number 7 requesting an ACK, on air for
+    /// 544µs.
+    const DATA: [u8; 9] = [0x61, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00];
+
+    /// Radio replaying the given CCA and acknowledgement results.
+    struct ScriptedRadio<'a> {
+        cca: core::slice::Iter<'a, bool>,
+        acks: core::slice::Iter<'a, bool>,
+        /// Whether the ACK of the last transmission is pending.
+        ack_pending: bool,
+        transmissions: usize,
+        /// The transmit power set last.
+        tx_power: Option<i8>,
+    }
+
+    impl<'a> ScriptedRadio<'a> {
+        fn new(cca: &'a [bool], acks: &'a [bool]) -> MacRadio<Self> {
+            mac_radio(Self {
+                cca: cca.iter(),
+                acks: acks.iter(),
+                ack_pending: false,
+                transmissions: 0,
+                tx_power: None,
+            })
+        }
+    }
+
+    impl Radio for ScriptedRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            self.tx_power = Some(dbm);
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            self.transmissions += 1;
+            self.ack_pending = *self.acks.next().unwrap();
+            transmitted(TestClock::now())
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            if core::mem::take(&mut self.ack_pending) {
+                received(buffer, &[0x02, 0x10, 0x07])
+            } else {
+                timed_out(window)
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(*self.cca.next().unwrap())
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    /// Limit the duty cycle on channel 11 so that the given number of
+    /// transmissions of [`DATA`] fit into the budget.
+    fn limit_duty_cycle(radio: &mut MacRadio<ScriptedRadio>, transmissions: i64) {
+        // 16 ms windows, the budget ends in the middle of the next frame.
+        let ppm = (transmissions * 544 + 272) * 1_000_000 / 16_000;
+        let limit = DutyCycleLimit::new(2_405_000, 2_480_000, Duration::new(16_000), ppm as u32);
+        let budget = DutyCycleBudget::new(&[limit], DutyCyclePolicy::Deny, TestClock::now());
+        radio.set_duty_cycle_budget(budget.ok());
+        radio.set_channel(Channel::_11).unwrap();
+    }
+
+    fn transmit(config: CsmaConfig, radio: &mut MacRadio<ScriptedRadio>) -> TxOutcome {
+        transmit_with(config, radio, &TxParameters::default())
+    }
+
+    fn transmit_with(
+        config: CsmaConfig,
+        radio: &mut MacRadio<ScriptedRadio>,
+        parameters: &TxParameters,
+    ) -> TxOutcome {
+        let mut mac = CsmaMac::<TestClock, _>::new(config, FixedRng(0)).unwrap();
+        let mut transmit = pin!(mac.transmit_with(radio, &DATA, parameters));
+        let mut cx = Context::from_waker(Waker::noop());
+        match transmit.as_mut().poll(&mut cx) {
+            Poll::Ready(outcome) => outcome,
+            Poll::Pending => panic!("zero backoffs must not block"),
+        }
+    }
+
+    #[test]
+    fn transmit_outcomes() {
+        TestClock::reset();
+
+        let mut radio = ScriptedRadio::new(&[false, true, true], &[false, true]);
+        assert_eq!(
+            transmit(CsmaConfig::default(), &mut radio),
+            TxOutcome::Success { retries: 1 }
+        );
+        assert_eq!(radio.radio_mut().transmissions, 2);
+
+        let config = CsmaConfig {
+            max_csma_backoffs: 1,
+            ..Default::default()
+        };
+        let mut radio = ScriptedRadio::new(&[false, false], &[]);
+        assert_eq!(
+            transmit(config, &mut radio),
+            TxOutcome::ChannelAccessFailure
+        );
+        assert_eq!(radio.radio_mut().transmissions, 0);
+
+        let config = CsmaConfig {
+            max_frame_retries: 2,
+            ..Default::default()
+        };
+        let mut radio = ScriptedRadio::new(&[true; 3], &[false; 3]);
+        assert_eq!(transmit(config, &mut radio), TxOutcome::NoAck);
+        assert_eq!(radio.radio_mut().transmissions, 3);
+    }
+
+    #[test]
+    fn transmit_exceeding_duty_cycle() {
+        TestClock::reset();
+
+        // The retransmission exceeds the budget.
+        let mut radio = ScriptedRadio::new(&[true; 2], &[false, true]);
+        limit_duty_cycle(&mut radio, 1);
+        assert_eq!(
+            transmit(CsmaConfig::default(), &mut radio),
+            TxOutcome::DutyCycleExceeded
+        );
+        assert_eq!(radio.radio_mut().transmissions, 1);
+
+        // Neither CCAs nor transmissions without budget.
+        let parameters = TxParameters {
+            csma: false,
+            cca: true,
+            ..Default::default()
+        };
+        let mut radio = ScriptedRadio::new(&[], &[]);
+        limit_duty_cycle(&mut radio, 0);
+        assert_eq!(
+            transmit_with(CsmaConfig::default(), &mut radio, &parameters),
+            TxOutcome::DutyCycleExceeded
+        );
+    }
+
+    #[test]
+    fn transmit_bypassing_csma() {
+        TestClock::reset();
+
+        let mut parameters = TxParameters {
+            tx_power: Some(-8),
+            csma: false,
+            cca: false,
+            phy_mode: None,
+        };
+        // Neither backoffs nor CCAs.
+        let mut radio = ScriptedRadio::new(&[], &[true]);
+        assert_eq!(
+            transmit_with(CsmaConfig::default(), &mut radio, &parameters),
+            TxOutcome::Success { retries: 0 }
+        );
+        assert_eq!(radio.radio_mut().tx_power, Some(-8));
+
+        // No retransmissions.
+        let mut radio = ScriptedRadio::new(&[], &[false, true]);
+        assert_eq!(
+            transmit_with(CsmaConfig::default(), &mut radio, &parameters),
+            TxOutcome::NoAck
+        );
+        assert_eq!(radio.radio_mut().transmissions, 1);
+
+        // A single CCA.
+        TestClock::reset();
+        parameters.cca = true;
+        let mut radio = ScriptedRadio::new(&[false, true], &[true]);
+        assert_eq!(
+            transmit_with(CsmaConfig::default(), &mut radio, &parameters),
+            TxOutcome::ChannelAccessFailure
+        );
+        assert_eq!(radio.radio_mut().transmissions, 0);
+        assert_eq!(TestClock::now(), Instant::new(0));
+    }
+
+    #[test]
+    fn transmit_backs_off() {
+        TestClock::reset();
+
+        let mut mac =
+            CsmaMac::<TestClock, _>::new(CsmaConfig::default(), FixedRng(u32::MAX)).unwrap();
+        let mut radio = ScriptedRadio::new(&[false, true], &[true]);
+        let parameters = TxParameters::default();
+        let mut transmit = pin!(mac.transmit_with(&mut radio, &DATA, &parameters));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // 2^3 - 1 unit backoff periods.
+        assert!(transmit.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(7 * 320)));
+
+        // The channel is busy, the backoff window doubles.
+        TestClock::advance(Duration::new(7 * 320));
+        assert!(transmit.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(22 * 320)));
+
+        TestClock::advance(Duration::new(15 * 320));
+        assert_eq!(
+            transmit.as_mut().poll(&mut cx),
+            Poll::Ready(TxOutcome::Success { retries: 0 })
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Filtering of received frames (IEEE 802.15.4-2020, section 6.7.2).
+//!
+//! [`FrameFilter`] applies the filtering levels of the standard in order:
+//!
+//! 1. The FCS must be valid, unless it was already checked by the driver.
+//! 2. In promiscuous mode, all frames with a valid FCS are accepted.
+//! 3. Otherwise the frame must be of a supported type and version, be
+//!    addressed to the device's PAN and address or to the broadcast PAN and
+//!    address, and beacons must stem from the device's PAN. Data and MAC
+//!    command frames without destination address are only accepted by the PAN
+//!    coordinator, unless macImplicitBroadcast is set.
+//!
+//! The monitor mode skips all filtering, including the FCS check, so that
+//! sniffers get to see corrupted frames, too.
+//!
+//! Addresses and the PAN ID are read from the [`Pib`]. Its extended address
+//! is expected in little endian byte order, like in frames.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        const_config::MAC_IMPLICIT_BROADCAST,
+        frame::{Address, FrameControl, FrameType, FrameVersion},
+        DriverConfig,
+    },
+    mac::frame::{fcs::check_fcs, mpdu::MpduFrame},
+};
+
+use super::{ack::MacHeader, pib::Pib};
+
+/// The broadcast PAN ID and short address.
+const BROADCAST: u16 = 0xffff;
+
+/// How strictly received frames are filtered, see the module documentation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// All filtering levels apply.
+    #[default]
+    Normal,
+    /// Only the FCS is checked, like with macPromiscuousMode.
+    Promiscuous,
+    /// No filtering at all.
+    Monitor,
+}
+
+/// The reason why a received frame was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterError {
+    InvalidFcs,
+    /// The MAC header is truncated or invalid.
+    Malformed,
+    UnsupportedFrameType,
+    InvalidFrameVersion,
+    DstPanIdMismatch,
+    DstAddressMismatch,
+    /// A beacon from another PAN.
+    SrcPanIdMismatch,
+    /// A data or MAC command frame without destination address that is not
+    /// meant for this device.
+    NoDstAddress,
+}
+
+/// Filters received frames, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameFilter {
+    /// The filtering mode. Frames are also filtered in promiscuous mode if
+    /// macPromiscuousMode is set.
+    pub mode: FilterMode,
+    /// Whether the device is the PAN coordinator.
+    pub pan_coordinator: bool,
+    /// macImplicitBroadcast, i.e. whether frames without destination address
+    /// are considered broadcast.
+    pub implicit_broadcast: bool,
+}
+
+impl Default for FrameFilter {
+    fn default() -> Self {
+        Self {
+            mode: FilterMode::Normal,
+            pan_coordinator: false,
+            implicit_broadcast: MAC_IMPLICIT_BROADCAST,
+        }
+    }
+}
+
+impl FrameFilter {
+    /// Filter the given frame.
+    ///
+    /// * `pib` - The PIB providing the PAN ID and addresses of the device
+    /// * `mpdu` - Received MPDU (without FCS)
+    /// * `fcs` - The FCS of the MPDU, empty if checked by the driver
+    pub fn filter(&self, pib: &Pib, mpdu: &[u8], fcs: &[u8]) -> Result<(), FilterError> {
+        if self.mode == FilterMode::Monitor {
+            return Ok(());
+        }
+        check_fcs(mpdu, fcs).map_err(|_| FilterError::InvalidFcs)?;
+        self.filter_header(pib, mpdu)
+    }
+
+    /// Filter the given frame like [`FrameFilter::filter()`], checking the
+    /// FCS as required by the driver configuration.
+    pub fn filter_frame<Config: DriverConfig>(
+        &self,
+        pib: &Pib,
+        frame: &MpduFrame,
+    ) -> Result<(), FilterError> {
+        if self.mode == FilterMode::Monitor {
+            return Ok(());
+        }
+        frame
+            .check_fcs::<Config>()
+            .map_err(|_| FilterError::InvalidFcs)?;
+        self.filter_header(pib, frame.pdu_ref_wo_fcs())
+    }
+
+    /// Apply the second and third filtering level to a frame with valid FCS.
+    fn filter_header(&self, pib: &Pib, mpdu: &[u8]) -> Result<(), FilterError> {
+        if self.mode == FilterMode::Promiscuous || pib.promiscuous_mode {
+            return Ok(());
+        }
+
+        let frame_control = FrameControl::new(mpdu).map_err(|_| FilterError::Malformed)?;
+        let frame_type = frame_control.frame_type();
+        match frame_type {
+            FrameType::Beacon
+            | FrameType::Data
+            | FrameType::Ack
+            | FrameType::MacCommand
+            | FrameType::Multipurpose => {}
+            _ => return Err(FilterError::UnsupportedFrameType),
+        }
+        if frame_control.frame_version() == FrameVersion::Unknown {
+            return Err(FilterError::InvalidFrameVersion);
+        }
+
+        let header = MacHeader::parse(mpdu, frame_control).map_err(|_| FilterError::Malformed)?;
+        let addressing_fields = header.addressing_fields.as_ref();
+        let dst_pan_id = addressing_fields
+            .and_then(|addressing_fields| addressing_fields.dst_pan_id())
+            .map(|pan_id| pan_id.into_u16());
+        let src_pan_id = addressing_fields
+            .and_then(|addressing_fields| addressing_fields.src_pan_id())
+            .map(|pan_id| pan_id.into_u16());
+        let pan_id = pib.pan_id.into_u16();
+
+        if dst_pan_id.is_some_and(|dst_pan_id| dst_pan_id != pan_id && dst_pan_id != BROADCAST) {
+            return Err(FilterError::DstPanIdMismatch);
+        }
+
+        match addressing_fields.and_then(|addressing_fields| addressing_fields.dst_address()) {
+            Some(Address::Short(address)) => {
+                let address = address.into_u16();
+                if address != pib.short_address && address != BROADCAST {
+                    return Err(FilterError::DstAddressMismatch);
+                }
+            }
+            Some(address @ Address::Extended(_)) => {
+                if pib.extended_address.as_ref().map(|address| &address[..])
+                    != Some(address.as_le_bytes())
+                {
+                    return Err(FilterError::DstAddressMismatch);
+                }
+            }
+            Some(Address::Absent) | None => {
+                let accepted = match frame_type {
+                    FrameType::Data | FrameType::MacCommand | FrameType::Multipurpose => {
+                        self.implicit_broadcast
+                            || (self.pan_coordinator && src_pan_id.or(dst_pan_id) == Some(pan_id))
+                    }
+                    // Beacons and ACKs need no destination.
+                    _ => true,
+                };
+                if !accepted {
+                    return Err(FilterError::NoDstAddress);
+                }
+            }
+        }
+
+        if frame_type == FrameType::Beacon
+            && pan_id != BROADCAST
+            && src_pan_id.is_some_and(|src_pan_id| src_pan_id != pan_id)
+        {
+            return Err(FilterError::SrcPanIdMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{driver::frame::PanId, mac::frame::fcs::crc16};
+
+    const EXTENDED: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    /// Data frame (2006) from 0x0001 to 0x0002 in PAN 0xabcd.
+    const DATA: [u8; 10] = [0x41, 0x98, 0x01, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11];
+
+    fn pib() -> Pib {
+        Pib {
+            pan_id: PanId::from_u16(0xabcd),
+            short_address: 0x0002,
+            extended_address: Some(EXTENDED),
+            ..Default::default()
+        }
+    }
+
+    fn filter(filter: &FrameFilter, pib: &Pib, mpdu: &[u8]) -> Result<(), FilterError> {
+        filter.filter(pib, mpdu, &crc16(mpdu).to_le_bytes())
+    }
+
+    #[test]
+    fn destination() {
+        let pib = pib();
+        let frame_filter = FrameFilter {
+            implicit_broadcast: false,
+            ..Default::default()
+        };
+        assert_eq!(filter(&frame_filter, &pib, &DATA), Ok(()));
+
+        let mut mpdu = DATA;
+        mpdu[5..7].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+        mpdu[5..7].copy_from_slice(&[0x03, 0x00]);
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::DstAddressMismatch)
+        );
+
+        let mut mpdu = DATA;
+        mpdu[3..5].copy_from_slice(&[0x34, 0x12]);
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::DstPanIdMismatch)
+        );
+        mpdu[3..5].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+
+        // Extended destination address.
+        let mut mpdu = [
+            0x41, 0x9c, 0x01, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x01,
+            0x00, 0x11,
+        ];
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+        mpdu[5] = 0x09;
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::DstAddressMismatch)
+        );
+
+        // Imm-Acks have no addressing fields.
+        assert_eq!(filter(&frame_filter, &pib, &[0x02, 0x10, 0x01]), Ok(()));
+    }
+
+    #[test]
+    fn without_destination() {
+        let pib = pib();
+        // Data frame (2006) from 0x0001 in PAN 0xabcd.
+        let mut mpdu = [0x01, 0x90, 0x01, 0xcd, 0xab, 0x01, 0x00, 0x11];
+        let mut frame_filter = FrameFilter {
+            implicit_broadcast: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::NoDstAddress)
+        );
+
+        frame_filter.pan_coordinator = true;
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+        mpdu[3..5].copy_from_slice(&[0x34, 0x12]);
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::NoDstAddress)
+        );
+
+        frame_filter.pan_coordinator = false;
+        frame_filter.implicit_broadcast = true;
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+    }
+
+    #[test]
+    fn beacon() {
+        let mut pib = pib();
+        let frame_filter = FrameFilter::default();
+        // Beacon (2006) of 0x0001 in PAN 0x1234.
+        let mpdu = [
+            0x00, 0x90, 0x01, 0x34, 0x12, 0x01, 0x00, 0xff, 0xcf, 0x00, 0x00,
+        ];
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::SrcPanIdMismatch)
+        );
+
+        // Devices without PAN accept all beacons, e.g. during scans.
+        pib.pan_id = PanId::from_u16(0xffff);
+        assert_eq!(filter(&frame_filter, &pib, &mpdu), Ok(()));
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let pib = pib();
+        let frame_filter = FrameFilter::default();
+
+        let mut mpdu = DATA;
+        mpdu[1] |= 0x30;
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::InvalidFrameVersion)
+        );
+        let mut mpdu = DATA;
+        mpdu[0] = 0x44;
+        assert_eq!(
+            filter(&frame_filter, &pib, &mpdu),
+            Err(FilterError::UnsupportedFrameType)
+        );
+        assert_eq!(
+            filter(&frame_filter, &pib, &DATA[..4]),
+            Err(FilterError::Malformed)
+        );
+        assert_eq!(
+            frame_filter.filter(&pib, &DATA, &[0x00, 0x00]),
+            Err(FilterError::InvalidFcs)
+        );
+        // The driver checked the FCS.
+        assert_eq!(frame_filter.filter(&pib, &DATA, &[]), Ok(()));
+    }
+
+    #[test]
+    fn filter_modes() {
+        let mut pib = pib();
+        let mut mpdu = DATA;
+        mpdu[5] = 0x03;
+
+        let promiscuous = FrameFilter {
+            mode: FilterMode::Promiscuous,
+            ..Default::default()
+        };
+        assert_eq!(filter(&promiscuous, &pib, &mpdu), Ok(()));
+        assert_eq!(
+            promiscuous.filter(&pib, &mpdu, &[0x00, 0x00]),
+            Err(FilterError::InvalidFcs)
+        );
+
+        let monitor = FrameFilter {
+            mode: FilterMode::Monitor,
+            ..Default::default()
+        };
+        assert_eq!(monitor.filter(&pib, &mpdu[..1], &[0x00, 0x00]), Ok(()));
+
+        // macPromiscuousMode
+        let normal = FrameFilter::default();
+        assert_eq!(
+            filter(&normal, &pib, &mpdu),
+            Err(FilterError::DstAddressMismatch)
+        );
+        pib.promiscuous_mode = true;
+        assert_eq!(filter(&normal, &pib, &mpdu), Ok(()));
+    }
+}
@@ -0,0 +1,1007 @@
+//! Indirect transmission (IEEE 802.15.4-2020, section 6.7.3).
+//!
+//! Devices that don't keep their receiver on when idle cannot be sent frames
+//! directly. Instead, the coordinator keeps frames for them in an
+//! [`IndirectQueue`] until the device polls with a Data Request command. The
+//! coordinator sets the Frame Pending flag in the ACK to the Data Request if a
+//! frame is pending for the device and sends the frame right after. Frames that
+//! are not polled within macTransactionPersistenceTime expire.
+//!
+//! On the device side, [`Poller`] sends the Data Request command (MLME-POLL)
+//! and receives the pending frame, if any.
+//!
+//! Radios acknowledging frames in hardware set the Frame Pending flag for the
+//! devices published with [`IndirectQueue::publish_pending_addresses()`]. The
+//! MAC acknowledges MAC commands in software if the radio lacks this feature,
+//! see [`requires_software_ack()`].
+//!
+//! In beacon-enabled PANs, the coordinator also lists the devices with pending
+//! frames in its beacons, see [`IndirectQueue::pending_addresses()`]. A device
+//! finding its address in a beacon of its coordinator polls automatically if
+//! macAutoRequest is set, see [`auto_request_frame()`].
+//!
+//! Note: Only Data Request commands without security and IEs are recognized.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::{A_BASE_SUPERFRAME_DURATION, PHY_MAX_PACKET_SIZE_127},
+        frame::{
+            Address, ExtendedAddress, FrameControl, FrameType, FrameVersion, PanId, ShortAddress,
+        },
+        radio::{Radio, RadioCapabilities},
+        time::{Duration, Frequency, Instant, SymbolsOQpsk250kB},
+        RadioTimerApi,
+    },
+    mac::frame::{
+        fields::{BeaconFields, MAX_PENDING_ADDRESSES},
+        mpdu::{FrameBuffer, FrameBuilder},
+        FrameError, FrameErrorKind,
+    },
+};
+
+use super::{
+    ack::{ack_frame, field, AckFrame, MacHeader, TimeCorrection},
+    counters::MAC_COUNTERS,
+    csma::{CsmaConfig, CsmaConfigError},
+    mcps::data::DataError,
+    pib::Pib,
+    radio::MacRadio,
+    retransmission::Retransmissions,
+};
+
+/// The command ID of the Data Request command.
+pub const DATA_REQUEST_COMMAND_ID: u8 = 0x04;
+
+/// Default value of macTransactionPersistenceTime in unit periods.
+pub const DEFAULT_TRANSACTION_PERSISTENCE_TIME: u16 = 0x01f4;
+
+/// Return the time a transaction is kept in the indirect queue.
+///
+/// The unit period of macTransactionPersistenceTime is
+/// aBaseSuperframeDuration in PANs without beacons.
+///
+/// * `persistence_time` - macTransactionPersistenceTime in unit periods
+pub const fn transaction_persistence_duration(
+    persistence_time: u16,
+) -> Duration<SymbolsOQpsk250kB> {
+    Duration::new(A_BASE_SUPERFRAME_DURATION.ticks() * persistence_time as i64)
+}
+
+/// Return the source address (little endian) of the given frame if it is a
+/// Data Request command.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::SecurityNotSupported`] if a MAC command is secured,
+/// - [`FrameErrorKind::IesNotSupported`] if a MAC command contains IEs,
+/// - any other error if the frame is truncated.
+pub fn data_request_source(mpdu: &[u8]) -> Result<Option<heapless::Vec<u8, 8>>, FrameError> {
+    command_source(mpdu, DATA_REQUEST_COMMAND_ID)
+}
+
+/// Return the source address (little endian) of the given frame if it is a
+/// MAC command with the given command ID, see [`data_request_source()`].
+pub(super) fn command_source(
+    mpdu: &[u8],
+    command_id: u8,
+) -> Result<Option<heapless::Vec<u8, 8>>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if frame_control.frame_type() != FrameType::MacCommand {
+        return Ok(None);
+    }
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+    if frame_control.information_elements_present() {
+        return Err(FrameErrorKind::IesNotSupported.into());
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    if field(mpdu, header.end, 1)?[0] != command_id {
+        return Ok(None);
+    }
+    Ok(header
+        .addressing_fields
+        .and_then(|addressing_fields| addressing_fields.into_src_address())
+        .filter(|address| !address.is_absent())
+        // Safety: Addresses never exceed 8 bytes.
+        .map(|address| heapless::Vec::from_slice(address.as_le_bytes()).unwrap()))
+}
+
+/// Return whether the MAC must acknowledge the given received frame in
+/// software.
+///
+/// Radios without hardware ACKs leave all ACKs to the MAC. Radios whose
+/// hardware ACKs don't control the Frame Pending flag leave the ACKs of MAC
+/// commands to the MAC, see [`IndirectQueue::ack_frame()`].
+///
+/// * `capabilities` - The capabilities of the radio
+/// * `mpdu` - Received MPDU (without FCS)
+pub fn requires_software_ack(capabilities: &RadioCapabilities, mpdu: &[u8]) -> bool {
+    !capabilities.hardware_ack
+        || (!capabilities.frame_pending
+            && FrameControl::new(mpdu)
+                .is_ok_and(|frame_control| frame_control.frame_type() == FrameType::MacCommand))
+}
+
+/// Build a Data Request command requesting an acknowledgement.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `coordinator` - PAN ID and address of the coordinator
+/// * `src_address` - Address of the device
+pub fn data_request_frame(
+    seq_nr: u8,
+    coordinator: (PanId<[u8; 2]>, Address<&[u8]>),
+    src_address: Address<&[u8]>,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let pan_id = coordinator.0;
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_ack_request(true)
+        .with_addressing(Some(coordinator), Some((pan_id, src_address)))
+        .without_security()
+        .without_ies()
+        .with_payload(&[DATA_REQUEST_COMMAND_ID]);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Return whether the given beacon lists the given device in its pending
+/// address list.
+///
+/// Other frames and Enhanced Beacons have no pending address list and never
+/// list a device.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+/// * `short_address` - macShortAddress of the device, not matched if it is
+///   0xfffe or 0xffff
+/// * `extended_address` - macExtendedAddress of the device (little endian)
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::SecurityNotSupported`] if the beacon is secured,
+/// - any other error if the frame is truncated.
+pub fn is_address_pending(
+    mpdu: &[u8],
+    short_address: u16,
+    extended_address: Option<&[u8; 8]>,
+) -> Result<bool, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if frame_control.frame_type() != FrameType::Beacon {
+        return Ok(false);
+    }
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+    match frame_control.frame_version() {
+        FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {}
+        FrameVersion::Ieee802154 => return Ok(false),
+        FrameVersion::Unknown => return Err(FrameErrorKind::InvalidFrameVersion.into()),
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    let beacon_fields =
+        BeaconFields::new(&mpdu[header.end..]).map_err(|e| e.shifted_by(header.end))?;
+    let pending_address_fields = beacon_fields.pending_address_fields();
+    if short_address < 0xfffe
+        && pending_address_fields
+            .short_addresses()
+            .any(|address| address.into_u16() == short_address)
+    {
+        return Ok(true);
+    }
+    Ok(extended_address.is_some_and(|extended_address| {
+        pending_address_fields
+            .extended_addresses()
+            .any(|address| *address.as_ref() == *extended_address)
+    }))
+}
+
+/// Build the Data Request command to send in response to the given beacon
+/// if the beacon is from the coordinator of the device and lists the device
+/// in its pending address list.
+///
+/// Returns `None` if macAutoRequest is not set, in which case the beacon is
+/// left to the upper layer (MLME-BEACON-NOTIFY).
+///
+/// * `pib` - The PIB of the device, providing macAutoRequest, the addresses
+///   and macDsn
+/// * `mpdu` - Received beacon (without FCS)
+///
+/// # Errors
+///
+/// See [`is_address_pending()`].
+pub fn auto_request_frame(
+    pib: &mut Pib,
+    mpdu: &[u8],
+) -> Result<Option<FrameBuffer<PHY_MAX_PACKET_SIZE_127>>, FrameError> {
+    if !pib.auto_request
+        || !is_address_pending(mpdu, pib.short_address, pib.extended_address.as_ref())?
+    {
+        return Ok(None);
+    }
+
+    let frame_control = FrameControl::new(mpdu)?;
+    let Some(addressing_fields) = MacHeader::parse(mpdu, frame_control)?.addressing_fields else {
+        return Ok(None);
+    };
+    let (Some(pan_id), Some(coordinator)) = (
+        addressing_fields.src_pan_id(),
+        addressing_fields.src_address(),
+    ) else {
+        return Ok(None);
+    };
+    let own_coordinator = match coordinator {
+        Address::Short(address) => address.into_u16() == pib.coord_short_address,
+        Address::Extended(address) => pib
+            .coord_extended_address
+            .is_some_and(|coord_address| *address.as_ref() == coord_address),
+        _ => false,
+    };
+    if !own_coordinator || pan_id.into_u16() != pib.pan_id.into_u16() {
+        return Ok(None);
+    }
+
+    let short_address = pib.short_address.to_le_bytes();
+    let src_address = if pib.short_address < 0xfffe {
+        Address::Short(ShortAddress::new(&short_address[..]))
+    } else {
+        match &pib.extended_address {
+            Some(extended_address) => {
+                Address::Extended(ExtendedAddress::new(&extended_address[..]))
+            }
+            None => return Ok(None),
+        }
+    };
+    data_request_frame(pib.dsn.next(), (pib.pan_id, coordinator), src_address).map(Some)
+}
+
+/// The addresses to list in the pending address fields of a beacon, see
+/// [`IndirectQueue::pending_addresses()`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PendingAddresses {
+    /// The short addresses of devices with pending frames.
+    pub short_addresses: heapless::Vec<ShortAddress<[u8; 2]>, MAX_PENDING_ADDRESSES>,
+    /// The extended addresses of devices with pending frames.
+    pub extended_addresses: heapless::Vec<ExtendedAddress<[u8; 8]>, MAX_PENDING_ADDRESSES>,
+}
+
+impl PendingAddresses {
+    /// Return the total number of addresses.
+    pub fn len(&self) -> usize {
+        self.short_addresses.len() + self.extended_addresses.len()
+    }
+
+    /// Return whether no address is listed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A frame kept for a device until it polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransaction<T, Timer: Frequency> {
+    /// The handle of the frame given by the upper layer.
+    pub msdu_handle: u8,
+    /// The destination address of the frame (little endian).
+    pub dst_address: heapless::Vec<u8, 8>,
+    /// The instant at which the transaction expires.
+    pub expires_at: Instant<Timer>,
+    /// The frame.
+    pub frame: T,
+}
+
+/// The frames kept for up to `N` transactions, see the module documentation.
+///
+/// Frames for the same device are delivered in the order in which they were
+/// queued.
+pub struct IndirectQueue<T, Timer: Frequency, const N: usize> {
+    /// Pending transactions in the order in which they were queued.
+    transactions: heapless::Vec<PendingTransaction<T, Timer>, N>,
+    /// The time a transaction is kept in the queue.
+    persistence: Duration<Timer>,
+    /// The addresses (little endian) for which the radio sets the Frame
+    /// Pending flag.
+    published: heapless::Vec<heapless::Vec<u8, 8>, N>,
+}
+
+impl<T, Timer: Frequency, const N: usize> Default for IndirectQueue<T, Timer, N> {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRANSACTION_PERSISTENCE_TIME)
+    }
+}
+
+impl<T, Timer: Frequency, const N: usize> IndirectQueue<T, Timer, N> {
+    /// Creates a new, empty [`IndirectQueue`].
+    ///
+    /// * `persistence_time` - macTransactionPersistenceTime in unit periods
+    pub fn new(persistence_time: u16) -> Self {
+        Self {
+            transactions: heapless::Vec::new(),
+            persistence: transaction_persistence_duration(persistence_time)
+                .convert_into_rounding_up(),
+            published: heapless::Vec::new(),
+        }
+    }
+
+    /// Queue a frame until the given device polls.
+    ///
+    /// * `dst_address` - Address of the device (little endian)
+    /// * `msdu_handle` - Handle of the frame given by the upper layer
+    /// * `frame` - The frame
+    /// * `now` - The current instant
+    ///
+    /// Returns the frame with [`DataError::TransactionOverflow`] if the queue
+    /// is full or [`DataError::InvalidAddress`] if the address is too long.
+    pub fn enqueue(
+        &mut self,
+        dst_address: &[u8],
+        msdu_handle: u8,
+        frame: T,
+        now: Instant<Timer>,
+    ) -> Result<(), (DataError, T)> {
+        let dst_address = match heapless::Vec::from_slice(dst_address) {
+            Ok(dst_address) => dst_address,
+            Err(_) => return Err((DataError::InvalidAddress, frame)),
+        };
+        self.transactions
+            .push(PendingTransaction {
+                msdu_handle,
+                dst_address,
+                expires_at: now + self.persistence,
+                frame,
+            })
+            .map_err(|transaction| {
+                MAC_COUNTERS.queue_overflows.increment();
+                (DataError::TransactionOverflow, transaction.frame)
+            })
+    }
+
+    /// Return whether a frame is pending for the given device.
+    ///
+    /// * `dst_address` - Address of the device (little endian)
+    pub fn is_pending(&self, dst_address: &[u8]) -> bool {
+        self.transactions
+            .iter()
+            .any(|transaction| *transaction.dst_address == *dst_address)
+    }
+
+    /// Return the addresses of the devices with pending frames to list in the
+    /// next beacon.
+    ///
+    /// A beacon lists at most seven addresses. Devices are listed in the order
+    /// in which their oldest frame was queued, the others have to wait for a
+    /// later beacon.
+    pub fn pending_addresses(&self) -> PendingAddresses {
+        let mut pending_addresses = PendingAddresses::default();
+        for transaction in &self.transactions {
+            if pending_addresses.len() == MAX_PENDING_ADDRESSES {
+                break;
+            }
+            match *transaction.dst_address {
+                [low, high] => {
+                    let address = ShortAddress::new_owned([low, high]);
+                    if !pending_addresses.short_addresses.contains(&address) {
+                        // Safety: At most MAX_PENDING_ADDRESSES are listed.
+                        pending_addresses.short_addresses.push(address).unwrap();
+                    }
+                }
+                [_, _, _, _, _, _, _, _] => {
+                    // Safety: The slice has 8 bytes.
+                    let address =
+                        ExtendedAddress::new_owned(transaction.dst_address[..].try_into().unwrap());
+                    if !pending_addresses.extended_addresses.contains(&address) {
+                        // Safety: At most MAX_PENDING_ADDRESSES are listed.
+                        pending_addresses.extended_addresses.push(address).unwrap();
+                    }
+                }
+                _ => {}
+            }
+        }
+        pending_addresses
+    }
+
+    /// Publishes the addresses of the devices with pending frames to the given
+    /// radio so that its hardware ACKs set the Frame Pending flag, see
+    /// [`RadioCapabilities::frame_pending`].
+    ///
+    /// Only changes since the last call are published. Call it whenever
+    /// frames were queued or removed.
+    ///
+    /// Returns whether the radio sets the flag for all devices with pending
+    /// frames. Devices that didn't fit into the radio are acknowledged without
+    /// the flag and find their frames at a later poll, once they fit.
+    pub fn publish_pending_addresses<R: Radio>(&mut self, radio: &mut R) -> bool {
+        if !radio.capabilities().frame_pending {
+            return false;
+        }
+
+        // Withdraw addresses first to make room in the radio.
+        let transactions = &self.transactions;
+        self.published.retain(|address| {
+            let pending = transactions
+                .iter()
+                .any(|transaction| transaction.dst_address == *address);
+            if !pending {
+                // A stale flag merely makes the device wait for a frame in
+                // vain.
+                let _ = radio.set_frame_pending(address, false);
+            }
+            pending
+        });
+
+        let mut complete = true;
+        for transaction in &self.transactions {
+            if self.published.contains(&transaction.dst_address) {
+                continue;
+            }
+            if radio
+                .set_frame_pending(&transaction.dst_address, true)
+                .is_ok()
+            {
+                // Safety: At most N distinct addresses are pending.
+                self.published
+                    .push(transaction.dst_address.clone())
+                    .unwrap();
+            } else {
+                complete = false;
+            }
+        }
+        complete
+    }
+
+    /// Build the ACK of a received frame like [`ack_frame()`], setting the
+    /// Frame Pending flag if the frame is a Data Request command of a device
+    /// for which a frame is pending.
+    ///
+    /// * `mpdu` - Received MPDU (without FCS)
+    /// * `time_correction` - Content of the Time Correction IE to include in an
+    ///   Enh-Ack, ignored for Imm-Acks
+    pub fn ack_frame(
+        &self,
+        mpdu: &[u8],
+        time_correction: Option<TimeCorrection>,
+    ) -> Result<Option<AckFrame>, FrameError> {
+        let Some(mut ack) = ack_frame(mpdu, time_correction)? else {
+            return Ok(None);
+        };
+        if let Some(src_address) = data_request_source(mpdu)? {
+            ack.frame_control_mut()?
+                .set_frame_pending(self.is_pending(&src_address));
+        }
+        Ok(Some(ack))
+    }
+
+    /// Remove the oldest frame pending for the given device in response to a
+    /// Data Request command.
+    ///
+    /// Returns the frame and whether more frames are pending for the device,
+    /// in which case the Frame Pending flag of the frame must be set.
+    ///
+    /// * `dst_address` - Address of the device (little endian)
+    pub fn poll(&mut self, dst_address: &[u8]) -> Option<(T, bool)> {
+        let index = self
+            .transactions
+            .iter()
+            .position(|transaction| *transaction.dst_address == *dst_address)?;
+        let transaction = self.transactions.remove(index);
+        Some((transaction.frame, self.is_pending(dst_address)))
+    }
+
+    /// Remove the frame with the given handle (MCPS-PURGE).
+    pub fn purge(&mut self, msdu_handle: u8) -> Option<T> {
+        let index = self
+            .transactions
+            .iter()
+            .position(|transaction| transaction.msdu_handle == msdu_handle)?;
+        Some(self.transactions.remove(index).frame)
+    }
+
+    /// Remove the next expired transaction, to be reported to the upper
+    /// layer with [`DataError::TransactionExpired`].
+    ///
+    /// * `now` - The current instant
+    pub fn pop_expired(&mut self, now: Instant<Timer>) -> Option<PendingTransaction<T, Timer>> {
+        let index = self
+            .transactions
+            .iter()
+            .position(|transaction| transaction.expires_at <= now)?;
+        Some(self.transactions.remove(index))
+    }
+
+    /// Return the instant at which the next transaction expires.
+    pub fn next_expiry(&self) -> Option<Instant<Timer>> {
+        self.transactions
+            .iter()
+            .map(|transaction| transaction.expires_at)
+            .min()
+    }
+
+    /// Return the number of pending transactions.
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Return whether no transaction is pending.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
+
+/// The outcome of a poll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollStatus<Timer: Frequency> {
+    /// A frame was received from the coordinator at the given instant.
+    Data(Instant<Timer>),
+    /// The coordinator has no frame pending for the device.
+    NoData,
+    /// The Data Request command was not acknowledged.
+    NoAck,
+}
+
+/// Polls the coordinator for pending frames (MLME-POLL), see the module
+/// documentation.
+pub struct Poller<Timer: RadioTimerApi> {
+    retransmissions: Retransmissions<Timer>,
+    /// macMaxFrameTotalWaitTime
+    max_frame_total_wait_time: Duration<Timer>,
+}
+
+impl<Timer: RadioTimerApi> Poller<Timer> {
+    /// Creates a new [`Poller`].
+    ///
+    /// * `config` - The configuration providing macMaxFrameRetries, the
+    ///   interframe spaces and macMaxFrameTotalWaitTime
+    pub fn new(config: &CsmaConfig) -> Result<Self, CsmaConfigError> {
+        Ok(Self {
+            retransmissions: Retransmissions::new(config)?,
+            max_frame_total_wait_time: config
+                .max_frame_total_wait_time()
+                .convert_into_rounding_up(),
+        })
+    }
+
+    /// Sends the given Data Request command and receives the pending frame, if
+    /// any, into the given buffer.
+    ///
+    /// The frame must be a Data frame from the coordinator that the command was
+    /// sent to or a MAC command addressed to the device, e.g. an Association
+    /// Response command. Other frames are ignored.
+    ///
+    /// * `radio` - The radio transmitting the command
+    /// * `data_request` - Data Request command, see [`data_request_frame()`]
+    /// * `frame` - Buffer receiving the pending frame
+    pub async fn poll<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        data_request: &[u8],
+        frame: &mut AckFrame,
+    ) -> Result<PollStatus<Timer>, FrameError> {
+        let frame_control = FrameControl::new(data_request)?;
+        let addressing_fields = MacHeader::parse(data_request, frame_control)?
+            .addressing_fields
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+        let (Some(coordinator), Some(device)) = (
+            addressing_fields.dst_address(),
+            addressing_fields.src_address(),
+        ) else {
+            return Err(FrameErrorKind::InvalidAddressingCombination.into());
+        };
+
+        let report = self.retransmissions.transmit(radio, data_request).await?;
+        let Some(ack) = report.attempts.last().and_then(|attempt| attempt.ack) else {
+            return Ok(PollStatus::NoAck);
+        };
+        if !ack.frame_pending {
+            return Ok(PollStatus::NoData);
+        }
+
+        let deadline = Timer::now() + self.max_frame_total_wait_time;
+        while let Some(rx) = radio.receive_until(frame, deadline).await {
+            if is_pending_frame(frame, coordinator.as_le_bytes(), device.as_le_bytes()) {
+                return Ok(PollStatus::Data(rx.end));
+            }
+        }
+        Ok(PollStatus::NoData)
+    }
+}
+
+/// Return whether the given frame is a Data frame from the given coordinator
+/// or a MAC command addressed to the given device.
+fn is_pending_frame(mpdu: &[u8], coordinator: &[u8], device: &[u8]) -> bool {
+    let Ok(frame_control) = FrameControl::new(mpdu) else {
+        return false;
+    };
+    let frame_type = frame_control.frame_type();
+    let Some(addressing_fields) = MacHeader::parse(mpdu, frame_control)
+        .ok()
+        .and_then(|header| header.addressing_fields)
+    else {
+        return false;
+    };
+    match frame_type {
+        FrameType::Data => addressing_fields
+            .src_address()
+            .is_some_and(|address| *address.as_le_bytes() == *coordinator),
+        FrameType::MacCommand => addressing_fields
+            .dst_address()
+            .is_some_and(|address| *address.as_le_bytes() == *device),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            radio::{RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+            time::Microseconds,
+        },
+        mac::{
+            pib::PibAttribute,
+            test_helpers::{mac_radio, received, timed_out, transmitted, TEST_CAPABILITIES},
+        },
+    };
+
+    const COORDINATOR: [u8; 2] = [0x00, 0x00];
+    const DEVICE: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    fn data_request(seq_nr: u8) -> FrameBuffer<PHY_MAX_PACKET_SIZE_127> {
+        data_request_frame(
+            seq_nr,
+            (
+                PanId::from_u16(0xabcd),
+                Address::Short(ShortAddress::new(&COORDINATOR[..])),
+            ),
+            Address::Extended(ExtendedAddress::new(&DEVICE[..])),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn data_request_command() {
+        let mpdu = data_request(7);
+        assert_eq!(
+            mpdu[..],
+            [
+                0x63, 0xd8, 0x07, 0xcd, 0xab, 0x00, 0x00, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02,
+                0x01, 0x04
+            ]
+        );
+        assert_eq!(data_request_source(&mpdu).unwrap().unwrap()[..], DEVICE);
+
+        // Other commands and frame types.
+        let mut mpdu = data_request(7);
+        let len = mpdu.len();
+        mpdu.as_mut_capacity()[len - 1] = 0x01;
+        assert_eq!(data_request_source(&mpdu), Ok(None));
+        assert_eq!(data_request_source(&[0x02, 0x10, 0x07]), Ok(None));
+    }
+
+    #[test]
+    fn indirect_queue() {
+        let mut queue = IndirectQueue::<u8, Microseconds, 2>::new(1);
+        queue.enqueue(&DEVICE, 1, 10, Instant::new(0)).unwrap();
+        queue.enqueue(&DEVICE, 2, 20, Instant::new(100)).unwrap();
+        assert!(matches!(
+            queue.enqueue(&COORDINATOR, 3, 30, Instant::new(100)),
+            Err((DataError::TransactionOverflow, 30))
+        ));
+        assert!(queue.is_pending(&DEVICE));
+        assert!(!queue.is_pending(&COORDINATOR));
+
+        // The ACK to the Data Request announces the pending frames.
+        let ack = queue.ack_frame(&data_request(7), None).unwrap().unwrap();
+        assert_eq!(ack[..], [0x12, 0x10, 0x07]);
+
+        assert_eq!(queue.poll(&DEVICE), Some((10, true)));
+        assert_eq!(queue.poll(&DEVICE), Some((20, false)));
+        assert_eq!(queue.poll(&DEVICE), None);
+        let ack = queue.ack_frame(&data_request(8), None).unwrap().unwrap();
+        assert_eq!(ack[..], [0x02, 0x10, 0x08]);
+
+        // Transactions expire after 960 symbols of 16µs each.
+        queue.enqueue(&DEVICE, 4, 40, Instant::new(0)).unwrap();
+        queue
+            .enqueue(&COORDINATOR, 5, 50, Instant::new(1_000))
+            .unwrap();
+        assert_eq!(queue.next_expiry(), Some(Instant::new(15_360)));
+        assert!(queue.pop_expired(Instant::new(15_359)).is_none());
+        let expired = queue.pop_expired(Instant::new(15_360)).unwrap();
+        assert_eq!((expired.msdu_handle, expired.frame), (4, 40));
+        assert!(queue.pop_expired(Instant::new(15_360)).is_none());
+
+        assert_eq!(queue.purge(4), None);
+        assert_eq!(queue.purge(5), Some(50));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn pending_addresses() {
+        let mut queue = IndirectQueue::<u8, Microseconds, 10>::new(1);
+        queue.enqueue(&DEVICE, 1, 10, Instant::new(0)).unwrap();
+        queue
+            .enqueue(&[0x34, 0x12], 2, 20, Instant::new(0))
+            .unwrap();
+        queue.enqueue(&DEVICE, 3, 30, Instant::new(0)).unwrap();
+        for handle in 4..10 {
+            queue
+                .enqueue(&[handle, 0x00], handle, 0, Instant::new(0))
+                .unwrap();
+        }
+
+        // Listed once per device, the last device has to wait.
+        let pending_addresses = queue.pending_addresses();
+        assert_eq!(pending_addresses.len(), MAX_PENDING_ADDRESSES);
+        assert_eq!(
+            pending_addresses.extended_addresses[..],
+            [ExtendedAddress::new_owned(DEVICE)]
+        );
+        assert_eq!(
+            pending_addresses
+                .short_addresses
+                .iter()
+                .map(|address| address.into_u16())
+                .collect::<heapless::Vec<_, 7>>()[..],
+            [0x1234, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008]
+        );
+    }
+
+    #[test]
+    fn auto_request() {
+        // Beacon (2006) of the coordinator listing 0x1234 and the device.
+        const BEACON: [u8; 21] = [
+            0x00, 0x90, 0x01, 0xcd, 0xab, 0x00, 0x00, 0xff, 0xcf, 0x00, 0x11, 0x34, 0x12, 0x08,
+            0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01,
+        ];
+
+        assert_eq!(is_address_pending(&BEACON, 0x1234, None), Ok(true));
+        assert_eq!(is_address_pending(&BEACON, 0x5678, None), Ok(false));
+        assert_eq!(is_address_pending(&BEACON, 0xfffe, Some(&DEVICE)), Ok(true));
+        assert_eq!(
+            is_address_pending(&data_request(7), 0x0000, None),
+            Ok(false)
+        );
+
+        let mut pib = Pib::default();
+        pib.set(PibAttribute::MacPanId(0xabcd)).unwrap();
+        pib.set(PibAttribute::MacCoordShortAddress(0x0000)).unwrap();
+        pib.set(PibAttribute::MacExtendedAddress(DEVICE)).unwrap();
+        pib.set(PibAttribute::MacDsn(7)).unwrap();
+
+        // Without a short address, the device polls with its extended address.
+        let mpdu = auto_request_frame(&mut pib, &BEACON).unwrap().unwrap();
+        assert_eq!(mpdu[..], data_request(7)[..]);
+
+        pib.set(PibAttribute::MacShortAddress(0x1234)).unwrap();
+        let mpdu = auto_request_frame(&mut pib, &BEACON).unwrap().unwrap();
+        assert_eq!(
+            mpdu[..],
+            [0x63, 0x98, 0x08, 0xcd, 0xab, 0x00, 0x00, 0x34, 0x12, 0x04]
+        );
+
+        // Beacons of other coordinators are ignored, as are all beacons
+        // without macAutoRequest.
+        pib.set(PibAttribute::MacCoordShortAddress(0x0001)).unwrap();
+        assert!(auto_request_frame(&mut pib, &BEACON).unwrap().is_none());
+        pib.set(PibAttribute::MacCoordShortAddress(0x0000)).unwrap();
+        pib.set(PibAttribute::MacAutoRequest(false)).unwrap();
+        assert!(auto_request_frame(&mut pib, &BEACON).unwrap().is_none());
+    }
+
+    /// Radio with hardware ACKs setting the Frame Pending flag for up to two
+    /// devices.
+    #[derive(Default)]
+    struct FramePendingRadio {
+        frame_pending: bool,
+        table: heapless::Vec<heapless::Vec<u8, 8>, 2>,
+    }
+
+    impl Radio for FramePendingRadio {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            RadioCapabilities {
+                hardware_ack: true,
+                frame_pending: self.frame_pending,
+                timestamp_resolution: Duration::new(1_000),
+                min_tx_power: 0,
+                max_tx_power: 0,
+            }
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            unimplemented!()
+        }
+
+        fn set_tx_power(&mut self, _dbm: i8) -> Result<i8, RadioError> {
+            unimplemented!()
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            unimplemented!()
+        }
+
+        async fn receive(
+            &mut self,
+            _buffer: &mut [u8],
+            _window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            unimplemented!()
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            unimplemented!()
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            unimplemented!()
+        }
+
+        fn set_frame_pending(
+            &mut self,
+            src_address: &[u8],
+            pending: bool,
+        ) -> Result<(), RadioError> {
+            let src_address = heapless::Vec::from_slice(src_address).unwrap();
+            if pending {
+                self.table
+                    .push(src_address)
+                    .map_err(|_| RadioError::Unsupported)
+            } else {
+                self.table.retain(|address| *address != src_address);
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn hardware_frame_pending() {
+        let mut queue = IndirectQueue::<u8, TestClock, 4>::new(1);
+        let mut radio = FramePendingRadio::default();
+        queue.enqueue(&DEVICE, 1, 10, Instant::new(0)).unwrap();
+        assert!(!queue.publish_pending_addresses(&mut radio));
+        let capabilities = radio.capabilities();
+        assert!(requires_software_ack(&capabilities, &data_request(7)));
+        assert!(!requires_software_ack(&capabilities, &[0x61, 0x88, 0x07]));
+
+        // Devices are published once.
+        radio.frame_pending = true;
+        queue.enqueue(&DEVICE, 2, 20, Instant::new(0)).unwrap();
+        queue
+            .enqueue(&[0x34, 0x12], 3, 30, Instant::new(0))
+            .unwrap();
+        assert!(queue.publish_pending_addresses(&mut radio));
+        assert_eq!(radio.table.len(), 2);
+        let capabilities = radio.capabilities();
+        assert!(!requires_software_ack(&capabilities, &data_request(7)));
+
+        // Devices that don't fit are published once others are withdrawn.
+        queue.enqueue(&COORDINATOR, 4, 40, Instant::new(0)).unwrap();
+        assert!(!queue.publish_pending_addresses(&mut radio));
+        assert_eq!(queue.purge(3), Some(30));
+        assert!(queue.publish_pending_addresses(&mut radio));
+        assert_eq!(radio.table[..], [&DEVICE[..], &COORDINATOR[..]]);
+    }
+
+    /// Radio acknowledging the Data Request and replaying the given frame.
+    struct CoordinatorRadio<'a> {
+        ack: &'a [u8],
+        frames: core::slice::Iter<'a, &'a [u8]>,
+    }
+
+    impl Radio for CoordinatorRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            let start = TestClock::now();
+            TestClock::advance(Duration::new(500));
+            transmitted(start)
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            let mpdu = if !self.ack.is_empty() {
+                core::mem::take(&mut self.ack)
+            } else {
+                match self.frames.next() {
+                    Some(mpdu) => *mpdu,
+                    None => return timed_out(window),
+                }
+            };
+            TestClock::advance(Duration::new(100));
+            received(buffer, mpdu)
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    fn poll(ack: &[u8], frames: &[&[u8]]) -> (PollStatus<TestClock>, AckFrame) {
+        TestClock::reset();
+        let poller = Poller::<TestClock>::new(&CsmaConfig::default()).unwrap();
+        let mut radio = mac_radio(CoordinatorRadio {
+            ack,
+            frames: frames.iter(),
+        });
+        let data_request = data_request(7);
+        let mut frame = AckFrame::new();
+        let status = {
+            let mut polling = pin!(poller.poll(&mut radio, &data_request, &mut frame));
+            let mut cx = Context::from_waker(Waker::noop());
+            match polling.as_mut().poll(&mut cx) {
+                Poll::Ready(status) => status.unwrap(),
+                Poll::Pending => panic!("polls must not wait beyond the ACK wait"),
+            }
+        };
+        (status, frame)
+    }
+
+    #[test]
+    fn poll_coordinator() {
+        // Data frame (2006) from the coordinator to the device.
+        const DATA: [u8; 16] = [
+            0x41, 0x9c, 0x01, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00,
+            0x00, 0x11,
+        ];
+        const OTHER_DATA: [u8; 16] = [
+            0x41, 0x9c, 0x01, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x01,
+            0x00, 0x11,
+        ];
+
+        let (status, frame) = poll(&[0x12, 0x10, 0x07], &[&OTHER_DATA, &DATA]);
+        assert_eq!(status, PollStatus::Data(Instant::new(800)));
+        assert_eq!(frame[..], DATA);
+
+        assert_eq!(poll(&[0x02, 0x10, 0x07], &[&DATA]).0, PollStatus::NoData);
+        // 500µs TX, 100µs ACK and 1986 symbols of 16µs each.
+        let (status, _) = poll(&[0x12, 0x10, 0x07], &[]);
+        assert_eq!(status, PollStatus::NoData);
+        assert_eq!(TestClock::now(), Instant::new(600 + 31_776));
+    }
+}
@@ -9,24 +9,28 @@ use crate::trace::{
 use crate::{
     driver::{
         frame::{
-            Address, AddressingMode, PanId, RadioFrame, RadioFrameRepr, RadioFrameSized,
-            RadioFrameUnsized,
+            Address, AddressingMode, Annotated, FrameVersion, PanId, RadioFrame, RadioFrameRepr,
+            RadioFrameSized, RadioFrameUnsized, RxMetadata,
         },
+        link_quality::LinkQuality,
+        phy::PhyMode,
         tasks::{RxError, RxResult, Timestamp, TxError, TxResult},
+        time::{Instant, Microseconds},
         DriverConfig, DrvSvcRequest, DrvSvcResponse, DrvSvcTaskError, DrvSvcTaskRx, DrvSvcTaskTx,
     },
     mac::{frame::mpdu::MpduFrame, task::*, MacBufferAllocator},
     util::{Error, Result as SimplifiedResult},
 };
 
+#[cfg(feature = "zigbee")]
+use crate::mac::zigbee::ZigbeeWarning;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataError {
-    // TODO: not supported
     TransactionOverflow,
-    // TODO: not supported
     TransactionExpired,
     // TODO: not supported
     ChannelAccessFailure,
-    // TODO: not supported
     InvalidAddress,
     // TODO: not supported
     NoAck,
@@ -34,13 +38,16 @@ pub enum DataError {
     CounterError,
     // TODO: not supported
     FrameTooLong,
-    // TODO: not supported
     InvalidParameter,
+    /// The frame exceeded the duty cycle budget of its band.
+    DutyCycleExceeded,
 }
 
 pub struct DataRequest {
     /// The frame to be sent.
     mpdu: MpduFrame,
+    /// The transmission parameters not encoded in the frame.
+    parameters: TxParameters,
 }
 
 /// Represents an MLME-DATA.request.
@@ -50,7 +57,10 @@ pub struct DataRequest {
 ///       move existing data around.
 impl DataRequest {
     pub fn new(mpdu: MpduFrame) -> Self {
-        Self { mpdu }
+        Self {
+            mpdu,
+            parameters: TxParameters::default(),
+        }
     }
 
     pub fn src_addr_mode(&self) -> AddressingMode {
@@ -105,14 +115,81 @@ impl DataRequest {
         addr_fields.dst_address_mut().ok_or(Error)?.set(dst_addr)
     }
 
+    /// Sets the sequence number of the frame, i.e. macDsn.
+    pub fn set_sequence_number(&mut self, seq_nr: u8) -> SimplifiedResult<()> {
+        self.mpdu.set_sequence_number(seq_nr)
+    }
+
+    /// Pins the frame to IEEE 802.15.4-2006 framing for Zigbee devices, see
+    /// [`zigbee::pin()`](crate::mac::zigbee::pin).
+    #[cfg(feature = "zigbee")]
+    pub fn pin_to_zigbee(&mut self) -> Result<bool, ZigbeeWarning> {
+        crate::mac::zigbee::pin(self.mpdu.pdu_mut_wo_fcs())
+    }
+
     pub fn tx_options(&mut self) -> TxOptions<'_> {
         TxOptions {
             mpdu: &mut self.mpdu,
+            parameters: &mut self.parameters,
+        }
+    }
+}
+
+/// The acknowledgement requested for a frame.
+///
+/// The type of acknowledgement follows from the frame version (IEEE
+/// 802.15.4-2020, section 6.7.4.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckType {
+    /// Imm-Ack, requested by frames of version IEEE 802.15.4-2003 and 2006.
+    ImmAck,
+    /// Enh-Ack, requested by frames of version IEEE 802.15.4-2015 and later.
+    EnhAck,
+}
+
+/// The transmission parameters of a frame that are not encoded in the frame
+/// itself, see [`TxOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxParameters {
+    /// The transmit power in dBm or [`None`] to use the power configured in
+    /// the radio. Radios round unsupported powers down to the next supported
+    /// power.
+    pub tx_power: Option<i8>,
+    /// Whether the frame is sent with CSMA-CA. Time-critical frames, e.g. in
+    /// dedicated slots, bypass CSMA-CA and are transmitted once without
+    /// backoff.
+    ///
+    /// The driver service cannot wait for backoffs yet. The MAC service sends
+    /// frames with CSMA-CA after a single CCA, i.e. like CSMA-CA with
+    /// macMinBe and macMaxCsmaBackoffs set to zero.
+    pub csma: bool,
+    /// Whether a CCA precedes the transmission of a frame bypassing CSMA-CA.
+    /// CSMA-CA always assesses the channel.
+    pub cca: bool,
+    /// The PHY mode or [`None`] to use the mode configured in the radio,
+    /// e.g. to send a frame at another data rate of a SUN PHY.
+    ///
+    /// The driver service doesn't switch PHY modes yet. The MAC service
+    /// rejects frames with a PHY mode with [`DataError::InvalidParameter`].
+    pub phy_mode: Option<PhyMode>,
+}
+
+impl Default for TxParameters {
+    fn default() -> Self {
+        Self {
+            tx_power: None,
+            csma: true,
+            cca: false,
+            phy_mode: None,
         }
     }
 }
+
+/// Represents the TxOptions of an MCPS-DATA.request, i.e. the flags of the
+/// frame and its [`TxParameters`].
 pub struct TxOptions<'mpdu> {
     mpdu: &'mpdu mut MpduFrame,
+    parameters: &'mpdu mut TxParameters,
 }
 
 impl<'mpdu> TxOptions<'mpdu> {
@@ -124,6 +201,76 @@ impl<'mpdu> TxOptions<'mpdu> {
         self.mpdu.frame_control_mut().set_ack_request(ack_tx);
     }
 
+    /// The requested acknowledgement or [`None`] if no acknowledgement is
+    /// requested.
+    pub fn ack_type(&self) -> Option<AckType> {
+        let frame_control = self.mpdu.frame_control();
+        if !frame_control.ack_request() {
+            return None;
+        }
+        match frame_control.frame_version() {
+            FrameVersion::Ieee802154 => Some(AckType::EnhAck),
+            _ => Some(AckType::ImmAck),
+        }
+    }
+
+    /// Requests the given acknowledgement or none.
+    ///
+    /// Fails if the acknowledgement doesn't match the frame version.
+    pub fn set_ack_type(&mut self, ack_type: Option<AckType>) -> SimplifiedResult<()> {
+        let frame_version = self.mpdu.frame_control().frame_version();
+        let matches_frame_version = match ack_type {
+            None => true,
+            Some(AckType::ImmAck) => matches!(
+                frame_version,
+                FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006
+            ),
+            Some(AckType::EnhAck) => frame_version == FrameVersion::Ieee802154,
+        };
+        if !matches_frame_version {
+            return Err(Error);
+        }
+        self.set_ack_tx(ack_type.is_some());
+        Ok(())
+    }
+
+    /// The transmit power of the frame in dBm, see [`TxParameters::tx_power`].
+    pub fn tx_power(&self) -> Option<i8> {
+        self.parameters.tx_power
+    }
+
+    pub fn set_tx_power(&mut self, tx_power: Option<i8>) {
+        self.parameters.tx_power = tx_power;
+    }
+
+    /// Whether the frame is sent with CSMA-CA, see [`TxParameters::csma`].
+    pub fn csma(&self) -> bool {
+        self.parameters.csma
+    }
+
+    pub fn set_csma(&mut self, csma: bool) {
+        self.parameters.csma = csma;
+    }
+
+    /// Whether a CCA precedes frames bypassing CSMA-CA, see
+    /// [`TxParameters::cca`].
+    pub fn cca(&self) -> bool {
+        self.parameters.cca
+    }
+
+    pub fn set_cca(&mut self, cca: bool) {
+        self.parameters.cca = cca;
+    }
+
+    /// The PHY mode of the frame, see [`TxParameters::phy_mode`].
+    pub fn phy_mode(&self) -> Option<PhyMode> {
+        self.parameters.phy_mode
+    }
+
+    pub fn set_phy_mode(&mut self, phy_mode: Option<PhyMode>) {
+        self.parameters.phy_mode = phy_mode;
+    }
+
     pub fn pan_id_suppressed(&self) -> bool {
         self.mpdu.frame_control().pan_id_compression()
     }
@@ -155,8 +302,11 @@ pub struct DataConfirm {
 pub struct DataIndication {
     /// The received frame.
     pub mpdu: MpduFrame,
-    /// Timestamp of frame reception
-    pub timestamp: Option<NonZero<u32>>,
+    /// The time at which the RMARKER of the frame passed the local antenna,
+    /// if reported by the driver.
+    pub timestamp: Option<Instant<Microseconds>>,
+    /// The link quality of the frame, if reported by the driver.
+    pub link_quality: Option<LinkQuality>,
 }
 
 pub(crate) struct DataRequestTask<'task, RadioDriverImpl: DriverConfig> {
@@ -167,6 +317,8 @@ enum DataRequestState<'task, RadioDriverImpl: DriverConfig> {
     Initial(
         /// MPDU to be sent.
         MpduFrame,
+        /// Transmission parameters of the MPDU.
+        TxParameters,
         /// Placeholder for future references.
         PhantomData<&'task RadioDriverImpl>,
     ),
@@ -176,7 +328,11 @@ enum DataRequestState<'task, RadioDriverImpl: DriverConfig> {
 impl<RadioDriverImpl: DriverConfig> DataRequestTask<'_, RadioDriverImpl> {
     pub fn new(data_request: DataRequest) -> Self {
         Self {
-            state: DataRequestState::Initial(data_request.mpdu, PhantomData),
+            state: DataRequestState::Initial(
+                data_request.mpdu,
+                data_request.parameters,
+                PhantomData,
+            ),
         }
     }
 
@@ -204,6 +360,12 @@ impl<RadioDriverImpl: DriverConfig> DataRequestTask<'_, RadioDriverImpl> {
 
                         DataRequestResult::CcaBusy(unsent_tx_frame)
                     }
+                    DrvSvcTaskError::Task(TxError::Cancelled(unsent_tx_frame)) => {
+                        DataRequestResult::Rejected(
+                            unsent_tx_frame.forget_size::<RadioDriverImpl>(),
+                            DataError::TransactionExpired,
+                        )
+                    }
                     // TODO: Implement if required by a driver implementation.
                     _ => unreachable!(),
                 },
@@ -213,12 +375,14 @@ impl<RadioDriverImpl: DriverConfig> DataRequestTask<'_, RadioDriverImpl> {
         }
     }
 
-    fn tx_task(tx_mpdu: MpduFrame) -> DrvSvcRequest {
+    fn tx_task(tx_mpdu: MpduFrame, parameters: TxParameters) -> DrvSvcRequest {
         DrvSvcTaskTx {
             at: Timestamp::BestEffort,
             radio_frame: tx_mpdu.into_radio_frame::<RadioDriverImpl>(),
-            // TODO: CSMA/CA
-            cca: false,
+            // TODO: Back off, see `TxParameters::csma`.
+            cca: parameters.csma || parameters.cca,
+            tx_power: parameters.tx_power,
+            cancellation: None,
         }
         .into()
     }
@@ -244,6 +408,14 @@ pub(crate) enum DataRequestResult {
         /// recovered Tx radio frame
         RadioFrame<RadioFrameSized>,
     ),
+    /// The request was rejected without handing the frame to the driver or
+    /// cancelled before the driver sent it.
+    Rejected(
+        /// unsent radio frame
+        RadioFrame<RadioFrameUnsized>,
+        /// the status of the request
+        DataError,
+    ),
 }
 
 impl<RadioDriverImpl: DriverConfig> MacTask<RadioDriverImpl::Timer>
@@ -256,10 +428,19 @@ impl<RadioDriverImpl: DriverConfig> MacTask<RadioDriverImpl::Timer>
         rtos_trace::trace::task_exec_begin(MAC_REQUEST);
 
         match self.state {
-            DataRequestState::Initial(tx_mpdu, _) => {
+            DataRequestState::Initial(tx_mpdu, parameters, _) => {
                 debug_assert!(matches!(event, MacTaskEvent::Entry));
+                if parameters.phy_mode.is_some() {
+                    let radio_frame = tx_mpdu
+                        .into_radio_frame::<RadioDriverImpl>()
+                        .forget_size::<RadioDriverImpl>();
+                    return MacTaskTransition::Terminated(DataRequestResult::Rejected(
+                        radio_frame,
+                        DataError::InvalidParameter,
+                    ));
+                }
                 self.state = DataRequestState::SendingFrame;
-                MacTaskTransition::DrvSvcRequest(self, Self::tx_task(tx_mpdu), None)
+                MacTaskTransition::DrvSvcRequest(self, Self::tx_task(tx_mpdu, parameters), None)
             }
             DataRequestState::SendingFrame => {
                 match event {
@@ -314,7 +495,7 @@ impl<'task, RadioDriverImpl: DriverConfig> DataIndicationTask<'task, RadioDriver
     fn handle_rx_driver_response(
         &self,
         response: DrvSvcResponse,
-    ) -> Result<MpduFrame, RadioFrame<RadioFrameUnsized>> {
+    ) -> Result<Annotated<MpduFrame>, RadioFrame<RadioFrameUnsized>> {
         match response {
             DrvSvcResponse::Rx(rx_result) => match rx_result {
                 Ok(rx_result) => match rx_result {
@@ -322,8 +503,7 @@ impl<'task, RadioDriverImpl: DriverConfig> DataIndicationTask<'task, RadioDriver
                         #[cfg(feature = "rtos-trace")]
                         rtos_trace::trace::marker(RX_FRAME);
 
-                        let mpdu = MpduFrame::from_radio_frame(rx_frame);
-                        Ok(mpdu)
+                        Ok(rx_frame.map(MpduFrame::from_radio_frame))
                     }
                     RxResult::FilteredFrame(recovered_radio_frame) => {
                         #[cfg(feature = "rtos-trace")]
@@ -359,12 +539,14 @@ impl<'task, RadioDriverImpl: DriverConfig> DataIndicationTask<'task, RadioDriver
     }
 
     fn produce_indication_and_restart_rx(
-        rx_mpdu: MpduFrame,
+        rx_mpdu: Annotated<MpduFrame>,
         buffer_allocator: MacBufferAllocator,
     ) -> MacTaskTransition<Self, RadioDriverImpl::Timer> {
+        let rx_metadata = rx_mpdu.annotations.get::<RxMetadata>();
         let data_indication = DataIndication {
-            mpdu: rx_mpdu,
-            timestamp: None,
+            timestamp: rx_metadata.and_then(|rx_metadata| rx_metadata.timestamp),
+            link_quality: rx_metadata.and_then(RxMetadata::link_quality),
+            mpdu: rx_mpdu.frame,
         };
         let next_rx_radio_frame =
             Self::allocate_rx_radio_frame(&buffer_allocator).expect("no capacity");
@@ -432,3 +614,141 @@ impl<RadioDriverImpl: DriverConfig> MacTask<RadioDriverImpl::Timer>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::frame::FrameAnnotations,
+        mac::test_helpers::{mac_buffer_allocator, TestDriverConfig},
+        util::{allocator::IntoBuffer, frame::Frame},
+    };
+
+    /// Return a data frame (2006) without addressing fields.
+    fn mpdu(buffer_allocator: MacBufferAllocator) -> MpduFrame {
+        let buffer = buffer_allocator
+            .try_allocate_buffer(
+                RadioFrameRepr::<TestDriverConfig, RadioFrameUnsized>::new().max_buffer_length()
+                    as usize,
+            )
+            .unwrap();
+        let mut radio_frame =
+            RadioFrame::new::<TestDriverConfig>(buffer).with_size(NonZero::new(3).unwrap());
+        radio_frame.sdu_mut().copy_from_slice(&[0x41, 0x20, 0x2a]);
+        MpduFrame::from_radio_frame(radio_frame)
+    }
+
+    #[test]
+    fn csma() {
+        let buffer_allocator = mac_buffer_allocator();
+        for (csma, cca, expected_cca) in [
+            (true, false, true),
+            (false, false, false),
+            (false, true, true),
+        ] {
+            let mut data_request = DataRequest::new(mpdu(buffer_allocator));
+            data_request.tx_options().set_csma(csma);
+            data_request.tx_options().set_cca(cca);
+
+            let task = DataRequestTask::<TestDriverConfig>::new(data_request);
+            let MacTaskTransition::DrvSvcRequest(_, DrvSvcRequest::Tx(tx_task), None) =
+                task.step(MacTaskEvent::Entry)
+            else {
+                panic!("expected a TX task");
+            };
+            assert_eq!(tx_task.cca, expected_cca);
+
+            // Safety: The buffer was allocated from the given allocator.
+            unsafe { buffer_allocator.deallocate_buffer(tx_task.radio_frame.into_buffer()) };
+        }
+    }
+
+    #[test]
+    fn phy_mode_is_rejected() {
+        let buffer_allocator = mac_buffer_allocator();
+        let mut data_request = DataRequest::new(mpdu(buffer_allocator));
+        data_request.tx_options().set_phy_mode(Some(PhyMode::Oqpsk));
+
+        let task = DataRequestTask::<TestDriverConfig>::new(data_request);
+        let MacTaskTransition::Terminated(DataRequestResult::Rejected(
+            radio_frame,
+            DataError::InvalidParameter,
+        )) = task.step(MacTaskEvent::Entry)
+        else {
+            panic!("expected the request to be rejected");
+        };
+
+        // Safety: The buffer was allocated from the given allocator.
+        unsafe { buffer_allocator.deallocate_buffer(radio_frame.into_buffer()) };
+    }
+
+    #[test]
+    fn cancelled_request_is_rejected() {
+        let buffer_allocator = mac_buffer_allocator();
+        let task =
+            DataRequestTask::<TestDriverConfig>::new(DataRequest::new(mpdu(buffer_allocator)));
+        let MacTaskTransition::DrvSvcRequest(task, DrvSvcRequest::Tx(tx_task), None) =
+            task.step(MacTaskEvent::Entry)
+        else {
+            panic!("expected a TX task");
+        };
+
+        let response = DrvSvcResponse::Tx(Err(DrvSvcTaskError::Task(TxError::Cancelled(
+            tx_task.radio_frame,
+        ))));
+        let MacTaskTransition::Terminated(DataRequestResult::Rejected(
+            radio_frame,
+            DataError::TransactionExpired,
+        )) = task.step(MacTaskEvent::DrvSvcResponse(response))
+        else {
+            panic!("expected the request to be rejected");
+        };
+
+        // Safety: The buffer was allocated from the given allocator.
+        unsafe { buffer_allocator.deallocate_buffer(radio_frame.into_buffer()) };
+    }
+
+    #[test]
+    fn data_indication() {
+        let buffer_allocator = mac_buffer_allocator();
+        let task = DataIndicationTask::<TestDriverConfig>::new(buffer_allocator);
+        let MacTaskTransition::DrvSvcRequest(task, DrvSvcRequest::Rx(rx_task), None) =
+            task.step(MacTaskEvent::Entry)
+        else {
+            panic!("expected an RX task");
+        };
+
+        // Data frame (2006) without addressing fields.
+        let mut radio_frame = rx_task.radio_frame.with_size(NonZero::new(3).unwrap());
+        radio_frame.sdu_mut().copy_from_slice(&[0x41, 0x20, 0x2a]);
+        let mut annotations = FrameAnnotations::new();
+        // Timestamps exceed 32 bits after about 71 minutes.
+        let timestamp = Instant::new(u32::MAX as u64 + 1);
+        annotations.insert(RxMetadata {
+            timestamp: Some(timestamp),
+            lqi: Some(0xff),
+            rssi: Some(-60),
+        });
+        let rx_result = RxResult::Frame(Annotated {
+            frame: radio_frame,
+            annotations,
+        });
+
+        let MacTaskTransition::DrvSvcRequest(_, DrvSvcRequest::Rx(rx_task), Some(indication)) =
+            task.step(MacTaskEvent::DrvSvcResponse(DrvSvcResponse::Rx(Ok(
+                rx_result,
+            ))))
+        else {
+            panic!("expected an indication");
+        };
+        assert_eq!(indication.timestamp, Some(timestamp));
+        assert_eq!(indication.link_quality, Some(LinkQuality::new(-60, 0xff)));
+        assert_eq!(indication.mpdu.pdu_ref_wo_fcs(), [0x41, 0x20, 0x2a]);
+
+        // Safety: The buffers were allocated from the given allocator.
+        unsafe {
+            buffer_allocator.deallocate_buffer(indication.mpdu.into_buffer());
+            buffer_allocator.deallocate_buffer(rx_task.radio_frame.into_buffer());
+        }
+    }
+}
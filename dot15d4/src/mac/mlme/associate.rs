@@ -1,14 +1,1089 @@
+//! Association and disassociation (MLME-ASSOCIATE and MLME-DISASSOCIATE,
+//! IEEE 802.15.4-2020, sections 6.4.1 and 6.4.2).
+//!
+//! A device joins a PAN with [`Associator::associate()`]: it sends an
+//! Association Request command to the coordinator, waits macResponseWaitTime
+//! and then polls the coordinator for the Association Response command that
+//! carries the allocated short address.
+//!
+//! The coordinator answers a request with [`AddressAllocator::respond()`] and
+//! keeps the Association Response in its [`IndirectQueue`] until the device
+//! polls.
+//!
+//! Either side ends the association with a Disassociation Notification
+//! command. A device sends it directly with [`Associator::disassociate()`], a
+//! coordinator queues it for the device like the Association Response.
+//!
+//! Note: Only commands without security and IEs are recognized. Fast
+//! association is not supported.
+//!
+//! [`IndirectQueue`]: crate::mac::indirect::IndirectQueue
+#![allow(dead_code)]
+
+use heapless::Vec;
 use rand_core::RngCore;
 
-use crate::{driver::DriverConfig, mac::MacService};
+use crate::{
+    driver::{
+        constants::{A_BASE_SUPERFRAME_DURATION, PHY_MAX_PACKET_SIZE_127},
+        frame::{Address, ExtendedAddress, FrameControl, FrameType, PanId},
+        radio::Radio,
+        time::{Duration, SymbolsOQpsk250kB},
+        DriverConfig, RadioTimerApi,
+    },
+    mac::{
+        ack::{field, AckFrame, MacHeader},
+        csma::{CsmaConfig, CsmaConfigError},
+        frame::{
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError, FrameErrorKind,
+        },
+        indirect::{data_request_frame, PollStatus, Poller},
+        pib::Pib,
+        radio::MacRadio,
+        retransmission::Retransmissions,
+        sequence::SequenceNumber,
+        MacService,
+    },
+};
+
+/// The command ID of the Association Request command.
+pub const ASSOCIATION_REQUEST_COMMAND_ID: u8 = 0x01;
+
+/// The command ID of the Association Response command.
+pub const ASSOCIATION_RESPONSE_COMMAND_ID: u8 = 0x02;
+
+/// The command ID of the Disassociation Notification command.
+pub const DISASSOCIATION_NOTIFICATION_COMMAND_ID: u8 = 0x03;
+
+/// Default value of macResponseWaitTime in aBaseSuperframeDuration.
+pub const DEFAULT_RESPONSE_WAIT_TIME: u8 = 32;
+
+/// The short address of an associated device that only uses its extended
+/// address.
+pub const SHORT_ADDRESS_NONE: u16 = 0xfffe;
+
+/// The short address of a device that is not associated.
+pub const SHORT_ADDRESS_UNASSIGNED: u16 = 0xffff;
+
+/// The first short address handed out by [`AddressAllocator`].
+const FIRST_SHORT_ADDRESS: u16 = 0x0001;
+
+/// The last short address handed out by [`AddressAllocator`].
+const LAST_SHORT_ADDRESS: u16 = 0xfffd;
+
+/// Return the time a device waits before polling for the response to a
+/// request command.
+///
+/// * `response_wait_time` - macResponseWaitTime in aBaseSuperframeDuration
+pub const fn response_wait_duration(response_wait_time: u8) -> Duration<SymbolsOQpsk250kB> {
+    Duration::new(A_BASE_SUPERFRAME_DURATION.ticks() * response_wait_time as i64)
+}
+
+/// The Capability Information field of the Association Request command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilityInformation {
+    /// Whether the device is an FFD.
+    pub ffd: bool,
+    /// Whether the device is mains powered.
+    pub mains_powered: bool,
+    /// Whether the device keeps its receiver on when idle.
+    pub rx_on_when_idle: bool,
+    /// Whether the device can send and receive secured frames.
+    pub security_capable: bool,
+    /// Whether the device wants the coordinator to allocate a short address.
+    pub allocate_address: bool,
+}
+
+impl CapabilityInformation {
+    /// Decodes the Capability Information field, ignoring reserved bits and
+    /// the association type.
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            ffd: byte & 0x02 != 0,
+            mains_powered: byte & 0x04 != 0,
+            rx_on_when_idle: byte & 0x08 != 0,
+            security_capable: byte & 0x40 != 0,
+            allocate_address: byte & 0x80 != 0,
+        }
+    }
+
+    /// Encodes the Capability Information field.
+    pub fn to_byte(self) -> u8 {
+        [
+            (self.ffd, 0x02),
+            (self.mains_powered, 0x04),
+            (self.rx_on_when_idle, 0x08),
+            (self.security_capable, 0x40),
+            (self.allocate_address, 0x80),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |byte, (_, bit)| byte | bit)
+    }
+}
+
+/// The Association Status field of the Association Response command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociationStatus {
+    Success,
+    PanAtCapacity,
+    PanAccessDenied,
+    HoppingSequenceOffsetDuplication,
+    FastAssociationSuccessful,
+    Reserved(u8),
+}
+
+impl AssociationStatus {
+    /// Decodes the Association Status field.
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x00 => Self::Success,
+            0x01 => Self::PanAtCapacity,
+            0x02 => Self::PanAccessDenied,
+            0x03 => Self::HoppingSequenceOffsetDuplication,
+            0x80 => Self::FastAssociationSuccessful,
+            byte => Self::Reserved(byte),
+        }
+    }
+
+    /// Encodes the Association Status field.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::Success => 0x00,
+            Self::PanAtCapacity => 0x01,
+            Self::PanAccessDenied => 0x02,
+            Self::HoppingSequenceOffsetDuplication => 0x03,
+            Self::FastAssociationSuccessful => 0x80,
+            Self::Reserved(byte) => byte,
+        }
+    }
+}
+
+/// The Disassociation Reason field of the Disassociation Notification
+/// command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisassociateReason {
+    CoordinatorWishesDeviceToLeave,
+    DeviceWishesToLeave,
+    Reserved(u8),
+}
+
+impl DisassociateReason {
+    /// Decodes the Disassociation Reason field.
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x01 => Self::CoordinatorWishesDeviceToLeave,
+            0x02 => Self::DeviceWishesToLeave,
+            byte => Self::Reserved(byte),
+        }
+    }
+
+    /// Encodes the Disassociation Reason field.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::CoordinatorWishesDeviceToLeave => 0x01,
+            Self::DeviceWishesToLeave => 0x02,
+            Self::Reserved(byte) => byte,
+        }
+    }
+}
+
+/// The content of an association related MAC command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociationCommand {
+    Request(CapabilityInformation),
+    Response {
+        short_address: u16,
+        status: AssociationStatus,
+    },
+    DisassociationNotification(DisassociateReason),
+}
+
+/// An association related MAC command received from another device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedCommand {
+    /// The source address of the command (little endian), empty if absent.
+    pub src_address: Vec<u8, 8>,
+    /// The destination address of the command (little endian), empty if
+    /// absent.
+    pub dst_address: Vec<u8, 8>,
+    /// The content of the command.
+    pub command: AssociationCommand,
+}
+
+/// Return the given frame if it is an association related MAC command.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::SecurityNotSupported`] if a MAC command is secured,
+/// - [`FrameErrorKind::IesNotSupported`] if a MAC command contains IEs,
+/// - [`FrameErrorKind::InvalidAddressingCombination`] if the command has no
+///   addressing fields,
+/// - any other error if the frame is truncated.
+pub fn parse_command(mpdu: &[u8]) -> Result<Option<ReceivedCommand>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if frame_control.frame_type() != FrameType::MacCommand {
+        return Ok(None);
+    }
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+    if frame_control.information_elements_present() {
+        return Err(FrameErrorKind::IesNotSupported.into());
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    let content = header.end + 1;
+    let command = match field(mpdu, header.end, 1)?[0] {
+        ASSOCIATION_REQUEST_COMMAND_ID => AssociationCommand::Request(
+            CapabilityInformation::from_byte(field(mpdu, content, 1)?[0]),
+        ),
+        ASSOCIATION_RESPONSE_COMMAND_ID => {
+            let content = field(mpdu, content, 3)?;
+            AssociationCommand::Response {
+                short_address: u16::from_le_bytes([content[0], content[1]]),
+                status: AssociationStatus::from_byte(content[2]),
+            }
+        }
+        DISASSOCIATION_NOTIFICATION_COMMAND_ID => AssociationCommand::DisassociationNotification(
+            DisassociateReason::from_byte(field(mpdu, content, 1)?[0]),
+        ),
+        _ => return Ok(None),
+    };
+
+    let addressing_fields = header
+        .addressing_fields
+        .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+    // Safety: Addresses never exceed 8 bytes.
+    let to_vec = |address: Option<Address<&[u8]>>| {
+        address
+            .map(|address| Vec::from_slice(address.as_le_bytes()).unwrap())
+            .unwrap_or_default()
+    };
+    Ok(Some(ReceivedCommand {
+        src_address: to_vec(addressing_fields.src_address()),
+        dst_address: to_vec(addressing_fields.dst_address()),
+        command,
+    }))
+}
+
+/// Build a MAC command requesting an acknowledgement.
+fn command_frame(
+    seq_nr: u8,
+    dst: (PanId<[u8; 2]>, Address<&[u8]>),
+    src: (PanId<[u8; 2]>, Address<&[u8]>),
+    command: &[u8],
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_ack_request(true)
+        .with_addressing(Some(dst), Some(src))
+        .without_security()
+        .without_ies()
+        .with_payload(command);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Build an Association Request command.
+///
+/// The source PAN ID is the broadcast PAN ID as the device is not part of a
+/// PAN yet.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `coordinator` - PAN ID and address of the coordinator
+/// * `device` - Extended address of the device
+/// * `capability_information` - Capabilities of the device
+pub fn association_request_frame(
+    seq_nr: u8,
+    coordinator: (PanId<[u8; 2]>, Address<&[u8]>),
+    device: &[u8; 8],
+    capability_information: CapabilityInformation,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    command_frame(
+        seq_nr,
+        coordinator,
+        (
+            PanId::from_u16(0xffff),
+            Address::Extended(ExtendedAddress::new(&device[..])),
+        ),
+        &[
+            ASSOCIATION_REQUEST_COMMAND_ID,
+            capability_information.to_byte(),
+        ],
+    )
+}
+
+/// Build an Association Response command.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `pan_id` - PAN ID of the coordinator
+/// * `device` - Extended address of the requesting device
+/// * `coordinator` - Extended address of the coordinator
+/// * `short_address` - Short address allocated to the device
+/// * `status` - Outcome of the association
+pub fn association_response_frame(
+    seq_nr: u8,
+    pan_id: PanId<[u8; 2]>,
+    device: &[u8; 8],
+    coordinator: &[u8; 8],
+    short_address: u16,
+    status: AssociationStatus,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let [low, high] = short_address.to_le_bytes();
+    command_frame(
+        seq_nr,
+        (pan_id, Address::Extended(ExtendedAddress::new(&device[..]))),
+        (
+            pan_id,
+            Address::Extended(ExtendedAddress::new(&coordinator[..])),
+        ),
+        &[ASSOCIATION_RESPONSE_COMMAND_ID, low, high, status.to_byte()],
+    )
+}
+
+/// Build a Disassociation Notification command.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `dst` - PAN ID and address of the coordinator or device to notify
+/// * `src` - Extended address of the notifying coordinator or device
+/// * `reason` - The reason of the disassociation
+pub fn disassociation_notification_frame(
+    seq_nr: u8,
+    dst: (PanId<[u8; 2]>, Address<&[u8]>),
+    src: &[u8; 8],
+    reason: DisassociateReason,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let pan_id = dst.0;
+    command_frame(
+        seq_nr,
+        dst,
+        (pan_id, Address::Extended(ExtendedAddress::new(&src[..]))),
+        &[DISASSOCIATION_NOTIFICATION_COMMAND_ID, reason.to_byte()],
+    )
+}
+
+/// Represents an MLME-ASSOCIATE.request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssociateRequest<'a> {
+    /// PAN ID and address of the coordinator, e.g. from a
+    /// [`PanDescriptor`](super::scan::PanDescriptor).
+    pub coordinator: (PanId<[u8; 2]>, Address<&'a [u8]>),
+    /// The extended address of the device.
+    pub device_address: [u8; 8],
+    /// The capabilities of the device.
+    pub capability_information: CapabilityInformation,
+}
+
+/// Represents an MLME-ASSOCIATE.confirm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssociateConfirm {
+    /// The short address allocated by the coordinator,
+    /// [`SHORT_ADDRESS_NONE`] if the device shall use its extended address.
+    pub assoc_short_address: u16,
+    /// The outcome reported by the coordinator.
+    pub status: AssociationStatus,
+    /// The extended address of the coordinator.
+    pub coord_extended_address: [u8; 8],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociateError {
+    // TODO: not supported
+    ChannelAccessFailure,
+    NoAck,
+    NoData,
+    // TODO: not supported
+    CounterError,
+    // TODO: not supported
+    FrameTooLong,
+    InvalidParameter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisassociateError {
+    // TODO: not supported
+    ChannelAccessFailure,
+    NoAck,
+    InvalidParameter,
+}
+
+/// Runs the device side of association and disassociation, see the module
+/// documentation.
+pub struct Associator<Timer: RadioTimerApi> {
+    retransmissions: Retransmissions<Timer>,
+    poller: Poller<Timer>,
+    /// macResponseWaitTime
+    response_wait: Duration<Timer>,
+}
+
+impl<Timer: RadioTimerApi> Associator<Timer> {
+    /// Creates a new [`Associator`].
+    ///
+    /// * `config` - The configuration providing macMaxFrameRetries, the
+    ///   interframe spaces and macMaxFrameTotalWaitTime
+    /// * `response_wait_time` - macResponseWaitTime in aBaseSuperframeDuration
+    pub fn new(config: &CsmaConfig, response_wait_time: u8) -> Result<Self, CsmaConfigError> {
+        Ok(Self {
+            retransmissions: Retransmissions::new(config)?,
+            poller: Poller::new(config)?,
+            response_wait: response_wait_duration(response_wait_time).convert_into_rounding_up(),
+        })
+    }
 
-pub struct AssociateConfirm;
+    /// Associates with the given coordinator.
+    ///
+    /// Refusals of the coordinator are reported in
+    /// [`AssociateConfirm::status`].
+    ///
+    /// * `radio` - The radio transmitting the commands
+    /// * `request` - The coordinator and the capabilities of the device
+    /// * `dsn` - macDsn, used to number the commands
+    pub async fn associate<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        request: &AssociateRequest<'_>,
+        dsn: &mut SequenceNumber,
+    ) -> Result<AssociateConfirm, AssociateError> {
+        if request.coordinator.1.is_absent() {
+            return Err(AssociateError::InvalidParameter);
+        }
+
+        let association_request = association_request_frame(
+            dsn.next(),
+            request.coordinator,
+            &request.device_address,
+            request.capability_information,
+        )
+        .map_err(|_| AssociateError::InvalidParameter)?;
+        let report = self
+            .retransmissions
+            .transmit(radio, &association_request)
+            .await
+            .map_err(|_| AssociateError::InvalidParameter)?;
+        if !report.is_success() {
+            return Err(AssociateError::NoAck);
+        }
+
+        // The coordinator needs time to decide and queue the response.
+        let poll_at = Timer::now() + self.response_wait;
+        if poll_at > Timer::now() {
+            Timer::wait_for_alarm_at(poll_at).await;
+        }
+
+        let data_request = data_request_frame(
+            dsn.next(),
+            request.coordinator,
+            Address::Extended(ExtendedAddress::new(&request.device_address[..])),
+        )
+        .map_err(|_| AssociateError::InvalidParameter)?;
+        let mut frame = AckFrame::new();
+        let status = self
+            .poller
+            .poll(radio, &data_request, &mut frame)
+            .await
+            .map_err(|_| AssociateError::InvalidParameter)?;
+        match status {
+            PollStatus::Data(_) => {}
+            PollStatus::NoData => return Err(AssociateError::NoData),
+            PollStatus::NoAck => return Err(AssociateError::NoAck),
+        }
+
+        match parse_command(&frame) {
+            Ok(Some(ReceivedCommand {
+                src_address,
+                command:
+                    AssociationCommand::Response {
+                        short_address,
+                        status,
+                    },
+                ..
+            })) => match src_address.into_array() {
+                Ok(coord_extended_address) => Ok(AssociateConfirm {
+                    assoc_short_address: short_address,
+                    status,
+                    coord_extended_address,
+                }),
+                Err(_) => Err(AssociateError::NoData),
+            },
+            _ => Err(AssociateError::NoData),
+        }
+    }
+
+    /// Notifies the coordinator that the device leaves the PAN.
+    ///
+    /// * `radio` - The radio transmitting the command
+    /// * `coordinator` - PAN ID and address of the coordinator
+    /// * `device` - Extended address of the device
+    /// * `dsn` - macDsn, used to number the command
+    pub async fn disassociate<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        coordinator: (PanId<[u8; 2]>, Address<&[u8]>),
+        device: &[u8; 8],
+        dsn: &mut SequenceNumber,
+    ) -> Result<(), DisassociateError> {
+        let notification = disassociation_notification_frame(
+            dsn.next(),
+            coordinator,
+            device,
+            DisassociateReason::DeviceWishesToLeave,
+        )
+        .map_err(|_| DisassociateError::InvalidParameter)?;
+        let report = self
+            .retransmissions
+            .transmit(radio, &notification)
+            .await
+            .map_err(|_| DisassociateError::InvalidParameter)?;
+        if report.is_success() {
+            Ok(())
+        } else {
+            Err(DisassociateError::NoAck)
+        }
+    }
+}
+
+/// Allocates short addresses to up to `N` associated devices.
+///
+/// Addresses are handed out round robin from 0x0001 to 0xfffd so that the
+/// address of a device that left is not reused right away. A device that
+/// associates again keeps its address.
+#[derive(Debug)]
+pub struct AddressAllocator<const N: usize> {
+    /// Extended address (little endian) and short address of each device.
+    devices: Vec<(Vec<u8, 8>, u16), N>,
+    /// The next short address to try.
+    next: u16,
+}
+
+impl<const N: usize> Default for AddressAllocator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AddressAllocator<N> {
+    /// Creates a new [`AddressAllocator`] without associated devices.
+    pub const fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            next: FIRST_SHORT_ADDRESS,
+        }
+    }
+
+    /// Decide on the Association Request of the given device.
+    ///
+    /// Returns the short address and status to send in the Association
+    /// Response.
+    ///
+    /// * `device` - Extended address of the device (little endian)
+    /// * `capability_information` - Capabilities of the device
+    /// * `association_permit` - macAssociationPermit
+    pub fn respond(
+        &mut self,
+        device: &[u8],
+        capability_information: CapabilityInformation,
+        association_permit: bool,
+    ) -> (u16, AssociationStatus) {
+        if !association_permit {
+            return (SHORT_ADDRESS_UNASSIGNED, AssociationStatus::PanAccessDenied);
+        }
+        if !capability_information.allocate_address {
+            return (SHORT_ADDRESS_NONE, AssociationStatus::Success);
+        }
+        match self.allocate(device) {
+            Some(short_address) => (short_address, AssociationStatus::Success),
+            None => (SHORT_ADDRESS_UNASSIGNED, AssociationStatus::PanAtCapacity),
+        }
+    }
+
+    /// Allocate a short address to the given device.
+    ///
+    /// Returns `None` if all addresses are taken.
+    ///
+    /// * `device` - Extended address of the device (little endian)
+    pub fn allocate(&mut self, device: &[u8]) -> Option<u16> {
+        if let Some(short_address) = self.short_address(device) {
+            return Some(short_address);
+        }
+        if self.devices.is_full() {
+            return None;
+        }
+        let device = Vec::from_slice(device).ok()?;
+
+        let mut short_address = self.next;
+        while self
+            .devices
+            .iter()
+            .any(|(_, taken)| *taken == short_address)
+        {
+            short_address = Self::following(short_address);
+        }
+        self.next = Self::following(short_address);
+        // Safety: We checked that the table is not full.
+        let _ = self.devices.push((device, short_address));
+        Some(short_address)
+    }
+
+    /// Release the short address of the given device, e.g. upon its
+    /// Disassociation Notification.
+    ///
+    /// * `device` - Extended address of the device (little endian)
+    pub fn release(&mut self, device: &[u8]) -> Option<u16> {
+        let index = self
+            .devices
+            .iter()
+            .position(|(address, _)| **address == *device)?;
+        Some(self.devices.remove(index).1)
+    }
+
+    /// Return the short address allocated to the given device.
+    ///
+    /// * `device` - Extended address of the device (little endian)
+    pub fn short_address(&self, device: &[u8]) -> Option<u16> {
+        self.devices
+            .iter()
+            .find(|(address, _)| **address == *device)
+            .map(|(_, short_address)| *short_address)
+    }
+
+    /// Return the number of associated devices with a short address.
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Return whether no device has a short address.
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    fn following(short_address: u16) -> u16 {
+        if short_address >= LAST_SHORT_ADDRESS {
+            FIRST_SHORT_ADDRESS
+        } else {
+            short_address + 1
+        }
+    }
+}
+
+impl Pib {
+    /// Record the association confirmed by the coordinator.
+    pub(crate) fn set_association(
+        &mut self,
+        request: &AssociateRequest<'_>,
+        confirm: &AssociateConfirm,
+    ) {
+        self.pan_id = request.coordinator.0;
+        self.short_address = confirm.assoc_short_address;
+        self.coord_extended_address = Some(confirm.coord_extended_address);
+        self.coord_short_address = match *request.coordinator.1.as_le_bytes() {
+            [low, high] => u16::from_le_bytes([low, high]),
+            _ => SHORT_ADDRESS_UNASSIGNED,
+        };
+        self.touch();
+    }
+
+    /// Forget the association after a disassociation.
+    pub(crate) fn clear_association(&mut self) {
+        self.pan_id = PanId::from_u16(0xffff);
+        self.short_address = SHORT_ADDRESS_UNASSIGNED;
+        self.coord_extended_address = None;
+        self.coord_short_address = SHORT_ADDRESS_UNASSIGNED;
+        self.associated_pan_coord = false;
+        self.touch();
+    }
+}
 
-#[allow(dead_code)]
 impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
-    /// Requests the association with a coordinator.
-    async fn mlme_associate_request(&self) -> Result<AssociateConfirm, ()> {
-        // TODO: support association
-        Err(())
+    /// Requests the association with a coordinator, see
+    /// [`Associator::associate()`].
+    ///
+    /// The association needs the radio for itself, i.e. a [`MacRadio`] that
+    /// is not shared with the driver service meanwhile. Commands are numbered
+    /// with macDsn. A successful association is recorded in the PIB.
+    ///
+    /// * `radio` - The radio transmitting the commands
+    /// * `request` - The coordinator and the capabilities of the device
+    pub(crate) async fn mlme_associate_request<R: Radio<Timer = RadioDriverImpl::Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        request: &AssociateRequest<'_>,
+    ) -> Result<AssociateConfirm, AssociateError> {
+        // The PIB must not stay borrowed while associating.
+        let (associator, mut dsn) = {
+            let pib = self.pib.borrow();
+            let associator = Associator::new(pib.csma(), pib.response_wait_time)
+                .map_err(|_| AssociateError::InvalidParameter)?;
+            (associator, pib.dsn)
+        };
+        let result = associator.associate(radio, request, &mut dsn).await;
+
+        let mut pib = self.pib.borrow_mut();
+        pib.dsn = dsn;
+        if let Ok(confirm) = &result {
+            if confirm.status == AssociationStatus::Success {
+                pib.set_association(request, confirm);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{frame::ShortAddress, test_clock::TestClock, time::Instant},
+        mac::{
+            indirect::IndirectQueue,
+            test_helpers::{with_mac_service, ScriptedAckRadio},
+        },
+    };
+
+    const PAN_ID: u16 = 0xabcd;
+    const COORDINATOR: [u8; 2] = [0x00, 0x00];
+    const COORDINATOR_EXTENDED: [u8; 8] = [0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18];
+    const DEVICE: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    fn coordinator() -> (PanId<[u8; 2]>, Address<&'static [u8]>) {
+        (
+            PanId::from_u16(PAN_ID),
+            Address::Short(ShortAddress::new(&COORDINATOR[..])),
+        )
+    }
+
+    fn request() -> AssociateRequest<'static> {
+        AssociateRequest {
+            coordinator: coordinator(),
+            device_address: DEVICE,
+            capability_information: CapabilityInformation {
+                allocate_address: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn response(seq_nr: u8) -> FrameBuffer<PHY_MAX_PACKET_SIZE_127> {
+        association_response_frame(
+            seq_nr,
+            PanId::from_u16(PAN_ID),
+            &DEVICE,
+            &COORDINATOR_EXTENDED,
+            0x0001,
+            AssociationStatus::Success,
+        )
+        .unwrap()
+    }
+
+    /// A frame received 100 ticks after the radio started listening.
+    fn rx(mpdu: &[u8]) -> Option<(i64, &[u8])> {
+        Some((100, mpdu))
+    }
+
+    /// Run the given future, advancing the clock to each alarm.
+    #[test]
+    fn capability_information() {
+        let capability_information = CapabilityInformation::from_byte(0x8e);
+        assert_eq!(
+            capability_information,
+            CapabilityInformation {
+                ffd: true,
+                mains_powered: true,
+                rx_on_when_idle: true,
+                security_capable: false,
+                allocate_address: true,
+            }
+        );
+        assert_eq!(capability_information.to_byte(), 0x8e);
+        // Reserved bits and the association type are ignored.
+        assert_eq!(CapabilityInformation::from_byte(0x31).to_byte(), 0x00);
+    }
+
+    #[test]
+    fn association_commands() {
+        let mpdu = association_request_frame(
+            5,
+            coordinator(),
+            &DEVICE,
+            CapabilityInformation::from_byte(0x8e),
+        )
+        .unwrap();
+        assert_eq!(
+            mpdu[..],
+            [
+                0x23, 0xd8, 0x05, 0xcd, 0xab, 0x00, 0x00, 0xff, 0xff, 0x08, 0x07, 0x06, 0x05, 0x04,
+                0x03, 0x02, 0x01, 0x01, 0x8e
+            ]
+        );
+        let command = parse_command(&mpdu).unwrap().unwrap();
+        assert_eq!(command.src_address[..], DEVICE);
+        assert_eq!(command.dst_address[..], COORDINATOR);
+        assert_eq!(
+            command.command,
+            AssociationCommand::Request(CapabilityInformation::from_byte(0x8e))
+        );
+
+        let mpdu = response(6);
+        assert_eq!(mpdu[..2], [0x63, 0xdc]);
+        assert_eq!(mpdu[mpdu.len() - 4..], [0x02, 0x01, 0x00, 0x00]);
+        let command = parse_command(&mpdu).unwrap().unwrap();
+        assert_eq!(command.src_address[..], COORDINATOR_EXTENDED);
+        assert_eq!(command.dst_address[..], DEVICE);
+        assert_eq!(
+            command.command,
+            AssociationCommand::Response {
+                short_address: 0x0001,
+                status: AssociationStatus::Success
+            }
+        );
+        assert!(parse_command(&mpdu[..mpdu.len() - 1]).is_err());
+
+        let mpdu = disassociation_notification_frame(
+            7,
+            coordinator(),
+            &DEVICE,
+            DisassociateReason::DeviceWishesToLeave,
+        )
+        .unwrap();
+        assert_eq!(mpdu[mpdu.len() - 2..], [0x03, 0x02]);
+        assert_eq!(
+            parse_command(&mpdu).unwrap().unwrap().command,
+            AssociationCommand::DisassociationNotification(DisassociateReason::DeviceWishesToLeave)
+        );
+
+        // Other commands and frame types.
+        let mut mpdu = mpdu;
+        let len = mpdu.len();
+        mpdu.as_mut_capacity()[len - 2] = 0x04;
+        assert_eq!(parse_command(&mpdu), Ok(None));
+        assert_eq!(parse_command(&[0x02, 0x10, 0x07]), Ok(None));
+    }
+
+    #[test]
+    fn address_allocator() {
+        let mut allocator = AddressAllocator::<2>::new();
+        let capability_information = CapabilityInformation {
+            allocate_address: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            allocator.respond(&DEVICE, capability_information, false),
+            (SHORT_ADDRESS_UNASSIGNED, AssociationStatus::PanAccessDenied)
+        );
+        assert_eq!(
+            allocator.respond(&DEVICE, Default::default(), true),
+            (SHORT_ADDRESS_NONE, AssociationStatus::Success)
+        );
+        assert!(allocator.is_empty());
+
+        assert_eq!(
+            allocator.respond(&DEVICE, capability_information, true),
+            (0x0001, AssociationStatus::Success)
+        );
+        // A device associating again keeps its address.
+        assert_eq!(allocator.allocate(&DEVICE), Some(0x0001));
+        assert_eq!(allocator.allocate(&COORDINATOR_EXTENDED), Some(0x0002));
+        assert_eq!(
+            allocator.respond(&[0x01; 8], capability_information, true),
+            (SHORT_ADDRESS_UNASSIGNED, AssociationStatus::PanAtCapacity)
+        );
+
+        // Released addresses are not reused right away.
+        assert_eq!(allocator.release(&DEVICE), Some(0x0001));
+        assert_eq!(allocator.release(&DEVICE), None);
+        assert_eq!(allocator.allocate(&[0x01; 8]), Some(0x0003));
+        assert_eq!(allocator.short_address(&COORDINATOR_EXTENDED), Some(0x0002));
+        assert_eq!(allocator.len(), 2);
+
+        // Addresses wrap around.
+        allocator.next = LAST_SHORT_ADDRESS;
+        allocator.release(&[0x01; 8]);
+        assert_eq!(allocator.allocate(&DEVICE), Some(LAST_SHORT_ADDRESS));
+        allocator.release(&DEVICE);
+        assert_eq!(allocator.allocate(&DEVICE), Some(0x0001));
+    }
+
+    #[test]
+    fn coordinator_queues_response() {
+        let mut queue = IndirectQueue::<_, TestClock, 2>::default();
+        let mut allocator = AddressAllocator::<2>::new();
+        let mpdu =
+            association_request_frame(5, coordinator(), &DEVICE, request().capability_information)
+                .unwrap();
+        let ReceivedCommand {
+            src_address,
+            command: AssociationCommand::Request(capability_information),
+            ..
+        } = parse_command(&mpdu).unwrap().unwrap()
+        else {
+            panic!("expected an Association Request");
+        };
+
+        let (short_address, status) = allocator.respond(&src_address, capability_information, true);
+        let response = association_response_frame(
+            9,
+            PanId::from_u16(PAN_ID),
+            &DEVICE,
+            &COORDINATOR_EXTENDED,
+            short_address,
+            status,
+        )
+        .unwrap();
+        queue
+            .enqueue(&src_address, 0, response, Instant::new(0))
+            .unwrap();
+
+        // The ACK to the Data Request of the device announces the response.
+        let data_request = data_request_frame(
+            6,
+            coordinator(),
+            Address::Extended(ExtendedAddress::new(&DEVICE[..])),
+        )
+        .unwrap();
+        let ack = queue.ack_frame(&data_request, None).unwrap().unwrap();
+        assert!(ack.frame_control().unwrap().frame_pending());
+        let (response, _) = queue.poll(&DEVICE).unwrap();
+        assert_eq!(response[..], self::response(9)[..]);
+    }
+
+    #[test]
+    fn associate() {
+        TestClock::reset();
+        let associator = Associator::<TestClock>::new(&CsmaConfig::default(), 1).unwrap();
+        let response = response(9);
+        let rx = [
+            rx(&[0x02, 0x10, 0x05]),
+            rx(&[0x12, 0x10, 0x06]),
+            rx(&response),
+        ];
+        let mut radio = ScriptedAckRadio::new(&rx);
+        let mut dsn = SequenceNumber::new(5);
+        let confirm =
+            TestClock::block_on(associator.associate(&mut radio, &request(), &mut dsn)).unwrap();
+        assert_eq!(
+            confirm,
+            AssociateConfirm {
+                assoc_short_address: 0x0001,
+                status: AssociationStatus::Success,
+                coord_extended_address: COORDINATOR_EXTENDED,
+            }
+        );
+        assert_eq!(radio.radio_mut().transmitted[..], [0x05, 0x06]);
+        assert_eq!(dsn.value(), 0x07);
+        // The Data Request is sent macResponseWaitTime after the ACK: 960
+        // symbols of 16µs each.
+        assert_eq!(TestClock::now(), Instant::new(600 + 15_360 + 600 + 100));
+
+        let mut pib = Pib::default();
+        pib.set_association(&request(), &confirm);
+        assert_eq!(pib.pan_id.into_u16(), PAN_ID);
+        assert_eq!(pib.short_address, 0x0001);
+        assert_eq!(pib.coord_short_address, 0x0000);
+        assert_eq!(pib.coord_extended_address, Some(COORDINATOR_EXTENDED));
+        pib.clear_association();
+        assert_eq!(pib.short_address, SHORT_ADDRESS_UNASSIGNED);
+        assert_eq!(pib.coord_extended_address, None);
+    }
+
+    #[test]
+    fn associate_fails() {
+        let associator = Associator::<TestClock>::new(&CsmaConfig::default(), 1).unwrap();
+        let mut dsn = SequenceNumber::new(5);
+
+        TestClock::reset();
+        let mut radio = ScriptedAckRadio::new(&[]);
+        assert_eq!(
+            TestClock::block_on(associator.associate(&mut radio, &request(), &mut dsn)),
+            Err(AssociateError::NoAck)
+        );
+
+        // The coordinator has no response pending.
+        TestClock::reset();
+        let rx = [rx(&[0x02, 0x10, 0x05]), rx(&[0x02, 0x10, 0x06])];
+        let mut radio = ScriptedAckRadio::new(&rx);
+        let mut dsn = SequenceNumber::new(5);
+        assert_eq!(
+            TestClock::block_on(associator.associate(&mut radio, &request(), &mut dsn)),
+            Err(AssociateError::NoData)
+        );
+
+        let mut request = request();
+        request.coordinator.1 = Address::Absent;
+        assert_eq!(
+            TestClock::block_on(associator.associate(&mut radio, &request, &mut dsn)),
+            Err(AssociateError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn mlme_associate_request() {
+        with_mac_service(5, |mac_service| {
+            TestClock::reset();
+            let response = response(9);
+            let rx = [
+                rx(&[0x02, 0x10, 0x05]),
+                rx(&[0x12, 0x10, 0x06]),
+                rx(&response),
+            ];
+            let mut radio = ScriptedAckRadio::new(&rx);
+            let confirm =
+                TestClock::block_on(mac_service.mlme_associate_request(&mut radio, &request()))
+                    .unwrap();
+            assert_eq!(confirm.assoc_short_address, 0x0001);
+            // The commands are numbered with macDsn.
+            assert_eq!(radio.radio_mut().transmitted[..], [0x05, 0x06]);
+
+            let pib = mac_service.pib.borrow();
+            assert_eq!(pib.dsn.value(), 0x07);
+            assert_eq!(pib.pan_id.into_u16(), PAN_ID);
+            assert_eq!(pib.short_address, 0x0001);
+            assert_eq!(pib.coord_extended_address, Some(COORDINATOR_EXTENDED));
+        });
+
+        // Failed associations are not recorded.
+        with_mac_service(5, |mac_service| {
+            TestClock::reset();
+            let mut radio = ScriptedAckRadio::new(&[]);
+            assert_eq!(
+                TestClock::block_on(mac_service.mlme_associate_request(&mut radio, &request())),
+                Err(AssociateError::NoAck)
+            );
+            let pib = mac_service.pib.borrow();
+            assert_eq!(pib.dsn.value(), 0x06);
+            assert_eq!(pib.short_address, SHORT_ADDRESS_UNASSIGNED);
+        });
+    }
+
+    #[test]
+    fn disassociate() {
+        let associator = Associator::<TestClock>::new(&CsmaConfig::default(), 1).unwrap();
+        let mut dsn = SequenceNumber::new(5);
+
+        TestClock::reset();
+        let rx = [rx(&[0x02, 0x10, 0x05])];
+        let mut radio = ScriptedAckRadio::new(&rx);
+        assert_eq!(
+            TestClock::block_on(associator.disassociate(
+                &mut radio,
+                coordinator(),
+                &DEVICE,
+                &mut dsn
+            )),
+            Ok(())
+        );
+
+        TestClock::reset();
+        let mut radio = ScriptedAckRadio::new(&[]);
+        assert_eq!(
+            TestClock::block_on(associator.disassociate(
+                &mut radio,
+                coordinator(),
+                &DEVICE,
+                &mut dsn
+            )),
+            Err(DisassociateError::NoAck)
+        );
     }
 }
@@ -0,0 +1,29 @@
+#![allow(dead_code)]
+use rand_core::RngCore;
+
+use crate::{
+    driver::DriverConfig,
+    mac::{
+        pib::{PibAttribute, PibAttributeId},
+        MacService,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GetError {
+    UnsupportedAttribute,
+}
+
+#[allow(dead_code)]
+impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
+    /// Used by the next higher layer to read the value of the indicated MAC
+    /// PIB attribute.
+    ///
+    /// * `attribute` - Attribute to read
+    pub(crate) async fn mlme_get_request(
+        &self,
+        attribute: PibAttributeId,
+    ) -> Result<PibAttribute, GetError> {
+        self.pib.borrow().get(attribute)
+    }
+}
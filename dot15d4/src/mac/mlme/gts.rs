@@ -0,0 +1,956 @@
+//! Guaranteed time slots in beacon-enabled PANs (MLME-GTS, IEEE
+//! 802.15.4-2020, section 6.8).
+//!
+//! A device requests a GTS by sending a GTS Request command to the PAN
+//! coordinator, see [`gts_request_frame()`]. The PAN coordinator decides with
+//! [`GtsAllocator::handle_request()`] and announces the outcome in the GTS
+//! list of its next aGTSDescPersistenceTime beacons: a descriptor with a
+//! starting slot of zero denies the request, any other descriptor allocates
+//! the GTS. GTSs are allocated at the end of the superframe, i.e. the
+//! contention free period (CFP) grows towards the contention access period
+//! (CAP), which never shrinks below aMinCapLength.
+//!
+//! The PAN coordinator deallocates GTSs that were not used for 2 * n
+//! superframes, see [`GtsAllocator::on_superframe_end()`]. Gaps left by
+//! deallocated GTSs are closed by moving the remaining GTSs towards the end
+//! of the superframe. Moved GTSs are announced again so that their owners
+//! learn about the new starting slot.
+//!
+//! A device tracks its GTSs with [`DeviceGts`], which evaluates the GTS list
+//! of each beacon, and transmits within a GTS only if the transaction fits
+//! into the [`GtsWindow`].
+//!
+//! Note: Only commands without security and IEs are recognized.
+#![allow(dead_code)]
+
+use heapless::Vec;
+use rand_core::RngCore;
+
+use crate::{
+    driver::{
+        constants::{
+            A_GTS_DESC_PERSISTENCE_TIME, A_MIN_CAP_LENGTH, A_NUM_SUPERFRAME_SLOTS,
+            PHY_MAX_PACKET_SIZE_127,
+        },
+        frame::{Address, FrameControl, FrameType, PanId, ShortAddress},
+        radio::Radio,
+        time::{Duration, Frequency, Instant},
+        DriverConfig,
+    },
+    mac::{
+        ack::{field, MacHeader},
+        frame::{
+            fields::{GtsDescriptor, GtsDirection, GtsFields, MAX_GTS_DESCRIPTORS},
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError, FrameErrorKind,
+        },
+        radio::MacRadio,
+        retransmission::Retransmissions,
+        superframe::{slot_duration, Superframe},
+        MacService,
+    },
+};
+
+use super::associate::{SHORT_ADDRESS_NONE, SHORT_ADDRESS_UNASSIGNED};
+
+/// The command ID of the GTS Request command.
+pub const GTS_REQUEST_COMMAND_ID: u8 = 0x09;
+
+/// The max length of a GTS in superframe slots.
+pub const MAX_GTS_LENGTH: u8 = 15;
+
+/// Return the number of superframes after which an unused GTS expires, i.e.
+/// 2 * n with n = 2^(8 - macBeaconOrder) for beacon orders up to 8, n = 1
+/// otherwise.
+///
+/// * `beacon_order` - macBeaconOrder
+pub const fn gts_expiration_superframes(beacon_order: u8) -> u32 {
+    if beacon_order <= 8 {
+        2 << (8 - beacon_order)
+    } else {
+        2
+    }
+}
+
+/// The GTS Characteristics field of the GTS Request command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GtsCharacteristics {
+    /// The number of superframe slots requested.
+    pub length: u8,
+    /// The direction of the GTS as seen from the device.
+    pub direction: GtsDirection,
+    /// Whether the GTS is requested (`true`) or released (`false`).
+    pub allocation: bool,
+}
+
+impl GtsCharacteristics {
+    /// Decodes the GTS Characteristics field, ignoring reserved bits.
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            length: byte & 0x0f,
+            direction: if byte & 0x10 != 0 {
+                GtsDirection::Receive
+            } else {
+                GtsDirection::Transmit
+            },
+            allocation: byte & 0x20 != 0,
+        }
+    }
+
+    /// Encodes the GTS Characteristics field.
+    pub fn to_byte(self) -> u8 {
+        let mut byte = self.length & 0x0f;
+        if self.direction == GtsDirection::Receive {
+            byte |= 0x10;
+        }
+        if self.allocation {
+            byte |= 0x20;
+        }
+        byte
+    }
+}
+
+/// Build a GTS Request command.
+///
+/// The command is sent to the PAN coordinator without destination address.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `pan_id` - macPanId
+/// * `short_address` - macShortAddress of the device
+/// * `characteristics` - The requested or released GTS
+pub fn gts_request_frame(
+    seq_nr: u8,
+    pan_id: PanId<[u8; 2]>,
+    short_address: u16,
+    characteristics: GtsCharacteristics,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let short_address = short_address.to_le_bytes();
+    let payload = [GTS_REQUEST_COMMAND_ID, characteristics.to_byte()];
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_ack_request(true)
+        .with_addressing(
+            None,
+            Some((
+                pan_id,
+                Address::Short(ShortAddress::new(&short_address[..])),
+            )),
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&payload);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Return the source short address and GTS characteristics if the given frame
+/// is a GTS Request command.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::SecurityNotSupported`] if a MAC command is secured,
+/// - [`FrameErrorKind::IesNotSupported`] if a MAC command contains IEs,
+/// - [`FrameErrorKind::InvalidAddressingCombination`] if the GTS Request has
+///   no short source address,
+/// - any other error if the frame is truncated.
+pub fn parse_gts_request(mpdu: &[u8]) -> Result<Option<(u16, GtsCharacteristics)>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if frame_control.frame_type() != FrameType::MacCommand {
+        return Ok(None);
+    }
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+    if frame_control.information_elements_present() {
+        return Err(FrameErrorKind::IesNotSupported.into());
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    if field(mpdu, header.end, 1)?[0] != GTS_REQUEST_COMMAND_ID {
+        return Ok(None);
+    }
+    let characteristics = GtsCharacteristics::from_byte(field(mpdu, header.end + 1, 1)?[0]);
+    match header
+        .addressing_fields
+        .and_then(|addressing_fields| addressing_fields.into_src_address())
+    {
+        Some(Address::Short(short_address)) => {
+            Ok(Some((short_address.into_u16(), characteristics)))
+        }
+        _ => Err(FrameErrorKind::InvalidAddressingCombination.into()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GtsError {
+    /// The PAN coordinator denied the request.
+    Denied,
+    /// The device has no short address.
+    NoShortAddress,
+    /// No GTS descriptor was received within aGTSDescPersistenceTime
+    /// superframes.
+    NoData,
+    // TODO: not supported
+    ChannelAccessFailure,
+    /// The PAN coordinator did not acknowledge the GTS Request command.
+    NoAck,
+    InvalidParameter,
+}
+
+/// Return whether both descriptors refer to the same GTS, i.e. the GTS of the
+/// same device and direction.
+fn same_gts(a: &GtsDescriptor, b: &GtsDescriptor) -> bool {
+    a.short_address == b.short_address && a.direction == b.direction
+}
+
+/// A GTS allocated by the PAN coordinator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AllocatedGts {
+    descriptor: GtsDescriptor,
+    /// The number of superframes since the GTS was last used.
+    idle_superframes: u32,
+}
+
+/// A GTS descriptor included in the next beacons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Announcement {
+    descriptor: GtsDescriptor,
+    /// The number of beacons that will still include the descriptor.
+    remaining: u8,
+}
+
+/// Allocates the GTSs of a PAN coordinator, see the module documentation.
+pub struct GtsAllocator {
+    /// Allocated GTSs ordered by descending starting slot.
+    allocated: Vec<AllocatedGts, MAX_GTS_DESCRIPTORS>,
+    announcements: Vec<Announcement, MAX_GTS_DESCRIPTORS>,
+}
+
+impl Default for GtsAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GtsAllocator {
+    /// Creates a new [`GtsAllocator`] without GTSs.
+    pub const fn new() -> Self {
+        Self {
+            allocated: Vec::new(),
+            announcements: Vec::new(),
+        }
+    }
+
+    /// Return the final superframe slot of the CAP.
+    pub fn final_cap_slot(&self) -> u8 {
+        self.allocated
+            .last()
+            .map_or(A_NUM_SUPERFRAME_SLOTS - 1, |gts| {
+                gts.descriptor.starting_slot - 1
+            })
+    }
+
+    /// Handle the GTS Request of the given device.
+    ///
+    /// Returns the descriptor to announce in the next beacons, `None` if the
+    /// request released a GTS or was ignored.
+    ///
+    /// * `device` - Short address of the device
+    /// * `characteristics` - The requested or released GTS
+    /// * `gts_permit` - macGtsPermit
+    /// * `superframe_order` - macSuperframeOrder
+    pub fn handle_request(
+        &mut self,
+        device: u16,
+        characteristics: GtsCharacteristics,
+        gts_permit: bool,
+        superframe_order: u8,
+    ) -> Option<GtsDescriptor> {
+        if device == SHORT_ADDRESS_NONE || device == SHORT_ADDRESS_UNASSIGNED {
+            return None;
+        }
+        if !characteristics.allocation {
+            self.deallocate(device, characteristics.direction);
+            return None;
+        }
+        if self.find(device, characteristics.direction).is_some() {
+            return None;
+        }
+
+        let mut descriptor = GtsDescriptor {
+            short_address: ShortAddress::from_u16(device),
+            starting_slot: 0,
+            length: characteristics.length,
+            direction: characteristics.direction,
+        };
+        let starting_slot = (self.final_cap_slot() + 1).saturating_sub(characteristics.length);
+        // The CAP spans the slots before the CFP, including the beacon.
+        let cap_length = slot_duration(superframe_order) * starting_slot as usize;
+        if gts_permit
+            && characteristics.length > 0
+            && !self.allocated.is_full()
+            && cap_length >= A_MIN_CAP_LENGTH
+        {
+            descriptor.starting_slot = starting_slot;
+            // Safety: We checked that the list is not full.
+            self.allocated
+                .push(AllocatedGts {
+                    descriptor,
+                    idle_superframes: 0,
+                })
+                .unwrap();
+        }
+        self.announce(descriptor);
+        Some(descriptor)
+    }
+
+    /// Record that the given GTS was used, i.e. a data frame was received
+    /// from the device in its transmit GTS or the device acknowledged a frame
+    /// in its receive GTS.
+    ///
+    /// * `device` - Short address of the device
+    /// * `direction` - Direction of the GTS
+    pub fn on_activity(&mut self, device: u16, direction: GtsDirection) {
+        if let Some(index) = self.find(device, direction) {
+            self.allocated[index].idle_superframes = 0;
+        }
+    }
+
+    /// Account for the end of a superframe, expiring unused GTSs.
+    ///
+    /// Expired GTSs are announced with a starting slot of zero.
+    ///
+    /// * `beacon_order` - macBeaconOrder
+    pub fn on_superframe_end(&mut self, beacon_order: u8) {
+        let expiration = gts_expiration_superframes(beacon_order);
+        let mut index = 0;
+        while index < self.allocated.len() {
+            let gts = &mut self.allocated[index];
+            gts.idle_superframes += 1;
+            if gts.idle_superframes >= expiration {
+                let mut descriptor = self.allocated.remove(index).descriptor;
+                descriptor.starting_slot = 0;
+                self.announce(descriptor);
+            } else {
+                index += 1;
+            }
+        }
+        self.compact();
+    }
+
+    /// Return the descriptors to include in the next beacon and advance the
+    /// persistence of the announcements.
+    pub fn next_beacon_descriptors(&mut self) -> Vec<GtsDescriptor, MAX_GTS_DESCRIPTORS> {
+        let descriptors = self
+            .announcements
+            .iter()
+            .map(|announcement| announcement.descriptor)
+            .collect();
+        for announcement in self.announcements.iter_mut() {
+            announcement.remaining -= 1;
+        }
+        self.announcements
+            .retain(|announcement| announcement.remaining > 0);
+        descriptors
+    }
+
+    /// Return the GTS allocated to the given device.
+    ///
+    /// * `device` - Short address of the device
+    /// * `direction` - Direction of the GTS
+    pub fn gts(&self, device: u16, direction: GtsDirection) -> Option<GtsDescriptor> {
+        self.find(device, direction)
+            .map(|index| self.allocated[index].descriptor)
+    }
+
+    /// Return the number of allocated GTSs.
+    pub fn len(&self) -> usize {
+        self.allocated.len()
+    }
+
+    /// Return whether no GTS is allocated.
+    pub fn is_empty(&self) -> bool {
+        self.allocated.is_empty()
+    }
+
+    fn find(&self, device: u16, direction: GtsDirection) -> Option<usize> {
+        self.allocated.iter().position(|gts| {
+            gts.descriptor.short_address.into_u16() == device
+                && gts.descriptor.direction == direction
+        })
+    }
+
+    fn deallocate(&mut self, device: u16, direction: GtsDirection) {
+        if let Some(index) = self.find(device, direction) {
+            // The device released the GTS, so there is nothing to announce.
+            let descriptor = self.allocated.remove(index).descriptor;
+            self.announcements
+                .retain(|announcement| !same_gts(&announcement.descriptor, &descriptor));
+            self.compact();
+        }
+    }
+
+    /// Move GTSs towards the end of the superframe to close gaps.
+    fn compact(&mut self) {
+        let mut end = A_NUM_SUPERFRAME_SLOTS;
+        for index in 0..self.allocated.len() {
+            let mut descriptor = self.allocated[index].descriptor;
+            let starting_slot = end - descriptor.length;
+            if descriptor.starting_slot != starting_slot {
+                descriptor.starting_slot = starting_slot;
+                self.allocated[index].descriptor = descriptor;
+                self.announce(descriptor);
+            }
+            end = starting_slot;
+        }
+    }
+
+    /// Include the given descriptor in the next aGTSDescPersistenceTime
+    /// beacons, replacing any previous announcement for the same GTS.
+    fn announce(&mut self, descriptor: GtsDescriptor) {
+        self.announcements
+            .retain(|announcement| !same_gts(&announcement.descriptor, &descriptor));
+        if self.announcements.is_full() {
+            // Drop the oldest announcement, its GTS was announced before.
+            self.announcements.remove(0);
+        }
+        // Safety: We made room for the announcement.
+        self.announcements
+            .push(Announcement {
+                descriptor,
+                remaining: A_GTS_DESC_PERSISTENCE_TIME,
+            })
+            .unwrap();
+    }
+}
+
+/// A change of the GTSs of a device learned from a beacon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GtsEvent {
+    /// The outcome of a GTS request (MLME-GTS.confirm).
+    Confirm(Result<GtsDescriptor, GtsError>),
+    /// The PAN coordinator deallocated a GTS (MLME-GTS.indication).
+    Deallocated(GtsDescriptor),
+}
+
+/// The GTS request awaiting its descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PendingRequest {
+    direction: GtsDirection,
+    /// The number of beacons that may still carry the descriptor.
+    remaining: u8,
+}
+
+/// The GTSs of a device, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceGts {
+    short_address: u16,
+    transmit: Option<GtsDescriptor>,
+    receive: Option<GtsDescriptor>,
+    pending: Option<PendingRequest>,
+}
+
+impl DeviceGts {
+    /// Creates a new [`DeviceGts`] without GTSs.
+    ///
+    /// * `short_address` - macShortAddress of the device
+    pub const fn new(short_address: u16) -> Self {
+        Self {
+            short_address,
+            transmit: None,
+            receive: None,
+            pending: None,
+        }
+    }
+
+    /// Prepare the given GTS request (MLME-GTS.request).
+    ///
+    /// The caller sends the GTS Request command built with
+    /// [`gts_request_frame()`] once this succeeded. Released GTSs are
+    /// forgotten right away, requested GTSs are confirmed by
+    /// [`DeviceGts::on_beacon()`].
+    pub fn request(&mut self, characteristics: GtsCharacteristics) -> Result<(), GtsError> {
+        if self.short_address == SHORT_ADDRESS_NONE
+            || self.short_address == SHORT_ADDRESS_UNASSIGNED
+        {
+            return Err(GtsError::NoShortAddress);
+        }
+        if !characteristics.allocation {
+            return self
+                .slot_mut(characteristics.direction)
+                .take()
+                .map(|_| ())
+                .ok_or(GtsError::InvalidParameter);
+        }
+        if self.pending.is_some()
+            || self.gts(characteristics.direction).is_some()
+            || !(1..=MAX_GTS_LENGTH).contains(&characteristics.length)
+        {
+            return Err(GtsError::InvalidParameter);
+        }
+        self.pending = Some(PendingRequest {
+            direction: characteristics.direction,
+            remaining: A_GTS_DESC_PERSISTENCE_TIME,
+        });
+        Ok(())
+    }
+
+    /// Evaluate the GTS fields of a beacon of the PAN coordinator.
+    ///
+    /// Returns up to one event per direction.
+    pub fn on_beacon(&mut self, gts_fields: &GtsFields<&[u8]>) -> Vec<GtsEvent, 2> {
+        let mut events = Vec::new();
+        for direction in [GtsDirection::Transmit, GtsDirection::Receive] {
+            let descriptor = gts_fields.descriptors().find(|descriptor| {
+                descriptor.short_address.into_u16() == self.short_address
+                    && descriptor.direction == direction
+            });
+            if let Some(event) = self.update(direction, descriptor) {
+                // Safety: There is at most one event per direction.
+                events.push(event).unwrap();
+            }
+        }
+        events
+    }
+
+    /// Return the GTS of the device in the given direction.
+    pub fn gts(&self, direction: GtsDirection) -> Option<GtsDescriptor> {
+        match direction {
+            GtsDirection::Transmit => self.transmit,
+            GtsDirection::Receive => self.receive,
+        }
+    }
+
+    /// Return the time window of the GTS in the given direction within the
+    /// superframe containing the given instant.
+    ///
+    /// * `direction` - Direction of the GTS
+    /// * `superframe` - Superframe schedule of the PAN
+    /// * `at` - Any instant within the superframe
+    pub fn window<Timer: Frequency>(
+        &self,
+        direction: GtsDirection,
+        superframe: &Superframe<Timer>,
+        at: Instant<Timer>,
+    ) -> Option<GtsWindow<Timer>> {
+        GtsWindow::new(&self.gts(direction)?, superframe, at)
+    }
+
+    fn slot_mut(&mut self, direction: GtsDirection) -> &mut Option<GtsDescriptor> {
+        match direction {
+            GtsDirection::Transmit => &mut self.transmit,
+            GtsDirection::Receive => &mut self.receive,
+        }
+    }
+
+    fn update(
+        &mut self,
+        direction: GtsDirection,
+        descriptor: Option<GtsDescriptor>,
+    ) -> Option<GtsEvent> {
+        let pending = self
+            .pending
+            .as_mut()
+            .filter(|pending| pending.direction == direction);
+        match (pending, descriptor) {
+            (Some(_), Some(descriptor)) => {
+                self.pending = None;
+                if descriptor.starting_slot == 0 {
+                    return Some(GtsEvent::Confirm(Err(GtsError::Denied)));
+                }
+                *self.slot_mut(direction) = Some(descriptor);
+                Some(GtsEvent::Confirm(Ok(descriptor)))
+            }
+            (Some(pending), None) => {
+                pending.remaining -= 1;
+                if pending.remaining > 0 {
+                    return None;
+                }
+                self.pending = None;
+                Some(GtsEvent::Confirm(Err(GtsError::NoData)))
+            }
+            (None, Some(descriptor)) => {
+                let slot = self.slot_mut(direction);
+                slot.as_ref()?;
+                if descriptor.starting_slot == 0 {
+                    slot.take().map(GtsEvent::Deallocated)
+                } else {
+                    // The GTS was moved.
+                    *slot = Some(descriptor);
+                    None
+                }
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// The time window of a GTS within a superframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GtsWindow<Timer: Frequency> {
+    /// The start of the first slot of the GTS.
+    pub start: Instant<Timer>,
+    /// The end of the last slot of the GTS.
+    pub end: Instant<Timer>,
+}
+
+impl<Timer: Frequency> GtsWindow<Timer> {
+    /// Creates the window of the given GTS within the superframe containing
+    /// the given instant.
+    ///
+    /// Returns `None` if the superframe schedule has no beacon yet.
+    ///
+    /// * `gts` - The GTS
+    /// * `superframe` - Superframe schedule of the PAN
+    /// * `at` - Any instant within the superframe
+    pub fn new(
+        gts: &GtsDescriptor,
+        superframe: &Superframe<Timer>,
+        at: Instant<Timer>,
+    ) -> Option<Self> {
+        Some(Self {
+            start: superframe.slot_start(at, gts.starting_slot)?,
+            end: superframe.slot_start(at, gts.starting_slot + gts.length)?,
+        })
+    }
+
+    /// Return whether a transaction of the given duration, including the
+    /// ACK and the following IFS, may start at the given instant.
+    pub fn fits(&self, at: Instant<Timer>, duration: Duration<Timer>) -> bool {
+        at >= self.start && at + duration <= self.end
+    }
+}
+
+impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
+    /// Requests or releases a GTS (MLME-GTS.request), see
+    /// [`DeviceGts::request()`].
+    ///
+    /// Sends the GTS Request command to the PAN coordinator. The command needs
+    /// the radio for itself, i.e. a [`MacRadio`] that is not shared with the
+    /// driver service meanwhile. It is numbered with macDsn.
+    ///
+    /// The PAN coordinator answers in its beacons, the outcome of an
+    /// allocation is therefore reported by [`DeviceGts::on_beacon()`]. A
+    /// released GTS is forgotten right away.
+    ///
+    /// * `radio` - The radio transmitting the command
+    /// * `device_gts` - The GTSs of the device
+    /// * `characteristics` - The requested or released GTS
+    pub(crate) async fn mlme_gts_request<R: Radio<Timer = RadioDriverImpl::Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        device_gts: &mut DeviceGts,
+        characteristics: GtsCharacteristics,
+    ) -> Result<(), GtsError> {
+        device_gts.request(characteristics)?;
+
+        // The PIB must not stay borrowed while transmitting.
+        let command = {
+            let mut pib = self.pib.borrow_mut();
+            Retransmissions::new(pib.csma()).ok().zip(
+                gts_request_frame(
+                    pib.dsn.next(),
+                    pib.pan_id,
+                    pib.short_address,
+                    characteristics,
+                )
+                .ok(),
+            )
+        };
+        let Some((retransmissions, gts_request)) = command else {
+            device_gts.pending = None;
+            return Err(GtsError::InvalidParameter);
+        };
+
+        match retransmissions.transmit(radio, &gts_request).await {
+            Ok(report) if report.is_success() => Ok(()),
+            Ok(_) => {
+                device_gts.pending = None;
+                Err(GtsError::NoAck)
+            }
+            Err(_) => {
+                device_gts.pending = None;
+                Err(GtsError::InvalidParameter)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{phy::PhyParameters, test_clock::TestClock, time::Microseconds},
+        mac::{
+            frame::fields::gts_fields_length,
+            test_helpers::{with_mac_service, ScriptedAckRadio},
+        },
+    };
+
+    const DEVICE: u16 = 0x0001;
+
+    fn request(length: u8, direction: GtsDirection) -> GtsCharacteristics {
+        GtsCharacteristics {
+            length,
+            direction,
+            allocation: true,
+        }
+    }
+
+    fn descriptor(
+        short_address: u16,
+        starting_slot: u8,
+        length: u8,
+        direction: GtsDirection,
+    ) -> GtsDescriptor {
+        GtsDescriptor {
+            short_address: ShortAddress::from_u16(short_address),
+            starting_slot,
+            length,
+            direction,
+        }
+    }
+
+    fn gts_fields(descriptors: &[GtsDescriptor]) -> Vec<u8, 23> {
+        let mut bytes = Vec::new();
+        bytes
+            .resize(gts_fields_length(descriptors.len() as u8) as usize, 0)
+            .unwrap();
+        GtsFields::new_unchecked(&mut bytes[..])
+            .emit(true, descriptors)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn gts_characteristics() {
+        let characteristics = GtsCharacteristics::from_byte(0x33);
+        assert_eq!(characteristics, request(3, GtsDirection::Receive));
+        assert_eq!(characteristics.to_byte(), 0x33);
+        assert_eq!(
+            GtsCharacteristics::from_byte(0xc2),
+            GtsCharacteristics {
+                length: 2,
+                direction: GtsDirection::Transmit,
+                allocation: false,
+            }
+        );
+    }
+
+    #[test]
+    fn gts_request_command() {
+        let mpdu = gts_request_frame(
+            0x2a,
+            PanId::from_u16(0xabcd),
+            DEVICE,
+            request(2, GtsDirection::Transmit),
+        )
+        .unwrap();
+        assert_eq!(
+            mpdu[..],
+            [0x23, 0x90, 0x2a, 0xcd, 0xab, 0x01, 0x00, 0x09, 0x22]
+        );
+        assert_eq!(
+            parse_gts_request(&mpdu),
+            Ok(Some((DEVICE, request(2, GtsDirection::Transmit))))
+        );
+
+        // Other commands are ignored.
+        let mut other = mpdu.clone();
+        other[7] = 0x04;
+        assert_eq!(parse_gts_request(&other), Ok(None));
+    }
+
+    #[test]
+    fn allocation() {
+        let mut allocator = GtsAllocator::new();
+        assert_eq!(allocator.final_cap_slot(), 15);
+
+        let gts = allocator.handle_request(DEVICE, request(2, GtsDirection::Transmit), true, 0);
+        assert_eq!(gts, Some(descriptor(DEVICE, 14, 2, GtsDirection::Transmit)));
+        let gts = allocator.handle_request(0x0002, request(3, GtsDirection::Receive), true, 0);
+        assert_eq!(gts, Some(descriptor(0x0002, 11, 3, GtsDirection::Receive)));
+        assert_eq!(allocator.final_cap_slot(), 10);
+
+        // Duplicate requests are ignored.
+        assert_eq!(
+            allocator.handle_request(DEVICE, request(1, GtsDirection::Transmit), true, 0),
+            None
+        );
+
+        // The CAP must span at least 440 symbols, i.e. 8 slots of 60 symbols.
+        let gts = allocator.handle_request(0x0003, request(4, GtsDirection::Transmit), true, 0);
+        assert_eq!(gts, Some(descriptor(0x0003, 0, 4, GtsDirection::Transmit)));
+        let gts = allocator.handle_request(0x0003, request(3, GtsDirection::Transmit), true, 0);
+        assert_eq!(gts, Some(descriptor(0x0003, 8, 3, GtsDirection::Transmit)));
+
+        // Requests are denied without macGtsPermit.
+        let gts = allocator.handle_request(0x0004, request(1, GtsDirection::Transmit), false, 3);
+        assert_eq!(gts, Some(descriptor(0x0004, 0, 1, GtsDirection::Transmit)));
+        assert_eq!(allocator.len(), 3);
+    }
+
+    #[test]
+    fn deallocation_and_expiration() {
+        let mut allocator = GtsAllocator::new();
+        allocator.handle_request(DEVICE, request(2, GtsDirection::Transmit), true, 0);
+        allocator.handle_request(0x0002, request(3, GtsDirection::Receive), true, 0);
+        assert_eq!(allocator.next_beacon_descriptors().len(), 2);
+
+        // Releasing the first GTS moves the second one.
+        let release = GtsCharacteristics {
+            allocation: false,
+            ..request(2, GtsDirection::Transmit)
+        };
+        assert_eq!(allocator.handle_request(DEVICE, release, true, 0), None);
+        let moved = descriptor(0x0002, 13, 3, GtsDirection::Receive);
+        assert_eq!(allocator.gts(0x0002, GtsDirection::Receive), Some(moved));
+        assert_eq!(*allocator.next_beacon_descriptors(), [moved]);
+
+        // The GTS is announced in aGTSDescPersistenceTime beacons.
+        assert_eq!(allocator.next_beacon_descriptors().len(), 1);
+        assert_eq!(allocator.next_beacon_descriptors().len(), 1);
+        assert_eq!(allocator.next_beacon_descriptors().len(), 1);
+        assert!(allocator.next_beacon_descriptors().is_empty());
+
+        // With a beacon order of 7, unused GTSs expire after 4 superframes.
+        for _ in 0..3 {
+            allocator.on_superframe_end(7);
+        }
+        allocator.on_activity(0x0002, GtsDirection::Receive);
+        for _ in 0..3 {
+            allocator.on_superframe_end(7);
+        }
+        assert_eq!(allocator.len(), 1);
+        allocator.on_superframe_end(7);
+        assert!(allocator.is_empty());
+        assert_eq!(allocator.final_cap_slot(), 15);
+        assert_eq!(
+            *allocator.next_beacon_descriptors(),
+            [descriptor(0x0002, 0, 3, GtsDirection::Receive)]
+        );
+    }
+
+    #[test]
+    fn device_gts() {
+        let mut device = DeviceGts::new(DEVICE);
+        assert_eq!(
+            DeviceGts::new(SHORT_ADDRESS_NONE).request(request(1, GtsDirection::Transmit)),
+            Err(GtsError::NoShortAddress)
+        );
+        assert_eq!(
+            device.request(request(0, GtsDirection::Transmit)),
+            Err(GtsError::InvalidParameter)
+        );
+
+        device.request(request(2, GtsDirection::Transmit)).unwrap();
+        let other = gts_fields(&[descriptor(0x0002, 14, 2, GtsDirection::Transmit)]);
+        assert!(device
+            .on_beacon(&GtsFields::new_unchecked(&other[..]))
+            .is_empty());
+        let allocated = descriptor(DEVICE, 12, 2, GtsDirection::Transmit);
+        let beacon = gts_fields(&[descriptor(0x0002, 14, 2, GtsDirection::Transmit), allocated]);
+        assert_eq!(
+            *device.on_beacon(&GtsFields::new_unchecked(&beacon[..])),
+            [GtsEvent::Confirm(Ok(allocated))]
+        );
+        assert_eq!(device.gts(GtsDirection::Transmit), Some(allocated));
+
+        // The GTS is moved, then deallocated.
+        let moved = descriptor(DEVICE, 14, 2, GtsDirection::Transmit);
+        let beacon = gts_fields(&[moved]);
+        assert!(device
+            .on_beacon(&GtsFields::new_unchecked(&beacon[..]))
+            .is_empty());
+        assert_eq!(device.gts(GtsDirection::Transmit), Some(moved));
+        let beacon = gts_fields(&[descriptor(DEVICE, 0, 2, GtsDirection::Transmit)]);
+        assert_eq!(
+            *device.on_beacon(&GtsFields::new_unchecked(&beacon[..])),
+            [GtsEvent::Deallocated(moved)]
+        );
+        assert_eq!(device.gts(GtsDirection::Transmit), None);
+
+        // Denied requests and missing descriptors.
+        device.request(request(1, GtsDirection::Receive)).unwrap();
+        let beacon = gts_fields(&[descriptor(DEVICE, 0, 1, GtsDirection::Receive)]);
+        assert_eq!(
+            *device.on_beacon(&GtsFields::new_unchecked(&beacon[..])),
+            [GtsEvent::Confirm(Err(GtsError::Denied))]
+        );
+        device.request(request(1, GtsDirection::Receive)).unwrap();
+        let empty = gts_fields(&[]);
+        for _ in 0..3 {
+            assert!(device
+                .on_beacon(&GtsFields::new_unchecked(&empty[..]))
+                .is_empty());
+        }
+        assert_eq!(
+            *device.on_beacon(&GtsFields::new_unchecked(&empty[..])),
+            [GtsEvent::Confirm(Err(GtsError::NoData))]
+        );
+    }
+
+    #[test]
+    fn gts_window() {
+        let mut device = DeviceGts::new(DEVICE);
+        device.transmit = Some(descriptor(DEVICE, 14, 2, GtsDirection::Transmit));
+
+        // Slots of 60 * 2 symbols of 16µs each.
+        let mut superframe =
+            Superframe::<Microseconds>::new(&PhyParameters::OQPSK_2450MHZ, 1, 1, 13).unwrap();
+        let at = Instant::new(1_000);
+        assert_eq!(device.window(GtsDirection::Transmit, &superframe, at), None);
+        superframe.on_beacon(at);
+        let window = device
+            .window(GtsDirection::Transmit, &superframe, at)
+            .unwrap();
+        assert_eq!(window.start, Instant::new(1_000 + 14 * 1_920));
+        assert_eq!(window.end, Instant::new(1_000 + 16 * 1_920));
+        assert!(window.fits(window.start, Duration::new(2 * 1_920)));
+        assert!(!window.fits(window.start, Duration::new(2 * 1_920 + 1)));
+        assert!(!window.fits(Instant::new(1_000), Duration::new(1)));
+        assert_eq!(device.window(GtsDirection::Receive, &superframe, at), None);
+    }
+
+    #[test]
+    fn mlme_gts_request() {
+        with_mac_service(0x2a, |mac_service| {
+            {
+                let mut pib = mac_service.pib.borrow_mut();
+                pib.short_address = DEVICE;
+                pib.pan_id = PanId::from_u16(0xabcd);
+            }
+            let mut device = DeviceGts::new(DEVICE);
+
+            TestClock::reset();
+            let rx = [Some((100, &[0x02, 0x10, 0x2a][..]))];
+            let mut radio = ScriptedAckRadio::new(&rx);
+            assert_eq!(
+                TestClock::block_on(mac_service.mlme_gts_request(
+                    &mut radio,
+                    &mut device,
+                    request(2, GtsDirection::Transmit)
+                )),
+                Ok(())
+            );
+            // The command is numbered with macDsn.
+            assert_eq!(radio.radio_mut().transmitted[..], [0x2a]);
+            assert_eq!(mac_service.pib.borrow().dsn.value(), 0x2b);
+
+            // Unacknowledged requests are forgotten, i.e. may be repeated.
+            let mut device = DeviceGts::new(DEVICE);
+            TestClock::reset();
+            let mut radio = ScriptedAckRadio::new(&[]);
+            assert_eq!(
+                TestClock::block_on(mac_service.mlme_gts_request(
+                    &mut radio,
+                    &mut device,
+                    request(2, GtsDirection::Transmit)
+                )),
+                Err(GtsError::NoAck)
+            );
+            assert_eq!(device.request(request(2, GtsDirection::Transmit)), Ok(()));
+        });
+    }
+}
@@ -1,5 +1,8 @@
 pub mod associate;
 pub mod beacon;
+pub mod get;
+pub mod gts;
+pub mod realign;
 pub mod reset;
 pub mod scan;
 pub mod set;
@@ -0,0 +1,778 @@
+//! Coordinator realignment, orphaned devices and PAN ID conflicts (IEEE
+//! 802.15.4-2020, sections 6.3.2 and 6.4.2).
+//!
+//! A device that lost synchronization with its coordinator runs an
+//! [`orphan_scan()`]: It sends an Orphan Notification command on each channel
+//! and listens for macResponseWaitTime for a Coordinator Realignment command
+//! addressed to it. A coordinator that knows the orphaned device answers with
+//! the realignment built by [`orphan_realignment()`], which tells the device
+//! its PAN ID, channel and short address.
+//!
+//! A PAN ID conflict exists if two PAN coordinators operate with the same PAN
+//! ID within range, see [`pan_id_conflict()`]. A device detecting a conflict
+//! notifies its PAN coordinator with a PAN ID Conflict Notification command.
+//! The PAN coordinator resolves the conflict by scanning for the PAN IDs in
+//! use, choosing a new one with [`select_pan_id()`] and broadcasting a
+//! Coordinator Realignment command built by [`broadcast_realignment()`].
+//!
+//! Devices apply a received realignment with [`Pib::set_realignment()`].
+//!
+//! Note: Only commands without security and IEs are recognized.
+#![allow(dead_code)]
+
+use heapless::Vec;
+use rand_core::RngCore;
+
+use crate::{
+    driver::{
+        config::{Channel, ChannelPage},
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, ExtendedAddress, FrameControl, FrameType, PanId},
+        radio::Radio,
+        time::Duration,
+        RadioTimerApi,
+    },
+    mac::{
+        ack::{field, MacHeader},
+        frame::{
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError, FrameErrorKind,
+        },
+        pib::Pib,
+        radio::MacRadio,
+        sequence::SequenceNumber,
+    },
+};
+
+use super::{
+    associate::{response_wait_duration, AddressAllocator, SHORT_ADDRESS_UNASSIGNED},
+    scan::{PanDescriptor, ScanChannels, ScanError},
+};
+
+/// The command ID of the PAN ID Conflict Notification command.
+pub const PAN_ID_CONFLICT_NOTIFICATION_COMMAND_ID: u8 = 0x05;
+
+/// The command ID of the Orphan Notification command.
+pub const ORPHAN_NOTIFICATION_COMMAND_ID: u8 = 0x06;
+
+/// The command ID of the Coordinator Realignment command.
+pub const COORDINATOR_REALIGNMENT_COMMAND_ID: u8 = 0x08;
+
+/// The PAN ID addressing all PANs.
+const BROADCAST_PAN_ID: u16 = 0xffff;
+
+/// The length of the Coordinator Realignment command content including the
+/// Channel Page field.
+const COORDINATOR_REALIGNMENT_LEN: usize = 8;
+
+/// The content of the Coordinator Realignment command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoordinatorRealignment {
+    /// The PAN ID the coordinator uses from now on.
+    pub pan_id: u16,
+    /// The short address of the coordinator.
+    pub coord_short_address: u16,
+    /// The channel the coordinator uses from now on.
+    pub channel: u8,
+    /// The short address of the orphaned device, 0xffff in broadcast
+    /// realignments.
+    pub short_address: u16,
+    /// The channel page the coordinator uses from now on, 0 if absent.
+    pub channel_page: u8,
+}
+
+impl CoordinatorRealignment {
+    /// Decodes the command content following the command ID.
+    ///
+    /// The Channel Page field is optional.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FrameError> {
+        let content = field(bytes, 0, COORDINATOR_REALIGNMENT_LEN - 1)?;
+        Ok(Self {
+            pan_id: u16::from_le_bytes([content[0], content[1]]),
+            coord_short_address: u16::from_le_bytes([content[2], content[3]]),
+            channel: content[4],
+            short_address: u16::from_le_bytes([content[5], content[6]]),
+            channel_page: bytes
+                .get(COORDINATOR_REALIGNMENT_LEN - 1)
+                .copied()
+                .unwrap_or(0),
+        })
+    }
+
+    /// Encodes the command content following the command ID.
+    pub fn to_bytes(self) -> [u8; COORDINATOR_REALIGNMENT_LEN] {
+        let [pan_id_low, pan_id_high] = self.pan_id.to_le_bytes();
+        let [coord_low, coord_high] = self.coord_short_address.to_le_bytes();
+        let [short_low, short_high] = self.short_address.to_le_bytes();
+        [
+            pan_id_low,
+            pan_id_high,
+            coord_low,
+            coord_high,
+            self.channel,
+            short_low,
+            short_high,
+            self.channel_page,
+        ]
+    }
+}
+
+/// The content of a realignment related MAC command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RealignmentCommand {
+    PanIdConflictNotification,
+    OrphanNotification,
+    CoordinatorRealignment(CoordinatorRealignment),
+}
+
+/// A realignment related MAC command received from another device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRealignmentCommand {
+    /// The source address of the command (little endian), empty if absent.
+    pub src_address: Vec<u8, 8>,
+    /// The destination address of the command (little endian), empty if
+    /// absent.
+    pub dst_address: Vec<u8, 8>,
+    /// The content of the command.
+    pub command: RealignmentCommand,
+}
+
+/// Return the given frame if it is a realignment related MAC command.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+///
+/// # Errors
+///
+/// - [`FrameErrorKind::SecurityNotSupported`] if a MAC command is secured,
+/// - [`FrameErrorKind::IesNotSupported`] if a MAC command contains IEs,
+/// - [`FrameErrorKind::InvalidAddressingCombination`] if the command has no
+///   addressing fields,
+/// - any other error if the frame is truncated.
+pub fn parse_realignment_command(
+    mpdu: &[u8],
+) -> Result<Option<ReceivedRealignmentCommand>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    if frame_control.frame_type() != FrameType::MacCommand {
+        return Ok(None);
+    }
+    if frame_control.security_enabled() {
+        return Err(FrameErrorKind::SecurityNotSupported.into());
+    }
+    if frame_control.information_elements_present() {
+        return Err(FrameErrorKind::IesNotSupported.into());
+    }
+
+    let header = MacHeader::parse(mpdu, frame_control)?;
+    let content = header.end + 1;
+    let command = match field(mpdu, header.end, 1)?[0] {
+        PAN_ID_CONFLICT_NOTIFICATION_COMMAND_ID => RealignmentCommand::PanIdConflictNotification,
+        ORPHAN_NOTIFICATION_COMMAND_ID => RealignmentCommand::OrphanNotification,
+        COORDINATOR_REALIGNMENT_COMMAND_ID => RealignmentCommand::CoordinatorRealignment(
+            CoordinatorRealignment::from_bytes(&mpdu[content.min(mpdu.len())..])
+                .map_err(|e| e.shifted_by(content))?,
+        ),
+        _ => return Ok(None),
+    };
+
+    let addressing_fields = header
+        .addressing_fields
+        .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+    // Safety: Addresses never exceed 8 bytes.
+    let to_vec = |address: Option<Address<&[u8]>>| {
+        address
+            .map(|address| Vec::from_slice(address.as_le_bytes()).unwrap())
+            .unwrap_or_default()
+    };
+    Ok(Some(ReceivedRealignmentCommand {
+        src_address: to_vec(addressing_fields.src_address()),
+        dst_address: to_vec(addressing_fields.dst_address()),
+        command,
+    }))
+}
+
+/// Build an Orphan Notification command.
+///
+/// The command is broadcast and not acknowledged.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `device` - Extended address of the orphaned device
+pub fn orphan_notification_frame(
+    seq_nr: u8,
+    device: &[u8; 8],
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let pan_id = PanId::from_u16(BROADCAST_PAN_ID);
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_addressing(
+            Some((pan_id, Address::<&[u8]>::BROADCAST_ADDR)),
+            Some((pan_id, Address::Extended(ExtendedAddress::new(&device[..])))),
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&[ORPHAN_NOTIFICATION_COMMAND_ID]);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Build a PAN ID Conflict Notification command requesting an
+/// acknowledgement.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `coordinator` - PAN ID and address of the PAN coordinator
+/// * `device` - Extended address of the notifying device
+pub fn pan_id_conflict_notification_frame(
+    seq_nr: u8,
+    coordinator: (PanId<[u8; 2]>, Address<&[u8]>),
+    device: &[u8; 8],
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let pan_id = coordinator.0;
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_ack_request(true)
+        .with_addressing(
+            Some(coordinator),
+            Some((pan_id, Address::Extended(ExtendedAddress::new(&device[..])))),
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&[PAN_ID_CONFLICT_NOTIFICATION_COMMAND_ID]);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Build a Coordinator Realignment command.
+///
+/// A realignment addressed to an orphaned device requests an
+/// acknowledgement, a broadcast realignment does not.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `coordinator` - Current PAN ID and extended address of the coordinator
+/// * `orphan` - Extended address of the orphaned device, `None` to broadcast
+/// * `realignment` - The content of the command
+pub fn coordinator_realignment_frame(
+    seq_nr: u8,
+    coordinator: (PanId<[u8; 2]>, &[u8; 8]),
+    orphan: Option<&[u8; 8]>,
+    realignment: &CoordinatorRealignment,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let dst_address = match orphan {
+        Some(orphan) => Address::Extended(ExtendedAddress::new(&orphan[..])),
+        None => Address::<&[u8]>::BROADCAST_ADDR,
+    };
+    let mut payload = [0; COORDINATOR_REALIGNMENT_LEN + 1];
+    payload[0] = COORDINATOR_REALIGNMENT_COMMAND_ID;
+    payload[1..].copy_from_slice(&realignment.to_bytes());
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_ack_request(orphan.is_some())
+        .with_addressing(
+            Some((PanId::from_u16(BROADCAST_PAN_ID), dst_address)),
+            Some((
+                coordinator.0,
+                Address::Extended(ExtendedAddress::new(&coordinator.1[..])),
+            )),
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&payload);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Return the realignment answering the Orphan Notification of the given
+/// device, `None` if the device is not associated with this coordinator.
+///
+/// * `allocator` - The short addresses of the associated devices
+/// * `orphan` - Extended address of the orphaned device (little endian)
+/// * `pib` - The PIB of the coordinator
+/// * `channel` - The current channel of the coordinator
+pub fn orphan_realignment<const N: usize>(
+    allocator: &AddressAllocator<N>,
+    orphan: &[u8],
+    pib: &Pib,
+    channel: u8,
+) -> Option<CoordinatorRealignment> {
+    Some(CoordinatorRealignment {
+        pan_id: pib.pan_id.into_u16(),
+        coord_short_address: pib.short_address,
+        channel,
+        short_address: allocator.short_address(orphan)?,
+        channel_page: 0,
+    })
+}
+
+/// Return the realignment announcing a new PAN ID or channel to all devices
+/// of the PAN.
+///
+/// * `pib` - The PIB of the coordinator
+/// * `pan_id` - The new PAN ID
+/// * `channel` - The new channel
+pub fn broadcast_realignment(pib: &Pib, pan_id: u16, channel: u8) -> CoordinatorRealignment {
+    CoordinatorRealignment {
+        pan_id,
+        coord_short_address: pib.short_address,
+        channel,
+        short_address: SHORT_ADDRESS_UNASSIGNED,
+        channel_page: 0,
+    }
+}
+
+/// Return whether the given beacon reveals a PAN ID conflict.
+///
+/// A PAN coordinator detects a conflict when it hears a beacon of another PAN
+/// coordinator using its PAN ID. A device detects a conflict when it hears a
+/// beacon of a PAN coordinator using its PAN ID that is not its own
+/// coordinator. Enhanced Beacons do not tell whether their source is a PAN
+/// coordinator and are ignored.
+///
+/// * `pib` - The PIB of the receiving device
+/// * `pan_coordinator` - Whether the receiving device is the PAN coordinator
+/// * `descriptor` - The received beacon
+pub fn pan_id_conflict(pib: &Pib, pan_coordinator: bool, descriptor: &PanDescriptor) -> bool {
+    let pan_id = pib.pan_id.into_u16();
+    if pan_id == BROADCAST_PAN_ID || descriptor.coord_pan_id != pan_id {
+        return false;
+    }
+    if !descriptor
+        .superframe_specification()
+        .is_some_and(|specification| specification.pan_coordinator())
+    {
+        return false;
+    }
+    if pan_coordinator {
+        return true;
+    }
+
+    let own_coordinator = match *descriptor.coord_address {
+        [low, high] => u16::from_le_bytes([low, high]) == pib.coord_short_address,
+        ref extended => pib
+            .coord_extended_address
+            .is_some_and(|address| address[..] == *extended),
+    };
+    !own_coordinator
+}
+
+/// Return a random PAN ID not used by any of the given PANs.
+///
+/// * `pans` - The PANs heard during a scan
+/// * `rng` - Random number generator
+pub fn select_pan_id<Rng: RngCore>(pans: &[PanDescriptor], rng: &mut Rng) -> u16 {
+    loop {
+        let pan_id = (rng.next_u32() & 0xffff) as u16;
+        // 0xffff is the broadcast PAN ID, 0xfffe is reserved.
+        if pan_id < 0xfffe && !pans.iter().any(|pan| pan.coord_pan_id == pan_id) {
+            return pan_id;
+        }
+    }
+}
+
+/// A Coordinator Realignment command received by an orphaned device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanScanResult {
+    /// The channel on which the realignment was received.
+    pub channel: Channel,
+    /// The address of the coordinator (little endian).
+    pub coord_address: Vec<u8, 8>,
+    /// The content of the command.
+    pub realignment: CoordinatorRealignment,
+}
+
+/// Scans the given channels for the coordinator of an orphaned device, see
+/// the module documentation.
+///
+/// The scan stops at the first Coordinator Realignment command addressed to
+/// the device. Channels the radio cannot tune to are skipped.
+///
+/// * `radio` - The radio performing the scan
+/// * `scan_channels` - The channels to scan
+/// * `channel_page` - The channel page and band of the channels to scan
+/// * `device` - Extended address of the orphaned device
+/// * `response_wait_time` - macResponseWaitTime
+/// * `dsn` - macDsn, used to number Orphan Notification commands
+///
+/// # Errors
+///
+/// - [`ScanError::BadChannel`] if a channel is not on the given channel page,
+/// - [`ScanError::NoBeacon`] if no realignment was received.
+pub async fn orphan_scan<Timer: RadioTimerApi, R: Radio<Timer = Timer>>(
+    radio: &mut MacRadio<R>,
+    scan_channels: ScanChannels,
+    channel_page: ChannelPage,
+    device: &[u8; 8],
+    response_wait_time: u8,
+    dsn: &mut SequenceNumber,
+) -> Result<OrphanScanResult, ScanError> {
+    if let ScanChannels::Single(channel) = scan_channels {
+        if channel.page() != channel_page {
+            return Err(ScanError::BadChannel);
+        }
+    }
+
+    let wait: Duration<Timer> =
+        response_wait_duration(response_wait_time).convert_into_rounding_up();
+    let mut frame = FrameBuffer::new();
+    for channel in scan_channels.channels(channel_page) {
+        if radio.set_channel(channel).is_err() {
+            continue;
+        }
+        // Safety: The Orphan Notification command always fits into the buffer.
+        let mpdu = orphan_notification_frame(dsn.next(), device).unwrap();
+        radio.transmit_now(&mpdu).await;
+
+        let until = Timer::now() + wait;
+        while radio.receive_until(&mut frame, until).await.is_some() {
+            if let Ok(Some(ReceivedRealignmentCommand {
+                src_address,
+                dst_address,
+                command: RealignmentCommand::CoordinatorRealignment(realignment),
+            })) = parse_realignment_command(&frame)
+            {
+                if dst_address[..] == device[..] {
+                    return Ok(OrphanScanResult {
+                        channel,
+                        coord_address: src_address,
+                        realignment,
+                    });
+                }
+            }
+        }
+    }
+    Err(ScanError::NoBeacon)
+}
+
+impl Pib {
+    /// Record the realignment received from the coordinator.
+    ///
+    /// The short address is kept if the realignment was broadcast.
+    ///
+    /// * `realignment` - The content of the Coordinator Realignment command
+    /// * `coord_address` - The address of the coordinator (little endian)
+    pub(crate) fn set_realignment(
+        &mut self,
+        realignment: &CoordinatorRealignment,
+        coord_address: &[u8],
+    ) {
+        self.pan_id = PanId::from_u16(realignment.pan_id);
+        self.coord_short_address = realignment.coord_short_address;
+        if let Ok(coord_extended_address) = coord_address.try_into() {
+            self.coord_extended_address = Some(coord_extended_address);
+        }
+        if realignment.short_address != SHORT_ADDRESS_UNASSIGNED {
+            self.short_address = realignment.short_address;
+        }
+        self.touch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::CcaMode,
+            frame::ShortAddress,
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+            time::Instant,
+        },
+        mac::{
+            frame::fields::SuperframeSpecification,
+            test_helpers::{
+                mac_radio, received, timed_out, transmitted, CountingRng, TEST_CAPABILITIES,
+            },
+        },
+    };
+
+    const COORDINATOR: [u8; 8] = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    const DEVICE: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    const REALIGNMENT: CoordinatorRealignment = CoordinatorRealignment {
+        pan_id: 0x1234,
+        coord_short_address: 0x0000,
+        channel: 15,
+        short_address: 0x0001,
+        channel_page: 0,
+    };
+
+    /// Radio answering Orphan Notifications on the given channel.
+    struct CoordinatorRadio {
+        channel: Channel,
+        tuned: Channel,
+        transmitted: Vec<Channel, 16>,
+        response: Option<FrameBuffer<PHY_MAX_PACKET_SIZE_127>>,
+    }
+
+    impl CoordinatorRadio {
+        fn new(channel: Channel) -> MacRadio<Self> {
+            mac_radio(Self {
+                channel,
+                tuned: Channel::_11,
+                transmitted: Vec::new(),
+                response: None,
+            })
+        }
+    }
+
+    impl Radio for CoordinatorRadio {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+            self.tuned = channel;
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            self.transmitted.push(self.tuned).unwrap();
+            if self.tuned == self.channel {
+                let realignment = coordinator_realignment_frame(
+                    0,
+                    (PanId::from_u16(0x1234), &COORDINATOR),
+                    Some(&DEVICE),
+                    &REALIGNMENT,
+                )
+                .unwrap();
+                assert_eq!(mpdu[mpdu.len() - 1], ORPHAN_NOTIFICATION_COMMAND_ID);
+                self.response = Some(realignment);
+            }
+            transmitted(TestClock::now())
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            match self.response.take() {
+                Some(response) => received(buffer, &response),
+                None => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    fn run_orphan_scan(
+        radio: &mut MacRadio<CoordinatorRadio>,
+    ) -> Result<OrphanScanResult, ScanError> {
+        TestClock::reset();
+        let mut dsn = SequenceNumber::new(0);
+        let mut scan = pin!(orphan_scan(
+            radio,
+            ScanChannels::All,
+            ChannelPage::Oqpsk2450Mhz,
+            &DEVICE,
+            32,
+            &mut dsn
+        ));
+        let mut cx = Context::from_waker(Waker::noop());
+        match scan.as_mut().poll(&mut cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("orphan scans must not wait for alarms"),
+        }
+    }
+
+    fn beacon(pan_id: u16, coord_address: &[u8], pan_coordinator: bool) -> PanDescriptor {
+        let mut superframe_specification = SuperframeSpecification::new_unchecked([0xff, 0x0f]);
+        superframe_specification.set_pan_coordinator(pan_coordinator);
+        PanDescriptor {
+            coord_pan_id: pan_id,
+            coord_address: Vec::from_slice(coord_address).unwrap(),
+            channel: Channel::_11,
+            superframe_specification: Some(superframe_specification.into_inner()),
+            enhanced: false,
+            rssi: -50,
+        }
+    }
+
+    #[test]
+    fn coordinator_realignment() {
+        let mpdu = coordinator_realignment_frame(
+            7,
+            (PanId::from_u16(0xabcd), &COORDINATOR),
+            None,
+            &REALIGNMENT,
+        )
+        .unwrap();
+        assert_eq!(
+            mpdu[..],
+            [
+                0x03, 0xd8, 0x07, 0xff, 0xff, 0xff, 0xff, 0xcd, 0xab, 0x01, 0x02, 0x03, 0x04, 0x05,
+                0x06, 0x07, 0x08, 0x08, 0x34, 0x12, 0x00, 0x00, 0x0f, 0x01, 0x00, 0x00
+            ]
+        );
+        assert_eq!(
+            parse_realignment_command(&mpdu),
+            Ok(Some(ReceivedRealignmentCommand {
+                src_address: Vec::from_slice(&COORDINATOR).unwrap(),
+                dst_address: Vec::from_slice(&[0xff, 0xff]).unwrap(),
+                command: RealignmentCommand::CoordinatorRealignment(REALIGNMENT),
+            }))
+        );
+
+        // The Channel Page field is optional, the other fields are not.
+        assert_eq!(
+            CoordinatorRealignment::from_bytes(&REALIGNMENT.to_bytes()[..7]),
+            Ok(REALIGNMENT)
+        );
+        assert!(parse_realignment_command(&mpdu[..mpdu.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn notifications() {
+        let mpdu = orphan_notification_frame(3, &DEVICE).unwrap();
+        assert_eq!(
+            mpdu[..],
+            [
+                0x43, 0xd8, 0x03, 0xff, 0xff, 0xff, 0xff, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02,
+                0x01, 0x06
+            ]
+        );
+        let command = parse_realignment_command(&mpdu).unwrap().unwrap();
+        assert_eq!(command.command, RealignmentCommand::OrphanNotification);
+        assert_eq!(command.src_address[..], DEVICE);
+
+        let coordinator = [0x00, 0x00];
+        let mpdu = pan_id_conflict_notification_frame(
+            4,
+            (
+                PanId::from_u16(0xabcd),
+                Address::Short(ShortAddress::new(&coordinator[..])),
+            ),
+            &DEVICE,
+        )
+        .unwrap();
+        let command = parse_realignment_command(&mpdu).unwrap().unwrap();
+        assert_eq!(
+            command.command,
+            RealignmentCommand::PanIdConflictNotification
+        );
+        assert_eq!(command.dst_address[..], coordinator);
+    }
+
+    #[test]
+    fn orphan_response() {
+        let mut allocator = AddressAllocator::<4>::new();
+        allocator.allocate(&DEVICE).unwrap();
+        let pib = Pib {
+            pan_id: PanId::from_u16(0x1234),
+            short_address: 0x0000,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            orphan_realignment(&allocator, &DEVICE, &pib, 15),
+            Some(REALIGNMENT)
+        );
+        assert_eq!(orphan_realignment(&allocator, &COORDINATOR, &pib, 15), None);
+        assert_eq!(
+            broadcast_realignment(&pib, 0x4321, 20).short_address,
+            SHORT_ADDRESS_UNASSIGNED
+        );
+    }
+
+    #[test]
+    fn orphan_scan_finds_coordinator() {
+        let mut radio = CoordinatorRadio::new(Channel::_15);
+        let result = run_orphan_scan(&mut radio).unwrap();
+        assert_eq!(result.channel, Channel::_15);
+        assert_eq!(result.coord_address[..], COORDINATOR);
+        assert_eq!(result.realignment, REALIGNMENT);
+        assert_eq!(
+            radio.radio_mut().transmitted[..],
+            [
+                Channel::_11,
+                Channel::_12,
+                Channel::_13,
+                Channel::_14,
+                Channel::_15
+            ]
+        );
+
+        let mut pib = Pib::default();
+        pib.set_realignment(&result.realignment, &result.coord_address);
+        assert_eq!(pib.pan_id.into_u16(), 0x1234);
+        assert_eq!(pib.short_address, 0x0001);
+        assert_eq!(pib.coord_short_address, 0x0000);
+        assert_eq!(pib.coord_extended_address, Some(COORDINATOR));
+
+        let mut radio = CoordinatorRadio::new(ChannelPage::Oqpsk868Mhz.channel(0).unwrap());
+        assert_eq!(run_orphan_scan(&mut radio), Err(ScanError::NoBeacon));
+        assert_eq!(radio.radio_mut().transmitted.len(), 16);
+    }
+
+    #[test]
+    fn pan_id_conflicts() {
+        let pib = Pib {
+            pan_id: PanId::from_u16(0xabcd),
+            coord_short_address: 0x0000,
+            ..Default::default()
+        };
+
+        // Beacons of the own PAN coordinator, of other PANs and of
+        // coordinators that are no PAN coordinators.
+        assert!(!pan_id_conflict(
+            &pib,
+            false,
+            &beacon(0xabcd, &[0x00, 0x00], true)
+        ));
+        assert!(!pan_id_conflict(
+            &pib,
+            false,
+            &beacon(0x1234, &[0x01, 0x00], true)
+        ));
+        assert!(!pan_id_conflict(
+            &pib,
+            false,
+            &beacon(0xabcd, &[0x01, 0x00], false)
+        ));
+
+        assert!(pan_id_conflict(
+            &pib,
+            false,
+            &beacon(0xabcd, &[0x01, 0x00], true)
+        ));
+        assert!(pan_id_conflict(
+            &pib,
+            true,
+            &beacon(0xabcd, &[0x01, 0x00], true)
+        ));
+        assert!(pan_id_conflict(
+            &pib,
+            false,
+            &beacon(0xabcd, &COORDINATOR, true)
+        ));
+    }
+
+    #[test]
+    fn new_pan_id() {
+        let pans = [beacon(0x0001, &[0x00, 0x00], true)];
+        assert_eq!(select_pan_id(&pans, &mut CountingRng(0xfffc)), 0xfffd);
+        // 0xfffe, 0xffff, 0x0000 are skipped as reserved, broadcast or in use.
+        let pans = [beacon(0x0000, &[0x00, 0x00], true)];
+        assert_eq!(select_pan_id(&pans, &mut CountingRng(0xfffd)), 0x0001);
+    }
+}
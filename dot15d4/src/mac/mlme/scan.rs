@@ -1,10 +1,65 @@
+//! Channel scans (MLME-SCAN, IEEE 802.15.4-2020, section 6.3.1).
+//!
+//! [`scan()`] steps through the requested channels and either measures the
+//! energy on each channel (ED scan) or listens for beacons. An active scan
+//! sends a Beacon Request command (an Enhanced Beacon Request for enhanced
+//! active scans) on each channel first, a passive scan only listens. Every
+//! distinct coordinator heard is reported as a [`PanDescriptor`].
 #![allow(dead_code)]
-use core::ops::RangeInclusive;
-
+use heapless::Vec;
 use rand_core::RngCore;
 
-use crate::{driver::DriverConfig, mac::MacService};
+use crate::{
+    driver::{
+        config::{Channel, ChannelPage},
+        constants::{A_BASE_SUPERFRAME_DURATION, PHY_MAX_PACKET_SIZE_127},
+        frame::{Address, FrameControl, FrameType, FrameVersion, PanId},
+        radio::Radio,
+        time::{Duration, SymbolsOQpsk250kB},
+        DriverConfig, RadioTimerApi,
+    },
+    mac::{
+        ack::MacHeader,
+        counters::MAC_COUNTERS,
+        frame::{
+            fields::{BeaconFields, SuperframeSpecification},
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError, FrameErrorKind,
+        },
+        radio::MacRadio,
+        sequence::SequenceNumber,
+        MacService,
+    },
+};
+
+/// The max number of channels scanned by a single scan request, i.e. all
+/// channels of the 2.4 GHz O-QPSK PHY.
+///
+/// SUN channel plans have more channels, their channels are scanned one by
+/// one.
+pub const MAX_SCAN_CHANNELS: usize = 16;
+
+/// The max number of PAN descriptors reported by a single scan.
+pub const MAX_PAN_DESCRIPTORS: usize = 8;
+
+/// The highest scan duration allowed by the standard.
+pub const MAX_SCAN_DURATION: u8 = 14;
+
+/// The command ID of the Beacon Request command.
+pub const BEACON_REQUEST_COMMAND_ID: u8 = 0x07;
 
+/// Return the time spent scanning each channel, i.e. aBaseSuperframeDuration *
+/// (2^n + 1) symbols, where n is the scan duration, see IEEE 802.15.4-2024,
+/// section 10.2.2.1.
+pub const fn channel_scan_duration(scan_duration: u8) -> Duration<SymbolsOQpsk250kB> {
+    Duration::new(A_BASE_SUPERFRAME_DURATION.ticks() * ((1 << scan_duration) + 1))
+}
+
+/// The default interval between two consecutive energy samples taken on the
+/// same channel during an ED scan: one CCA duration (8 symbols = 128µs).
+pub const DEFAULT_ED_SAMPLE_INTERVAL: Duration<SymbolsOQpsk250kB> = Duration::new(8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScanType {
     Ed,
     Active,
@@ -13,19 +68,290 @@ pub enum ScanType {
     EnhancedActiveScan,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScanChannels {
+    /// All channels of the scanned channel page.
     All,
-    Single(u8),
+    Single(Channel),
+}
+
+impl ScanChannels {
+    /// Return the channels to scan on the given channel page in ascending
+    /// order.
+    pub fn channels(self, channel_page: ChannelPage) -> impl Iterator<Item = Channel> {
+        channel_page.channels().filter(move |channel| match self {
+            ScanChannels::All => true,
+            ScanChannels::Single(single) => *channel == single,
+        })
+    }
+}
+
+/// Energy statistics collected on a single channel over the whole scan
+/// duration of an ED scan.
+///
+/// A single ED sample only reflects the channel's occupancy at one point in
+/// time. Site surveys need to know how busy a channel is on average and how
+/// strong the worst interferer is, so we keep track of the min, max and
+/// average of all samples taken on the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnergyDetectionResult {
+    channel: Channel,
+    min: u8,
+    max: u8,
+    sum: u32,
+    num_samples: u16,
+}
+
+impl EnergyDetectionResult {
+    /// Creates an empty result for the given channel.
+    pub const fn new(channel: Channel) -> Self {
+        Self {
+            channel,
+            min: u8::MAX,
+            max: u8::MIN,
+            sum: 0,
+            num_samples: 0,
+        }
+    }
+
+    /// Records a single ED sample as reported by the driver.
+    ///
+    /// Samples beyond [`u16::MAX`] are ignored.
+    pub fn add_sample(&mut self, ed: u8) {
+        if self.num_samples == u16::MAX {
+            return;
+        }
+
+        self.min = self.min.min(ed);
+        self.max = self.max.max(ed);
+        self.sum += ed as u32;
+        self.num_samples += 1;
+    }
+
+    /// The channel the samples were taken on.
+    pub const fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    /// The number of samples taken on the channel.
+    pub const fn num_samples(&self) -> u16 {
+        self.num_samples
+    }
+
+    /// The lowest energy level measured on the channel or [`None`] if no
+    /// sample has been taken.
+    pub const fn min(&self) -> Option<u8> {
+        if self.num_samples == 0 {
+            return None;
+        }
+        Some(self.min)
+    }
+
+    /// The highest energy level measured on the channel or [`None`] if no
+    /// sample has been taken.
+    pub const fn max(&self) -> Option<u8> {
+        if self.num_samples == 0 {
+            return None;
+        }
+        Some(self.max)
+    }
+
+    /// The average energy level measured on the channel (rounded to the
+    /// nearest integer) or [`None`] if no sample has been taken.
+    pub const fn avg(&self) -> Option<u8> {
+        if self.num_samples == 0 {
+            return None;
+        }
+        let num_samples = self.num_samples as u32;
+        Some(((self.sum + num_samples / 2) / num_samples) as u8)
+    }
+}
+
+/// Configuration of an ED scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdScanConfig {
+    /// Interval between two consecutive ED samples on the same channel.
+    pub sample_interval: Duration<SymbolsOQpsk250kB>,
+}
+
+impl Default for EdScanConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval: DEFAULT_ED_SAMPLE_INTERVAL,
+        }
+    }
+}
+
+impl EdScanConfig {
+    /// The number of ED samples to be taken on each channel for the given
+    /// scan duration.
+    ///
+    /// The time spent scanning each channel is given by
+    /// [`channel_scan_duration()`]. At least one sample will be taken per
+    /// channel.
+    pub const fn samples_per_channel(&self, scan_duration: u8) -> u16 {
+        debug_assert!(scan_duration <= MAX_SCAN_DURATION);
+        debug_assert!(self.sample_interval.ticks() > 0);
+
+        let num_samples =
+            channel_scan_duration(scan_duration).ticks() / self.sample_interval.ticks();
+        if num_samples < 1 {
+            1
+        } else if num_samples > u16::MAX as i64 {
+            u16::MAX
+        } else {
+            num_samples as u16
+        }
+    }
+}
+
+/// A coordinator heard during an active or passive scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanDescriptor {
+    /// The PAN ID of the coordinator.
+    pub coord_pan_id: u16,
+    /// The short or extended address of the coordinator (little endian).
+    pub coord_address: Vec<u8, 8>,
+    /// The channel on which the beacon was received.
+    pub channel: Channel,
+    /// The Superframe Specification of the beacon, absent in Enhanced
+    /// Beacons.
+    pub superframe_specification: Option<[u8; 2]>,
+    /// Whether the beacon was an Enhanced Beacon.
+    pub enhanced: bool,
+    /// The received signal strength of the beacon in dBm.
+    pub rssi: i8,
+}
+
+impl PanDescriptor {
+    /// Extracts the PAN descriptor from the given frame.
+    ///
+    /// Returns `None` if the frame is not a beacon.
+    ///
+    /// * `mpdu` - Received MPDU (without FCS)
+    /// * `channel` - The channel on which the frame was received
+    /// * `rssi` - The received signal strength in dBm
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::InvalidFrameVersion`] if the frame version is
+    ///   reserved,
+    /// - [`FrameErrorKind::SecurityNotSupported`] if the beacon is secured,
+    /// - [`FrameErrorKind::InvalidAddressingCombination`] if the source address
+    ///   or PAN ID is absent,
+    /// - any other error if the frame is truncated.
+    pub fn parse(mpdu: &[u8], channel: Channel, rssi: i8) -> Result<Option<Self>, FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        if frame_control.frame_type() != FrameType::Beacon {
+            return Ok(None);
+        }
+        if frame_control.security_enabled() {
+            return Err(FrameErrorKind::SecurityNotSupported.into());
+        }
+        let enhanced = match frame_control.frame_version() {
+            FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => false,
+            FrameVersion::Ieee802154 => true,
+            FrameVersion::Unknown => return Err(FrameErrorKind::InvalidFrameVersion.into()),
+        };
+
+        let header = MacHeader::parse(mpdu, frame_control)?;
+        let addressing_fields = header
+            .addressing_fields
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+        let coord_pan_id = addressing_fields
+            .src_pan_id()
+            .or(addressing_fields.dst_pan_id())
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?
+            .into_u16();
+        let coord_address = addressing_fields
+            .into_src_address()
+            .filter(|address| !address.is_absent())
+            // Safety: Addresses never exceed 8 bytes.
+            .map(|address| Vec::from_slice(address.as_le_bytes()).unwrap())
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+
+        // Enhanced Beacons convey the superframe structure in IEs, if at all.
+        let superframe_specification = if enhanced {
+            None
+        } else {
+            let beacon_fields =
+                BeaconFields::new(&mpdu[header.end..]).map_err(|e| e.shifted_by(header.end))?;
+            let mut superframe_specification = [0; 2];
+            superframe_specification
+                .copy_from_slice(beacon_fields.superframe_specification().into_inner());
+            Some(superframe_specification)
+        };
+
+        Ok(Some(Self {
+            coord_pan_id,
+            coord_address,
+            channel,
+            superframe_specification,
+            enhanced,
+            rssi,
+        }))
+    }
+
+    /// Return the Superframe Specification of the beacon, if present.
+    pub fn superframe_specification(&self) -> Option<SuperframeSpecification<&[u8]>> {
+        self.superframe_specification
+            .as_ref()
+            .map(|bytes| SuperframeSpecification::new_unchecked(&bytes[..]))
+    }
+
+    /// Return whether the descriptor describes the same coordinator as the
+    /// given one.
+    fn is_same_coordinator(&self, other: &Self) -> bool {
+        self.coord_pan_id == other.coord_pan_id
+            && self.coord_address == other.coord_address
+            && self.channel == other.channel
+    }
 }
 
 pub struct ScanConfirm {
     scan_type: ScanType,
-    channel_page: u8,
+    channel_page: ChannelPage,
+    /// Channels that were not scanned because the PAN descriptor list was
+    /// full.
+    unscanned_channels: Vec<Channel, MAX_SCAN_CHANNELS>,
+    /// Per-channel energy statistics, only populated for ED scans.
+    energy_detect_list: Vec<EnergyDetectionResult, MAX_SCAN_CHANNELS>,
+    /// The coordinators heard, only populated for active and passive scans.
+    pan_descriptor_list: Vec<PanDescriptor, MAX_PAN_DESCRIPTORS>,
 }
+
+impl ScanConfirm {
+    /// The type of the scan.
+    pub fn scan_type(&self) -> ScanType {
+        self.scan_type
+    }
+
+    /// The channel page that was scanned.
+    pub fn channel_page(&self) -> ChannelPage {
+        self.channel_page
+    }
+
+    /// The channels that were not scanned because the PAN descriptor list was
+    /// full.
+    pub fn unscanned_channels(&self) -> &[Channel] {
+        &self.unscanned_channels
+    }
+
+    /// The per-channel energy statistics of an ED scan.
+    pub fn energy_detect_list(&self) -> &[EnergyDetectionResult] {
+        &self.energy_detect_list
+    }
+
+    /// The coordinators heard during an active or passive scan.
+    pub fn pan_descriptor_list(&self) -> &[PanDescriptor] {
+        &self.pan_descriptor_list
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScanError {
     // TODO: not supported
     LimitReached,
-    // TODO: not supported
     NoBeacon,
     // TODO: not supported
     ScanInProgress,
@@ -33,34 +359,620 @@ pub enum ScanError {
     CounterError,
     // TODO: not supported
     FrameTooLong,
-    // TODO: not supported
     BadChannel,
-    // TODO: not supported
     InvalidParameter,
 }
 
+/// Build a Beacon Request command, see the module documentation.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `enhanced` - Whether to request Enhanced Beacons
+pub fn beacon_request_frame(
+    seq_nr: u8,
+    enhanced: bool,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let builder = FrameBuilder::new(FrameType::MacCommand).with_sequence_number(seq_nr);
+    let builder = if enhanced {
+        builder.with_enhanced_frame_version()
+    } else {
+        builder
+    };
+    let builder = builder
+        .with_addressing(
+            Some((PanId::from_u16(0xffff), Address::<&[u8]>::BROADCAST_ADDR)),
+            None,
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&[BEACON_REQUEST_COMMAND_ID]);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// Scans the given channels, see the module documentation.
+///
+/// The scan stops early once [`MAX_PAN_DESCRIPTORS`] coordinators were heard.
+/// The remaining channels are then reported as unscanned, as are channels the
+/// radio cannot tune to.
+///
+/// Scans of more than [`MAX_SCAN_CHANNELS`] channels are rejected with
+/// [`ScanError::InvalidParameter`], a single channel of another channel page
+/// with [`ScanError::BadChannel`].
+///
+/// * `radio` - The radio performing the scan
+/// * `scan_type` - The type of the scan (orphan scans are run by
+///   [`orphan_scan()`](super::realign::orphan_scan))
+/// * `scan_channels` - The channels to scan
+/// * `scan_duration` - The time spent on each channel, see
+///   [`channel_scan_duration()`]
+/// * `channel_page` - The channel page and band of the channels to scan
+/// * `ed_scan_config` - Sampling of ED scans, ignored for other scan types
+/// * `dsn` - macDsn, used to number Beacon Request commands
+pub async fn scan<Timer: RadioTimerApi, R: Radio<Timer = Timer>>(
+    radio: &mut MacRadio<R>,
+    scan_type: ScanType,
+    scan_channels: ScanChannels,
+    scan_duration: u8,
+    channel_page: ChannelPage,
+    ed_scan_config: EdScanConfig,
+    dsn: &mut SequenceNumber,
+) -> Result<ScanConfirm, ScanError> {
+    if scan_duration > MAX_SCAN_DURATION
+        || scan_channels.channels(channel_page).count() > MAX_SCAN_CHANNELS
+        || ed_scan_config.sample_interval.ticks() <= 0
+        || scan_type == ScanType::Orphan
+    {
+        return Err(ScanError::InvalidParameter);
+    }
+    if let ScanChannels::Single(channel) = scan_channels {
+        if channel.page() != channel_page {
+            return Err(ScanError::BadChannel);
+        }
+    }
+
+    let mut confirm = ScanConfirm {
+        scan_type,
+        channel_page,
+        unscanned_channels: Vec::new(),
+        energy_detect_list: Vec::new(),
+        pan_descriptor_list: Vec::new(),
+    };
+    let dwell: Duration<Timer> = channel_scan_duration(scan_duration).convert_into_rounding_up();
+    let mut frame = FrameBuffer::new();
+    for channel in scan_channels.channels(channel_page) {
+        if confirm.pan_descriptor_list.is_full() {
+            // Safety: At most MAX_SCAN_CHANNELS channels are scanned.
+            let _ = confirm.unscanned_channels.push(channel);
+            continue;
+        }
+        if radio.set_channel(channel).is_err() {
+            // Safety: At most MAX_SCAN_CHANNELS channels are scanned.
+            let _ = confirm.unscanned_channels.push(channel);
+            continue;
+        }
+
+        let beacon_request = match scan_type {
+            ScanType::Ed => {
+                let result = energy_detection(
+                    radio,
+                    channel,
+                    ed_scan_config.samples_per_channel(scan_duration),
+                    ed_scan_config.sample_interval.convert_into_rounding_up(),
+                )
+                .await;
+                // Safety: At most MAX_SCAN_CHANNELS channels are scanned.
+                let _ = confirm.energy_detect_list.push(result);
+                continue;
+            }
+            ScanType::Active => Some(false),
+            ScanType::EnhancedActiveScan => Some(true),
+            _ => None,
+        };
+        if let Some(enhanced) = beacon_request {
+            // Safety: The Beacon Request command always fits into the buffer.
+            let mpdu = beacon_request_frame(dsn.next(), enhanced).unwrap();
+            radio.transmit_now(&mpdu).await;
+        }
+
+        let until = Timer::now() + dwell;
+        while let Some(rx) = radio.receive_until(&mut frame, until).await {
+            let descriptor = match PanDescriptor::parse(&frame, channel, rx.rssi) {
+                Ok(Some(descriptor)) => descriptor,
+                Err(e) if e.kind() == FrameErrorKind::SecurityNotSupported => {
+                    MAC_COUNTERS.security_failures.increment();
+                    continue;
+                }
+                _ => continue,
+            };
+            if confirm
+                .pan_descriptor_list
+                .iter()
+                .any(|known| known.is_same_coordinator(&descriptor))
+            {
+                continue;
+            }
+            // Safety: We stop listening once the list is full.
+            let _ = confirm.pan_descriptor_list.push(descriptor);
+            if confirm.pan_descriptor_list.is_full() {
+                break;
+            }
+        }
+    }
+
+    if scan_type != ScanType::Ed && confirm.pan_descriptor_list.is_empty() {
+        return Err(ScanError::NoBeacon);
+    }
+    Ok(confirm)
+}
+
+/// Takes the given number of ED samples on the given channel, one per sample
+/// interval.
+async fn energy_detection<Timer: RadioTimerApi, R: Radio<Timer = Timer>>(
+    radio: &mut MacRadio<R>,
+    channel: Channel,
+    num_samples: u16,
+    sample_interval: Duration<Timer>,
+) -> EnergyDetectionResult {
+    let mut result = EnergyDetectionResult::new(channel);
+    let mut next_sample = Timer::now();
+    for _ in 0..num_samples {
+        if next_sample > Timer::now() {
+            Timer::wait_for_alarm_at(next_sample).await;
+        }
+        result.add_sample(radio.energy_level().await);
+        next_sample = next_sample + sample_interval;
+    }
+    result
+}
+
 impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
-    /// Initiates a channel scan over a given set of channels.
-    pub(crate) async fn mlme_scan_request(
+    /// Initiates a channel scan over a given set of channels, see [`scan()`].
+    ///
+    /// The scan needs the radio for itself, i.e. a [`MacRadio`] that is not
+    /// shared with the driver service while scanning. Beacon Request commands are numbered
+    /// with macDsn.
+    ///
+    /// * `radio` - The radio performing the scan
+    /// * `scan_type` - The type of the scan
+    /// * `scan_channels` - The channels to scan
+    /// * `scan_duration` - The time spent on each channel, see
+    ///   [`channel_scan_duration()`]
+    /// * `channel_page` - The channel page and band of the channels to scan
+    /// * `ed_scan_config` - Sampling of ED scans, ignored for other scan types
+    pub(crate) async fn mlme_scan_request<R: Radio<Timer = RadioDriverImpl::Timer>>(
         &self,
-        _scan_type: ScanType,
-        _scan_channels: ScanChannels,
-        _scan_duration: u8,
-        _channel_page: u8,
+        radio: &mut MacRadio<R>,
+        scan_type: ScanType,
+        scan_channels: ScanChannels,
+        scan_duration: u8,
+        channel_page: ChannelPage,
+        ed_scan_config: EdScanConfig,
     ) -> Result<ScanConfirm, ScanError> {
-        Err(ScanError::InvalidParameter)
+        // The PIB must not stay borrowed while scanning.
+        let mut dsn = self.pib.borrow().dsn;
+        let result = scan(
+            radio,
+            scan_type,
+            scan_channels,
+            scan_duration,
+            channel_page,
+            ed_scan_config,
+            &mut dsn,
+        )
+        .await;
+        self.pib.borrow_mut().dsn = dsn;
+        result
     }
 }
 
-// Implement IntoIterator for Channels so you can write: for x in scan_channels { ... }
-impl IntoIterator for ScanChannels {
-    type Item = u8;
-    type IntoIter = RangeInclusive<u8>;
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, SunChannelPlan},
+            link_quality::ed_from_dbm,
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+            time::Instant,
+        },
+        mac::test_helpers::{
+            mac_radio, poll_ready, received, timed_out, transmitted, with_mac_service,
+            TEST_CAPABILITIES,
+        },
+    };
 
-    fn into_iter(self) -> Self::IntoIter {
-        match self {
-            ScanChannels::All => 11_u8..=26,
-            ScanChannels::Single(ch) => ch..=ch,
+    /// Beacon (2006) of coordinator 0x0001 in PAN 0xabcd permitting
+    /// association.
+    const BEACON: [u8; 11] = [
+        0x00, 0x90, 0x01, 0xcd, 0xab, 0x01, 0x00, 0xff, 0xcf, 0x00, 0x00,
+    ];
+    /// Enhanced Beacon of coordinator 0x0002 in PAN 0xabcd.
+    const EB: [u8; 7] = [0x00, 0xa0, 0x05, 0xcd, 0xab, 0x02, 0x00];
+    /// Data frame (2006) from 0x0001 to 0x0002.
+    const DATA: [u8; 10] = [0x41, 0x98, 0x01, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11];
+
+    /// 1920 symbols of 16µs each.
+    const CHANNEL_SCAN_DURATION: u64 = 30_720;
+
+    /// Radio replaying the given frames on their channel.
+    struct ScriptedRadio<'a> {
+        frames: core::iter::Peekable<core::slice::Iter<'a, (Channel, &'a [u8])>>,
+        channel: Channel,
+        ed: i8,
+        transmitted: Vec<(Channel, u8), MAX_SCAN_CHANNELS>,
+    }
+
+    impl<'a> ScriptedRadio<'a> {
+        fn new(frames: &'a [(Channel, &'a [u8])]) -> MacRadio<Self> {
+            mac_radio(Self {
+                frames: frames.iter().peekable(),
+                channel: Channel::_11,
+                ed: -75,
+                transmitted: Vec::new(),
+            })
+        }
+    }
+
+    impl Radio for ScriptedRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+            self.channel = channel;
+            Ok(())
         }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            self.transmitted.push((self.channel, mpdu[2])).unwrap();
+            transmitted(TestClock::now())
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            let channel = self.channel;
+            match self
+                .frames
+                .next_if(|(frame_channel, _)| *frame_channel == channel)
+            {
+                Some((_, mpdu)) => received(buffer, mpdu),
+                None => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            TestClock::advance(Duration::new(128));
+            self.ed += 10;
+            Ok(self.ed)
+        }
+    }
+
+    fn run_scan(
+        radio: &mut MacRadio<ScriptedRadio>,
+        scan_type: ScanType,
+        scan_channels: ScanChannels,
+        dsn: &mut SequenceNumber,
+    ) -> Result<ScanConfirm, ScanError> {
+        TestClock::reset();
+        let mut scan = pin!(scan(
+            radio,
+            scan_type,
+            scan_channels,
+            0,
+            ChannelPage::Oqpsk2450Mhz,
+            EdScanConfig::default(),
+            dsn
+        ));
+        let mut cx = Context::from_waker(Waker::noop());
+        match scan.as_mut().poll(&mut cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("beacon scans must not wait for alarms"),
+        }
+    }
+
+    #[test]
+    fn pan_descriptor() {
+        let descriptor = PanDescriptor::parse(&BEACON, Channel::_11, -40)
+            .unwrap()
+            .unwrap();
+        assert_eq!(descriptor.coord_pan_id, 0xabcd);
+        assert_eq!(descriptor.coord_address[..], [0x01, 0x00]);
+        assert!(!descriptor.enhanced);
+        let superframe_specification = descriptor.superframe_specification().unwrap();
+        assert_eq!(superframe_specification.beacon_order(), 15);
+        assert!(superframe_specification.pan_coordinator());
+        assert!(superframe_specification.association_permit());
+
+        let descriptor = PanDescriptor::parse(&EB, Channel::_11, -40)
+            .unwrap()
+            .unwrap();
+        assert_eq!(descriptor.coord_address[..], [0x02, 0x00]);
+        assert!(descriptor.enhanced);
+        assert!(descriptor.superframe_specification().is_none());
+
+        assert_eq!(PanDescriptor::parse(&DATA, Channel::_11, -40), Ok(None));
+        assert!(PanDescriptor::parse(&BEACON[..8], Channel::_11, -40).is_err());
+    }
+
+    #[test]
+    fn beacon_request() {
+        let mpdu = beacon_request_frame(3, false).unwrap();
+        assert_eq!(mpdu[..], [0x03, 0x18, 0x03, 0xff, 0xff, 0xff, 0xff, 0x07]);
+        let mpdu = beacon_request_frame(3, true).unwrap();
+        assert_eq!(mpdu[..], [0x03, 0x28, 0x03, 0xff, 0xff, 0xff, 0xff, 0x07]);
+    }
+
+    #[test]
+    fn passive_scan() {
+        let frames = [
+            (Channel::_12, &BEACON[..]),
+            (Channel::_12, &BEACON[..]),
+            (Channel::_12, &DATA[..]),
+            (Channel::_15, &EB[..]),
+        ];
+        let mut radio = ScriptedRadio::new(&frames);
+        let mut dsn = SequenceNumber::new(0);
+        let confirm = run_scan(&mut radio, ScanType::Passive, ScanChannels::All, &mut dsn).unwrap();
+
+        // The repeated beacon is reported once.
+        let descriptors = confirm.pan_descriptor_list();
+        assert_eq!(descriptors.len(), 2);
+        assert_eq!(
+            (descriptors[0].channel, descriptors[0].enhanced),
+            (Channel::_12, false)
+        );
+        assert_eq!(
+            (descriptors[1].channel, descriptors[1].enhanced),
+            (Channel::_15, true)
+        );
+        assert!(confirm.unscanned_channels().is_empty());
+        assert!(confirm.energy_detect_list().is_empty());
+        assert!(radio.radio_mut().transmitted.is_empty());
+        assert_eq!(TestClock::now(), Instant::new(16 * CHANNEL_SCAN_DURATION));
+
+        let mut radio = ScriptedRadio::new(&[]);
+        assert!(matches!(
+            run_scan(
+                &mut radio,
+                ScanType::Passive,
+                ScanChannels::Single(Channel::_11),
+                &mut dsn
+            ),
+            Err(ScanError::NoBeacon)
+        ));
+        assert!(matches!(
+            run_scan(
+                &mut radio,
+                ScanType::Passive,
+                ScanChannels::Single(ChannelPage::Oqpsk915Mhz.channel(1).unwrap()),
+                &mut dsn
+            ),
+            Err(ScanError::BadChannel)
+        ));
+        assert!(matches!(
+            run_scan(
+                &mut radio,
+                ScanType::Orphan,
+                ScanChannels::Single(Channel::_11),
+                &mut dsn
+            ),
+            Err(ScanError::InvalidParameter)
+        ));
+    }
+
+    #[test]
+    fn scan_channel_pages() {
+        let page = ChannelPage::Sun(SunChannelPlan::EU_863MHZ);
+        assert_eq!(ScanChannels::All.channels(page).count(), 34);
+        let channel = page.channel(3).unwrap();
+        assert!(ScanChannels::Single(channel).channels(page).eq([channel]));
+
+        // The channels of SUN channel plans are scanned one by one.
+        let mut radio = ScriptedRadio::new(&[]);
+        let mut dsn = SequenceNumber::new(0);
+        let mut scan = pin!(scan(
+            &mut radio,
+            ScanType::Passive,
+            ScanChannels::All,
+            0,
+            page,
+            EdScanConfig::default(),
+            &mut dsn
+        ));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(matches!(
+            scan.as_mut().poll(&mut cx),
+            Poll::Ready(Err(ScanError::InvalidParameter))
+        ));
+    }
+
+    #[test]
+    fn active_scan() {
+        let frames = [(Channel::_20, &EB[..])];
+        let mut radio = ScriptedRadio::new(&frames);
+        let mut dsn = SequenceNumber::new(0xfe);
+        let confirm = run_scan(
+            &mut radio,
+            ScanType::EnhancedActiveScan,
+            ScanChannels::All,
+            &mut dsn,
+        )
+        .unwrap();
+        assert_eq!(confirm.pan_descriptor_list().len(), 1);
+        assert_eq!(confirm.pan_descriptor_list()[0].channel, Channel::_20);
+
+        // One Beacon Request per channel.
+        assert_eq!(radio.radio_mut().transmitted.len(), 16);
+        assert_eq!(radio.radio_mut().transmitted[0], (Channel::_11, 0xfe));
+        assert_eq!(radio.radio_mut().transmitted[2], (Channel::_13, 0x00));
+        assert_eq!(dsn.value(), 0x0e);
+    }
+
+    #[test]
+    fn scan_limit_reached() {
+        let beacons: [[u8; 11]; MAX_PAN_DESCRIPTORS + 1] = core::array::from_fn(|i| {
+            let mut beacon = BEACON;
+            beacon[5] = i as u8;
+            beacon
+        });
+        let frames: Vec<(Channel, &[u8]), { MAX_PAN_DESCRIPTORS + 1 }> = beacons
+            .iter()
+            .map(|beacon| (Channel::_11, &beacon[..]))
+            .collect();
+        let mut radio = ScriptedRadio::new(&frames);
+        let mut dsn = SequenceNumber::new(0);
+        let confirm = run_scan(&mut radio, ScanType::Passive, ScanChannels::All, &mut dsn).unwrap();
+        assert_eq!(confirm.pan_descriptor_list().len(), MAX_PAN_DESCRIPTORS);
+        assert_eq!(
+            confirm.unscanned_channels(),
+            &ScanChannels::All
+                .channels(ChannelPage::Oqpsk2450Mhz)
+                .skip(1)
+                .collect::<Vec<Channel, 16>>()[..]
+        );
+    }
+
+    #[test]
+    fn ed_scan() {
+        TestClock::reset();
+        let mut radio = ScriptedRadio::new(&[]);
+        let mut dsn = SequenceNumber::new(0);
+        // Two samples per channel.
+        let ed_scan_config = EdScanConfig {
+            sample_interval: Duration::new(960),
+        };
+        let mut scan = pin!(scan(
+            &mut radio,
+            ScanType::Ed,
+            ScanChannels::Single(Channel::_15),
+            0,
+            ChannelPage::Oqpsk2450Mhz,
+            ed_scan_config,
+            &mut dsn
+        ));
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // 960 symbols of 16µs each.
+        assert!(scan.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(Instant::new(15_360)));
+        TestClock::advance(Duration::new(15_360 - 128));
+        let Poll::Ready(Ok(confirm)) = scan.as_mut().poll(&mut cx) else {
+            panic!("the ED scan must complete");
+        };
+
+        let results = confirm.energy_detect_list();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].channel(), Channel::_15);
+        assert_eq!(results[0].num_samples(), 2);
+        assert_eq!(
+            (results[0].min(), results[0].max()),
+            (Some(ed_from_dbm(-65)), Some(ed_from_dbm(-55)))
+        );
+        assert!(confirm.pan_descriptor_list().is_empty());
+    }
+
+    #[test]
+    fn mlme_scan_request() {
+        with_mac_service(0x2a, |mac_service| {
+            TestClock::reset();
+            let frames = [(Channel::_11, &BEACON[..])];
+            let mut radio = ScriptedRadio::new(&frames);
+            let confirm = poll_ready(mac_service.mlme_scan_request(
+                &mut radio,
+                ScanType::Active,
+                ScanChannels::Single(Channel::_11),
+                0,
+                ChannelPage::Oqpsk2450Mhz,
+                EdScanConfig::default(),
+            ))
+            .unwrap();
+            assert_eq!(confirm.pan_descriptor_list().len(), 1);
+            // The Beacon Request is numbered with macDsn.
+            assert_eq!(radio.radio_mut().transmitted[..], [(Channel::_11, 0x2a)]);
+            assert_eq!(mac_service.pib.borrow().dsn.value(), 0x2b);
+
+            // ED scans report the energy statistics of each channel.
+            let mut radio = ScriptedRadio::new(&[]);
+            let ed_scan_config = EdScanConfig {
+                sample_interval: Duration::new(1920),
+            };
+            let confirm = poll_ready(mac_service.mlme_scan_request(
+                &mut radio,
+                ScanType::Ed,
+                ScanChannels::All,
+                0,
+                ChannelPage::Oqpsk2450Mhz,
+                ed_scan_config,
+            ))
+            .unwrap();
+            let results = confirm.energy_detect_list();
+            assert_eq!(results.len(), 16);
+            assert_eq!(results[15].channel(), Channel::_26);
+            assert_eq!(results[15].num_samples(), 1);
+            // The energy on the last channel exceeds the range of ED values.
+            assert_eq!(results[15].avg(), Some(u8::MAX));
+            assert_eq!(mac_service.pib.borrow().dsn.value(), 0x2b);
+        });
+    }
+
+    #[test]
+    fn energy_detection_result() {
+        let mut result = EnergyDetectionResult::new(Channel::_11);
+        assert_eq!(result.channel(), Channel::_11);
+        assert_eq!(result.num_samples(), 0);
+        assert_eq!(result.min(), None);
+        assert_eq!(result.avg(), None);
+        assert_eq!(result.max(), None);
+
+        for ed in [10, 20, 40, 31] {
+            result.add_sample(ed);
+        }
+        assert_eq!(result.num_samples(), 4);
+        assert_eq!(result.min(), Some(10));
+        assert_eq!(result.avg(), Some(25));
+        assert_eq!(result.max(), Some(40));
+    }
+
+    #[test]
+    fn samples_per_channel() {
+        let config = EdScanConfig::default();
+        // 960 * (2^0 + 1) / 8
+        assert_eq!(config.samples_per_channel(0), 240);
+
+        let config = EdScanConfig {
+            sample_interval: Duration::new(1920),
+        };
+        assert_eq!(config.samples_per_channel(0), 1);
+
+        let config = EdScanConfig {
+            sample_interval: Duration::new(4000),
+        };
+        assert_eq!(config.samples_per_channel(0), 1);
     }
 }
@@ -1,20 +1,21 @@
 #![allow(dead_code)]
 use rand_core::RngCore;
 
-use crate::{driver::DriverConfig, mac::MacService};
+use crate::{
+    driver::DriverConfig,
+    mac::{pib::PibAttribute, MacService},
+};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetError {
+    ReadOnly,
+    // TODO: not supported
+    UnsupportedAttribute,
     InvalidParameter,
 }
 
-/// Attributes that may be written by an upper layer
-pub enum SetRequestAttribute {
-    // IEEE 802.15.4-2020, section 8.4.3.1, table 8-94
-    MacExtendedAddress([u8; 8]),
-    MacAssociationPermit(bool),
-    MacPanId(u16),
-    MacShortAddress(u16),
-}
+/// Attributes that may be written by an upper layer, see [`PibAttribute`].
+pub type SetRequestAttribute = PibAttribute;
 
 #[allow(dead_code)]
 impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
@@ -26,19 +27,6 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
         &self,
         attribute: &SetRequestAttribute,
     ) -> Result<(), SetError> {
-        let mut pib = self.pib.borrow_mut();
-        match attribute {
-            SetRequestAttribute::MacPanId(pan_id) => pib.pan_id.set_u16(*pan_id),
-            SetRequestAttribute::MacShortAddress(short_address) => {
-                pib.short_address = *short_address
-            }
-            SetRequestAttribute::MacExtendedAddress(extended_address) => {
-                pib.extended_address = Some(*extended_address)
-            }
-            SetRequestAttribute::MacAssociationPermit(association_permit) => {
-                pib.association_permit = *association_permit
-            }
-        }
-        Ok(())
+        self.pib.borrow_mut().set(*attribute)
     }
 }
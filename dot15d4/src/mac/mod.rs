@@ -1,10 +1,33 @@
+mod ack;
+pub mod counters;
+mod csl;
+mod csma;
+mod filter;
+mod indirect;
 mod mcps;
 mod mlme;
 mod neighbors;
 mod pib;
+mod power;
 pub mod primitives;
+mod radio;
+mod regulatory;
+mod retransmission;
+mod retry;
+mod rit;
+#[cfg(feature = "security")]
+mod security;
+mod sequence;
+mod superframe;
 mod task;
+#[cfg(test)]
+pub(crate) mod test_helpers;
+#[cfg(feature = "thread")]
+mod thread;
 mod tsch;
+mod tx_queue;
+#[cfg(feature = "zigbee")]
+mod zigbee;
 
 pub use dot15d4_frame as frame;
 
@@ -30,10 +53,12 @@ use crate::{
 };
 
 use self::{
-    frame::mpdu::MpduFrame,
+    counters::MAC_COUNTERS,
+    filter::FrameFilter,
     mcps::data::{DataIndication, DataIndicationTask, DataRequestTask},
     pib::Pib,
     primitives::{MacIndication, MacRequest},
+    sequence::{DuplicateFilter, SequenceNumber, DEFAULT_DUPLICATE_FILTER_SIZE},
     task::*,
 };
 
@@ -155,6 +180,9 @@ mac_svc_tasks!(DataRequest, DataIndication);
 /// the main event loop that handles interactions between an upper layer and the
 /// PHY sublayer. It uses channels to communicate with upper layer tasks and
 /// with radio drivers.
+///
+/// Internal: Run the MAC service via [`crate::Device::run()`] instead.
+#[doc(hidden)]
 pub struct MacService<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> {
     radio: PhantomData<RadioDriverImpl>,
     /// Pseudo-random number generator
@@ -169,6 +197,10 @@ pub struct MacService<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> {
     driver_request_sender: DriverRequestSender<'svc>,
     /// PAN Information Base
     pib: RefCell<Pib>,
+    /// Duplicate detection of incoming frames
+    duplicate_filter: RefCell<DuplicateFilter<DEFAULT_DUPLICATE_FILTER_SIZE>>,
+    /// Filtering of incoming frames by frame type, PAN and address
+    frame_filter: FrameFilter,
 }
 
 impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, RadioDriverImpl> {
@@ -180,6 +212,14 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
         indication_sender: MacIndicationSender<'svc>,
         driver_request_sender: DriverRequestSender<'svc>,
     ) -> Self {
+        // The standard recommends to initialize macDsn and macBsn randomly.
+        let [dsn, bsn, ..] = rng.get_mut().next_u32().to_le_bytes();
+        let pib = Pib {
+            dsn: SequenceNumber::new(dsn),
+            bsn: SequenceNumber::new(bsn),
+            ..Default::default()
+        };
+
         Self {
             radio: PhantomData,
             rng,
@@ -187,7 +227,9 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
             request_receiver,
             indication_sender,
             driver_request_sender,
-            pib: RefCell::new(Pib::default()),
+            pib: RefCell::new(pib),
+            duplicate_filter: RefCell::new(DuplicateFilter::new()),
+            frame_filter: FrameFilter::default(),
         }
     }
 
@@ -391,7 +433,12 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
 
     fn create_request_task(&self, mac_request: MacRequest) -> MacSvcTask<'_, RadioDriverImpl> {
         match mac_request {
-            MacRequest::McpsDataRequest(data_request) => {
+            MacRequest::McpsDataRequest(mut data_request) => {
+                if !data_request.tx_options().seq_num_suppressed() {
+                    let dsn = self.pib.borrow_mut().dsn.next();
+                    // Safety: The sequence number is not suppressed.
+                    let _ = data_request.set_sequence_number(dsn);
+                }
                 MacSvcTask::DataRequest(DataRequestTask::new(data_request))
             }
             MacRequest::MlmeBeaconRequest(_) => todo!(),
@@ -407,12 +454,22 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
         match result {
             MacSvcTaskResult::DataRequest(task_result) => {
                 let recovered_radio_frame = match task_result {
-                    DataRequestResult::Sent(recovered_radio_frame) => recovered_radio_frame,
-                    DataRequestResult::CcaBusy(unsent_radio_frame)
-                    | DataRequestResult::Nack(unsent_radio_frame) => {
+                    DataRequestResult::Sent(recovered_radio_frame) => {
+                        MAC_COUNTERS.tx_success.increment();
+                        recovered_radio_frame
+                    }
+                    DataRequestResult::CcaBusy(unsent_radio_frame) => {
+                        MAC_COUNTERS.cca_busy.increment();
+                        MAC_COUNTERS.tx_channel_access_failure.increment();
                         // TODO: CSMA/CA or Retry.
                         unsent_radio_frame.forget_size::<RadioDriverImpl>()
                     }
+                    DataRequestResult::Nack(unsent_radio_frame) => {
+                        MAC_COUNTERS.tx_no_ack.increment();
+                        // TODO: CSMA/CA or Retry.
+                        unsent_radio_frame.forget_size::<RadioDriverImpl>()
+                    }
+                    DataRequestResult::Rejected(unsent_radio_frame, _) => unsent_radio_frame,
                 };
 
                 // Safety: Clients must allocate buffers from the MAC's
@@ -438,23 +495,47 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
 
     fn handle_indication_task_result(&self, result: MacSvcTaskResult<RadioDriverImpl>) {
         match result {
-            MacSvcTaskResult::DataIndication(DataIndication { mpdu, .. }) => {
-                self.handle_incoming_mpdu(mpdu);
+            MacSvcTaskResult::DataIndication(data_indication) => {
+                self.handle_incoming_mpdu(data_indication);
             }
             // The rest are requests
             _ => unreachable!(),
         }
     }
 
-    fn handle_incoming_mpdu(&self, mpdu: MpduFrame) {
+    fn handle_incoming_mpdu(&self, data_indication: DataIndication) {
         // TODO: Implement proper handling of incoming frames.
-        match mpdu.frame_control().frame_type() {
-            FrameType::Data => {
+
+        // The driver only filters on the hardware address, if at all.
+        let accepted = self
+            .frame_filter
+            .filter_frame::<RadioDriverImpl>(&self.pib.borrow(), &data_indication.mpdu);
+        if accepted.is_err() {
+            // Safety: Incoming frames are allocated by the MAC service itself.
+            unsafe {
+                self.buffer_allocator
+                    .deallocate_buffer(data_indication.mpdu.into_buffer());
+            }
+            return;
+        }
+
+        let frame_type = data_indication.mpdu.frame_control().frame_type();
+        MAC_COUNTERS.count_rx(frame_type);
+        let is_duplicate = frame_type == FrameType::Data
+            && self
+                .duplicate_filter
+                .borrow_mut()
+                .is_duplicate_frame(&data_indication.mpdu);
+        if is_duplicate {
+            MAC_COUNTERS.duplicate_drops.increment();
+        }
+
+        match frame_type {
+            // Retransmitted frames have already been acknowledged by the
+            // driver. Duplicates are dropped below.
+            FrameType::Data if !is_duplicate => {
                 if let Some(request_token) = self.indication_sender.try_allocate_request_token() {
-                    let indication = MacIndication::McpsData(DataIndication {
-                        mpdu,
-                        timestamp: None,
-                    });
+                    let indication = MacIndication::McpsData(data_indication);
 
                     // TODO: Poll response, once we work with MAC response
                     //       primitives.
@@ -463,11 +544,13 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
                 } else {
                     // To avoid DoS we drop incoming packets if the upper layer
                     // is not able to ingest them fast enough.
+                    MAC_COUNTERS.queue_overflows.increment();
 
                     // Safety: Incoming frames are allocated by the
                     //         MAC service itself.
                     unsafe {
-                        self.buffer_allocator.deallocate_buffer(mpdu.into_buffer());
+                        self.buffer_allocator
+                            .deallocate_buffer(data_indication.mpdu.into_buffer());
                     }
                 }
 
@@ -478,9 +561,102 @@ impl<'svc, Rng: RngCore, RadioDriverImpl: DriverConfig> MacService<'svc, Rng, Ra
                 // Safety: Incoming frames are allocated by the
                 //         MAC service itself.
                 unsafe {
-                    self.buffer_allocator.deallocate_buffer(mpdu.into_buffer());
+                    self.buffer_allocator
+                        .deallocate_buffer(data_indication.mpdu.into_buffer());
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::num::NonZero;
+
+    use super::*;
+    use crate::{
+        driver::{
+            frame::{PanId, RadioFrame, RadioFrameRepr, RadioFrameUnsized},
+            DriverRequestChannel,
+        },
+        mac::{
+            frame::mpdu::MpduFrame,
+            test_helpers::{mac_buffer_allocator, FixedRng, TestDriverConfig},
+        },
+        util::frame::Frame,
+    };
+
+    /// Data frame (2006) from 0x0001 to 0x0002 in PAN 0xabcd.
+    const DATA: [u8; 10] = [0x41, 0x98, 0x01, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11];
+
+    fn data_indication(buffer_allocator: MacBufferAllocator, mpdu: &[u8]) -> DataIndication {
+        let buffer = buffer_allocator
+            .try_allocate_buffer(
+                RadioFrameRepr::<TestDriverConfig, RadioFrameUnsized>::new().max_buffer_length()
+                    as usize,
+            )
+            .unwrap();
+        let mut radio_frame = RadioFrame::new::<TestDriverConfig>(buffer)
+            .with_size(NonZero::new(mpdu.len() as u16).unwrap());
+        radio_frame.sdu_mut().copy_from_slice(mpdu);
+        let mut mpdu = MpduFrame::from_radio_frame(radio_frame);
+        mpdu.write_fcs::<TestDriverConfig>().unwrap();
+        DataIndication {
+            mpdu,
+            timestamp: None,
+            link_quality: None,
+        }
+    }
+
+    #[test]
+    fn incoming_frames_are_filtered() {
+        let buffer_allocator = mac_buffer_allocator();
+        let mut rng = Mutex::new(FixedRng(0));
+        let request_channel = MacRequestChannel::new();
+        let indication_channel = MacIndicationChannel::new();
+        let indication_receiver = indication_channel.receiver();
+        let driver_request_channel = DriverRequestChannel::new();
+        let mac_service = MacService::<_, TestDriverConfig>::new(
+            &mut rng,
+            buffer_allocator,
+            request_channel.receiver(),
+            indication_channel.sender(),
+            driver_request_channel.sender(),
+        );
+        {
+            let mut pib = mac_service.pib.borrow_mut();
+            pib.pan_id = PanId::from_u16(0xabcd);
+            pib.short_address = 0x0002;
+        }
+
+        // Foreign PAN
+        let mut mpdu = DATA;
+        mpdu[3..5].copy_from_slice(&[0x34, 0x12]);
+        mac_service.handle_incoming_mpdu(data_indication(buffer_allocator, &mpdu));
+        assert!(indication_receiver.try_receive_request(&()).is_none());
+
+        // Foreign address
+        let mut mpdu = DATA;
+        mpdu[5..7].copy_from_slice(&[0x03, 0x00]);
+        mac_service.handle_incoming_mpdu(data_indication(buffer_allocator, &mpdu));
+        assert!(indication_receiver.try_receive_request(&()).is_none());
+
+        // Corrupted FCS
+        let mut indication = data_indication(buffer_allocator, &DATA);
+        indication.mpdu.pdu_mut_wo_fcs()[DATA.len() - 1] ^= 0xff;
+        mac_service.handle_incoming_mpdu(indication);
+        assert!(indication_receiver.try_receive_request(&()).is_none());
+
+        mac_service.handle_incoming_mpdu(data_indication(buffer_allocator, &DATA));
+        let Some((response_token, MacIndication::McpsData(indication))) =
+            indication_receiver.try_receive_request(&())
+        else {
+            panic!("expected a data indication");
+        };
+        indication_receiver.received(response_token, ());
+        assert_eq!(indication.mpdu.pdu_ref_wo_fcs(), &DATA);
+
+        // Safety: The frame was allocated above.
+        unsafe { buffer_allocator.deallocate_buffer(indication.mpdu.into_buffer()) };
+    }
+}
@@ -1,7 +1,18 @@
 #![allow(dead_code)]
 
-use crate::driver::time::{Instant, Microseconds};
+use crate::driver::{
+    link_quality::LinkQuality,
+    time::{Instant, Microseconds},
+};
 
+/// The weight of a new sample in the link quality averages, as a power of two,
+/// i.e. 1/8.
+const LINK_QUALITY_WEIGHT_SHIFT: u32 = 3;
+
+/// The fractional bits of the link quality averages.
+const LINK_QUALITY_FRACTION_BITS: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TableError {
     Full,
 }
@@ -45,11 +56,146 @@ pub trait MacNeighbor {
     fn set_num_rx(&mut self, num_rx: u32);
 }
 
+/// Exponentially weighted moving average of the link quality of frames
+/// received from a neighbor.
+///
+/// Each new sample is weighted by 1/8. The first sample initializes the
+/// average.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkQualityAverage {
+    /// The averages of the RSSI, LQI and ED value in fixed point.
+    rssi: i32,
+    lqi: i32,
+    ed: i32,
+    /// The number of samples, saturating.
+    samples: u32,
+}
+
+impl LinkQualityAverage {
+    /// Creates a new average without samples.
+    pub const fn new() -> Self {
+        Self {
+            rssi: 0,
+            lqi: 0,
+            ed: 0,
+            samples: 0,
+        }
+    }
+
+    /// Add the link quality of a received frame to the average.
+    pub fn update(&mut self, sample: &LinkQuality) {
+        let update = |average: &mut i32, sample: i32| {
+            let sample = sample << LINK_QUALITY_FRACTION_BITS;
+            if self.samples == 0 {
+                *average = sample;
+            } else {
+                *average += (sample - *average) >> LINK_QUALITY_WEIGHT_SHIFT;
+            }
+        };
+        update(&mut self.rssi, sample.rssi as i32);
+        update(&mut self.lqi, sample.lqi as i32);
+        update(&mut self.ed, sample.ed as i32);
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    /// Return the average link quality, `None` if no frame was received.
+    pub fn get(&self) -> Option<LinkQuality> {
+        let round = |average: i32| {
+            (average + (1 << (LINK_QUALITY_FRACTION_BITS - 1))) >> LINK_QUALITY_FRACTION_BITS
+        };
+        (self.samples > 0).then(|| LinkQuality {
+            rssi: round(self.rssi) as i8,
+            lqi: round(self.lqi) as u8,
+            ed: round(self.ed) as u8,
+        })
+    }
+
+    /// Return the number of samples the average is made of.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+/// Table of the average link quality of the neighbors, e.g. to select a parent
+/// or a route.
+pub struct LinkQualityTable<const N: usize> {
+    neighbors: heapless::Vec<([u8; 8], LinkQualityAverage), N>,
+}
+
+impl<const N: usize> Default for LinkQualityTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> LinkQualityTable<N> {
+    /// Creates a new, empty [`LinkQualityTable`].
+    pub const fn new() -> Self {
+        Self {
+            neighbors: heapless::Vec::new(),
+        }
+    }
+
+    /// Record the link quality of a frame received from the given neighbor.
+    /// The neighbor is added to the table if needed.
+    ///
+    /// * `address` - Extended address of the sender (little endian)
+    /// * `sample` - Link quality of the received frame
+    pub fn update(
+        &mut self,
+        address: [u8; 8],
+        sample: &LinkQuality,
+    ) -> Result<&LinkQualityAverage, TableError> {
+        let index = match self.neighbors.iter().position(|(a, _)| *a == address) {
+            Some(index) => index,
+            None => {
+                self.neighbors
+                    .push((address, LinkQualityAverage::new()))
+                    .map_err(|_| TableError::Full)?;
+                self.neighbors.len() - 1
+            }
+        };
+        let (_, average) = &mut self.neighbors[index];
+        average.update(sample);
+        Ok(average)
+    }
+
+    /// Return the average link quality of the given neighbor.
+    pub fn get(&self, address: &[u8; 8]) -> Option<LinkQuality> {
+        self.neighbors
+            .iter()
+            .find(|(a, _)| a == address)
+            .and_then(|(_, average)| average.get())
+    }
+
+    /// Remove the given neighbor from the table.
+    pub fn remove(&mut self, address: &[u8; 8]) {
+        self.neighbors.retain(|(a, _)| a != address);
+    }
+
+    /// Return the neighbors and their average link quality.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8; 8], &LinkQualityAverage)> {
+        self.neighbors
+            .iter()
+            .map(|(address, average)| (address, average))
+    }
+
+    /// Return the neighbor with the highest average LQI, if any.
+    pub fn best(&self) -> Option<&[u8; 8]> {
+        self.iter()
+            .filter_map(|(address, average)| Some((address, average.get()?)))
+            .max_by_key(|(_, link_quality)| link_quality.lqi)
+            .map(|(address, _)| address)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::driver::time::{Instant, Microseconds};
 
-    use super::MacNeighbor;
+    use super::{LinkQualityAverage, LinkQualityTable, MacNeighbor, TableError};
+    use crate::driver::link_quality::LinkQuality;
+
     pub(crate) struct TestNeighbor {
         address: [u8; 8],
         last_tx: Instant<Microseconds>,
@@ -81,6 +227,12 @@ pub mod tests {
         }
     }
 
+    impl From<[u8; 8]> for TestNeighbor {
+        fn from(address: [u8; 8]) -> Self {
+            Self::new(address)
+        }
+    }
+
     impl MacNeighbor for TestNeighbor {
         fn address(&self) -> [u8; 8] {
             self.address
@@ -127,6 +279,52 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn link_quality_average() {
+        let mut average = LinkQualityAverage::new();
+        assert_eq!(average.get(), None);
+
+        average.update(&LinkQuality::new(-60, 0xc0));
+        assert_eq!(average.get(), Some(LinkQuality::new(-60, 0xc0)));
+
+        // A single bad frame only moves the average by 1/8.
+        average.update(&LinkQuality::new(-76, 0x40));
+        assert_eq!(
+            average.get(),
+            Some(LinkQuality {
+                rssi: -62,
+                lqi: 0xb0,
+                ed: 83
+            })
+        );
+        assert_eq!(average.samples(), 2);
+    }
+
+    #[test]
+    fn link_quality_table() {
+        let mut table = LinkQualityTable::<2>::new();
+        let nbr1 = [0, 0, 0, 0, 0, 0, 0, 1];
+        let nbr2 = [0, 0, 0, 0, 0, 0, 0, 2];
+        let nbr3 = [0, 0, 0, 0, 0, 0, 0, 3];
+        assert_eq!(table.best(), None);
+
+        table.update(nbr1, &LinkQuality::new(-80, 0x40)).unwrap();
+        table.update(nbr2, &LinkQuality::new(-60, 0xc0)).unwrap();
+        let average = table.update(nbr1, &LinkQuality::new(-80, 0x40)).unwrap();
+        assert_eq!(average.samples(), 2);
+        assert_eq!(table.get(&nbr1), Some(LinkQuality::new(-80, 0x40)));
+        assert_eq!(table.best(), Some(&nbr2));
+
+        assert_eq!(
+            table.update(nbr3, &LinkQuality::new(-50, 0xff)).err(),
+            Some(TableError::Full)
+        );
+        table.remove(&nbr2);
+        assert_eq!(table.get(&nbr2), None);
+        assert_eq!(table.best(), Some(&nbr1));
+        assert_eq!(table.iter().count(), 1);
+    }
+
     // pub(crate) struct TestNeighborTable<const N: usize> {
     //     neighbors: heapless::Vec<TestNeighbor, N>,
     // }
@@ -1,4 +1,144 @@
-use crate::driver::frame::PanId;
+//! MAC PAN Information Base (IEEE 802.15.4-2020, section 8.4.3).
+//!
+//! The upper layer reads and writes attributes with MLME-GET and MLME-SET,
+//! see [`Pib::get()`] and [`Pib::set()`]. Writes are validated against the
+//! ranges allowed by the standard. The CSMA-CA and TSCH configurations are
+//! derived from the PIB, see [`Pib::csma()`] and [`Pib::tsch()`]. A
+//! [`PibWatcher`] notices when they need to be derived anew.
+
+// TODO: Remove once the MAC engines derive their configuration from the PIB.
+#![allow(dead_code)]
+
+use crate::driver::{
+    frame::PanId,
+    time::{Duration, Microseconds},
+};
+
+use super::{
+    csma::CsmaConfig,
+    indirect::DEFAULT_TRANSACTION_PERSISTENCE_TIME,
+    mlme::{associate::DEFAULT_RESPONSE_WAIT_TIME, get::GetError, set::SetError},
+    sequence::SequenceNumber,
+    superframe::DEFAULT_BATT_LIFE_EXT_PERIODS,
+    tsch::TschConfig,
+};
+
+#[cfg(feature = "security")]
+use super::security::KeyTable;
+
+/// The max number of keys in macKeyTable, e.g. the current and the next key of
+/// a rollover for two key identifier modes.
+#[cfg(feature = "security")]
+pub const KEY_TABLE_SIZE: usize = 4;
+
+/// The range of macResponseWaitTime allowed by the standard.
+const RESPONSE_WAIT_TIME_RANGE: core::ops::RangeInclusive<u8> = 2..=64;
+
+/// The range of macBattLifeExtPeriods allowed by the standard.
+const BATT_LIFE_EXT_PERIODS_RANGE: core::ops::RangeInclusive<u8> = 6..=41;
+
+/// The highest Enhanced Beacon order, meaning that no periodic Enhanced
+/// Beacons are transmitted.
+const MAX_ENHANCED_BEACON_ORDER: u8 = 15;
+
+/// Identifies a MAC PIB attribute, e.g. in an MLME-GET request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PibAttributeId {
+    MacAckWaitDuration,
+    MacAssociatedPanCoord,
+    MacAssociationPermit,
+    MacAutoRequest,
+    MacBattLifeExt,
+    MacBattLifeExtPeriods,
+    MacBsn,
+    MacCoordExtendedAddress,
+    MacCoordShortAddress,
+    MacDsn,
+    MacEnhancedBeaconOrder,
+    MacExtendedAddress,
+    MacMaxBe,
+    MacMaxCsmaBackoffs,
+    MacMaxFrameRetries,
+    MacMaxFrameTotalWaitTime,
+    MacMinBe,
+    MacPanId,
+    MacPromiscuousMode,
+    MacResponseWaitTime,
+    MacRxOnWhenIdle,
+    MacSecurityEnabled,
+    MacShortAddress,
+    MacTimeslotTemplateId,
+    MacTransactionPersistenceTime,
+}
+
+/// A MAC PIB attribute and its value (IEEE 802.15.4-2020, tables 8-94 to
+/// 8-96).
+///
+/// Durations are given in microseconds as the symbol period depends on the
+/// PHY, all other times in the units of the standard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PibAttribute {
+    /// Read-only.
+    MacAckWaitDuration(Duration<Microseconds>),
+    MacAssociatedPanCoord(bool),
+    MacAssociationPermit(bool),
+    MacAutoRequest(bool),
+    MacBattLifeExt(bool),
+    MacBattLifeExtPeriods(u8),
+    MacBsn(u8),
+    MacCoordExtendedAddress(Option<[u8; 8]>),
+    MacCoordShortAddress(u16),
+    MacDsn(u8),
+    MacEnhancedBeaconOrder(u8),
+    MacExtendedAddress([u8; 8]),
+    MacMaxBe(u8),
+    MacMaxCsmaBackoffs(u8),
+    MacMaxFrameRetries(u8),
+    /// Read-only, derived from the CSMA-CA attributes.
+    MacMaxFrameTotalWaitTime(Duration<Microseconds>),
+    MacMinBe(u8),
+    MacPanId(u16),
+    MacPromiscuousMode(bool),
+    MacResponseWaitTime(u8),
+    MacRxOnWhenIdle(bool),
+    MacSecurityEnabled(bool),
+    MacShortAddress(u16),
+    MacTimeslotTemplateId(u8),
+    MacTransactionPersistenceTime(u16),
+}
+
+impl PibAttribute {
+    /// Return the identifier of the attribute.
+    pub fn id(&self) -> PibAttributeId {
+        match self {
+            Self::MacAckWaitDuration(_) => PibAttributeId::MacAckWaitDuration,
+            Self::MacAssociatedPanCoord(_) => PibAttributeId::MacAssociatedPanCoord,
+            Self::MacAssociationPermit(_) => PibAttributeId::MacAssociationPermit,
+            Self::MacAutoRequest(_) => PibAttributeId::MacAutoRequest,
+            Self::MacBattLifeExt(_) => PibAttributeId::MacBattLifeExt,
+            Self::MacBattLifeExtPeriods(_) => PibAttributeId::MacBattLifeExtPeriods,
+            Self::MacBsn(_) => PibAttributeId::MacBsn,
+            Self::MacCoordExtendedAddress(_) => PibAttributeId::MacCoordExtendedAddress,
+            Self::MacCoordShortAddress(_) => PibAttributeId::MacCoordShortAddress,
+            Self::MacDsn(_) => PibAttributeId::MacDsn,
+            Self::MacEnhancedBeaconOrder(_) => PibAttributeId::MacEnhancedBeaconOrder,
+            Self::MacExtendedAddress(_) => PibAttributeId::MacExtendedAddress,
+            Self::MacMaxBe(_) => PibAttributeId::MacMaxBe,
+            Self::MacMaxCsmaBackoffs(_) => PibAttributeId::MacMaxCsmaBackoffs,
+            Self::MacMaxFrameRetries(_) => PibAttributeId::MacMaxFrameRetries,
+            Self::MacMaxFrameTotalWaitTime(_) => PibAttributeId::MacMaxFrameTotalWaitTime,
+            Self::MacMinBe(_) => PibAttributeId::MacMinBe,
+            Self::MacPanId(_) => PibAttributeId::MacPanId,
+            Self::MacPromiscuousMode(_) => PibAttributeId::MacPromiscuousMode,
+            Self::MacResponseWaitTime(_) => PibAttributeId::MacResponseWaitTime,
+            Self::MacRxOnWhenIdle(_) => PibAttributeId::MacRxOnWhenIdle,
+            Self::MacSecurityEnabled(_) => PibAttributeId::MacSecurityEnabled,
+            Self::MacShortAddress(_) => PibAttributeId::MacShortAddress,
+            Self::MacTimeslotTemplateId(_) => PibAttributeId::MacTimeslotTemplateId,
+            Self::MacTransactionPersistenceTime(_) => PibAttributeId::MacTransactionPersistenceTime,
+        }
+    }
+}
 
 /// PAN Information Base (PIB) specified by MAC sublayer
 #[allow(dead_code)]
@@ -12,6 +152,21 @@ pub struct Pib {
     /// Indication of whether a coordinator is currently allowing association.
     /// If `true`, association is permitted.
     pub(crate) association_permit: bool,
+    /// Indication of whether a device automatically sends a Data Request
+    /// command if its address is listed in the pending address list of a
+    /// beacon from its coordinator. If `false`, the beacons are left to the
+    /// upper layer.
+    pub(crate) auto_request: bool,
+    /// Indication of whether battery life extension is used by the
+    /// coordinator, i.e. transmissions in the CAP are restricted to
+    /// macBattLifeExtPeriods backoff periods after the IFS following the
+    /// beacon. Announced in the beacons of the coordinator.
+    pub(crate) batt_life_ext: bool,
+    /// The number of backoff periods during which the receiver is enabled
+    /// after the IFS following a beacon if battery life extension is used.
+    pub(crate) batt_life_ext_periods: u8,
+    /// The sequence number added to the transmitted Beacon frame.
+    pub(crate) bsn: SequenceNumber,
     /// The address of the coordinator through which the device is associated.
     pub(crate) coord_extended_address: Option<[u8; 8]>,
     /// The short address assigned to the coordinator through which the device
@@ -19,17 +174,12 @@ pub struct Pib {
     /// only using its extended address. A value of 0xffff indicates that this
     /// value is unknown.
     pub(crate) coord_short_address: u16,
-    /// The maximum value of the backoff exponent, BE, in the CSMA-CA
-    /// algorithm.
-    pub(crate) max_be: u8,
-    /// The minimum value of the backoff exponent (BE) in the CSMA-CA
-    /// algorithm.
-    pub(crate) min_be: u8,
-    /// The maximum number of retries allowed after a transmission failure.
-    pub(crate) max_frame_retries: u8,
-    /// The maximum number of backoffs the CSMA-CA algorithm will attempt
-    /// before declaring a channel access failure.
-    pub(crate) max_csma_backoffs: u8,
+    /// The parameters of the CSMA-CA algorithm (macMinBe, macMaxBe,
+    /// macMaxCsmaBackoffs and macMaxFrameRetries).
+    pub(crate) csma: CsmaConfig,
+    /// The sequence number added to the transmitted Data frame or MAC
+    /// command.
+    pub(crate) dsn: SequenceNumber,
     /// The identifier of the PAN on which the device is operating. If this
     /// value is 0xffff, the device is not associated.
     pub(crate) pan_id: PanId<[u8; 2]>,
@@ -37,6 +187,10 @@ pub struct Pib {
     /// all) mode. A value of `true` indicates that the MAC sublayer accepts
     /// all frames received from the PHY.
     pub(crate) promiscuous_mode: bool,
+    /// The maximum time (in aBaseSuperframeDuration) a device waits for the
+    /// response to a request command before polling the coordinator, e.g.
+    /// for the Association Response command.
+    pub(crate) response_wait_time: u8,
     /// Indication of whether the MAC sublayer is to enable its receiver
     /// during idle periods. For a beacon-enabled PAN, this attribute is
     /// relevant only during the CAP of the incoming superframe. For a
@@ -46,15 +200,26 @@ pub struct Pib {
     /// of `true` indicates that security is enabled, while a value of `false`
     /// indicates that security is disabled.
     pub(crate) security_enabled: bool,
+    /// The keys used to secure and unsecure frames, including the keys of an
+    /// ongoing rollover.
+    #[cfg(feature = "security")]
+    pub(crate) key_table: KeyTable<KEY_TABLE_SIZE>,
     /// The address that the device uses to communicate in the PAN. If the
     /// device is the PAN coordinator, this value shall be chosen before a PAN
     /// is started. Otherwise, the short address is allocated by a coordinator
     /// during association.
     pub(crate) short_address: u16,
+    /// The maximum time (in unit periods) that a transaction is stored by a
+    /// coordinator and indicated in its beacon.
+    pub(crate) transaction_persistence_time: u16,
     /// Specification of how often the coordinator transmits an Enhanced
     /// Beacon frame. Value ranges from 0 to 15. If value is 15, no periodic
     /// Enhanced Beacon frame will be transmitted.
     pub(crate) enhanced_beacon_order: u8,
+    /// The configuration of the TSCH network incl. macTimeslotTemplateId.
+    pub(crate) tsch: TschConfig,
+    /// Incremented whenever an attribute changes, see [`PibWatcher`].
+    pub(crate) revision: u32,
 }
 
 impl Default for Pib {
@@ -65,18 +230,306 @@ impl Default for Pib {
             extended_address: None,
             associated_pan_coord: false,
             association_permit: false,
+            auto_request: true,
+            batt_life_ext: false,
+            batt_life_ext_periods: DEFAULT_BATT_LIFE_EXT_PERIODS,
+            bsn: SequenceNumber::default(),
             coord_extended_address: None,
             coord_short_address: 0xffff,
-            max_be: MAC_MAX_BE,
-            min_be: MAC_MIN_BE,
-            max_frame_retries: MAC_MAX_FRAME_RETRIES,
-            max_csma_backoffs: MAC_MAX_CSMA_BACKOFFS,
+            csma: CsmaConfig::default(),
+            dsn: SequenceNumber::default(),
             pan_id: MAC_PAN_ID,
             promiscuous_mode: false,
+            response_wait_time: DEFAULT_RESPONSE_WAIT_TIME,
             rx_on_when_idle: false,
             security_enabled: false,
+            #[cfg(feature = "security")]
+            key_table: KeyTable::new(),
             short_address: 0xffff,
+            transaction_persistence_time: DEFAULT_TRANSACTION_PERSISTENCE_TIME,
             enhanced_beacon_order: 0,
+            tsch: TschConfig::minimal_6tisch(),
+            revision: 0,
+        }
+    }
+}
+
+impl Pib {
+    /// Read the given attribute (MLME-GET).
+    ///
+    /// Returns [`GetError::UnsupportedAttribute`] if macExtendedAddress was not
+    /// assigned yet.
+    pub fn get(&self, attribute: PibAttributeId) -> Result<PibAttribute, GetError> {
+        use PibAttributeId::*;
+
+        Ok(match attribute {
+            MacAckWaitDuration => PibAttribute::MacAckWaitDuration(self.csma.ack_wait_duration),
+            MacAssociatedPanCoord => PibAttribute::MacAssociatedPanCoord(self.associated_pan_coord),
+            MacAssociationPermit => PibAttribute::MacAssociationPermit(self.association_permit),
+            MacAutoRequest => PibAttribute::MacAutoRequest(self.auto_request),
+            MacBattLifeExt => PibAttribute::MacBattLifeExt(self.batt_life_ext),
+            MacBattLifeExtPeriods => {
+                PibAttribute::MacBattLifeExtPeriods(self.batt_life_ext_periods)
+            }
+            MacBsn => PibAttribute::MacBsn(self.bsn.value()),
+            MacCoordExtendedAddress => {
+                PibAttribute::MacCoordExtendedAddress(self.coord_extended_address)
+            }
+            MacCoordShortAddress => PibAttribute::MacCoordShortAddress(self.coord_short_address),
+            MacDsn => PibAttribute::MacDsn(self.dsn.value()),
+            MacEnhancedBeaconOrder => {
+                PibAttribute::MacEnhancedBeaconOrder(self.enhanced_beacon_order)
+            }
+            MacExtendedAddress => PibAttribute::MacExtendedAddress(
+                self.extended_address
+                    .ok_or(GetError::UnsupportedAttribute)?,
+            ),
+            MacMaxBe => PibAttribute::MacMaxBe(self.csma.max_be),
+            MacMaxCsmaBackoffs => PibAttribute::MacMaxCsmaBackoffs(self.csma.max_csma_backoffs),
+            MacMaxFrameRetries => PibAttribute::MacMaxFrameRetries(self.csma.max_frame_retries),
+            MacMaxFrameTotalWaitTime => {
+                PibAttribute::MacMaxFrameTotalWaitTime(self.csma.max_frame_total_wait_time())
+            }
+            MacMinBe => PibAttribute::MacMinBe(self.csma.min_be),
+            MacPanId => PibAttribute::MacPanId(self.pan_id.into_u16()),
+            MacPromiscuousMode => PibAttribute::MacPromiscuousMode(self.promiscuous_mode),
+            MacResponseWaitTime => PibAttribute::MacResponseWaitTime(self.response_wait_time),
+            MacRxOnWhenIdle => PibAttribute::MacRxOnWhenIdle(self.rx_on_when_idle),
+            MacSecurityEnabled => PibAttribute::MacSecurityEnabled(self.security_enabled),
+            MacShortAddress => PibAttribute::MacShortAddress(self.short_address),
+            MacTimeslotTemplateId => {
+                PibAttribute::MacTimeslotTemplateId(self.tsch.timeslot_template_id)
+            }
+            MacTransactionPersistenceTime => {
+                PibAttribute::MacTransactionPersistenceTime(self.transaction_persistence_time)
+            }
+        })
+    }
+
+    /// Write the given attribute (MLME-SET).
+    ///
+    /// # Errors
+    ///
+    /// - [`SetError::ReadOnly`] if the attribute cannot be written,
+    /// - [`SetError::InvalidParameter`] if the value is out of the range
+    ///   allowed by the standard.
+    pub fn set(&mut self, attribute: PibAttribute) -> Result<(), SetError> {
+        if self.get(attribute.id()) == Ok(attribute) {
+            return Ok(());
         }
+
+        let mut csma = self.csma;
+        match attribute {
+            PibAttribute::MacAckWaitDuration(_) | PibAttribute::MacMaxFrameTotalWaitTime(_) => {
+                return Err(SetError::ReadOnly)
+            }
+            PibAttribute::MacAssociatedPanCoord(associated_pan_coord) => {
+                self.associated_pan_coord = associated_pan_coord
+            }
+            PibAttribute::MacAssociationPermit(association_permit) => {
+                self.association_permit = association_permit
+            }
+            PibAttribute::MacAutoRequest(auto_request) => self.auto_request = auto_request,
+            PibAttribute::MacBattLifeExt(batt_life_ext) => self.batt_life_ext = batt_life_ext,
+            PibAttribute::MacBattLifeExtPeriods(batt_life_ext_periods) => {
+                if !BATT_LIFE_EXT_PERIODS_RANGE.contains(&batt_life_ext_periods) {
+                    return Err(SetError::InvalidParameter);
+                }
+                self.batt_life_ext_periods = batt_life_ext_periods
+            }
+            PibAttribute::MacBsn(bsn) => self.bsn = SequenceNumber::new(bsn),
+            PibAttribute::MacCoordExtendedAddress(coord_extended_address) => {
+                self.coord_extended_address = coord_extended_address
+            }
+            PibAttribute::MacCoordShortAddress(coord_short_address) => {
+                self.coord_short_address = coord_short_address
+            }
+            PibAttribute::MacDsn(dsn) => self.dsn = SequenceNumber::new(dsn),
+            PibAttribute::MacEnhancedBeaconOrder(enhanced_beacon_order) => {
+                if enhanced_beacon_order > MAX_ENHANCED_BEACON_ORDER {
+                    return Err(SetError::InvalidParameter);
+                }
+                self.enhanced_beacon_order = enhanced_beacon_order
+            }
+            PibAttribute::MacExtendedAddress(extended_address) => {
+                self.extended_address = Some(extended_address)
+            }
+            PibAttribute::MacMaxBe(max_be) => csma.max_be = max_be,
+            PibAttribute::MacMaxCsmaBackoffs(max_csma_backoffs) => {
+                csma.max_csma_backoffs = max_csma_backoffs
+            }
+            PibAttribute::MacMaxFrameRetries(max_frame_retries) => {
+                csma.max_frame_retries = max_frame_retries
+            }
+            PibAttribute::MacMinBe(min_be) => csma.min_be = min_be,
+            PibAttribute::MacPanId(pan_id) => self.pan_id.set_u16(pan_id),
+            PibAttribute::MacPromiscuousMode(promiscuous_mode) => {
+                self.promiscuous_mode = promiscuous_mode
+            }
+            PibAttribute::MacResponseWaitTime(response_wait_time) => {
+                if !RESPONSE_WAIT_TIME_RANGE.contains(&response_wait_time) {
+                    return Err(SetError::InvalidParameter);
+                }
+                self.response_wait_time = response_wait_time
+            }
+            PibAttribute::MacRxOnWhenIdle(rx_on_when_idle) => {
+                self.rx_on_when_idle = rx_on_when_idle
+            }
+            PibAttribute::MacSecurityEnabled(security_enabled) => {
+                self.security_enabled = security_enabled
+            }
+            PibAttribute::MacShortAddress(short_address) => self.short_address = short_address,
+            PibAttribute::MacTimeslotTemplateId(timeslot_template_id) => {
+                self.tsch.timeslot_template_id = timeslot_template_id
+            }
+            PibAttribute::MacTransactionPersistenceTime(transaction_persistence_time) => {
+                self.transaction_persistence_time = transaction_persistence_time
+            }
+        }
+        if csma != self.csma {
+            csma.validate().map_err(|_| SetError::InvalidParameter)?;
+            self.csma = csma;
+        }
+
+        self.touch();
+        Ok(())
+    }
+
+    /// Return the configuration of the CSMA-CA algorithm.
+    pub fn csma(&self) -> &CsmaConfig {
+        &self.csma
+    }
+
+    /// Return the configuration of the TSCH network.
+    pub fn tsch(&self) -> &TschConfig {
+        &self.tsch
+    }
+
+    /// Return macKeyTable.
+    #[cfg(feature = "security")]
+    #[allow(dead_code)]
+    pub fn key_table(&self) -> &KeyTable<KEY_TABLE_SIZE> {
+        &self.key_table
+    }
+
+    /// Return macKeyTable for modification, e.g. to add the key of a
+    /// rollover.
+    #[cfg(feature = "security")]
+    #[allow(dead_code)]
+    pub fn key_table_mut(&mut self) -> &mut KeyTable<KEY_TABLE_SIZE> {
+        self.touch();
+        &mut self.key_table
+    }
+
+    /// Record a change of the attributes, see [`PibWatcher`].
+    pub(crate) fn touch(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+}
+
+/// Notices changes of the PIB, see the module documentation.
+///
+/// Sequence numbers are not considered attributes in this regard as they change
+/// with every frame.
+#[derive(Debug, Default)]
+pub struct PibWatcher {
+    /// The revision of the PIB seen last.
+    revision: Option<u32>,
+}
+
+impl PibWatcher {
+    /// Creates a new [`PibWatcher`] that has not seen the PIB yet.
+    pub const fn new() -> Self {
+        Self { revision: None }
+    }
+
+    /// Return the PIB if it changed since the last call, i.e. always on the
+    /// first call.
+    pub fn changed<'pib>(&mut self, pib: &'pib Pib) -> Option<&'pib Pib> {
+        if self.revision == Some(pib.revision) {
+            return None;
+        }
+        self.revision = Some(pib.revision);
+        Some(pib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_set() {
+        let mut pib = Pib::default();
+        assert_eq!(
+            pib.get(PibAttributeId::MacExtendedAddress),
+            Err(GetError::UnsupportedAttribute)
+        );
+        pib.set(PibAttribute::MacExtendedAddress([0x01; 8]))
+            .unwrap();
+        assert_eq!(
+            pib.get(PibAttributeId::MacExtendedAddress),
+            Ok(PibAttribute::MacExtendedAddress([0x01; 8]))
+        );
+
+        pib.set(PibAttribute::MacPanId(0xabcd)).unwrap();
+        assert_eq!(pib.pan_id.into_u16(), 0xabcd);
+        pib.set(PibAttribute::MacDsn(0x2a)).unwrap();
+        assert_eq!(pib.dsn.next(), 0x2a);
+        assert_eq!(
+            pib.get(PibAttributeId::MacDsn),
+            Ok(PibAttribute::MacDsn(0x2b))
+        );
+
+        pib.set(PibAttribute::MacMinBe(5)).unwrap();
+        assert_eq!(pib.csma().min_be, 5);
+        // (2^5 - 1) * 4 * 20 + 266 symbols of 16µs each.
+        assert_eq!(
+            pib.get(PibAttributeId::MacMaxFrameTotalWaitTime),
+            Ok(PibAttribute::MacMaxFrameTotalWaitTime(Duration::new(
+                2746 * 16
+            )))
+        );
+
+        pib.set(PibAttribute::MacTimeslotTemplateId(1)).unwrap();
+        assert_eq!(pib.tsch().timeslot_timings().id(), 1);
+    }
+
+    #[test]
+    fn set_validation() {
+        let mut pib = Pib::default();
+        for attribute in [
+            PibAttribute::MacMinBe(6),
+            PibAttribute::MacMaxBe(2),
+            PibAttribute::MacMaxCsmaBackoffs(6),
+            PibAttribute::MacMaxFrameRetries(8),
+            PibAttribute::MacEnhancedBeaconOrder(16),
+            PibAttribute::MacResponseWaitTime(1),
+            PibAttribute::MacBattLifeExtPeriods(5),
+        ] {
+            assert_eq!(pib.set(attribute), Err(SetError::InvalidParameter));
+        }
+        assert_eq!(
+            pib.set(PibAttribute::MacAckWaitDuration(Duration::new(100))),
+            Err(SetError::ReadOnly)
+        );
+        assert_eq!(*pib.csma(), CsmaConfig::default());
+        assert_eq!(pib.revision, 0);
+    }
+
+    #[test]
+    fn pib_watcher() {
+        let mut pib = Pib::default();
+        let mut watcher = PibWatcher::new();
+        assert!(watcher.changed(&pib).is_some());
+        assert!(watcher.changed(&pib).is_none());
+
+        // Writing the current value is no change.
+        pib.set(PibAttribute::MacMaxBe(5)).unwrap();
+        assert!(watcher.changed(&pib).is_none());
+
+        pib.set(PibAttribute::MacMaxBe(4)).unwrap();
+        assert_eq!(watcher.changed(&pib).unwrap().csma().max_be, 4);
+        assert!(watcher.changed(&pib).is_none());
     }
 }
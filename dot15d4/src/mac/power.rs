@@ -0,0 +1,553 @@
+//! Runtime selection of the power saving mode of the MAC.
+//!
+//! A [`PowerSavingMode`] selects how the radio is duty cycled:
+//! - [`PowerSavingMode::AlwaysOn`]: The receiver is on whenever the radio is
+//!   idle, frames are transmitted with unslotted CSMA-CA, see [`CsmaMac`].
+//! - [`PowerSavingMode::Csl`]: Coordinated sampled listening, see
+//!   [`CslReceiver`] and [`CslTransmitter`].
+//! - [`PowerSavingMode::Rit`]: Receiver initiated transmission, see
+//!   [`RitReceiver`] and [`RitTransmitter`].
+//! - [`PowerSavingMode::Tsch`]: Frames are transmitted and received in the
+//!   cells of a TSCH schedule, see [`TschExecutor`](super::tsch::TschExecutor).
+//!
+//! [`DutyCycle`] switches between the modes at runtime while exposing the same
+//! data interface in all of them: [`DutyCycle::data_request()`] transmits a
+//! frame and confirms its outcome, [`DutyCycle::receive()`] receives the next
+//! frame. Applications thus select a mode without restructuring their code.
+//!
+//! In TSCH mode, data requests queue frames in [`DutyCycle::tsch_queues()`]
+//! which the radio of the TSCH executor serves, and receptions are left to the
+//! executor.
+#![allow(dead_code)]
+
+use rand_core::RngCore;
+
+use crate::driver::{
+    frame::{Address, ExtendedAddress, FrameControl, ShortAddress},
+    radio::Radio,
+    time::{Duration, Instant, Microseconds},
+    RadioTimerApi,
+};
+
+use super::{
+    ack::{AckFrame, MacHeader},
+    csl::{CslConfig, CslPeer, CslReceiver, CslTransmitter},
+    csma::{CsmaConfig, CsmaConfigError, CsmaMac, TxOutcome},
+    mcps::data::{DataError, TxParameters},
+    pib::Pib,
+    radio::MacRadio,
+    retransmission::TxReport,
+    rit::{rit_data_request_frame, RitConfig, RitReceiver, RitTransmitter},
+    tsch::{TschDestination, TschQueueError, TschQueues},
+};
+
+/// The window of a single reception while idle listening. Receptions are
+/// restarted at the end of each window.
+const IDLE_LISTENING_WINDOW: Duration<Microseconds> = Duration::new(1_000_000);
+
+/// The power saving mode of the MAC, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSavingMode {
+    /// Idle listening with CSMA-CA.
+    AlwaysOn,
+    /// Coordinated sampled listening. The receiver is always on if
+    /// macCslPeriod is 0.
+    Csl(CslConfig),
+    /// Receiver initiated transmission. The receiver is always on if
+    /// macRitPeriod is 0.
+    Rit(RitConfig),
+    /// Time slotted channel hopping.
+    Tsch,
+}
+
+/// The outcome of a successful [`DutyCycle::data_request()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataStatus {
+    /// The frame was transmitted and acknowledged if requested.
+    Sent {
+        /// Number of retransmissions before the frame was acknowledged.
+        retries: u8,
+    },
+    /// The frame was queued for transmission in the TSCH schedule.
+    Queued,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSavingError {
+    /// The CSMA-CA configuration is invalid.
+    InvalidConfig(CsmaConfigError),
+    /// Frames are still queued for the TSCH schedule.
+    FramesQueued,
+}
+
+impl From<CsmaConfigError> for PowerSavingError {
+    fn from(error: CsmaConfigError) -> Self {
+        Self::InvalidConfig(error)
+    }
+}
+
+/// The state of the current mode.
+enum ModeState<Timer: RadioTimerApi, const N: usize> {
+    AlwaysOn,
+    Csl {
+        receiver: Option<CslReceiver<Timer>>,
+        transmitter: CslTransmitter<Timer>,
+        /// The schedules of the receivers by address (little endian).
+        peers: heapless::LinearMap<heapless::Vec<u8, 8>, CslPeer<Timer>, N>,
+    },
+    Rit {
+        receiver: Option<RitReceiver<Timer>>,
+        transmitter: RitTransmitter<Timer>,
+    },
+    Tsch,
+}
+
+/// Duty cycles the radio in the selected [`PowerSavingMode`], see the module
+/// documentation.
+///
+/// Up to `N` CSL receivers are tracked and frames to up to `N` TSCH neighbors
+/// are queued, up to `Q` frames per neighbor.
+pub struct DutyCycle<Timer: RadioTimerApi, Rng: RngCore, const N: usize, const Q: usize> {
+    mode: PowerSavingMode,
+    state: ModeState<Timer, N>,
+    csma: CsmaMac<Timer, Rng>,
+    tsch_queues: TschQueues<AckFrame, N, Q>,
+}
+
+impl<Timer: RadioTimerApi, Rng: RngCore, const N: usize, const Q: usize>
+    DutyCycle<Timer, Rng, N, Q>
+{
+    /// Creates a new [`DutyCycle`] in the given mode.
+    ///
+    /// * `mode` - The initial power saving mode
+    /// * `csma_config` - The configuration of CSMA-CA, also providing
+    ///   macMaxFrameRetries and macAckWaitDuration to CSL and RIT
+    /// * `rng` - Source of the random backoffs
+    pub fn new(
+        mode: PowerSavingMode,
+        csma_config: CsmaConfig,
+        rng: Rng,
+    ) -> Result<Self, PowerSavingError> {
+        let csma = CsmaMac::new(csma_config, rng)?;
+        Ok(Self {
+            mode,
+            state: Self::mode_state(&mode, csma.config())?,
+            csma,
+            tsch_queues: TschQueues::default(),
+        })
+    }
+
+    /// Return the current power saving mode.
+    pub fn mode(&self) -> PowerSavingMode {
+        self.mode
+    }
+
+    /// Switches to the given power saving mode.
+    ///
+    /// CSL and RIT receivers start sampling immediately. The schedules of CSL
+    /// receivers learned before are forgotten.
+    ///
+    /// # Errors
+    ///
+    /// [`PowerSavingError::FramesQueued`] when leaving TSCH while frames are
+    /// queued, see [`DutyCycle::tsch_queues()`].
+    pub fn set_mode(&mut self, mode: PowerSavingMode) -> Result<(), PowerSavingError> {
+        if mode != PowerSavingMode::Tsch && !self.tsch_queues.is_empty() {
+            return Err(PowerSavingError::FramesQueued);
+        }
+        self.state = Self::mode_state(&mode, self.csma.config())?;
+        self.mode = mode;
+        Ok(())
+    }
+
+    /// Return the TSCH transmit queues.
+    pub fn tsch_queues(&mut self) -> &mut TschQueues<AckFrame, N, Q> {
+        &mut self.tsch_queues
+    }
+
+    fn mode_state(
+        mode: &PowerSavingMode,
+        csma_config: &CsmaConfig,
+    ) -> Result<ModeState<Timer, N>, PowerSavingError> {
+        Ok(match mode {
+            PowerSavingMode::AlwaysOn => ModeState::AlwaysOn,
+            PowerSavingMode::Csl(config) => ModeState::Csl {
+                receiver: CslReceiver::new(config, Timer::now()),
+                transmitter: CslTransmitter::new(config, csma_config)?,
+                peers: heapless::LinearMap::new(),
+            },
+            PowerSavingMode::Rit(config) => ModeState::Rit {
+                receiver: RitReceiver::new(config, Timer::now()),
+                transmitter: RitTransmitter::new(config, csma_config)?,
+            },
+            PowerSavingMode::Tsch => ModeState::Tsch,
+        })
+    }
+
+    /// Transmits the given frame (MCPS-DATA.request) in the current mode.
+    ///
+    /// The transmission parameters only apply to idle listening. In TSCH
+    /// mode, the frame is queued for the TSCH schedule.
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `frame` - The MPDU (without FCS) to transmit
+    /// * `parameters` - The transmission parameters of the frame
+    ///
+    /// # Errors
+    ///
+    /// - [`DataError::ChannelAccessFailure`] if CSMA-CA failed,
+    /// - [`DataError::NoAck`] if the frame was not acknowledged,
+    /// - [`DataError::DutyCycleExceeded`] if the frame exceeded the duty cycle
+    ///   budget of its band,
+    /// - [`DataError::TransactionExpired`] if no RIT Data Request command of
+    ///   the destination was received within macRitTxWaitDuration,
+    /// - [`DataError::TransactionOverflow`] if the TSCH queue of the
+    ///   destination is full,
+    /// - [`DataError::InvalidAddress`] if a frame for TSCH has a short
+    ///   destination address,
+    /// - [`DataError::InvalidParameter`] if the frame is malformed.
+    pub async fn data_request<R: Radio<Timer = Timer>>(
+        &mut self,
+        radio: &mut MacRadio<R>,
+        frame: &AckFrame,
+        parameters: &TxParameters,
+    ) -> Result<DataStatus, DataError> {
+        match &mut self.state {
+            ModeState::AlwaysOn => match self.csma.transmit_with(radio, frame, parameters).await {
+                TxOutcome::Success { retries } => Ok(DataStatus::Sent { retries }),
+                TxOutcome::ChannelAccessFailure => Err(DataError::ChannelAccessFailure),
+                TxOutcome::NoAck => Err(DataError::NoAck),
+                TxOutcome::DutyCycleExceeded => Err(DataError::DutyCycleExceeded),
+            },
+            ModeState::Csl {
+                transmitter, peers, ..
+            } => {
+                let dst_address = dst_address(frame)?
+                    // Safety: Addresses never exceed 8 bytes.
+                    .map(|address| heapless::Vec::from_slice(address.as_le_bytes()).unwrap());
+                let mut peer = dst_address
+                    .as_ref()
+                    .and_then(|dst_address| peers.remove(dst_address));
+                let report = transmitter.transmit(radio, &mut peer, frame).await;
+                if let (Some(dst_address), Some(peer)) = (dst_address, peer) {
+                    // Receivers that don't fit are reached with wake-up
+                    // sequences.
+                    let _ = peers.insert(dst_address, peer);
+                }
+                report
+                    .map_err(|_| DataError::InvalidParameter)
+                    .and_then(sent)
+            }
+            ModeState::Rit { transmitter, .. } => match transmitter.transmit(radio, frame).await {
+                Ok(Some(report)) => sent(report),
+                Ok(None) => Err(DataError::TransactionExpired),
+                Err(_) => Err(DataError::InvalidParameter),
+            },
+            ModeState::Tsch => {
+                let destination = match dst_address(frame)? {
+                    None => TschDestination::Broadcast,
+                    Some(Address::Extended(address)) => {
+                        // Safety: Extended addresses have 8 bytes.
+                        TschDestination::Unicast(address.as_ref().try_into().unwrap())
+                    }
+                    Some(_) => return Err(DataError::InvalidAddress),
+                };
+                let ack_requested = FrameControl::new(&frame[..])
+                    .map_err(|_| DataError::InvalidParameter)?
+                    .ack_request();
+                self.tsch_queues
+                    .enqueue(destination, frame.clone(), ack_requested)
+                    .map_err(|TschQueueError::Full| DataError::TransactionOverflow)?;
+                Ok(DataStatus::Queued)
+            }
+        }
+    }
+
+    /// Receives the next frame into the given buffer in the current mode and
+    /// returns the instant at which the reception ended.
+    ///
+    /// Returns `None` in TSCH mode, where frames are received in the cells of
+    /// the TSCH schedule.
+    ///
+    /// * `radio` - The radio receiving the frame
+    /// * `pib` - The PIB providing macPanId, the addresses of the device and
+    ///   macDsn for RIT Data Request commands
+    /// * `frame` - Buffer receiving the frame
+    pub async fn receive<R: Radio<Timer = Timer>>(
+        &mut self,
+        radio: &mut MacRadio<R>,
+        pib: &mut Pib,
+        frame: &mut AckFrame,
+    ) -> Option<Instant<Timer>> {
+        match &self.state {
+            ModeState::Csl {
+                receiver: Some(receiver),
+                ..
+            } => return Some(receiver.receive(radio, frame).await),
+            ModeState::Rit {
+                receiver: Some(receiver),
+                ..
+            } => {
+                if let Some(data_request) = rit_data_request(pib) {
+                    return Some(receiver.receive(radio, &data_request, frame).await);
+                }
+            }
+            ModeState::Tsch => return None,
+            _ => {}
+        }
+
+        let window: Duration<Timer> = IDLE_LISTENING_WINDOW.convert_into_rounding_up();
+        loop {
+            if let Some(rx) = radio.receive_until(frame, Timer::now() + window).await {
+                return Some(rx.end);
+            }
+        }
+    }
+}
+
+/// Return the destination address of the given frame, `None` if it is absent
+/// or the broadcast address.
+fn dst_address(mpdu: &[u8]) -> Result<Option<Address<&[u8]>>, DataError> {
+    let frame_control = FrameControl::new(mpdu).map_err(|_| DataError::InvalidParameter)?;
+    Ok(MacHeader::parse(mpdu, frame_control)
+        .map_err(|_| DataError::InvalidParameter)?
+        .addressing_fields
+        .and_then(|addressing_fields| addressing_fields.into_dst_address())
+        .filter(|address| !address.is_absent() && !address.is_broadcast()))
+}
+
+/// Confirms a CSL or RIT transmission.
+fn sent<Timer: RadioTimerApi>(report: TxReport<Timer>) -> Result<DataStatus, DataError> {
+    if report.is_success() {
+        Ok(DataStatus::Sent {
+            retries: report.retries(),
+        })
+    } else {
+        Err(DataError::NoAck)
+    }
+}
+
+/// Build the RIT Data Request command of the device, `None` if the device has
+/// no address.
+fn rit_data_request(pib: &mut Pib) -> Option<AckFrame> {
+    let short_address = pib.short_address.to_le_bytes();
+    let src_address = if pib.short_address < 0xfffe {
+        Address::Short(ShortAddress::new(&short_address[..]))
+    } else {
+        Address::Extended(ExtendedAddress::new(&pib.extended_address.as_ref()?[..]))
+    };
+    rit_data_request_frame(pib.dsn.next(), pib.pan_id, src_address).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            frame::PanId,
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+        },
+        mac::test_helpers::{
+            mac_radio, received, timed_out, transmitted, FixedRng, TEST_CAPABILITIES,
+        },
+    };
+
+    /// Data frame (2006) to an extended address requesting an ACK.
+    const UNICAST: [u8; 22] = [
+        0x61, 0xcc, 0x2a, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x08, 0x07,
+        0x06, 0x05, 0x04, 0x03, 0x02, 0x02, 0x11,
+    ];
+
+    /// Data frame (2006) to a short address requesting an ACK.
+    const SHORT_UNICAST: [u8; 10] = [0x61, 0x88, 0x2a, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00, 0x11];
+
+    /// Radio of a peer that acknowledges all frames.
+    #[derive(Default)]
+    struct PeerRadio {
+        /// The frame to receive once no ACK is pending.
+        rx: Option<AckFrame>,
+        /// The ACK to receive next.
+        ack: Option<AckFrame>,
+        /// The number of transmitted frames.
+        transmissions: usize,
+    }
+
+    impl Radio for PeerRadio {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            self.transmissions += 1;
+            if FrameControl::new(mpdu).unwrap().ack_request() {
+                self.ack = Some(AckFrame::from_slice(&[0x02, 0x00, mpdu[2]]).unwrap());
+            }
+            let start = TestClock::now();
+            TestClock::advance(Duration::new(500));
+            transmitted(start)
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            match self.ack.take().or_else(|| self.rx.take()) {
+                Some(frame) => {
+                    TestClock::advance(Duration::new(100));
+                    received(buffer, &frame)
+                }
+                None => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    #[test]
+    fn data_requests() {
+        TestClock::reset();
+        let mut duty_cycle = DutyCycle::<TestClock, _, 4, 2>::new(
+            PowerSavingMode::AlwaysOn,
+            CsmaConfig::default(),
+            FixedRng(0),
+        )
+        .unwrap();
+        let frame = AckFrame::from_slice(&UNICAST).unwrap();
+        let parameters = TxParameters::default();
+        let mut radio = mac_radio(PeerRadio::default());
+        let data_request = |duty_cycle: &mut DutyCycle<TestClock, FixedRng, 4, 2>,
+                            radio: &mut MacRadio<PeerRadio>| {
+            TestClock::block_on(duty_cycle.data_request(radio, &frame, &parameters))
+        };
+
+        // Idle listening.
+        assert_eq!(
+            data_request(&mut duty_cycle, &mut radio),
+            Ok(DataStatus::Sent { retries: 0 })
+        );
+        assert_eq!(radio.radio_mut().transmissions, 1);
+
+        // CSL: The first frame is preceded by a wake-up sequence.
+        let csl = PowerSavingMode::Csl(CslConfig::default());
+        duty_cycle.set_mode(csl).unwrap();
+        assert_eq!(duty_cycle.mode(), csl);
+        let mut radio = mac_radio(PeerRadio::default());
+        assert_eq!(
+            data_request(&mut duty_cycle, &mut radio),
+            Ok(DataStatus::Sent { retries: 0 })
+        );
+        assert!(radio.radio_mut().transmissions > 100);
+
+        // RIT: Frames wait for a RIT Data Request command of the destination.
+        duty_cycle
+            .set_mode(PowerSavingMode::Rit(RitConfig::default()))
+            .unwrap();
+        let mut radio = mac_radio(PeerRadio::default());
+        assert_eq!(
+            data_request(&mut duty_cycle, &mut radio),
+            Err(DataError::TransactionExpired)
+        );
+        let rit_data_request = rit_data_request_frame(
+            1,
+            PanId::from_u16(0xabcd),
+            Address::Extended(ExtendedAddress::new(&UNICAST[5..13])),
+        )
+        .unwrap();
+        radio.radio_mut().rx = Some(rit_data_request);
+        assert_eq!(
+            data_request(&mut duty_cycle, &mut radio),
+            Ok(DataStatus::Sent { retries: 0 })
+        );
+
+        // TSCH: Frames are queued.
+        duty_cycle.set_mode(PowerSavingMode::Tsch).unwrap();
+        assert_eq!(
+            data_request(&mut duty_cycle, &mut radio),
+            Ok(DataStatus::Queued)
+        );
+        let short_unicast = AckFrame::from_slice(&SHORT_UNICAST).unwrap();
+        assert_eq!(
+            TestClock::block_on(duty_cycle.data_request(&mut radio, &short_unicast, &parameters)),
+            Err(DataError::InvalidAddress)
+        );
+        let destination = TschDestination::Unicast(UNICAST[5..13].try_into().unwrap());
+        assert_eq!(duty_cycle.tsch_queues().len(&destination), 1);
+
+        // Queued frames must be served before leaving TSCH.
+        assert_eq!(
+            duty_cycle.set_mode(PowerSavingMode::AlwaysOn),
+            Err(PowerSavingError::FramesQueued)
+        );
+        duty_cycle.tsch_queues().remove(&destination);
+        duty_cycle.set_mode(PowerSavingMode::AlwaysOn).unwrap();
+    }
+
+    #[test]
+    fn receive() {
+        TestClock::reset();
+        let mut duty_cycle = DutyCycle::<TestClock, _, 4, 2>::new(
+            PowerSavingMode::Rit(RitConfig {
+                period: 10,
+                ..Default::default()
+            }),
+            CsmaConfig::default(),
+            FixedRng(0),
+        )
+        .unwrap();
+        let mut pib = Pib {
+            short_address: 0x1234,
+            ..Default::default()
+        };
+        let mut radio = mac_radio(PeerRadio {
+            rx: Some(AckFrame::from_slice(&SHORT_UNICAST).unwrap()),
+            ..Default::default()
+        });
+        let mut frame = AckFrame::new();
+
+        // The RIT receiver sends a command and receives the frame following
+        // it.
+        let rx_end = TestClock::block_on(duty_cycle.receive(&mut radio, &mut pib, &mut frame));
+        assert_eq!(rx_end, Some(Instant::new(600)));
+        assert_eq!(frame[..], SHORT_UNICAST);
+        assert_eq!(radio.radio_mut().transmissions, 1);
+
+        // Idle listening.
+        duty_cycle.set_mode(PowerSavingMode::AlwaysOn).unwrap();
+        radio.radio_mut().rx = Some(AckFrame::from_slice(&UNICAST).unwrap());
+        let rx_end = TestClock::block_on(duty_cycle.receive(&mut radio, &mut pib, &mut frame));
+        assert_eq!(rx_end, Some(Instant::new(700)));
+        assert_eq!(frame[..], UNICAST);
+
+        duty_cycle.set_mode(PowerSavingMode::Tsch).unwrap();
+        assert_eq!(
+            TestClock::block_on(duty_cycle.receive(&mut radio, &mut pib, &mut frame)),
+            None
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use crate::util::sync::HasAddress;
 
 pub use super::{
-    mcps::data::{DataIndication, DataRequest},
+    mcps::data::{AckType, DataConfirm, DataIndication, DataRequest, TxOptions, TxParameters},
     mlme::{
         beacon::{BeaconNotifyIndication, BeaconRequest},
         set::SetRequestAttribute,
     },
+    pib::{PibAttribute, PibAttributeId},
 };
 
 /// Enum representing all (currently) supported MAC services request primitives
@@ -0,0 +1,752 @@
+//! Radio operations of the MAC engines on top of a [`Radio`] driver.
+//!
+//! The MAC engines drive a [`MacRadio`], which wraps any driver implementing
+//! [`Radio`] and adds the MAC policies on top: the CCA mode of CSMA-CA, the
+//! wait for ACKs, the default transmit power and PHY mode as well as the duty
+//! cycle budget. [`MacRadio`] implements [`Radio`] itself, accounting all
+//! transmissions in the duty cycle budget, so that new hardware is integrated
+//! by implementing [`Radio`] only.
+//!
+//! The operations added by [`MacRadio`] are infallible. Radio errors are
+//! therefore mapped to the outcome closest to the truth: A failed transmission
+//! is reported as done so that the missing ACK triggers a retransmission, a
+//! failed reception as no frame received and a failed CCA as a busy channel.
+//!
+//! Drivers report energy measurements in dBm while the MLME works with ED
+//! values, see [`ed_from_dbm()`].
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        config::{CcaMode, Channel},
+        constants::{FCS_LEN, PHY_MAX_PACKET_SIZE_127},
+        link_quality::ed_from_dbm,
+        phy::{air_time, PhyMode, PhyParameters},
+        radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+        time::{Duration, Instant, Microseconds},
+        RadioTimerApi,
+    },
+    mac::{frame::mpdu::FrameBuffer, mcps::data::TxParameters},
+};
+
+use super::{
+    ack::{AckFrame, AckMatcher},
+    regulatory::{DutyCycleBudget, DutyCyclePolicy},
+};
+
+/// Adapts a [`Radio`] driver to the MAC engines, see the module
+/// documentation.
+pub struct MacRadio<R: Radio> {
+    radio: R,
+    /// The CCA mode used by CSMA-CA.
+    cca_mode: CcaMode,
+    /// macAckWaitDuration
+    ack_wait_duration: Duration<R::Timer>,
+    /// The duration of a single energy measurement, i.e. 8 symbol periods.
+    ed_duration: Duration<R::Timer>,
+    /// The transmit power of frames without their own, if set through
+    /// [`Radio::set_tx_power()`].
+    tx_power: Option<i8>,
+    /// The PHY mode of frames without their own, if set through
+    /// [`Radio::set_phy_mode()`].
+    phy_mode: Option<PhyMode>,
+    /// The timing parameters of the PHY of frames without their own PHY mode.
+    phy: PhyParameters,
+    /// The channel the radio is tuned to, if known.
+    channel: Option<Channel>,
+    /// The duty cycle budget limiting transmissions, if set through
+    /// [`MacRadio::set_duty_cycle_budget()`].
+    duty_cycle: Option<DutyCycleBudget<R::Timer>>,
+}
+
+impl<R: Radio> MacRadio<R> {
+    /// Creates a new [`MacRadio`].
+    ///
+    /// * `radio` - The radio driver
+    /// * `cca_mode` - The CCA mode used by CSMA-CA
+    /// * `phy` - The timing parameters of the PHY providing macAckWaitDuration
+    pub fn new(radio: R, cca_mode: CcaMode, phy: &PhyParameters) -> Self {
+        Self {
+            radio,
+            cca_mode,
+            ack_wait_duration: phy.ack_wait_duration().convert_into_rounding_up(),
+            ed_duration: phy.symbols(8).convert_into_rounding_up(),
+            tx_power: None,
+            phy_mode: None,
+            phy: *phy,
+            channel: None,
+            duty_cycle: None,
+        }
+    }
+
+    /// Set the duty cycle budget limiting transmissions, [`None`] to lift all
+    /// limits.
+    ///
+    /// Transmissions are only limited once the channel is known, i.e. after
+    /// [`Radio::set_channel()`].
+    pub fn set_duty_cycle_budget(&mut self, budget: Option<DutyCycleBudget<R::Timer>>) {
+        self.duty_cycle = budget;
+    }
+
+    /// Return the duty cycle budget limiting transmissions.
+    pub fn duty_cycle_budget(&self) -> Option<&DutyCycleBudget<R::Timer>> {
+        self.duty_cycle.as_ref()
+    }
+
+    /// Return the CCA mode used by CSMA-CA.
+    pub fn cca_mode(&self) -> CcaMode {
+        self.cca_mode
+    }
+
+    /// Set the CCA mode used by CSMA-CA.
+    ///
+    /// Modes unsupported by the radio make every CCA fail, i.e. report a busy
+    /// channel.
+    pub fn set_cca_mode(&mut self, cca_mode: CcaMode) {
+        self.cca_mode = cca_mode;
+    }
+
+    /// Return the radio driver.
+    pub fn radio_mut(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    /// Return the radio driver.
+    pub fn into_inner(self) -> R {
+        self.radio
+    }
+
+    /// Performs a clear channel assessment with the CCA mode of CSMA-CA and
+    /// returns whether the channel is idle.
+    pub async fn clear_channel(&mut self) -> bool {
+        self.radio.cca(self.cca_mode).await.unwrap_or(false)
+    }
+
+    /// Transmits the given MPDU (without FCS) immediately and returns the
+    /// instant at which the transmission ended.
+    pub async fn transmit_now(&mut self, mpdu: &[u8]) -> Instant<R::Timer> {
+        match self.transmit_at(mpdu, None).await {
+            Ok(info) => info.end,
+            Err(_) => R::Timer::now(),
+        }
+    }
+
+    /// Transmits the given frame immediately and returns whether it was
+    /// acknowledged within macAckWaitDuration.
+    ///
+    /// Frames that don't request an acknowledgement are always considered
+    /// acknowledged.
+    ///
+    /// * `frame` - The MPDU (without FCS) to transmit
+    /// * `parameters` - The transmission parameters of the frame, i.e. its
+    ///   transmit power and PHY mode
+    pub async fn transmit_acked(&mut self, frame: &[u8], parameters: &TxParameters) -> bool {
+        let Ok(info) = self.transmit_with_parameters(frame, parameters).await else {
+            return false;
+        };
+        let matcher = match AckMatcher::new(frame) {
+            Ok(Some(matcher)) => matcher,
+            Ok(None) => return true,
+            Err(_) => return false,
+        };
+
+        let deadline = info.end + self.ack_wait_duration;
+        let mut ack = AckFrame::new();
+        while self.receive_until(&mut ack, deadline).await.is_some() {
+            if matcher.matches(&ack).is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Waits until the given frame fits into the duty cycle budget of its
+    /// band and returns whether it may be transmitted, see
+    /// [`MacRadio::set_duty_cycle_budget()`].
+    ///
+    /// * `frame` - The MPDU (without FCS) to transmit
+    /// * `parameters` - The transmission parameters of the frame
+    pub async fn acquire_air_time(&mut self, frame: &[u8], parameters: &TxParameters) -> bool {
+        let air_time = self.air_time(frame, parameters.phy_mode);
+        let (Some(budget), Some(channel)) = (&mut self.duty_cycle, self.channel) else {
+            return true;
+        };
+        loop {
+            let now = R::Timer::now();
+            let Err(exceeded) = budget.check(&channel, air_time, now) else {
+                return true;
+            };
+            match (budget.policy(), exceeded.available_at) {
+                (DutyCyclePolicy::Defer(max_delay), Some(at))
+                    if at - now <= max_delay.convert_into_rounding_up() =>
+                {
+                    R::Timer::wait_for_alarm_at(at).await
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    /// Receives the next frame into the given buffer.
+    ///
+    /// Returns `None` and clears the buffer if no frame was received until the
+    /// given deadline.
+    pub async fn receive_until(
+        &mut self,
+        frame: &mut FrameBuffer<PHY_MAX_PACKET_SIZE_127>,
+        until: Instant<R::Timer>,
+    ) -> Option<RxInfo<R::Timer>> {
+        self.receive_frame(frame, RxWindow::until(until)).await
+    }
+
+    /// Receives the next frame within the given window into the given buffer.
+    ///
+    /// Returns `None` and clears the buffer if no frame was received within
+    /// the window.
+    pub async fn receive_frame(
+        &mut self,
+        frame: &mut FrameBuffer<PHY_MAX_PACKET_SIZE_127>,
+        window: RxWindow<R::Timer>,
+    ) -> Option<RxInfo<R::Timer>> {
+        let received = self
+            .radio
+            .receive(frame.as_mut_capacity(), window)
+            .await
+            .ok()
+            .flatten()
+            .filter(|info| frame.set_len(info.len).is_ok());
+        if received.is_none() {
+            frame.clear();
+        }
+        received
+    }
+
+    /// Measures the energy on the current channel over 8 symbol periods and
+    /// returns it as ED value, 0 if the measurement failed.
+    pub async fn energy_level(&mut self) -> u8 {
+        self.radio
+            .energy_detect(self.ed_duration)
+            .await
+            .map_or(0, ed_from_dbm)
+    }
+
+    /// Return the on-air time of the given MPDU in the given PHY mode, or the
+    /// default one.
+    ///
+    /// SUN FSK modes without exact timing fall back to the default PHY.
+    fn air_time(&self, mpdu: &[u8], phy_mode: Option<PhyMode>) -> Duration<Microseconds> {
+        let phy = match phy_mode.or(self.phy_mode) {
+            Some(PhyMode::SunFsk(mode)) => PhyParameters::sun_fsk(mode).unwrap_or(self.phy),
+            _ => self.phy,
+        };
+        air_time(mpdu.len() + FCS_LEN, &phy)
+    }
+
+    /// Accounts a transmission of the given MPDU that ended at the given
+    /// instant in the duty cycle budget.
+    fn record_air_time(&mut self, mpdu: &[u8], phy_mode: Option<PhyMode>, end: Instant<R::Timer>) {
+        let air_time = self.air_time(mpdu, phy_mode);
+        if let (Some(budget), Some(channel)) = (&mut self.duty_cycle, &self.channel) {
+            budget.record(channel, air_time, end);
+        }
+    }
+
+    /// Transmits the given MPDU immediately with the given transmit power and
+    /// PHY mode, see [`Radio::set_tx_power()`] and [`Radio::set_phy_mode()`].
+    ///
+    /// Returns [`RadioError::Unsupported`] without transmitting if the radio
+    /// doesn't support the PHY mode.
+    async fn transmit_with_parameters(
+        &mut self,
+        mpdu: &[u8],
+        parameters: &TxParameters,
+    ) -> Result<TxInfo<R::Timer>, RadioError> {
+        if let Some(mode) = parameters.phy_mode {
+            self.radio.set_phy_mode(mode)?;
+        }
+        // Radios fall back to the closest power they support.
+        if let Some(dbm) = parameters.tx_power {
+            let _ = self.radio.set_tx_power(dbm);
+        }
+        let result = self.radio.transmit_at(mpdu, None).await;
+        if let Ok(info) = &result {
+            self.record_air_time(mpdu, parameters.phy_mode, info.end);
+        }
+        if let (Some(_), Some(dbm)) = (parameters.tx_power, self.tx_power) {
+            let _ = self.radio.set_tx_power(dbm);
+        }
+        if let (Some(_), Some(mode)) = (parameters.phy_mode, self.phy_mode) {
+            let _ = self.radio.set_phy_mode(mode);
+        }
+        result
+    }
+}
+
+impl<R: Radio> Radio for MacRadio<R> {
+    type Timer = R::Timer;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        self.radio.capabilities()
+    }
+
+    /// Tune the radio to the channel of subsequent operations.
+    ///
+    /// The duty cycle budget accounts transmissions in the band of this
+    /// channel, see [`MacRadio::set_duty_cycle_budget()`].
+    fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+        self.radio.set_channel(channel)?;
+        self.channel = Some(channel);
+        Ok(())
+    }
+
+    /// Set the transmit power of frames without their own and return the
+    /// power actually used by the radio.
+    ///
+    /// The radio returns to this power after frames with their own transmit
+    /// power. Without it, the radio keeps the power of the last such frame.
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        let dbm = self.radio.set_tx_power(dbm)?;
+        self.tx_power = Some(dbm);
+        Ok(dbm)
+    }
+
+    /// Set the PHY mode of frames without their own.
+    ///
+    /// The radio returns to this mode after frames with their own PHY mode.
+    /// Without it, the radio keeps the mode of the last such frame.
+    fn set_phy_mode(&mut self, mode: PhyMode) -> Result<(), RadioError> {
+        self.radio.set_phy_mode(mode)?;
+        self.phy_mode = Some(mode);
+        Ok(())
+    }
+
+    /// Transmits the given MPDU like the driver and accounts it in the duty
+    /// cycle budget.
+    ///
+    /// The budget is not checked, see [`MacRadio::acquire_air_time()`].
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        at: Option<Instant<R::Timer>>,
+    ) -> Result<TxInfo<R::Timer>, RadioError> {
+        let info = self.radio.transmit_at(mpdu, at).await?;
+        self.record_air_time(mpdu, None, info.end);
+        Ok(info)
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<R::Timer>,
+    ) -> Result<Option<RxInfo<R::Timer>>, RadioError> {
+        self.radio.receive(buffer, window).await
+    }
+
+    async fn cca(&mut self, mode: CcaMode) -> Result<bool, RadioError> {
+        self.radio.cca(mode).await
+    }
+
+    async fn energy_detect(&mut self, duration: Duration<R::Timer>) -> Result<i8, RadioError> {
+        self.radio.energy_detect(duration).await
+    }
+
+    fn set_frame_pending(&mut self, src_address: &[u8], pending: bool) -> Result<(), RadioError> {
+        self.radio.set_frame_pending(src_address, pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::{
+        cell::RefCell,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use std::rc::Rc;
+
+    use rand_core::RngCore;
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::ChannelPage, phy::SunFskMode, simulator::Simulator, test_clock::TestClock,
+            time::Nanoseconds, virtual_radio::VirtualMediumConfig,
+        },
+        mac::{
+            csma::{CsmaConfig, CsmaMac, TxOutcome},
+            mcps::data::TxParameters,
+            regulatory::DutyCycleLimit,
+            test_helpers::poll_ready,
+        },
+    };
+
+    /// Return the RMARKER of a frame starting at the given instant, i.e. the
+    /// end of its SHR of 160µs.
+    fn rmarker(start: Instant<TestClock>) -> Instant<Nanoseconds> {
+        Instant::new((start.tick() + 160) * 1_000)
+    }
+
+    /// Radio replaying the given frames after each transmission.
+    struct ReplayRadio<'a> {
+        channel: Option<Channel>,
+        frames: core::slice::Iter<'a, &'a [u8]>,
+        transmissions: usize,
+        tx_power: i8,
+        /// The transmit power of each transmission.
+        tx_powers: std::vec::Vec<i8>,
+    }
+
+    impl Radio for ReplayRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            RadioCapabilities {
+                hardware_ack: false,
+                frame_pending: false,
+                timestamp_resolution: Duration::new(1_000),
+                min_tx_power: -20,
+                max_tx_power: 8,
+            }
+        }
+
+        fn set_channel(&mut self, channel: Channel) -> Result<(), RadioError> {
+            if channel.page() != ChannelPage::Oqpsk2450Mhz {
+                return Err(RadioError::Unsupported);
+            }
+            self.channel = Some(channel);
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            self.tx_power = dbm.clamp(-20, 8);
+            Ok(self.tx_power)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            self.transmissions += 1;
+            self.tx_powers.push(self.tx_power);
+            let timestamp = rmarker(TestClock::now());
+            TestClock::advance(Duration::new(500));
+            Ok(TxInfo {
+                timestamp,
+                end: TestClock::now(),
+            })
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            let Some(mpdu) = self.frames.next() else {
+                TestClock::advance(window.end.unwrap() - TestClock::now());
+                return Ok(None);
+            };
+            buffer
+                .get_mut(..mpdu.len())
+                .ok_or(RadioError::FrameTooLong)?
+                .copy_from_slice(mpdu);
+            let timestamp = rmarker(TestClock::now());
+            TestClock::advance(Duration::new(100));
+            Ok(Some(RxInfo {
+                len: mpdu.len(),
+                rssi: -60,
+                lqi: 0xff,
+                timestamp,
+                end: TestClock::now(),
+            }))
+        }
+
+        async fn cca(&mut self, mode: CcaMode) -> Result<bool, RadioError> {
+            match mode {
+                CcaMode::CarrierSense => Ok(true),
+                _ => Err(RadioError::Unsupported),
+            }
+        }
+
+        async fn energy_detect(&mut self, duration: Duration<TestClock>) -> Result<i8, RadioError> {
+            TestClock::advance(duration);
+            Ok(-60)
+        }
+    }
+
+    fn radio<'a>(frames: &'a [&'a [u8]]) -> MacRadio<ReplayRadio<'a>> {
+        TestClock::reset();
+        MacRadio::new(
+            ReplayRadio {
+                channel: None,
+                frames: frames.iter(),
+                transmissions: 0,
+                tx_power: 0,
+                tx_powers: std::vec::Vec::new(),
+            },
+            CcaMode::CarrierSense,
+            &PhyParameters::default(),
+        )
+    }
+
+    #[test]
+    fn csma_radio() {
+        // Data frame (2006) with sequence number 7 requesting an ACK.
+        let data =
+            AckFrame::from_slice(&[0x61, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00]).unwrap();
+        let other_ack: &[u8] = &[0x02, 0x10, 0x06];
+        let ack: &[u8] = &[0x02, 0x10, 0x07];
+        let frames = [other_ack, ack];
+        let defaults = TxParameters::default();
+
+        let mut mac_radio = radio(&frames);
+        assert!(poll_ready(mac_radio.clear_channel()));
+        assert!(poll_ready(mac_radio.transmit_acked(&data, &defaults)));
+        assert_eq!(TestClock::now(), Instant::new(700));
+
+        // The ACK wait duration of 54 symbols of 16µs each expires.
+        let mut mac_radio = radio(&frames[..1]);
+        assert!(!poll_ready(mac_radio.transmit_acked(&data, &defaults)));
+        assert_eq!(TestClock::now(), Instant::new(500 + 864));
+
+        let mut mac_radio = radio(&[]);
+        mac_radio.set_cca_mode(CcaMode::EnergyDetection { ed_threshold: 10 });
+        assert!(!poll_ready(mac_radio.clear_channel()));
+    }
+
+    fn with_tx_power(dbm: i8) -> TxParameters {
+        TxParameters {
+            tx_power: Some(dbm),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tx_power() {
+        // Data frame (2006) not requesting an ACK.
+        let data =
+            AckFrame::from_slice(&[0x41, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00]).unwrap();
+        let defaults = TxParameters::default();
+
+        let mut mac_radio = radio(&[]);
+        assert!(poll_ready(
+            mac_radio.transmit_acked(&data, &with_tx_power(-4))
+        ));
+        // The radio keeps the power without a default.
+        assert!(poll_ready(mac_radio.transmit_acked(&data, &defaults)));
+
+        assert_eq!(mac_radio.set_tx_power(12), Ok(8));
+        assert!(poll_ready(
+            mac_radio.transmit_acked(&data, &with_tx_power(-30))
+        ));
+        assert!(poll_ready(mac_radio.transmit_acked(&data, &defaults)));
+        assert_eq!(mac_radio.radio_mut().tx_powers, [-4, -4, -20, 8]);
+    }
+
+    #[test]
+    fn phy_mode() {
+        // Data frame (2006) not requesting an ACK.
+        let data =
+            AckFrame::from_slice(&[0x41, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00]).unwrap();
+
+        // The radio only supports O-QPSK.
+        let mut mac_radio = radio(&[]);
+        assert_eq!(mac_radio.set_phy_mode(PhyMode::Oqpsk), Ok(()));
+        assert_eq!(
+            mac_radio.set_phy_mode(PhyMode::SunFsk(SunFskMode::FSK_50KBPS)),
+            Err(RadioError::Unsupported)
+        );
+
+        let mut parameters = TxParameters {
+            phy_mode: Some(PhyMode::SunFsk(SunFskMode::FSK_100KBPS)),
+            ..Default::default()
+        };
+        assert!(!poll_ready(mac_radio.transmit_acked(&data, &parameters)));
+        assert_eq!(mac_radio.radio_mut().transmissions, 0);
+
+        parameters.phy_mode = Some(PhyMode::Oqpsk);
+        assert!(poll_ready(mac_radio.transmit_acked(&data, &parameters)));
+        assert_eq!(mac_radio.radio_mut().transmissions, 1);
+    }
+
+    #[test]
+    fn duty_cycle() {
+        // Data frame (2006) not requesting an ACK, on air for 544µs.
+        let data =
+            AckFrame::from_slice(&[0x41, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x00, 0x00]).unwrap();
+        let defaults = TxParameters::default();
+        // 10% of a window of 16 ms, i.e. buckets of 1 ms.
+        let limit = DutyCycleLimit::new(2_405_000, 2_480_000, Duration::new(16_000), 100_000);
+
+        let mut mac_radio = radio(&[]);
+        let budget = DutyCycleBudget::new(&[limit], DutyCyclePolicy::Deny, TestClock::now());
+        mac_radio.set_duty_cycle_budget(budget.ok());
+        // Transmissions on unknown channels are not limited.
+        assert!(poll_ready(mac_radio.acquire_air_time(&data, &defaults)));
+
+        assert_eq!(mac_radio.set_channel(Channel::_11), Ok(()));
+        assert!(poll_ready(mac_radio.acquire_air_time(&data, &defaults)));
+        assert!(poll_ready(mac_radio.transmit_acked(&data, &defaults)));
+        // Frames transmitted through the driver operations are accounted too.
+        assert!(poll_ready(mac_radio.acquire_air_time(&data, &defaults)));
+        assert!(poll_ready(mac_radio.transmit_at(&data, None)).is_ok());
+        assert!(!poll_ready(mac_radio.acquire_air_time(&data, &defaults)));
+
+        // Frames are denied if they don't fit within the deferral, i.e. within
+        // a window and a bucket after the bucket of the first transmission
+        // started.
+        let start = TestClock::now();
+        let policy = DutyCyclePolicy::Defer(Duration::new(16_000));
+        let mut budget = DutyCycleBudget::new(&[limit], policy, start).unwrap();
+        budget.record(&Channel::_11, Duration::new(1_500), start);
+        mac_radio.set_duty_cycle_budget(Some(budget));
+        assert!(!poll_ready(mac_radio.acquire_air_time(&data, &defaults)));
+
+        // Otherwise, they are deferred until then.
+        let start = TestClock::now();
+        let policy = DutyCyclePolicy::Defer(Duration::new(17_000));
+        let mut budget = DutyCycleBudget::new(&[limit], policy, start).unwrap();
+        budget.record(&Channel::_11, Duration::new(1_500), start);
+        mac_radio.set_duty_cycle_budget(Some(budget));
+        let mut acquire = pin!(mac_radio.acquire_air_time(&data, &defaults));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(acquire.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(TestClock::alarm(), Some(start + Duration::new(17_000)));
+        TestClock::advance(Duration::new(17_000));
+        assert_eq!(acquire.as_mut().poll(&mut cx), Poll::Ready(true));
+    }
+
+    #[test]
+    fn scan_radio() {
+        let beacon: &[u8] = &[
+            0x00, 0x80, 0x01, 0xcd, 0xab, 0x00, 0x00, 0xff, 0xcf, 0x00, 0x00,
+        ];
+        let frames = [beacon];
+        let mut mac_radio = radio(&frames);
+        assert_eq!(mac_radio.set_channel(Channel::_11), Ok(()));
+        assert_eq!(mac_radio.radio_mut().channel, Some(Channel::_11));
+        // 15 dB above the lowest ED value, measured over 8 symbols of 16µs.
+        assert_eq!(poll_ready(mac_radio.energy_level()), 95);
+        assert_eq!(TestClock::now(), Instant::new(128));
+        // Channels of other bands are not supported.
+        let channel = ChannelPage::Oqpsk868Mhz.channel(0).unwrap();
+        assert_eq!(mac_radio.set_channel(channel), Err(RadioError::Unsupported));
+
+        let mut frame = FrameBuffer::new();
+        assert_eq!(
+            poll_ready(mac_radio.transmit_now(&[0x03])),
+            Instant::new(628)
+        );
+        assert_eq!(mac_radio.radio_mut().transmissions, 1);
+        let until = Instant::new(10_000);
+        let info = poll_ready(mac_radio.receive_until(&mut frame, until)).unwrap();
+        assert_eq!(frame[..], *beacon);
+        assert_eq!(info.rssi, -60);
+        assert_eq!(info.timestamp, Instant::new(788_000));
+        assert_eq!(poll_ready(mac_radio.receive_until(&mut frame, until)), None);
+        assert!(frame.is_empty());
+        assert_eq!(TestClock::now(), until);
+
+        assert_eq!(
+            mac_radio.capabilities().timestamp_resolution,
+            Duration::<Nanoseconds>::new(1_000)
+        );
+    }
+
+    /// Linear congruential generator, good enough for random backoffs.
+    struct Lcg(u64);
+
+    impl RngCore for Lcg {
+        fn next_u32(&mut self) -> u32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (self.0 >> 33) as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn csma_fairness() {
+        const SENDERS: u8 = 3;
+        const FRAMES: u8 = 10;
+
+        let mut simulator = Simulator::new(VirtualMediumConfig::default());
+        // The frames received by the coordinator and acknowledged by the
+        // senders, per sender.
+        let received = Rc::new(RefCell::new([0u8; SENDERS as usize]));
+        let acknowledged = Rc::new(RefCell::new([0u8; SENDERS as usize]));
+
+        for sender in 0..SENDERS {
+            let acknowledged = acknowledged.clone();
+            simulator.spawn(move |radio| async move {
+                let phy = PhyParameters::default();
+                let mut radio = MacRadio::new(radio, CcaMode::CarrierSense, &phy);
+                let mut mac =
+                    CsmaMac::<TestClock, _>::new(CsmaConfig::default(), Lcg(sender as u64))
+                        .unwrap();
+                for seq in sender * FRAMES..(sender + 1) * FRAMES {
+                    // Data frame (2006) from the short address of the sender
+                    // requesting an ACK, with distinct sequence numbers.
+                    let data = [0x61, 0x98, seq, 0xcd, 0xab, 0, 0, sender, 0];
+                    let outcome = mac
+                        .transmit_with(&mut radio, &data, &TxParameters::default())
+                        .await;
+                    if let TxOutcome::Success { .. } = outcome {
+                        acknowledged.borrow_mut()[sender as usize] += 1;
+                    }
+                }
+            });
+        }
+        let frames = received.clone();
+        simulator.spawn(move |mut radio| async move {
+            let mut buffer = [0; 127];
+            let mut last_seq = None;
+            loop {
+                let rx = radio
+                    .receive(&mut buffer, RxWindow::unbounded())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let seq = buffer[2];
+                // Retransmissions are acknowledged but counted once.
+                if last_seq != Some(seq) {
+                    frames.borrow_mut()[buffer[7] as usize] += 1;
+                    last_seq = Some(seq);
+                }
+                // macSifsPeriod of 192µs followed by the SHR of 160µs.
+                let at = rx.end + Duration::new(192 + 160);
+                let _ = radio.transmit_at(&[0x02, 0x10, seq], Some(at)).await;
+            }
+        });
+
+        // The coordinator never completes.
+        assert!(!simulator.run_until(Instant::new(1_000_000)));
+        for sender in 0..SENDERS {
+            assert!(simulator.is_done(sender as usize));
+        }
+        let received = *received.borrow();
+        let acknowledged = *acknowledged.borrow();
+        for sender in 0..SENDERS as usize {
+            // Each sender gets its share of the channel.
+            assert!(acknowledged[sender] >= FRAMES / 2);
+            assert!(acknowledged[sender] <= received[sender]);
+        }
+    }
+}
@@ -0,0 +1,387 @@
+//! Duty cycle limits of regional regulations, e.g. of ETSI EN 300 220 in the
+//! 868 MHz band.
+//!
+//! A [`DutyCycleBudget`] accounts the on-air time of all transmissions per
+//! band, see [`DutyCycleLimit`], over a sliding window. Frames that would
+//! exceed the budget of their band are deferred until enough on-air time has
+//! left the window or denied, see [`DutyCyclePolicy`]. [`CsmaMac`] then
+//! reports [`TxOutcome::DutyCycleExceeded`].
+//!
+//! The window is divided into [`WINDOW_BUCKETS`] buckets. On-air time is
+//! accounted conservatively: a transmission is only forgotten once the whole
+//! bucket it fell into has left the window, i.e. up to a bucket later than
+//! required.
+//!
+//! [`MacRadio`] accounts all transmissions on its current channel but only
+//! limits those of [`CsmaMac`].
+//!
+//! Note: CSL and RIT transmissions, including wake-up sequences, are
+//!       accounted but not limited yet. ACKs sent by the radio are not
+//!       accounted.
+//!
+//! [`CsmaMac`]: super::csma::CsmaMac
+//! [`MacRadio`]: super::radio::MacRadio
+//! [`TxOutcome::DutyCycleExceeded`]: super::csma::TxOutcome::DutyCycleExceeded
+#![allow(dead_code)]
+
+use crate::driver::{
+    config::Channel,
+    time::{Duration, Frequency, Instant, Microseconds},
+};
+
+/// The number of buckets the window of a [`DutyCycleLimit`] is divided into.
+pub const WINDOW_BUCKETS: usize = 16;
+
+/// The max number of bands a [`DutyCycleBudget`] accounts for.
+pub const MAX_DUTY_CYCLE_BANDS: usize = 4;
+
+/// The max on-air time permitted in a band within a sliding window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycleLimit {
+    /// The lowest channel center frequency of the band in kHz.
+    pub start: u32,
+    /// The highest channel center frequency of the band in kHz.
+    pub end: u32,
+    /// The duration of the sliding window.
+    pub window: Duration<Microseconds>,
+    /// The on-air time permitted within the window.
+    pub budget: Duration<Microseconds>,
+}
+
+impl DutyCycleLimit {
+    /// The 868.0-868.6 MHz band of ETSI EN 300 220-2, 1% per hour.
+    pub const ETSI_868MHZ: Self = Self::new(868_000, 868_600, Duration::new(3_600_000_000), 10_000);
+
+    /// Creates a new limit.
+    ///
+    /// * `start` - Lowest channel center frequency of the band in kHz
+    /// * `end` - Highest channel center frequency of the band in kHz
+    /// * `window` - Duration of the sliding window
+    /// * `duty_cycle_ppm` - Permitted share of on-air time in parts per
+    ///   million, e.g. 10 000 for 1%
+    pub const fn new(
+        start: u32,
+        end: u32,
+        window: Duration<Microseconds>,
+        duty_cycle_ppm: u32,
+    ) -> Self {
+        Self {
+            start,
+            end,
+            window,
+            budget: Duration::new(window.ticks() * duty_cycle_ppm as i64 / 1_000_000),
+        }
+    }
+
+    /// Return whether the given channel lies within the band.
+    pub const fn contains(&self, channel: &Channel) -> bool {
+        let center_frequency = channel.center_frequency();
+        self.start <= center_frequency && center_frequency <= self.end
+    }
+}
+
+/// How frames exceeding the budget of their band are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DutyCyclePolicy {
+    /// Frames are denied immediately.
+    Deny,
+    /// Frames are deferred for up to the given duration until they fit into
+    /// the budget and denied otherwise.
+    ///
+    /// As on-air time is forgotten up to a bucket late, see the module
+    /// documentation, frames may have to wait for up to a window and a bucket.
+    Defer(Duration<Microseconds>),
+}
+
+/// An invalid configuration of a [`DutyCycleBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DutyCycleConfigError {
+    /// The window or budget of a limit is not positive or its band is empty.
+    InvalidLimit,
+    /// More than [`MAX_DUTY_CYCLE_BANDS`] limits were given.
+    TooManyBands,
+}
+
+/// A frame doesn't fit into the budget of its band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycleExceeded<Timer: Frequency> {
+    /// The instant from which on the frame fits into the budget, [`None`] if
+    /// it exceeds the whole budget.
+    pub available_at: Option<Instant<Timer>>,
+}
+
+/// The on-air time in a band over the window of its limit.
+#[derive(Debug, Clone, Copy)]
+struct BandUsage<Timer: Frequency> {
+    limit: DutyCycleLimit,
+    /// The on-air time in µs per bucket, the current bucket starting at
+    /// `current_start`. The additional bucket ensures that the buckets cover
+    /// the whole window.
+    buckets: [u32; WINDOW_BUCKETS + 1],
+    current: usize,
+    current_start: Instant<Timer>,
+    bucket_duration: Duration<Timer>,
+}
+
+impl<Timer: Frequency> BandUsage<Timer> {
+    fn new(limit: DutyCycleLimit, now: Instant<Timer>) -> Self {
+        // The window is positive, see `DutyCycleBudget::new()`.
+        let buckets = WINDOW_BUCKETS as i64;
+        let bucket_duration: Duration<Timer> =
+            Duration::<Microseconds>::new((limit.window.ticks() + buckets - 1) / buckets)
+                .convert_into_rounding_up();
+        Self {
+            limit,
+            buckets: [0; WINDOW_BUCKETS + 1],
+            current: 0,
+            current_start: now,
+            bucket_duration: Duration::new(bucket_duration.ticks().max(1)),
+        }
+    }
+
+    /// Forgets the buckets that left the window.
+    fn advance(&mut self, now: Instant<Timer>) {
+        for _ in 0..self.buckets.len() {
+            if now < self.current_start + self.bucket_duration {
+                return;
+            }
+            self.current = (self.current + 1) % self.buckets.len();
+            self.buckets[self.current] = 0;
+            self.current_start = self.current_start + self.bucket_duration;
+        }
+        // All buckets left the window.
+        self.current_start = now;
+    }
+
+    /// Return the on-air time within the window in µs.
+    fn used(&self) -> i64 {
+        self.buckets.iter().map(|&air_time| air_time as i64).sum()
+    }
+
+    /// Return the instant from which on the given on-air time fits into the
+    /// budget.
+    fn available_at(
+        &self,
+        air_time: Duration<Microseconds>,
+        now: Instant<Timer>,
+    ) -> Option<Instant<Timer>> {
+        if air_time > self.limit.budget {
+            return None;
+        }
+
+        let mut used = self.used();
+        let mut available_at = now;
+        let mut expired = 0;
+        while used + air_time.ticks() > self.limit.budget.ticks() {
+            // The oldest bucket follows the current one.
+            expired += 1;
+            used -= self.buckets[(self.current + expired) % self.buckets.len()] as i64;
+            available_at = self.current_start + self.bucket_duration * expired;
+        }
+        Some(available_at)
+    }
+}
+
+/// Accounts the on-air time of transmissions per band, see the module
+/// documentation.
+#[derive(Debug, Clone)]
+pub struct DutyCycleBudget<Timer: Frequency> {
+    policy: DutyCyclePolicy,
+    bands: heapless::Vec<BandUsage<Timer>, MAX_DUTY_CYCLE_BANDS>,
+}
+
+impl<Timer: Frequency> DutyCycleBudget<Timer> {
+    /// Creates a new budget without any on-air time accounted.
+    ///
+    /// Transmissions on channels outside all bands are not limited.
+    ///
+    /// * `limits` - The limits of the bands
+    /// * `policy` - How frames exceeding the budget are handled
+    /// * `now` - The current instant
+    pub fn new(
+        limits: &[DutyCycleLimit],
+        policy: DutyCyclePolicy,
+        now: Instant<Timer>,
+    ) -> Result<Self, DutyCycleConfigError> {
+        let mut bands = heapless::Vec::new();
+        for limit in limits {
+            if limit.window.ticks() <= 0 || limit.budget.ticks() <= 0 || limit.start > limit.end {
+                return Err(DutyCycleConfigError::InvalidLimit);
+            }
+            bands
+                .push(BandUsage::new(*limit, now))
+                .map_err(|_| DutyCycleConfigError::TooManyBands)?;
+        }
+        Ok(Self { policy, bands })
+    }
+
+    /// Return how frames exceeding the budget are handled.
+    pub fn policy(&self) -> DutyCyclePolicy {
+        self.policy
+    }
+
+    /// Return the on-air time accounted within the window of the band of the
+    /// given channel, [`None`] if the channel lies outside all bands.
+    pub fn used(
+        &mut self,
+        channel: &Channel,
+        now: Instant<Timer>,
+    ) -> Option<Duration<Microseconds>> {
+        let band = self.band(channel, now)?;
+        Some(Duration::new(band.used()))
+    }
+
+    /// Checks whether a transmission of the given on-air time fits into the
+    /// budget of the band of the given channel.
+    ///
+    /// * `channel` - The channel of the transmission
+    /// * `air_time` - The on-air time of the transmission, see
+    ///   [`air_time()`](crate::driver::phy::air_time)
+    /// * `now` - The current instant
+    pub fn check(
+        &mut self,
+        channel: &Channel,
+        air_time: Duration<Microseconds>,
+        now: Instant<Timer>,
+    ) -> Result<(), DutyCycleExceeded<Timer>> {
+        let Some(band) = self.band(channel, now) else {
+            return Ok(());
+        };
+        match band.available_at(air_time, now) {
+            Some(available_at) if available_at <= now => Ok(()),
+            available_at => Err(DutyCycleExceeded { available_at }),
+        }
+    }
+
+    /// Accounts a transmission of the given on-air time in the band of the
+    /// given channel.
+    ///
+    /// * `channel` - The channel of the transmission
+    /// * `air_time` - The on-air time of the transmission
+    /// * `now` - The current instant, i.e. the end of the transmission
+    pub fn record(
+        &mut self,
+        channel: &Channel,
+        air_time: Duration<Microseconds>,
+        now: Instant<Timer>,
+    ) {
+        if let Some(band) = self.band(channel, now) {
+            let bucket = &mut band.buckets[band.current];
+            *bucket = bucket.saturating_add(air_time.ticks().clamp(0, u32::MAX as i64) as u32);
+        }
+    }
+
+    /// Return the usage of the band of the given channel.
+    fn band(&mut self, channel: &Channel, now: Instant<Timer>) -> Option<&mut BandUsage<Timer>> {
+        let band = self
+            .bands
+            .iter_mut()
+            .find(|band| band.limit.contains(channel))?;
+        band.advance(now);
+        Some(band)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::{
+        config::{ChannelPage, SunChannelPlan},
+        test_clock::TestClock,
+    };
+
+    /// Return a channel at the given center frequency in kHz.
+    fn channel(center_frequency: u32) -> Channel {
+        let plan = SunChannelPlan {
+            center_frequency_0: center_frequency,
+            channel_spacing: 200,
+            num_channels: 1,
+        };
+        ChannelPage::Sun(plan).channel(0).unwrap()
+    }
+
+    fn instant(micros: u64) -> Instant<TestClock> {
+        Instant::new(micros)
+    }
+
+    /// 10% of a window of 16 ms, i.e. buckets of 1 ms.
+    const LIMIT: DutyCycleLimit =
+        DutyCycleLimit::new(868_000, 868_600, Duration::new(16_000), 100_000);
+
+    #[test]
+    fn limits() {
+        let limit = DutyCycleLimit::ETSI_868MHZ;
+        assert_eq!(limit.budget, Duration::new(36_000_000));
+        assert!(limit.contains(&channel(868_300)));
+        assert!(!limit.contains(&channel(868_700)));
+        assert!(!limit.contains(&Channel::_11));
+
+        assert_eq!(
+            DutyCycleBudget::<TestClock>::new(&[LIMIT; 5], DutyCyclePolicy::Deny, instant(0)).err(),
+            Some(DutyCycleConfigError::TooManyBands)
+        );
+        let empty = DutyCycleLimit::new(868_000, 868_600, Duration::new(16_000), 0);
+        assert_eq!(
+            DutyCycleBudget::<TestClock>::new(&[empty], DutyCyclePolicy::Deny, instant(0)).err(),
+            Some(DutyCycleConfigError::InvalidLimit)
+        );
+    }
+
+    #[test]
+    fn sliding_window() {
+        let mut budget =
+            DutyCycleBudget::<TestClock>::new(&[LIMIT], DutyCyclePolicy::Deny, instant(0)).unwrap();
+        let channel = channel(868_300);
+
+        budget.record(&channel, Duration::new(1_000), instant(500));
+        budget.record(&channel, Duration::new(500), instant(2_500));
+        assert_eq!(
+            budget.used(&channel, instant(3_000)),
+            Some(Duration::new(1_500))
+        );
+        assert_eq!(
+            budget.check(&channel, Duration::new(100), instant(3_000)),
+            Ok(())
+        );
+
+        // The frame fits once the first bucket left the window.
+        assert_eq!(
+            budget.check(&channel, Duration::new(200), instant(3_000)),
+            Err(DutyCycleExceeded {
+                available_at: Some(instant(17_000))
+            })
+        );
+        assert_eq!(
+            budget.check(&channel, Duration::new(200), instant(17_000)),
+            Ok(())
+        );
+        assert_eq!(
+            budget.used(&channel, instant(17_000)),
+            Some(Duration::new(500))
+        );
+
+        // Frames exceeding the whole budget never fit.
+        assert_eq!(
+            budget.check(&channel, Duration::new(1_601), instant(17_000)),
+            Err(DutyCycleExceeded { available_at: None })
+        );
+
+        // All buckets left the window.
+        assert_eq!(
+            budget.used(&channel, instant(100_000)),
+            Some(Duration::new(0))
+        );
+    }
+
+    #[test]
+    fn unlimited_channels() {
+        let mut budget =
+            DutyCycleBudget::<TestClock>::new(&[LIMIT], DutyCyclePolicy::Deny, instant(0)).unwrap();
+        budget.record(&Channel::_11, Duration::new(10_000), instant(0));
+        assert_eq!(budget.used(&Channel::_11, instant(0)), None);
+        assert_eq!(
+            budget.check(&Channel::_11, Duration::new(10_000), instant(0)),
+            Ok(())
+        );
+    }
+}
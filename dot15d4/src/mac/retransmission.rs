@@ -0,0 +1,399 @@
+//! Acknowledged transmissions (IEEE 802.15.4-2020, section 6.7.4.3).
+//!
+//! After transmitting a frame that requests an acknowledgement,
+//! [`Retransmissions`] listens for the matching ACK during the ACK wait
+//! duration and retransmits the frame up to macMaxFrameRetries times. A
+//! retransmission starts once the ACK wait duration expired, but never before
+//! the interframe space following the previous transmission. The timing of
+//! each attempt is reported so that it can be traced or used for link
+//! statistics.
+//!
+//! With [`Retransmissions::transmit_with_policy()`], a [`RetryPolicy`] decides
+//! the number of retransmissions per destination instead and learns from the
+//! outcome of each acknowledged transmission.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::A_MAX_SIFS_FRAME_SIZE,
+        frame::FrameControl,
+        radio::Radio,
+        time::{Duration, Frequency, Instant},
+        RadioTimerApi,
+    },
+    mac::frame::FrameError,
+};
+
+use super::{
+    ack::{AckFrame, AckMatcher, MacHeader, ReceivedAck},
+    counters::MAC_COUNTERS,
+    csma::{CsmaConfig, CsmaConfigError, MAX_FRAME_RETRIES},
+    radio::MacRadio,
+    retry::{self, FixedRetries, RetryPolicy},
+};
+
+/// The max number of transmission attempts of a frame.
+const MAX_ATTEMPTS: usize = MAX_FRAME_RETRIES as usize + 1;
+
+/// A single transmission attempt of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxAttempt<Timer: Frequency> {
+    /// The instant at which the transmission started.
+    pub start: Instant<Timer>,
+    /// The instant at which the transmission ended.
+    pub end: Instant<Timer>,
+    /// The matching ACK, if one was received.
+    pub ack: Option<ReceivedAck>,
+}
+
+/// The outcome of a transmission with [`Retransmissions::transmit()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxReport<Timer: Frequency> {
+    /// Whether the frame requested an acknowledgement.
+    pub ack_requested: bool,
+    /// All transmission attempts in chronological order.
+    pub attempts: heapless::Vec<TxAttempt<Timer>, MAX_ATTEMPTS>,
+    /// The earliest instant at which the next frame may be transmitted.
+    pub ifs_end: Instant<Timer>,
+}
+
+impl<Timer: Frequency> TxReport<Timer> {
+    /// Return whether the frame was acknowledged or didn't request an
+    /// acknowledgement.
+    pub fn is_success(&self) -> bool {
+        !self.ack_requested
+            || self
+                .attempts
+                .last()
+                .and_then(|attempt| attempt.ack)
+                .is_some_and(|ack| !ack.is_nack())
+    }
+
+    /// Return the number of retransmissions.
+    pub fn retries(&self) -> u8 {
+        self.attempts.len().saturating_sub(1) as u8
+    }
+}
+
+/// Transmits frames and retransmits them until they are acknowledged, see the
+/// module documentation.
+pub struct Retransmissions<Timer: RadioTimerApi> {
+    max_frame_retries: u8,
+    ack_wait: Duration<Timer>,
+    sifs: Duration<Timer>,
+    lifs: Duration<Timer>,
+    fcs_len: usize,
+}
+
+impl<Timer: RadioTimerApi> Retransmissions<Timer> {
+    /// Creates a new [`Retransmissions`] waiting macAckWaitDuration for ACKs.
+    ///
+    /// * `config` - The configuration providing macMaxFrameRetries,
+    ///   macAckWaitDuration, the interframe spaces and the FCS length
+    pub fn new(config: &CsmaConfig) -> Result<Self, CsmaConfigError> {
+        Self::with_ack_wait(config, config.ack_wait_duration)
+    }
+
+    /// Creates a new [`Retransmissions`] with the given ACK wait duration,
+    /// e.g. macTsAckWait in TSCH timeslots.
+    ///
+    /// * `config` - The configuration providing macMaxFrameRetries, the
+    ///   interframe spaces and the FCS length
+    /// * `ack_wait` - The time to wait for an ACK after a transmission
+    pub fn with_ack_wait<F: Frequency>(
+        config: &CsmaConfig,
+        ack_wait: Duration<F>,
+    ) -> Result<Self, CsmaConfigError> {
+        config.validate()?;
+        Ok(Self {
+            max_frame_retries: config.max_frame_retries,
+            ack_wait: ack_wait.convert_into_rounding_up(),
+            sifs: config.sifs.convert_into_rounding_up(),
+            lifs: config.lifs.convert_into_rounding_up(),
+            fcs_len: config.fcs_len as usize,
+        })
+    }
+
+    /// Transmits the given frame and retransmits it until it is acknowledged
+    /// or macMaxFrameRetries retransmissions failed.
+    ///
+    /// Frames that don't request an acknowledgement are transmitted once.
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `mpdu` - The MPDU (without FCS) to transmit
+    pub async fn transmit<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        mpdu: &[u8],
+    ) -> Result<TxReport<Timer>, FrameError> {
+        let mut policy = FixedRetries(self.max_frame_retries);
+        self.transmit_with_policy(radio, mpdu, &mut policy).await
+    }
+
+    /// Transmits the given frame and retransmits it until it is acknowledged
+    /// or the retransmissions allowed by the given policy for its destination
+    /// failed.
+    ///
+    /// The outcome of frames that request an acknowledgement is reported to
+    /// the policy.
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `mpdu` - The MPDU (without FCS) to transmit
+    /// * `policy` - The policy deciding the number of retransmissions
+    pub async fn transmit_with_policy<R: Radio<Timer = Timer>, Policy: RetryPolicy + ?Sized>(
+        &self,
+        radio: &mut MacRadio<R>,
+        mpdu: &[u8],
+        policy: &mut Policy,
+    ) -> Result<TxReport<Timer>, FrameError> {
+        let matcher = AckMatcher::new(mpdu)?;
+        let dst_address = dst_address(mpdu)?;
+        let max_frame_retries = retry::max_frame_retries(policy, &dst_address);
+        let mut report = TxReport {
+            ack_requested: matcher.is_some(),
+            attempts: heapless::Vec::new(),
+            ifs_end: Timer::now(),
+        };
+        let mut frame = AckFrame::new();
+        let mut next_start = Timer::now();
+        loop {
+            if next_start > Timer::now() {
+                Timer::wait_for_alarm_at(next_start).await;
+            }
+            let start = Timer::now();
+            let end = radio.transmit_now(mpdu).await;
+            report.ifs_end = end + self.ifs(mpdu.len());
+
+            let mut ack = None;
+            let ack_deadline = end + self.ack_wait;
+            if let Some(matcher) = &matcher {
+                while let Some(rx) = radio.receive_until(&mut frame, ack_deadline).await {
+                    if let Some(received) = matcher.matches(&frame) {
+                        ack = Some(received);
+                        report.ifs_end = rx.end + self.ifs(frame.len());
+                        break;
+                    }
+                }
+                if ack.is_none() {
+                    MAC_COUNTERS.ack_timeouts.increment();
+                }
+            }
+
+            // Safety: The number of attempts is limited to MAX_ATTEMPTS.
+            let _ = report.attempts.push(TxAttempt { start, end, ack });
+            if report.is_success() || report.retries() >= max_frame_retries {
+                if report.ack_requested {
+                    policy.update(&dst_address, report.is_success());
+                }
+                return Ok(report);
+            }
+            next_start = ack_deadline.max(report.ifs_end);
+        }
+    }
+
+    /// Return the interframe space following a frame of the given length.
+    ///
+    /// * `mpdu_len` - Length of the MPDU in octets, excluding the FCS
+    fn ifs(&self, mpdu_len: usize) -> Duration<Timer> {
+        if mpdu_len + self.fcs_len <= A_MAX_SIFS_FRAME_SIZE as usize {
+            self.sifs
+        } else {
+            self.lifs
+        }
+    }
+}
+
+/// Return the destination address (little endian) of the given frame, empty
+/// if it has none.
+fn dst_address(mpdu: &[u8]) -> Result<heapless::Vec<u8, 8>, FrameError> {
+    let frame_control = FrameControl::new(mpdu)?;
+    let dst_address = MacHeader::parse(mpdu, frame_control)?
+        .addressing_fields
+        .and_then(|addressing_fields| addressing_fields.into_dst_address());
+    Ok(dst_address
+        .and_then(|address| heapless::Vec::from_slice(address.as_le_bytes()).ok())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::*;
+    use crate::{
+        driver::test_clock::TestClock,
+        mac::test_helpers::{ScriptedAckRadio, SCRIPTED_TX_DURATION},
+    };
+
+    /// Data frame (2006) with sequence number 0x2a requesting an ACK.
+    const DATA: [u8; 12] = [
+        0x61, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33,
+    ];
+
+    const TX_DURATION: u64 = SCRIPTED_TX_DURATION as u64;
+
+    fn transmit(
+        config: &CsmaConfig,
+        rx: &[Option<(i64, &[u8])>],
+        mpdu: &[u8],
+    ) -> TxReport<TestClock> {
+        TestClock::reset();
+        let retransmissions = Retransmissions::<TestClock>::new(config).unwrap();
+        let mut radio = ScriptedAckRadio::new(rx);
+        let mut transmit = pin!(retransmissions.transmit(&mut radio, mpdu));
+        let mut cx = Context::from_waker(Waker::noop());
+        match transmit.as_mut().poll(&mut cx) {
+            Poll::Ready(report) => report.unwrap(),
+            Poll::Pending => panic!("retransmissions must not wait beyond the ACK wait"),
+        }
+    }
+
+    #[test]
+    fn retransmit_until_acked() {
+        // 54 symbols of 16µs each.
+        const ACK_WAIT: u64 = 864;
+        const SIFS: u64 = 192;
+
+        let report = transmit(
+            &CsmaConfig::default(),
+            &[
+                None,
+                Some((200, &[0x02, 0x10, 0x2b][..])),
+                Some((100, &[0x02, 0x10, 0x2a][..])),
+            ],
+            &DATA,
+        );
+        assert!(report.is_success());
+        assert_eq!(report.retries(), 1);
+
+        let first = &report.attempts[0];
+        assert_eq!(
+            (first.start, first.end),
+            (Instant::new(0), Instant::new(TX_DURATION))
+        );
+        assert_eq!(first.ack, None);
+
+        // The retransmission starts once the ACK wait expired.
+        let second = &report.attempts[1];
+        let start = TX_DURATION + ACK_WAIT;
+        assert_eq!(
+            (second.start, second.end),
+            (Instant::new(start), Instant::new(start + TX_DURATION))
+        );
+        assert_eq!(
+            second.ack,
+            Some(ReceivedAck {
+                frame_pending: false,
+                time_correction: None,
+                csl: None,
+            })
+        );
+        assert_eq!(
+            report.ifs_end,
+            Instant::new(start + TX_DURATION + 300 + SIFS)
+        );
+    }
+
+    #[test]
+    fn retries_exhausted() {
+        let config = CsmaConfig {
+            max_frame_retries: 1,
+            ..Default::default()
+        };
+        let report = transmit(&config, &[None, None], &DATA);
+        assert!(!report.is_success());
+        assert_eq!(report.attempts.len(), 2);
+        assert!(report.attempts.iter().all(|attempt| attempt.ack.is_none()));
+
+        // Frames without ACK request are transmitted once.
+        let mut mpdu = DATA;
+        mpdu[0] &= !0x20;
+        let report = transmit(&config, &[], &mpdu);
+        assert!(!report.ack_requested);
+        assert!(report.is_success());
+        assert_eq!(report.attempts.len(), 1);
+    }
+
+    #[test]
+    fn retry_policy() {
+        /// Policy allowing a single retransmission and recording outcomes.
+        struct RecordingPolicy(heapless::Vec<(heapless::Vec<u8, 8>, bool), 2>);
+
+        impl RetryPolicy for RecordingPolicy {
+            fn max_frame_retries(&self, dst_address: &[u8]) -> u8 {
+                assert_eq!(dst_address, [0x02, 0x00]);
+                1
+            }
+
+            fn update(&mut self, dst_address: &[u8], acked: bool) {
+                let dst_address = heapless::Vec::from_slice(dst_address).unwrap();
+                self.0.push((dst_address, acked)).unwrap();
+            }
+        }
+
+        TestClock::reset();
+        let retransmissions = Retransmissions::<TestClock>::new(&CsmaConfig::default()).unwrap();
+        let mut policy = RecordingPolicy(heapless::Vec::new());
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let rx = [None, None, None];
+        let mut radio = ScriptedAckRadio::new(&rx);
+        {
+            let mut transmit =
+                pin!(retransmissions.transmit_with_policy(&mut radio, &DATA, &mut policy));
+            let Poll::Ready(Ok(report)) = transmit.as_mut().poll(&mut cx) else {
+                panic!("retransmissions must not wait beyond the ACK wait");
+            };
+            assert_eq!(report.attempts.len(), 2);
+        }
+
+        // Frames without ACK request are not reported.
+        let mut mpdu = DATA;
+        mpdu[0] &= !0x20;
+        {
+            let mut transmit =
+                pin!(retransmissions.transmit_with_policy(&mut radio, &mpdu, &mut policy));
+            assert!(matches!(
+                transmit.as_mut().poll(&mut cx),
+                Poll::Ready(Ok(_))
+            ));
+        }
+        assert_eq!(
+            policy.0,
+            [(heapless::Vec::from_slice(&[0x02, 0x00]).unwrap(), false)]
+        );
+    }
+
+    #[test]
+    fn ifs_depends_on_frame_length() {
+        // 12 and 40 symbols of 16µs each.
+        const SIFS: u64 = 192;
+        const LIFS: u64 = 640;
+
+        let mut mpdu = [0; 17];
+        mpdu[..DATA.len()].copy_from_slice(&DATA);
+        mpdu[0] &= !0x20;
+
+        // 16 + 2 octets of FCS
+        let report = transmit(&CsmaConfig::default(), &[], &mpdu[..16]);
+        assert_eq!(report.ifs_end, Instant::new(TX_DURATION + SIFS));
+
+        // 17 + 2 octets of FCS exceed aMaxSifsFrameSize.
+        let report = transmit(&CsmaConfig::default(), &[], &mpdu);
+        assert_eq!(report.ifs_end, Instant::new(TX_DURATION + LIFS));
+
+        // 14 and 15 + 4 octets of CRC-32
+        let crc32 = CsmaConfig {
+            fcs_len: 4,
+            ..Default::default()
+        };
+        let report = transmit(&crc32, &[], &mpdu[..14]);
+        assert_eq!(report.ifs_end, Instant::new(TX_DURATION + SIFS));
+        let report = transmit(&crc32, &[], &mpdu[..15]);
+        assert_eq!(report.ifs_end, Instant::new(TX_DURATION + LIFS));
+    }
+}
@@ -0,0 +1,246 @@
+//! Retry policies of acknowledged transmissions.
+//!
+//! [`Retransmissions`](super::retransmission::Retransmissions) consults a
+//! [`RetryPolicy`] for the number of retransmissions of each frame and reports
+//! the outcome back to it:
+//! - [`FixedRetries`] always allows macMaxFrameRetries retransmissions.
+//! - [`LinearRetries`] lowers the limit by one for every frame that was not
+//!   acknowledged and raises it by one for every acknowledged frame.
+//! - [`AdaptiveRetries`] tracks the recent success rate per destination and
+//!   scales the limit of each destination with it.
+//!
+//! Lossy links thereby don't burn the energy of a full set of retransmissions
+//! on every frame, while good links keep the full limit.
+#![allow(dead_code)]
+
+use super::csma::MAX_FRAME_RETRIES;
+
+/// The weight of a new outcome in the success rates, as a power of two, i.e.
+/// 1/8.
+const SUCCESS_RATE_WEIGHT_SHIFT: u32 = 3;
+
+/// The fixed point representation of a success rate of 100%.
+const SUCCESS_RATE_ONE: u16 = 1 << 8;
+
+/// Decides how often frames are retransmitted, see the module documentation.
+pub trait RetryPolicy {
+    /// Return the max number of retransmissions of a frame to the given
+    /// destination, i.e. macMaxFrameRetries.
+    ///
+    /// Limits above [`MAX_FRAME_RETRIES`] are capped.
+    ///
+    /// * `dst_address` - Destination address (little endian), empty if the
+    ///   frame has none
+    fn max_frame_retries(&self, dst_address: &[u8]) -> u8;
+
+    /// Records the outcome of a frame that requested an acknowledgement.
+    ///
+    /// * `dst_address` - Destination address (little endian), empty if the
+    ///   frame has none
+    /// * `acked` - Whether the frame was acknowledged
+    fn update(&mut self, dst_address: &[u8], acked: bool);
+}
+
+/// The same number of retransmissions for all frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedRetries(pub u8);
+
+impl RetryPolicy for FixedRetries {
+    fn max_frame_retries(&self, _dst_address: &[u8]) -> u8 {
+        self.0
+    }
+
+    fn update(&mut self, _dst_address: &[u8], _acked: bool) {}
+}
+
+/// A number of retransmissions that decreases linearly with consecutive
+/// failures and recovers with each acknowledged frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinearRetries {
+    min: u8,
+    max: u8,
+    current: u8,
+}
+
+impl LinearRetries {
+    /// Creates a new [`LinearRetries`] starting at the max limit.
+    ///
+    /// * `min` - Lowest number of retransmissions
+    /// * `max` - Highest number of retransmissions
+    pub const fn new(min: u8, max: u8) -> Self {
+        Self {
+            min,
+            max,
+            current: max,
+        }
+    }
+}
+
+impl RetryPolicy for LinearRetries {
+    fn max_frame_retries(&self, _dst_address: &[u8]) -> u8 {
+        self.current
+    }
+
+    fn update(&mut self, _dst_address: &[u8], acked: bool) {
+        self.current = if acked {
+            self.current.saturating_add(1).min(self.max)
+        } else {
+            self.current.saturating_sub(1).max(self.min)
+        };
+    }
+}
+
+/// A number of retransmissions per destination that scales with the recent
+/// success rate of the destination.
+///
+/// The success rate is an exponentially weighted moving average of the
+/// outcomes of frames to up to `N` destinations, each new outcome weighted by
+/// 1/8. Unknown destinations are assumed to succeed. Once the table is full,
+/// the destination with the highest success rate is replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdaptiveRetries<const N: usize> {
+    min: u8,
+    max: u8,
+    /// The destinations (little endian) and their success rates in fixed
+    /// point.
+    destinations: heapless::Vec<(heapless::Vec<u8, 8>, u16), N>,
+}
+
+impl<const N: usize> AdaptiveRetries<N> {
+    /// Creates a new [`AdaptiveRetries`] without known destinations.
+    ///
+    /// * `min` - Number of retransmissions at a success rate of 0%
+    /// * `max` - Number of retransmissions at a success rate of 100%
+    pub const fn new(min: u8, max: u8) -> Self {
+        Self {
+            min,
+            max,
+            destinations: heapless::Vec::new(),
+        }
+    }
+
+    /// Return the success rate of the given destination in percent.
+    pub fn success_rate(&self, dst_address: &[u8]) -> u8 {
+        let rate = self.rate(dst_address) as u32;
+        ((rate * 100 + SUCCESS_RATE_ONE as u32 / 2) / SUCCESS_RATE_ONE as u32) as u8
+    }
+
+    fn rate(&self, dst_address: &[u8]) -> u16 {
+        self.destinations
+            .iter()
+            .find(|(address, _)| **address == *dst_address)
+            .map_or(SUCCESS_RATE_ONE, |(_, rate)| *rate)
+    }
+}
+
+impl<const N: usize> RetryPolicy for AdaptiveRetries<N> {
+    fn max_frame_retries(&self, dst_address: &[u8]) -> u8 {
+        let range = self.max.saturating_sub(self.min) as u32;
+        let rate = self.rate(dst_address) as u32;
+        let scaled = (range * rate + SUCCESS_RATE_ONE as u32 / 2) / SUCCESS_RATE_ONE as u32;
+        self.min + scaled as u8
+    }
+
+    fn update(&mut self, dst_address: &[u8], acked: bool) {
+        let Ok(address) = heapless::Vec::from_slice(dst_address) else {
+            return;
+        };
+        let index = match self
+            .destinations
+            .iter()
+            .position(|(known, _)| *known == address)
+        {
+            Some(index) => index,
+            None => {
+                let entry = (address, SUCCESS_RATE_ONE);
+                match self.destinations.push(entry) {
+                    Ok(()) => self.destinations.len() - 1,
+                    Err(entry) => {
+                        let Some(index) = (0..self.destinations.len())
+                            .max_by_key(|index| self.destinations[*index].1)
+                        else {
+                            // No destinations are tracked at all.
+                            return;
+                        };
+                        self.destinations[index] = entry;
+                        index
+                    }
+                }
+            }
+        };
+        let rate = &mut self.destinations[index].1;
+        let sample = if acked { SUCCESS_RATE_ONE } else { 0 } as i32;
+        let average = *rate as i32;
+        *rate = (average + ((sample - average) >> SUCCESS_RATE_WEIGHT_SHIFT)) as u16;
+    }
+}
+
+/// Return the number of retransmissions the given policy allows for a frame
+/// to the given destination, capped to [`MAX_FRAME_RETRIES`].
+pub(super) fn max_frame_retries<Policy: RetryPolicy + ?Sized>(
+    policy: &Policy,
+    dst_address: &[u8],
+) -> u8 {
+    policy.max_frame_retries(dst_address).min(MAX_FRAME_RETRIES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOD: [u8; 2] = [0x01, 0x00];
+    const LOSSY: [u8; 2] = [0x02, 0x00];
+
+    #[test]
+    fn fixed_and_linear() {
+        let mut fixed = FixedRetries(3);
+        fixed.update(&GOOD, false);
+        assert_eq!(fixed.max_frame_retries(&GOOD), 3);
+        assert_eq!(
+            max_frame_retries(&FixedRetries(9), &GOOD),
+            MAX_FRAME_RETRIES
+        );
+
+        let mut linear = LinearRetries::new(1, 3);
+        assert_eq!(linear.max_frame_retries(&GOOD), 3);
+        for _ in 0..3 {
+            linear.update(&GOOD, false);
+        }
+        assert_eq!(linear.max_frame_retries(&GOOD), 1);
+        linear.update(&LOSSY, true);
+        assert_eq!(linear.max_frame_retries(&GOOD), 2);
+    }
+
+    #[test]
+    fn adaptive() {
+        let mut adaptive = AdaptiveRetries::<2>::new(0, 4);
+        assert_eq!(adaptive.max_frame_retries(&LOSSY), 4);
+
+        // Each failure costs 1/8 of the success rate.
+        for _ in 0..4 {
+            adaptive.update(&GOOD, true);
+            adaptive.update(&LOSSY, false);
+        }
+        assert_eq!(adaptive.success_rate(&GOOD), 100);
+        assert_eq!(adaptive.success_rate(&LOSSY), 58);
+        assert_eq!(adaptive.max_frame_retries(&GOOD), 4);
+        assert_eq!(adaptive.max_frame_retries(&LOSSY), 2);
+        for _ in 0..16 {
+            adaptive.update(&LOSSY, false);
+        }
+        assert_eq!(adaptive.max_frame_retries(&LOSSY), 0);
+
+        // Recovery.
+        for _ in 0..8 {
+            adaptive.update(&LOSSY, true);
+        }
+        assert_eq!(adaptive.max_frame_retries(&LOSSY), 3);
+
+        // New destinations replace the best known one.
+        adaptive.update(&[0x03, 0x00], false);
+        assert_eq!(adaptive.success_rate(&[0x03, 0x00]), 88);
+        assert_eq!(adaptive.success_rate(&GOOD), 100);
+        assert!(adaptive.success_rate(&LOSSY) < 100);
+        assert_eq!(adaptive.destinations.len(), 2);
+    }
+}
@@ -0,0 +1,407 @@
+//! Receiver initiated transmission (IEEE 802.15.4-2020, section 6.12.3).
+//!
+//! RIT is a low-power mode in which the receiver rather than the transmitter
+//! initiates the exchange. A [`RitReceiver`] broadcasts a RIT Data Request
+//! command every macRitPeriod and keeps its receiver on for
+//! macRitDataWaitDuration afterwards. Its radio is off otherwise.
+//!
+//! A [`RitTransmitter`] keeps its receiver on for up to macRitTxWaitDuration
+//! until it receives a RIT Data Request command from the destination of the
+//! frame and transmits the frame right after. Broadcast frames are transmitted
+//! after the RIT Data Request command of any device.
+//!
+//! Unlike CSL, RIT requires no timing knowledge about the peers, at the cost
+//! of a listening transmitter and of a command broadcast every period.
+//!
+//! Note: Only RIT Data Request commands without payload, security and IEs are
+//! recognized. Destination and source addresses are compared as is, i.e. a
+//! frame to an extended address is not transmitted after a command from the
+//! corresponding short address.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::{A_BASE_SUPERFRAME_DURATION, PHY_MAX_PACKET_SIZE_127},
+        frame::{Address, FrameControl, FrameType, PanId},
+        phy::PhyParameters,
+        radio::Radio,
+        time::{Duration, Instant, Microseconds},
+        RadioTimerApi,
+    },
+    mac::frame::{
+        mpdu::{FrameBuffer, FrameBuilder},
+        FrameError,
+    },
+};
+
+use super::{
+    ack::{AckFrame, MacHeader},
+    csl::next_sample,
+    csma::{CsmaConfig, CsmaConfigError},
+    indirect::command_source,
+    radio::MacRadio,
+    retransmission::{Retransmissions, TxReport},
+};
+
+/// The command ID of the RIT Data Request command.
+pub const RIT_DATA_REQUEST_COMMAND_ID: u8 = 0x20;
+
+/// Configuration of RIT.
+///
+/// All durations are given in unit periods of aBaseSuperframeDuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RitConfig {
+    /// The timing of the PHY.
+    pub phy: PhyParameters,
+    /// macRitPeriod, the interval of the RIT Data Request commands of the
+    /// receiver, 0 if RIT is off.
+    pub period: u32,
+    /// macRitDataWaitDuration, the time the receiver listens after each RIT
+    /// Data Request command. Must cover the retransmissions of a frame.
+    pub data_wait_duration: u8,
+    /// macRitTxWaitDuration, the time a transmitter waits for a RIT Data
+    /// Request command of the destination. Should exceed macRitPeriod of the
+    /// receivers.
+    pub tx_wait_duration: u32,
+}
+
+impl Default for RitConfig {
+    fn default() -> Self {
+        Self {
+            phy: PhyParameters::default(),
+            period: 0,
+            data_wait_duration: 1,
+            // ~1s with the O-QPSK PHY
+            tx_wait_duration: 64,
+        }
+    }
+}
+
+impl RitConfig {
+    /// Return the duration of the given number of unit periods.
+    pub const fn unit_periods(&self, unit_periods: u32) -> Duration<Microseconds> {
+        self.phy
+            .symbols(A_BASE_SUPERFRAME_DURATION.ticks() * unit_periods as i64)
+    }
+}
+
+/// Build a RIT Data Request command.
+///
+/// * `seq_nr` - Sequence number of the command, i.e. macDsn
+/// * `pan_id` - macPanId of the device
+/// * `src_address` - Address of the device
+pub fn rit_data_request_frame(
+    seq_nr: u8,
+    pan_id: PanId<[u8; 2]>,
+    src_address: Address<&[u8]>,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let builder = FrameBuilder::new(FrameType::MacCommand)
+        .with_sequence_number(seq_nr)
+        .with_addressing(
+            Some((pan_id, Address::<&[u8]>::BROADCAST_ADDR)),
+            Some((pan_id, src_address)),
+        )
+        .without_security()
+        .without_ies()
+        .with_payload(&[RIT_DATA_REQUEST_COMMAND_ID]);
+    FrameBuffer::from_builder(&builder)
+}
+
+/// The receiving side of RIT, see the module documentation.
+pub struct RitReceiver<Timer: RadioTimerApi> {
+    period: Duration<Microseconds>,
+    data_wait: Duration<Timer>,
+    /// An instant at which a RIT Data Request command is sent.
+    anchor: Instant<Timer>,
+}
+
+impl<Timer: RadioTimerApi> RitReceiver<Timer> {
+    /// Creates a new [`RitReceiver`] sending RIT Data Request commands every
+    /// macRitPeriod from the given instant on.
+    ///
+    /// Returns `None` if macRitPeriod is 0.
+    pub fn new(config: &RitConfig, anchor: Instant<Timer>) -> Option<Self> {
+        (config.period > 0).then(|| Self {
+            period: config.unit_periods(config.period),
+            data_wait: config
+                .unit_periods(config.data_wait_duration as u32)
+                .convert_into_rounding_up(),
+            anchor,
+        })
+    }
+
+    /// Return the first RIT Data Request command at or after the given
+    /// instant.
+    pub fn next_request(&self, after: Instant<Timer>) -> Instant<Timer> {
+        next_sample(self.anchor, self.period, after)
+    }
+
+    /// Sends the given RIT Data Request command every macRitPeriod until a
+    /// frame is received into the given buffer and returns the instant at
+    /// which the reception ended.
+    ///
+    /// RIT Data Request commands of other receivers are ignored.
+    ///
+    /// * `radio` - The radio receiving the frame
+    /// * `data_request` - RIT Data Request command, see
+    ///   [`rit_data_request_frame()`]
+    /// * `frame` - Buffer receiving the frame
+    pub async fn receive<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        data_request: &[u8],
+        frame: &mut AckFrame,
+    ) -> Instant<Timer> {
+        loop {
+            let request_at = self.next_request(Timer::now());
+            if request_at > Timer::now() {
+                Timer::wait_for_alarm_at(request_at).await;
+            }
+            let deadline = radio.transmit_now(data_request).await + self.data_wait;
+            while let Some(rx) = radio.receive_until(frame, deadline).await {
+                if !matches!(
+                    command_source(frame, RIT_DATA_REQUEST_COMMAND_ID),
+                    Ok(Some(_))
+                ) {
+                    return rx.end;
+                }
+            }
+        }
+    }
+}
+
+/// The transmitting side of RIT, see the module documentation.
+pub struct RitTransmitter<Timer: RadioTimerApi> {
+    tx_wait: Duration<Timer>,
+    retransmissions: Retransmissions<Timer>,
+}
+
+impl<Timer: RadioTimerApi> RitTransmitter<Timer> {
+    /// Creates a new [`RitTransmitter`].
+    ///
+    /// * `config` - The RIT configuration
+    /// * `csma_config` - The configuration providing macMaxFrameRetries,
+    ///   macAckWaitDuration and the interframe spaces
+    pub fn new(config: &RitConfig, csma_config: &CsmaConfig) -> Result<Self, CsmaConfigError> {
+        Ok(Self {
+            tx_wait: config
+                .unit_periods(config.tx_wait_duration)
+                .convert_into_rounding_up(),
+            retransmissions: Retransmissions::new(csma_config)?,
+        })
+    }
+
+    /// Waits for a RIT Data Request command from the destination of the given
+    /// frame and transmits the frame, retransmitting it until it is
+    /// acknowledged or macMaxFrameRetries retransmissions failed.
+    ///
+    /// Returns `None` if no matching RIT Data Request command was received
+    /// within macRitTxWaitDuration.
+    ///
+    /// * `radio` - The radio transmitting the frame
+    /// * `mpdu` - The MPDU (without FCS) to transmit
+    ///
+    /// # Errors
+    ///
+    /// Any error of [`Retransmissions::transmit()`].
+    pub async fn transmit<R: Radio<Timer = Timer>>(
+        &self,
+        radio: &mut MacRadio<R>,
+        mpdu: &[u8],
+    ) -> Result<Option<TxReport<Timer>>, FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        let dst_address = MacHeader::parse(mpdu, frame_control)?
+            .addressing_fields
+            .and_then(|addressing_fields| addressing_fields.into_dst_address())
+            .filter(|address| !address.is_absent() && !address.is_broadcast());
+
+        let deadline = Timer::now() + self.tx_wait;
+        let mut frame = AckFrame::new();
+        while radio.receive_until(&mut frame, deadline).await.is_some() {
+            let Ok(Some(src_address)) = command_source(&frame, RIT_DATA_REQUEST_COMMAND_ID) else {
+                continue;
+            };
+            if dst_address
+                .as_ref()
+                .is_none_or(|dst_address| *dst_address.as_le_bytes() == src_address[..])
+            {
+                return self.retransmissions.transmit(radio, mpdu).await.map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            frame::{ExtendedAddress, ShortAddress},
+            radio::{RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+            test_clock::TestClock,
+        },
+        mac::test_helpers::{mac_radio, received, timed_out, transmitted, TEST_CAPABILITIES},
+    };
+
+    const RECEIVER: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    fn data_request(seq_nr: u8, src_address: &[u8]) -> FrameBuffer<PHY_MAX_PACKET_SIZE_127> {
+        let src_address = match src_address.len() {
+            2 => Address::Short(ShortAddress::new(src_address)),
+            _ => Address::Extended(ExtendedAddress::new(src_address)),
+        };
+        rit_data_request_frame(seq_nr, PanId::from_u16(0xabcd), src_address).unwrap()
+    }
+
+    /// Radio receiving the given frames at the given instants and recording
+    /// the start of its transmissions.
+    struct ScriptedRadio<'a> {
+        rx: core::slice::Iter<'a, (u64, &'a [u8])>,
+        tx_starts: heapless::Vec<u64, 4>,
+    }
+
+    impl Radio for ScriptedRadio<'_> {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            _mpdu: &[u8],
+            _at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            let start = TestClock::now();
+            self.tx_starts.push(start.tick()).unwrap();
+            TestClock::advance(Duration::new(500));
+            transmitted(start)
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            match self.rx.as_slice().first() {
+                Some((rx_end, mpdu)) if Some(Instant::new(*rx_end)) <= window.end => {
+                    self.rx.next();
+                    TestClock::advance(Instant::new(*rx_end) - TestClock::now());
+                    received(buffer, mpdu)
+                }
+                _ => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    #[test]
+    fn rit_data_request() {
+        let mpdu = data_request(7, &RECEIVER);
+        assert_eq!(
+            mpdu[..],
+            [
+                0x43, 0xd8, 0x07, 0xcd, 0xab, 0xff, 0xff, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02,
+                0x01, 0x20
+            ]
+        );
+        assert_eq!(
+            command_source(&mpdu, RIT_DATA_REQUEST_COMMAND_ID)
+                .unwrap()
+                .unwrap()[..],
+            RECEIVER
+        );
+    }
+
+    #[test]
+    fn receive() {
+        TestClock::reset();
+        let config = RitConfig {
+            period: 10,
+            ..Default::default()
+        };
+        // 10 unit periods of 15.36ms
+        let receiver = RitReceiver::<TestClock>::new(&config, Instant::new(1_000)).unwrap();
+        assert_eq!(receiver.next_request(Instant::new(1_001)).tick(), 154_600);
+        assert!(RitReceiver::<TestClock>::new(&RitConfig::default(), Instant::new(0)).is_none());
+
+        // The command of another receiver is ignored, the frame following the
+        // second command is received.
+        let other_request = data_request(1, &[0x34, 0x12]);
+        let data = [0x41, 0x88, 0x01, 0xcd, 0xab, 0xff, 0xff, 0x00, 0x00, 0x11];
+        let rx = [(2_000, &other_request[..]), (155_500, &data[..])];
+        let mut radio = mac_radio(ScriptedRadio {
+            rx: rx.iter(),
+            tx_starts: heapless::Vec::new(),
+        });
+        let request = data_request(7, &RECEIVER);
+        let mut frame = AckFrame::new();
+        let rx_end = TestClock::block_on(receiver.receive(&mut radio, &request, &mut frame));
+        assert_eq!(rx_end, Instant::new(155_500));
+        assert_eq!(frame[..], data);
+        assert_eq!(radio.radio_mut().tx_starts[..], [1_000, 154_600]);
+    }
+
+    #[test]
+    fn transmit() {
+        TestClock::reset();
+        let config = RitConfig::default();
+        let transmitter =
+            RitTransmitter::<TestClock>::new(&config, &CsmaConfig::default()).unwrap();
+        let data = [
+            0x61, 0xcc, 0x01, 0xcd, 0xab, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x08,
+            0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x02, 0x11,
+        ];
+
+        // The frame is transmitted after the command of its destination.
+        let other_request = data_request(1, &[0x34, 0x12]);
+        let request = data_request(7, &RECEIVER);
+        let rx = [
+            (2_000, &other_request[..]),
+            (3_000, &request[..]),
+            (3_800, &[0x02, 0x00, 0x01][..]),
+        ];
+        let mut radio = mac_radio(ScriptedRadio {
+            rx: rx.iter(),
+            tx_starts: heapless::Vec::new(),
+        });
+        let report = TestClock::block_on(transmitter.transmit(&mut radio, &data))
+            .unwrap()
+            .unwrap();
+        assert!(report.is_success());
+        assert_eq!(radio.radio_mut().tx_starts[..], [3_000]);
+
+        // Without command, the transmission fails after macRitTxWaitDuration.
+        TestClock::reset();
+        let mut radio = mac_radio(ScriptedRadio {
+            rx: [].iter(),
+            tx_starts: heapless::Vec::new(),
+        });
+        assert!(matches!(
+            TestClock::block_on(transmitter.transmit(&mut radio, &data)),
+            Ok(None)
+        ));
+        assert_eq!(TestClock::now().tick(), 64 * 15_360);
+        assert!(radio.radio_mut().tx_starts.is_empty());
+    }
+}
@@ -0,0 +1,780 @@
+//! MAC security (IEEE 802.15.4-2020, section 9).
+//!
+//! # Frame counters
+//!
+//! Outside of TSCH, the CCM* nonce of a secured frame contains the frame
+//! counter of the sender, see
+//! [`CcmNonce::from_frame_counter()`](super::tsch::CcmNonce::from_frame_counter).
+//! A nonce must never be reused with the same key, so the frame counter must
+//! keep increasing across reboots, otherwise receivers drop the frames as
+//! replays and an eavesdropper learns the XOR of the plaintexts.
+//!
+//! [`FrameCounter`] hands out the frame counters and persists them in a
+//! [`FrameCounterStore`] backed by the application, e.g. by flash or EEPROM.
+//! To spare the storage, only every `interval`-th frame counter is stored.
+//! On restore, the frame counter continues at the stored one plus a safety
+//! margin of at least `interval`, skipping all frame counters that may have
+//! been used since the last store.
+//!
+//! # Keys
+//!
+//! The [`KeyTable`] (macKeyTable) holds several keys per key identifier mode,
+//! each with the ASN or time from which on it is used for outgoing frames.
+//! Incoming frames are unsecured with whichever key their key identifier
+//! selects, so that during a key rollover, e.g. the 6TiSCH minimal-security
+//! rekeying or a Thread key sequence increment, frames secured with the
+//! previous key are still accepted while outgoing frames switch to the new key
+//! at its activation. The previous key is removed once the rollover completed,
+//! see [`KeyTable::remove_superseded()`].
+//!
+//! # Replay protection
+//!
+//! [`FrameCounterFilter`] keeps the frame counters received from each device
+//! per key (DeviceDescriptor). By default, a frame is only accepted if its
+//! frame counter is greater than the one of the last authenticated frame. In
+//! the optional window mode, frames of up to 64 frame counters below the
+//! greatest one received are accepted as well unless they were seen already,
+//! which tolerates reordering, e.g. when offloaded ACKs delay the processing
+//! in software. Replays are counted in
+//! [`MacCounters::replay_drops`](super::counters::MacCounters::replay_drops).
+#![allow(dead_code)]
+
+use crate::{
+    driver::time::{Instant, Microseconds},
+    mac::frame::repr::KeyIdRepr,
+};
+
+use super::{counters::MAC_COUNTERS, tsch::AbsoluteSlotNumber};
+
+/// The frame counter that indicates that the frame counter is exhausted.
+const EXHAUSTED_FRAME_COUNTER: u32 = u32::MAX;
+
+/// Default number of frame counters handed out between stores.
+pub const DEFAULT_STORE_INTERVAL: u32 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameCounterError<E> {
+    /// All frame counters were handed out, i.e. the key must be replaced
+    /// (COUNTER_ERROR).
+    Exhausted,
+    /// The frame counter could not be persisted. No frame counter is handed
+    /// out until a store succeeds.
+    Store(E),
+}
+
+/// Persists the outgoing frame counter, see the module documentation.
+pub trait FrameCounterStore {
+    type Error;
+
+    /// Return the last stored frame counter, if any.
+    fn load(&mut self) -> Result<Option<u32>, Self::Error>;
+
+    /// Persist the given frame counter. The frame counter must be durable
+    /// once this returns successfully.
+    fn store(&mut self, frame_counter: u32) -> Result<(), Self::Error>;
+}
+
+/// A [`FrameCounterStore`] that keeps nothing, e.g. for devices whose key is
+/// replaced on every boot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VolatileStore;
+
+impl FrameCounterStore for VolatileStore {
+    type Error = core::convert::Infallible;
+
+    fn load(&mut self) -> Result<Option<u32>, Self::Error> {
+        Ok(None)
+    }
+
+    fn store(&mut self, _frame_counter: u32) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// The outgoing frame counter (macFrameCounter) with batched persistence,
+/// see the module documentation.
+pub struct FrameCounter<Store: FrameCounterStore> {
+    store: Store,
+    /// The next frame counter to hand out.
+    next: u32,
+    /// The last stored frame counter.
+    stored: u32,
+    /// Number of frame counters handed out between stores.
+    interval: u32,
+}
+
+impl<Store: FrameCounterStore> FrameCounter<Store> {
+    /// Restores the frame counter from the given store.
+    ///
+    /// Without a stored frame counter, the frame counter starts at zero.
+    /// Otherwise it continues at the stored one plus the safety margin, which
+    /// is raised to the store interval if smaller. The restored frame counter
+    /// is stored right away, so that a subsequent reboot doesn't reuse it.
+    ///
+    /// * `store` - Persists the frame counter
+    /// * `interval` - Number of frame counters handed out between stores, at
+    ///   least 1
+    /// * `margin` - Number of frame counters skipped on restore
+    pub fn restore(
+        mut store: Store,
+        interval: u32,
+        margin: u32,
+    ) -> Result<Self, FrameCounterError<Store::Error>> {
+        let interval = interval.max(1);
+        let next = match store.load().map_err(FrameCounterError::Store)? {
+            Some(stored) => stored.saturating_add(margin.max(interval)),
+            None => 0,
+        };
+        store.store(next).map_err(FrameCounterError::Store)?;
+        Ok(Self {
+            store,
+            next,
+            stored: next,
+            interval,
+        })
+    }
+
+    /// Return the next frame counter to use for a secured frame and advance
+    /// it. The frame counter is stored first if `interval` frame counters were
+    /// handed out since the last store.
+    pub fn next(&mut self) -> Result<u32, FrameCounterError<Store::Error>> {
+        if self.next == EXHAUSTED_FRAME_COUNTER {
+            return Err(FrameCounterError::Exhausted);
+        }
+        if self.next - self.stored >= self.interval {
+            self.store
+                .store(self.next)
+                .map_err(FrameCounterError::Store)?;
+            self.stored = self.next;
+        }
+        let frame_counter = self.next;
+        self.next += 1;
+        Ok(frame_counter)
+    }
+
+    /// Return the next frame counter without advancing it.
+    pub fn peek(&self) -> u32 {
+        self.next
+    }
+
+    /// Persist the next frame counter immediately, e.g. before a planned
+    /// shutdown, so that no frame counters are skipped on restore.
+    pub fn flush(&mut self) -> Result<(), FrameCounterError<Store::Error>> {
+        self.store
+            .store(self.next)
+            .map_err(FrameCounterError::Store)?;
+        self.stored = self.next;
+        Ok(())
+    }
+
+    /// Restart the frame counter at zero, e.g. after the key was replaced.
+    pub fn reset(&mut self) -> Result<(), FrameCounterError<Store::Error>> {
+        self.next = 0;
+        self.flush()
+    }
+
+    /// Return the store.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+}
+
+/// The largest replay window in frame counters.
+pub const MAX_REPLAY_WINDOW: u8 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The frame counter was already seen from the device or lies below the
+    /// window.
+    Replay,
+    /// The frame counter is 0xffffffff, which is never used (COUNTER_ERROR).
+    Exhausted,
+    /// No room is left to track a new device and key.
+    Full,
+}
+
+/// The frame counters received from a device with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DeviceDescriptor {
+    /// Extended address (little endian) of the device.
+    address: [u8; 8],
+    key_id: KeyId,
+    /// The greatest frame counter of an authenticated frame.
+    frame_counter: u32,
+    /// Bit `i` is set if `frame_counter - i` was received, in window mode.
+    seen: u64,
+}
+
+/// Replay protection of frames secured with frame counters for up to `N`
+/// devices and keys, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct FrameCounterFilter<const N: usize> {
+    devices: heapless::Vec<DeviceDescriptor, N>,
+    /// Number of frame counters below the greatest one that are accepted if
+    /// not seen yet, zero if frame counters must strictly increase.
+    window: u8,
+}
+
+impl<const N: usize> Default for FrameCounterFilter<N> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<const N: usize> FrameCounterFilter<N> {
+    /// Creates a new [`FrameCounterFilter`].
+    ///
+    /// * `window` - Number of frame counters below the greatest one received
+    ///   that are accepted if not seen yet, zero for strictly increasing frame
+    ///   counters, at most [`MAX_REPLAY_WINDOW`]
+    pub fn new(window: u8) -> Self {
+        Self {
+            devices: heapless::Vec::new(),
+            window: window.min(MAX_REPLAY_WINDOW),
+        }
+    }
+
+    /// Check whether a frame received from the given device may be processed.
+    /// Must be called before authenticating the frame. Replays are counted.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `key_id` - Key identifier of the frame
+    /// * `frame_counter` - Frame counter of the frame
+    pub fn check(
+        &self,
+        src_address: &[u8; 8],
+        key_id: &KeyId,
+        frame_counter: u32,
+    ) -> Result<(), ReplayError> {
+        if frame_counter == EXHAUSTED_FRAME_COUNTER {
+            return Err(ReplayError::Exhausted);
+        }
+        let Some(device) = self.device(src_address, key_id) else {
+            return Ok(());
+        };
+        let fresh = match device.frame_counter.checked_sub(frame_counter) {
+            None => true,
+            Some(0) => false,
+            Some(age) if age < self.window as u32 => device.seen & (1 << age) == 0,
+            Some(_) => false,
+        };
+        if fresh {
+            Ok(())
+        } else {
+            MAC_COUNTERS.replay_drops.increment();
+            Err(ReplayError::Replay)
+        }
+    }
+
+    /// Record a frame of the given device that was successfully
+    /// authenticated.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `key_id` - Key identifier of the frame
+    /// * `frame_counter` - Frame counter of the frame
+    pub fn accept(
+        &mut self,
+        src_address: &[u8; 8],
+        key_id: &KeyId,
+        frame_counter: u32,
+    ) -> Result<(), ReplayError> {
+        match self
+            .devices
+            .iter_mut()
+            .find(|device| device.address == *src_address && device.key_id == *key_id)
+        {
+            Some(device) => match frame_counter.checked_sub(device.frame_counter) {
+                Some(advance) => {
+                    device.seen = device.seen.checked_shl(advance).unwrap_or(0) | 1;
+                    device.frame_counter = frame_counter;
+                }
+                None => {
+                    let age = device.frame_counter - frame_counter;
+                    if age < MAX_REPLAY_WINDOW as u32 {
+                        device.seen |= 1 << age;
+                    }
+                }
+            },
+            None => self
+                .devices
+                .push(DeviceDescriptor {
+                    address: *src_address,
+                    key_id: *key_id,
+                    frame_counter,
+                    seen: 1,
+                })
+                .map_err(|_| ReplayError::Full)?,
+        }
+        Ok(())
+    }
+
+    /// Return the greatest frame counter received from the given device with
+    /// the given key.
+    pub fn frame_counter(&self, src_address: &[u8; 8], key_id: &KeyId) -> Option<u32> {
+        self.device(src_address, key_id)
+            .map(|device| device.frame_counter)
+    }
+
+    /// Forget the given device.
+    pub fn remove(&mut self, src_address: &[u8; 8]) {
+        self.devices.retain(|device| device.address != *src_address);
+    }
+
+    /// Forget the frame counters of all devices with the given key, e.g.
+    /// after the key was removed from the [`KeyTable`].
+    pub fn remove_key(&mut self, key_id: &KeyId) {
+        self.devices.retain(|device| device.key_id != *key_id);
+    }
+
+    fn device(&self, src_address: &[u8; 8], key_id: &KeyId) -> Option<&DeviceDescriptor> {
+        self.devices
+            .iter()
+            .find(|device| device.address == *src_address && device.key_id == *key_id)
+    }
+}
+
+/// The length of a CCM* key.
+pub const KEY_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyError {
+    /// No room is left for a new key.
+    Full,
+    /// No key matches the key identifier or none is active.
+    UnknownKey,
+}
+
+/// The key identifier of the auxiliary security header, i.e. the key
+/// identifier mode, the key source and the key index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyId {
+    mode: KeyIdRepr,
+    /// The key source, of which only the first 0, 4 or 8 bytes are used
+    /// depending on the mode.
+    source: [u8; 8],
+    index: u8,
+}
+
+impl KeyId {
+    /// Creates the key identifier of a key determined implicitly by the
+    /// originator and the recipient (mode 0).
+    pub const fn implicit() -> Self {
+        Self {
+            mode: KeyIdRepr::Implicit,
+            source: [0; 8],
+            index: 0,
+        }
+    }
+
+    /// Creates the key identifier of a key determined by the key index and
+    /// macDefaultKeySource (mode 1).
+    pub const fn index(index: u8) -> Self {
+        Self {
+            mode: KeyIdRepr::SourceNone,
+            source: [0; 8],
+            index,
+        }
+    }
+
+    /// Creates the key identifier of a key determined by the given 4-byte key
+    /// source and key index (mode 2).
+    pub const fn source_4_byte(source: [u8; 4], index: u8) -> Self {
+        Self {
+            mode: KeyIdRepr::Source4Byte,
+            source: [source[0], source[1], source[2], source[3], 0, 0, 0, 0],
+            index,
+        }
+    }
+
+    /// Creates the key identifier of a key determined by the given 8-byte key
+    /// source and key index (mode 3).
+    pub const fn source_8_byte(source: [u8; 8], index: u8) -> Self {
+        Self {
+            mode: KeyIdRepr::Source8Byte,
+            source,
+            index,
+        }
+    }
+
+    /// Return the key identifier mode.
+    pub const fn mode(&self) -> KeyIdRepr {
+        self.mode
+    }
+
+    /// Return the key source, empty in modes 0 and 1.
+    pub fn source(&self) -> &[u8] {
+        let length = match self.mode {
+            KeyIdRepr::Implicit | KeyIdRepr::SourceNone => 0,
+            KeyIdRepr::Source4Byte => 4,
+            KeyIdRepr::Source8Byte => 8,
+        };
+        &self.source[..length]
+    }
+
+    /// Return the key index, zero in mode 0.
+    pub const fn key_index(&self) -> u8 {
+        self.index
+    }
+}
+
+/// The point from which on a key is used for outgoing frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyActivation {
+    /// The key is used as soon as it is added.
+    Immediate,
+    /// The key is used from the timeslot with the given ASN on, in TSCH.
+    Asn(AbsoluteSlotNumber),
+    /// The key is used from the given instant on.
+    Time(Instant<Microseconds>),
+}
+
+impl KeyActivation {
+    /// Return whether the key is active at the given ASN and instant.
+    ///
+    /// * `asn` - Current ASN, if TSCH is on
+    /// * `now` - Current instant
+    pub fn is_active(&self, asn: Option<AbsoluteSlotNumber>, now: Instant<Microseconds>) -> bool {
+        match self {
+            Self::Immediate => true,
+            Self::Asn(activation) => asn.is_some_and(|asn| asn >= *activation),
+            Self::Time(activation) => now >= *activation,
+        }
+    }
+}
+
+/// A key of the [`KeyTable`] (KeyDescriptor).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDescriptor {
+    /// Identifies the key in the auxiliary security header.
+    pub key_id: KeyId,
+    /// The CCM* key.
+    pub key: [u8; KEY_LEN],
+    /// The point from which on the key is used for outgoing frames.
+    pub activation: KeyActivation,
+}
+
+/// The keys of the device for up to `N` key identifiers (macKeyTable), see
+/// the module documentation.
+#[derive(Debug, Clone, Default)]
+pub struct KeyTable<const N: usize> {
+    /// The keys in the order in which they were added, i.e. the order of
+    /// their rollover.
+    keys: heapless::Vec<KeyDescriptor, N>,
+}
+
+impl<const N: usize> KeyTable<N> {
+    /// Creates a new empty [`KeyTable`].
+    pub const fn new() -> Self {
+        Self {
+            keys: heapless::Vec::new(),
+        }
+    }
+
+    /// Add the given key. A key with the same key identifier is replaced.
+    /// Keys are expected to be added in the order of their activation.
+    pub fn add(&mut self, descriptor: KeyDescriptor) -> Result<(), KeyError> {
+        self.remove(&descriptor.key_id);
+        self.keys.push(descriptor).map_err(|_| KeyError::Full)
+    }
+
+    /// Remove the key with the given key identifier.
+    pub fn remove(&mut self, key_id: &KeyId) {
+        self.keys.retain(|descriptor| descriptor.key_id != *key_id);
+    }
+
+    /// Return the key with the given key identifier to unsecure an incoming
+    /// frame, whether active or not.
+    pub fn incoming(&self, key_id: &KeyId) -> Result<&KeyDescriptor, KeyError> {
+        self.keys
+            .iter()
+            .find(|descriptor| descriptor.key_id == *key_id)
+            .ok_or(KeyError::UnknownKey)
+    }
+
+    /// Return the key to secure an outgoing frame with the given key
+    /// identifier mode, i.e. the most recently added key of the mode that is
+    /// active.
+    ///
+    /// * `mode` - Key identifier mode of the frame
+    /// * `asn` - Current ASN, if TSCH is on
+    /// * `now` - Current instant
+    pub fn outgoing(
+        &self,
+        mode: KeyIdRepr,
+        asn: Option<AbsoluteSlotNumber>,
+        now: Instant<Microseconds>,
+    ) -> Result<&KeyDescriptor, KeyError> {
+        self.keys
+            .iter()
+            .rev()
+            .filter(|descriptor| descriptor.key_id.mode == mode)
+            .find(|descriptor| descriptor.activation.is_active(asn, now))
+            .ok_or(KeyError::UnknownKey)
+    }
+
+    /// Remove the keys of the given key identifier mode that were superseded
+    /// by the key currently used for outgoing frames, i.e. complete a key
+    /// rollover. Keys that are not active yet are kept.
+    ///
+    /// * `mode` - Key identifier mode of the rollover
+    /// * `asn` - Current ASN, if TSCH is on
+    /// * `now` - Current instant
+    pub fn remove_superseded(
+        &mut self,
+        mode: KeyIdRepr,
+        asn: Option<AbsoluteSlotNumber>,
+        now: Instant<Microseconds>,
+    ) {
+        let Ok(current) = self
+            .outgoing(mode, asn, now)
+            .map(|descriptor| descriptor.key_id)
+        else {
+            return;
+        };
+        let Some(position) = self
+            .keys
+            .iter()
+            .position(|descriptor| descriptor.key_id == current)
+        else {
+            return;
+        };
+        let mut index = 0;
+        self.keys.retain(|descriptor| {
+            let superseded = index < position && descriptor.key_id.mode == mode;
+            index += 1;
+            !superseded
+        });
+    }
+
+    /// Return the keys in the order in which they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &KeyDescriptor> {
+        self.keys.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC_ADDRESS: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    /// A store recording its writes.
+    #[derive(Default)]
+    struct TestStore {
+        stored: Option<u32>,
+        writes: usize,
+        fail: bool,
+    }
+
+    impl FrameCounterStore for TestStore {
+        type Error = ();
+
+        fn load(&mut self) -> Result<Option<u32>, Self::Error> {
+            Ok(self.stored)
+        }
+
+        fn store(&mut self, frame_counter: u32) -> Result<(), Self::Error> {
+            if self.fail {
+                return Err(());
+            }
+            self.stored = Some(frame_counter);
+            self.writes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn batched_store() {
+        let mut counter = FrameCounter::restore(TestStore::default(), 4, 0).unwrap();
+        assert_eq!(counter.store().stored, Some(0));
+        for expected in 0..10 {
+            assert_eq!(counter.next(), Ok(expected));
+        }
+        // Stored on restore and before handing out 4 and 8.
+        assert_eq!(counter.store().writes, 3);
+        assert_eq!(counter.store().stored, Some(8));
+
+        // After a reboot, all frame counters possibly used are skipped.
+        let store = TestStore {
+            stored: counter.store().stored,
+            ..Default::default()
+        };
+        let mut counter = FrameCounter::restore(store, 4, 2).unwrap();
+        assert_eq!(counter.next(), Ok(12));
+        assert_eq!(counter.store().stored, Some(12));
+
+        let store = TestStore {
+            stored: Some(12),
+            ..Default::default()
+        };
+        let mut counter = FrameCounter::restore(store, 4, 100).unwrap();
+        assert_eq!(counter.next(), Ok(112));
+    }
+
+    #[test]
+    fn store_failure() {
+        let mut counter = FrameCounter::restore(TestStore::default(), 2, 0).unwrap();
+        assert_eq!(counter.next(), Ok(0));
+        assert_eq!(counter.next(), Ok(1));
+        counter.store.fail = true;
+        assert_eq!(counter.next(), Err(FrameCounterError::Store(())));
+        counter.store.fail = false;
+        assert_eq!(counter.next(), Ok(2));
+        assert_eq!(counter.store().stored, Some(2));
+    }
+
+    #[test]
+    fn exhausted() {
+        let store = TestStore {
+            stored: Some(u32::MAX - 3),
+            ..Default::default()
+        };
+        let mut counter = FrameCounter::restore(store, 2, 0).unwrap();
+        assert_eq!(counter.next(), Ok(u32::MAX - 1));
+        assert_eq!(counter.next(), Err(FrameCounterError::Exhausted));
+
+        counter.reset().unwrap();
+        assert_eq!(counter.next(), Ok(0));
+    }
+
+    fn key(key_id: KeyId, byte: u8, activation: KeyActivation) -> KeyDescriptor {
+        KeyDescriptor {
+            key_id,
+            key: [byte; KEY_LEN],
+            activation,
+        }
+    }
+
+    #[test]
+    fn key_ids() {
+        let key_id = KeyId::source_4_byte([0, 0, 0, 5], 6);
+        assert_eq!(key_id.mode(), KeyIdRepr::Source4Byte);
+        assert_eq!(key_id.source(), &[0, 0, 0, 5]);
+        assert_eq!(key_id.key_index(), 6);
+        assert_eq!(KeyId::index(1).source(), &[] as &[u8]);
+        assert_ne!(KeyId::index(1), KeyId::source_4_byte([0; 4], 1));
+    }
+
+    #[test]
+    fn key_rollover() {
+        let asn = |asn: i64| AbsoluteSlotNumber::try_from(asn).unwrap();
+        let now = Instant::new(0);
+        let mut table = KeyTable::<3>::new();
+        table
+            .add(key(KeyId::index(1), 0x11, KeyActivation::Immediate))
+            .unwrap();
+        table
+            .add(key(KeyId::index(2), 0x22, KeyActivation::Asn(asn(100))))
+            .unwrap();
+        table
+            .add(key(KeyId::implicit(), 0x33, KeyActivation::Immediate))
+            .unwrap();
+        assert_eq!(
+            table.add(key(KeyId::index(3), 0x44, KeyActivation::Immediate)),
+            Err(KeyError::Full)
+        );
+
+        // Before the activation, the previous key secures outgoing frames but
+        // both unsecure incoming ones.
+        let outgoing = table.outgoing(KeyIdRepr::SourceNone, Some(asn(99)), now);
+        assert_eq!(outgoing.unwrap().key, [0x11; KEY_LEN]);
+        assert_eq!(
+            table
+                .outgoing(KeyIdRepr::SourceNone, None, now)
+                .unwrap()
+                .key,
+            [0x11; KEY_LEN]
+        );
+        assert!(table.incoming(&KeyId::index(2)).is_ok());
+        assert_eq!(table.incoming(&KeyId::index(3)), Err(KeyError::UnknownKey));
+
+        let outgoing = table.outgoing(KeyIdRepr::SourceNone, Some(asn(100)), now);
+        assert_eq!(outgoing.unwrap().key, [0x22; KEY_LEN]);
+        assert_eq!(
+            table.outgoing(KeyIdRepr::Source8Byte, None, now),
+            Err(KeyError::UnknownKey)
+        );
+
+        // Completing the rollover only removes the keys of the same mode.
+        table.remove_superseded(KeyIdRepr::SourceNone, Some(asn(99)), now);
+        assert_eq!(table.iter().count(), 3);
+        table.remove_superseded(KeyIdRepr::SourceNone, Some(asn(100)), now);
+        assert_eq!(table.incoming(&KeyId::index(1)), Err(KeyError::UnknownKey));
+        assert!(table.incoming(&KeyId::implicit()).is_ok());
+        assert_eq!(table.iter().count(), 2);
+    }
+
+    #[test]
+    fn strict_replay_filter() {
+        let key_id = KeyId::index(1);
+        let mut filter = FrameCounterFilter::<1>::default();
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 5), Ok(()));
+        filter.accept(&SRC_ADDRESS, &key_id, 5).unwrap();
+        assert_eq!(filter.frame_counter(&SRC_ADDRESS, &key_id), Some(5));
+        for frame_counter in [4, 5] {
+            assert_eq!(
+                filter.check(&SRC_ADDRESS, &key_id, frame_counter),
+                Err(ReplayError::Replay)
+            );
+        }
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 6), Ok(()));
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, &key_id, u32::MAX),
+            Err(ReplayError::Exhausted)
+        );
+
+        // Frame counters are tracked per key.
+        assert_eq!(filter.check(&SRC_ADDRESS, &KeyId::index(2), 1), Ok(()));
+        assert_eq!(
+            filter.accept(&SRC_ADDRESS, &KeyId::index(2), 1),
+            Err(ReplayError::Full)
+        );
+        filter.remove_key(&key_id);
+        assert_eq!(filter.frame_counter(&SRC_ADDRESS, &key_id), None);
+    }
+
+    #[test]
+    fn windowed_replay_filter() {
+        let key_id = KeyId::index(1);
+        let mut filter = FrameCounterFilter::<2>::new(4);
+        filter.accept(&SRC_ADDRESS, &key_id, 10).unwrap();
+        filter.accept(&SRC_ADDRESS, &key_id, 8).unwrap();
+
+        // Reordered frames within the window are accepted once.
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 9), Ok(()));
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 7), Ok(()));
+        for frame_counter in [10, 8, 6] {
+            assert_eq!(
+                filter.check(&SRC_ADDRESS, &key_id, frame_counter),
+                Err(ReplayError::Replay)
+            );
+        }
+
+        // The window moves with the greatest frame counter.
+        filter.accept(&SRC_ADDRESS, &key_id, 12).unwrap();
+        assert_eq!(filter.frame_counter(&SRC_ADDRESS, &key_id), Some(12));
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 9), Ok(()));
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, &key_id, 10),
+            Err(ReplayError::Replay)
+        );
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, &key_id, 8),
+            Err(ReplayError::Replay)
+        );
+        filter.accept(&SRC_ADDRESS, &key_id, 100).unwrap();
+        assert_eq!(filter.check(&SRC_ADDRESS, &key_id, 99), Ok(()));
+
+        filter.remove(&SRC_ADDRESS);
+        assert_eq!(filter.frame_counter(&SRC_ADDRESS, &key_id), None);
+    }
+
+    #[test]
+    fn time_activation() {
+        let activation = KeyActivation::Time(Instant::new(1_000));
+        assert!(!activation.is_active(None, Instant::new(999)));
+        assert!(activation.is_active(None, Instant::new(1_000)));
+        let asn = AbsoluteSlotNumber::try_from(5i64).unwrap();
+        assert!(!KeyActivation::Asn(asn).is_active(None, Instant::new(0)));
+    }
+}
@@ -0,0 +1,157 @@
+//! Sequence numbers (IEEE 802.15.4-2020, sections 6.7.2 and 6.7.4.2).
+//!
+//! The MAC sublayer numbers outgoing data and MAC command frames with macDsn
+//! and beacons with macBsn, see [`SequenceNumber`]. On the receive path,
+//! retransmissions of a frame carry the same sequence number. They are
+//! acknowledged like any other frame, but [`DuplicateFilter`] remembers the
+//! last sequence number received from each source so that a retransmission
+//! whose first ACK got lost is not delivered twice to the upper layer.
+#![allow(dead_code)]
+
+use crate::mac::frame::mpdu::MpduFrame;
+
+/// Default number of sources tracked by [`DuplicateFilter`].
+pub const DEFAULT_DUPLICATE_FILTER_SIZE: usize = 8;
+
+/// A sequence number counter such as macDsn or macBsn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceNumber(u8);
+
+impl SequenceNumber {
+    /// Creates a new [`SequenceNumber`] starting at the given value, e.g. a
+    /// random value as recommended by the standard.
+    pub const fn new(initial: u8) -> Self {
+        Self(initial)
+    }
+
+    /// Return the sequence number of the next frame without allocating it.
+    pub const fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Allocate the sequence number of the next frame.
+    pub fn next(&mut self) -> u8 {
+        let seq_nr = self.0;
+        self.0 = self.0.wrapping_add(1);
+        seq_nr
+    }
+}
+
+/// Duplicate detection of received frames for up to `N` sources, see the
+/// module documentation.
+///
+/// When the table is full, the least recently seen source is forgotten.
+#[derive(Debug, Default)]
+pub struct DuplicateFilter<const N: usize> {
+    /// Source address (little endian) and sequence number of the last frame
+    /// received from each source, least recently seen first.
+    entries: heapless::Vec<(heapless::Vec<u8, 8>, u8), N>,
+}
+
+impl<const N: usize> DuplicateFilter<N> {
+    /// Creates a new, empty [`DuplicateFilter`].
+    pub const fn new() -> Self {
+        Self {
+            entries: heapless::Vec::new(),
+        }
+    }
+
+    /// Record a frame received from the given source and return whether it
+    /// duplicates the previous frame of that source.
+    ///
+    /// * `src_address` - Short or extended source address (little endian)
+    /// * `seq_nr` - Sequence number of the frame
+    pub fn is_duplicate(&mut self, src_address: &[u8], seq_nr: u8) -> bool {
+        let (mut entry, duplicate) = match self
+            .entries
+            .iter()
+            .position(|(address, _)| **address == *src_address)
+        {
+            Some(index) => {
+                let entry = self.entries.remove(index);
+                let duplicate = entry.1 == seq_nr;
+                (entry, duplicate)
+            }
+            None => {
+                if self.entries.is_full() {
+                    self.entries.remove(0);
+                }
+                // Safety: Addresses are at most 8 bytes long.
+                (
+                    (heapless::Vec::from_slice(src_address).unwrap(), seq_nr),
+                    false,
+                )
+            }
+        };
+        entry.1 = seq_nr;
+        // Safety: We made room for the entry above.
+        let _ = self.entries.push(entry);
+        duplicate
+    }
+
+    /// Record the given frame and return whether it duplicates the previous
+    /// frame of its source.
+    ///
+    /// Frames without sequence number or source address are never considered
+    /// duplicates as they cannot be told apart.
+    pub fn is_duplicate_frame(&mut self, mpdu: &MpduFrame) -> bool {
+        let Some(seq_nr) = mpdu.sequence_number() else {
+            return false;
+        };
+        let reader = match mpdu.reader().parse_addressing() {
+            Ok(reader) => reader,
+            Err(_) => return false,
+        };
+        match reader
+            .into_addressing_fields()
+            .ok()
+            .flatten()
+            .and_then(|fields| fields.into_src_address())
+        {
+            Some(src_address) if !src_address.as_le_bytes().is_empty() => {
+                self.is_duplicate(src_address.as_le_bytes(), seq_nr)
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget all sources.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORT: [u8; 2] = [0x01, 0x00];
+    const EXTENDED: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    #[test]
+    fn sequence_number() {
+        let mut dsn = SequenceNumber::new(0xfe);
+        assert_eq!(dsn.next(), 0xfe);
+        assert_eq!(dsn.next(), 0xff);
+        assert_eq!(dsn.next(), 0x00);
+        assert_eq!(dsn.value(), 0x01);
+    }
+
+    #[test]
+    fn duplicate_filter() {
+        let mut filter = DuplicateFilter::<2>::new();
+        assert!(!filter.is_duplicate(&SHORT, 1));
+        assert!(filter.is_duplicate(&SHORT, 1));
+        assert!(!filter.is_duplicate(&EXTENDED, 1));
+        assert!(!filter.is_duplicate(&SHORT, 2));
+        assert!(filter.is_duplicate(&SHORT, 2));
+
+        // The least recently seen source is forgotten.
+        assert!(!filter.is_duplicate(&[0x02, 0x00], 2));
+        assert!(!filter.is_duplicate(&EXTENDED, 1));
+        assert!(filter.is_duplicate(&[0x02, 0x00], 2));
+
+        filter.clear();
+        assert!(!filter.is_duplicate(&[0x02, 0x00], 2));
+    }
+}
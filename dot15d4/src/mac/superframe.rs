@@ -0,0 +1,538 @@
+//! Superframe structure of beacon-enabled PANs (IEEE 802.15.4-2020, section
+//! 6.2.1).
+//!
+//! A superframe starts with a beacon and is divided into
+//! [`A_NUM_SUPERFRAME_SLOTS`] equally sized slots. The active portion consists
+//! of the contention access period (CAP), which ends with the final CAP slot,
+//! followed by the contention free period (CFP) containing the GTSs. If the
+//! superframe order (SO) is smaller than the beacon order (BO), the active
+//! portion is followed by an inactive period lasting until the next beacon:
+//!
+//! ```text
+//! | beacon |<- CAP ->|<- CFP ->|<- inactive ->| beacon |
+//! |<-- aBaseSuperframeDuration * 2^SO -->|
+//! |<------ aBaseSuperframeDuration * 2^BO ------>|
+//! ```
+//!
+//! [`Superframe`] derives the boundaries of the slots and periods in radio
+//! timer ticks from the superframe specification and the start of the last
+//! beacon transmitted or received. Superframes following the last beacon are
+//! extrapolated from the beacon interval, so that a device keeps its schedule
+//! while it misses up to [`A_MAX_LOST_BEACONS`] beacons.
+//!
+//! Slotted CSMA-CA aligns its backoffs with
+//! [`Superframe::next_backoff_boundary()`] and only transmits if the
+//! transaction completes before the end of the CAP, see
+//! [`Superframe::fits_in_cap()`].
+//!
+//! With battery life extension (BLE), the coordinator only listens for a short
+//! window after the beacon. Transmissions in the CAP must start within
+//! macBattLifeExtPeriods backoff periods after the IFS following the beacon,
+//! see [`Superframe::enable_battery_life_extension()`]. The coordinator
+//! announces BLE in the superframe specification of its beacons, see
+//! [`Superframe::write_specification()`].
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::{A_BASE_SLOT_DURATION, A_MAX_LOST_BEACONS, A_NUM_SUPERFRAME_SLOTS},
+        phy::PhyParameters,
+        time::{Duration, Frequency, Instant, SymbolsOQpsk250kB},
+    },
+    mac::frame::fields::{SuperframeSpecification, NON_BEACON_ENABLED_ORDER},
+};
+
+/// The max beacon order of a beacon-enabled PAN.
+pub const MAX_BEACON_ORDER: u8 = NON_BEACON_ENABLED_ORDER - 1;
+
+/// Default value of macBattLifeExtPeriods.
+pub const DEFAULT_BATT_LIFE_EXT_PERIODS: u8 = 6;
+
+/// The highest initial backoff exponent of the slotted CSMA-CA algorithm with
+/// battery life extension.
+const BATT_LIFE_EXT_MAX_INITIAL_BE: u8 = 2;
+
+/// Return the duration of a superframe slot in symbols.
+///
+/// * `superframe_order` - macSuperframeOrder
+pub const fn slot_duration(superframe_order: u8) -> Duration<SymbolsOQpsk250kB> {
+    Duration::new(A_BASE_SLOT_DURATION.ticks() << superframe_order)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuperframeError {
+    /// The PAN does not transmit beacons or the beacon order is invalid.
+    BeaconOrder,
+    /// The superframe order exceeds the beacon order.
+    SuperframeOrder,
+    /// The final CAP slot is not a superframe slot.
+    FinalCapSlot,
+}
+
+/// A period of the superframe, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuperframePeriod {
+    /// The contention access period, including the beacon.
+    Cap,
+    /// The contention free period.
+    Cfp,
+    /// The inactive period.
+    Inactive,
+}
+
+/// The superframe schedule for a given radio timer, see the module
+/// documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Superframe<Timer: Frequency> {
+    beacon_order: u8,
+    superframe_order: u8,
+    final_cap_slot: u8,
+    beacon_interval: Duration<Timer>,
+    slot_duration: Duration<Timer>,
+    unit_backoff_period: Duration<Timer>,
+    /// The start of the last beacon transmitted or received.
+    last_beacon: Option<Instant<Timer>>,
+    /// The number of consecutive beacons missed since the last beacon.
+    lost_beacons: u8,
+    /// The end of the BLE window relative to the start of the beacon, if
+    /// battery life extension is enabled.
+    battery_life_extension: Option<Duration<Timer>>,
+}
+
+impl<Timer: Frequency> Superframe<Timer> {
+    /// Creates a new [`Superframe`] schedule without beacon.
+    ///
+    /// * `phy` - Timing parameters of the PHY
+    /// * `beacon_order` - macBeaconOrder
+    /// * `superframe_order` - macSuperframeOrder
+    /// * `final_cap_slot` - The final superframe slot of the CAP
+    pub fn new(
+        phy: &PhyParameters,
+        beacon_order: u8,
+        superframe_order: u8,
+        final_cap_slot: u8,
+    ) -> Result<Self, SuperframeError> {
+        if beacon_order > MAX_BEACON_ORDER {
+            return Err(SuperframeError::BeaconOrder);
+        }
+        if superframe_order > beacon_order {
+            return Err(SuperframeError::SuperframeOrder);
+        }
+        if final_cap_slot >= A_NUM_SUPERFRAME_SLOTS {
+            return Err(SuperframeError::FinalCapSlot);
+        }
+
+        let slot_symbols = A_BASE_SLOT_DURATION.ticks();
+        let superframe_symbols = slot_symbols * A_NUM_SUPERFRAME_SLOTS as i64;
+        Ok(Self {
+            beacon_order,
+            superframe_order,
+            final_cap_slot,
+            beacon_interval: phy
+                .symbols(superframe_symbols << beacon_order)
+                .convert_into_rounding_down(),
+            slot_duration: phy
+                .symbols(slot_symbols << superframe_order)
+                .convert_into_rounding_down(),
+            unit_backoff_period: phy.unit_backoff_period().convert_into_rounding_up(),
+            last_beacon: None,
+            lost_beacons: 0,
+            battery_life_extension: None,
+        })
+    }
+
+    /// Creates a new [`Superframe`] schedule from the superframe
+    /// specification of a beacon.
+    ///
+    /// Battery life extension has to be enabled separately as the BLE window
+    /// depends on the length of the beacon.
+    pub fn from_specification<Bytes: AsRef<[u8]>>(
+        phy: &PhyParameters,
+        specification: &SuperframeSpecification<Bytes>,
+    ) -> Result<Self, SuperframeError> {
+        Self::new(
+            phy,
+            specification.beacon_order(),
+            specification.superframe_order(),
+            specification.final_cap_slot(),
+        )
+    }
+
+    /// Return macBeaconOrder.
+    pub fn beacon_order(&self) -> u8 {
+        self.beacon_order
+    }
+
+    /// Return macSuperframeOrder.
+    pub fn superframe_order(&self) -> u8 {
+        self.superframe_order
+    }
+
+    /// Return the final superframe slot of the CAP.
+    pub fn final_cap_slot(&self) -> u8 {
+        self.final_cap_slot
+    }
+
+    /// Update the final superframe slot of the CAP, e.g. after GTSs were
+    /// allocated or deallocated.
+    pub fn set_final_cap_slot(&mut self, final_cap_slot: u8) -> Result<(), SuperframeError> {
+        if final_cap_slot >= A_NUM_SUPERFRAME_SLOTS {
+            return Err(SuperframeError::FinalCapSlot);
+        }
+        self.final_cap_slot = final_cap_slot;
+        Ok(())
+    }
+
+    /// Return whether battery life extension is enabled (macBattLifeExt).
+    pub fn battery_life_extension(&self) -> bool {
+        self.battery_life_extension.is_some()
+    }
+
+    /// Enable battery life extension, restricting transmissions in the CAP to
+    /// the BLE window.
+    ///
+    /// * `periods` - macBattLifeExtPeriods
+    /// * `beacon_duration` - Duration of the beacon including the IFS
+    ///   following it
+    pub fn enable_battery_life_extension<F: Frequency>(
+        &mut self,
+        periods: u8,
+        beacon_duration: Duration<F>,
+    ) {
+        let beacon_duration: Duration<Timer> = beacon_duration.convert_into_rounding_up();
+        self.battery_life_extension =
+            Some(beacon_duration + self.unit_backoff_period * periods as usize);
+    }
+
+    /// Disable battery life extension.
+    pub fn disable_battery_life_extension(&mut self) {
+        self.battery_life_extension = None;
+    }
+
+    /// Write the orders, the final CAP slot and the BLE flag to the given
+    /// superframe specification of a beacon.
+    pub fn write_specification<Bytes: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        specification: &mut SuperframeSpecification<Bytes>,
+    ) {
+        specification.set_beacon_order(self.beacon_order);
+        specification.set_superframe_order(self.superframe_order);
+        specification.set_final_cap_slot(self.final_cap_slot);
+        specification.set_battery_life_extension(self.battery_life_extension());
+    }
+
+    /// Return the initial backoff exponent of the slotted CSMA-CA algorithm,
+    /// i.e. the lesser of 2 and macMinBE with battery life extension.
+    ///
+    /// * `min_be` - macMinBE
+    pub fn initial_backoff_exponent(&self, min_be: u8) -> u8 {
+        if self.battery_life_extension() {
+            min_be.min(BATT_LIFE_EXT_MAX_INITIAL_BE)
+        } else {
+            min_be
+        }
+    }
+
+    /// Return the time between the start of two consecutive beacons.
+    pub fn beacon_interval(&self) -> Duration<Timer> {
+        self.beacon_interval
+    }
+
+    /// Return the duration of a superframe slot.
+    pub fn slot_duration(&self) -> Duration<Timer> {
+        self.slot_duration
+    }
+
+    /// Return the duration of the active portion of the superframe.
+    pub fn superframe_duration(&self) -> Duration<Timer> {
+        self.slot_duration * A_NUM_SUPERFRAME_SLOTS as usize
+    }
+
+    /// Record the start of a beacon transmitted or received.
+    pub fn on_beacon(&mut self, at: Instant<Timer>) {
+        self.last_beacon = Some(at);
+        self.lost_beacons = 0;
+    }
+
+    /// Record that an expected beacon was not received.
+    ///
+    /// Returns `true` if aMaxLostBeacons consecutive beacons were missed, i.e.
+    /// the device lost synchronization with its coordinator. The schedule is
+    /// reset in this case.
+    pub fn on_beacon_missed(&mut self) -> bool {
+        if self.last_beacon.is_none() {
+            return false;
+        }
+        self.lost_beacons += 1;
+        if self.lost_beacons < A_MAX_LOST_BEACONS {
+            return false;
+        }
+        self.last_beacon = None;
+        self.lost_beacons = 0;
+        true
+    }
+
+    /// Return the start of the last beacon transmitted or received.
+    pub fn last_beacon(&self) -> Option<Instant<Timer>> {
+        self.last_beacon
+    }
+
+    /// Return the start of the superframe containing the given instant.
+    ///
+    /// Returns `None` if no beacon was recorded or the instant lies before the
+    /// last beacon.
+    pub fn superframe_start(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        let last_beacon = self.last_beacon?;
+        if at < last_beacon {
+            return None;
+        }
+        let elapsed = (at - last_beacon).ticks();
+        let superframes = elapsed / self.beacon_interval.ticks();
+        Some(last_beacon + self.beacon_interval * superframes as usize)
+    }
+
+    /// Return the start of the next beacon following the given instant.
+    pub fn next_beacon(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        self.superframe_start(at)
+            .map(|start| start + self.beacon_interval)
+    }
+
+    /// Return the start of the given slot within the superframe containing
+    /// the given instant.
+    ///
+    /// Slot [`A_NUM_SUPERFRAME_SLOTS`] denotes the end of the active portion.
+    pub fn slot_start(&self, at: Instant<Timer>, slot: u8) -> Option<Instant<Timer>> {
+        self.superframe_start(at)
+            .map(|start| start + self.slot_duration * slot as usize)
+    }
+
+    /// Return the end of the CAP within the superframe containing the given
+    /// instant.
+    pub fn cap_end(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        self.slot_start(at, self.final_cap_slot + 1)
+    }
+
+    /// Return the end of the active portion within the superframe containing
+    /// the given instant.
+    pub fn active_end(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        self.slot_start(at, A_NUM_SUPERFRAME_SLOTS)
+    }
+
+    /// Return the end of the BLE window within the superframe containing the
+    /// given instant.
+    ///
+    /// Returns `None` if battery life extension is disabled.
+    pub fn ble_window_end(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        let window = self.battery_life_extension?;
+        self.superframe_start(at).map(|start| start + window)
+    }
+
+    /// Return the period of the superframe the given instant lies in.
+    pub fn period(&self, at: Instant<Timer>) -> Option<SuperframePeriod> {
+        let period = if at < self.cap_end(at)? {
+            SuperframePeriod::Cap
+        } else if at < self.active_end(at)? {
+            SuperframePeriod::Cfp
+        } else {
+            SuperframePeriod::Inactive
+        };
+        Some(period)
+    }
+
+    /// Return the time left in the CAP at the given instant.
+    ///
+    /// Returns `None` outside the CAP.
+    pub fn cap_remaining(&self, at: Instant<Timer>) -> Option<Duration<Timer>> {
+        let cap_end = self.cap_end(at)?;
+        (at < cap_end).then(|| cap_end - at)
+    }
+
+    /// Return whether a transaction of the given duration, including the
+    /// CCAs, the ACK and the following IFS, completes within the CAP if it
+    /// starts at the given instant.
+    ///
+    /// With battery life extension, the transaction must also start within the
+    /// BLE window.
+    pub fn fits_in_cap(&self, at: Instant<Timer>, duration: Duration<Timer>) -> bool {
+        self.ble_window_end(at)
+            .is_none_or(|ble_window_end| at < ble_window_end)
+            && self
+                .cap_remaining(at)
+                .is_some_and(|remaining| duration <= remaining)
+    }
+
+    /// Return the first backoff period boundary at or after the given
+    /// instant.
+    ///
+    /// Backoff periods of the slotted CSMA-CA algorithm are aligned with the
+    /// start of the beacon.
+    pub fn next_backoff_boundary(&self, at: Instant<Timer>) -> Option<Instant<Timer>> {
+        let start = self.superframe_start(at)?;
+        let unit_backoff_period = self.unit_backoff_period.ticks();
+        let periods = ((at - start).ticks() + unit_backoff_period - 1) / unit_backoff_period;
+        Some(start + self.unit_backoff_period * periods as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::time::Microseconds;
+
+    fn superframe(beacon_order: u8, superframe_order: u8) -> Superframe<Microseconds> {
+        Superframe::new(
+            &PhyParameters::OQPSK_2450MHZ,
+            beacon_order,
+            superframe_order,
+            9,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn invalid_orders() {
+        let phy = PhyParameters::OQPSK_2450MHZ;
+        assert_eq!(
+            Superframe::<Microseconds>::new(&phy, 15, 15, 15),
+            Err(SuperframeError::BeaconOrder)
+        );
+        assert_eq!(
+            Superframe::<Microseconds>::new(&phy, 3, 4, 15),
+            Err(SuperframeError::SuperframeOrder)
+        );
+        assert_eq!(
+            Superframe::<Microseconds>::new(&phy, 3, 3, 16),
+            Err(SuperframeError::FinalCapSlot)
+        );
+    }
+
+    #[test]
+    fn durations() {
+        let superframe = superframe(6, 4);
+        // 960 symbols * 2^6 and 60 symbols * 2^4 of 16µs each.
+        assert_eq!(superframe.beacon_interval(), Duration::new(983_040));
+        assert_eq!(superframe.slot_duration(), Duration::new(15_360));
+        assert_eq!(superframe.superframe_duration(), Duration::new(245_760));
+        assert_eq!(slot_duration(4), Duration::new(960));
+    }
+
+    #[test]
+    fn periods() {
+        let mut superframe = superframe(1, 0);
+        assert_eq!(superframe.period(Instant::new(0)), None);
+
+        // Slots of 960µs, CAP of 10 slots, beacon interval of 30720µs.
+        superframe.on_beacon(Instant::new(10_000));
+        assert_eq!(superframe.period(Instant::new(9_999)), None);
+        assert_eq!(
+            superframe.period(Instant::new(10_000)),
+            Some(SuperframePeriod::Cap)
+        );
+        assert_eq!(
+            superframe.period(Instant::new(19_599)),
+            Some(SuperframePeriod::Cap)
+        );
+        assert_eq!(
+            superframe.period(Instant::new(19_600)),
+            Some(SuperframePeriod::Cfp)
+        );
+        assert_eq!(
+            superframe.period(Instant::new(25_360)),
+            Some(SuperframePeriod::Inactive)
+        );
+
+        // Following superframes are extrapolated.
+        let at = Instant::new(10_000 + 2 * 30_720 + 1_000);
+        assert_eq!(
+            superframe.superframe_start(at),
+            Some(Instant::new(10_000 + 2 * 30_720))
+        );
+        assert_eq!(
+            superframe.next_beacon(at),
+            Some(Instant::new(10_000 + 3 * 30_720))
+        );
+        assert_eq!(superframe.period(at), Some(SuperframePeriod::Cap));
+
+        // Updating the final CAP slot shortens the CAP.
+        superframe.set_final_cap_slot(0).unwrap();
+        assert_eq!(superframe.period(at), Some(SuperframePeriod::Cfp));
+    }
+
+    #[test]
+    fn cap_queries() {
+        let mut superframe = superframe(0, 0);
+        superframe.on_beacon(Instant::new(0));
+
+        let cap_end = Instant::new(10 * 960);
+        assert_eq!(superframe.cap_end(Instant::new(0)), Some(cap_end));
+        assert_eq!(
+            superframe.cap_remaining(Instant::new(9_000)),
+            Some(Duration::new(600))
+        );
+        assert!(superframe.fits_in_cap(Instant::new(9_000), Duration::new(600)));
+        assert!(!superframe.fits_in_cap(Instant::new(9_000), Duration::new(601)));
+        assert!(!superframe.fits_in_cap(cap_end, Duration::new(0)));
+        assert_eq!(superframe.cap_remaining(cap_end), None);
+
+        // Backoff periods of 320µs are aligned with the beacon.
+        assert_eq!(
+            superframe.next_backoff_boundary(Instant::new(640)),
+            Some(Instant::new(640))
+        );
+        assert_eq!(
+            superframe.next_backoff_boundary(Instant::new(641)),
+            Some(Instant::new(960))
+        );
+        assert_eq!(
+            superframe.next_backoff_boundary(Instant::new(15_360 + 1)),
+            Some(Instant::new(15_360 + 320))
+        );
+    }
+
+    #[test]
+    fn battery_life_extension() {
+        let mut superframe = superframe(0, 0);
+        superframe.on_beacon(Instant::new(0));
+        assert_eq!(superframe.initial_backoff_exponent(3), 3);
+
+        // A beacon of 1000µs, the IFS of 640µs and 6 backoff periods of 320µs.
+        superframe.enable_battery_life_extension(6, Duration::<Microseconds>::new(1_640));
+        let ble_window_end = Instant::new(1_640 + 6 * 320);
+        assert_eq!(
+            superframe.ble_window_end(Instant::new(0)),
+            Some(ble_window_end)
+        );
+        assert_eq!(
+            superframe.ble_window_end(Instant::new(15_360)),
+            Some(ble_window_end + Duration::new(15_360))
+        );
+        assert!(superframe.fits_in_cap(Instant::new(3_000), Duration::new(1_000)));
+        assert!(!superframe.fits_in_cap(ble_window_end, Duration::new(0)));
+        assert_eq!(superframe.initial_backoff_exponent(3), 2);
+        assert_eq!(superframe.initial_backoff_exponent(1), 1);
+
+        let mut specification = SuperframeSpecification::new_unchecked([0; 2]);
+        superframe.write_specification(&mut specification);
+        assert_eq!(specification.into_inner(), [0x00, 0x19]);
+
+        superframe.disable_battery_life_extension();
+        assert!(superframe.fits_in_cap(ble_window_end, Duration::new(0)));
+    }
+
+    #[test]
+    fn lost_beacons() {
+        let mut superframe = superframe(3, 3);
+        assert!(!superframe.on_beacon_missed());
+
+        superframe.on_beacon(Instant::new(0));
+        for _ in 0..A_MAX_LOST_BEACONS - 1 {
+            assert!(!superframe.on_beacon_missed());
+        }
+        superframe.on_beacon(Instant::new(1_000));
+        for _ in 0..A_MAX_LOST_BEACONS - 1 {
+            assert!(!superframe.on_beacon_missed());
+        }
+        assert!(superframe.on_beacon_missed());
+        assert_eq!(superframe.last_beacon(), None);
+    }
+}
@@ -0,0 +1,273 @@
+//! Fixtures shared by the unit tests of the MAC sublayer.
+extern crate std;
+
+use core::pin::Pin;
+
+use rand_core::RngCore;
+use std::boxed::Box;
+
+pub(crate) use crate::driver::test_clock::poll_ready;
+use crate::{
+    driver::{
+        config::{CcaMode, Channel},
+        constants::PHY_MAX_PACKET_SIZE_127,
+        export::U,
+        frame::{RadioFrameRepr, RadioFrameUnsized},
+        phy::PhyParameters,
+        radio::{Radio, RadioCapabilities, RadioError, RxInfo, RxWindow, TxInfo},
+        test_clock::TestClock,
+        time::{Duration, Instant, Nanoseconds},
+        DriverConfig, DriverRequestChannel, FcsTwoBytes, RadioTimerApi,
+    },
+    mac::{
+        radio::MacRadio, MacBufferAllocator, MacIndicationChannel, MacRequestChannel, MacService,
+    },
+    util::{allocator::BufferAllocatorBackend, sync::mutex::Mutex},
+};
+
+/// A random number generator always returning the same value.
+pub(crate) struct FixedRng(pub u32);
+
+impl RngCore for FixedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// A random number generator returning consecutive values, starting after the
+/// given one.
+pub(crate) struct CountingRng(pub u32);
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 = self.0.wrapping_add(1);
+        self.0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Xorshift generator seeded with the given non-zero value, good enough to
+/// spread choices.
+pub(crate) struct XorshiftRng(pub u32);
+
+impl RngCore for XorshiftRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// A driver configuration running on the [`TestClock`].
+pub(crate) struct TestDriverConfig;
+
+impl DriverConfig for TestDriverConfig {
+    type Headroom = U<0>;
+    type Tailroom = U<0>;
+    type MaxSduLength = U<PHY_MAX_PACKET_SIZE_127>;
+    type Fcs = FcsTwoBytes;
+    type Timer = TestClock;
+}
+
+/// Return an allocator of two buffers, each large enough for a radio frame of
+/// the [`TestDriverConfig`]. Tests leak the allocator.
+pub(crate) fn mac_buffer_allocator() -> MacBufferAllocator {
+    const BUFFER_SIZE: usize =
+        RadioFrameRepr::<TestDriverConfig, RadioFrameUnsized>::new().max_buffer_length() as usize;
+    let backend: &'static mut BufferAllocatorBackend<BUFFER_SIZE, 2> = Box::leak(Box::default());
+    MacBufferAllocator::new(Box::leak(Box::new(backend.pin())))
+}
+
+/// Runs the given closure on a [`MacService`] whose channels are not served,
+/// e.g. to test MLME requests that run on a radio of their own.
+///
+/// * `rng` - The value returned by the random number generator of the
+///   service, e.g. the initial macDsn in the lowest byte
+pub(crate) fn with_mac_service<R>(
+    rng: u32,
+    f: impl FnOnce(&mut MacService<'_, FixedRng, TestDriverConfig>) -> R,
+) -> R {
+    // The allocator must be static, tests leak it.
+    let backend: &'static mut BufferAllocatorBackend<1, 1> = Box::leak(Box::default());
+    let backend: &'static Pin<&'static BufferAllocatorBackend<1, 1>> =
+        Box::leak(Box::new(backend.pin()));
+
+    let mut rng = Mutex::new(FixedRng(rng));
+    let request_channel = MacRequestChannel::new();
+    let indication_channel = MacIndicationChannel::new();
+    let driver_request_channel = DriverRequestChannel::new();
+    let mut mac_service = MacService::new(
+        &mut rng,
+        MacBufferAllocator::new(backend),
+        request_channel.receiver(),
+        indication_channel.sender(),
+        driver_request_channel.sender(),
+    );
+    f(&mut mac_service)
+}
+
+/// The capabilities of the radios of the unit tests: software ACKs and
+/// timestamps accurate to 1µs.
+pub(crate) const TEST_CAPABILITIES: RadioCapabilities = RadioCapabilities {
+    hardware_ack: false,
+    frame_pending: false,
+    timestamp_resolution: Duration::new(1_000),
+    min_tx_power: -20,
+    max_tx_power: 8,
+};
+
+/// Return the given instant of the [`TestClock`] as a timestamp of a radio.
+pub(crate) fn timestamp(instant: Instant<TestClock>) -> Instant<Nanoseconds> {
+    Instant::new(instant.tick() * 1_000)
+}
+
+/// Return the outcome of a transmission that started at the given instant
+/// and ends now.
+pub(crate) fn transmitted(start: Instant<TestClock>) -> Result<TxInfo<TestClock>, RadioError> {
+    Ok(TxInfo {
+        timestamp: timestamp(start),
+        end: TestClock::now(),
+    })
+}
+
+/// Copy the given MPDU into the given buffer and return it as received now.
+pub(crate) fn received(
+    buffer: &mut [u8],
+    mpdu: &[u8],
+) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+    buffer[..mpdu.len()].copy_from_slice(mpdu);
+    Ok(Some(RxInfo {
+        len: mpdu.len(),
+        rssi: -60,
+        lqi: 0xff,
+        timestamp: timestamp(TestClock::now()),
+        end: TestClock::now(),
+    }))
+}
+
+/// Advance the [`TestClock`] to the end of the given window and return that
+/// no frame was received.
+pub(crate) fn timed_out(
+    window: RxWindow<TestClock>,
+) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+    TestClock::advance(window.end.unwrap() - TestClock::now());
+    Ok(None)
+}
+
+/// Wraps the given radio into a [`MacRadio`] for the default O-QPSK PHY.
+pub(crate) fn mac_radio<R: Radio>(radio: R) -> MacRadio<R> {
+    MacRadio::new(radio, CcaMode::CarrierSense, &PhyParameters::default())
+}
+
+/// The time it takes a [`ScriptedAckRadio`] to transmit a frame.
+pub(crate) const SCRIPTED_TX_DURATION: i64 = 500;
+
+/// A [`Radio`] replaying the given receptions and recording the sequence
+/// numbers of its transmissions.
+///
+/// Each reception is either a frame received after the given delay or a
+/// timeout. Receptions time out once all receptions were replayed.
+/// Transmissions take [`SCRIPTED_TX_DURATION`].
+pub(crate) struct ScriptedAckRadio<'a> {
+    rx: core::slice::Iter<'a, Option<(i64, &'a [u8])>>,
+    pub transmitted: heapless::Vec<u8, 8>,
+}
+
+impl<'a> ScriptedAckRadio<'a> {
+    pub(crate) fn new(rx: &'a [Option<(i64, &'a [u8])>]) -> MacRadio<Self> {
+        mac_radio(Self {
+            rx: rx.iter(),
+            transmitted: heapless::Vec::new(),
+        })
+    }
+}
+
+impl Radio for ScriptedAckRadio<'_> {
+    type Timer = TestClock;
+
+    fn capabilities(&self) -> RadioCapabilities {
+        TEST_CAPABILITIES
+    }
+
+    fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+        Ok(())
+    }
+
+    fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+        Ok(dbm)
+    }
+
+    async fn transmit_at(
+        &mut self,
+        mpdu: &[u8],
+        _at: Option<Instant<TestClock>>,
+    ) -> Result<TxInfo<TestClock>, RadioError> {
+        self.transmitted.push(mpdu[2]).unwrap();
+        let start = TestClock::now();
+        TestClock::advance(Duration::new(SCRIPTED_TX_DURATION));
+        transmitted(start)
+    }
+
+    async fn receive(
+        &mut self,
+        buffer: &mut [u8],
+        window: RxWindow<TestClock>,
+    ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+        match self.rx.next() {
+            Some(Some((delay, mpdu))) => {
+                TestClock::advance(Duration::new(*delay));
+                assert!(TestClock::now() <= window.end.unwrap());
+                received(buffer, mpdu)
+            }
+            _ => timed_out(window),
+        }
+    }
+
+    async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+        Ok(true)
+    }
+
+    async fn energy_detect(&mut self, duration: Duration<TestClock>) -> Result<i8, RadioError> {
+        TestClock::advance(duration);
+        Ok(-100)
+    }
+}
@@ -0,0 +1,289 @@
+//! Thread compatibility (Thread 1.3).
+//!
+//! Thread runs on the IEEE 802.15.4 O-QPSK 2.4 GHz PHY without beacons or
+//! TSCH and fixes many of the options the standard leaves open. This module
+//! gathers the ones of the link layer, so that a device interoperates with
+//! OpenThread devices.
+//!
+//! # Security
+//!
+//! MAC frames are secured with security level 5 (ENC-MIC-32) and key
+//! identifier mode 1, see [`security()`]. The key index is derived from the
+//! Thread key sequence, see [`key_index()`], and selects one of the MAC keys
+//! derived from the network key, i.e. `HMAC-SHA256(network key, key sequence
+//! || "Thread")`. The derivation is left to the application. Keys of the
+//! previous and next key sequence are kept in the
+//! [`KeyTable`](super::security::KeyTable) next to the current one, so that
+//! frames secured during a key sequence increment are still accepted.
+//!
+//! The MLE Discovery Request and Response are secured with key identifier
+//! mode 2 and a well-known key instead, see [`mode_2_security()`] and
+//! [`mode_2_key()`]. The nonce is the regular one containing the frame
+//! counter of the sender, see [`nonce()`].
+//!
+//! # CSL
+//!
+//! Synchronized sleepy end devices are CSL receivers. The transmitter learns
+//! their phase from the CSL IE of their frames and Enh-Acks and never sends
+//! wake-up sequences, see [`csl_config()`].
+//!
+//! # Enh-Acks
+//!
+//! Enh-Acks carry the CSL IE if CSL is on but never the Time Correction IE.
+//! Devices that configured Enh-Ack based probing of link metrics additionally
+//! receive a Thread vendor specific IE with the link metrics of their frames,
+//! see [`enh_ack_policy()`] and [`emit_link_metrics_ie()`].
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        phy::PhyParameters,
+        time::{Duration, Microseconds},
+    },
+    mac::frame::repr::{KeyIdRepr, SecurityLevelRepr, SecurityRepr},
+};
+
+use super::{
+    ack::{EnhAckPolicy, NoVendorIes},
+    csl::CslConfig,
+    security::{KeyActivation, KeyDescriptor, KeyId, KEY_LEN},
+    tsch::CcmNonce,
+};
+
+/// The security level of Thread MAC frames (ENC-MIC-32).
+pub const SECURITY_LEVEL: SecurityLevelRepr = SecurityLevelRepr::EncMic32;
+
+/// The key source of frames secured with the key identifier mode 2 key.
+pub const MODE_2_KEY_SOURCE: [u8; 4] = [0xff; 4];
+
+/// The key index of frames secured with the key identifier mode 2 key.
+pub const MODE_2_KEY_INDEX: u8 = 0xff;
+
+/// The well-known key of frames secured with key identifier mode 2.
+pub const MODE_2_KEY: [u8; KEY_LEN] = [
+    0x78, 0x58, 0x16, 0x86, 0xfd, 0xb4, 0x58, 0x0f, 0xb0, 0x92, 0x54, 0x6a, 0xec, 0xbd, 0x15, 0x66,
+];
+
+/// The default CSL period of synchronized sleepy end devices, i.e. 500ms.
+pub const DEFAULT_CSL_PERIOD: u16 = 3125;
+
+/// The clock accuracy required from Thread devices in parts per million.
+pub const CSL_CLOCK_ACCURACY_PPM: u16 = 20;
+
+/// The default uncertainty of CSL transmissions regardless of clock drift.
+pub const CSL_UNCERTAINTY: Duration<Microseconds> = Duration::new(100);
+
+/// The OUI of the Thread vendor specific IEs.
+pub const THREAD_OUI: u32 = 0xeab89b;
+
+/// The sub-type of the vendor specific IE carrying Enh-Ack link metrics.
+const LINK_METRICS_SUB_TYPE: u8 = 0;
+
+/// The length of header IE headers.
+const IE_HEADER_LEN: usize = 2;
+
+/// The length of an OUI.
+const OUI_LEN: usize = 3;
+
+/// The element ID of the Vendor Specific header IE.
+const VENDOR_SPECIFIC_IE_ELEMENT_ID: u16 = 0x00;
+
+/// The max number of link metrics reported in an Enh-Ack.
+pub const MAX_LINK_METRICS: usize = 2;
+
+/// Return the security configuration of Thread MAC frames, see the module
+/// documentation.
+pub const fn security() -> SecurityRepr {
+    SecurityRepr::new(false, SECURITY_LEVEL, KeyIdRepr::SourceNone)
+}
+
+/// Return the security configuration of frames secured with the key
+/// identifier mode 2 key, i.e. MLE Discovery Requests and Responses.
+pub const fn mode_2_security() -> SecurityRepr {
+    SecurityRepr::new(false, SECURITY_LEVEL, KeyIdRepr::Source4Byte)
+}
+
+/// Return the key index of the MAC key of the given key sequence.
+///
+/// * `key_sequence` - Thread key sequence
+pub const fn key_index(key_sequence: u32) -> u8 {
+    (key_sequence & 0x7f) as u8 + 1
+}
+
+/// Return the key identifier of the MAC key of the given key sequence.
+///
+/// * `key_sequence` - Thread key sequence
+pub const fn key_id(key_sequence: u32) -> KeyId {
+    KeyId::index(key_index(key_sequence))
+}
+
+/// Return the descriptor of the MAC key of the given key sequence.
+///
+/// * `key_sequence` - Thread key sequence
+/// * `key` - MAC key derived from the network key and the key sequence
+/// * `activation` - The point from which on the key is used for outgoing
+///   frames
+pub const fn key_descriptor(
+    key_sequence: u32,
+    key: [u8; KEY_LEN],
+    activation: KeyActivation,
+) -> KeyDescriptor {
+    KeyDescriptor {
+        key_id: key_id(key_sequence),
+        key,
+        activation,
+    }
+}
+
+/// Return the descriptor of the well-known key identifier mode 2 key.
+pub const fn mode_2_key() -> KeyDescriptor {
+    KeyDescriptor {
+        key_id: KeyId::source_4_byte(MODE_2_KEY_SOURCE, MODE_2_KEY_INDEX),
+        key: MODE_2_KEY,
+        activation: KeyActivation::Immediate,
+    }
+}
+
+/// Return the CCM* nonce of a Thread MAC frame.
+///
+/// * `src_address` - Extended address of the sender (little endian)
+/// * `frame_counter` - Frame counter of the frame
+pub fn nonce(src_address: &[u8; 8], frame_counter: u32) -> CcmNonce {
+    CcmNonce::from_frame_counter(src_address, frame_counter, SECURITY_LEVEL.value())
+}
+
+/// Return the CSL configuration of a Thread device, see the module
+/// documentation.
+///
+/// * `period` - macCslPeriod in units of 10 symbols, 0 if the device is no
+///   CSL receiver
+pub fn csl_config(period: u16) -> CslConfig {
+    CslConfig {
+        phy: PhyParameters::OQPSK_2450MHZ,
+        period,
+        // Thread transmitters are synchronized with their CSL receivers, the
+        // wake-up sequences are no longer than the period of the receiver.
+        max_period: period,
+        clock_accuracy_ppm: CSL_CLOCK_ACCURACY_PPM,
+        min_guard_time: CSL_UNCERTAINTY,
+        ..Default::default()
+    }
+}
+
+/// Return the Enh-Ack policy of a Thread device without link metrics, see the
+/// module documentation.
+///
+/// * `csl` - Whether the device is a CSL receiver
+pub fn enh_ack_policy(csl: bool) -> EnhAckPolicy<NoVendorIes> {
+    EnhAckPolicy::new(false, csl)
+}
+
+/// Return the Enh-Ack policy of a Thread device reporting link metrics to the
+/// devices that configured Enh-Ack based probing.
+///
+/// * `csl` - Whether the device is a CSL receiver
+/// * `link_metrics` - Writes the link metrics of the last frame of the given
+///   requester to the given buffer and returns their number, 0 if the
+///   requester didn't configure probing
+pub fn enh_ack_policy_with_probing<LinkMetrics>(
+    csl: bool,
+    mut link_metrics: LinkMetrics,
+) -> EnhAckPolicy<impl FnMut(&[u8], &mut [u8]) -> usize>
+where
+    LinkMetrics: FnMut(&[u8], &mut [u8; MAX_LINK_METRICS]) -> usize,
+{
+    EnhAckPolicy::with_vendor_ies(false, csl, move |requester: &[u8], buffer: &mut [u8]| {
+        let mut values = [0; MAX_LINK_METRICS];
+        match link_metrics(requester, &mut values) {
+            0 => 0,
+            len => emit_link_metrics_ie(&values[..len.min(MAX_LINK_METRICS)], buffer),
+        }
+    })
+}
+
+/// Write the Thread vendor specific IE carrying the given link metrics to the
+/// given buffer and return its length, 0 if it doesn't fit.
+///
+/// * `values` - The link metrics in the order configured by the requester,
+///   e.g. the link margin and the RSSI
+/// * `buffer` - The space left for header IEs
+pub fn emit_link_metrics_ie(values: &[u8], buffer: &mut [u8]) -> usize {
+    let content_len = OUI_LEN + 1 + values.len();
+    let Some(ie) = buffer.get_mut(..IE_HEADER_LEN + content_len) else {
+        return 0;
+    };
+    let header = (VENDOR_SPECIFIC_IE_ELEMENT_ID << 7) | content_len as u16;
+    ie[..IE_HEADER_LEN].copy_from_slice(&header.to_le_bytes());
+    ie[IE_HEADER_LEN..IE_HEADER_LEN + OUI_LEN]
+        .copy_from_slice(&THREAD_OUI.to_le_bytes()[..OUI_LEN]);
+    ie[IE_HEADER_LEN + OUI_LEN] = LINK_METRICS_SUB_TYPE;
+    ie[IE_HEADER_LEN + OUI_LEN + 1..].copy_from_slice(values);
+    IE_HEADER_LEN + content_len
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::ack::AckIePolicy;
+
+    use super::*;
+
+    #[test]
+    fn security_defaults() {
+        assert_eq!(security().aux_sec_header_length(), 6);
+        assert_eq!(security().mic_length(), 4);
+        assert_eq!(mode_2_security().aux_sec_header_length(), 10);
+
+        assert_eq!(key_index(0), 1);
+        assert_eq!(key_index(0x7f), 0x80);
+        assert_eq!(key_index(0x80), 1);
+        assert_eq!(key_id(5), KeyId::index(6));
+
+        let key = mode_2_key();
+        assert_eq!(key.key_id.mode(), KeyIdRepr::Source4Byte);
+        assert_eq!(key.key_id.source(), &[0xff; 4]);
+        assert_eq!(key.key_id.key_index(), 0xff);
+
+        let src_address = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(
+            nonce(&src_address, 0x01020304).as_bytes(),
+            &[8, 7, 6, 5, 4, 3, 2, 1, 0x01, 0x02, 0x03, 0x04, 5]
+        );
+    }
+
+    #[test]
+    fn csl_preset() {
+        let config = csl_config(DEFAULT_CSL_PERIOD);
+        assert_eq!(config.period, DEFAULT_CSL_PERIOD);
+        assert_eq!(config.max_period, DEFAULT_CSL_PERIOD);
+        // 500ms.
+        assert_eq!(config.units(config.period).ticks(), 500_000);
+    }
+
+    #[test]
+    fn link_metrics() {
+        let mut buffer = [0; 16];
+        assert_eq!(emit_link_metrics_ie(&[0x2a, 0xc4], &mut buffer), 8);
+        assert_eq!(
+            &buffer[..8],
+            &[0x06, 0x00, 0x9b, 0xb8, 0xea, 0x00, 0x2a, 0xc4]
+        );
+        assert_eq!(emit_link_metrics_ie(&[0x2a, 0xc4], &mut buffer[..7]), 0);
+
+        let probing = [0xaa; 8];
+        let mut policy = enh_ack_policy_with_probing(true, |requester, values| {
+            if requester == probing {
+                values[0] = 0x2a;
+                1
+            } else {
+                0
+            }
+        });
+        assert!(!policy.time_correction(&probing));
+        assert!(policy.csl(&probing));
+        assert_eq!(policy.emit_ies(&probing, &mut buffer), 7);
+        assert_eq!(policy.emit_ies(&[0xbb; 8], &mut buffer), 0);
+
+        let policy = enh_ack_policy(false);
+        assert!(!policy.csl(&probing));
+    }
+}
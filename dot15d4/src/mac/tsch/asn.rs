@@ -3,7 +3,7 @@
 /// elapsed since the start of the network or an arbitrary start time
 /// determined by the PAN coordinator. It is stored as a 5-byte unsigned
 /// integer.
-#[derive(Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct AbsoluteSlotNumber {
     /// least significant 4 bytes of the absolute slot number
     ls4b: u32,
@@ -36,6 +36,8 @@ impl PartialEq for AbsoluteSlotNumber {
     }
 }
 
+impl Eq for AbsoluteSlotNumber {}
+
 impl PartialEq<i64> for AbsoluteSlotNumber {
     fn eq(&self, other: &i64) -> bool {
         match Self::try_from(*other) {
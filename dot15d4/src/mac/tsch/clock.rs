@@ -0,0 +1,378 @@
+//! Virtual TSCH clock.
+//!
+//! The TSCH clock is a nanosecond-resolution clock that runs at the rate of
+//! the time source. It is derived from the radio timer through an anchor
+//! point, i.e. a pair of corresponding instants in both domains, and the rate
+//! correction estimated by a [`super::syntonization::Syntonization`]
+//! algorithm:
+//!
+//! ```notrust
+//! tsch = tsch_anchor + (radio - radio_anchor) * (1 + rate_correction)
+//! ```
+//!
+//! For a given anchor point and rate correction, conversions are monotonic in
+//! both directions. Time corrections move the anchor point.
+//!
+//! Drivers that characterized the skew of their radio timer feed it into the
+//! clock as a [`TickCorrection`] instead of correcting timestamps themselves.
+//! The correction is added to the elapsed radio time before the rate
+//! correction applies. As the skew of a crystal depends on its temperature, a
+//! recalibration callback may compute the correction from temperature
+//! measurements, see [`VirtualClock::set_recalibration()`].
+#![allow(dead_code)]
+
+use crate::driver::{
+    time::{Duration, Instant, Nanoseconds},
+    RadioTimerApi,
+};
+
+use super::syntonization::Ppm;
+
+/// Denominator of a [`Ppm`] rate correction, i.e. one million in Q16.16.
+const PPM_DENOMINATOR: i64 = 1_000_000 << Ppm::FRAC_BITS;
+
+/// Clock bridging the radio timer and the syntonized TSCH clock, see the
+/// module documentation.
+pub struct VirtualClock<Timer: RadioTimerApi> {
+    /// Radio timer instant of the anchor point.
+    radio_anchor: Instant<Timer>,
+    /// TSCH clock instant of the anchor point.
+    tsch_anchor: Instant<Nanoseconds>,
+    /// Rate correction of the TSCH clock relative to the radio timer.
+    rate_correction: Ppm,
+    /// Correction of the radio timer ticks.
+    tick_correction: TickCorrection,
+    recalibration: Option<Recalibration>,
+}
+
+/// Correction of the duration of radio timer ticks, in picoseconds per tick.
+///
+/// Low-power RTCs often count both edges of their crystal, so that ticks
+/// starting at even and odd counter values differ in length. A uniform skew
+/// applies the same correction to both.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TickCorrection {
+    /// Picoseconds added to each tick that starts at an even counter value.
+    pub even: i32,
+    /// Picoseconds added to each tick that starts at an odd counter value.
+    pub odd: i32,
+}
+
+impl TickCorrection {
+    /// No correction.
+    pub const ZERO: Self = Self::new(0, 0);
+
+    /// Creates a [`TickCorrection`] from the corrections of even and odd
+    /// ticks.
+    pub const fn new(even: i32, odd: i32) -> Self {
+        Self { even, odd }
+    }
+
+    /// Creates a [`TickCorrection`] that applies to all ticks alike.
+    pub const fn uniform(correction: i32) -> Self {
+        Self::new(correction, correction)
+    }
+
+    /// Return the correction accumulated by the ticks in `[from, to)`, in
+    /// picoseconds.
+    const fn between(&self, from: u64, to: u64) -> i128 {
+        let evens = to.div_ceil(2) - from.div_ceil(2);
+        let odds = to - from - evens;
+        evens as i128 * self.even as i128 + odds as i128 * self.odd as i128
+    }
+}
+
+/// Temperature-triggered recalibration, see
+/// [`VirtualClock::set_recalibration()`].
+#[derive(Debug, Clone, Copy)]
+struct Recalibration {
+    /// Temperature change that triggers a recalibration, in millidegrees
+    /// Celsius.
+    threshold: u32,
+    callback: fn(i32) -> TickCorrection,
+    /// Temperature of the last recalibration, in millidegrees Celsius.
+    temperature: Option<i32>,
+}
+
+impl<Timer: RadioTimerApi> VirtualClock<Timer> {
+    /// Creates a new [`VirtualClock`] without rate correction.
+    ///
+    /// * `radio_anchor` - Radio timer instant of the anchor point
+    /// * `tsch_anchor` - Corresponding TSCH clock instant
+    pub fn new(radio_anchor: Instant<Timer>, tsch_anchor: Instant<Nanoseconds>) -> Self {
+        Self {
+            radio_anchor,
+            tsch_anchor,
+            rate_correction: Ppm::ZERO,
+            tick_correction: TickCorrection::ZERO,
+            recalibration: None,
+        }
+    }
+
+    /// Return the current instant of the TSCH clock.
+    pub fn now(&self) -> Instant<Nanoseconds> {
+        self.to_tsch(Timer::now())
+    }
+
+    /// Convert a radio timer instant to the TSCH clock.
+    pub fn to_tsch(&self, instant: Instant<Timer>) -> Instant<Nanoseconds> {
+        let elapsed = self.radio_elapsed(instant);
+        let elapsed =
+            elapsed + mul_div(elapsed, self.rate_correction.raw() as i64, PPM_DENOMINATOR);
+        self.tsch_anchor + Duration::new(elapsed)
+    }
+
+    /// Return the corrected radio time elapsed since the anchor point, in
+    /// nanoseconds.
+    fn radio_elapsed(&self, instant: Instant<Timer>) -> i64 {
+        let (anchor, tick) = (self.radio_anchor.tick(), instant.tick());
+        let correction = if tick >= anchor {
+            self.tick_correction.between(anchor, tick)
+        } else {
+            -self.tick_correction.between(tick, anchor)
+        };
+        let elapsed = (instant - Instant::new(anchor))
+            .convert_into_rounding_down::<Nanoseconds>()
+            .ticks();
+        elapsed + correction.div_euclid(1_000) as i64
+    }
+
+    /// Convert a TSCH clock instant to the radio timer, e.g. to schedule an
+    /// alarm at that instant.
+    ///
+    /// The result is rounded down to a radio timer tick.
+    pub fn at(&self, instant: Instant<Nanoseconds>) -> Instant<Timer> {
+        let elapsed = (instant - self.tsch_anchor).ticks();
+        let elapsed = mul_div(
+            elapsed,
+            PPM_DENOMINATOR,
+            PPM_DENOMINATOR + self.rate_correction.raw() as i64,
+        );
+        if self.tick_correction != TickCorrection::ZERO {
+            return self.corrected_at(elapsed);
+        }
+        let elapsed = if elapsed < 0 {
+            // Round toward negative infinity.
+            Duration::<Nanoseconds>::new(elapsed).convert_into_rounding_up::<Timer>()
+        } else {
+            Duration::<Nanoseconds>::new(elapsed).convert_into_rounding_down::<Timer>()
+        };
+        Instant::new(self.radio_anchor.tick()) + elapsed
+    }
+
+    /// Return the last radio timer tick at which the corrected radio time
+    /// elapsed since the anchor point does not exceed `elapsed` nanoseconds.
+    fn corrected_at(&self, elapsed: i64) -> Instant<Timer> {
+        // Estimate from the average corrected tick duration, then step to the
+        // exact tick.
+        let tick_ps = 1_000_000_000_000 / Timer::FREQUENCY as i128;
+        let correction_ps =
+            (self.tick_correction.even as i128 + self.tick_correction.odd as i128) / 2;
+        let ticks = elapsed as i128 * 1_000 / (tick_ps + correction_ps);
+        let mut instant = Instant::new(self.radio_anchor.tick()) + Duration::new(ticks as i64);
+        while self.radio_elapsed(instant) > elapsed {
+            instant = instant - Duration::new(1);
+        }
+        while self.radio_elapsed(instant + Duration::new(1)) <= elapsed {
+            instant = instant + Duration::new(1);
+        }
+        instant
+    }
+
+    /// Return the anchor point as radio timer and TSCH clock instants.
+    pub fn anchor(&self) -> (Instant<Timer>, Instant<Nanoseconds>) {
+        (Instant::new(self.radio_anchor.tick()), self.tsch_anchor)
+    }
+
+    /// Move the anchor point, e.g. after a time correction.
+    ///
+    /// * `radio_anchor` - Radio timer instant of the anchor point
+    /// * `tsch_anchor` - Corresponding TSCH clock instant
+    pub fn set_anchor(&mut self, radio_anchor: Instant<Timer>, tsch_anchor: Instant<Nanoseconds>) {
+        self.radio_anchor = radio_anchor;
+        self.tsch_anchor = tsch_anchor;
+    }
+
+    /// Return the rate correction of the TSCH clock.
+    pub fn rate_correction(&self) -> Ppm {
+        self.rate_correction
+    }
+
+    /// Change the rate correction of the TSCH clock from the given instant on.
+    ///
+    /// The anchor point moves to that instant so that the TSCH clock doesn't
+    /// jump.
+    ///
+    /// * `at` - Radio timer instant from which the new rate applies
+    /// * `rate_correction` - The new rate correction
+    pub fn set_rate_correction(&mut self, at: Instant<Timer>, rate_correction: Ppm) {
+        let tsch_anchor = self.to_tsch(Instant::new(at.tick()));
+        self.set_anchor(at, tsch_anchor);
+        self.rate_correction = rate_correction;
+    }
+
+    /// Return the correction of the radio timer ticks.
+    pub fn tick_correction(&self) -> TickCorrection {
+        self.tick_correction
+    }
+
+    /// Change the correction of the radio timer ticks from the given instant
+    /// on.
+    ///
+    /// The anchor point moves to that instant so that the TSCH clock doesn't
+    /// jump. Corrected ticks must remain longer than zero.
+    ///
+    /// * `at` - Radio timer instant from which the new correction applies
+    /// * `tick_correction` - The new tick correction
+    pub fn set_tick_correction(&mut self, at: Instant<Timer>, tick_correction: TickCorrection) {
+        let tick_ps = 1_000_000_000_000 / Timer::FREQUENCY as i64;
+        debug_assert!(tick_ps + (tick_correction.even.min(tick_correction.odd) as i64) > 0);
+        let tsch_anchor = self.to_tsch(Instant::new(at.tick()));
+        self.set_anchor(at, tsch_anchor);
+        self.tick_correction = tick_correction;
+    }
+
+    /// Register a callback that returns the tick correction at a given
+    /// temperature in millidegrees Celsius, e.g. from the characterized
+    /// temperature curve of the crystal.
+    ///
+    /// The callback runs on the next call to
+    /// [`VirtualClock::report_temperature()`] and whenever the temperature
+    /// changed by at least `threshold` millidegrees Celsius since the last
+    /// recalibration.
+    pub fn set_recalibration(&mut self, threshold: u32, callback: fn(i32) -> TickCorrection) {
+        self.recalibration = Some(Recalibration {
+            threshold,
+            callback,
+            temperature: None,
+        });
+    }
+
+    /// Report a temperature measured at the given instant, in millidegrees
+    /// Celsius.
+    ///
+    /// Applies the correction returned by the recalibration callback from
+    /// that instant on if the temperature changed enough, see
+    /// [`VirtualClock::set_recalibration()`]. Returns whether the clock was
+    /// recalibrated.
+    pub fn report_temperature(&mut self, at: Instant<Timer>, temperature: i32) -> bool {
+        let Some(recalibration) = self.recalibration.as_mut() else {
+            return false;
+        };
+        if recalibration
+            .temperature
+            .is_some_and(|last| last.abs_diff(temperature) < recalibration.threshold)
+        {
+            return false;
+        }
+        recalibration.temperature = Some(temperature);
+        let tick_correction = (recalibration.callback)(temperature);
+        self.set_tick_correction(at, tick_correction);
+        true
+    }
+}
+
+/// Compute `value * numerator / denominator` with a 128-bit intermediate
+/// product, rounding toward zero.
+fn mul_div(value: i64, numerator: i64, denominator: i64) -> i64 {
+    (value as i128 * numerator as i128 / denominator as i128) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::driver::test_clock::TestClock;
+
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        TestClock::reset();
+        TestClock::advance(Duration::new(1_000));
+        let mut clock =
+            VirtualClock::<TestClock>::new(Instant::new(1_000), Instant::new(5_000_000_000));
+        assert_eq!(clock.now().tick(), 5_000_000_000);
+
+        TestClock::advance(Duration::new(250));
+        assert_eq!(clock.now().tick(), 5_000_250_000);
+        assert_eq!(clock.to_tsch(Instant::new(0)).tick(), 4_999_000_000);
+        assert_eq!(clock.at(Instant::new(5_000_250_999)).tick(), 1_250);
+        assert_eq!(clock.at(Instant::new(4_999_999_999)).tick(), 999);
+
+        // The time source runs 50ppm faster.
+        clock.set_rate_correction(Instant::new(1_000_000), Ppm::from_ppm(50));
+        assert_eq!(clock.anchor().1.tick(), 5_999_000_000);
+        assert_eq!(clock.to_tsch(Instant::new(2_000_000)).tick(), 6_999_050_000);
+        assert_eq!(clock.at(Instant::new(6_999_050_000)).tick(), 2_000_000);
+
+        // Conversions are monotonic.
+        let mut last = clock.at(Instant::new(6_999_000_000));
+        for tick in (6_999_000_000..7_001_000_000).step_by(999) {
+            let instant = clock.at(Instant::new(tick));
+            assert!(instant >= last);
+            last = instant;
+        }
+    }
+
+    #[test]
+    fn tick_corrections() {
+        TestClock::reset();
+        let mut clock = VirtualClock::<TestClock>::new(Instant::new(1), Instant::new(1_000_000));
+        assert_eq!(TickCorrection::new(3, 5).between(1, 4), 3 + 2 * 5);
+        assert_eq!(TickCorrection::new(3, 5).between(2, 4), 3 + 5);
+
+        // Even ticks last 1000.5ns, odd ticks 999.5ns.
+        clock.set_tick_correction(Instant::new(1), TickCorrection::new(500, -500));
+        assert_eq!(clock.to_tsch(Instant::new(2)).tick(), 1_000_999);
+        assert_eq!(clock.to_tsch(Instant::new(3)).tick(), 1_002_000);
+        assert_eq!(clock.to_tsch(Instant::new(0)).tick(), 998_999);
+
+        // A uniform skew of 20ppm.
+        clock.set_tick_correction(Instant::new(1), TickCorrection::uniform(20));
+        assert_eq!(clock.to_tsch(Instant::new(1_000_001)).tick(), 1_001_020_000);
+        assert_eq!(clock.at(Instant::new(1_001_020_000)).tick(), 1_000_001);
+        assert_eq!(clock.at(Instant::new(1_001_019_999)).tick(), 1_000_000);
+
+        // Conversions remain monotonic and round trip.
+        clock.set_rate_correction(Instant::new(1_000_001), Ppm::from_ppm(-30));
+        let mut last = clock.at(Instant::new(1_001_000_000));
+        for tick in (1_001_000_000..1_003_000_000).step_by(997) {
+            let instant = clock.at(Instant::new(tick));
+            assert!(instant >= last);
+            assert!(clock.to_tsch(instant).tick() <= tick);
+            assert_eq!(clock.at(clock.to_tsch(instant)), instant);
+            last = instant;
+        }
+    }
+
+    #[test]
+    fn recalibration() {
+        TestClock::reset();
+        let mut clock = VirtualClock::<TestClock>::new(Instant::new(0), Instant::new(0));
+        assert!(!clock.report_temperature(Instant::new(0), 25_000));
+
+        // A parabolic temperature curve around 25°C.
+        clock.set_recalibration(2_000, |temperature| {
+            let delta = (temperature - 25_000) / 1_000;
+            TickCorrection::uniform(-delta * delta)
+        });
+        assert!(clock.report_temperature(Instant::new(0), 25_000));
+        assert_eq!(clock.tick_correction(), TickCorrection::ZERO);
+        assert!(!clock.report_temperature(Instant::new(1_000), 26_999));
+
+        assert!(clock.report_temperature(Instant::new(1_000_000), 35_000));
+        assert_eq!(clock.tick_correction(), TickCorrection::uniform(-100));
+        assert_eq!(clock.to_tsch(Instant::new(2_000_000)).tick(), 1_999_900_000);
+        assert!(!clock.report_temperature(Instant::new(2_000_000), 33_001));
+        assert!(clock.report_temperature(Instant::new(2_000_000), 33_000));
+        assert_eq!(clock.tick_correction(), TickCorrection::uniform(-64));
+    }
+
+    #[test]
+    fn mul_div_large() {
+        assert_eq!(mul_div(1 << 50, 3, 1 << 40), 3 << 10);
+        assert_eq!(
+            mul_div(-10_000_000_000_000, 1_000_050, 1_000_000),
+            -10_000_500_000_000
+        );
+    }
+}
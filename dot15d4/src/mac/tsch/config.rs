@@ -0,0 +1,175 @@
+//! TSCH configuration presets.
+//!
+//! [`TschConfig::minimal_6tisch()`] follows the Minimal 6TiSCH Configuration
+//! (RFC 8180): a single slotframe of [`MINIMAL_SLOTFRAME_SIZE`] timeslots
+//! with one shared cell in timeslot 0 and channel offset 0, used for EBs,
+//! broadcast and unicast traffic alike, and the default timeslot template.
+#![allow(dead_code)]
+
+use crate::{
+    driver::time::{Duration, Microseconds},
+    mac::{
+        frame::fields::{TschLinkOption, TschTimeslotTimings},
+        neighbors::MacNeighbor,
+    },
+};
+
+#[cfg(feature = "ies")]
+use super::{eb::EbGenerator, join::JoinConfig};
+use super::{
+    hopping::HoppingSequence,
+    schedule::{ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe},
+    sync::{TschSyncMonitor, DEFAULT_DESYNC_TIMEOUT, DEFAULT_KEEP_ALIVE_PERIOD},
+};
+
+/// The number of timeslots of the minimal slotframe (RFC 8180).
+pub const MINIMAL_SLOTFRAME_SIZE: u16 = 101;
+
+/// EB period of the minimal configuration in timeslots, i.e. 10s with the
+/// default timeslot length.
+pub const MINIMAL_EB_PERIOD: u32 = 1000;
+
+/// Configuration of a TSCH network.
+///
+/// Periods are expressed in timeslots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TschConfig {
+    /// The hopping sequence of the network.
+    pub hopping_sequence: HoppingSequence,
+    /// The number of timeslots of the slotframe.
+    pub slotframe_size: u16,
+    /// The ID of the timeslot template (0 for the default timings).
+    pub timeslot_template_id: u8,
+    /// The guard time used with the timeslot template.
+    pub guard_time: Duration<Microseconds>,
+    /// Number of timeslots between two EBs.
+    pub eb_period: u32,
+    /// Number of timeslots without traffic with the time source before a
+    /// keep-alive is sent.
+    pub keep_alive_period: u32,
+    /// Number of timeslots without synchronization after which the device
+    /// leaves the network.
+    pub desync_timeout: u32,
+}
+
+impl TschConfig {
+    /// Return the Minimal 6TiSCH Configuration (RFC 8180), see the module
+    /// documentation.
+    pub const fn minimal_6tisch() -> Self {
+        Self {
+            hopping_sequence: HoppingSequence::DEFAULT_16_16,
+            slotframe_size: MINIMAL_SLOTFRAME_SIZE,
+            timeslot_template_id: 0,
+            guard_time: TschTimeslotTimings::DEFAULT_GUARD_TIME,
+            eb_period: MINIMAL_EB_PERIOD,
+            keep_alive_period: DEFAULT_KEEP_ALIVE_PERIOD,
+            desync_timeout: DEFAULT_DESYNC_TIMEOUT,
+        }
+    }
+
+    /// Return the timings of the timeslot template.
+    pub fn timeslot_timings(&self) -> TschTimeslotTimings {
+        TschTimeslotTimings::new(self.timeslot_template_id, self.guard_time)
+    }
+
+    /// Replace the schedule with the single shared cell schedule and the
+    /// timeslot template of the configuration.
+    ///
+    /// * `schedule` - The schedule
+    pub fn install<const S: usize, const L: usize, T: MacNeighbor>(
+        &self,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Result<(), ScheduleError> {
+        schedule.clear();
+        schedule.set_timeslot_timings(self.timeslot_timings());
+        schedule.add_slotframe(minimal_slotframe(
+            self.slotframe_size,
+            self.hopping_sequence,
+        )?)
+    }
+
+    /// Return a monitor of the synchronization using the keep-alive period and
+    /// desync timeout of the configuration.
+    pub fn sync_monitor(&self) -> TschSyncMonitor {
+        TschSyncMonitor::new(self.keep_alive_period, self.desync_timeout)
+    }
+
+    /// Return an EB generator using the EB period of the configuration.
+    ///
+    /// * `pan_id` - PAN ID of the network
+    /// * `src_address` - Extended address of the device (little endian)
+    #[cfg(feature = "ies")]
+    pub fn eb_generator(&self, pan_id: u16, src_address: [u8; 8]) -> EbGenerator {
+        let mut generator = EbGenerator::new(pan_id, src_address);
+        generator.set_period(self.eb_period);
+        generator
+    }
+
+    /// Return the configuration of the join process scanning the hopping
+    /// sequence of the configuration.
+    #[cfg(feature = "ies")]
+    pub fn join_config(&self) -> JoinConfig {
+        JoinConfig {
+            hopping_sequence: self.hopping_sequence,
+            ..Default::default()
+        }
+    }
+}
+
+/// Return a slotframe with handle 0 and a single shared cell in timeslot 0
+/// and channel offset 0, used for advertising and synchronization.
+///
+/// * `size` - Number of timeslots of the slotframe
+/// * `hopping_sequence` - Hopping sequence of the network
+pub fn minimal_slotframe<const L: usize, T: MacNeighbor>(
+    size: u16,
+    hopping_sequence: HoppingSequence,
+) -> Result<TschSlotframe<L, T>, ScheduleError> {
+    let mut slotframe = TschSlotframe::new(0, size, hopping_sequence);
+    slotframe.add_link(TschLink::new(
+        0,
+        0,
+        0,
+        TschLinkOption::Tx
+            | TschLinkOption::Rx
+            | TschLinkOption::Shared
+            | TschLinkOption::TimeKeeping,
+        TschLinkType::Advertising,
+        None,
+    ))?;
+    Ok(slotframe)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::neighbors::tests::TestNeighbor;
+
+    use super::*;
+
+    #[test]
+    fn minimal_6tisch() {
+        let config = TschConfig::minimal_6tisch();
+        let mut schedule = TschSchedule::<2, 4, TestNeighbor>::new();
+        schedule
+            .add_slotframe(TschSlotframe::new(3, 7, HoppingSequence::DEFAULT_4_4))
+            .unwrap();
+
+        config.install(&mut schedule).unwrap();
+        assert_eq!(schedule.slotframes().len(), 1);
+        let slotframe = schedule.slotframe(0).unwrap();
+        assert_eq!(slotframe.size(), 101);
+        assert_eq!(slotframe.links().len(), 1);
+        let link = &slotframe.links()[0];
+        assert_eq!((link.timeslot(), link.channel_offset()), (0, 0));
+        assert!(link
+            .link_options()
+            .contains(TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared));
+        assert_eq!(
+            schedule.timeslot_timings().timeslot_length(),
+            TschTimeslotTimings::default().timeslot_length()
+        );
+
+        assert!(config.keep_alive_period < config.desync_timeout);
+        assert!(!config.sync_monitor().is_synchronized());
+    }
+}
@@ -0,0 +1,363 @@
+//! Enhanced Beacon (EB) generation for TSCH coordinators.
+//!
+//! EBs advertise the presence of a TSCH network. They carry all information
+//! required by a joining device to synchronize to the network (IEEE
+//! 802.15.4-2020, section 6.3.6):
+//!
+//! ```notrust
+//! +--------------------+-----------------------------------------------------+
+//! | Header Termination | MLME payload IE                                     |
+//! | IE 1               | +-----------+----------+----------+---------------+ |
+//! |                    | | TSCH Sync | Timeslot | Channel  | Slotframe and | |
+//! |                    | |           |          | Hopping  | Link          | |
+//! |                    | +-----------+----------+----------+---------------+ |
+//! +--------------------+-----------------------------------------------------+
+//! ```
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, ExtendedAddress, FrameType, PanId},
+    },
+    mac::{
+        frame::{
+            fields::{
+                Asn, LinkDescriptorRepr, SlotframeDescriptorRepr, TschSlotframeAndLinkRepr,
+                TschSynchronizationRepr, TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID,
+                TSCH_SYNCHRONIZATION_IE_SUB_ID, TSCH_TIMESLOT_IE_SUB_ID,
+            },
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError, FrameErrorKind,
+        },
+        neighbors::MacNeighbor,
+    },
+    util,
+};
+
+use super::{asn::AbsoluteSlotNumber, schedule::TschSchedule};
+
+/// Header Termination IE 1 (element ID 0x7e, no content).
+const HEADER_TERMINATION_IE_1: [u8; 2] = [0x00, 0x3f];
+
+/// The group ID of the MLME payload IE.
+const MLME_PAYLOAD_IE_GROUP_ID: u16 = 0x1;
+
+/// The nested IE sub-ID of the Channel Hopping IE (long format).
+const CHANNEL_HOPPING_IE_SUB_ID: u16 = 0x9;
+
+/// The length of header, payload and nested IE headers.
+const IE_HEADER_LEN: usize = 2;
+
+/// Default EB period in timeslots, i.e. 16s with the default timeslot length.
+pub const DEFAULT_EB_PERIOD: u32 = 1600;
+
+/// An MPDU containing an Enhanced Beacon.
+pub type EnhancedBeacon = FrameBuffer<PHY_MAX_PACKET_SIZE_127>;
+
+/// Assembles Enhanced Beacons from the live TSCH schedule.
+///
+/// The generated EBs are meant to be queued for transmission in advertising
+/// slots. As the TSCH Synchronization IE carries the ASN of the timeslot in
+/// which the EB is sent, EBs should be generated right before they are
+/// transmitted.
+pub struct EbGenerator {
+    /// The PAN ID of the network.
+    pan_id: u16,
+    /// The extended address of the coordinator (little endian).
+    src_address: [u8; 8],
+    /// Number of timeslots between two EBs.
+    period: u32,
+    /// The join metric advertised in the TSCH Synchronization IE.
+    join_metric: u8,
+    /// The ID of the hopping sequence advertised in the Channel Hopping IE.
+    hopping_sequence_id: u8,
+    /// ASN of the last generated EB.
+    last_eb: Option<i64>,
+}
+
+impl EbGenerator {
+    /// Creates a new [`EbGenerator`].
+    ///
+    /// * `pan_id` - PAN ID of the network
+    /// * `src_address` - Extended address of the coordinator (little endian)
+    pub fn new(pan_id: u16, src_address: [u8; 8]) -> Self {
+        Self {
+            pan_id,
+            src_address,
+            period: DEFAULT_EB_PERIOD,
+            join_metric: 0,
+            hopping_sequence_id: 0,
+            last_eb: None,
+        }
+    }
+
+    /// Set the number of timeslots between two EBs.
+    ///
+    /// * `period` - EB period in timeslots
+    pub fn set_period(&mut self, period: u32) {
+        self.period = period;
+    }
+
+    /// Set the join metric, i.e. the number of hops to the PAN coordinator.
+    ///
+    /// * `join_metric` - Join metric to advertise
+    pub fn set_join_metric(&mut self, join_metric: u8) {
+        self.join_metric = join_metric;
+    }
+
+    /// Set the ID of the advertised hopping sequence (0 for the default
+    /// sequence).
+    ///
+    /// * `hopping_sequence_id` - Hopping sequence ID to advertise
+    pub fn set_hopping_sequence_id(&mut self, hopping_sequence_id: u8) {
+        self.hopping_sequence_id = hopping_sequence_id;
+    }
+
+    /// Return whether an EB is due at the given ASN.
+    ///
+    /// * `asn` - Absolute slot number of the advertising slot
+    pub fn is_due(&self, asn: AbsoluteSlotNumber) -> bool {
+        let asn: i64 = asn.try_into().unwrap();
+        self.last_eb
+            .is_none_or(|last_eb| asn - last_eb >= self.period as i64)
+    }
+
+    /// Generate an EB advertising the given schedule.
+    ///
+    /// All links of all slotframes of the schedule are advertised.
+    ///
+    /// * `schedule` - Schedule to advertise
+    /// * `asn` - Absolute slot number of the timeslot in which the EB will be
+    ///   sent
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::IeExceedsMtu`] if the advertised schedule does not
+    ///   fit into a single frame,
+    /// - [`FrameErrorKind::MalformedIe`] if a slotframe handle cannot be
+    ///   encoded in the TSCH Slotframe and Link IE.
+    pub fn generate<const S: usize, const L: usize, T: MacNeighbor>(
+        &mut self,
+        schedule: &TschSchedule<S, L, T>,
+        asn: AbsoluteSlotNumber,
+    ) -> Result<EnhancedBeacon, FrameError> {
+        let asn: i64 = asn.try_into().unwrap();
+
+        let mut links = heapless::Vec::<heapless::Vec<LinkDescriptorRepr, L>, S>::new();
+        let mut slotframes = heapless::Vec::<SlotframeDescriptorRepr, S>::new();
+        for slotframe in schedule.slotframes() {
+            // Capacities match those of the schedule.
+            let _ = links.push(
+                slotframe
+                    .links()
+                    .iter()
+                    .map(|link| LinkDescriptorRepr {
+                        timeslot: link.timeslot(),
+                        channel_offset: link.channel_offset(),
+                        link_options: link.link_options(),
+                    })
+                    .collect(),
+            );
+        }
+        for (slotframe, links) in schedule.slotframes().iter().zip(&links) {
+            let handle =
+                u8::try_from(slotframe.handle()).map_err(|_| FrameErrorKind::MalformedIe)?;
+            let _ = slotframes.push(SlotframeDescriptorRepr {
+                handle,
+                size: slotframe.size(),
+                links,
+            });
+        }
+        let slotframe_and_link = TschSlotframeAndLinkRepr {
+            slotframes: &slotframes,
+        };
+        let synchronization = TschSynchronizationRepr {
+            // The ASN never exceeds 5 bytes.
+            asn: Asn::new(asn as u64).unwrap(),
+            join_metric: self.join_metric,
+        };
+        let timings = schedule.timeslot_timings();
+
+        let mut ies = [0; PHY_MAX_PACKET_SIZE_127];
+        let mut writer = IeWriter::new(&mut ies);
+        writer.write(&HEADER_TERMINATION_IE_1)?;
+        let mlme_start = writer.reserve_header()?;
+        writer.nested_ie(
+            TSCH_SYNCHRONIZATION_IE_SUB_ID,
+            TschSynchronizationRepr::CONTENT_LEN as usize,
+            |content| synchronization.emit(content),
+        )?;
+        if timings.id() == 0 {
+            // The default timings are implied by the timeslot ID.
+            writer.nested_ie(TSCH_TIMESLOT_IE_SUB_ID, 1, |content| {
+                content[0] = 0;
+                Ok(())
+            })?;
+        } else {
+            writer.nested_ie(
+                TSCH_TIMESLOT_IE_SUB_ID,
+                timings.content_length() as usize,
+                |content| timings.emit(content),
+            )?;
+        }
+        writer.long_nested_ie(CHANNEL_HOPPING_IE_SUB_ID, 1, |content| {
+            content[0] = self.hopping_sequence_id;
+            Ok(())
+        })?;
+        writer.nested_ie(
+            TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID,
+            slotframe_and_link.content_length(),
+            |content| slotframe_and_link.emit(content),
+        )?;
+        let mlme_len = writer.offset - mlme_start - IE_HEADER_LEN;
+        writer.set_header(
+            mlme_start,
+            (1 << 15) | (MLME_PAYLOAD_IE_GROUP_ID << 11) | mlme_len as u16,
+        );
+        let ies_len = writer.offset;
+
+        let pan_id = PanId::from_u16(self.pan_id);
+        let builder = FrameBuilder::new(FrameType::Beacon)
+            .with_addressing(
+                Some((pan_id, Address::<&[u8]>::BROADCAST_ADDR)),
+                Some((
+                    pan_id,
+                    Address::Extended(ExtendedAddress::new(&self.src_address[..])),
+                )),
+            )
+            .without_security()
+            .with_ies(&ies[..ies_len])
+            .without_payload();
+        let eb = EnhancedBeacon::from_builder(&builder)?;
+
+        self.last_eb = Some(asn);
+        Ok(eb)
+    }
+}
+
+/// Writes IEs sequentially into a buffer.
+struct IeWriter<'buffer> {
+    buffer: &'buffer mut [u8],
+    offset: usize,
+}
+
+impl<'buffer> IeWriter<'buffer> {
+    fn new(buffer: &'buffer mut [u8]) -> Self {
+        Self { buffer, offset: 0 }
+    }
+
+    /// Reserve `len` bytes and return them.
+    fn reserve(&mut self, len: usize) -> Result<&mut [u8], FrameError> {
+        let end = self.offset + len;
+        if end > self.buffer.len() {
+            return Err(FrameErrorKind::IeExceedsMtu {
+                length: end,
+                mtu: self.buffer.len() as u16,
+            }
+            .into());
+        }
+        let bytes = &mut self.buffer[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), FrameError> {
+        self.reserve(bytes.len())?.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Reserve an IE header to be set later and return its offset.
+    fn reserve_header(&mut self) -> Result<usize, FrameError> {
+        let offset = self.offset;
+        self.reserve(IE_HEADER_LEN)?;
+        Ok(offset)
+    }
+
+    fn set_header(&mut self, offset: usize, header: u16) {
+        self.buffer[offset..offset + IE_HEADER_LEN].copy_from_slice(&header.to_le_bytes());
+    }
+
+    /// Write a short format nested IE.
+    fn nested_ie(
+        &mut self,
+        sub_id: u8,
+        len: usize,
+        emit: impl FnOnce(&mut [u8]) -> util::Result<()>,
+    ) -> Result<(), FrameError> {
+        let header = ((sub_id as u16 & 0x7f) << 8) | (len as u16 & 0xff);
+        self.write(&header.to_le_bytes())?;
+        emit(self.reserve(len)?).map_err(|_| FrameErrorKind::MalformedIe.into())
+    }
+
+    /// Write a long format nested IE.
+    fn long_nested_ie(
+        &mut self,
+        sub_id: u16,
+        len: usize,
+        emit: impl FnOnce(&mut [u8]) -> util::Result<()>,
+    ) -> Result<(), FrameError> {
+        let header = (1 << 15) | ((sub_id & 0xf) << 11) | (len as u16 & 0x7ff);
+        self.write(&header.to_le_bytes())?;
+        emit(self.reserve(len)?).map_err(|_| FrameErrorKind::MalformedIe.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        frame::fields::TschLinkOption,
+        neighbors::tests::TestNeighbor,
+        tsch::{
+            hopping::HoppingSequence,
+            schedule::{TschLink, TschLinkType, TschSlotframe},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn enhanced_beacon() {
+        let mut schedule = TschSchedule::<1, 1, TestNeighbor>::new();
+        let mut slotframe = TschSlotframe::new(0, 101, HoppingSequence::DEFAULT_4_4);
+        assert!(slotframe
+            .add_link(TschLink::new(
+                0,
+                0,
+                0,
+                TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared,
+                TschLinkType::Advertising,
+                None,
+            ))
+            .is_ok());
+        assert!(schedule.add_slotframe(slotframe).is_ok());
+
+        let mut generator = EbGenerator::new(0xabcd, [1, 2, 3, 4, 5, 6, 7, 8]);
+        generator.set_period(100);
+        let asn = |asn: u32| AbsoluteSlotNumber::try_from(asn).unwrap();
+        assert!(generator.is_due(asn(14)));
+
+        let eb = generator.generate(&schedule, asn(14)).unwrap();
+        assert_eq!(
+            &eb[..],
+            &[
+                0x40, 0xeb, // frame control
+                0xcd, 0xab, // dst PAN ID
+                0xff, 0xff, // dst address
+                0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // src address
+                0x00, 0x3f, // header termination IE 1
+                0x1a, 0x88, // MLME payload IE
+                0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, // TSCH synchronization IE
+                0x01, 0x1c, 0x00, // TSCH timeslot IE
+                0x01, 0xc8, 0x00, // channel hopping IE
+                0x0a, 0x1b, // TSCH slotframe and link IE
+                0x01, // number of slotframes
+                0x00, 0x65, 0x00, 0x01, // slotframe descriptor
+                0x00, 0x00, 0x00, 0x00, 0x07, // link information
+            ][..]
+        );
+
+        assert!(!generator.is_due(asn(113)));
+        assert!(generator.is_due(asn(114)));
+    }
+}
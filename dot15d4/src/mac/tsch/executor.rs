@@ -0,0 +1,610 @@
+//! Executes a TSCH schedule slot by slot.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::FrameControl,
+        radio::{Radio, RxWindow},
+        time::{Duration, Instant},
+        RadioTimerApi,
+    },
+    mac::{
+        ack::{ack_frame, AckFrame, AckMatcher, TimeCorrection},
+        frame::{fields::TschLinkOption, mpdu::FrameBuffer},
+        neighbors::MacNeighbor,
+        radio::MacRadio,
+    },
+};
+
+use super::{
+    asn::AbsoluteSlotNumber,
+    queue::TschQueues,
+    schedule::TschSchedule,
+    timeslot::{
+        TimeslotAction, TimeslotEvent, TimeslotResult, TimeslotRole, TimeslotTicks,
+        TimeslotTransition, TschTimeslot,
+    },
+};
+
+/// Executes the links of a TSCH schedule.
+///
+/// The executor aligns timeslots to a reference timeslot whose start is known,
+/// e.g. from the reception of an Enhanced Beacon. Timeslot `n` starts `n - asn`
+/// timeslot lengths after the start of the reference timeslot `asn`.
+pub struct TschExecutor<
+    'schedule,
+    Timer: RadioTimerApi,
+    const S: usize,
+    const L: usize,
+    T: MacNeighbor,
+> {
+    schedule: &'schedule mut TschSchedule<S, L, T>,
+    ticks: TimeslotTicks<Timer>,
+    reference_asn: AbsoluteSlotNumber,
+    reference_start: Instant<Timer>,
+}
+
+impl<'schedule, Timer: RadioTimerApi, const S: usize, const L: usize, T: MacNeighbor>
+    TschExecutor<'schedule, Timer, S, L, T>
+{
+    /// Creates an executor for the given schedule. Execution starts at the
+    /// reference timeslot.
+    ///
+    /// * `schedule` - Schedule to execute
+    /// * `asn` - Absolute slot number of the reference timeslot
+    /// * `slot_start` - Instant at which the reference timeslot starts
+    pub fn new(
+        schedule: &'schedule mut TschSchedule<S, L, T>,
+        asn: AbsoluteSlotNumber,
+        slot_start: Instant<Timer>,
+    ) -> Self {
+        let ticks = TimeslotTicks::new(schedule.timeslot_timings());
+        schedule.set_asn(asn);
+        Self {
+            schedule,
+            ticks,
+            reference_asn: asn,
+            reference_start: slot_start,
+        }
+    }
+
+    /// Waits for the next active timeslot, executes it and returns its ASN
+    /// together with the result.
+    ///
+    /// Links with the TX option send the frame selected by
+    /// [`TschQueues::pending_tx()`], the caller reports the outcome with
+    /// [`TschQueues::on_tx_result()`]. A frame received in the timeslot is
+    /// left in the given buffer.
+    ///
+    /// Links without any pending frame and without the RX option are skipped
+    /// as are timeslots that already started and timeslots on channels the
+    /// radio cannot tune to. The executor sleeps through skipped timeslots so
+    /// that frames queued in the meantime are sent in the next one. Returns
+    /// `None` if the schedule does not contain any link or if the start of the
+    /// next timeslot is out of the range of the radio timer.
+    ///
+    /// * `radio` - The radio executing the timeslots
+    /// * `queues` - The frames to send
+    /// * `frame` - Buffer for the frame received in the timeslot
+    pub async fn run_next_slot<
+        R: Radio<Timer = Timer>,
+        F: AsRef<[u8]>,
+        const N: usize,
+        const Q: usize,
+    >(
+        &mut self,
+        radio: &mut MacRadio<R>,
+        queues: &mut TschQueues<F, N, Q>,
+        frame: &mut FrameBuffer<PHY_MAX_PACKET_SIZE_127>,
+    ) -> Option<(AbsoluteSlotNumber, TimeslotResult)> {
+        loop {
+            let (asn, channel, link) = self.schedule.next_active_cell()?;
+
+            let role = if let Some(ack_requested) = link
+                .link_options()
+                .contains(TschLinkOption::Tx)
+                .then(|| queues.pending_tx(link))
+                .flatten()
+            {
+                Some(TimeslotRole::Tx {
+                    cca: link.link_options().contains(TschLinkOption::Shared),
+                    ack_requested,
+                })
+            } else if link.link_options().contains(TschLinkOption::Rx) {
+                Some(TimeslotRole::Rx)
+            } else {
+                None
+            };
+
+            let slot_start = self.slot_start(asn)?;
+            if Timer::now().tick() > slot_start.tick() {
+                continue;
+            }
+            Timer::wait_for_alarm_at(slot_start).await;
+
+            let Some(role) = role else {
+                continue;
+            };
+            if radio.set_channel(channel).is_err() {
+                continue;
+            }
+
+            let tx = queues
+                .selected()
+                .map_or(&[][..], |queued| queued.frame.as_ref());
+            let mut time_correction = None;
+            let mut transition = TschTimeslot::new(self.ticks, slot_start, role).start();
+            let result = loop {
+                match transition {
+                    TimeslotTransition::Action(timeslot, action) => {
+                        let event = execute(radio, action, tx, frame, &mut time_correction).await;
+                        transition = timeslot.step(event);
+                    }
+                    TimeslotTransition::Done(result) => break result,
+                }
+            };
+
+            return Some((asn, result));
+        }
+    }
+
+    /// Return the instant at which the timeslot with the given ASN starts, if
+    /// it can be represented by the radio timer.
+    fn slot_start(&self, asn: AbsoluteSlotNumber) -> Option<Instant<Timer>> {
+        let asn: i64 = asn.try_into().ok()?;
+        let reference_asn: i64 = self.reference_asn.try_into().ok()?;
+        let offset = self
+            .ticks
+            .timeslot_length()
+            .ticks()
+            .checked_mul(asn - reference_asn)?;
+        let tick = self.reference_start.tick().checked_add_signed(offset)?;
+        Some(Instant::new(tick))
+    }
+}
+
+/// Executes the given radio operation of a timeslot and returns its outcome.
+///
+/// Radio errors are mapped like in [`MacRadio`], i.e. a failed transmission is
+/// reported as done and a failed reception as timeout.
+///
+/// * `radio` - The radio executing the operation
+/// * `action` - The radio operation
+/// * `tx` - The MPDU (without FCS) to send in a TX timeslot
+/// * `frame` - Buffer for the frame received in an RX timeslot
+/// * `time_correction` - The Time Correction of the frame received in an RX
+///   timeslot, set on reception and sent with its ACK
+async fn execute<R: Radio>(
+    radio: &mut MacRadio<R>,
+    action: TimeslotAction<R::Timer>,
+    tx: &[u8],
+    frame: &mut FrameBuffer<PHY_MAX_PACKET_SIZE_127>,
+    time_correction: &mut Option<TimeCorrection>,
+) -> TimeslotEvent<R::Timer> {
+    match action {
+        TimeslotAction::Cca { at, .. } => {
+            if at > R::Timer::now() {
+                R::Timer::wait_for_alarm_at(at).await;
+            }
+            TimeslotEvent::CcaDone {
+                busy: !radio.clear_channel().await,
+            }
+        }
+        TimeslotAction::Tx { at } => transmit(radio, tx, at).await,
+        TimeslotAction::Rx { at, until } => {
+            let Some(rx) = radio
+                .receive_frame(frame, RxWindow::between(at, until))
+                .await
+            else {
+                return TimeslotEvent::Timeout;
+            };
+            // The RMARKER is expected in the middle of the RX window.
+            let expected = at + Duration::new((until - at).ticks() / 2);
+            let measured = rx.timestamp.convert_into_rounding_down();
+            *time_correction = Some(TimeCorrection {
+                correction: (expected - measured).convert_into_rounding_down(),
+                nack: false,
+            });
+            TimeslotEvent::FrameReceived {
+                end: rx.end,
+                ack_requested: FrameControl::new(&frame[..])
+                    .is_ok_and(|frame_control| frame_control.ack_request()),
+            }
+        }
+        TimeslotAction::RxAck { at, until } => {
+            let Ok(Some(matcher)) = AckMatcher::new(tx) else {
+                return TimeslotEvent::Timeout;
+            };
+            let mut ack = AckFrame::new();
+            while radio
+                .receive_frame(&mut ack, RxWindow::between(at, until))
+                .await
+                .is_some()
+            {
+                if let Some(received) = matcher.matches(&ack) {
+                    return TimeslotEvent::AckReceived {
+                        nack: received.is_nack(),
+                    };
+                }
+            }
+            TimeslotEvent::Timeout
+        }
+        TimeslotAction::TxAck { at } => {
+            // Radios acknowledging frames in hardware already sent the ACK.
+            if radio.capabilities().hardware_ack {
+                return TimeslotEvent::TxDone {
+                    end: R::Timer::now(),
+                };
+            }
+            // Without IE support, Enh-Acks go without Time Correction IE.
+            let ack = ack_frame(frame, *time_correction).or_else(|_| ack_frame(frame, None));
+            match ack {
+                Ok(Some(ack)) => transmit(radio, &ack, at).await,
+                _ => TimeslotEvent::TxDone {
+                    end: R::Timer::now(),
+                },
+            }
+        }
+    }
+}
+
+/// Sends the given MPDU so that its RMARKER passes the antenna at the given
+/// instant.
+async fn transmit<R: Radio>(
+    radio: &mut MacRadio<R>,
+    mpdu: &[u8],
+    at: Instant<R::Timer>,
+) -> TimeslotEvent<R::Timer> {
+    let end = match radio.transmit_at(mpdu, Some(at)).await {
+        Ok(info) => info.end,
+        Err(_) => R::Timer::now(),
+    };
+    TimeslotEvent::TxDone { end }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{
+        driver::{
+            config::{CcaMode, Channel},
+            radio::{RadioCapabilities, RadioError, RxInfo, TxInfo},
+            test_clock::TestClock,
+        },
+        mac::{
+            neighbors::tests::TestNeighbor,
+            test_helpers::{mac_radio, received, timed_out, timestamp, TEST_CAPABILITIES},
+            tsch::{
+                queue::TschDestination,
+                schedule::{TschLink, TschLinkType, TschSlotframe},
+                HoppingSequence,
+            },
+        },
+    };
+
+    /// The start of the reference timeslot with ASN 0.
+    const REFERENCE_START: u64 = 1_000;
+
+    /// The default timeslot length.
+    const TIMESLOT_LENGTH: u64 = 10_000;
+
+    /// The time the [`FakeRadio`] takes to send a frame.
+    const TX_DURATION: u64 = 1_000;
+
+    /// Data frame (2006) with sequence number 7 requesting an ACK.
+    const DATA: [u8; 9] = [0x61, 0x98, 0x07, 0xcd, 0xab, 0x34, 0x12, 0x01, 0x00];
+
+    /// A radio operation of the [`FakeRadio`].
+    #[derive(Debug, PartialEq, Eq)]
+    enum Operation {
+        Cca { at: Instant<TestClock> },
+        Tx { at: Instant<TestClock>, seq_nr: u8 },
+        Rx(RxWindow<TestClock>),
+    }
+
+    /// A radio whose operations all succeed, recording them.
+    ///
+    /// Transmitted frames are acknowledged. The frame to receive, if any, is
+    /// received 30µs after the middle of the RX window.
+    #[derive(Default)]
+    struct FakeRadio {
+        operations: Vec<Operation>,
+        ack: Option<[u8; 3]>,
+        rx: Option<&'static [u8]>,
+    }
+
+    impl Radio for FakeRadio {
+        type Timer = TestClock;
+
+        fn capabilities(&self) -> RadioCapabilities {
+            TEST_CAPABILITIES
+        }
+
+        fn set_channel(&mut self, _channel: Channel) -> Result<(), RadioError> {
+            Ok(())
+        }
+
+        fn set_tx_power(&mut self, dbm: i8) -> Result<i8, RadioError> {
+            Ok(dbm)
+        }
+
+        async fn transmit_at(
+            &mut self,
+            mpdu: &[u8],
+            at: Option<Instant<TestClock>>,
+        ) -> Result<TxInfo<TestClock>, RadioError> {
+            let at = at.unwrap();
+            self.operations.push(Operation::Tx {
+                at,
+                seq_nr: mpdu[2],
+            });
+            self.ack = Some([0x02, 0x00, mpdu[2]]);
+            TestClock::advance(at - TestClock::now() + Duration::new(TX_DURATION as i64));
+            Ok(TxInfo {
+                timestamp: timestamp(at),
+                end: TestClock::now(),
+            })
+        }
+
+        async fn receive(
+            &mut self,
+            buffer: &mut [u8],
+            window: RxWindow<TestClock>,
+        ) -> Result<Option<RxInfo<TestClock>>, RadioError> {
+            self.operations.push(Operation::Rx(window));
+            let (Some(start), Some(end)) = (window.start, window.end) else {
+                panic!("TSCH receptions are bounded");
+            };
+            if let Some(ack) = self.ack.take() {
+                TestClock::advance(start - TestClock::now());
+                return received(buffer, &ack);
+            }
+            match self.rx.take() {
+                Some(mpdu) => {
+                    let middle = start + Duration::new((end - start).ticks() / 2);
+                    TestClock::advance(middle - TestClock::now() + Duration::new(30));
+                    received(buffer, mpdu)
+                }
+                None => timed_out(window),
+            }
+        }
+
+        async fn cca(&mut self, _mode: CcaMode) -> Result<bool, RadioError> {
+            self.operations.push(Operation::Cca {
+                at: TestClock::now(),
+            });
+            Ok(true)
+        }
+
+        async fn energy_detect(
+            &mut self,
+            _duration: Duration<TestClock>,
+        ) -> Result<i8, RadioError> {
+            Err(RadioError::Unsupported)
+        }
+    }
+
+    type Schedule = TschSchedule<1, 4, TestNeighbor>;
+
+    type Queues = TschQueues<&'static [u8], 1, 2>;
+
+    fn schedule(size: u16, links: &[(u16, TschLinkOption)]) -> Schedule {
+        let mut schedule = Schedule::new();
+        schedule
+            .add_slotframe(TschSlotframe::new(0, size, HoppingSequence::DEFAULT_4_4))
+            .unwrap();
+        for (handle, &(timeslot, link_options)) in links.iter().enumerate() {
+            let link = TschLink::new(
+                handle as u16,
+                timeslot,
+                0,
+                link_options,
+                TschLinkType::Normal,
+                None,
+            );
+            schedule.add_link(0, link).unwrap();
+        }
+        schedule
+    }
+
+    fn executor(schedule: &mut Schedule) -> TschExecutor<'_, TestClock, 1, 4, TestNeighbor> {
+        TestClock::reset();
+        TschExecutor::new(
+            schedule,
+            AbsoluteSlotNumber::default(),
+            Instant::new(REFERENCE_START),
+        )
+    }
+
+    fn slot_start(asn: u64) -> u64 {
+        REFERENCE_START + asn * TIMESLOT_LENGTH
+    }
+
+    fn asn(value: u32) -> AbsoluteSlotNumber {
+        value.try_into().unwrap()
+    }
+
+    fn at(tick: u64) -> Instant<TestClock> {
+        Instant::new(tick)
+    }
+
+    #[test]
+    fn tx_and_rx_links() {
+        let mut schedule = schedule(
+            3,
+            &[
+                (0, TschLinkOption::Tx | TschLinkOption::Shared),
+                (1, TschLinkOption::Rx),
+            ],
+        );
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio::default());
+        let mut queues = Queues::default();
+        let mut frame = FrameBuffer::new();
+        queues
+            .enqueue(TschDestination::Broadcast, &DATA, true)
+            .unwrap();
+
+        let (slot, result) =
+            TestClock::block_on(executor.run_next_slot(&mut radio, &mut queues, &mut frame))
+                .unwrap();
+        assert_eq!(slot, asn(0));
+        assert_eq!(result, TimeslotResult::Sent);
+        let tx = slot_start(0) + 2120;
+        let rx_ack = tx + TX_DURATION + 800;
+        assert_eq!(
+            radio.radio_mut().operations,
+            [
+                Operation::Cca {
+                    at: at(slot_start(0) + 1800),
+                },
+                Operation::Tx {
+                    at: at(tx),
+                    seq_nr: 0x07
+                },
+                Operation::Rx(RxWindow::between(at(rx_ack), at(rx_ack + 400))),
+            ]
+        );
+
+        radio.radio_mut().operations.clear();
+        let (slot, result) =
+            TestClock::block_on(executor.run_next_slot(&mut radio, &mut queues, &mut frame))
+                .unwrap();
+        assert_eq!(slot, asn(1));
+        assert_eq!(result, TimeslotResult::Idle);
+        assert_eq!(
+            radio.radio_mut().operations,
+            [Operation::Rx(RxWindow::between(
+                at(slot_start(1) + 1020),
+                at(slot_start(1) + 3220)
+            ))]
+        );
+        assert!(frame.is_empty());
+    }
+
+    #[test]
+    fn received_frames_are_acknowledged() {
+        let mut schedule = schedule(1, &[(0, TschLinkOption::Rx)]);
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio {
+            rx: Some(&DATA),
+            ..Default::default()
+        });
+        let mut frame = FrameBuffer::new();
+
+        let (slot, result) = TestClock::block_on(executor.run_next_slot(
+            &mut radio,
+            &mut Queues::default(),
+            &mut frame,
+        ))
+        .unwrap();
+        assert_eq!(slot, asn(0));
+        assert_eq!(result, TimeslotResult::Received);
+        assert_eq!(frame[..], DATA);
+        let rx_end = slot_start(0) + 2120 + 30;
+        assert_eq!(
+            radio.radio_mut().operations[1..],
+            [Operation::Tx {
+                at: at(rx_end + 1000),
+                seq_nr: 0x07
+            }]
+        );
+    }
+
+    #[test]
+    fn idle_slots() {
+        let mut schedule = schedule(3, &[(0, TschLinkOption::Tx), (1, TschLinkOption::Rx)]);
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio::default());
+        let mut queues = Queues::default();
+        let mut frame = FrameBuffer::new();
+
+        // Nothing is pending for the TX link at ASN 0 and 3.
+        let (slot, _) =
+            TestClock::block_on(executor.run_next_slot(&mut radio, &mut queues, &mut frame))
+                .unwrap();
+        assert_eq!(slot, asn(1));
+        let (slot, _) =
+            TestClock::block_on(executor.run_next_slot(&mut radio, &mut queues, &mut frame))
+                .unwrap();
+        assert_eq!(slot, asn(4));
+        // The RX window of ASN 4 ended.
+        assert_eq!(TestClock::now().tick(), slot_start(4) + 3220);
+
+        // Data frame (2006) with sequence number 8 not requesting an ACK.
+        let data = &[0x41, 0x98, 0x08, 0xcd, 0xab, 0x34, 0x12, 0x01, 0x00];
+        queues
+            .enqueue(TschDestination::Broadcast, data, false)
+            .unwrap();
+        let (slot, result) =
+            TestClock::block_on(executor.run_next_slot(&mut radio, &mut queues, &mut frame))
+                .unwrap();
+        assert_eq!(slot, asn(6));
+        assert_eq!(result, TimeslotResult::Sent);
+    }
+
+    #[test]
+    fn idle_tx_links_wait_for_the_next_slot() {
+        let mut schedule = schedule(2, &[(0, TschLinkOption::Tx)]);
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio::default());
+        let mut queues = Queues::default();
+        let mut frame = FrameBuffer::new();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        {
+            let mut next_slot = pin!(executor.run_next_slot(&mut radio, &mut queues, &mut frame));
+            for slot in [0, 2, 4] {
+                assert!(next_slot.as_mut().poll(&mut cx).is_pending());
+                assert_eq!(TestClock::alarm(), Some(at(slot_start(slot))));
+                TestClock::advance(at(slot_start(slot)) - TestClock::now());
+            }
+            assert!(next_slot.as_mut().poll(&mut cx).is_pending());
+        }
+        assert!(radio.radio_mut().operations.is_empty());
+    }
+
+    #[test]
+    fn started_slots_are_skipped() {
+        let mut schedule = schedule(1, &[(0, TschLinkOption::Rx)]);
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio::default());
+        let mut frame = FrameBuffer::new();
+
+        TestClock::advance(Duration::new(REFERENCE_START as i64 + 1));
+        let (slot, _) = TestClock::block_on(executor.run_next_slot(
+            &mut radio,
+            &mut Queues::default(),
+            &mut frame,
+        ))
+        .unwrap();
+        assert_eq!(slot, asn(1));
+        assert_eq!(radio.radio_mut().operations.len(), 1);
+    }
+
+    #[test]
+    fn empty_schedule() {
+        let mut schedule = Schedule::new();
+        let mut executor = executor(&mut schedule);
+        let mut radio = mac_radio(FakeRadio::default());
+        let mut frame = FrameBuffer::new();
+        assert_eq!(
+            TestClock::block_on(executor.run_next_slot(
+                &mut radio,
+                &mut Queues::default(),
+                &mut frame
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,397 @@
+#![allow(dead_code)]
+use super::asn::AbsoluteSlotNumber;
+use crate::{
+    driver::config::{Channel, ChannelPage},
+    mac::mlme::scan::EnergyDetectionResult,
+};
+
+/// Maximum number of channels in a hopping sequence.
+pub const MAX_HOPPING_SEQUENCE_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoppingSequenceError {
+    /// The sequence is empty or exceeds [`MAX_HOPPING_SEQUENCE_LEN`].
+    InvalidLength,
+    /// The channel is not on the channel page of the sequence.
+    InvalidChannel,
+    /// Blacklisting the channel would leave no channel to hop on.
+    AllChannelsBlacklisted,
+}
+
+/// A channel hopping sequence (IEEE 802.15.4-2020, section 6.2.6.3).
+///
+/// The channel used by a link at a given ASN is
+/// `hopping_sequence[(ASN + channel_offset) % len]`. Blacklisted channels are
+/// removed from the sequence, i.e. the computation only takes the remaining
+/// channels into account so that all devices sharing the same blacklist
+/// still agree on the channel.
+///
+/// All channels of a sequence belong to the same [`ChannelPage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoppingSequence {
+    /// The channel page and band of the channels.
+    page: ChannelPage,
+    /// Channel numbers of the sequence, only the first `len` entries are
+    /// valid.
+    channels: [u16; MAX_HOPPING_SEQUENCE_LEN],
+    /// Number of channels in the sequence.
+    len: u8,
+    /// Bitmap of the positions in the sequence holding blacklisted channels.
+    blacklist: u16,
+}
+
+impl HoppingSequence {
+    /// Default hopping sequence over all 16 channels of the 2.4 GHz band.
+    pub const DEFAULT_16_16: Self = Self::from_array([
+        16, 17, 23, 18, 26, 15, 25, 22, 19, 11, 12, 13, 24, 14, 20, 21,
+    ]);
+    /// Hopping sequence over the 4 channels that least overlap with Wi-Fi.
+    pub const DEFAULT_4_4: Self = Self::from_array([15, 25, 26, 20]);
+    /// Hopping sequence of length 16 over the channels of
+    /// [`HoppingSequence::DEFAULT_4_4`].
+    pub const DEFAULT_4_16: Self = Self::from_array([
+        20, 26, 25, 26, 15, 15, 25, 20, 26, 15, 26, 25, 20, 15, 20, 25,
+    ]);
+    /// Hopping sequence over 2 channels.
+    pub const DEFAULT_2_2: Self = Self::from_array([20, 25]);
+    /// Single channel, i.e. no channel hopping.
+    pub const DEFAULT_1_1: Self = Self::from_array([20]);
+
+    /// Creates a hopping sequence from the given channels of the 2.4 GHz
+    /// band.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence is empty, longer than
+    /// [`MAX_HOPPING_SEQUENCE_LEN`] or contains a channel outside 11-26. Use
+    /// [`HoppingSequence::new()`] for user-provided sequences.
+    pub const fn from_array<const N: usize>(channels: [u16; N]) -> Self {
+        assert!(N > 0 && N <= MAX_HOPPING_SEQUENCE_LEN);
+        let page = ChannelPage::Oqpsk2450Mhz;
+        let mut sequence = Self {
+            page,
+            channels: [0; MAX_HOPPING_SEQUENCE_LEN],
+            len: N as u8,
+            blacklist: 0,
+        };
+        let mut i = 0;
+        while i < N {
+            assert!(page.channel(channels[i]).is_some());
+            sequence.channels[i] = channels[i];
+            i += 1;
+        }
+        sequence
+    }
+
+    /// Creates a hopping sequence from the given channels.
+    ///
+    /// * `page` - The channel page and band of the channels
+    /// * `channels` - Channel numbers of the sequence
+    pub fn new(page: ChannelPage, channels: &[u16]) -> Result<Self, HoppingSequenceError> {
+        if channels.is_empty() || channels.len() > MAX_HOPPING_SEQUENCE_LEN {
+            return Err(HoppingSequenceError::InvalidLength);
+        }
+        if channels
+            .iter()
+            .any(|&channel| page.channel(channel).is_none())
+        {
+            return Err(HoppingSequenceError::InvalidChannel);
+        }
+        let mut sequence = Self {
+            page,
+            channels: [0; MAX_HOPPING_SEQUENCE_LEN],
+            len: channels.len() as u8,
+            blacklist: 0,
+        };
+        sequence.channels[..channels.len()].copy_from_slice(channels);
+        Ok(sequence)
+    }
+
+    /// Return the channel page and band of the channels of the sequence.
+    pub fn page(&self) -> ChannelPage {
+        self.page
+    }
+
+    /// Return the number of channels in the sequence, including blacklisted
+    /// channels.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Return whether the sequence is empty, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the channel numbers of the sequence, including blacklisted
+    /// channels.
+    pub fn channel_numbers(&self) -> &[u16] {
+        &self.channels[..self.len()]
+    }
+
+    /// Return an iterator over the channels of the sequence, including
+    /// blacklisted channels.
+    pub fn channels(&self) -> impl Iterator<Item = Channel> + '_ {
+        (0..self.len()).map(|index| self.channel_at(index))
+    }
+
+    /// Return an iterator over the channels actually used for hopping.
+    pub fn active_channels(&self) -> impl Iterator<Item = Channel> + '_ {
+        (0..self.len())
+            .filter(|index| self.blacklist & (1 << index) == 0)
+            .map(|index| self.channel_at(index))
+    }
+
+    /// Return the channel to be used at the given ASN and channel offset.
+    ///
+    /// * `asn` - Absolute slot number
+    /// * `channel_offset` - Channel offset of the link
+    pub fn channel(&self, asn: AbsoluteSlotNumber, channel_offset: u16) -> Channel {
+        let len = self.active_channels().count() as u16;
+        let index = (asn + channel_offset) % len;
+        // Blacklisting the last channel is rejected, so there is always at
+        // least one active channel.
+        self.active_channels().nth(index as usize).unwrap()
+    }
+
+    /// Remove the given channel from the hopping sequence.
+    ///
+    /// Blacklisting a channel of the channel page that is not part of the
+    /// sequence has no effect.
+    ///
+    /// * `channel` - Channel to blacklist
+    pub fn blacklist(&mut self, channel: Channel) -> Result<(), HoppingSequenceError> {
+        if channel.page() != self.page {
+            return Err(HoppingSequenceError::InvalidChannel);
+        }
+        let blacklist = self.blacklist | self.positions(channel);
+        if blacklist == u16::MAX >> (MAX_HOPPING_SEQUENCE_LEN - self.len()) {
+            return Err(HoppingSequenceError::AllChannelsBlacklisted);
+        }
+        self.blacklist = blacklist;
+        Ok(())
+    }
+
+    /// Re-add a previously blacklisted channel to the hopping sequence.
+    ///
+    /// * `channel` - Channel to remove from the blacklist
+    pub fn unblacklist(&mut self, channel: Channel) {
+        self.blacklist &= !self.positions(channel);
+    }
+
+    /// Return whether the given channel is blacklisted.
+    ///
+    /// * `channel` - Channel to check
+    pub fn is_blacklisted(&self, channel: Channel) -> bool {
+        self.blacklist & self.positions(channel) != 0
+    }
+
+    /// Update the blacklist from the results of an ED scan.
+    ///
+    /// Channels of the sequence with an average energy above the threshold
+    /// are blacklisted, busiest first, and quiet channels are re-added. The
+    /// quietest of the busy channels is kept if blacklisting it would leave no
+    /// channel to hop on. Channels without samples keep their state.
+    ///
+    /// Returns the number of busy channels still used for hopping, i.e. 0 or
+    /// 1.
+    ///
+    /// * `results` - Results of an ED scan
+    /// * `threshold` - ED value above which a channel is considered busy
+    pub fn blacklist_interference(
+        &mut self,
+        results: &[EnergyDetectionResult],
+        threshold: u8,
+    ) -> usize {
+        // Bitmap of the positions holding busy channels.
+        let mut busy = 0u16;
+        for result in results {
+            let positions = self.positions(result.channel());
+            let Some(avg) = result.avg() else {
+                continue;
+            };
+            if positions == 0 {
+                continue;
+            }
+            if avg > threshold {
+                busy |= positions;
+            } else {
+                self.unblacklist(result.channel());
+            }
+        }
+
+        loop {
+            let busiest = results
+                .iter()
+                .filter(|result| {
+                    busy & self.positions(result.channel()) != 0
+                        && !self.is_blacklisted(result.channel())
+                })
+                .max_by_key(|result| result.avg());
+            let Some(busiest) = busiest else {
+                return 0;
+            };
+            if self.blacklist(busiest.channel()).is_err() {
+                return 1;
+            }
+        }
+    }
+
+    /// Return the channel at the given position of the sequence.
+    fn channel_at(&self, index: usize) -> Channel {
+        // Safety: The channels were checked against the channel page.
+        self.page.channel(self.channels[index]).unwrap()
+    }
+
+    /// Return the bitmap of the positions in the sequence holding the given
+    /// channel.
+    fn positions(&self, channel: Channel) -> u16 {
+        if channel.page() != self.page {
+            return 0;
+        }
+        self.channel_numbers()
+            .iter()
+            .enumerate()
+            .filter(|(_, &number)| number == channel.number())
+            .fold(0, |positions, (index, _)| positions | (1 << index))
+    }
+}
+
+impl Default for HoppingSequence {
+    fn default() -> Self {
+        Self::DEFAULT_16_16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::config::SunChannelPlan;
+
+    #[test]
+    fn channel() {
+        let sequence = HoppingSequence::DEFAULT_4_4;
+        let asn = |asn: u32| AbsoluteSlotNumber::try_from(asn).unwrap();
+
+        assert_eq!(sequence.channel(asn(0), 0), Channel::_15);
+        assert_eq!(sequence.channel(asn(1), 0), Channel::_25);
+        assert_eq!(sequence.channel(asn(1), 2), Channel::_20);
+        assert_eq!(sequence.channel(asn(4242), 3), Channel::_25);
+        assert_eq!(
+            HoppingSequence::default().channel(asn(0xffff_fff0), 1),
+            Channel::_17
+        );
+    }
+
+    #[test]
+    fn user_provided() {
+        let page = ChannelPage::Oqpsk2450Mhz;
+        let sequence = HoppingSequence::new(page, &[11, 12, 13]).unwrap();
+        assert_eq!(sequence.len(), 3);
+        assert_eq!(sequence.channel_numbers(), &[11, 12, 13]);
+        assert!(sequence
+            .channels()
+            .eq([Channel::_11, Channel::_12, Channel::_13]));
+
+        assert_eq!(
+            HoppingSequence::new(page, &[]),
+            Err(HoppingSequenceError::InvalidLength)
+        );
+        assert_eq!(
+            HoppingSequence::new(page, &[11; MAX_HOPPING_SEQUENCE_LEN + 1]),
+            Err(HoppingSequenceError::InvalidLength)
+        );
+        assert_eq!(
+            HoppingSequence::new(page, &[11, 27]),
+            Err(HoppingSequenceError::InvalidChannel)
+        );
+    }
+
+    #[test]
+    fn sun_channel_plan() {
+        let page = ChannelPage::Sun(SunChannelPlan::EU_863MHZ);
+        let asn = |asn: u32| AbsoluteSlotNumber::try_from(asn).unwrap();
+
+        let mut sequence = HoppingSequence::new(page, &[0, 5, 33]).unwrap();
+        let channel = sequence.channel(asn(1), 0);
+        assert_eq!(channel, page.channel(5).unwrap());
+        assert_eq!(channel.center_frequency(), 864_125);
+
+        assert_eq!(
+            sequence.blacklist(Channel::_11),
+            Err(HoppingSequenceError::InvalidChannel)
+        );
+        assert!(sequence.blacklist(channel).is_ok());
+        assert_eq!(sequence.channel(asn(1), 0), page.channel(33).unwrap());
+        assert_eq!(
+            HoppingSequence::new(page, &[0, 34]),
+            Err(HoppingSequenceError::InvalidChannel)
+        );
+    }
+
+    #[test]
+    fn blacklist() {
+        let mut sequence = HoppingSequence::DEFAULT_4_4;
+        let asn = |asn: u32| AbsoluteSlotNumber::try_from(asn).unwrap();
+
+        assert!(sequence.blacklist(Channel::_25).is_ok());
+        assert!(sequence.is_blacklisted(Channel::_25));
+        assert_eq!(sequence.len(), 4);
+        assert!(sequence
+            .active_channels()
+            .eq([Channel::_15, Channel::_26, Channel::_20]));
+        assert_eq!(sequence.channel(asn(0), 0), Channel::_15);
+        assert_eq!(sequence.channel(asn(1), 0), Channel::_26);
+        assert_eq!(sequence.channel(asn(3), 0), Channel::_15);
+
+        assert!(sequence.blacklist(Channel::_15).is_ok());
+        assert!(sequence.blacklist(Channel::_26).is_ok());
+        assert_eq!(
+            sequence.blacklist(Channel::_20),
+            Err(HoppingSequenceError::AllChannelsBlacklisted)
+        );
+        assert_eq!(sequence.channel(asn(7), 1), Channel::_20);
+
+        sequence.unblacklist(Channel::_25);
+        assert!(!sequence.is_blacklisted(Channel::_25));
+        assert!(sequence.active_channels().eq([Channel::_25, Channel::_20]));
+
+        // Repeated channels are blacklisted at all positions.
+        let mut sequence = HoppingSequence::DEFAULT_4_16;
+        assert!(sequence.blacklist(Channel::_26).is_ok());
+        assert_eq!(sequence.active_channels().count(), 12);
+    }
+
+    #[test]
+    fn blacklist_interference() {
+        let result = |channel: Channel, ed: u8| {
+            let mut result = EnergyDetectionResult::new(channel);
+            result.add_sample(ed);
+            result
+        };
+        let mut sequence = HoppingSequence::DEFAULT_4_4;
+        sequence.blacklist(Channel::_20).unwrap();
+
+        // Channel 11 is not part of the sequence and channel 26 was not
+        // sampled.
+        let results = [
+            result(Channel::_11, 0xff),
+            result(Channel::_15, 0x80),
+            result(Channel::_20, 0x10),
+            result(Channel::_25, 0x40),
+            EnergyDetectionResult::new(Channel::_26),
+        ];
+        assert_eq!(sequence.blacklist_interference(&results, 0x20), 0);
+        assert!(sequence.active_channels().eq([Channel::_26, Channel::_20]));
+
+        // The quietest busy channel is kept.
+        let results = [
+            result(Channel::_15, 0x80),
+            result(Channel::_20, 0x30),
+            result(Channel::_25, 0x40),
+            result(Channel::_26, 0x90),
+        ];
+        assert_eq!(sequence.blacklist_interference(&results, 0x20), 1);
+        assert!(sequence.active_channels().eq([Channel::_20]));
+    }
+}
@@ -0,0 +1,542 @@
+//! TSCH join process (IEEE 802.15.4-2020, section 6.3.6).
+//!
+//! A device joins a TSCH network by passively scanning the channels of the
+//! hopping sequence for Enhanced Beacons (EBs). The best EB, i.e. the one with
+//! the lowest join metric and, among those, the highest RSSI, is selected as
+//! time source. Its TSCH Synchronization IE provides the ASN while its
+//! reception time provides the start of the timeslot in which it was sent.
+//! Finally, the schedule advertised in the EB (or the minimal schedule if the
+//! EB does not advertise any links) is installed.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, AddressingFields, AddressingRepr, FrameControl, FrameType},
+        radio::Radio,
+        time::{Duration, Frequency, Instant, Microseconds},
+        RadioTimerApi,
+    },
+    mac::{
+        ack::field,
+        frame::{
+            fields::{
+                Asn, HeaderIes, PayloadIes, TschSlotframeAndLink, TschSynchronization,
+                TschTimeslot, TschTimeslotTimings, TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID,
+                TSCH_SYNCHRONIZATION_IE_SUB_ID, TSCH_TIMESLOT_IE_SUB_ID,
+            },
+            mpdu::FrameBuffer,
+            FrameError, FrameErrorKind,
+        },
+        neighbors::MacNeighbor,
+        radio::MacRadio,
+    },
+};
+
+use super::{
+    asn::AbsoluteSlotNumber,
+    config::{minimal_slotframe, MINIMAL_SLOTFRAME_SIZE},
+    hopping::HoppingSequence,
+    schedule::{ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe},
+};
+
+const FRAME_CONTROL_LEN: usize = 2;
+
+/// The nested IE sub-ID of the Channel Hopping IE (long format).
+const CHANNEL_HOPPING_IE_SUB_ID: u8 = 0x9;
+
+/// The TSCH information carried by an Enhanced Beacon.
+#[derive(Debug)]
+pub struct EbInfo<'frame> {
+    /// The PAN ID of the network.
+    pub pan_id: u16,
+    /// The extended address of the sender (little endian).
+    pub src_address: [u8; 8],
+    /// The ASN of the timeslot in which the EB was sent.
+    pub asn: Asn,
+    /// The join metric of the sender.
+    pub join_metric: u8,
+    /// The TSCH Timeslot IE, if present.
+    pub timeslot: Option<TschTimeslot<&'frame [u8]>>,
+    /// The hopping sequence ID of the Channel Hopping IE, if present.
+    pub hopping_sequence_id: Option<u8>,
+    /// The TSCH Slotframe and Link IE, if present.
+    pub slotframe_and_link: Option<TschSlotframeAndLink<&'frame [u8]>>,
+}
+
+impl<'frame> EbInfo<'frame> {
+    /// Extracts the TSCH information from the given MPDU (without FCS).
+    ///
+    /// # Errors
+    ///
+    /// - [`FrameErrorKind::SecurityNotSupported`] if the frame is secured,
+    /// - [`FrameErrorKind::InvalidAddressingCombination`] if the source address
+    ///   is not an extended address. The time source is tracked by its
+    ///   extended address (see [`TschSyncMonitor`](super::sync::TschSyncMonitor)),
+    ///   which cannot be derived from a short address,
+    /// - [`FrameErrorKind::MalformedIe`] if the frame is not an Enhanced Beacon
+    ///   carrying a TSCH Synchronization IE or if a TSCH IE is malformed,
+    /// - any other error if the frame is truncated.
+    pub fn parse(mpdu: &'frame [u8]) -> Result<Self, FrameError> {
+        let frame_control = FrameControl::new(mpdu)?;
+        if frame_control.frame_type() != FrameType::Beacon
+            || !frame_control.information_elements_present()
+        {
+            return Err(FrameErrorKind::MalformedIe.into());
+        }
+        if frame_control.security_enabled() {
+            return Err(FrameErrorKind::SecurityNotSupported.into());
+        }
+
+        let mut offset = FRAME_CONTROL_LEN;
+        if !frame_control.sequence_number_suppression() {
+            offset += 1;
+        }
+
+        let addressing = AddressingRepr::from_frame_control(frame_control.clone())?
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?;
+        let length = addressing
+            .addressing_fields_length()
+            .map_err(|_| FrameErrorKind::InvalidAddressingCombination)?
+            as usize;
+        let addressing_fields = AddressingFields::new(field(mpdu, offset, length)?, addressing)
+            .map_err(|e| e.shifted_by(offset))?;
+        let pan_id = addressing_fields
+            .dst_pan_id()
+            .or(addressing_fields.src_pan_id())
+            .ok_or(FrameErrorKind::InvalidAddressingCombination)?
+            .into_u16();
+        let src_address = match addressing_fields.src_address() {
+            Some(address @ Address::Extended(_)) => {
+                let mut src_address = [0; 8];
+                src_address.copy_from_slice(address.as_le_bytes());
+                src_address
+            }
+            _ => return Err(FrameErrorKind::InvalidAddressingCombination.into()),
+        };
+        offset += length;
+
+        // Skip header IEs.
+        let mut header_ies = HeaderIes::new(&mpdu[offset..]);
+        for header_ie in header_ies.by_ref() {
+            header_ie.map_err(|e| e.shifted_by(offset))?;
+        }
+        if !header_ies.payload_ies_follow() {
+            return Err(FrameErrorKind::MalformedIe.into());
+        }
+        offset += header_ies.offset();
+
+        let mut synchronization = None;
+        let mut timeslot = None;
+        let mut hopping_sequence_id = None;
+        let mut slotframe_and_link = None;
+        for payload_ie in PayloadIes::new(header_ies.remaining()) {
+            let payload_ie = payload_ie.map_err(|e| e.shifted_by(offset))?;
+            let Some(nested_ies) = payload_ie.nested_ies() else {
+                continue;
+            };
+
+            for nested_ie in nested_ies {
+                // The IEs following a nested IE that exceeds the MLME IE cannot
+                // be located, but those preceding it and the other payload IEs
+                // are still valid, e.g. after a truncated vendor specific IE.
+                let Ok(nested_ie) = nested_ie else {
+                    break;
+                };
+                let malformed = |_| FrameError::from(FrameErrorKind::MalformedIe);
+                match (nested_ie.is_long_format(), nested_ie.sub_id()) {
+                    (false, TSCH_SYNCHRONIZATION_IE_SUB_ID) => {
+                        synchronization = Some(
+                            TschSynchronization::new(nested_ie.into_content())
+                                .map_err(malformed)?,
+                        );
+                    }
+                    (false, TSCH_TIMESLOT_IE_SUB_ID) => {
+                        timeslot =
+                            Some(TschTimeslot::new(nested_ie.into_content()).map_err(malformed)?);
+                    }
+                    (false, TSCH_SLOTFRAME_AND_LINK_IE_SUB_ID) => {
+                        slotframe_and_link = Some(
+                            TschSlotframeAndLink::new(nested_ie.into_content())
+                                .map_err(malformed)?,
+                        );
+                    }
+                    (true, CHANNEL_HOPPING_IE_SUB_ID) => {
+                        hopping_sequence_id = nested_ie.content().first().copied();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let synchronization = synchronization.ok_or(FrameErrorKind::MalformedIe)?;
+        Ok(Self {
+            pan_id,
+            src_address,
+            asn: synchronization.asn(),
+            join_metric: synchronization.join_metric(),
+            timeslot,
+            hopping_sequence_id,
+            slotframe_and_link,
+        })
+    }
+
+    /// Return the timeslot timings advertised by the EB, the default timings
+    /// if the EB does not contain a TSCH Timeslot IE.
+    pub fn timeslot_timings(&self) -> TschTimeslotTimings {
+        self.timeslot
+            .as_ref()
+            .map_or_else(TschTimeslotTimings::default, |timeslot| {
+                timeslot.timeslot_timings()
+            })
+    }
+}
+
+/// A frame received while scanning for EBs.
+pub struct ReceivedFrame<Timer: Frequency> {
+    /// The MPDU without FCS.
+    pub mpdu: FrameBuffer<PHY_MAX_PACKET_SIZE_127>,
+    /// The received signal strength in dBm.
+    pub rssi: i8,
+    /// The time at which the RMARKER of the frame passed the antenna.
+    pub timestamp: Instant<Timer>,
+}
+
+/// Configuration of the join process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoinConfig {
+    /// The hopping sequence whose channels are scanned.
+    pub hopping_sequence: HoppingSequence,
+    /// The time spent listening on each channel.
+    pub channel_dwell: Duration<Microseconds>,
+    /// The maximum number of scans over all channels.
+    pub scan_rounds: u8,
+    /// Only join the network with the given PAN ID, if any.
+    pub pan_id: Option<u16>,
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        Self {
+            hopping_sequence: HoppingSequence::default(),
+            channel_dwell: Duration::new(1_000_000),
+            scan_rounds: 4,
+            pan_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    /// No suitable EB was received.
+    NoBeacon,
+    /// The advertised schedule could not be installed.
+    Schedule(ScheduleError),
+}
+
+/// The outcome of a successful join.
+#[derive(Debug, PartialEq, Eq)]
+pub struct JoinResult<Timer: Frequency> {
+    /// The PAN ID of the joined network.
+    pub pan_id: u16,
+    /// The extended address of the time source neighbor (little endian).
+    pub time_source: [u8; 8],
+    /// The ASN of the timeslot in which the selected EB was received.
+    pub asn: AbsoluteSlotNumber,
+    /// The start of the timeslot in which the selected EB was received.
+    pub slot_start: Instant<Timer>,
+}
+
+/// An EB received during the scan that qualifies as time source.
+struct JoinCandidate<Timer: Frequency> {
+    frame: ReceivedFrame<Timer>,
+    join_metric: u8,
+}
+
+impl<Timer: Frequency> JoinCandidate<Timer> {
+    /// Return whether the candidate is preferred over the given one.
+    fn is_better_than(&self, other: &Self) -> bool {
+        (self.join_metric, -(self.frame.rssi as i16))
+            < (other.join_metric, -(other.frame.rssi as i16))
+    }
+}
+
+/// Installs the schedule advertised by the given EB, replacing all existing
+/// slotframes. If the EB does not advertise any slotframe, the minimal
+/// schedule (RFC 8180) is installed, i.e. a single slotframe of
+/// [`MINIMAL_SLOTFRAME_SIZE`] timeslots with one shared link in timeslot 0.
+///
+/// * `schedule` - Schedule to install the slotframes into
+/// * `eb` - EB advertising the schedule
+/// * `hopping_sequence` - Hopping sequence of the network
+pub fn install_schedule<const S: usize, const L: usize, T: MacNeighbor>(
+    schedule: &mut TschSchedule<S, L, T>,
+    eb: &EbInfo,
+    hopping_sequence: HoppingSequence,
+) -> Result<(), ScheduleError> {
+    schedule.clear();
+    schedule.set_timeslot_timings(eb.timeslot_timings());
+
+    let descriptors = eb
+        .slotframe_and_link
+        .as_ref()
+        .filter(|slotframe_and_link| slotframe_and_link.number_of_slotframes() > 0);
+    let Some(slotframe_and_link) = descriptors else {
+        let slotframe = minimal_slotframe(MINIMAL_SLOTFRAME_SIZE, hopping_sequence)?;
+        return schedule.add_slotframe(slotframe);
+    };
+
+    for descriptor in slotframe_and_link.slotframe_descriptors() {
+        let mut slotframe = TschSlotframe::new(
+            descriptor.handle() as u16,
+            descriptor.size(),
+            hopping_sequence,
+        );
+        for (handle, link) in descriptor.links().enumerate() {
+            slotframe.add_link(TschLink::new(
+                handle as u16,
+                link.timeslot(),
+                link.channel_offset(),
+                link.link_options(),
+                TschLinkType::Normal,
+                None,
+            ))?;
+        }
+        schedule.add_slotframe(slotframe)?;
+    }
+    Ok(())
+}
+
+/// Joins a TSCH network.
+///
+/// Passively scans the channels of the configured hopping sequence for EBs,
+/// selects the best time source and installs the advertised schedule. The
+/// schedule's ASN is set to the ASN of the timeslot in which the selected EB
+/// was received so that the schedule can be executed right away, see
+/// [`super::TschExecutor::new()`].
+///
+/// Each round listens on every channel for the configured dwell time,
+/// channels the radio cannot tune to are skipped. The scan ends after the
+/// first round in which a suitable EB was received.
+pub async fn join<
+    Timer: RadioTimerApi,
+    R: Radio<Timer = Timer>,
+    const S: usize,
+    const L: usize,
+    T: MacNeighbor,
+>(
+    radio: &mut MacRadio<R>,
+    schedule: &mut TschSchedule<S, L, T>,
+    config: &JoinConfig,
+) -> Result<JoinResult<Timer>, JoinError> {
+    let dwell = config
+        .channel_dwell
+        .convert_into_rounding_up::<Timer>()
+        .ticks();
+
+    for _ in 0..config.scan_rounds {
+        let mut best: Option<JoinCandidate<Timer>> = None;
+        let mut mpdu = FrameBuffer::new();
+        for channel in config.hopping_sequence.active_channels() {
+            if radio.set_channel(channel).is_err() {
+                continue;
+            }
+            let until = Timer::now().tick().saturating_add_signed(dwell);
+            while let Some(rx) = radio.receive_until(&mut mpdu, Instant::new(until)).await {
+                let Ok(eb) = EbInfo::parse(&mpdu) else {
+                    continue;
+                };
+                if config.pan_id.is_some_and(|pan_id| pan_id != eb.pan_id) {
+                    continue;
+                }
+                let join_metric = eb.join_metric;
+                let candidate = JoinCandidate {
+                    join_metric,
+                    frame: ReceivedFrame {
+                        mpdu: core::mem::replace(&mut mpdu, FrameBuffer::new()),
+                        rssi: rx.rssi,
+                        timestamp: rx.timestamp.convert_into_rounding_down(),
+                    },
+                };
+                if best
+                    .as_ref()
+                    .is_none_or(|best| candidate.is_better_than(best))
+                {
+                    best = Some(candidate);
+                }
+            }
+        }
+
+        if let Some(candidate) = best {
+            return synchronize(schedule, config, candidate).map_err(JoinError::Schedule);
+        }
+    }
+
+    Err(JoinError::NoBeacon)
+}
+
+/// Synchronizes to the given time source and installs its schedule.
+fn synchronize<Timer: RadioTimerApi, const S: usize, const L: usize, T: MacNeighbor>(
+    schedule: &mut TschSchedule<S, L, T>,
+    config: &JoinConfig,
+    candidate: JoinCandidate<Timer>,
+) -> Result<JoinResult<Timer>, ScheduleError> {
+    // Safety: The EB was successfully parsed when selecting the candidate.
+    let eb = EbInfo::parse(&candidate.frame.mpdu).unwrap();
+    install_schedule(schedule, &eb, config.hopping_sequence)?;
+
+    // Safety: The ASN has been decoded from 5 bytes.
+    let asn = AbsoluteSlotNumber::try_from(eb.asn.as_u64() as i64).unwrap();
+    schedule.set_asn(asn);
+    schedule.set_join_metric(eb.join_metric as u16 + 1);
+
+    // The RMARKER of an EB passes the antenna at the TX offset.
+    let tx_offset = schedule
+        .timeslot_timings()
+        .tx_offset()
+        .convert_into_rounding_down::<Timer>()
+        .ticks();
+    let slot_start = candidate
+        .frame
+        .timestamp
+        .tick()
+        .saturating_add_signed(-tx_offset);
+
+    Ok(JoinResult {
+        pan_id: eb.pan_id,
+        time_source: eb.src_address,
+        asn,
+        slot_start: Instant::new(slot_start),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        frame::fields::TschLinkOption, neighbors::tests::TestNeighbor, tsch::eb::EbGenerator,
+    };
+
+    use super::*;
+
+    const SRC_ADDRESS: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn asn(asn: u32) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    #[test]
+    fn parse_eb() {
+        let mut coordinator = TschSchedule::<1, 2, TestNeighbor>::new();
+        let mut slotframe = TschSlotframe::new(2, 11, HoppingSequence::DEFAULT_4_4);
+        for (handle, timeslot) in [(0, 0), (1, 5)] {
+            assert!(slotframe
+                .add_link(TschLink::new(
+                    handle,
+                    timeslot,
+                    1,
+                    TschLinkOption::Rx,
+                    TschLinkType::Normal,
+                    None,
+                ))
+                .is_ok());
+        }
+        assert!(coordinator.add_slotframe(slotframe).is_ok());
+
+        let mut generator = EbGenerator::new(0xabcd, SRC_ADDRESS);
+        generator.set_join_metric(2);
+        let mpdu = generator.generate(&coordinator, asn(4242)).unwrap();
+
+        let eb = EbInfo::parse(&mpdu).unwrap();
+        assert_eq!(eb.pan_id, 0xabcd);
+        assert_eq!(eb.src_address, SRC_ADDRESS);
+        assert_eq!(eb.asn.as_u64(), 4242);
+        assert_eq!(eb.join_metric, 2);
+        assert_eq!(eb.hopping_sequence_id, Some(0));
+        assert_eq!(eb.timeslot.as_ref().map(|timeslot| timeslot.id()), Some(0));
+
+        let mut schedule = TschSchedule::<2, 2, TestNeighbor>::new();
+        assert!(install_schedule(&mut schedule, &eb, HoppingSequence::DEFAULT_4_4).is_ok());
+        assert_eq!(schedule.slotframes().len(), 1);
+        let slotframe = schedule.slotframe(2).unwrap();
+        assert_eq!(slotframe.size(), 11);
+        assert_eq!(slotframe.links().len(), 2);
+        assert_eq!(slotframe.links()[1].timeslot(), 5);
+        assert_eq!(slotframe.links()[1].channel_offset(), 1);
+
+        // Not an EB.
+        assert!(EbInfo::parse(&mpdu[..14]).is_err());
+        assert!(EbInfo::parse(&[0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff]).is_err());
+    }
+
+    #[test]
+    fn parse_eb_with_truncated_vendor_ie() {
+        // 6TiSCH minimal EB whose MLME IE ends with a Vendor Specific nested IE
+        // announcing more content than present.
+        let mpdu = [
+            0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
+            0x00, 0x3f, 0x1f, 0x88, 0x06, 0x1a, 0x4f, 0x2a, 0x01, 0x00, 0x00, 0x00, 0x01, 0x1c,
+            0x00, 0x01, 0xc8, 0x00, 0x0a, 0x1b, 0x01, 0x00, 0x65, 0x00, 0x01, 0x00, 0x00, 0x00,
+            0x00, 0x0f, 0x0a, 0xc0, 0x01, 0x02, 0x03,
+        ];
+        let eb = EbInfo::parse(&mpdu).unwrap();
+        assert_eq!(eb.pan_id, 0xabcd);
+        assert_eq!(eb.asn.as_u64(), 0x01_2a4f);
+        assert_eq!(eb.hopping_sequence_id, Some(0));
+        assert!(eb.slotframe_and_link.is_some());
+
+        // With Header Termination 2 IE, there are no payload IEs.
+        let mut mpdu = mpdu;
+        mpdu[14] = 0x80;
+        assert!(EbInfo::parse(&mpdu).is_err());
+    }
+
+    #[test]
+    fn parse_eb_with_short_source_address() {
+        // The 6TiSCH minimal EB above, sent from the short address 0xd9c7.
+        let mpdu = [
+            0x40, 0xab, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0x00, 0x3f, 0x1f, 0x88, 0x06, 0x1a,
+            0x4f, 0x2a, 0x01, 0x00, 0x00, 0x00, 0x01, 0x1c, 0x00, 0x01, 0xc8, 0x00, 0x0a, 0x1b,
+            0x01, 0x00, 0x65, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x0a, 0xc0, 0x01, 0x02,
+            0x03,
+        ];
+        assert_eq!(
+            EbInfo::parse(&mpdu).unwrap_err().kind(),
+            FrameErrorKind::InvalidAddressingCombination
+        );
+    }
+
+    #[test]
+    fn minimal_schedule() {
+        let coordinator = TschSchedule::<1, 1, TestNeighbor>::new();
+        let mpdu = EbGenerator::new(0xabcd, SRC_ADDRESS)
+            .generate(&coordinator, asn(0))
+            .unwrap();
+        let eb = EbInfo::parse(&mpdu).unwrap();
+
+        let mut schedule = TschSchedule::<1, 1, TestNeighbor>::new();
+        assert!(install_schedule(&mut schedule, &eb, HoppingSequence::DEFAULT_4_4).is_ok());
+        let slotframe = schedule.slotframe(0).unwrap();
+        assert_eq!(slotframe.size(), MINIMAL_SLOTFRAME_SIZE);
+        assert_eq!(slotframe.links().len(), 1);
+        assert!(slotframe.links()[0]
+            .link_options()
+            .contains(TschLinkOption::Shared | TschLinkOption::TimeKeeping));
+    }
+
+    #[test]
+    fn candidate_ranking() {
+        let candidate = |join_metric, rssi| JoinCandidate::<Microseconds> {
+            frame: ReceivedFrame {
+                mpdu: FrameBuffer::new(),
+                rssi,
+                timestamp: Instant::new(0),
+            },
+            join_metric,
+        };
+
+        assert!(candidate(0, -90).is_better_than(&candidate(1, -40)));
+        assert!(candidate(1, -40).is_better_than(&candidate(1, -60)));
+        assert!(!candidate(1, -60).is_better_than(&candidate(1, -60)));
+        assert!(!candidate(2, -20).is_better_than(&candidate(1, -60)));
+    }
+}
@@ -1,6 +1,46 @@
 #![allow(unused_imports)]
 pub mod asn;
+pub mod clock;
+pub mod config;
+#[cfg(feature = "ies")]
+pub mod eb;
+pub mod executor;
+pub mod hopping;
+#[cfg(feature = "ies")]
+pub mod join;
+#[cfg(feature = "ies")]
+pub mod msf;
+pub mod orchestra;
+pub mod queue;
 pub mod schedule;
+#[cfg(feature = "security")]
+pub mod security;
+#[cfg(feature = "ies")]
+pub mod sixp;
+pub mod sync;
+pub mod syntonization;
+pub mod time_source;
+pub mod timeslot;
 
 pub use asn::AbsoluteSlotNumber;
-pub use schedule::{TschHoppingSequence, TschLink, TschSchedule, TschSlotframe};
+pub use clock::{TickCorrection, VirtualClock};
+pub use config::TschConfig;
+#[cfg(feature = "ies")]
+pub use eb::{EbGenerator, EnhancedBeacon};
+pub use executor::TschExecutor;
+pub use hopping::{HoppingSequence, HoppingSequenceError};
+#[cfg(feature = "ies")]
+pub use join::{join, JoinConfig, JoinError, JoinResult};
+#[cfg(feature = "ies")]
+pub use msf::{Msf, MSF_SFID};
+pub use orchestra::{Orchestra, OrchestraRule, OrchestraSlotframe};
+pub use queue::{TschDestination, TschQueueError, TschQueuedFrame, TschQueues};
+pub use schedule::{ScheduleError, TschActiveCells, TschLink, TschSchedule, TschSlotframe};
+#[cfg(feature = "security")]
+pub use security::{AsnReplayFilter, CcmNonce, SecurityError};
+#[cfg(feature = "ies")]
+pub use sixp::{SixpError, SixpEvent, SixpMessage, SixpTransactions};
+pub use sync::{keep_alive_frame, SyncAction, TschSyncMonitor};
+pub use syntonization::{NoSyntonization, PiController, Ppm, Syntonization};
+pub use time_source::{TimeSourceNeighbor, TimeSourceNeighbors};
+pub use timeslot::{TimeslotResult, TimeslotRole, TschTimeslot};
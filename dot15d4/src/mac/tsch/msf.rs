@@ -0,0 +1,874 @@
+//! Minimal Scheduling Function (MSF, RFC 9033).
+//!
+//! MSF builds an adaptive schedule on top of 6P. It uses a single slotframe,
+//! distinct from the minimal schedule, containing:
+//! - Autonomous cells, whose position is derived from the EUI-64 of the node
+//!   with the SAX hash: every node listens in its autonomous Rx cell and sends
+//!   to its parent in the parent's autonomous cell, without any negotiation.
+//! - Negotiated cells, added and deleted with the parent through 6P depending
+//!   on their utilization.
+//!
+//! The utilization of the negotiated cells with the parent is tracked per
+//! direction: after [`MAX_NUM_CELLS`] cells elapsed, a cell is added if more
+//! than [`LIM_NUMCELLSUSED_HIGH`] were used and deleted if less than
+//! [`LIM_NUMCELLSUSED_LOW`] were used.
+#![allow(dead_code)]
+
+use rand_core::RngCore;
+
+use crate::mac::{frame::fields::TschLinkOption, neighbors::MacNeighbor};
+
+use super::{
+    asn::AbsoluteSlotNumber,
+    hopping::HoppingSequence,
+    schedule::{ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe},
+    sixp::{
+        SixpCell, SixpCells, SixpCommand, SixpEvent, SixpMessage, SixpRequest, SixpResponseFields,
+        SixpReturnCode, SixpTransactions,
+    },
+};
+
+/// The scheduling function identifier of MSF.
+pub const MSF_SFID: u8 = 0;
+
+/// The handle of the slotframe used by MSF.
+pub const MSF_SLOTFRAME_HANDLE: u16 = 1;
+
+/// The length of the slotframe used by MSF.
+pub const SLOTFRAME_LENGTH: u16 = 101;
+
+/// The number of channel offsets used by MSF.
+pub const NUM_CH_OFFSET: u16 = 16;
+
+/// The number of elapsed cells after which the utilization is evaluated.
+pub const MAX_NUM_CELLS: u16 = 100;
+
+/// The number of used cells above which a cell is added.
+pub const LIM_NUMCELLSUSED_HIGH: u16 = 75;
+
+/// The number of used cells below which a cell is deleted.
+pub const LIM_NUMCELLSUSED_LOW: u16 = 25;
+
+/// The number of candidate cells proposed in ADD requests.
+pub const CELL_LIST_SIZE: usize = 5;
+
+/// Return the SAX hash of the given bytes, modulo `table_size` (RFC 9033,
+/// section 3).
+pub fn sax(table_size: u16, bytes: &[u8]) -> u16 {
+    let hash = bytes.iter().fold(0u32, |h, &c| {
+        h ^ h.wrapping_add(h >> 1).wrapping_add(c as u32)
+    });
+    (hash % table_size as u32) as u16
+}
+
+/// Return the autonomous cell of the node with the given address.
+///
+/// * `address` - Extended address of the node (little endian)
+pub fn autonomous_cell(address: &[u8; 8]) -> SixpCell {
+    let mut eui64 = *address;
+    eui64.reverse();
+    SixpCell {
+        slot_offset: 1 + sax(SLOTFRAME_LENGTH - 1, &eui64),
+        channel_offset: sax(NUM_CH_OFFSET, &eui64),
+    }
+}
+
+/// Utilization counters of the negotiated cells in one direction.
+#[derive(Debug, Default, Clone, Copy)]
+struct CellUsage {
+    elapsed: u16,
+    used: u16,
+}
+
+impl CellUsage {
+    /// Record an elapsed cell and return the resulting adaptation, if any.
+    fn on_cell_elapsed(&mut self, used: bool) -> Option<SixpCommand> {
+        self.elapsed += 1;
+        if used {
+            self.used += 1;
+        }
+        if self.elapsed < MAX_NUM_CELLS {
+            return None;
+        }
+
+        let used = self.used;
+        *self = Self::default();
+        if used > LIM_NUMCELLSUSED_HIGH {
+            Some(SixpCommand::Add)
+        } else if used < LIM_NUMCELLSUSED_LOW {
+            Some(SixpCommand::Delete)
+        } else {
+            None
+        }
+    }
+}
+
+/// A cell negotiated with a neighbor.
+#[derive(Debug, Clone, Copy)]
+struct NegotiatedCell {
+    neighbor: [u8; 8],
+    cell: SixpCell,
+    link_options: TschLinkOption,
+    link_handle: u16,
+}
+
+/// The Minimal Scheduling Function, with 6P transactions with up to `N`
+/// neighbors and up to `C` negotiated cells.
+pub struct Msf<const N: usize, const C: usize> {
+    /// Extended address of the node (little endian).
+    address: [u8; 8],
+    /// Extended address of the preferred parent (little endian).
+    parent: Option<[u8; 8]>,
+    /// Former parent with which the negotiated cells must be cleared.
+    pending_clear: Option<[u8; 8]>,
+    /// Pending adaptation of the negotiated cells with the parent.
+    pending: Option<(SixpCommand, TschLinkOption)>,
+    /// Cell options of the request in progress with the parent.
+    request_options: TschLinkOption,
+    tx_usage: CellUsage,
+    rx_usage: CellUsage,
+    transactions: SixpTransactions<N>,
+    cells: heapless::Vec<NegotiatedCell, C>,
+    /// Cell list of the last message.
+    cell_list: heapless::Vec<SixpCell, CELL_LIST_SIZE>,
+}
+
+impl<const N: usize, const C: usize> Msf<N, C> {
+    /// Creates a new [`Msf`].
+    ///
+    /// * `address` - Extended address of the node (little endian)
+    pub fn new(address: [u8; 8]) -> Self {
+        Self {
+            address,
+            parent: None,
+            pending_clear: None,
+            pending: None,
+            request_options: TschLinkOption::empty(),
+            tx_usage: CellUsage::default(),
+            rx_usage: CellUsage::default(),
+            transactions: SixpTransactions::default(),
+            cells: heapless::Vec::new(),
+            cell_list: heapless::Vec::new(),
+        }
+    }
+
+    /// Return the preferred parent.
+    pub fn parent(&self) -> Option<&[u8; 8]> {
+        self.parent.as_ref()
+    }
+
+    /// Return the number of cells negotiated with the given neighbor and
+    /// link options.
+    pub fn num_cells(&self, neighbor: &[u8; 8], link_options: TschLinkOption) -> usize {
+        self.negotiated(neighbor, link_options).count()
+    }
+
+    /// Add the MSF slotframe and the autonomous Rx cell to the schedule.
+    ///
+    /// * `schedule` - The schedule
+    /// * `hopping_sequence` - Hopping sequence of the network
+    pub fn install<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        schedule: &mut TschSchedule<S, L, T>,
+        hopping_sequence: HoppingSequence,
+    ) -> Result<(), ScheduleError> {
+        if schedule.slotframe(MSF_SLOTFRAME_HANDLE).is_none() {
+            schedule.add_slotframe(TschSlotframe::new(
+                MSF_SLOTFRAME_HANDLE,
+                SLOTFRAME_LENGTH,
+                hopping_sequence,
+            ))?;
+        }
+        let cell = autonomous_cell(&self.address);
+        add_link(schedule, &cell, TschLinkOption::Rx, None)?;
+        Ok(())
+    }
+
+    /// Select the preferred parent.
+    ///
+    /// The autonomous Tx cell moves to the new parent, one negotiated Tx cell
+    /// is requested from it and the cells negotiated with the former parent
+    /// are cleared.
+    ///
+    /// * `parent` - Extended address of the new parent (little endian)
+    /// * `schedule` - The schedule
+    pub fn set_parent<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        parent: [u8; 8],
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Result<(), ScheduleError> {
+        if self.parent == Some(parent) {
+            return Ok(());
+        }
+        if let Some(former) = self.parent.replace(parent) {
+            let cell = autonomous_cell(&former);
+            remove_link(schedule, &cell);
+            if self
+                .negotiated(&former, TschLinkOption::all())
+                .next()
+                .is_some()
+            {
+                self.pending_clear = Some(former);
+            }
+        }
+
+        self.tx_usage = CellUsage::default();
+        self.rx_usage = CellUsage::default();
+        self.pending = Some((SixpCommand::Add, TschLinkOption::Tx));
+        let cell = autonomous_cell(&parent);
+        add_link(
+            schedule,
+            &cell,
+            TschLinkOption::Tx | TschLinkOption::Shared,
+            Some(parent),
+        )?;
+        Ok(())
+    }
+
+    /// Record an elapsed cell. Only negotiated dedicated cells with the parent
+    /// are taken into account.
+    ///
+    /// * `neighbor` - Neighbor of the link of the cell
+    /// * `link_options` - Options of the link of the cell
+    /// * `used` - Whether a frame was sent or received in the cell
+    pub fn on_cell_elapsed(
+        &mut self,
+        neighbor: &[u8; 8],
+        link_options: TschLinkOption,
+        used: bool,
+    ) {
+        if self.parent.as_ref() != Some(neighbor) {
+            return;
+        }
+        let usage = if link_options == TschLinkOption::Tx {
+            &mut self.tx_usage
+        } else if link_options == TschLinkOption::Rx {
+            &mut self.rx_usage
+        } else {
+            return;
+        };
+
+        match usage.on_cell_elapsed(used) {
+            // The last negotiated cell is kept.
+            Some(SixpCommand::Delete) if self.num_cells(neighbor, link_options) <= 1 => (),
+            Some(command) => self.pending = Some((command, link_options)),
+            None => (),
+        }
+    }
+
+    /// Return the next 6P request to send, if any, along with the neighbor to
+    /// send it to.
+    ///
+    /// * `asn` - Current absolute slot number
+    /// * `schedule` - The schedule, to select free candidate cells
+    /// * `rng` - Random number generator, to select candidate cells
+    pub fn poll<const S: usize, const L: usize, T: MacNeighbor, Rng: RngCore>(
+        &mut self,
+        asn: AbsoluteSlotNumber,
+        schedule: &TschSchedule<S, L, T>,
+        rng: &mut Rng,
+    ) -> Option<([u8; 8], SixpMessage<'_>)> {
+        while self.transactions.poll_timeout(asn).is_some() {}
+
+        if let Some(former) = self.pending_clear {
+            if !self.transactions.is_busy(&former) {
+                self.pending_clear = None;
+                let request = SixpRequest::Clear { metadata: 0 };
+                let message = self.transactions.request(&former, MSF_SFID, request, asn);
+                return message.ok().map(|message| (former, message));
+            }
+        }
+
+        let parent = self.parent?;
+        let (command, link_options) = self.pending?;
+        if self.transactions.is_busy(&parent) {
+            return None;
+        }
+
+        self.cell_list.clear();
+        if command == SixpCommand::Add {
+            self.select_free_cells(schedule, rng);
+        } else {
+            for cell in self
+                .cells
+                .iter()
+                .filter(|c| c.neighbor == parent && c.link_options == link_options)
+                .take(CELL_LIST_SIZE)
+            {
+                let _ = self.cell_list.push(cell.cell);
+            }
+        }
+        if self.cell_list.is_empty() {
+            return None;
+        }
+
+        self.pending = None;
+        self.request_options = link_options;
+        let cells = SixpCells::Cells(&self.cell_list);
+        let request = if command == SixpCommand::Add {
+            SixpRequest::Add {
+                metadata: 0,
+                cell_options: link_options,
+                num_cells: 1,
+                cells,
+            }
+        } else {
+            SixpRequest::Delete {
+                metadata: 0,
+                cell_options: link_options,
+                num_cells: 1,
+                cells,
+            }
+        };
+        let message = self.transactions.request(&parent, MSF_SFID, request, asn);
+        message.ok().map(|message| (parent, message))
+    }
+
+    /// Process a 6P message received from the given neighbor and return the
+    /// message to answer with, if any.
+    ///
+    /// Negotiated cells are added to or removed from the schedule.
+    ///
+    /// * `neighbor` - Extended address of the sender (little endian)
+    /// * `message` - The received message
+    /// * `asn` - Current absolute slot number
+    /// * `schedule` - The schedule
+    pub fn on_message<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        neighbor: &[u8; 8],
+        message: SixpMessage,
+        asn: AbsoluteSlotNumber,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Option<([u8; 8], SixpMessage<'_>)> {
+        match self.transactions.on_message(neighbor, message, asn) {
+            SixpEvent::Request { sfid, request } => {
+                let (code, fields) = if sfid != MSF_SFID {
+                    (SixpReturnCode::ErrSfid, SixpResponseFields::Empty)
+                } else {
+                    self.handle_request(neighbor, request, schedule)
+                };
+                let fields = match fields {
+                    SixpResponseFields::Cells(_) => {
+                        SixpResponseFields::Cells(SixpCells::Cells(&self.cell_list))
+                    }
+                    fields => fields,
+                };
+                let response = self.transactions.respond(neighbor, code, fields, asn);
+                response.ok().map(|response| (*neighbor, response))
+            }
+            SixpEvent::Response { command, response } => {
+                match (command, response.code) {
+                    (SixpCommand::Clear, _) | (_, SixpReturnCode::ErrSeqNum) => {
+                        self.remove_cells(neighbor, TschLinkOption::all(), schedule);
+                        if response.code == SixpReturnCode::ErrSeqNum {
+                            self.pending_clear = Some(*neighbor);
+                        }
+                    }
+                    (SixpCommand::Add, code) if code.is_success() => {
+                        for cell in response.fields.cells().iter().flat_map(|c| c.iter()) {
+                            if self
+                                .add_cell(neighbor, cell, self.request_options, schedule)
+                                .is_err()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    (SixpCommand::Delete, code) if code.is_success() => {
+                        for cell in response.fields.cells().iter().flat_map(|c| c.iter()) {
+                            self.remove_cell(neighbor, &cell, schedule);
+                        }
+                    }
+                    _ => (),
+                }
+                None
+            }
+            SixpEvent::Confirmation { .. } | SixpEvent::Ignored => None,
+            SixpEvent::Reject(response) => Some((*neighbor, response)),
+        }
+    }
+
+    /// Handle a request and return the return code and response fields. Cell
+    /// lists are returned in `self.cell_list`.
+    fn handle_request<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        neighbor: &[u8; 8],
+        request: SixpRequest,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> (SixpReturnCode, SixpResponseFields<'static>) {
+        let cells = SixpResponseFields::Cells(SixpCells::EMPTY);
+        self.cell_list.clear();
+
+        match request {
+            // MSF only uses two-step transactions.
+            _ if request.is_three_step() => (SixpReturnCode::Err, SixpResponseFields::Empty),
+            SixpRequest::Add {
+                cell_options,
+                num_cells,
+                cells: candidates,
+                ..
+            } => {
+                let link_options = invert(cell_options);
+                for cell in candidates.iter() {
+                    if self.cell_list.len() == num_cells as usize || self.cell_list.is_full() {
+                        break;
+                    }
+                    if is_free(schedule, &cell)
+                        && self
+                            .add_cell(neighbor, cell, link_options, schedule)
+                            .is_ok()
+                    {
+                        let _ = self.cell_list.push(cell);
+                    }
+                }
+                (SixpReturnCode::Success, cells)
+            }
+            SixpRequest::Delete {
+                cell_options,
+                num_cells,
+                cells: candidates,
+                ..
+            } => {
+                let link_options = invert(cell_options);
+                for cell in candidates.iter() {
+                    if self.cell_list.len() == num_cells as usize || self.cell_list.is_full() {
+                        break;
+                    }
+                    if self
+                        .negotiated(neighbor, link_options)
+                        .any(|c| c.cell == cell)
+                    {
+                        self.remove_cell(neighbor, &cell, schedule);
+                        let _ = self.cell_list.push(cell);
+                    }
+                }
+                if self.cell_list.is_empty() {
+                    (SixpReturnCode::ErrCellList, SixpResponseFields::Empty)
+                } else {
+                    (SixpReturnCode::Success, cells)
+                }
+            }
+            SixpRequest::Relocate {
+                cell_options,
+                num_cells,
+                relocation_cells,
+                candidate_cells,
+                ..
+            } => {
+                let link_options = invert(cell_options);
+                if relocation_cells.len() != num_cells as usize
+                    || !relocation_cells.iter().all(|cell| {
+                        self.negotiated(neighbor, link_options)
+                            .any(|c| c.cell == cell)
+                    })
+                {
+                    return (SixpReturnCode::ErrCellList, SixpResponseFields::Empty);
+                }
+                let mut relocated = relocation_cells.iter();
+                for cell in candidate_cells.iter() {
+                    if self.cell_list.len() == num_cells as usize || self.cell_list.is_full() {
+                        break;
+                    }
+                    if !is_free(schedule, &cell) {
+                        continue;
+                    }
+                    let Some(relocated) = relocated.next() else {
+                        break;
+                    };
+                    self.remove_cell(neighbor, &relocated, schedule);
+                    if self
+                        .add_cell(neighbor, cell, link_options, schedule)
+                        .is_ok()
+                    {
+                        let _ = self.cell_list.push(cell);
+                    }
+                }
+                (SixpReturnCode::Success, cells)
+            }
+            SixpRequest::Count { cell_options, .. } => {
+                let count = self.num_cells(neighbor, invert(cell_options));
+                (
+                    SixpReturnCode::Success,
+                    SixpResponseFields::NumCells(count as u16),
+                )
+            }
+            SixpRequest::List {
+                cell_options,
+                offset,
+                max_num_cells,
+                ..
+            } => {
+                let link_options = invert(cell_options);
+                let mut listed = self
+                    .cells
+                    .iter()
+                    .filter(|c| &c.neighbor == neighbor && c.link_options == link_options)
+                    .skip(offset as usize);
+                for cell in listed
+                    .by_ref()
+                    .take((max_num_cells as usize).min(CELL_LIST_SIZE))
+                {
+                    let _ = self.cell_list.push(cell.cell);
+                }
+                let code = if listed.next().is_some() {
+                    SixpReturnCode::Success
+                } else {
+                    SixpReturnCode::Eol
+                };
+                (code, cells)
+            }
+            SixpRequest::Clear { .. } => {
+                self.remove_cells(neighbor, TschLinkOption::all(), schedule);
+                (SixpReturnCode::Success, SixpResponseFields::Empty)
+            }
+            SixpRequest::Signal { .. } => (SixpReturnCode::Err, SixpResponseFields::Empty),
+        }
+    }
+
+    /// Select up to [`CELL_LIST_SIZE`] random free cells into
+    /// `self.cell_list`.
+    fn select_free_cells<const S: usize, const L: usize, T: MacNeighbor, Rng: RngCore>(
+        &mut self,
+        schedule: &TschSchedule<S, L, T>,
+        rng: &mut Rng,
+    ) {
+        for _ in 0..4 * CELL_LIST_SIZE {
+            if self.cell_list.is_full() {
+                break;
+            }
+            let cell = SixpCell {
+                slot_offset: 1 + (rng.next_u32() % (SLOTFRAME_LENGTH as u32 - 1)) as u16,
+                channel_offset: (rng.next_u32() % NUM_CH_OFFSET as u32) as u16,
+            };
+            if is_free(schedule, &cell)
+                && !self
+                    .cell_list
+                    .iter()
+                    .any(|c| c.slot_offset == cell.slot_offset)
+            {
+                let _ = self.cell_list.push(cell);
+            }
+        }
+    }
+
+    fn negotiated<'c>(
+        &'c self,
+        neighbor: &'c [u8; 8],
+        link_options: TschLinkOption,
+    ) -> impl Iterator<Item = &'c NegotiatedCell> {
+        self.cells
+            .iter()
+            .filter(move |c| &c.neighbor == neighbor && link_options.contains(c.link_options))
+    }
+
+    fn add_cell<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        neighbor: &[u8; 8],
+        cell: SixpCell,
+        link_options: TschLinkOption,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Result<(), ScheduleError> {
+        if self.cells.is_full() {
+            return Err(ScheduleError::CapacityExceeded);
+        }
+        let link_handle = add_link(schedule, &cell, link_options, Some(*neighbor))?;
+        let _ = self.cells.push(NegotiatedCell {
+            neighbor: *neighbor,
+            cell,
+            link_options,
+            link_handle,
+        });
+        Ok(())
+    }
+
+    fn remove_cell<const S: usize, const L: usize, T: MacNeighbor>(
+        &mut self,
+        neighbor: &[u8; 8],
+        cell: &SixpCell,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) {
+        if let Some(index) = self
+            .cells
+            .iter()
+            .position(|c| &c.neighbor == neighbor && &c.cell == cell)
+        {
+            let removed = self.cells.swap_remove(index);
+            let _ = schedule.delete_link(MSF_SLOTFRAME_HANDLE, removed.link_handle);
+        }
+    }
+
+    fn remove_cells<const S: usize, const L: usize, T: MacNeighbor>(
+        &mut self,
+        neighbor: &[u8; 8],
+        link_options: TschLinkOption,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) {
+        loop {
+            let Some(cell) = self
+                .negotiated(neighbor, link_options)
+                .next()
+                .map(|c| c.cell)
+            else {
+                break;
+            };
+            self.remove_cell(neighbor, &cell, schedule);
+        }
+    }
+}
+
+/// Return the cell options from the point of view of the neighbor.
+fn invert(cell_options: TschLinkOption) -> TschLinkOption {
+    let mut inverted = cell_options & TschLinkOption::Shared;
+    inverted.set(
+        TschLinkOption::Rx,
+        cell_options.contains(TschLinkOption::Tx),
+    );
+    inverted.set(
+        TschLinkOption::Tx,
+        cell_options.contains(TschLinkOption::Rx),
+    );
+    inverted
+}
+
+/// Return whether the timeslot of the cell is unused in the MSF slotframe.
+fn is_free<const S: usize, const L: usize, T: MacNeighbor>(
+    schedule: &TschSchedule<S, L, T>,
+    cell: &SixpCell,
+) -> bool {
+    schedule
+        .slotframe(MSF_SLOTFRAME_HANDLE)
+        .is_some_and(|slotframe| {
+            cell.slot_offset < slotframe.size()
+                && !slotframe
+                    .links()
+                    .iter()
+                    .any(|link| link.timeslot() == cell.slot_offset)
+        })
+}
+
+/// Add a link for the given cell to the MSF slotframe and return its handle.
+fn add_link<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+    schedule: &mut TschSchedule<S, L, T>,
+    cell: &SixpCell,
+    link_options: TschLinkOption,
+    neighbor: Option<[u8; 8]>,
+) -> Result<u16, ScheduleError> {
+    let handle = schedule
+        .slotframe(MSF_SLOTFRAME_HANDLE)
+        .ok_or(ScheduleError::UnknownHandle)?
+        .free_link_handle()
+        .ok_or(ScheduleError::CapacityExceeded)?;
+    schedule.add_link(
+        MSF_SLOTFRAME_HANDLE,
+        TschLink::new(
+            handle,
+            cell.slot_offset,
+            cell.channel_offset,
+            link_options,
+            TschLinkType::Normal,
+            neighbor.map(T::from),
+        ),
+    )?;
+    Ok(handle)
+}
+
+/// Remove the link of the given cell from the MSF slotframe.
+fn remove_link<const S: usize, const L: usize, T: MacNeighbor>(
+    schedule: &mut TschSchedule<S, L, T>,
+    cell: &SixpCell,
+) {
+    let handle = schedule
+        .slotframe(MSF_SLOTFRAME_HANDLE)
+        .and_then(|slotframe| {
+            slotframe.links().iter().find(|link| {
+                link.timeslot() == cell.slot_offset && link.channel_offset() == cell.channel_offset
+            })
+        })
+        .map(|link| link.handle());
+    if let Some(handle) = handle {
+        let _ = schedule.delete_link(MSF_SLOTFRAME_HANDLE, handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        neighbors::tests::TestNeighbor, test_helpers::XorshiftRng, tsch::sixp::SixpBody,
+    };
+
+    use super::*;
+
+    const CHILD: [u8; 8] = [1, 0, 0, 0, 0, 0, 0, 0];
+    const PARENT: [u8; 8] = [2, 0, 0, 0, 0, 0, 0, 0];
+    const OTHER_PARENT: [u8; 8] = [3, 0, 0, 0, 0, 0, 0, 0];
+
+    type Schedule = TschSchedule<2, 16, TestNeighbor>;
+
+    fn asn(asn: u32) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    /// Send a message over the air.
+    fn transfer<'b>(message: &SixpMessage, buffer: &'b mut [u8; 127]) -> SixpMessage<'b> {
+        let len = message.emit(buffer).unwrap();
+        SixpMessage::parse(&buffer[..len]).unwrap()
+    }
+
+    /// Run the next transaction of the child with its parent.
+    fn transaction(
+        child: &mut Msf<4, 8>,
+        child_schedule: &mut Schedule,
+        parent: &mut Msf<4, 8>,
+        parent_schedule: &mut Schedule,
+        rng: &mut XorshiftRng,
+    ) -> Option<SixpCommand> {
+        let mut buffer = [0; 127];
+        let (dst, request) = child.poll(asn(10), child_schedule, rng)?;
+        let SixpBody::Request(body) = request.body else {
+            panic!("not a request");
+        };
+        let command = body.command();
+        let request = transfer(&request, &mut buffer);
+
+        let (to, response) = parent
+            .on_message(&CHILD, request, asn(11), parent_schedule)
+            .unwrap();
+        assert_eq!(to, CHILD);
+        let mut response_buffer = [0; 127];
+        let response = transfer(&response, &mut response_buffer);
+        assert!(child
+            .on_message(&dst, response, asn(12), child_schedule)
+            .is_none());
+        Some(command)
+    }
+
+    fn schedules() -> (Msf<4, 8>, Schedule, Msf<4, 8>, Schedule) {
+        let mut child = Msf::new(CHILD);
+        let mut child_schedule = Schedule::new();
+        child
+            .install(&mut child_schedule, HoppingSequence::DEFAULT_16_16)
+            .unwrap();
+        let mut parent = Msf::new(PARENT);
+        let mut parent_schedule = Schedule::new();
+        parent
+            .install(&mut parent_schedule, HoppingSequence::DEFAULT_16_16)
+            .unwrap();
+        (child, child_schedule, parent, parent_schedule)
+    }
+
+    #[test]
+    fn autonomous_cells() {
+        let eui64 = [0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7];
+        assert_eq!(sax(100, &eui64), 97);
+        assert_eq!(sax(16, &eui64), 13);
+
+        let mut address = eui64;
+        address.reverse();
+        assert_eq!(
+            autonomous_cell(&address),
+            SixpCell {
+                slot_offset: 98,
+                channel_offset: 13
+            }
+        );
+
+        let (_, schedule, _, _) = schedules();
+        let links = schedule.slotframe(MSF_SLOTFRAME_HANDLE).unwrap().links();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].timeslot(), 2);
+        assert_eq!(links[0].channel_offset(), 1);
+        assert_eq!(links[0].link_options(), TschLinkOption::Rx);
+    }
+
+    #[test]
+    fn negotiation() {
+        let mut rng = XorshiftRng(0x1234_5678);
+        let (mut child, mut cs, mut parent, mut ps) = schedules();
+        child.set_parent(PARENT, &mut cs).unwrap();
+
+        // The autonomous Tx cell to the parent doesn't need any negotiation.
+        let links = cs.slotframe(MSF_SLOTFRAME_HANDLE).unwrap().links();
+        assert!(links.iter().any(|link| link.timeslot() == 3
+            && link.link_options() == TschLinkOption::Tx | TschLinkOption::Shared));
+
+        let command = transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng);
+        assert_eq!(command, Some(SixpCommand::Add));
+        assert_eq!(child.num_cells(&PARENT, TschLinkOption::Tx), 1);
+        assert_eq!(parent.num_cells(&CHILD, TschLinkOption::Rx), 1);
+
+        // Both ends installed the same cell in opposite directions.
+        let tx = cs.slotframe(MSF_SLOTFRAME_HANDLE).unwrap().links();
+        let tx = tx
+            .iter()
+            .find(|link| link.link_options() == TschLinkOption::Tx)
+            .unwrap();
+        let rx = ps.slotframe(MSF_SLOTFRAME_HANDLE).unwrap().links();
+        let rx = rx
+            .iter()
+            .find(|link| link.timeslot() == tx.timeslot())
+            .unwrap();
+        assert_eq!(rx.channel_offset(), tx.channel_offset());
+        assert_eq!(rx.link_options(), TschLinkOption::Rx);
+        assert!(transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng).is_none());
+
+        // Changing parent clears the cells with the former parent first.
+        child.set_parent(OTHER_PARENT, &mut cs).unwrap();
+        let command = transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng);
+        assert_eq!(command, Some(SixpCommand::Clear));
+        assert_eq!(child.num_cells(&PARENT, TschLinkOption::all()), 0);
+        assert_eq!(parent.num_cells(&CHILD, TschLinkOption::all()), 0);
+        assert_eq!(ps.slotframe(MSF_SLOTFRAME_HANDLE).unwrap().links().len(), 1);
+
+        let (dst, message) = child.poll(asn(20), &cs, &mut rng).unwrap();
+        assert_eq!(dst, OTHER_PARENT);
+        assert!(matches!(
+            message.body,
+            SixpBody::Request(SixpRequest::Add { .. })
+        ));
+    }
+
+    #[test]
+    fn adaptation() {
+        let mut rng = XorshiftRng(0xdead_beef);
+        let (mut child, mut cs, mut parent, mut ps) = schedules();
+        child.set_parent(PARENT, &mut cs).unwrap();
+        transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng).unwrap();
+
+        // Cells of other neighbors or shared cells don't count.
+        for _ in 0..MAX_NUM_CELLS {
+            child.on_cell_elapsed(&OTHER_PARENT, TschLinkOption::Tx, true);
+            child.on_cell_elapsed(&PARENT, TschLinkOption::Tx | TschLinkOption::Shared, true);
+        }
+        assert!(transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng).is_none());
+
+        // High utilization.
+        for i in 0..MAX_NUM_CELLS {
+            child.on_cell_elapsed(&PARENT, TschLinkOption::Tx, i < 80);
+        }
+        let command = transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng);
+        assert_eq!(command, Some(SixpCommand::Add));
+        assert_eq!(child.num_cells(&PARENT, TschLinkOption::Tx), 2);
+        assert_eq!(parent.num_cells(&CHILD, TschLinkOption::Rx), 2);
+
+        // Medium utilization.
+        for i in 0..MAX_NUM_CELLS {
+            child.on_cell_elapsed(&PARENT, TschLinkOption::Tx, i < 50);
+        }
+        assert!(transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng).is_none());
+
+        // Low utilization.
+        for i in 0..MAX_NUM_CELLS {
+            child.on_cell_elapsed(&PARENT, TschLinkOption::Tx, i < 10);
+        }
+        let command = transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng);
+        assert_eq!(command, Some(SixpCommand::Delete));
+        assert_eq!(child.num_cells(&PARENT, TschLinkOption::Tx), 1);
+        assert_eq!(parent.num_cells(&CHILD, TschLinkOption::Rx), 1);
+
+        // The last cell is kept.
+        for _ in 0..MAX_NUM_CELLS {
+            child.on_cell_elapsed(&PARENT, TschLinkOption::Tx, false);
+        }
+        assert!(transaction(&mut child, &mut cs, &mut parent, &mut ps, &mut rng).is_none());
+    }
+}
@@ -0,0 +1,420 @@
+//! Orchestra-style autonomous scheduling.
+//!
+//! Orchestra derives the whole schedule from the addresses of the node and of
+//! its neighbors, without any signaling. Each slotframe follows one rule:
+//! - [`OrchestraRule::EnhancedBeacon`]: sender-based, every node advertises in
+//!   the timeslot derived from its address and listens in the timeslot of its
+//!   time source.
+//! - [`OrchestraRule::CommonShared`]: a single shared timeslot for broadcast
+//!   and unicast traffic without a dedicated link.
+//! - [`OrchestraRule::ReceiverBased`]: every node listens in the timeslot
+//!   derived from its address and sends to a neighbor in the timeslot of that
+//!   neighbor. Transmit links are shared since several senders contend.
+//! - [`OrchestraRule::SenderBased`]: every node sends in the timeslot derived
+//!   from its address and listens in the timeslots of its neighbors.
+//!
+//! Timeslots are derived like in Contiki-NG, from the last two bytes of the
+//! extended address (in big endian), so that Orchestra networks of both stacks
+//! compute the same schedule.
+#![allow(dead_code)]
+
+use crate::mac::{frame::fields::TschLinkOption, neighbors::MacNeighbor};
+
+use super::{
+    hopping::HoppingSequence,
+    schedule::{ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe},
+};
+
+/// Default length of the EB slotframe.
+pub const DEFAULT_EB_PERIOD: u16 = 397;
+
+/// Default length of the common shared slotframe.
+pub const DEFAULT_COMMON_SHARED_PERIOD: u16 = 31;
+
+/// Default length of the unicast slotframe.
+pub const DEFAULT_UNICAST_PERIOD: u16 = 17;
+
+/// The rule that populates an Orchestra slotframe, see the module
+/// documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrchestraRule {
+    EnhancedBeacon,
+    CommonShared,
+    ReceiverBased,
+    SenderBased,
+}
+
+/// The configuration of an Orchestra slotframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrchestraSlotframe {
+    /// Handle of the slotframe. Lower handles take precedence when links of
+    /// several slotframes fall in the same timeslot.
+    pub handle: u16,
+    /// Number of timeslots in the slotframe.
+    pub length: u16,
+    /// Channel offset of all the links of the slotframe.
+    pub channel_offset: u16,
+    /// The rule populating the slotframe.
+    pub rule: OrchestraRule,
+}
+
+impl OrchestraSlotframe {
+    /// Return the slotframes of the default Contiki-NG configuration: an EB
+    /// slotframe, a receiver-based unicast slotframe and a common shared
+    /// slotframe.
+    pub const fn defaults() -> [Self; 3] {
+        [
+            Self {
+                handle: 0,
+                length: DEFAULT_EB_PERIOD,
+                channel_offset: 0,
+                rule: OrchestraRule::EnhancedBeacon,
+            },
+            Self {
+                handle: 1,
+                length: DEFAULT_UNICAST_PERIOD,
+                channel_offset: 2,
+                rule: OrchestraRule::ReceiverBased,
+            },
+            Self {
+                handle: 2,
+                length: DEFAULT_COMMON_SHARED_PERIOD,
+                channel_offset: 1,
+                rule: OrchestraRule::CommonShared,
+            },
+        ]
+    }
+
+    /// Return the timeslot of the node with the given address in the
+    /// slotframe.
+    ///
+    /// * `address` - Extended address of the node (little endian)
+    pub fn timeslot(&self, address: &[u8; 8]) -> u16 {
+        match self.rule {
+            OrchestraRule::CommonShared => 0,
+            _ => u16::from_le_bytes([address[0], address[1]]) % self.length,
+        }
+    }
+}
+
+/// Orchestra scheduler with up to `R` slotframes, see the module
+/// documentation.
+pub struct Orchestra<const R: usize> {
+    /// Extended address of the node (little endian).
+    address: [u8; 8],
+    slotframes: heapless::Vec<OrchestraSlotframe, R>,
+    /// Extended address of the time source (little endian).
+    time_source: Option<[u8; 8]>,
+}
+
+impl<const R: usize> Orchestra<R> {
+    /// Creates a new [`Orchestra`] scheduler without any slotframe.
+    ///
+    /// * `address` - Extended address of the node (little endian)
+    pub fn new(address: [u8; 8]) -> Self {
+        Self {
+            address,
+            slotframes: heapless::Vec::new(),
+            time_source: None,
+        }
+    }
+
+    /// Return the configured slotframes.
+    pub fn slotframes(&self) -> &[OrchestraSlotframe] {
+        &self.slotframes
+    }
+
+    /// Return the time source.
+    pub fn time_source(&self) -> Option<&[u8; 8]> {
+        self.time_source.as_ref()
+    }
+
+    /// Add a slotframe to the configuration. Slotframes must be added before
+    /// the scheduler is installed.
+    ///
+    /// * `slotframe` - Configuration of the slotframe
+    pub fn add_slotframe(&mut self, slotframe: OrchestraSlotframe) -> Result<(), ScheduleError> {
+        if self.slotframes.iter().any(|s| s.handle == slotframe.handle) {
+            return Err(ScheduleError::HandleDuplicate);
+        }
+        self.slotframes
+            .push(slotframe)
+            .map_err(|_| ScheduleError::CapacityExceeded)
+    }
+
+    /// Add the configured slotframes and the links of the node to the
+    /// schedule.
+    ///
+    /// * `schedule` - The schedule
+    /// * `hopping_sequence` - Hopping sequence of the network
+    pub fn install<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &self,
+        schedule: &mut TschSchedule<S, L, T>,
+        hopping_sequence: HoppingSequence,
+    ) -> Result<(), ScheduleError> {
+        for slotframe in &self.slotframes {
+            schedule.add_slotframe(TschSlotframe::new(
+                slotframe.handle,
+                slotframe.length,
+                hopping_sequence,
+            ))?;
+
+            let (link_options, link_type) = match slotframe.rule {
+                OrchestraRule::EnhancedBeacon => (TschLinkOption::Tx, TschLinkType::Advertising),
+                OrchestraRule::CommonShared => (
+                    TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared,
+                    TschLinkType::Normal,
+                ),
+                OrchestraRule::ReceiverBased => (TschLinkOption::Rx, TschLinkType::Normal),
+                OrchestraRule::SenderBased => (TschLinkOption::Tx, TschLinkType::Normal),
+            };
+            add_link(
+                schedule,
+                slotframe,
+                slotframe.timeslot(&self.address),
+                link_options,
+                link_type,
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Change the time source, whose EBs are listened to.
+    ///
+    /// * `time_source` - Extended address of the time source (little endian)
+    /// * `schedule` - The schedule
+    pub fn set_time_source<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &mut self,
+        time_source: [u8; 8],
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Result<(), ScheduleError> {
+        if self.time_source == Some(time_source) {
+            return Ok(());
+        }
+        let former = self.time_source.replace(time_source);
+
+        for slotframe in self
+            .slotframes
+            .iter()
+            .filter(|s| s.rule == OrchestraRule::EnhancedBeacon)
+        {
+            if let Some(former) = former {
+                remove_links(schedule, slotframe, &former);
+            }
+            add_link(
+                schedule,
+                slotframe,
+                slotframe.timeslot(&time_source),
+                TschLinkOption::Rx | TschLinkOption::TimeKeeping,
+                TschLinkType::Advertising,
+                Some(time_source),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Add the links to communicate with a new neighbor.
+    ///
+    /// Fails with [`ScheduleError::CellDuplicate`] if the timeslot of the
+    /// neighbor is already used in a unicast slotframe, e.g. by the node itself
+    /// or another neighbor. Traffic to that neighbor then falls back to the
+    /// common shared slotframe.
+    ///
+    /// * `neighbor` - Extended address of the neighbor (little endian)
+    /// * `schedule` - The schedule
+    pub fn add_neighbor<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+        &self,
+        neighbor: [u8; 8],
+        schedule: &mut TschSchedule<S, L, T>,
+    ) -> Result<(), ScheduleError> {
+        for slotframe in &self.slotframes {
+            let link_options = match slotframe.rule {
+                OrchestraRule::ReceiverBased => TschLinkOption::Tx | TschLinkOption::Shared,
+                OrchestraRule::SenderBased => TschLinkOption::Rx,
+                _ => continue,
+            };
+            add_link(
+                schedule,
+                slotframe,
+                slotframe.timeslot(&neighbor),
+                link_options,
+                TschLinkType::Normal,
+                Some(neighbor),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Remove the links to communicate with a neighbor.
+    ///
+    /// * `neighbor` - Extended address of the neighbor (little endian)
+    /// * `schedule` - The schedule
+    pub fn remove_neighbor<const S: usize, const L: usize, T: MacNeighbor>(
+        &self,
+        neighbor: &[u8; 8],
+        schedule: &mut TschSchedule<S, L, T>,
+    ) {
+        for slotframe in self.slotframes.iter().filter(|s| {
+            matches!(
+                s.rule,
+                OrchestraRule::ReceiverBased | OrchestraRule::SenderBased
+            )
+        }) {
+            remove_links(schedule, slotframe, neighbor);
+        }
+    }
+}
+
+/// Add a link to the given slotframe.
+fn add_link<const S: usize, const L: usize, T: MacNeighbor + From<[u8; 8]>>(
+    schedule: &mut TschSchedule<S, L, T>,
+    slotframe: &OrchestraSlotframe,
+    timeslot: u16,
+    link_options: TschLinkOption,
+    link_type: TschLinkType,
+    neighbor: Option<[u8; 8]>,
+) -> Result<(), ScheduleError> {
+    let handle = schedule
+        .slotframe(slotframe.handle)
+        .ok_or(ScheduleError::UnknownHandle)?
+        .free_link_handle()
+        .ok_or(ScheduleError::CapacityExceeded)?;
+    schedule.add_link(
+        slotframe.handle,
+        TschLink::new(
+            handle,
+            timeslot,
+            slotframe.channel_offset,
+            link_options,
+            link_type,
+            neighbor.map(T::from),
+        ),
+    )
+}
+
+/// Remove the links of the given slotframe with the given neighbor.
+fn remove_links<const S: usize, const L: usize, T: MacNeighbor>(
+    schedule: &mut TschSchedule<S, L, T>,
+    slotframe: &OrchestraSlotframe,
+    neighbor: &[u8; 8],
+) {
+    while let Some(handle) = schedule
+        .slotframe(slotframe.handle)
+        .and_then(|s| {
+            s.links()
+                .iter()
+                .find(|link| link.neighbor().is_some_and(|n| &n.address() == neighbor))
+        })
+        .map(|link| link.handle())
+    {
+        let _ = schedule.delete_link(slotframe.handle, handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::neighbors::tests::TestNeighbor;
+
+    use super::*;
+
+    const NODE: [u8; 8] = [0x05, 0x01, 0, 0, 0, 0, 0, 0];
+    const PARENT: [u8; 8] = [0x0b, 0x00, 0, 0, 0, 0, 0, 0];
+    const CHILD: [u8; 8] = [0x22, 0x00, 0, 0, 0, 0, 0, 0];
+
+    fn orchestra() -> (Orchestra<3>, TschSchedule<3, 8, TestNeighbor>) {
+        let mut orchestra = Orchestra::new(NODE);
+        for slotframe in OrchestraSlotframe::defaults() {
+            orchestra.add_slotframe(slotframe).unwrap();
+        }
+        let mut schedule = TschSchedule::new();
+        orchestra
+            .install(&mut schedule, HoppingSequence::DEFAULT_4_4)
+            .unwrap();
+        (orchestra, schedule)
+    }
+
+    fn link(
+        schedule: &TschSchedule<3, 8, TestNeighbor>,
+        handle: u16,
+        timeslot: u16,
+    ) -> Option<&TschLink<TestNeighbor>> {
+        schedule
+            .slotframe(handle)
+            .unwrap()
+            .links()
+            .iter()
+            .find(|link| link.timeslot() == timeslot)
+    }
+
+    #[test]
+    fn timeslots() {
+        let [eb, unicast, shared] = OrchestraSlotframe::defaults();
+        // 0x0105 = 261
+        assert_eq!(eb.timeslot(&NODE), 261);
+        assert_eq!(unicast.timeslot(&NODE), 261 % 17);
+        assert_eq!(shared.timeslot(&NODE), 0);
+
+        let (mut orchestra, schedule) = orchestra();
+        assert_eq!(
+            orchestra.add_slotframe(OrchestraSlotframe::defaults()[0]),
+            Err(ScheduleError::HandleDuplicate)
+        );
+
+        let eb_link = link(&schedule, 0, 261).unwrap();
+        assert_eq!(eb_link.link_options(), TschLinkOption::Tx);
+        assert!(eb_link.neighbor().is_none());
+        let rx = link(&schedule, 1, 261 % 17).unwrap();
+        assert_eq!(rx.link_options(), TschLinkOption::Rx);
+        assert_eq!(rx.channel_offset(), 2);
+        let shared_link = link(&schedule, 2, 0).unwrap();
+        assert!(shared_link.link_options().contains(TschLinkOption::Shared));
+        assert_eq!(shared_link.channel_offset(), 1);
+    }
+
+    #[test]
+    fn neighbors() {
+        let (mut orchestra, mut schedule) = orchestra();
+
+        orchestra.set_time_source(PARENT, &mut schedule).unwrap();
+        let eb_rx = link(&schedule, 0, 11).unwrap();
+        assert_eq!(
+            eb_rx.link_options(),
+            TschLinkOption::Rx | TschLinkOption::TimeKeeping
+        );
+        assert_eq!(eb_rx.neighbor().unwrap().address(), PARENT);
+
+        orchestra.add_neighbor(PARENT, &mut schedule).unwrap();
+        orchestra.add_neighbor(CHILD, &mut schedule).unwrap();
+        let tx = link(&schedule, 1, 11).unwrap();
+        assert_eq!(
+            tx.link_options(),
+            TschLinkOption::Tx | TschLinkOption::Shared
+        );
+        assert_eq!(tx.neighbor().unwrap().address(), PARENT);
+        assert_eq!(
+            link(&schedule, 1, 0x22 % 17)
+                .unwrap()
+                .neighbor()
+                .unwrap()
+                .address(),
+            CHILD
+        );
+
+        // 0x0105 and 0x0006 both map to the unicast timeslot of the node.
+        let colliding = [0x06, 0x00, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            orchestra.add_neighbor(colliding, &mut schedule),
+            Err(ScheduleError::CellDuplicate)
+        );
+
+        orchestra.remove_neighbor(&CHILD, &mut schedule);
+        assert!(link(&schedule, 1, 0x22 % 17).is_none());
+        assert_eq!(schedule.slotframe(1).unwrap().links().len(), 2);
+
+        // Switching time source moves the EB Rx link.
+        orchestra.set_time_source(CHILD, &mut schedule).unwrap();
+        assert!(link(&schedule, 0, 11).is_none());
+        assert!(link(&schedule, 0, 0x22).is_some());
+    }
+}
@@ -0,0 +1,373 @@
+//! Per-neighbor TSCH transmit queues (IEEE 802.15.4-2020, section 6.2.5.3).
+//!
+//! Frames are queued by destination, broadcast frames having their own queue.
+//! When a link with the TX option comes up, a frame matching the link is
+//! selected:
+//! - A link to a given neighbor carries frames to that neighbor.
+//! - A link without neighbor carries broadcast frames and, if it is shared,
+//!   frames to any neighbor.
+//!
+//! Dedicated links are contention free. On shared links, each queue runs the
+//! TSCH CSMA-CA algorithm: after a failed transmission, the queue backs off
+//! for a random number of shared links usable to reach its destination.
+#![allow(dead_code)]
+
+use rand_core::RngCore;
+
+use crate::mac::{frame::fields::TschLinkOption, neighbors::MacNeighbor};
+
+use super::schedule::TschLink;
+
+/// Default minimum backoff exponent of the TSCH CSMA-CA algorithm.
+pub const DEFAULT_MIN_BE: u8 = 1;
+
+/// Default maximum backoff exponent of the TSCH CSMA-CA algorithm.
+pub const DEFAULT_MAX_BE: u8 = 7;
+
+/// Default number of retransmissions before a frame is dropped.
+pub const DEFAULT_MAX_FRAME_RETRIES: u8 = 3;
+
+/// The destination of a queued frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TschDestination {
+    Broadcast,
+    /// Extended address of the neighbor (little endian).
+    Unicast([u8; 8]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TschQueueError {
+    /// The queue of the destination is full or no queue is left for a new
+    /// destination.
+    Full,
+}
+
+/// A queued frame.
+#[derive(Debug)]
+pub struct TschQueuedFrame<F> {
+    pub frame: F,
+    /// Whether the frame requests an acknowledgement.
+    pub ack_requested: bool,
+    /// Number of failed transmissions of the frame.
+    pub retries: u8,
+}
+
+/// The queue of a destination with its CSMA-CA state.
+struct TschQueue<F, const Q: usize> {
+    destination: TschDestination,
+    frames: heapless::Deque<TschQueuedFrame<F>, Q>,
+    /// Backoff exponent.
+    backoff_exponent: u8,
+    /// Number of shared links to skip before transmitting again.
+    backoff_window: u32,
+}
+
+/// The transmit queues of up to `N` destinations with up to `Q` frames each,
+/// see the module documentation.
+pub struct TschQueues<F, const N: usize, const Q: usize> {
+    queues: heapless::Vec<TschQueue<F, Q>, N>,
+    min_be: u8,
+    max_be: u8,
+    max_frame_retries: u8,
+    /// Index of the queue selected for the current link and whether the link
+    /// is shared.
+    selected: Option<(usize, bool)>,
+}
+
+impl<F, const N: usize, const Q: usize> Default for TschQueues<F, N, Q> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_BE, DEFAULT_MAX_BE, DEFAULT_MAX_FRAME_RETRIES)
+    }
+}
+
+impl<F, const N: usize, const Q: usize> TschQueues<F, N, Q> {
+    /// Creates new empty [`TschQueues`].
+    ///
+    /// * `min_be` - Minimum backoff exponent
+    /// * `max_be` - Maximum backoff exponent
+    /// * `max_frame_retries` - Retransmissions before a frame is dropped
+    pub fn new(min_be: u8, max_be: u8, max_frame_retries: u8) -> Self {
+        Self {
+            queues: heapless::Vec::new(),
+            min_be,
+            max_be,
+            max_frame_retries,
+            selected: None,
+        }
+    }
+
+    /// Queue a frame for the given destination.
+    ///
+    /// * `destination` - Destination of the frame
+    /// * `frame` - The frame
+    /// * `ack_requested` - Whether the frame requests an acknowledgement
+    pub fn enqueue(
+        &mut self,
+        destination: TschDestination,
+        frame: F,
+        ack_requested: bool,
+    ) -> Result<(), TschQueueError> {
+        let index = match self.index(&destination) {
+            Some(index) => index,
+            None => {
+                self.queues
+                    .push(TschQueue {
+                        destination,
+                        frames: heapless::Deque::new(),
+                        backoff_exponent: self.min_be,
+                        backoff_window: 0,
+                    })
+                    .map_err(|_| TschQueueError::Full)?;
+                self.queues.len() - 1
+            }
+        };
+        self.queues[index]
+            .frames
+            .push_back(TschQueuedFrame {
+                frame,
+                ack_requested,
+                retries: 0,
+            })
+            .map_err(|_| TschQueueError::Full)
+    }
+
+    /// Return the number of frames queued for the given destination.
+    pub fn len(&self, destination: &TschDestination) -> usize {
+        self.index(destination)
+            .map_or(0, |index| self.queues[index].frames.len())
+    }
+
+    /// Return whether no frame is queued.
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(|queue| queue.frames.is_empty())
+    }
+
+    /// Select the frame to send over the given link, if any, and return
+    /// whether it requests an acknowledgement, see
+    /// [`super::TschExecutor::run_next_slot()`].
+    ///
+    /// Must be called once for every link with the TX option, as the backoff
+    /// of the queues is counted in shared links.
+    ///
+    /// * `link` - The current link
+    pub fn pending_tx<T: MacNeighbor>(&mut self, link: &TschLink<T>) -> Option<bool> {
+        self.selected = None;
+        let options = link.link_options();
+        if !options.contains(TschLinkOption::Tx) {
+            return None;
+        }
+        let shared = options.contains(TschLinkOption::Shared);
+        let neighbor = link
+            .neighbor()
+            .map(|neighbor| TschDestination::Unicast(neighbor.address()));
+
+        let matches = |queue: &TschQueue<F, Q>| match neighbor {
+            Some(neighbor) => queue.destination == neighbor,
+            None => queue.destination == TschDestination::Broadcast || shared,
+        };
+        let ready = |queue: &TschQueue<F, Q>| {
+            matches(queue) && !queue.frames.is_empty() && (!shared || queue.backoff_window == 0)
+        };
+
+        // Broadcast frames take precedence on links without neighbor.
+        let index = self
+            .queues
+            .iter()
+            .position(|queue| queue.destination == TschDestination::Broadcast && ready(queue))
+            .or_else(|| self.queues.iter().position(ready));
+
+        if shared {
+            for queue in self.queues.iter_mut() {
+                if matches(&*queue) && !queue.frames.is_empty() && queue.backoff_window > 0 {
+                    queue.backoff_window -= 1;
+                }
+            }
+        }
+
+        let index = index?;
+        self.selected = Some((index, shared));
+        self.queues[index]
+            .frames
+            .front()
+            .map(|frame| frame.ack_requested)
+    }
+
+    /// Return the frame selected by [`TschQueues::pending_tx()`].
+    pub fn selected(&self) -> Option<&TschQueuedFrame<F>> {
+        let (index, _) = self.selected?;
+        self.queues[index].frames.front()
+    }
+
+    /// Report the outcome of the transmission of the selected frame and return
+    /// the frame if it left the queue, i.e. it was sent or dropped after too
+    /// many retries.
+    ///
+    /// * `success` - Whether the frame was sent (and acknowledged if requested)
+    /// * `rng` - Random number generator, to draw the backoff window
+    pub fn on_tx_result<Rng: RngCore>(
+        &mut self,
+        success: bool,
+        rng: &mut Rng,
+    ) -> Option<TschQueuedFrame<F>> {
+        let (index, shared) = self.selected.take()?;
+        let (min_be, max_be) = (self.min_be, self.max_be);
+        let queue = &mut self.queues[index];
+
+        if success {
+            queue.backoff_exponent = min_be;
+            queue.backoff_window = 0;
+            return queue.frames.pop_front();
+        }
+
+        let frame = queue.frames.front_mut()?;
+        frame.retries += 1;
+        let dropped = frame.retries > self.max_frame_retries;
+        if shared {
+            queue.backoff_exponent = (queue.backoff_exponent + 1).min(max_be);
+            queue.backoff_window = rng.next_u32() % (1 << queue.backoff_exponent);
+        }
+        if !dropped {
+            return None;
+        }
+        if queue.frames.len() == 1 {
+            queue.backoff_exponent = min_be;
+            queue.backoff_window = 0;
+        }
+        queue.frames.pop_front()
+    }
+
+    /// Drop the queue of the given destination, e.g. when a neighbor leaves.
+    pub fn remove(&mut self, destination: &TschDestination) {
+        self.selected = None;
+        self.queues
+            .retain(|queue| &queue.destination != destination);
+    }
+
+    fn index(&self, destination: &TschDestination) -> Option<usize> {
+        self.queues
+            .iter()
+            .position(|queue| &queue.destination == destination)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        neighbors::tests::TestNeighbor, test_helpers::FixedRng, tsch::schedule::TschLinkType,
+    };
+
+    use super::*;
+
+    const NEIGHBOR: [u8; 8] = [1, 0, 0, 0, 0, 0, 0, 0];
+    const OTHER_NEIGHBOR: [u8; 8] = [2, 0, 0, 0, 0, 0, 0, 0];
+
+    fn link(options: TschLinkOption, neighbor: Option<[u8; 8]>) -> TschLink<TestNeighbor> {
+        TschLink::new(
+            0,
+            0,
+            0,
+            options,
+            TschLinkType::Normal,
+            neighbor.map(TestNeighbor::new),
+        )
+    }
+
+    #[test]
+    fn link_matching() {
+        let mut queues = TschQueues::<u8, 3, 2>::default();
+        queues
+            .enqueue(TschDestination::Unicast(NEIGHBOR), 1, true)
+            .unwrap();
+        queues
+            .enqueue(TschDestination::Unicast(NEIGHBOR), 2, true)
+            .unwrap();
+        assert_eq!(
+            queues.enqueue(TschDestination::Unicast(NEIGHBOR), 3, true),
+            Err(TschQueueError::Full)
+        );
+        queues
+            .enqueue(TschDestination::Broadcast, 4, false)
+            .unwrap();
+        assert_eq!(queues.len(&TschDestination::Unicast(NEIGHBOR)), 2);
+
+        // Rx links and links to other neighbors don't carry any frame.
+        assert_eq!(queues.pending_tx(&link(TschLinkOption::Rx, None)), None);
+        let dedicated = link(TschLinkOption::Tx, Some(OTHER_NEIGHBOR));
+        assert_eq!(queues.pending_tx(&dedicated), None);
+        assert!(queues.selected().is_none());
+
+        // Dedicated link to the neighbor.
+        let dedicated = link(TschLinkOption::Tx, Some(NEIGHBOR));
+        assert_eq!(queues.pending_tx(&dedicated), Some(true));
+        assert_eq!(queues.selected().unwrap().frame, 1);
+
+        // Broadcast frames take precedence on shared links without neighbor.
+        let shared = link(TschLinkOption::Tx | TschLinkOption::Shared, None);
+        assert_eq!(queues.pending_tx(&shared), Some(false));
+        let sent = queues.on_tx_result(true, &mut FixedRng(0)).unwrap();
+        assert_eq!(sent.frame, 4);
+        assert_eq!(queues.pending_tx(&shared), Some(true));
+        assert_eq!(queues.selected().unwrap().frame, 1);
+
+        // Dedicated links without neighbor only carry broadcast frames.
+        assert_eq!(queues.pending_tx(&link(TschLinkOption::Tx, None)), None);
+
+        queues.remove(&TschDestination::Unicast(NEIGHBOR));
+        assert!(queues.is_empty());
+    }
+
+    #[test]
+    fn shared_link_backoff() {
+        let mut queues = TschQueues::<u8, 2, 2>::default();
+        let mut rng = FixedRng(2);
+        queues
+            .enqueue(TschDestination::Unicast(NEIGHBOR), 1, true)
+            .unwrap();
+        let shared = link(TschLinkOption::Tx | TschLinkOption::Shared, Some(NEIGHBOR));
+        let dedicated = link(TschLinkOption::Tx, Some(NEIGHBOR));
+
+        assert_eq!(queues.pending_tx(&shared), Some(true));
+        assert!(queues.on_tx_result(false, &mut rng).is_none());
+
+        // The queue backs off for 2 shared links...
+        assert_eq!(queues.pending_tx(&shared), None);
+        // ...but dedicated links are contention free.
+        assert_eq!(queues.pending_tx(&dedicated), Some(true));
+        assert!(queues.on_tx_result(false, &mut rng).is_none());
+        assert_eq!(queues.pending_tx(&shared), None);
+        assert_eq!(queues.pending_tx(&shared), Some(true));
+        assert_eq!(queues.selected().unwrap().retries, 2);
+
+        let sent = queues.on_tx_result(true, &mut rng).unwrap();
+        assert_eq!((sent.frame, sent.retries), (1, 2));
+        assert!(queues.is_empty());
+    }
+
+    #[test]
+    fn retries() {
+        let mut queues = TschQueues::<u8, 1, 2>::new(DEFAULT_MIN_BE, DEFAULT_MAX_BE, 1);
+        let mut rng = FixedRng(0);
+        queues
+            .enqueue(TschDestination::Unicast(NEIGHBOR), 1, true)
+            .unwrap();
+        queues
+            .enqueue(TschDestination::Unicast(NEIGHBOR), 2, true)
+            .unwrap();
+        assert_eq!(
+            queues.enqueue(TschDestination::Broadcast, 3, false),
+            Err(TschQueueError::Full)
+        );
+
+        let dedicated = link(TschLinkOption::Tx, Some(NEIGHBOR));
+        queues.pending_tx(&dedicated);
+        assert!(queues.on_tx_result(false, &mut rng).is_none());
+        queues.pending_tx(&dedicated);
+        let dropped = queues.on_tx_result(false, &mut rng).unwrap();
+        assert_eq!((dropped.frame, dropped.retries), (1, 2));
+
+        queues.pending_tx(&dedicated);
+        assert_eq!(queues.selected().unwrap().frame, 2);
+        assert!(queues.on_tx_result(true, &mut rng).is_some());
+        assert!(queues.on_tx_result(true, &mut rng).is_none());
+    }
+}
@@ -1,16 +1,24 @@
 #![allow(dead_code)]
-use crate::mac::{
-    frame::fields::{TschLinkOption, TschTimeslotTimings},
-    neighbors::MacNeighbor,
+use crate::{
+    driver::config::Channel,
+    mac::{
+        frame::fields::{TschLinkOption, TschTimeslotTimings},
+        neighbors::MacNeighbor,
+    },
 };
 
-use super::asn::AbsoluteSlotNumber;
+use super::{asn::AbsoluteSlotNumber, hopping::HoppingSequence};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScheduleError {
     InvalidTimeslot,
     InvalidChannelOffset,
     CapacityExceeded,
     HandleDuplicate,
+    /// Another link already occupies the same timeslot and channel offset.
+    CellDuplicate,
+    /// No slotframe or link with the given handle exists.
+    UnknownHandle,
 }
 
 /// A TSCH link is a pairwise assignment of a directed communication between
@@ -32,15 +40,63 @@ pub struct TschLink<T: MacNeighbor> {
     neighbor: Option<T>,
 }
 
+impl<T: MacNeighbor> TschLink<T> {
+    /// Creates a new [`TschLink`].
+    pub fn new(
+        handle: u16,
+        timeslot: u16,
+        channel_offset: u16,
+        link_options: TschLinkOption,
+        link_type: TschLinkType,
+        neighbor: Option<T>,
+    ) -> Self {
+        Self {
+            handle,
+            timeslot,
+            channel_offset,
+            link_options,
+            link_type,
+            neighbor,
+        }
+    }
+
+    /// Return the link identifier.
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    /// Return the timeslot of the link within its slotframe.
+    pub fn timeslot(&self) -> u16 {
+        self.timeslot
+    }
+
+    /// Return the channel offset of the link.
+    pub fn channel_offset(&self) -> u16 {
+        self.channel_offset
+    }
+
+    /// Return the link communication options.
+    pub fn link_options(&self) -> TschLinkOption {
+        self.link_options
+    }
+
+    /// Return the neighbor assigned to the link, if any.
+    pub fn neighbor(&self) -> Option<&T> {
+        self.neighbor.as_ref()
+    }
+
+    /// Return whether both links use the same timeslot and channel offset.
+    fn occupies_cell_of(&self, other: &Self) -> bool {
+        self.timeslot == other.timeslot && self.channel_offset == other.channel_offset
+    }
+}
+
 /// Type of link
 pub enum TschLinkType {
     Advertising,
     Normal,
 }
 
-/// Represents a channel hopping sequence
-pub type TschHoppingSequence = [u8; 4];
-
 /// A TSCH slotframe collection of timeslots repeating in time, analogous to a
 /// superframe in that it defines periods of communication opportunities.
 #[allow(dead_code)]
@@ -52,7 +108,7 @@ pub struct TschSlotframe<const N: usize, T: MacNeighbor> {
     size: u16,
     /// Sequence of PHY channels that allows for a different channel to be
     /// used at a given ASN
-    hopping_sequence: TschHoppingSequence,
+    hopping_sequence: HoppingSequence,
     /// Sequence of links configured for the slotframe.
     links: heapless::Vec<TschLink<T>, N>,
 }
@@ -60,7 +116,7 @@ pub struct TschSlotframe<const N: usize, T: MacNeighbor> {
 #[allow(dead_code)]
 impl<const N: usize, T: MacNeighbor> TschSlotframe<N, T> {
     /// Creates a new [`TschSlotframe`].
-    pub fn new(handle: u16, size: u16, hopping_sequence: TschHoppingSequence) -> Self {
+    pub fn new(handle: u16, size: u16, hopping_sequence: HoppingSequence) -> Self {
         Self {
             handle,
             size,
@@ -69,23 +125,110 @@ impl<const N: usize, T: MacNeighbor> TschSlotframe<N, T> {
         }
     }
 
+    /// Return the slotframe identifier.
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    /// Return the number of timeslots in the slotframe.
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    /// Return the links of the slotframe.
+    pub fn links(&self) -> &[TschLink<T>] {
+        &self.links
+    }
+
+    /// Return the lowest link handle not used in the slotframe.
+    pub fn free_link_handle(&self) -> Option<u16> {
+        (0..=u16::MAX).find(|handle| self.links.iter().all(|l| l.handle != *handle))
+    }
+
     /// Add the given link to the slotframe
     ///
     /// * `link` - Link to add
     pub fn add_link(&mut self, link: TschLink<T>) -> Result<(), ScheduleError> {
+        self.check_link(&link)?;
+        if self.links.iter().any(|l| l.handle == link.handle) {
+            Err(ScheduleError::HandleDuplicate)
+        } else if self.links.iter().any(|l| l.occupies_cell_of(&link)) {
+            Err(ScheduleError::CellDuplicate)
+        } else if self.links.push(link).is_err() {
+            Err(ScheduleError::CapacityExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Replace the link with the same handle as the given link.
+    ///
+    /// * `link` - Link to replace the existing link with
+    pub fn modify_link(&mut self, link: TschLink<T>) -> Result<(), ScheduleError> {
+        self.check_link(&link)?;
+        if self
+            .links
+            .iter()
+            .any(|l| l.handle != link.handle && l.occupies_cell_of(&link))
+        {
+            return Err(ScheduleError::CellDuplicate);
+        }
+        let existing = self
+            .links
+            .iter_mut()
+            .find(|l| l.handle == link.handle)
+            .ok_or(ScheduleError::UnknownHandle)?;
+        *existing = link;
+        Ok(())
+    }
+
+    /// Remove the link with the given handle from the slotframe and return
+    /// it.
+    ///
+    /// * `handle` - Handle of the link to remove
+    pub fn delete_link(&mut self, handle: u16) -> Result<TschLink<T>, ScheduleError> {
+        let index = self
+            .links
+            .iter()
+            .position(|l| l.handle == handle)
+            .ok_or(ScheduleError::UnknownHandle)?;
+        Ok(self.links.remove(index))
+    }
+
+    /// Change the number of timeslots in the slotframe.
+    ///
+    /// * `size` - New number of timeslots
+    pub fn set_size(&mut self, size: u16) -> Result<(), ScheduleError> {
+        if size == 0 || self.links.iter().any(|l| l.timeslot >= size) {
+            Err(ScheduleError::InvalidTimeslot)
+        } else {
+            self.size = size;
+            Ok(())
+        }
+    }
+
+    /// Check that the given link fits into the slotframe.
+    fn check_link(&self, link: &TschLink<T>) -> Result<(), ScheduleError> {
         if link.timeslot >= self.size {
             Err(ScheduleError::InvalidTimeslot)
         } else if link.channel_offset as usize >= self.hopping_sequence.len() {
             Err(ScheduleError::InvalidChannelOffset)
-        } else if self.links.iter().any(|l| l.handle == link.handle) {
-            Err(ScheduleError::HandleDuplicate)
-        } else if self.links.push(link).is_err() {
-            Err(ScheduleError::CapacityExceeded)
         } else {
             Ok(())
         }
     }
 
+    /// Return the number of slots from the given ASN until the next active
+    /// slot of the given link (0 if the given ASN is active).
+    fn slots_until(&self, asn: AbsoluteSlotNumber, link: &TschLink<T>) -> u16 {
+        let timeslot = self.timeslot(asn);
+        if link.timeslot >= timeslot {
+            link.timeslot - timeslot
+        } else {
+            self.size - timeslot + link.timeslot
+        }
+    }
+
     /// Return the link associated to the given ASN, if any.
     ///
     /// * `asn` - Absolute slot number
@@ -101,11 +244,12 @@ impl<const N: usize, T: MacNeighbor> TschSlotframe<N, T> {
         asn % self.size
     }
 
-    /// Return the channel offset for a given link at a given ASN
+    /// Return the channel to be used by a given link at a given ASN
+    ///
     /// * `asn` - Absolute slot number
     /// * `link` - Link to consider
-    fn channel_offset(&self, asn: AbsoluteSlotNumber, link: TschLink<T>) -> u16 {
-        (asn + link.channel_offset) % self.size
+    fn channel(&self, asn: AbsoluteSlotNumber, link: &TschLink<T>) -> Channel {
+        self.hopping_sequence.channel(asn, link.channel_offset)
     }
 }
 
@@ -131,13 +275,10 @@ impl<const S: usize, const L: usize, T: MacNeighbor> TschSchedule<S, L, T> {
         Self::default()
     }
 
-    /// Add a given slotframe to the schedule.
+    /// Add a given slotframe to the schedule (MLME-SET-SLOTFRAME, ADD).
     ///
     /// * `slotframe` - Slotframe to add
-    pub(crate) fn add_slotframe(
-        &mut self,
-        slotframe: TschSlotframe<L, T>,
-    ) -> Result<(), ScheduleError> {
+    pub fn add_slotframe(&mut self, slotframe: TschSlotframe<L, T>) -> Result<(), ScheduleError> {
         if self.slotframes.iter().any(|s| s.handle == slotframe.handle) {
             Err(ScheduleError::HandleDuplicate)
         } else if self.slotframes.push(slotframe).is_err() {
@@ -182,12 +323,174 @@ impl<const S: usize, const L: usize, T: MacNeighbor> TschSchedule<S, L, T> {
         }
     }
 
+    /// Increment ASN until a link is found. Return the ASN of the link
+    /// together with the channel to be used and the link itself.
+    pub(crate) fn next_active_cell(
+        &mut self,
+    ) -> Option<(AbsoluteSlotNumber, Channel, &TschLink<T>)> {
+        let (asn, _, _) = self.active_cells(self.asn).next()?;
+        self.asn = asn + 1u32;
+        self.active_cells(asn).next()
+    }
+
+    /// Return an iterator over the active cells starting at the given ASN,
+    /// see [`TschActiveCells`].
+    ///
+    /// * `asn` - Absolute slot number to start from (inclusive)
+    pub fn active_cells(&self, asn: AbsoluteSlotNumber) -> TschActiveCells<'_, S, L, T> {
+        TschActiveCells {
+            schedule: self,
+            asn,
+        }
+    }
+
+    /// Return the slotframes of the schedule.
+    pub fn slotframes(&self) -> &[TschSlotframe<L, T>] {
+        &self.slotframes
+    }
+
+    /// Return the slotframe with the given handle, if any.
+    ///
+    /// * `handle` - Handle of the slotframe
+    pub fn slotframe(&self, handle: u16) -> Option<&TschSlotframe<L, T>> {
+        self.slotframes.iter().find(|s| s.handle == handle)
+    }
+
+    /// Remove the slotframe with the given handle from the schedule together
+    /// with all of its links (MLME-SET-SLOTFRAME, DELETE).
+    ///
+    /// * `handle` - Handle of the slotframe to remove
+    pub fn delete_slotframe(&mut self, handle: u16) -> Result<TschSlotframe<L, T>, ScheduleError> {
+        let index = self
+            .slotframes
+            .iter()
+            .position(|s| s.handle == handle)
+            .ok_or(ScheduleError::UnknownHandle)?;
+        Ok(self.slotframes.remove(index))
+    }
+
+    /// Change the number of timeslots of the slotframe with the given handle
+    /// (MLME-SET-SLOTFRAME, MODIFY).
+    ///
+    /// * `handle` - Handle of the slotframe to modify
+    /// * `size` - New number of timeslots
+    pub fn modify_slotframe(&mut self, handle: u16, size: u16) -> Result<(), ScheduleError> {
+        self.slotframe_mut(handle)?.set_size(size)
+    }
+
+    /// Add a link to the slotframe with the given handle (MLME-SET-LINK,
+    /// ADD_LINK).
+    ///
+    /// * `slotframe_handle` - Handle of the slotframe
+    /// * `link` - Link to add
+    pub fn add_link(
+        &mut self,
+        slotframe_handle: u16,
+        link: TschLink<T>,
+    ) -> Result<(), ScheduleError> {
+        self.slotframe_mut(slotframe_handle)?.add_link(link)
+    }
+
+    /// Replace a link of the slotframe with the given handle (MLME-SET-LINK,
+    /// MODIFY_LINK).
+    ///
+    /// * `slotframe_handle` - Handle of the slotframe
+    /// * `link` - Link to replace the link with the same handle with
+    pub fn modify_link(
+        &mut self,
+        slotframe_handle: u16,
+        link: TschLink<T>,
+    ) -> Result<(), ScheduleError> {
+        self.slotframe_mut(slotframe_handle)?.modify_link(link)
+    }
+
+    /// Remove a link from the slotframe with the given handle (MLME-SET-LINK,
+    /// DELETE_LINK).
+    ///
+    /// * `slotframe_handle` - Handle of the slotframe
+    /// * `link_handle` - Handle of the link to remove
+    pub fn delete_link(
+        &mut self,
+        slotframe_handle: u16,
+        link_handle: u16,
+    ) -> Result<TschLink<T>, ScheduleError> {
+        self.slotframe_mut(slotframe_handle)?
+            .delete_link(link_handle)
+    }
+
+    fn slotframe_mut(&mut self, handle: u16) -> Result<&mut TschSlotframe<L, T>, ScheduleError> {
+        self.slotframes
+            .iter_mut()
+            .find(|s| s.handle == handle)
+            .ok_or(ScheduleError::UnknownHandle)
+    }
+
+    /// Return the timings used for communication inside a timeslot.
+    pub(crate) fn timeslot_timings(&self) -> &TschTimeslotTimings {
+        &self.timeslot_timings
+    }
+
+    /// Set the timings used for communication inside a timeslot.
+    pub(crate) fn set_timeslot_timings(&mut self, timeslot_timings: TschTimeslotTimings) {
+        self.timeslot_timings = timeslot_timings;
+    }
+
+    /// Return the metric used when selecting and joining a TSCH network.
+    pub(crate) fn join_metric(&self) -> u16 {
+        self.join_metric
+    }
+
+    /// Set the metric used when selecting and joining a TSCH network.
+    pub(crate) fn set_join_metric(&mut self, join_metric: u16) {
+        self.join_metric = join_metric;
+    }
+
+    /// Remove all slotframes and their links from the schedule.
+    pub(crate) fn clear(&mut self) {
+        self.slotframes.clear();
+    }
+
     /// Set the absolute slot number.
     pub(crate) fn set_asn(&mut self, asn: AbsoluteSlotNumber) {
         self.asn = asn;
     }
 }
 
+/// Iterator over the active cells of a [`TschSchedule`] in ASN order.
+///
+/// Each item consists of the ASN, the channel and the link to be used. If
+/// links of multiple slotframes are scheduled in the same slot, the link of the
+/// slotframe added first takes precedence. The next active cell is computed
+/// from the link positions directly rather than by probing every slot in
+/// between.
+pub struct TschActiveCells<'schedule, const S: usize, const L: usize, T: MacNeighbor> {
+    schedule: &'schedule TschSchedule<S, L, T>,
+    asn: AbsoluteSlotNumber,
+}
+
+impl<'schedule, const S: usize, const L: usize, T: MacNeighbor> Iterator
+    for TschActiveCells<'schedule, S, L, T>
+{
+    type Item = (AbsoluteSlotNumber, Channel, &'schedule TschLink<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut next: Option<(u16, &TschSlotframe<L, T>, &TschLink<T>)> = None;
+        for slotframe in &self.schedule.slotframes {
+            for link in &slotframe.links {
+                let slots = slotframe.slots_until(self.asn, link);
+                if next.is_none_or(|(min, _, _)| slots < min) {
+                    next = Some((slots, slotframe, link));
+                }
+            }
+        }
+
+        let (slots, slotframe, link) = next?;
+        let asn = self.asn + slots;
+        self.asn = asn + 1u32;
+        Some((asn, slotframe.channel(asn, link), link))
+    }
+}
+
 impl<const S: usize, const L: usize, T: MacNeighbor> Default for TschSchedule<S, L, T> {
     fn default() -> Self {
         Self {
@@ -201,13 +504,18 @@ impl<const S: usize, const L: usize, T: MacNeighbor> Default for TschSchedule<S,
 
 #[cfg(test)]
 pub mod tests {
-    use crate::mac::{frame::fields::TschLinkOption, neighbors::tests::TestNeighbor};
+    use crate::{
+        driver::config::Channel,
+        mac::{frame::fields::TschLinkOption, neighbors::tests::TestNeighbor},
+    };
 
-    use super::{ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe};
+    use super::{
+        HoppingSequence, ScheduleError, TschLink, TschLinkType, TschSchedule, TschSlotframe,
+    };
 
     #[test]
     fn schedule() {
-        let hopping_sequence = [15, 25, 26, 20];
+        let hopping_sequence = HoppingSequence::DEFAULT_4_4;
         let nbr1 = TestNeighbor::new([0, 0, 0, 0, 0, 0, 0, 1]);
         let nbr2 = TestNeighbor::new([0, 0, 0, 0, 0, 0, 0, 2]);
         let mut sf = TschSlotframe::new(1, 3, hopping_sequence);
@@ -279,7 +587,7 @@ pub mod tests {
 
     #[test]
     fn invalid_links() {
-        let hopping_sequence = [15, 25, 26, 20];
+        let hopping_sequence = HoppingSequence::DEFAULT_4_4;
         let mut sf = TschSlotframe::<2, TestNeighbor>::new(1, 11, hopping_sequence);
 
         let res = sf.add_link(TschLink {
@@ -333,7 +641,7 @@ pub mod tests {
     }
     #[test]
     fn multiple_slotframes() {
-        let hopping_sequence = [15, 25, 26, 20];
+        let hopping_sequence = HoppingSequence::DEFAULT_4_4;
         let mut sf1 = TschSlotframe::new(1, 3, hopping_sequence);
         let mut sf2 = TschSlotframe::new(2, 2, hopping_sequence);
 
@@ -387,6 +695,13 @@ pub mod tests {
             let active_slot = schedule.next_active_slot().unwrap();
             assert_eq!(active_slot.handle, 1);
         }
+        {
+            // ASN 4 is active in SF 2, channel offset 1 maps to channel 25.
+            let (asn, channel, link) = schedule.next_active_cell().unwrap();
+            assert!(asn == 4i64);
+            assert_eq!(channel, Channel::_25);
+            assert_eq!(link.handle(), 2);
+        }
 
         // Adding a third slotframe should not work
         let sf3 = TschSlotframe::new(3, 3, hopping_sequence);
@@ -396,4 +711,113 @@ pub mod tests {
             _ => panic!(),
         };
     }
+
+    #[test]
+    fn set_slotframe_and_link() {
+        let hopping_sequence = HoppingSequence::DEFAULT_4_4;
+        let mut schedule = TschSchedule::<2, 2, TestNeighbor>::new();
+        let link = |handle, timeslot, channel_offset| {
+            TschLink::new(
+                handle,
+                timeslot,
+                channel_offset,
+                TschLinkOption::Tx,
+                TschLinkType::Normal,
+                None,
+            )
+        };
+
+        assert!(schedule
+            .add_slotframe(TschSlotframe::new(1, 5, hopping_sequence))
+            .is_ok());
+        assert!(schedule.add_link(1, link(0, 1, 0)).is_ok());
+        assert!(schedule.add_link(1, link(1, 1, 1)).is_ok());
+        assert!(matches!(
+            schedule.add_link(2, link(2, 2, 0)),
+            Err(ScheduleError::UnknownHandle)
+        ));
+
+        // Same slotframe, timeslot and channel offset as link 0.
+        let mut sf = TschSlotframe::<2, TestNeighbor>::new(2, 5, hopping_sequence);
+        assert!(sf.add_link(link(0, 1, 0)).is_ok());
+        assert!(matches!(
+            sf.add_link(link(1, 1, 0)),
+            Err(ScheduleError::CellDuplicate)
+        ));
+
+        // Modify link 1 to occupy the cell of link 0.
+        assert!(matches!(
+            schedule.modify_link(1, link(1, 1, 0)),
+            Err(ScheduleError::CellDuplicate)
+        ));
+        assert!(schedule.modify_link(1, link(1, 4, 1)).is_ok());
+        assert!(matches!(
+            schedule.modify_link(1, link(2, 3, 1)),
+            Err(ScheduleError::UnknownHandle)
+        ));
+
+        // The slotframe cannot shrink below its links.
+        assert!(matches!(
+            schedule.modify_slotframe(1, 4),
+            Err(ScheduleError::InvalidTimeslot)
+        ));
+        assert!(schedule.modify_slotframe(1, 7).is_ok());
+        assert_eq!(schedule.slotframe(1).unwrap().size(), 7);
+
+        assert_eq!(schedule.delete_link(1, 1).ok().map(|l| l.handle()), Some(1));
+        assert!(matches!(
+            schedule.delete_link(1, 1),
+            Err(ScheduleError::UnknownHandle)
+        ));
+
+        assert!(schedule.delete_slotframe(1).is_ok());
+        assert!(schedule.slotframe(1).is_none());
+        assert!(matches!(
+            schedule.delete_slotframe(1),
+            Err(ScheduleError::UnknownHandle)
+        ));
+    }
+
+    #[test]
+    fn active_cells() {
+        let hopping_sequence = HoppingSequence::DEFAULT_4_4;
+        let mut schedule = TschSchedule::<2, 2, TestNeighbor>::new();
+        let link = |handle, timeslot, channel_offset| {
+            TschLink::new(
+                handle,
+                timeslot,
+                channel_offset,
+                TschLinkOption::Rx,
+                TschLinkType::Normal,
+                None,
+            )
+        };
+
+        assert!(schedule
+            .active_cells(0.try_into().unwrap())
+            .next()
+            .is_none());
+
+        let mut sf1 = TschSlotframe::new(1, 101, hopping_sequence);
+        assert!(sf1.add_link(link(1, 100, 0)).is_ok());
+        let mut sf2 = TschSlotframe::new(2, 7, hopping_sequence);
+        assert!(sf2.add_link(link(2, 2, 1)).is_ok());
+        assert!(sf2.add_link(link(3, 3, 2)).is_ok());
+        assert!(schedule.add_slotframe(sf1).is_ok());
+        assert!(schedule.add_slotframe(sf2).is_ok());
+
+        let mut cells = schedule.active_cells(95.try_into().unwrap());
+        let mut expect = |asn: i64, channel: Channel, handle: u16| {
+            let (next_asn, next_channel, next_link) = cells.next().unwrap();
+            assert!(next_asn == asn);
+            assert_eq!(next_channel, channel);
+            assert_eq!(next_link.handle(), handle);
+        };
+        // Slotframe 1 takes precedence over slotframe 2 at ASN 100.
+        expect(100, Channel::_15, 1);
+        expect(101, Channel::_20, 3);
+        expect(107, Channel::_15, 2);
+        expect(108, Channel::_26, 3);
+        expect(114, Channel::_20, 2);
+    }
 }
@@ -0,0 +1,273 @@
+//! Security of TSCH frames (IEEE 802.15.4-2020, section 9.3.2.2).
+//!
+//! In TSCH mode, the frame counter is suppressed from the auxiliary security
+//! header and the CCM* nonce is built from the ASN of the timeslot in which the
+//! frame is sent instead. Both ends know the ASN, so it never goes over the
+//! air. As the ASN increases monotonically, it also provides replay
+//! protection: [`AsnReplayFilter`] rejects frames whose ASN is not strictly
+//! greater than the one of the last authenticated frame from the same
+//! neighbor, or that lies outside the window of recent timeslots. Replays are
+//! counted in
+//! [`MacCounters::replay_drops`](crate::mac::counters::MacCounters::replay_drops).
+#![allow(dead_code)]
+
+use crate::mac::{counters::MAC_COUNTERS, frame::repr::SecurityRepr};
+
+use super::asn::AbsoluteSlotNumber;
+
+/// The length of a CCM* nonce.
+pub const NONCE_LEN: usize = 13;
+
+/// Default number of past timeslots in which received frames are accepted.
+pub const DEFAULT_ASN_WINDOW: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityError {
+    /// The frame uses TSCH mode but no ASN is available.
+    MissingAsn,
+    /// The frame doesn't use TSCH mode but has no frame counter.
+    MissingFrameCounter,
+    /// The frame was sent in a timeslot outside the accepted window.
+    OutsideAsnWindow,
+    /// A frame with the same or a later ASN was already accepted from the
+    /// neighbor.
+    Replay,
+    /// No room is left to track a new neighbor.
+    Full,
+}
+
+/// A CCM* nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CcmNonce([u8; NONCE_LEN]);
+
+impl CcmNonce {
+    /// Creates the nonce of a frame.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `security` - Security configuration of the frame
+    /// * `frame_counter` - Frame counter, if not in TSCH mode
+    /// * `asn` - ASN of the timeslot of the frame, in TSCH mode
+    pub fn new(
+        src_address: &[u8; 8],
+        security: &SecurityRepr,
+        frame_counter: Option<u32>,
+        asn: Option<AbsoluteSlotNumber>,
+    ) -> Result<Self, SecurityError> {
+        if security.tsch_mode() {
+            let asn = asn.ok_or(SecurityError::MissingAsn)?;
+            Ok(Self::from_asn(src_address, asn))
+        } else {
+            let frame_counter = frame_counter.ok_or(SecurityError::MissingFrameCounter)?;
+            Ok(Self::from_frame_counter(
+                src_address,
+                frame_counter,
+                security.security_level().value(),
+            ))
+        }
+    }
+
+    /// Creates the nonce of a frame secured with a frame counter, i.e. the
+    /// source address, the frame counter and the security level.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `frame_counter` - Frame counter of the frame
+    /// * `security_level` - Value of the security level subfield
+    pub fn from_frame_counter(
+        src_address: &[u8; 8],
+        frame_counter: u32,
+        security_level: u8,
+    ) -> Self {
+        let mut nonce = Self::with_src_address(src_address);
+        nonce.0[8..12].copy_from_slice(&frame_counter.to_be_bytes());
+        nonce.0[12] = security_level;
+        nonce
+    }
+
+    /// Creates the nonce of a frame secured in TSCH mode, i.e. the source
+    /// address and the 5-byte ASN.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `asn` - ASN of the timeslot in which the frame is sent
+    pub fn from_asn(src_address: &[u8; 8], asn: AbsoluteSlotNumber) -> Self {
+        let mut nonce = Self::with_src_address(src_address);
+        nonce.0[8..].copy_from_slice(&to_i64(asn).to_be_bytes()[3..]);
+        nonce
+    }
+
+    /// Return the nonce as bytes.
+    pub fn as_bytes(&self) -> &[u8; NONCE_LEN] {
+        &self.0
+    }
+
+    fn with_src_address(src_address: &[u8; 8]) -> Self {
+        let mut nonce = [0; NONCE_LEN];
+        nonce[..8].copy_from_slice(src_address);
+        // The address is in big endian in the nonce.
+        nonce[..8].reverse();
+        Self(nonce)
+    }
+}
+
+/// Replay protection of frames secured in TSCH mode for up to `N` neighbors,
+/// see the module documentation.
+pub struct AsnReplayFilter<const N: usize> {
+    /// Extended address (little endian) and ASN of the last authenticated
+    /// frame of each neighbor.
+    neighbors: heapless::Vec<([u8; 8], i64), N>,
+    /// Number of past timeslots in which received frames are accepted.
+    window: u32,
+}
+
+impl<const N: usize> Default for AsnReplayFilter<N> {
+    fn default() -> Self {
+        Self::new(DEFAULT_ASN_WINDOW)
+    }
+}
+
+impl<const N: usize> AsnReplayFilter<N> {
+    /// Creates a new [`AsnReplayFilter`].
+    ///
+    /// * `window` - Number of past timeslots in which received frames are
+    ///   accepted, e.g. to account for processing delays
+    pub fn new(window: u32) -> Self {
+        Self {
+            neighbors: heapless::Vec::new(),
+            window,
+        }
+    }
+
+    /// Check whether a frame received from the given neighbor may be
+    /// processed. Must be called before authenticating the frame. Replays are
+    /// counted.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `frame_asn` - ASN of the timeslot in which the frame was received
+    /// * `current_asn` - Current ASN
+    pub fn check(
+        &self,
+        src_address: &[u8; 8],
+        frame_asn: AbsoluteSlotNumber,
+        current_asn: AbsoluteSlotNumber,
+    ) -> Result<(), SecurityError> {
+        let frame_asn = to_i64(frame_asn);
+        let age = to_i64(current_asn) - frame_asn;
+        if !(0..=self.window as i64).contains(&age) {
+            return Err(SecurityError::OutsideAsnWindow);
+        }
+        match self.last_asn(src_address) {
+            Some(last_asn) if frame_asn <= last_asn => {
+                MAC_COUNTERS.replay_drops.increment();
+                Err(SecurityError::Replay)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a frame of the given neighbor that was successfully
+    /// authenticated.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `frame_asn` - ASN of the timeslot in which the frame was received
+    pub fn accept(
+        &mut self,
+        src_address: &[u8; 8],
+        frame_asn: AbsoluteSlotNumber,
+    ) -> Result<(), SecurityError> {
+        let frame_asn = to_i64(frame_asn);
+        match self
+            .neighbors
+            .iter_mut()
+            .find(|(address, _)| address == src_address)
+        {
+            Some((_, last_asn)) => *last_asn = frame_asn.max(*last_asn),
+            None => self
+                .neighbors
+                .push((*src_address, frame_asn))
+                .map_err(|_| SecurityError::Full)?,
+        }
+        Ok(())
+    }
+
+    /// Return the ASN of the last authenticated frame of the given neighbor.
+    pub fn last_asn(&self, src_address: &[u8; 8]) -> Option<i64> {
+        self.neighbors
+            .iter()
+            .find(|(address, _)| address == src_address)
+            .map(|(_, last_asn)| *last_asn)
+    }
+
+    /// Forget the given neighbor.
+    pub fn remove(&mut self, src_address: &[u8; 8]) {
+        self.neighbors.retain(|(address, _)| address != src_address);
+    }
+}
+
+fn to_i64(asn: AbsoluteSlotNumber) -> i64 {
+    // Safety: The conversion never fails.
+    asn.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::frame::repr::{KeyIdRepr, SecurityLevelRepr};
+
+    use super::*;
+
+    const SRC_ADDRESS: [u8; 8] = [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01];
+
+    fn asn(asn: i64) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    #[test]
+    fn nonces() {
+        let tsch = SecurityRepr::new(true, SecurityLevelRepr::EncMic32, KeyIdRepr::SourceNone);
+        let nonce = CcmNonce::new(&SRC_ADDRESS, &tsch, None, Some(asn(0x01_0203_0405))).unwrap();
+        assert_eq!(
+            nonce.as_bytes(),
+            &[1, 2, 3, 4, 5, 6, 7, 8, 0x01, 0x02, 0x03, 0x04, 0x05]
+        );
+        assert_eq!(
+            CcmNonce::new(&SRC_ADDRESS, &tsch, Some(1), None),
+            Err(SecurityError::MissingAsn)
+        );
+
+        let legacy = SecurityRepr::new(false, SecurityLevelRepr::EncMic32, KeyIdRepr::SourceNone);
+        let nonce = CcmNonce::new(&SRC_ADDRESS, &legacy, Some(0x0a0b_0c0d), None).unwrap();
+        assert_eq!(
+            nonce.as_bytes(),
+            &[1, 2, 3, 4, 5, 6, 7, 8, 0x0a, 0x0b, 0x0c, 0x0d, 5]
+        );
+        assert_eq!(
+            CcmNonce::new(&SRC_ADDRESS, &legacy, None, Some(asn(1))),
+            Err(SecurityError::MissingFrameCounter)
+        );
+    }
+
+    #[test]
+    fn replay_filter() {
+        let mut filter = AsnReplayFilter::<1>::new(2);
+        assert_eq!(filter.check(&SRC_ADDRESS, asn(100), asn(100)), Ok(()));
+        assert_eq!(filter.check(&SRC_ADDRESS, asn(98), asn(100)), Ok(()));
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, asn(97), asn(100)),
+            Err(SecurityError::OutsideAsnWindow)
+        );
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, asn(101), asn(100)),
+            Err(SecurityError::OutsideAsnWindow)
+        );
+
+        filter.accept(&SRC_ADDRESS, asn(99)).unwrap();
+        assert_eq!(filter.last_asn(&SRC_ADDRESS), Some(99));
+        assert_eq!(
+            filter.check(&SRC_ADDRESS, asn(99), asn(100)),
+            Err(SecurityError::Replay)
+        );
+        assert_eq!(filter.check(&SRC_ADDRESS, asn(100), asn(100)), Ok(()));
+        assert_eq!(filter.accept(&[0; 8], asn(100)), Err(SecurityError::Full));
+
+        filter.remove(&SRC_ADDRESS);
+        assert_eq!(filter.last_asn(&SRC_ADDRESS), None);
+    }
+}
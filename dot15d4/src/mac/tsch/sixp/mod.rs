@@ -0,0 +1,886 @@
+//! 6top Protocol (6P, RFC 8480) messages.
+//!
+//! 6P messages are carried in the IETF payload IE (group ID 0x5) with the 6top
+//! sub-ID. They let two neighbors negotiate the addition, deletion and
+//! relocation of cells on behalf of a scheduling function (SF):
+//!
+//! ```notrust
+//! +------------+---------+------+------+--------+--------------+
+//! | IE header  | Sub-ID  | Ver. | Code | SFID   | SeqNum | ... |
+//! | (2 bytes)  | (0xc9)  | Type |      |        |        |     |
+//! +------------+---------+------+------+--------+--------------+
+//! ```
+//!
+//! The transaction engine in [`transaction`] tracks the state of the 6P
+//! transactions with each neighbor.
+#![allow(dead_code)]
+
+pub mod transaction;
+
+use crate::{
+    driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, ExtendedAddress, FrameType, PanId},
+    },
+    mac::frame::{
+        fields::TschLinkOption,
+        mpdu::{FrameBuffer, FrameBuilder},
+        FrameError, FrameErrorKind,
+    },
+};
+
+pub use transaction::{SixpEvent, SixpTransactions};
+
+/// The version of 6P implemented.
+pub const SIXP_VERSION: u8 = 0;
+
+/// The group ID of the IETF payload IE.
+pub const IETF_PAYLOAD_IE_GROUP_ID: u16 = 0x5;
+
+/// The sub-ID of the 6top IE within the IETF payload IE.
+pub const SIXTOP_IE_SUB_ID: u8 = 0xc9;
+
+/// The length of a 6P cell.
+pub const CELL_LEN: usize = 4;
+
+/// Header Termination IE 1 (element ID 0x7e, no content).
+const HEADER_TERMINATION_IE_1: [u8; 2] = [0x00, 0x3f];
+
+/// The length of the payload IE header.
+const IE_HEADER_LEN: usize = 2;
+
+/// The length of the 6P header (version and type, code, SFID and SeqNum).
+const HEADER_LEN: usize = 4;
+
+/// The length of the Metadata field.
+const METADATA_LEN: usize = 2;
+
+/// The cell options defined by 6P.
+const CELL_OPTIONS_MASK: u8 = 0b0000_0111;
+
+/// An error that can occur when handling 6P messages and transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpError {
+    /// The message is shorter than its fields require.
+    Truncated,
+    /// The IE is not a 6top IE.
+    NotSixp,
+    /// The message uses an unsupported 6P version.
+    UnsupportedVersion(u8),
+    /// The message type is reserved.
+    InvalidType,
+    /// The command or return code is unknown.
+    InvalidCode(u8),
+    /// The buffer is too small for the message.
+    BufferTooSmall,
+    /// A transaction with the neighbor is already in progress.
+    Busy,
+    /// No matching transaction is in progress.
+    NoTransaction,
+    /// The maximum number of neighbors is reached.
+    CapacityExceeded,
+}
+
+/// The type of a 6P message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpType {
+    Request = 0b00,
+    Response = 0b01,
+    Confirmation = 0b10,
+}
+
+/// The command of a 6P request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpCommand {
+    Add = 1,
+    Delete = 2,
+    Relocate = 3,
+    Count = 4,
+    List = 5,
+    Signal = 6,
+    Clear = 7,
+}
+
+impl TryFrom<u8> for SixpCommand {
+    type Error = SixpError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => Self::Add,
+            2 => Self::Delete,
+            3 => Self::Relocate,
+            4 => Self::Count,
+            5 => Self::List,
+            6 => Self::Signal,
+            7 => Self::Clear,
+            _ => return Err(SixpError::InvalidCode(value)),
+        })
+    }
+}
+
+/// The return code of a 6P response or confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpReturnCode {
+    /// Operation succeeded.
+    Success = 0,
+    /// End of list.
+    Eol = 1,
+    /// Generic error.
+    Err = 2,
+    /// Critical error, reset.
+    Reset = 3,
+    /// Unsupported 6P version.
+    ErrVersion = 4,
+    /// Unsupported SFID.
+    ErrSfid = 5,
+    /// Schedule inconsistency.
+    ErrSeqNum = 6,
+    /// Cell list error.
+    ErrCellList = 7,
+    /// Busy.
+    ErrBusy = 8,
+    /// Cells are locked.
+    ErrLocked = 9,
+}
+
+impl SixpReturnCode {
+    /// Return whether the code reports a successful operation.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Success | Self::Eol)
+    }
+}
+
+impl TryFrom<u8> for SixpReturnCode {
+    type Error = SixpError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Success,
+            1 => Self::Eol,
+            2 => Self::Err,
+            3 => Self::Reset,
+            4 => Self::ErrVersion,
+            5 => Self::ErrSfid,
+            6 => Self::ErrSeqNum,
+            7 => Self::ErrCellList,
+            8 => Self::ErrBusy,
+            9 => Self::ErrLocked,
+            _ => return Err(SixpError::InvalidCode(value)),
+        })
+    }
+}
+
+/// A cell, i.e. a timeslot and channel offset in the slotframe of the SF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixpCell {
+    pub slot_offset: u16,
+    pub channel_offset: u16,
+}
+
+impl SixpCell {
+    fn parse(bytes: &[u8]) -> Self {
+        Self {
+            slot_offset: u16::from_le_bytes([bytes[0], bytes[1]]),
+            channel_offset: u16::from_le_bytes([bytes[2], bytes[3]]),
+        }
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        buffer[..2].copy_from_slice(&self.slot_offset.to_le_bytes());
+        buffer[2..CELL_LEN].copy_from_slice(&self.channel_offset.to_le_bytes());
+    }
+}
+
+/// A cell list, either as received or as given by the SF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpCells<'a> {
+    /// Cells in their wire format.
+    Bytes(&'a [u8]),
+    /// Cells to be emitted.
+    Cells(&'a [SixpCell]),
+}
+
+impl<'a> SixpCells<'a> {
+    /// An empty cell list.
+    pub const EMPTY: Self = Self::Cells(&[]);
+
+    /// Return the number of cells.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Bytes(bytes) => bytes.len() / CELL_LEN,
+            Self::Cells(cells) => cells.len(),
+        }
+    }
+
+    /// Return whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return an iterator over the cells.
+    pub fn iter(&self) -> SixpCellsIterator<'a> {
+        SixpCellsIterator {
+            cells: *self,
+            index: 0,
+        }
+    }
+
+    /// Return the length of the cell list in bytes.
+    pub fn buffer_len(&self) -> usize {
+        self.len() * CELL_LEN
+    }
+
+    /// Emit the cell list into the given buffer.
+    fn emit(&self, buffer: &mut [u8]) {
+        for (cell, bytes) in self.iter().zip(buffer.chunks_exact_mut(CELL_LEN)) {
+            cell.emit(bytes);
+        }
+    }
+
+    fn parse(bytes: &'a [u8]) -> Result<Self, SixpError> {
+        if !bytes.len().is_multiple_of(CELL_LEN) {
+            return Err(SixpError::Truncated);
+        }
+        Ok(Self::Bytes(bytes))
+    }
+}
+
+/// Iterator over the cells of a [`SixpCells`] list.
+pub struct SixpCellsIterator<'a> {
+    cells: SixpCells<'a>,
+    index: usize,
+}
+
+impl Iterator for SixpCellsIterator<'_> {
+    type Item = SixpCell;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cell = match self.cells {
+            SixpCells::Bytes(bytes) => bytes
+                .get(self.index * CELL_LEN..(self.index + 1) * CELL_LEN)
+                .map(SixpCell::parse),
+            SixpCells::Cells(cells) => cells.get(self.index).copied(),
+        }?;
+        self.index += 1;
+        Some(cell)
+    }
+}
+
+/// A 6P request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpRequest<'a> {
+    /// Add `num_cells` cells, chosen from `cells`.
+    Add {
+        metadata: u16,
+        cell_options: TschLinkOption,
+        num_cells: u8,
+        cells: SixpCells<'a>,
+    },
+    /// Delete `num_cells` cells, chosen from `cells`.
+    Delete {
+        metadata: u16,
+        cell_options: TschLinkOption,
+        num_cells: u8,
+        cells: SixpCells<'a>,
+    },
+    /// Relocate the `num_cells` cells of `relocation_cells` to cells chosen
+    /// from `candidate_cells`.
+    Relocate {
+        metadata: u16,
+        cell_options: TschLinkOption,
+        num_cells: u8,
+        relocation_cells: SixpCells<'a>,
+        candidate_cells: SixpCells<'a>,
+    },
+    /// Count the scheduled cells with the given options.
+    Count {
+        metadata: u16,
+        cell_options: TschLinkOption,
+    },
+    /// List up to `max_num_cells` scheduled cells with the given options,
+    /// starting at `offset`.
+    List {
+        metadata: u16,
+        cell_options: TschLinkOption,
+        offset: u16,
+        max_num_cells: u16,
+    },
+    /// Exchange SF-specific data.
+    Signal { metadata: u16, payload: &'a [u8] },
+    /// Clear all cells scheduled with the neighbor.
+    Clear { metadata: u16 },
+}
+
+impl<'a> SixpRequest<'a> {
+    /// Return the command of the request.
+    pub fn command(&self) -> SixpCommand {
+        match self {
+            Self::Add { .. } => SixpCommand::Add,
+            Self::Delete { .. } => SixpCommand::Delete,
+            Self::Relocate { .. } => SixpCommand::Relocate,
+            Self::Count { .. } => SixpCommand::Count,
+            Self::List { .. } => SixpCommand::List,
+            Self::Signal { .. } => SixpCommand::Signal,
+            Self::Clear { .. } => SixpCommand::Clear,
+        }
+    }
+
+    /// Return whether the request starts a three-step transaction, i.e. the
+    /// requester leaves the choice of the candidate cells to the responder.
+    pub fn is_three_step(&self) -> bool {
+        match self {
+            Self::Add {
+                num_cells, cells, ..
+            }
+            | Self::Delete {
+                num_cells, cells, ..
+            } => *num_cells > 0 && cells.is_empty(),
+            Self::Relocate {
+                candidate_cells, ..
+            } => candidate_cells.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Return the length of the request fields in bytes.
+    pub fn buffer_len(&self) -> usize {
+        METADATA_LEN
+            + match self {
+                Self::Add { cells, .. } | Self::Delete { cells, .. } => 2 + cells.buffer_len(),
+                Self::Relocate {
+                    relocation_cells,
+                    candidate_cells,
+                    ..
+                } => 2 + relocation_cells.buffer_len() + candidate_cells.buffer_len(),
+                Self::Count { .. } => 1,
+                Self::List { .. } => 6,
+                Self::Signal { payload, .. } => payload.len(),
+                Self::Clear { .. } => 0,
+            }
+    }
+
+    fn parse(command: SixpCommand, bytes: &'a [u8]) -> Result<Self, SixpError> {
+        if bytes.len() < METADATA_LEN {
+            return Err(SixpError::Truncated);
+        }
+        let metadata = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let bytes = &bytes[METADATA_LEN..];
+        let cell_options = || {
+            bytes
+                .first()
+                .map(|options| TschLinkOption::from_bits_truncate(options & CELL_OPTIONS_MASK))
+                .ok_or(SixpError::Truncated)
+        };
+
+        Ok(match command {
+            SixpCommand::Add | SixpCommand::Delete => {
+                let cell_options = cell_options()?;
+                let num_cells = *bytes.get(1).ok_or(SixpError::Truncated)?;
+                let cells = SixpCells::parse(&bytes[2..])?;
+                if command == SixpCommand::Add {
+                    Self::Add {
+                        metadata,
+                        cell_options,
+                        num_cells,
+                        cells,
+                    }
+                } else {
+                    Self::Delete {
+                        metadata,
+                        cell_options,
+                        num_cells,
+                        cells,
+                    }
+                }
+            }
+            SixpCommand::Relocate => {
+                let cell_options = cell_options()?;
+                let num_cells = *bytes.get(1).ok_or(SixpError::Truncated)?;
+                let split = 2 + num_cells as usize * CELL_LEN;
+                if bytes.len() < split {
+                    return Err(SixpError::Truncated);
+                }
+                Self::Relocate {
+                    metadata,
+                    cell_options,
+                    num_cells,
+                    relocation_cells: SixpCells::parse(&bytes[2..split])?,
+                    candidate_cells: SixpCells::parse(&bytes[split..])?,
+                }
+            }
+            SixpCommand::Count => Self::Count {
+                metadata,
+                cell_options: cell_options()?,
+            },
+            SixpCommand::List => {
+                if bytes.len() < 6 {
+                    return Err(SixpError::Truncated);
+                }
+                Self::List {
+                    metadata,
+                    cell_options: cell_options()?,
+                    offset: u16::from_le_bytes([bytes[2], bytes[3]]),
+                    max_num_cells: u16::from_le_bytes([bytes[4], bytes[5]]),
+                }
+            }
+            SixpCommand::Signal => Self::Signal {
+                metadata,
+                payload: bytes,
+            },
+            SixpCommand::Clear => Self::Clear { metadata },
+        })
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        let metadata = match self {
+            Self::Add { metadata, .. }
+            | Self::Delete { metadata, .. }
+            | Self::Relocate { metadata, .. }
+            | Self::Count { metadata, .. }
+            | Self::List { metadata, .. }
+            | Self::Signal { metadata, .. }
+            | Self::Clear { metadata } => *metadata,
+        };
+        buffer[..METADATA_LEN].copy_from_slice(&metadata.to_le_bytes());
+        let buffer = &mut buffer[METADATA_LEN..];
+
+        match self {
+            Self::Add {
+                cell_options,
+                num_cells,
+                cells,
+                ..
+            }
+            | Self::Delete {
+                cell_options,
+                num_cells,
+                cells,
+                ..
+            } => {
+                buffer[0] = cell_options.bits() & CELL_OPTIONS_MASK;
+                buffer[1] = *num_cells;
+                cells.emit(&mut buffer[2..]);
+            }
+            Self::Relocate {
+                cell_options,
+                num_cells,
+                relocation_cells,
+                candidate_cells,
+                ..
+            } => {
+                buffer[0] = cell_options.bits() & CELL_OPTIONS_MASK;
+                buffer[1] = *num_cells;
+                let split = 2 + relocation_cells.buffer_len();
+                relocation_cells.emit(&mut buffer[2..split]);
+                candidate_cells.emit(&mut buffer[split..]);
+            }
+            Self::Count { cell_options, .. } => {
+                buffer[0] = cell_options.bits() & CELL_OPTIONS_MASK;
+            }
+            Self::List {
+                cell_options,
+                offset,
+                max_num_cells,
+                ..
+            } => {
+                buffer[0] = cell_options.bits() & CELL_OPTIONS_MASK;
+                buffer[1] = 0;
+                buffer[2..4].copy_from_slice(&offset.to_le_bytes());
+                buffer[4..6].copy_from_slice(&max_num_cells.to_le_bytes());
+            }
+            Self::Signal { payload, .. } => buffer[..payload.len()].copy_from_slice(payload),
+            Self::Clear { .. } => (),
+        }
+    }
+}
+
+/// The fields of a 6P response or confirmation following the return code.
+///
+/// Their format depends on the command of the transaction, hence received
+/// fields are kept raw and interpreted on access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpResponseFields<'a> {
+    /// No fields, e.g. in error responses.
+    Empty,
+    /// Fields as received or opaque SF data, e.g. for SIGNAL.
+    Raw(&'a [u8]),
+    /// A cell list, for ADD, DELETE, RELOCATE and LIST.
+    Cells(SixpCells<'a>),
+    /// The number of cells, for COUNT.
+    NumCells(u16),
+}
+
+impl<'a> SixpResponseFields<'a> {
+    /// Return the fields as a cell list.
+    pub fn cells(&self) -> Option<SixpCells<'a>> {
+        match self {
+            Self::Empty => Some(SixpCells::EMPTY),
+            Self::Raw(bytes) => SixpCells::parse(bytes).ok(),
+            Self::Cells(cells) => Some(*cells),
+            Self::NumCells(_) => None,
+        }
+    }
+
+    /// Return the fields as a number of cells.
+    pub fn num_cells(&self) -> Option<u16> {
+        match *self {
+            Self::Raw(&[lsb, msb]) => Some(u16::from_le_bytes([lsb, msb])),
+            Self::NumCells(num_cells) => Some(num_cells),
+            _ => None,
+        }
+    }
+
+    /// Return the length of the fields in bytes.
+    pub fn buffer_len(&self) -> usize {
+        match self {
+            Self::Empty => 0,
+            Self::Raw(bytes) => bytes.len(),
+            Self::Cells(cells) => cells.buffer_len(),
+            Self::NumCells(_) => 2,
+        }
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Empty => (),
+            Self::Raw(bytes) => buffer[..bytes.len()].copy_from_slice(bytes),
+            Self::Cells(cells) => cells.emit(buffer),
+            Self::NumCells(num_cells) => buffer[..2].copy_from_slice(&num_cells.to_le_bytes()),
+        }
+    }
+}
+
+/// A 6P response or confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixpResponse<'a> {
+    pub code: SixpReturnCode,
+    pub fields: SixpResponseFields<'a>,
+}
+
+/// The content of a 6P message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpBody<'a> {
+    Request(SixpRequest<'a>),
+    Response(SixpResponse<'a>),
+    Confirmation(SixpResponse<'a>),
+}
+
+/// A 6P message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SixpMessage<'a> {
+    /// Identifier of the scheduling function.
+    pub sfid: u8,
+    /// Sequence number of the transaction.
+    pub seq_num: u8,
+    pub body: SixpBody<'a>,
+}
+
+impl<'a> SixpMessage<'a> {
+    /// Return the type of the message.
+    pub fn message_type(&self) -> SixpType {
+        match self.body {
+            SixpBody::Request(_) => SixpType::Request,
+            SixpBody::Response(_) => SixpType::Response,
+            SixpBody::Confirmation(_) => SixpType::Confirmation,
+        }
+    }
+
+    /// Parse a 6P message, i.e. the content of a 6top IE following the
+    /// sub-ID.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SixpError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(SixpError::Truncated);
+        }
+        let version = bytes[0] & 0x0f;
+        if version != SIXP_VERSION {
+            return Err(SixpError::UnsupportedVersion(version));
+        }
+        let code = bytes[1];
+        let sfid = bytes[2];
+        let seq_num = bytes[3];
+        let fields = &bytes[HEADER_LEN..];
+
+        let response = || -> Result<SixpResponse<'a>, SixpError> {
+            Ok(SixpResponse {
+                code: SixpReturnCode::try_from(code)?,
+                fields: if fields.is_empty() {
+                    SixpResponseFields::Empty
+                } else {
+                    SixpResponseFields::Raw(fields)
+                },
+            })
+        };
+        let body = match (bytes[0] >> 4) & 0b11 {
+            0b00 => SixpBody::Request(SixpRequest::parse(SixpCommand::try_from(code)?, fields)?),
+            0b01 => SixpBody::Response(response()?),
+            0b10 => SixpBody::Confirmation(response()?),
+            _ => return Err(SixpError::InvalidType),
+        };
+
+        Ok(Self {
+            sfid,
+            seq_num,
+            body,
+        })
+    }
+
+    /// Return the length of the message in bytes.
+    pub fn buffer_len(&self) -> usize {
+        HEADER_LEN
+            + match &self.body {
+                SixpBody::Request(request) => request.buffer_len(),
+                SixpBody::Response(response) | SixpBody::Confirmation(response) => {
+                    response.fields.buffer_len()
+                }
+            }
+    }
+
+    /// Emit the message into the given buffer and return its length.
+    pub fn emit(&self, buffer: &mut [u8]) -> Result<usize, SixpError> {
+        let len = self.buffer_len();
+        if buffer.len() < len {
+            return Err(SixpError::BufferTooSmall);
+        }
+        buffer[0] = SIXP_VERSION | ((self.message_type() as u8) << 4);
+        buffer[1] = match &self.body {
+            SixpBody::Request(request) => request.command() as u8,
+            SixpBody::Response(response) | SixpBody::Confirmation(response) => response.code as u8,
+        };
+        buffer[2] = self.sfid;
+        buffer[3] = self.seq_num;
+        let fields = &mut buffer[HEADER_LEN..len];
+        match &self.body {
+            SixpBody::Request(request) => request.emit(fields),
+            SixpBody::Response(response) | SixpBody::Confirmation(response) => {
+                response.fields.emit(fields)
+            }
+        }
+        Ok(len)
+    }
+
+    /// Parse a 6P message from an IETF payload IE, including its header.
+    pub fn parse_ie(ie: &'a [u8]) -> Result<Self, SixpError> {
+        if ie.len() < IE_HEADER_LEN + 1 {
+            return Err(SixpError::Truncated);
+        }
+        let header = u16::from_le_bytes([ie[0], ie[1]]);
+        let len = (header & 0x7ff) as usize;
+        if header >> 15 != 1
+            || (header >> 11) & 0xf != IETF_PAYLOAD_IE_GROUP_ID
+            || ie[IE_HEADER_LEN] != SIXTOP_IE_SUB_ID
+        {
+            return Err(SixpError::NotSixp);
+        }
+        let content = ie
+            .get(IE_HEADER_LEN + 1..IE_HEADER_LEN + len)
+            .ok_or(SixpError::Truncated)?;
+        Self::parse(content)
+    }
+
+    /// Return the length of the IETF payload IE carrying the message.
+    pub fn ie_buffer_len(&self) -> usize {
+        IE_HEADER_LEN + 1 + self.buffer_len()
+    }
+
+    /// Emit the message as IETF payload IE and return its length.
+    pub fn emit_ie(&self, buffer: &mut [u8]) -> Result<usize, SixpError> {
+        if buffer.len() < self.ie_buffer_len() {
+            return Err(SixpError::BufferTooSmall);
+        }
+        let len = 1 + self.emit(&mut buffer[IE_HEADER_LEN + 1..])?;
+        let header = (1 << 15) | (IETF_PAYLOAD_IE_GROUP_ID << 11) | len as u16;
+        buffer[..IE_HEADER_LEN].copy_from_slice(&header.to_le_bytes());
+        buffer[IE_HEADER_LEN] = SIXTOP_IE_SUB_ID;
+        Ok(IE_HEADER_LEN + len)
+    }
+}
+
+/// Build a data frame carrying the given 6P message.
+///
+/// * `sequence_number` - Sequence number of the frame
+/// * `pan_id` - PAN ID of the network
+/// * `src_address` - Extended address of the device (little endian)
+/// * `dst_address` - Extended address of the neighbor (little endian)
+/// * `message` - The 6P message
+pub fn sixp_frame(
+    sequence_number: u8,
+    pan_id: u16,
+    src_address: &[u8; 8],
+    dst_address: &[u8; 8],
+    message: &SixpMessage,
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let mut ies = [0; PHY_MAX_PACKET_SIZE_127];
+    ies[..IE_HEADER_LEN].copy_from_slice(&HEADER_TERMINATION_IE_1);
+    let ies_len = IE_HEADER_LEN
+        + message
+            .emit_ie(&mut ies[IE_HEADER_LEN..])
+            .map_err(|_| FrameError::from(FrameErrorKind::MalformedIe))?;
+
+    let pan_id = PanId::from_u16(pan_id);
+    let builder = FrameBuilder::new(FrameType::Data)
+        .with_sequence_number(sequence_number)
+        .with_ack_request(true)
+        .with_addressing(
+            Some((
+                pan_id,
+                Address::Extended(ExtendedAddress::new(&dst_address[..])),
+            )),
+            Some((
+                pan_id,
+                Address::Extended(ExtendedAddress::new(&src_address[..])),
+            )),
+        )
+        .without_security()
+        .with_ies(&ies[..ies_len])
+        .without_payload();
+    FrameBuffer::from_builder(&builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CELLS: [SixpCell; 2] = [
+        SixpCell {
+            slot_offset: 0x0102,
+            channel_offset: 3,
+        },
+        SixpCell {
+            slot_offset: 7,
+            channel_offset: 0x0405,
+        },
+    ];
+
+    #[test]
+    fn add_request() {
+        let message = SixpMessage {
+            sfid: 0,
+            seq_num: 10,
+            body: SixpBody::Request(SixpRequest::Add {
+                metadata: 0x1234,
+                cell_options: TschLinkOption::Tx,
+                num_cells: 1,
+                cells: SixpCells::Cells(&CELLS),
+            }),
+        };
+        let bytes = [
+            0x11, 0xa8, // IE header (group ID 0x5, length 17)
+            0xc9, // 6top sub-ID
+            0x00, // version and type
+            0x01, // ADD
+            0x00, // SFID
+            0x0a, // SeqNum
+            0x34, 0x12, // metadata
+            0x01, // cell options
+            0x01, // NumCells
+            0x02, 0x01, 0x03, 0x00, // cell 1
+            0x07, 0x00, 0x05, 0x04, // cell 2
+        ];
+
+        let mut buffer = [0; 32];
+        assert_eq!(message.ie_buffer_len(), bytes.len());
+        assert_eq!(message.emit_ie(&mut buffer), Ok(bytes.len()));
+        assert_eq!(&buffer[..bytes.len()], &bytes[..]);
+
+        let parsed = SixpMessage::parse_ie(&bytes).unwrap();
+        let SixpBody::Request(request) = parsed.body else {
+            panic!("not a request");
+        };
+        assert_eq!(request.command(), SixpCommand::Add);
+        assert!(!request.is_three_step());
+        let SixpRequest::Add {
+            metadata, cells, ..
+        } = request
+        else {
+            unreachable!();
+        };
+        assert_eq!(metadata, 0x1234);
+        assert!(cells.iter().eq(CELLS.iter().copied()));
+        assert_eq!(parsed.seq_num, 10);
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = [
+            SixpRequest::Delete {
+                metadata: 1,
+                cell_options: TschLinkOption::Rx | TschLinkOption::Shared,
+                num_cells: 2,
+                cells: SixpCells::EMPTY,
+            },
+            SixpRequest::Relocate {
+                metadata: 2,
+                cell_options: TschLinkOption::Tx,
+                num_cells: 1,
+                relocation_cells: SixpCells::Cells(&CELLS[..1]),
+                candidate_cells: SixpCells::Cells(&CELLS[1..]),
+            },
+            SixpRequest::Count {
+                metadata: 3,
+                cell_options: TschLinkOption::Tx,
+            },
+            SixpRequest::List {
+                metadata: 4,
+                cell_options: TschLinkOption::Rx,
+                offset: 5,
+                max_num_cells: 6,
+            },
+            SixpRequest::Signal {
+                metadata: 5,
+                payload: &[1, 2, 3],
+            },
+            SixpRequest::Clear { metadata: 6 },
+        ];
+
+        for request in requests {
+            let message = SixpMessage {
+                sfid: 1,
+                seq_num: 2,
+                body: SixpBody::Request(request),
+            };
+            let mut buffer = [0; 32];
+            let len = message.emit(&mut buffer).unwrap();
+            let parsed = SixpMessage::parse(&buffer[..len]).unwrap();
+            let SixpBody::Request(parsed_request) = parsed.body else {
+                panic!("not a request");
+            };
+            assert_eq!(parsed_request.command(), request.command());
+            assert_eq!(parsed_request.buffer_len(), request.buffer_len());
+            assert_eq!(parsed_request.is_three_step(), request.is_three_step());
+        }
+    }
+
+    #[test]
+    fn response() {
+        let message = SixpMessage {
+            sfid: 0,
+            seq_num: 3,
+            body: SixpBody::Response(SixpResponse {
+                code: SixpReturnCode::Success,
+                fields: SixpResponseFields::NumCells(0x0203),
+            }),
+        };
+        let mut buffer = [0; 8];
+        assert_eq!(message.emit(&mut buffer), Ok(6));
+        assert_eq!(&buffer[..6], &[0x10, 0x00, 0x00, 0x03, 0x03, 0x02]);
+
+        let parsed = SixpMessage::parse(&buffer[..6]).unwrap();
+        let SixpBody::Response(response) = parsed.body else {
+            panic!("not a response");
+        };
+        assert_eq!(response.fields.num_cells(), Some(0x0203));
+
+        assert_eq!(
+            SixpMessage::parse(&[0x01, 0x00, 0x00, 0x00]),
+            Err(SixpError::UnsupportedVersion(1))
+        );
+        assert_eq!(
+            SixpMessage::parse(&[0x20, 0x0a, 0x00, 0x00]),
+            Err(SixpError::InvalidCode(10))
+        );
+        assert_eq!(
+            SixpMessage::parse(&[0x30, 0x00, 0x00, 0x00]),
+            Err(SixpError::InvalidType)
+        );
+    }
+}
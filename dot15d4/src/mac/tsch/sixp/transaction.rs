@@ -0,0 +1,599 @@
+//! 6P transactions (RFC 8480, section 3.4).
+//!
+//! A 6P transaction is either two-step (request, response) or three-step
+//! (request, response, confirmation). The latter is used when the requester
+//! leaves the choice of the cells to the responder. [`SixpTransactions`] is a
+//! sans-IO state machine: it consumes received messages, returns the events
+//! the scheduling function (SF) has to act upon, and builds the messages to
+//! send.
+//!
+//! The following rules apply:
+//! - At most one transaction is in progress with a given neighbor. Requests
+//!   received while a transaction is in progress are rejected with
+//!   [`SixpReturnCode::ErrBusy`].
+//! - Each neighbor has a SeqNum, incremented by every transaction and reset
+//!   to zero by CLEAR. It wraps from 0xff to 0x01. A request with SeqNum zero
+//!   from a neighbor whose SeqNum is not zero reveals that the neighbor was
+//!   reset; it is rejected with [`SixpReturnCode::ErrSeqNum`] and the SF
+//!   should clear the schedule with that neighbor.
+//! - Transactions that don't complete within the timeout are aborted.
+#![allow(dead_code)]
+
+use crate::mac::tsch::asn::AbsoluteSlotNumber;
+
+use super::{
+    SixpBody, SixpCommand, SixpError, SixpMessage, SixpRequest, SixpResponse, SixpResponseFields,
+    SixpReturnCode,
+};
+
+/// Default transaction timeout in timeslots, i.e. 10s with the default
+/// timeslot length.
+pub const DEFAULT_TRANSACTION_TIMEOUT: u32 = 1000;
+
+/// The state of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SixpState {
+    /// The request was sent, waiting for the response.
+    WaitResponse,
+    /// The request was received, the SF has to respond.
+    SendResponse,
+    /// The response was sent, waiting for the confirmation.
+    WaitConfirmation,
+    /// The response was received, the SF has to confirm.
+    SendConfirmation,
+}
+
+/// A transaction in progress.
+#[derive(Debug, Clone, Copy)]
+struct SixpTransaction {
+    command: SixpCommand,
+    sfid: u8,
+    seq_num: u8,
+    three_step: bool,
+    state: SixpState,
+    /// ASN after which the transaction is aborted.
+    deadline: i64,
+}
+
+/// A neighbor with which 6P transactions are held.
+#[derive(Debug, Clone, Copy)]
+struct SixpNeighbor {
+    address: [u8; 8],
+    /// SeqNum of the next transaction.
+    seq_num: u8,
+    transaction: Option<SixpTransaction>,
+}
+
+/// An event the SF has to act upon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SixpEvent<'a> {
+    /// A request was received. Answer it with
+    /// [`SixpTransactions::respond()`].
+    Request { sfid: u8, request: SixpRequest<'a> },
+    /// The response to a request was received. Three-step transactions must
+    /// be completed with [`SixpTransactions::confirm()`].
+    Response {
+        command: SixpCommand,
+        response: SixpResponse<'a>,
+    },
+    /// The confirmation of a three-step transaction was received.
+    Confirmation {
+        command: SixpCommand,
+        response: SixpResponse<'a>,
+    },
+    /// The message was rejected, send the given error response.
+    Reject(SixpMessage<'static>),
+    /// The message doesn't belong to any transaction and is dropped.
+    Ignored,
+}
+
+/// The 6P transactions with up to `N` neighbors, see the module
+/// documentation.
+pub struct SixpTransactions<const N: usize> {
+    neighbors: heapless::Vec<SixpNeighbor, N>,
+    /// Number of timeslots after which a transaction is aborted.
+    timeout: u32,
+}
+
+impl<const N: usize> Default for SixpTransactions<N> {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRANSACTION_TIMEOUT)
+    }
+}
+
+impl<const N: usize> SixpTransactions<N> {
+    /// Creates a new [`SixpTransactions`] without any transaction.
+    ///
+    /// * `timeout` - Timeslots after which a transaction is aborted
+    pub fn new(timeout: u32) -> Self {
+        Self {
+            neighbors: heapless::Vec::new(),
+            timeout,
+        }
+    }
+
+    /// Return whether a transaction with the given neighbor is in progress.
+    pub fn is_busy(&self, address: &[u8; 8]) -> bool {
+        self.neighbor(address)
+            .is_some_and(|neighbor| neighbor.transaction.is_some())
+    }
+
+    /// Return the SeqNum of the next transaction with the given neighbor.
+    pub fn seq_num(&self, address: &[u8; 8]) -> u8 {
+        self.neighbor(address)
+            .map_or(0, |neighbor| neighbor.seq_num)
+    }
+
+    /// Start a transaction with the given neighbor and return the request to
+    /// send.
+    ///
+    /// * `address` - Extended address of the neighbor (little endian)
+    /// * `sfid` - Identifier of the SF
+    /// * `request` - The request
+    /// * `asn` - Current absolute slot number
+    pub fn request<'a>(
+        &mut self,
+        address: &[u8; 8],
+        sfid: u8,
+        request: SixpRequest<'a>,
+        asn: AbsoluteSlotNumber,
+    ) -> Result<SixpMessage<'a>, SixpError> {
+        let deadline = to_i64(asn) + self.timeout as i64;
+        let neighbor = self.neighbor_or_insert(address)?;
+        if neighbor.transaction.is_some() {
+            return Err(SixpError::Busy);
+        }
+
+        let seq_num = neighbor.seq_num;
+        neighbor.seq_num = next_seq_num(seq_num);
+        neighbor.transaction = Some(SixpTransaction {
+            command: request.command(),
+            sfid,
+            seq_num,
+            three_step: request.is_three_step(),
+            state: SixpState::WaitResponse,
+            deadline,
+        });
+
+        Ok(SixpMessage {
+            sfid,
+            seq_num,
+            body: SixpBody::Request(request),
+        })
+    }
+
+    /// Process a message received from the given neighbor.
+    ///
+    /// * `address` - Extended address of the neighbor (little endian)
+    /// * `message` - The received message
+    /// * `asn` - Current absolute slot number
+    pub fn on_message<'a>(
+        &mut self,
+        address: &[u8; 8],
+        message: SixpMessage<'a>,
+        asn: AbsoluteSlotNumber,
+    ) -> SixpEvent<'a> {
+        let deadline = to_i64(asn) + self.timeout as i64;
+        let reject = |code| {
+            SixpEvent::Reject(SixpMessage {
+                sfid: message.sfid,
+                seq_num: message.seq_num,
+                body: SixpBody::Response(SixpResponse {
+                    code,
+                    fields: SixpResponseFields::Empty,
+                }),
+            })
+        };
+
+        match message.body {
+            SixpBody::Request(request) => {
+                let Ok(neighbor) = self.neighbor_or_insert(address) else {
+                    return reject(SixpReturnCode::ErrBusy);
+                };
+                if neighbor.transaction.is_some() {
+                    return reject(SixpReturnCode::ErrBusy);
+                }
+                if message.seq_num == 0
+                    && neighbor.seq_num != 0
+                    && request.command() != SixpCommand::Clear
+                {
+                    return reject(SixpReturnCode::ErrSeqNum);
+                }
+
+                neighbor.seq_num = next_seq_num(message.seq_num);
+                neighbor.transaction = Some(SixpTransaction {
+                    command: request.command(),
+                    sfid: message.sfid,
+                    seq_num: message.seq_num,
+                    three_step: request.is_three_step(),
+                    state: SixpState::SendResponse,
+                    deadline,
+                });
+                SixpEvent::Request {
+                    sfid: message.sfid,
+                    request,
+                }
+            }
+            SixpBody::Response(response) => {
+                let Some(transaction) =
+                    self.transaction(address, message.seq_num, SixpState::WaitResponse)
+                else {
+                    return SixpEvent::Ignored;
+                };
+                let command = transaction.command;
+                if transaction.three_step && response.code.is_success() {
+                    transaction.state = SixpState::SendConfirmation;
+                    transaction.deadline = deadline;
+                } else {
+                    self.complete(address);
+                }
+                SixpEvent::Response { command, response }
+            }
+            SixpBody::Confirmation(response) => {
+                let Some(transaction) =
+                    self.transaction(address, message.seq_num, SixpState::WaitConfirmation)
+                else {
+                    return SixpEvent::Ignored;
+                };
+                let command = transaction.command;
+                self.complete(address);
+                SixpEvent::Confirmation { command, response }
+            }
+        }
+    }
+
+    /// Respond to the request received from the given neighbor and return the
+    /// response to send.
+    ///
+    /// * `address` - Extended address of the neighbor (little endian)
+    /// * `code` - Return code
+    /// * `fields` - Fields of the response, depending on the command
+    /// * `asn` - Current absolute slot number
+    pub fn respond<'a>(
+        &mut self,
+        address: &[u8; 8],
+        code: SixpReturnCode,
+        fields: SixpResponseFields<'a>,
+        asn: AbsoluteSlotNumber,
+    ) -> Result<SixpMessage<'a>, SixpError> {
+        let deadline = to_i64(asn) + self.timeout as i64;
+        let transaction = self
+            .pending(address, SixpState::SendResponse)
+            .ok_or(SixpError::NoTransaction)?;
+        let message = SixpMessage {
+            sfid: transaction.sfid,
+            seq_num: transaction.seq_num,
+            body: SixpBody::Response(SixpResponse { code, fields }),
+        };
+
+        if transaction.three_step && code.is_success() {
+            transaction.state = SixpState::WaitConfirmation;
+            transaction.deadline = deadline;
+        } else {
+            self.complete(address);
+        }
+        Ok(message)
+    }
+
+    /// Confirm the response received from the given neighbor in a
+    /// three-step transaction and return the confirmation to send.
+    ///
+    /// * `address` - Extended address of the neighbor (little endian)
+    /// * `code` - Return code
+    /// * `fields` - Fields of the confirmation, i.e. the selected cells
+    pub fn confirm<'a>(
+        &mut self,
+        address: &[u8; 8],
+        code: SixpReturnCode,
+        fields: SixpResponseFields<'a>,
+    ) -> Result<SixpMessage<'a>, SixpError> {
+        let transaction = self
+            .pending(address, SixpState::SendConfirmation)
+            .ok_or(SixpError::NoTransaction)?;
+        let message = SixpMessage {
+            sfid: transaction.sfid,
+            seq_num: transaction.seq_num,
+            body: SixpBody::Confirmation(SixpResponse { code, fields }),
+        };
+        self.complete(address);
+        Ok(message)
+    }
+
+    /// Abort a transaction that timed out at the given ASN, if any, and
+    /// return the neighbor and command of the aborted transaction.
+    ///
+    /// * `asn` - Current absolute slot number
+    pub fn poll_timeout(&mut self, asn: AbsoluteSlotNumber) -> Option<([u8; 8], SixpCommand)> {
+        let asn = to_i64(asn);
+        let neighbor = self.neighbors.iter_mut().find(|neighbor| {
+            neighbor
+                .transaction
+                .is_some_and(|transaction| asn > transaction.deadline)
+        })?;
+        let transaction = neighbor.transaction.take()?;
+        Some((neighbor.address, transaction.command))
+    }
+
+    /// Abort the transaction with the given neighbor, e.g. because the frame
+    /// carrying a message could not be sent.
+    pub fn abort(&mut self, address: &[u8; 8]) {
+        if let Some(neighbor) = self.neighbor_mut(address) {
+            neighbor.transaction = None;
+        }
+    }
+
+    /// Forget the given neighbor.
+    pub fn remove(&mut self, address: &[u8; 8]) {
+        self.neighbors
+            .retain(|neighbor| &neighbor.address != address);
+    }
+
+    /// Return the transaction with the given neighbor if it matches the given
+    /// SeqNum and state.
+    fn transaction(
+        &mut self,
+        address: &[u8; 8],
+        seq_num: u8,
+        state: SixpState,
+    ) -> Option<&mut SixpTransaction> {
+        self.pending(address, state)
+            .filter(|transaction| transaction.seq_num == seq_num)
+    }
+
+    /// Return the transaction with the given neighbor if it is in the given
+    /// state.
+    fn pending(&mut self, address: &[u8; 8], state: SixpState) -> Option<&mut SixpTransaction> {
+        self.neighbor_mut(address)?
+            .transaction
+            .as_mut()
+            .filter(|transaction| transaction.state == state)
+    }
+
+    /// Complete the transaction with the given neighbor. CLEAR resets the
+    /// SeqNum.
+    fn complete(&mut self, address: &[u8; 8]) {
+        if let Some(neighbor) = self.neighbor_mut(address) {
+            if let Some(transaction) = neighbor.transaction.take() {
+                if transaction.command == SixpCommand::Clear {
+                    neighbor.seq_num = 0;
+                }
+            }
+        }
+    }
+
+    fn neighbor(&self, address: &[u8; 8]) -> Option<&SixpNeighbor> {
+        self.neighbors
+            .iter()
+            .find(|neighbor| &neighbor.address == address)
+    }
+
+    fn neighbor_mut(&mut self, address: &[u8; 8]) -> Option<&mut SixpNeighbor> {
+        self.neighbors
+            .iter_mut()
+            .find(|neighbor| &neighbor.address == address)
+    }
+
+    fn neighbor_or_insert(&mut self, address: &[u8; 8]) -> Result<&mut SixpNeighbor, SixpError> {
+        if let Some(index) = self
+            .neighbors
+            .iter()
+            .position(|neighbor| &neighbor.address == address)
+        {
+            return Ok(&mut self.neighbors[index]);
+        }
+        self.neighbors
+            .push(SixpNeighbor {
+                address: *address,
+                seq_num: 0,
+                transaction: None,
+            })
+            .map_err(|_| SixpError::CapacityExceeded)?;
+        Ok(self.neighbors.last_mut().unwrap())
+    }
+}
+
+/// Return the SeqNum following the given one.
+fn next_seq_num(seq_num: u8) -> u8 {
+    if seq_num == u8::MAX {
+        1
+    } else {
+        seq_num + 1
+    }
+}
+
+fn to_i64(asn: AbsoluteSlotNumber) -> i64 {
+    // Safety: The conversion never fails.
+    asn.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        frame::fields::TschLinkOption,
+        tsch::sixp::{SixpCell, SixpCells},
+    };
+
+    use super::*;
+
+    const A: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+    const B: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 2];
+
+    const CELLS: [SixpCell; 1] = [SixpCell {
+        slot_offset: 5,
+        channel_offset: 1,
+    }];
+
+    fn asn(asn: u32) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    fn add(cells: SixpCells) -> SixpRequest {
+        SixpRequest::Add {
+            metadata: 0,
+            cell_options: TschLinkOption::Tx,
+            num_cells: 1,
+            cells,
+        }
+    }
+
+    #[test]
+    fn two_step() {
+        let mut a = SixpTransactions::<2>::default();
+        let mut b = SixpTransactions::<2>::default();
+
+        let request = a
+            .request(&B, 0, add(SixpCells::Cells(&CELLS)), asn(0))
+            .unwrap();
+        assert_eq!(request.seq_num, 0);
+        assert!(a.is_busy(&B));
+        assert_eq!(
+            a.request(&B, 0, add(SixpCells::EMPTY), asn(0)),
+            Err(SixpError::Busy)
+        );
+
+        assert!(matches!(
+            b.on_message(&A, request, asn(1)),
+            SixpEvent::Request {
+                request: SixpRequest::Add { .. },
+                ..
+            }
+        ));
+        let response = b
+            .respond(
+                &A,
+                SixpReturnCode::Success,
+                SixpResponseFields::Cells(SixpCells::Cells(&CELLS)),
+                asn(2),
+            )
+            .unwrap();
+        assert!(!b.is_busy(&A));
+
+        assert!(matches!(
+            a.on_message(&B, response, asn(3)),
+            SixpEvent::Response {
+                command: SixpCommand::Add,
+                ..
+            }
+        ));
+        assert!(!a.is_busy(&B));
+        assert_eq!(a.seq_num(&B), 1);
+        assert_eq!(b.seq_num(&A), 1);
+
+        // A duplicate response is dropped.
+        assert_eq!(a.on_message(&B, response, asn(4)), SixpEvent::Ignored);
+    }
+
+    #[test]
+    fn three_step() {
+        let mut a = SixpTransactions::<1>::default();
+        let mut b = SixpTransactions::<1>::default();
+
+        let request = a.request(&B, 0, add(SixpCells::EMPTY), asn(0)).unwrap();
+        b.on_message(&A, request, asn(1));
+        let response = b
+            .respond(
+                &A,
+                SixpReturnCode::Success,
+                SixpResponseFields::Cells(SixpCells::Cells(&CELLS)),
+                asn(2),
+            )
+            .unwrap();
+        assert!(b.is_busy(&A));
+
+        a.on_message(&B, response, asn(3));
+        assert!(a.is_busy(&B));
+        let confirmation = a
+            .confirm(
+                &B,
+                SixpReturnCode::Success,
+                SixpResponseFields::Cells(SixpCells::Cells(&CELLS)),
+            )
+            .unwrap();
+        assert!(!a.is_busy(&B));
+
+        assert!(matches!(
+            b.on_message(&A, confirmation, asn(4)),
+            SixpEvent::Confirmation { .. }
+        ));
+        assert!(!b.is_busy(&A));
+    }
+
+    #[test]
+    fn concurrency_and_seq_num() {
+        let mut a = SixpTransactions::<1>::default();
+        let mut b = SixpTransactions::<1>::default();
+
+        // Concurrent requests are rejected.
+        let request = a.request(&B, 0, add(SixpCells::EMPTY), asn(0)).unwrap();
+        let concurrent = b.request(&A, 0, add(SixpCells::EMPTY), asn(0)).unwrap();
+        let SixpEvent::Reject(reject) = b.on_message(&A, request, asn(1)) else {
+            panic!("request not rejected");
+        };
+        assert!(matches!(
+            reject.body,
+            SixpBody::Response(SixpResponse {
+                code: SixpReturnCode::ErrBusy,
+                ..
+            })
+        ));
+        assert!(matches!(
+            a.on_message(&B, concurrent, asn(1)),
+            SixpEvent::Reject(_)
+        ));
+        assert!(matches!(
+            a.on_message(&B, reject, asn(2)),
+            SixpEvent::Response { .. }
+        ));
+        assert!(!a.is_busy(&B));
+        b.abort(&A);
+
+        // A reset neighbor restarts with SeqNum 0.
+        let mut reset = SixpTransactions::<1>::default();
+        let request = reset.request(&B, 0, add(SixpCells::EMPTY), asn(3)).unwrap();
+        assert!(matches!(
+            b.on_message(&A, request, asn(4)),
+            SixpEvent::Reject(SixpMessage {
+                body: SixpBody::Response(SixpResponse {
+                    code: SixpReturnCode::ErrSeqNum,
+                    ..
+                }),
+                ..
+            })
+        ));
+
+        // CLEAR resets the SeqNum.
+        let clear = reset.request(&B, 0, SixpRequest::Clear { metadata: 0 }, asn(5));
+        assert_eq!(clear, Err(SixpError::Busy));
+        reset.abort(&B);
+        let clear = reset
+            .request(&B, 0, SixpRequest::Clear { metadata: 0 }, asn(5))
+            .unwrap();
+        b.on_message(&A, clear, asn(6));
+        let response = b
+            .respond(
+                &A,
+                SixpReturnCode::Success,
+                SixpResponseFields::Empty,
+                asn(7),
+            )
+            .unwrap();
+        reset.on_message(&B, response, asn(8));
+        assert_eq!(b.seq_num(&A), 0);
+        assert_eq!(reset.seq_num(&B), 0);
+    }
+
+    #[test]
+    fn timeout_and_capacity() {
+        let mut a = SixpTransactions::<1>::new(10);
+        assert!(a.request(&B, 0, add(SixpCells::EMPTY), asn(0)).is_ok());
+        assert_eq!(
+            a.request(&A, 0, add(SixpCells::EMPTY), asn(0)),
+            Err(SixpError::CapacityExceeded)
+        );
+
+        assert_eq!(a.poll_timeout(asn(10)), None);
+        assert_eq!(a.poll_timeout(asn(11)), Some((B, SixpCommand::Add)));
+        assert!(!a.is_busy(&B));
+        assert_eq!(next_seq_num(0xff), 1);
+    }
+}
@@ -0,0 +1,266 @@
+//! Keep-alive and desynchronization handling (IEEE 802.15.4-2020, section
+//! 6.5.4).
+//!
+//! A TSCH device stays synchronized by receiving frames or ACKs from its time
+//! source neighbor, both of which carry timing information. If no traffic has
+//! been exchanged with the time source for the keep-alive period, a keep-alive
+//! frame (an empty data frame requesting an ACK) is sent to it. If the device
+//! has not synchronized for the desync timeout, its clock drift can no longer
+//! be compensated by the guard time and it has to leave the network and
+//! re-join.
+#![allow(dead_code)]
+
+use crate::{
+    driver::{
+        constants::PHY_MAX_PACKET_SIZE_127,
+        frame::{Address, ExtendedAddress, FrameType, PanId},
+    },
+    mac::{
+        frame::{
+            mpdu::{FrameBuffer, FrameBuilder},
+            FrameError,
+        },
+        neighbors::MacNeighbor,
+    },
+};
+
+use super::{asn::AbsoluteSlotNumber, schedule::TschSchedule};
+
+/// Default keep-alive period in timeslots, i.e. 12s with the default timeslot
+/// length.
+pub const DEFAULT_KEEP_ALIVE_PERIOD: u32 = 1200;
+
+/// Default desync timeout in timeslots, i.e. 60s with the default timeslot
+/// length.
+pub const DEFAULT_DESYNC_TIMEOUT: u32 = 6000;
+
+/// The action required to maintain synchronization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncAction {
+    /// Nothing to do.
+    None,
+    /// Send a keep-alive frame to the time source, see [`keep_alive_frame()`].
+    SendKeepAlive,
+    /// Synchronization has been lost. Tear it down with
+    /// [`TschSyncMonitor::desynchronize()`] and re-join the network.
+    Desynchronized,
+}
+
+/// Monitors the synchronization to the time source neighbor.
+///
+/// All times are expressed as ASNs, periods as number of timeslots.
+pub struct TschSyncMonitor {
+    /// The extended address of the time source (little endian), `None` if not
+    /// synchronized.
+    time_source: Option<[u8; 8]>,
+    /// Number of timeslots without traffic after which a keep-alive is sent.
+    keep_alive_period: u32,
+    /// Number of timeslots without synchronization after which the device
+    /// desynchronizes.
+    desync_timeout: u32,
+    /// ASN at which the device last synchronized to the time source.
+    last_sync: i64,
+    /// ASN at which the last keep-alive was requested.
+    last_keep_alive: i64,
+}
+
+impl Default for TschSyncMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEEP_ALIVE_PERIOD, DEFAULT_DESYNC_TIMEOUT)
+    }
+}
+
+impl TschSyncMonitor {
+    /// Creates a new, not yet synchronized [`TschSyncMonitor`].
+    ///
+    /// * `keep_alive_period` - Timeslots without traffic before a keep-alive
+    ///   is sent
+    /// * `desync_timeout` - Timeslots without synchronization before the
+    ///   device desynchronizes
+    pub fn new(keep_alive_period: u32, desync_timeout: u32) -> Self {
+        Self {
+            time_source: None,
+            keep_alive_period,
+            desync_timeout,
+            last_sync: 0,
+            last_keep_alive: 0,
+        }
+    }
+
+    /// Start monitoring the synchronization to the given time source, e.g.
+    /// after joining the network.
+    ///
+    /// * `time_source` - Extended address of the time source (little endian)
+    /// * `asn` - Absolute slot number at which the device synchronized
+    pub fn synchronized(&mut self, time_source: [u8; 8], asn: AbsoluteSlotNumber) {
+        let asn = to_i64(asn);
+        self.time_source = Some(time_source);
+        self.last_sync = asn;
+        self.last_keep_alive = asn;
+    }
+
+    /// Return whether the device is synchronized.
+    pub fn is_synchronized(&self) -> bool {
+        self.time_source.is_some()
+    }
+
+    /// Return the extended address of the time source, if synchronized.
+    pub fn time_source(&self) -> Option<&[u8; 8]> {
+        self.time_source.as_ref()
+    }
+
+    /// Record a frame or ACK received from the given neighbor. Traffic from
+    /// the time source resynchronizes the device.
+    ///
+    /// * `src_address` - Extended address of the sender (little endian)
+    /// * `asn` - Absolute slot number of the reception
+    pub fn on_receive(&mut self, src_address: &[u8; 8], asn: AbsoluteSlotNumber) {
+        if self.time_source.as_ref() == Some(src_address) {
+            self.last_sync = to_i64(asn);
+        }
+    }
+
+    /// Return the action required at the given ASN to maintain
+    /// synchronization.
+    ///
+    /// Returns [`SyncAction::SendKeepAlive`] at most once per keep-alive
+    /// period so that unacknowledged keep-alives are retried once per period.
+    ///
+    /// * `asn` - Current absolute slot number
+    pub fn poll(&mut self, asn: AbsoluteSlotNumber) -> SyncAction {
+        if !self.is_synchronized() {
+            return SyncAction::None;
+        }
+
+        let asn = to_i64(asn);
+        if asn - self.last_sync >= self.desync_timeout as i64 {
+            SyncAction::Desynchronized
+        } else if asn - self.last_sync.max(self.last_keep_alive) >= self.keep_alive_period as i64 {
+            self.last_keep_alive = asn;
+            SyncAction::SendKeepAlive
+        } else {
+            SyncAction::None
+        }
+    }
+
+    /// Tear down synchronization: forget the time source and remove all
+    /// slotframes from the schedule. The device has to re-join the network
+    /// afterwards.
+    ///
+    /// * `schedule` - Schedule that was installed when joining
+    pub fn desynchronize<const S: usize, const L: usize, T: MacNeighbor>(
+        &mut self,
+        schedule: &mut TschSchedule<S, L, T>,
+    ) {
+        self.time_source = None;
+        schedule.clear();
+    }
+}
+
+fn to_i64(asn: AbsoluteSlotNumber) -> i64 {
+    // Safety: The conversion never fails.
+    asn.try_into().unwrap()
+}
+
+/// Build a keep-alive frame, i.e. an empty data frame requesting an ACK.
+///
+/// * `sequence_number` - Sequence number of the frame
+/// * `pan_id` - PAN ID of the network
+/// * `src_address` - Extended address of the device (little endian)
+/// * `time_source` - Extended address of the time source (little endian)
+pub fn keep_alive_frame(
+    sequence_number: u8,
+    pan_id: u16,
+    src_address: &[u8; 8],
+    time_source: &[u8; 8],
+) -> Result<FrameBuffer<PHY_MAX_PACKET_SIZE_127>, FrameError> {
+    let pan_id = PanId::from_u16(pan_id);
+    let builder = FrameBuilder::new(FrameType::Data)
+        .with_sequence_number(sequence_number)
+        .with_ack_request(true)
+        .with_addressing(
+            Some((
+                pan_id,
+                Address::Extended(ExtendedAddress::new(&time_source[..])),
+            )),
+            Some((
+                pan_id,
+                Address::Extended(ExtendedAddress::new(&src_address[..])),
+            )),
+        )
+        .without_security()
+        .without_ies()
+        .without_payload();
+    FrameBuffer::from_builder(&builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIME_SOURCE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn asn(asn: u32) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    #[test]
+    fn keep_alive_and_desync() {
+        let mut monitor = TschSyncMonitor::new(10, 35);
+        assert_eq!(monitor.poll(asn(100)), SyncAction::None);
+
+        monitor.synchronized(TIME_SOURCE, asn(100));
+        assert!(monitor.is_synchronized());
+        assert_eq!(monitor.poll(asn(109)), SyncAction::None);
+
+        // Traffic from other neighbors doesn't count.
+        monitor.on_receive(&[0; 8], asn(105));
+        assert_eq!(monitor.poll(asn(110)), SyncAction::SendKeepAlive);
+        assert_eq!(monitor.poll(asn(111)), SyncAction::None);
+
+        // The keep-alive was acknowledged.
+        monitor.on_receive(&TIME_SOURCE, asn(112));
+        assert_eq!(monitor.poll(asn(121)), SyncAction::None);
+        assert_eq!(monitor.poll(asn(122)), SyncAction::SendKeepAlive);
+
+        // Keep-alives are not acknowledged anymore.
+        assert_eq!(monitor.poll(asn(132)), SyncAction::SendKeepAlive);
+        assert_eq!(monitor.poll(asn(142)), SyncAction::SendKeepAlive);
+        assert_eq!(monitor.poll(asn(147)), SyncAction::Desynchronized);
+    }
+
+    #[test]
+    fn desynchronize() {
+        use crate::mac::{
+            neighbors::tests::TestNeighbor,
+            tsch::{hopping::HoppingSequence, schedule::TschSlotframe},
+        };
+
+        let mut schedule = TschSchedule::<1, 1, TestNeighbor>::new();
+        assert!(schedule
+            .add_slotframe(TschSlotframe::new(0, 101, HoppingSequence::DEFAULT_4_4))
+            .is_ok());
+
+        let mut monitor = TschSyncMonitor::default();
+        monitor.synchronized(TIME_SOURCE, asn(0));
+        monitor.desynchronize(&mut schedule);
+        assert!(!monitor.is_synchronized());
+        assert!(schedule.slotframes().is_empty());
+        assert_eq!(monitor.poll(asn(100_000)), SyncAction::None);
+    }
+
+    #[test]
+    fn keep_alive() {
+        let frame = keep_alive_frame(7, 0xabcd, &[9; 8], &TIME_SOURCE).unwrap();
+        assert_eq!(
+            &frame[..],
+            &[
+                0x61, 0xdc, // frame control
+                0x07, // sequence number
+                0xcd, 0xab, // dst PAN ID
+                0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // dst address
+                0x09, 0x09, 0x09, 0x09, 0x09, 0x09, 0x09, 0x09, // src address
+            ][..]
+        );
+    }
+}
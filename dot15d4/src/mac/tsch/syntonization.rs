@@ -0,0 +1,285 @@
+//! Syntonization of the TSCH clock to the time source.
+//!
+//! Time corrections keep the phase of the local clock aligned with the time
+//! source. Between corrections, the local clock drifts away at a rate given by
+//! the frequency offset between both crystals. A [`Syntonization`] algorithm
+//! estimates that offset from the phase errors reported by successive time
+//! corrections, so that the virtual TSCH clock can run at the rate of the time
+//! source and smaller guard times suffice.
+//!
+//! All arithmetic is fixed-point: no floating point unit is required, which
+//! makes the algorithms suitable for Cortex-M0 class targets.
+#![allow(dead_code)]
+
+use crate::driver::time::{Duration, Microseconds};
+
+/// A frequency offset in parts per million, as a signed Q16.16 fixed-point
+/// number.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ppm(i32);
+
+impl Ppm {
+    /// Number of fractional bits.
+    pub const FRAC_BITS: u32 = 16;
+
+    /// No frequency offset.
+    pub const ZERO: Self = Self(0);
+
+    /// Creates a [`Ppm`] from a whole number of parts per million.
+    pub const fn from_ppm(ppm: i16) -> Self {
+        Self((ppm as i32) << Self::FRAC_BITS)
+    }
+
+    /// Creates a [`Ppm`] from its raw Q16.16 representation.
+    pub const fn from_raw(raw: i32) -> Self {
+        Self(raw)
+    }
+
+    /// Return the raw Q16.16 representation.
+    pub const fn raw(&self) -> i32 {
+        self.0
+    }
+
+    /// Return the frequency offset in whole parts per million, rounded toward
+    /// negative infinity.
+    pub const fn to_ppm(self) -> i32 {
+        self.0 >> Self::FRAC_BITS
+    }
+
+    /// Return the frequency offset of a clock that accumulated the given phase
+    /// error over the given interval.
+    ///
+    /// Returns [`Ppm::ZERO`] if the interval is not positive.
+    pub const fn from_phase_error(
+        phase_error: Duration<Microseconds>,
+        interval: Duration<Microseconds>,
+    ) -> Self {
+        if interval.ticks() <= 0 {
+            return Self::ZERO;
+        }
+        let ppm = (phase_error.ticks() << Self::FRAC_BITS) * 1_000_000 / interval.ticks();
+        Self::saturate(ppm)
+    }
+
+    /// Multiply by a Q16.16 fixed-point gain.
+    const fn scale(self, gain: i32) -> Self {
+        Self::saturate((self.0 as i64 * gain as i64) >> Self::FRAC_BITS)
+    }
+
+    const fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    const fn clamp_abs(self, limit: Self) -> Self {
+        if self.0 > limit.0 {
+            limit
+        } else if self.0 < -limit.0 {
+            Self(-limit.0)
+        } else {
+            self
+        }
+    }
+
+    const fn saturate(raw: i64) -> Self {
+        if raw > i32::MAX as i64 {
+            Self(i32::MAX)
+        } else if raw < i32::MIN as i64 {
+            Self(i32::MIN)
+        } else {
+            Self(raw as i32)
+        }
+    }
+}
+
+/// Algorithm estimating the frequency offset of the local clock relative to
+/// the time source.
+pub trait Syntonization {
+    /// Consume the phase error reported by a time correction and return the
+    /// updated rate correction.
+    ///
+    /// * `phase_error` - Offset of the time source's clock relative to the
+    ///   local clock, i.e. positive if the local clock is late
+    /// * `interval` - Time elapsed since the previous time correction
+    fn on_time_correction(
+        &mut self,
+        phase_error: Duration<Microseconds>,
+        interval: Duration<Microseconds>,
+    ) -> Ppm;
+
+    /// Return the current rate correction, i.e. the frequency adjustment to
+    /// apply to the local clock. A positive correction speeds the virtual
+    /// clock up.
+    fn rate_correction(&self) -> Ppm;
+
+    /// Forget all state, e.g. after a change of time source.
+    fn reset(&mut self);
+}
+
+/// Syntonization algorithm that never corrects the rate of the local clock.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoSyntonization;
+
+impl Syntonization for NoSyntonization {
+    fn on_time_correction(
+        &mut self,
+        _phase_error: Duration<Microseconds>,
+        _interval: Duration<Microseconds>,
+    ) -> Ppm {
+        Ppm::ZERO
+    }
+
+    fn rate_correction(&self) -> Ppm {
+        Ppm::ZERO
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Proportional-integral controller driving the rate correction toward the
+/// frequency offset measured by each time correction.
+///
+/// The integral term converges to the frequency offset between the local
+/// clock and the time source, while the proportional term speeds up the
+/// reaction to changes, e.g. due to temperature. Both the integral term and
+/// the output are clamped to the maximum rate correction to prevent windup
+/// after outliers.
+#[derive(Debug, Clone, Copy)]
+pub struct PiController {
+    /// Proportional gain (Q16.16).
+    kp: i32,
+    /// Integral gain (Q16.16).
+    ki: i32,
+    /// Maximum absolute rate correction.
+    max_correction: Ppm,
+    /// Integral term.
+    integral: Ppm,
+    /// Current rate correction.
+    correction: Ppm,
+}
+
+impl PiController {
+    /// Default proportional gain, i.e. 0.25.
+    pub const DEFAULT_KP: i32 = 1 << 14;
+    /// Default integral gain, i.e. 0.5.
+    pub const DEFAULT_KI: i32 = 1 << 15;
+    /// Default maximum rate correction. IEEE 802.15.4 requires a clock
+    /// accuracy of ±40 ppm, so two devices differ by at most 80 ppm.
+    pub const DEFAULT_MAX_CORRECTION: Ppm = Ppm::from_ppm(80);
+
+    /// Creates a new [`PiController`].
+    ///
+    /// * `kp` - Proportional gain (Q16.16)
+    /// * `ki` - Integral gain (Q16.16)
+    /// * `max_correction` - Maximum absolute rate correction
+    pub const fn new(kp: i32, ki: i32, max_correction: Ppm) -> Self {
+        Self {
+            kp,
+            ki,
+            max_correction,
+            integral: Ppm::ZERO,
+            correction: Ppm::ZERO,
+        }
+    }
+}
+
+impl Default for PiController {
+    fn default() -> Self {
+        Self::new(
+            Self::DEFAULT_KP,
+            Self::DEFAULT_KI,
+            Self::DEFAULT_MAX_CORRECTION,
+        )
+    }
+}
+
+impl Syntonization for PiController {
+    fn on_time_correction(
+        &mut self,
+        phase_error: Duration<Microseconds>,
+        interval: Duration<Microseconds>,
+    ) -> Ppm {
+        // The phase error was accumulated while the current rate correction
+        // was applied, hence it measures the residual frequency offset.
+        let error = Ppm::from_phase_error(phase_error, interval);
+        self.integral = self
+            .integral
+            .saturating_add(error.scale(self.ki))
+            .clamp_abs(self.max_correction);
+        self.correction = error
+            .scale(self.kp)
+            .saturating_add(self.integral)
+            .clamp_abs(self.max_correction);
+        self.correction
+    }
+
+    fn rate_correction(&self) -> Ppm {
+        self.correction
+    }
+
+    fn reset(&mut self) {
+        self.integral = Ppm::ZERO;
+        self.correction = Ppm::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm() {
+        assert_eq!(Ppm::from_ppm(-3).raw(), -3 << 16);
+        assert_eq!(Ppm::from_ppm(-3).to_ppm(), -3);
+        assert_eq!(Ppm::from_raw(0x18000).to_ppm(), 1);
+
+        // 25us over 1s.
+        assert_eq!(
+            Ppm::from_phase_error(Duration::new(25), Duration::new(1_000_000)),
+            Ppm::from_ppm(25)
+        );
+        // 1us over 4s.
+        assert_eq!(
+            Ppm::from_phase_error(Duration::new(-1), Duration::new(4_000_000)),
+            Ppm::from_raw(-(1 << 14))
+        );
+        assert_eq!(
+            Ppm::from_phase_error(Duration::new(10), Duration::new(0)),
+            Ppm::ZERO
+        );
+    }
+
+    #[test]
+    fn pi_controller_converges() {
+        let drift = Ppm::from_ppm(-23);
+        let interval = Duration::new(10_000_000);
+        let mut pi = PiController::default();
+
+        for _ in 0..20 {
+            // Phase error accumulated over the interval by the residual drift.
+            let residual = (drift.raw() - pi.rate_correction().raw()) as i64;
+            let phase_error = Duration::new(((residual * interval.ticks()) >> 16) / 1_000_000);
+            pi.on_time_correction(phase_error, interval);
+        }
+
+        let error = pi.rate_correction().raw() - drift.raw();
+        assert!(error.abs() < Ppm::from_raw(1 << 15).raw());
+
+        pi.reset();
+        assert_eq!(pi.rate_correction(), Ppm::ZERO);
+    }
+
+    #[test]
+    fn pi_controller_anti_windup() {
+        let mut pi = PiController::default();
+        for _ in 0..10 {
+            pi.on_time_correction(Duration::new(1_000), Duration::new(1_000_000));
+        }
+        assert_eq!(pi.rate_correction(), PiController::DEFAULT_MAX_CORRECTION);
+
+        // A single outlier in the other direction doesn't need to unwind a
+        // huge integral term.
+        let correction = pi.on_time_correction(Duration::new(-80), Duration::new(1_000_000));
+        assert!(correction < Ppm::from_ppm(80));
+        assert!(correction > Ppm::from_ppm(0));
+    }
+}
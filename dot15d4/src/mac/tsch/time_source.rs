@@ -0,0 +1,390 @@
+//! Time source neighbors (IEEE 802.15.4-2020, section 6.5.4.2).
+//!
+//! Neighbors reachable over a link with the TimeKeeping option are time
+//! sources: the device adjusts its clock to theirs. The table tracks when each
+//! time source was last heard along with an estimate of the clock drift
+//! relative to it, and selects the time source used for synchronization, i.e.
+//! the synchronization parent. If the parent hasn't been heard for too long,
+//! another time source takes over.
+#![allow(dead_code)]
+
+use crate::{
+    driver::time::{Duration, Microseconds},
+    mac::{
+        frame::fields::TschLinkOption,
+        neighbors::{MacNeighbor, TableError},
+    },
+};
+
+use super::{asn::AbsoluteSlotNumber, schedule::TschSchedule};
+
+/// Default number of timeslots after which a time source that hasn't been
+/// heard is no longer eligible as parent, i.e. 30s with the default timeslot
+/// length.
+pub const DEFAULT_TIME_SOURCE_TIMEOUT: u32 = 3000;
+
+/// A neighbor with a TimeKeeping link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSourceNeighbor {
+    /// Extended address of the neighbor (little endian).
+    address: [u8; 8],
+    /// ASN at which the neighbor was last heard, `None` if never heard.
+    last_heard: Option<i64>,
+    /// ASN of the last time correction, `None` if no correction happened.
+    last_correction: Option<i64>,
+    /// Estimated drift of the local clock relative to the neighbor's in parts
+    /// per billion, `None` until two corrections have been made.
+    drift_ppb: Option<i32>,
+}
+
+impl TimeSourceNeighbor {
+    fn new(address: [u8; 8]) -> Self {
+        Self {
+            address,
+            last_heard: None,
+            last_correction: None,
+            drift_ppb: None,
+        }
+    }
+
+    /// Return the extended address of the neighbor (little endian).
+    pub fn address(&self) -> &[u8; 8] {
+        &self.address
+    }
+
+    /// Return the ASN at which the neighbor was last heard.
+    pub fn last_heard(&self) -> Option<AbsoluteSlotNumber> {
+        self.last_heard
+            .and_then(|asn| AbsoluteSlotNumber::try_from(asn).ok())
+    }
+
+    /// Return the estimated drift of the local clock relative to the
+    /// neighbor's in parts per billion. A positive drift means that the local
+    /// clock is slow.
+    pub fn drift_ppb(&self) -> Option<i32> {
+        self.drift_ppb
+    }
+
+    /// Return whether the neighbor has been heard within `timeout` timeslots.
+    fn is_alive(&self, asn: i64, timeout: u32) -> bool {
+        self.last_heard
+            .is_some_and(|last_heard| asn - last_heard < timeout as i64)
+    }
+}
+
+/// Table of the time source neighbors and selection of the synchronization
+/// parent among them.
+pub struct TimeSourceNeighbors<const N: usize> {
+    neighbors: heapless::Vec<TimeSourceNeighbor, N>,
+    /// Address of the synchronization parent.
+    parent: Option<[u8; 8]>,
+    /// Number of timeslots after which a time source that hasn't been heard
+    /// is no longer eligible as parent.
+    timeout: u32,
+}
+
+impl<const N: usize> Default for TimeSourceNeighbors<N> {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIME_SOURCE_TIMEOUT)
+    }
+}
+
+impl<const N: usize> TimeSourceNeighbors<N> {
+    /// Creates a new, empty [`TimeSourceNeighbors`] table.
+    ///
+    /// * `timeout` - Timeslots after which a time source that hasn't been
+    ///   heard is no longer eligible as parent
+    pub fn new(timeout: u32) -> Self {
+        Self {
+            neighbors: heapless::Vec::new(),
+            parent: None,
+            timeout,
+        }
+    }
+
+    /// Return the time source neighbors.
+    pub fn neighbors(&self) -> &[TimeSourceNeighbor] {
+        &self.neighbors
+    }
+
+    /// Return the time source neighbor with the given address.
+    pub fn neighbor(&self, address: &[u8; 8]) -> Option<&TimeSourceNeighbor> {
+        self.neighbors.iter().find(|n| &n.address == address)
+    }
+
+    /// Return the synchronization parent.
+    pub fn parent(&self) -> Option<&TimeSourceNeighbor> {
+        self.parent
+            .as_ref()
+            .and_then(|parent| self.neighbor(parent))
+    }
+
+    /// Set the synchronization parent, e.g. the time source selected when
+    /// joining. The parent is added to the table if needed.
+    ///
+    /// * `address` - Extended address of the parent (little endian)
+    /// * `asn` - Absolute slot number at which the parent was heard
+    pub fn set_parent(
+        &mut self,
+        address: [u8; 8],
+        asn: AbsoluteSlotNumber,
+    ) -> Result<(), TableError> {
+        self.insert(address)?;
+        self.parent = Some(address);
+        self.on_heard(&address, asn);
+        Ok(())
+    }
+
+    /// Synchronize the table with the TimeKeeping links of the schedule.
+    ///
+    /// Neighbors that are no longer reachable over a TimeKeeping link are
+    /// removed, new ones are added. The state of the remaining neighbors is
+    /// kept.
+    ///
+    /// * `schedule` - The installed schedule
+    pub fn update_from_schedule<const S: usize, const L: usize, T: MacNeighbor>(
+        &mut self,
+        schedule: &TschSchedule<S, L, T>,
+    ) -> Result<(), TableError> {
+        let is_time_source = |address: &[u8; 8]| {
+            schedule
+                .slotframes()
+                .iter()
+                .flat_map(|slotframe| slotframe.links())
+                .filter(|link| link.link_options().contains(TschLinkOption::TimeKeeping))
+                .filter_map(|link| link.neighbor())
+                .any(|neighbor| &neighbor.address() == address)
+        };
+
+        self.neighbors.retain(|n| is_time_source(&n.address));
+        if self
+            .parent
+            .is_some_and(|parent| self.neighbor(&parent).is_none())
+        {
+            self.parent = None;
+        }
+
+        for slotframe in schedule.slotframes() {
+            for link in slotframe.links() {
+                if !link.link_options().contains(TschLinkOption::TimeKeeping) {
+                    continue;
+                }
+                if let Some(neighbor) = link.neighbor() {
+                    self.insert(neighbor.address())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a frame or ACK received from the given neighbor.
+    ///
+    /// * `address` - Extended address of the sender (little endian)
+    /// * `asn` - Absolute slot number of the reception
+    pub fn on_heard(&mut self, address: &[u8; 8], asn: AbsoluteSlotNumber) {
+        if let Some(neighbor) = self.neighbor_mut(address) {
+            neighbor.last_heard = Some(to_i64(asn));
+        }
+    }
+
+    /// Record a time correction relative to the given neighbor and update its
+    /// drift estimate.
+    ///
+    /// The drift is estimated from the correction and the time elapsed since
+    /// the previous correction, and smoothed with an exponentially weighted
+    /// moving average.
+    ///
+    /// * `address` - Extended address of the time source (little endian)
+    /// * `asn` - Absolute slot number of the correction
+    /// * `correction` - Offset of the neighbor's clock relative to the local
+    ///   clock
+    /// * `timeslot_length` - Length of a timeslot
+    pub fn on_time_correction(
+        &mut self,
+        address: &[u8; 8],
+        asn: AbsoluteSlotNumber,
+        correction: Duration<Microseconds>,
+        timeslot_length: Duration<Microseconds>,
+    ) {
+        let asn = to_i64(asn);
+        let Some(neighbor) = self.neighbor_mut(address) else {
+            return;
+        };
+
+        if let Some(last_correction) = neighbor.last_correction {
+            let elapsed = (asn - last_correction) * timeslot_length.ticks();
+            if elapsed > 0 {
+                let sample = (correction.ticks() * 1_000_000_000 / elapsed)
+                    .clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+                neighbor.drift_ppb = Some(match neighbor.drift_ppb {
+                    Some(drift) => ((3 * drift as i64 + sample as i64) / 4) as i32,
+                    None => sample,
+                });
+            }
+        }
+        neighbor.last_correction = Some(asn);
+        neighbor.last_heard = Some(asn);
+    }
+
+    /// Select the synchronization parent at the given ASN.
+    ///
+    /// The current parent is kept as long as it has been heard within the
+    /// timeout. Otherwise, the table fails over to the time source that has
+    /// been heard within the timeout with the lowest drift, preferring the
+    /// most recently heard one if drifts are equal or unknown.
+    ///
+    /// * `asn` - Current absolute slot number
+    pub fn select_parent(&mut self, asn: AbsoluteSlotNumber) -> Option<&TimeSourceNeighbor> {
+        let asn = to_i64(asn);
+        let timeout = self.timeout;
+
+        if self.parent().is_some_and(|p| p.is_alive(asn, timeout)) {
+            return self.parent();
+        }
+
+        self.parent = self
+            .neighbors
+            .iter()
+            .filter(|n| n.is_alive(asn, timeout))
+            .min_by_key(|n| {
+                (
+                    n.drift_ppb.map_or(u32::MAX, i32::unsigned_abs),
+                    core::cmp::Reverse(n.last_heard),
+                )
+            })
+            .map(|n| n.address);
+        self.parent()
+    }
+
+    /// Remove the given neighbor from the table.
+    pub fn remove(&mut self, address: &[u8; 8]) {
+        self.neighbors.retain(|n| &n.address != address);
+        if self.parent.as_ref() == Some(address) {
+            self.parent = None;
+        }
+    }
+
+    fn insert(&mut self, address: [u8; 8]) -> Result<(), TableError> {
+        if self.neighbor(&address).is_none() {
+            self.neighbors
+                .push(TimeSourceNeighbor::new(address))
+                .map_err(|_| TableError::Full)?;
+        }
+        Ok(())
+    }
+
+    fn neighbor_mut(&mut self, address: &[u8; 8]) -> Option<&mut TimeSourceNeighbor> {
+        self.neighbors.iter_mut().find(|n| &n.address == address)
+    }
+}
+
+fn to_i64(asn: AbsoluteSlotNumber) -> i64 {
+    // Safety: The conversion never fails.
+    asn.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mac::{
+        neighbors::tests::TestNeighbor,
+        tsch::{
+            hopping::HoppingSequence,
+            schedule::{TschLink, TschLinkType, TschSlotframe},
+        },
+    };
+
+    use super::*;
+
+    const NBR1: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+    const NBR2: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 2];
+    const NBR3: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 3];
+
+    fn asn(asn: u32) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::try_from(asn).unwrap()
+    }
+
+    fn link(handle: u16, options: TschLinkOption, address: [u8; 8]) -> TschLink<TestNeighbor> {
+        TschLink::new(
+            handle,
+            handle,
+            0,
+            options,
+            TschLinkType::Normal,
+            Some(TestNeighbor::new(address)),
+        )
+    }
+
+    #[test]
+    fn update_from_schedule() {
+        let mut sf = TschSlotframe::<3, TestNeighbor>::new(0, 10, HoppingSequence::DEFAULT_4_4);
+        assert!(sf.add_link(link(0, TschLinkOption::Tx, NBR1)).is_ok());
+        assert!(sf
+            .add_link(link(
+                1,
+                TschLinkOption::Rx | TschLinkOption::TimeKeeping,
+                NBR2
+            ))
+            .is_ok());
+        assert!(sf
+            .add_link(link(
+                2,
+                TschLinkOption::Tx | TschLinkOption::TimeKeeping,
+                NBR3
+            ))
+            .is_ok());
+        let mut schedule = TschSchedule::<1, 3, TestNeighbor>::new();
+        assert!(schedule.add_slotframe(sf).is_ok());
+
+        let mut table = TimeSourceNeighbors::<4>::default();
+        assert!(table.set_parent(NBR1, asn(0)).is_ok());
+        assert!(table.update_from_schedule(&schedule).is_ok());
+        assert!(table.parent().is_none());
+        assert_eq!(table.neighbors().len(), 2);
+        assert!(table.neighbor(&NBR2).is_some());
+        assert!(table.neighbor(&NBR3).is_some());
+
+        let mut small = TimeSourceNeighbors::<1>::default();
+        assert!(small.update_from_schedule(&schedule).is_err());
+    }
+
+    #[test]
+    fn drift_estimate() {
+        let mut table = TimeSourceNeighbors::<1>::default();
+        assert!(table.set_parent(NBR1, asn(0)).is_ok());
+        let timeslot_length = Duration::new(10_000);
+
+        table.on_time_correction(&NBR1, asn(100), Duration::new(5), timeslot_length);
+        assert_eq!(table.parent().unwrap().drift_ppb(), None);
+
+        // 10us over 1s.
+        table.on_time_correction(&NBR1, asn(200), Duration::new(10), timeslot_length);
+        assert_eq!(table.parent().unwrap().drift_ppb(), Some(10_000));
+
+        // -30us over 1s.
+        table.on_time_correction(&NBR1, asn(300), Duration::new(-30), timeslot_length);
+        assert_eq!(table.parent().unwrap().drift_ppb(), Some(0));
+        assert!(table.parent().unwrap().last_heard().unwrap() == 300i64);
+    }
+
+    #[test]
+    fn parent_failover() {
+        let timeslot_length = Duration::new(10_000);
+        let mut table = TimeSourceNeighbors::<3>::new(100);
+        assert!(table.set_parent(NBR1, asn(0)).is_ok());
+        assert!(table.insert(NBR2).is_ok());
+        assert!(table.insert(NBR3).is_ok());
+
+        table.on_time_correction(&NBR2, asn(0), Duration::new(0), timeslot_length);
+        table.on_time_correction(&NBR2, asn(50), Duration::new(10), timeslot_length);
+        table.on_heard(&NBR3, asn(60));
+
+        assert_eq!(table.select_parent(asn(99)).unwrap().address(), &NBR1);
+
+        // NBR1 timed out, NBR2 has a known drift.
+        assert_eq!(table.select_parent(asn(100)).unwrap().address(), &NBR2);
+
+        // NBR2 timed out as well.
+        assert_eq!(table.select_parent(asn(150)).unwrap().address(), &NBR3);
+        assert!(table.select_parent(asn(160)).is_none());
+    }
+}
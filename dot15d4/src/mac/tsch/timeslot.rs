@@ -0,0 +1,487 @@
+//! TSCH timeslot state machine (IEEE 802.15.4-2024, section 6.2.6.2).
+//!
+//! The state machine executes a single timeslot. It does not interact with the
+//! radio directly. Instead, each step produces the next radio operation
+//! together with its precise start time derived from the slot start and the
+//! [`TschTimeslotTimings`]. The outcome of the operation is then fed back into
+//! the state machine as a [`TimeslotEvent`].
+//!
+//! ```notrust
+//! TX: |-- CCA offset --|CCA|-- TX offset --|TX|-- RX ACK delay --|RX ACK (ACK wait)|
+//! RX: |-- RX offset --|RX (RX wait)|...|RX|-- TX ACK delay --|TX ACK|
+//! ```
+#![allow(dead_code)]
+
+use core::marker::PhantomData;
+
+use crate::{
+    driver::time::{Duration, Frequency, Instant, Microseconds},
+    mac::frame::fields::TschTimeslotTimings,
+};
+
+/// Timeslot timings converted into radio timer ticks.
+///
+/// Conversions are expensive, so they are done once when the timings are
+/// configured rather than once per timeslot.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TimeslotTicks<Timer: Frequency> {
+    cca_offset: i64,
+    cca: i64,
+    tx_offset: i64,
+    rx_offset: i64,
+    rx_ack_delay: i64,
+    tx_ack_delay: i64,
+    rx_wait: i64,
+    ack_wait: i64,
+    timeslot_length: i64,
+    timer: PhantomData<Timer>,
+}
+
+impl<Timer: Frequency> TimeslotTicks<Timer> {
+    /// Converts the given timings into radio timer ticks.
+    pub fn new(timings: &TschTimeslotTimings) -> Self {
+        let ticks = |duration: Duration<Microseconds>| {
+            duration.convert_into_rounding_down::<Timer>().ticks()
+        };
+        Self {
+            cca_offset: ticks(timings.cca_offset()),
+            cca: ticks(timings.cca()),
+            tx_offset: ticks(timings.tx_offset()),
+            rx_offset: ticks(timings.rx_offset()),
+            rx_ack_delay: ticks(timings.rx_ack_delay()),
+            tx_ack_delay: ticks(timings.tx_ack_delay()),
+            rx_wait: ticks(timings.rx_wait()),
+            ack_wait: ticks(timings.ack_wait()),
+            timeslot_length: ticks(timings.timeslot_length()),
+            timer: PhantomData,
+        }
+    }
+
+    /// The length of a timeslot.
+    pub fn timeslot_length(&self) -> Duration<Timer> {
+        Duration::new(self.timeslot_length)
+    }
+}
+
+// Implemented manually as deriving would require the timer to be `Copy`.
+impl<Timer: Frequency> Clone for TimeslotTicks<Timer> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Timer: Frequency> Copy for TimeslotTicks<Timer> {}
+
+/// The role of the local device in a timeslot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeslotRole {
+    /// Send a frame.
+    Tx {
+        /// Whether to perform CCA before sending, e.g. on shared links.
+        cca: bool,
+        /// Whether the frame requests an acknowledgement.
+        ack_requested: bool,
+    },
+    /// Listen for an incoming frame.
+    Rx,
+}
+
+/// The radio operation to be executed next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeslotAction<Timer: Frequency> {
+    /// Perform CCA starting at the given time for the given duration. Respond
+    /// with [`TimeslotEvent::CcaDone`].
+    Cca {
+        at: Instant<Timer>,
+        duration: Duration<Timer>,
+    },
+    /// Send the frame so that its RMARKER passes the antenna at the given
+    /// time. Respond with [`TimeslotEvent::TxDone`].
+    Tx { at: Instant<Timer> },
+    /// Listen for a frame whose RMARKER passes the antenna no earlier than
+    /// `at` and no later than `until`. Respond with
+    /// [`TimeslotEvent::FrameReceived`] or [`TimeslotEvent::Timeout`].
+    Rx {
+        at: Instant<Timer>,
+        until: Instant<Timer>,
+    },
+    /// Listen for an ACK frame. Respond with [`TimeslotEvent::AckReceived`] or
+    /// [`TimeslotEvent::Timeout`].
+    RxAck {
+        at: Instant<Timer>,
+        until: Instant<Timer>,
+    },
+    /// Send an ACK frame so that its RMARKER passes the antenna at the given
+    /// time. Respond with [`TimeslotEvent::TxDone`].
+    TxAck { at: Instant<Timer> },
+}
+
+/// The outcome of a radio operation, see [`TimeslotAction`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeslotEvent<Timer: Frequency> {
+    /// The CCA finished.
+    CcaDone {
+        /// Whether the channel was found busy.
+        busy: bool,
+    },
+    /// The frame was sent.
+    TxDone {
+        /// The time at which the last symbol of the frame was sent.
+        end: Instant<Timer>,
+    },
+    /// A frame was received.
+    FrameReceived {
+        /// The time at which the last symbol of the frame was received.
+        end: Instant<Timer>,
+        /// Whether the frame requests an acknowledgement.
+        ack_requested: bool,
+    },
+    /// An ACK frame was received.
+    AckReceived {
+        /// Whether the ACK frame signals a NACK.
+        nack: bool,
+    },
+    /// The RX window ended without receiving a frame.
+    Timeout,
+}
+
+/// The final result of a timeslot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeslotResult {
+    /// The frame was sent and acknowledged if requested.
+    Sent,
+    /// The frame was sent but no ACK was received or the ACK signalled a NACK.
+    NoAck,
+    /// The frame was not sent as the channel was busy.
+    CcaBusy,
+    /// A frame was received and acknowledged if requested.
+    Received,
+    /// No frame was received in the RX window.
+    Idle,
+}
+
+/// A transition of the timeslot state machine.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeslotTransition<Timer: Frequency> {
+    /// The state machine requests a radio operation.
+    Action(TschTimeslot<Timer>, TimeslotAction<Timer>),
+    /// The timeslot is over.
+    Done(TimeslotResult),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeslotState {
+    Initial(TimeslotRole),
+    Cca { ack_requested: bool },
+    Tx { ack_requested: bool },
+    RxAck,
+    Rx,
+    TxAck,
+}
+
+/// A state machine executing a single TSCH timeslot.
+///
+/// Start the timeslot with [`TschTimeslot::start()`], then execute the requested radio operations and feed their outcome back
+/// via [`TschTimeslot::step()`] until the timeslot is done.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TschTimeslot<Timer: Frequency> {
+    ticks: TimeslotTicks<Timer>,
+    slot_start: u64,
+    state: TimeslotState,
+}
+
+impl<Timer: Frequency> TschTimeslot<Timer> {
+    /// Creates a timeslot starting at the given time.
+    pub fn new(
+        ticks: TimeslotTicks<Timer>,
+        slot_start: Instant<Timer>,
+        role: TimeslotRole,
+    ) -> Self {
+        Self {
+            ticks,
+            slot_start: slot_start.tick(),
+            state: TimeslotState::Initial(role),
+        }
+    }
+
+    /// Enters the timeslot and returns the first radio operation.
+    pub fn start(self) -> TimeslotTransition<Timer> {
+        let TimeslotState::Initial(role) = self.state else {
+            unreachable!()
+        };
+
+        match role {
+            TimeslotRole::Tx {
+                cca: true,
+                ack_requested,
+            } => {
+                let action = TimeslotAction::Cca {
+                    at: self.at_offset(self.ticks.cca_offset),
+                    duration: Duration::new(self.ticks.cca),
+                };
+                self.transition(TimeslotState::Cca { ack_requested }, action)
+            }
+            TimeslotRole::Tx {
+                cca: false,
+                ack_requested,
+            } => self.tx(ack_requested),
+            TimeslotRole::Rx => {
+                let action = TimeslotAction::Rx {
+                    at: self.at_offset(self.ticks.rx_offset),
+                    until: self.at_offset(self.ticks.rx_offset + self.ticks.rx_wait),
+                };
+                self.transition(TimeslotState::Rx, action)
+            }
+        }
+    }
+
+    /// Feeds the outcome of the previously requested radio operation into the
+    /// state machine.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event does not match the requested radio operation.
+    pub fn step(self, event: TimeslotEvent<Timer>) -> TimeslotTransition<Timer> {
+        match (self.state, event) {
+            (TimeslotState::Cca { .. }, TimeslotEvent::CcaDone { busy: true }) => {
+                TimeslotTransition::Done(TimeslotResult::CcaBusy)
+            }
+            (TimeslotState::Cca { ack_requested }, TimeslotEvent::CcaDone { busy: false }) => {
+                self.tx(ack_requested)
+            }
+            (
+                TimeslotState::Tx {
+                    ack_requested: false,
+                },
+                TimeslotEvent::TxDone { .. },
+            ) => TimeslotTransition::Done(TimeslotResult::Sent),
+            (
+                TimeslotState::Tx {
+                    ack_requested: true,
+                },
+                TimeslotEvent::TxDone { end },
+            ) => {
+                let at = end.tick().saturating_add_signed(self.ticks.rx_ack_delay);
+                let action = TimeslotAction::RxAck {
+                    at: Instant::new(at),
+                    until: Instant::new(at.saturating_add_signed(self.ticks.ack_wait)),
+                };
+                self.transition(TimeslotState::RxAck, action)
+            }
+            (TimeslotState::RxAck, TimeslotEvent::AckReceived { nack: false }) => {
+                TimeslotTransition::Done(TimeslotResult::Sent)
+            }
+            (TimeslotState::RxAck, TimeslotEvent::AckReceived { nack: true })
+            | (TimeslotState::RxAck, TimeslotEvent::Timeout) => {
+                TimeslotTransition::Done(TimeslotResult::NoAck)
+            }
+            (TimeslotState::Rx, TimeslotEvent::Timeout) => {
+                TimeslotTransition::Done(TimeslotResult::Idle)
+            }
+            (
+                TimeslotState::Rx,
+                TimeslotEvent::FrameReceived {
+                    ack_requested: false,
+                    ..
+                },
+            ) => TimeslotTransition::Done(TimeslotResult::Received),
+            (
+                TimeslotState::Rx,
+                TimeslotEvent::FrameReceived {
+                    end,
+                    ack_requested: true,
+                },
+            ) => {
+                let at = end.tick().saturating_add_signed(self.ticks.tx_ack_delay);
+                let action = TimeslotAction::TxAck {
+                    at: Instant::new(at),
+                };
+                self.transition(TimeslotState::TxAck, action)
+            }
+            (TimeslotState::TxAck, TimeslotEvent::TxDone { .. }) => {
+                TimeslotTransition::Done(TimeslotResult::Received)
+            }
+            (state, _) => panic!("unexpected event in state {state:?}"),
+        }
+    }
+
+    /// The time at which the timeslot started.
+    pub fn slot_start(&self) -> Instant<Timer> {
+        Instant::new(self.slot_start)
+    }
+
+    fn tx(self, ack_requested: bool) -> TimeslotTransition<Timer> {
+        let action = TimeslotAction::Tx {
+            at: self.at_offset(self.ticks.tx_offset),
+        };
+        self.transition(TimeslotState::Tx { ack_requested }, action)
+    }
+
+    fn at_offset(&self, offset: i64) -> Instant<Timer> {
+        Instant::new(self.slot_start.saturating_add_signed(offset))
+    }
+
+    fn transition(
+        mut self,
+        state: TimeslotState,
+        action: TimeslotAction<Timer>,
+    ) -> TimeslotTransition<Timer> {
+        self.state = state;
+        TimeslotTransition::Action(self, action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Timeslot = TschTimeslot<Microseconds>;
+
+    const SLOT_START: u64 = 1_000_000;
+
+    fn timeslot(role: TimeslotRole) -> Timeslot {
+        let ticks = TimeslotTicks::new(&TschTimeslotTimings::default());
+        Timeslot::new(ticks, Instant::new(SLOT_START), role)
+    }
+
+    fn expect_action(
+        transition: TimeslotTransition<Microseconds>,
+    ) -> (Timeslot, TimeslotAction<Microseconds>) {
+        match transition {
+            TimeslotTransition::Action(timeslot, action) => (timeslot, action),
+            TimeslotTransition::Done(result) => panic!("unexpected result {result:?}"),
+        }
+    }
+
+    fn at(offset: u64) -> Instant<Microseconds> {
+        Instant::new(SLOT_START + offset)
+    }
+
+    #[test]
+    fn tx_with_cca_and_ack() {
+        let (ts, action) = expect_action(
+            timeslot(TimeslotRole::Tx {
+                cca: true,
+                ack_requested: true,
+            })
+            .start(),
+        );
+        assert_eq!(
+            action,
+            TimeslotAction::Cca {
+                at: at(1800),
+                duration: Duration::new(128)
+            }
+        );
+
+        let (ts, action) = expect_action(ts.step(TimeslotEvent::CcaDone { busy: false }));
+        assert_eq!(action, TimeslotAction::Tx { at: at(2120) });
+
+        let (ts, action) = expect_action(ts.step(TimeslotEvent::TxDone { end: at(4000) }));
+        assert_eq!(
+            action,
+            TimeslotAction::RxAck {
+                at: at(4800),
+                until: at(5200)
+            }
+        );
+
+        assert_eq!(
+            ts.step(TimeslotEvent::AckReceived { nack: false }),
+            TimeslotTransition::Done(TimeslotResult::Sent)
+        );
+    }
+
+    #[test]
+    fn tx_failures() {
+        let (ts, _) = expect_action(
+            timeslot(TimeslotRole::Tx {
+                cca: true,
+                ack_requested: false,
+            })
+            .start(),
+        );
+        assert_eq!(
+            ts.step(TimeslotEvent::CcaDone { busy: true }),
+            TimeslotTransition::Done(TimeslotResult::CcaBusy)
+        );
+
+        for event in [
+            TimeslotEvent::Timeout,
+            TimeslotEvent::AckReceived { nack: true },
+        ] {
+            let (ts, action) = expect_action(
+                timeslot(TimeslotRole::Tx {
+                    cca: false,
+                    ack_requested: true,
+                })
+                .start(),
+            );
+            assert_eq!(action, TimeslotAction::Tx { at: at(2120) });
+            let (ts, _) = expect_action(ts.step(TimeslotEvent::TxDone { end: at(4000) }));
+            assert_eq!(
+                ts.step(event),
+                TimeslotTransition::Done(TimeslotResult::NoAck)
+            );
+        }
+    }
+
+    #[test]
+    fn tx_without_ack() {
+        let (ts, _) = expect_action(
+            timeslot(TimeslotRole::Tx {
+                cca: false,
+                ack_requested: false,
+            })
+            .start(),
+        );
+        assert_eq!(
+            ts.step(TimeslotEvent::TxDone { end: at(4000) }),
+            TimeslotTransition::Done(TimeslotResult::Sent)
+        );
+    }
+
+    #[test]
+    fn rx() {
+        let (ts, action) = expect_action(timeslot(TimeslotRole::Rx).start());
+        // The RX window is centered around the TX offset.
+        assert_eq!(
+            action,
+            TimeslotAction::Rx {
+                at: at(1020),
+                until: at(3220)
+            }
+        );
+        assert_eq!(
+            ts.step(TimeslotEvent::Timeout),
+            TimeslotTransition::Done(TimeslotResult::Idle)
+        );
+
+        let (ts, _) = expect_action(timeslot(TimeslotRole::Rx).start());
+        let (ts, action) = expect_action(ts.step(TimeslotEvent::FrameReceived {
+            end: at(4000),
+            ack_requested: true,
+        }));
+        assert_eq!(action, TimeslotAction::TxAck { at: at(5000) });
+        assert_eq!(
+            ts.step(TimeslotEvent::TxDone { end: at(5352) }),
+            TimeslotTransition::Done(TimeslotResult::Received)
+        );
+
+        let (ts, _) = expect_action(timeslot(TimeslotRole::Rx).start());
+        assert_eq!(
+            ts.step(TimeslotEvent::FrameReceived {
+                end: at(4000),
+                ack_requested: false,
+            }),
+            TimeslotTransition::Done(TimeslotResult::Received)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn unexpected_event() {
+        let (ts, _) = expect_action(timeslot(TimeslotRole::Rx).start());
+        let _ = ts.step(TimeslotEvent::CcaDone { busy: false });
+    }
+}
@@ -0,0 +1,229 @@
+//! Transmit queue with priority classes and deadlines.
+//!
+//! Frames waiting for transmission are queued in one of three priority
+//! classes, see [`TxPriority`]. The next frame to transmit is always the oldest
+//! frame of the highest non-empty class, so that network control frames such
+//! as Enhanced Beacons and keep-alives are never starved behind bulk data of
+//! the upper layer.
+//!
+//! Each frame may have a deadline. Frames still queued at their deadline are
+//! dropped and handed back to be confirmed to the upper layer with
+//! [`DataError::TransactionExpired`], before any other frame is transmitted.
+#![allow(dead_code)]
+
+use crate::driver::time::{Frequency, Instant};
+
+use super::{counters::MAC_COUNTERS, mcps::data::DataError};
+
+/// The number of priority classes.
+const PRIORITY_CLASSES: usize = 3;
+
+/// The priority class of a queued frame, from highest to lowest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TxPriority {
+    /// Frames keeping the network running, e.g. beacons, keep-alives and MAC
+    /// commands.
+    NetworkControl,
+    /// Frames of the upper layer.
+    Normal,
+    /// Frames of the upper layer that may wait for all others, e.g. bulk
+    /// transfers.
+    Bulk,
+}
+
+/// A frame in the [`TxQueue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedFrame<T, Timer: Frequency> {
+    /// The handle of the frame given by the upper layer.
+    pub msdu_handle: u8,
+    /// The instant at which the frame expires, if any.
+    pub deadline: Option<Instant<Timer>>,
+    /// The frame.
+    pub frame: T,
+}
+
+impl<T, Timer: Frequency> QueuedFrame<T, Timer> {
+    fn is_expired(&self, now: Instant<Timer>) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+/// A frame taken from the [`TxQueue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxQueueEntry<T, Timer: Frequency> {
+    /// The frame to transmit next.
+    Transmit(QueuedFrame<T, Timer>),
+    /// A frame dropped at its deadline, to be confirmed with
+    /// [`DataError::TransactionExpired`].
+    Expired(QueuedFrame<T, Timer>),
+}
+
+/// Up to `Q` frames per priority class waiting for transmission, see the
+/// module documentation.
+pub struct TxQueue<T, Timer: Frequency, const Q: usize> {
+    /// The frames of each priority class in the order in which they were
+    /// queued.
+    classes: [heapless::Vec<QueuedFrame<T, Timer>, Q>; PRIORITY_CLASSES],
+}
+
+impl<T, Timer: Frequency, const Q: usize> Default for TxQueue<T, Timer, Q> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Timer: Frequency, const Q: usize> TxQueue<T, Timer, Q> {
+    /// Creates a new, empty [`TxQueue`].
+    pub const fn new() -> Self {
+        Self {
+            classes: [
+                heapless::Vec::new(),
+                heapless::Vec::new(),
+                heapless::Vec::new(),
+            ],
+        }
+    }
+
+    /// Queue a frame for transmission.
+    ///
+    /// * `priority` - Priority class of the frame
+    /// * `msdu_handle` - Handle of the frame given by the upper layer
+    /// * `frame` - The frame
+    /// * `deadline` - The instant at which the frame expires, if any
+    ///
+    /// Returns the frame with [`DataError::TransactionOverflow`] if its
+    /// priority class is full.
+    pub fn enqueue(
+        &mut self,
+        priority: TxPriority,
+        msdu_handle: u8,
+        frame: T,
+        deadline: Option<Instant<Timer>>,
+    ) -> Result<(), (DataError, T)> {
+        self.classes[priority as usize]
+            .push(QueuedFrame {
+                msdu_handle,
+                deadline,
+                frame,
+            })
+            .map_err(|queued| {
+                MAC_COUNTERS.queue_overflows.increment();
+                (DataError::TransactionOverflow, queued.frame)
+            })
+    }
+
+    /// Take the next frame from the queue.
+    ///
+    /// Expired frames are returned first, highest priority class first.
+    /// Otherwise, the oldest frame of the highest non-empty priority class is
+    /// returned for transmission.
+    ///
+    /// * `now` - The current instant
+    pub fn dequeue(&mut self, now: Instant<Timer>) -> Option<TxQueueEntry<T, Timer>> {
+        for class in &mut self.classes {
+            if let Some(index) = class.iter().position(|queued| queued.is_expired(now)) {
+                return Some(TxQueueEntry::Expired(class.remove(index)));
+            }
+        }
+        self.classes
+            .iter_mut()
+            .find(|class| !class.is_empty())
+            .map(|class| TxQueueEntry::Transmit(class.remove(0)))
+    }
+
+    /// Return the instant at which the next frame expires.
+    pub fn next_deadline(&self) -> Option<Instant<Timer>> {
+        self.classes
+            .iter()
+            .flatten()
+            .filter_map(|queued| queued.deadline)
+            .min()
+    }
+
+    /// Remove the frame with the given handle (MCPS-PURGE).
+    pub fn purge(&mut self, msdu_handle: u8) -> Option<T> {
+        self.classes.iter_mut().find_map(|class| {
+            let index = class
+                .iter()
+                .position(|queued| queued.msdu_handle == msdu_handle)?;
+            Some(class.remove(index).frame)
+        })
+    }
+
+    /// Return the number of frames queued in the given priority class.
+    pub fn len(&self, priority: TxPriority) -> usize {
+        self.classes[priority as usize].len()
+    }
+
+    /// Return whether no frame is queued.
+    pub fn is_empty(&self) -> bool {
+        self.classes.iter().all(|class| class.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::time::Microseconds;
+
+    fn handle(entry: Option<TxQueueEntry<u8, Microseconds>>) -> Option<(bool, u8)> {
+        entry.map(|entry| match entry {
+            TxQueueEntry::Transmit(queued) => (false, queued.msdu_handle),
+            TxQueueEntry::Expired(queued) => (true, queued.msdu_handle),
+        })
+    }
+
+    #[test]
+    fn priority_classes() {
+        let mut queue = TxQueue::<u8, Microseconds, 2>::new();
+        let now = Instant::new(0);
+        queue.enqueue(TxPriority::Bulk, 1, 10, None).unwrap();
+        queue.enqueue(TxPriority::Normal, 2, 20, None).unwrap();
+        queue.enqueue(TxPriority::Bulk, 3, 30, None).unwrap();
+        assert!(matches!(
+            queue.enqueue(TxPriority::Bulk, 4, 40, None),
+            Err((DataError::TransactionOverflow, 40))
+        ));
+        queue
+            .enqueue(TxPriority::NetworkControl, 5, 50, None)
+            .unwrap();
+        assert_eq!(queue.len(TxPriority::Bulk), 2);
+
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 5)));
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 2)));
+        // Network control frames overtake queued bulk frames.
+        queue
+            .enqueue(TxPriority::NetworkControl, 6, 60, None)
+            .unwrap();
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 6)));
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 1)));
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 3)));
+        assert_eq!(handle(queue.dequeue(now)), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn deadlines() {
+        let mut queue = TxQueue::<u8, Microseconds, 4>::new();
+        queue
+            .enqueue(TxPriority::Normal, 1, 10, Some(Instant::new(2_000)))
+            .unwrap();
+        queue.enqueue(TxPriority::Bulk, 2, 20, None).unwrap();
+        queue
+            .enqueue(TxPriority::Bulk, 3, 30, Some(Instant::new(1_000)))
+            .unwrap();
+        queue.enqueue(TxPriority::Bulk, 4, 40, None).unwrap();
+        assert_eq!(queue.next_deadline(), Some(Instant::new(1_000)));
+
+        // Expired frames are confirmed before anything is transmitted.
+        let now = Instant::new(1_000);
+        assert_eq!(handle(queue.dequeue(now)), Some((true, 3)));
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 1)));
+        assert_eq!(queue.next_deadline(), None);
+
+        assert_eq!(queue.purge(3), None);
+        assert_eq!(queue.purge(4), Some(40));
+        assert_eq!(handle(queue.dequeue(now)), Some((false, 2)));
+        assert!(queue.is_empty());
+    }
+}
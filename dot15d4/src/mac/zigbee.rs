@@ -0,0 +1,199 @@
+//! Zigbee compatibility (Zigbee R23).
+//!
+//! Zigbee stacks frame their traffic as IEEE 802.15.4-2006 (or 2003) and
+//! neither send nor expect IEs or Enh-Acks. This module restricts the MAC
+//! accordingly, e.g. when testing coexistence with Zigbee devices:
+//!
+//! - [`pin()`] rewrites frames of version IEEE 802.15.4-2015 that don't need
+//!   any of its features to version IEEE 802.15.4-2006, e.g. frames built
+//!   with [`FrameBuilder::with_enhanced_frame_version()`], and reports the
+//!   features that cannot be expressed, see [`ZigbeeWarning`].
+//! - [`ack_frame()`] acknowledges frames with Imm-Acks only.
+//!
+//! Note: IEEE 802.15.4-2015 derives the presence of the PAN IDs from the PAN
+//!       ID compression flag differently. The flag is rewritten so that the
+//!       PAN IDs present in the frame keep their meaning, frames that elide
+//!       PAN IDs in ways IEEE 802.15.4-2006 doesn't know are rejected.
+//!
+//! [`FrameBuilder::with_enhanced_frame_version()`]: crate::mac::frame::mpdu::FrameBuilder::with_enhanced_frame_version
+#![allow(dead_code)]
+
+use crate::{
+    driver::frame::{AddressingMode, FrameControl, FrameType, FrameVersion},
+    mac::frame::FrameError,
+};
+
+use super::ack::{self, AckFrame};
+
+/// The frame version of frames sent to Zigbee devices.
+pub const FRAME_VERSION: FrameVersion = FrameVersion::Ieee802154_2006;
+
+/// A feature of IEEE 802.15.4-2015 and later requested for a frame that
+/// Zigbee devices don't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZigbeeWarning {
+    /// The frame is malformed.
+    Malformed(FrameError),
+    /// The frame type was introduced in IEEE 802.15.4-2015, e.g. multipurpose
+    /// frames.
+    FrameType,
+    /// The frame carries IEs.
+    InformationElements,
+    /// The sequence number is suppressed.
+    SequenceNumberSuppression,
+    /// The PAN IDs are elided in a way only IEEE 802.15.4-2015 supports.
+    PanIdCompression,
+    /// The frame is secured according to IEEE 802.15.4-2015, e.g. with a
+    /// suppressed frame counter.
+    Security,
+    /// The frame version is reserved.
+    FrameVersion,
+}
+
+impl From<FrameError> for ZigbeeWarning {
+    fn from(error: FrameError) -> Self {
+        Self::Malformed(error)
+    }
+}
+
+/// Pin the given frame to IEEE 802.15.4-2006 framing, see the module
+/// documentation.
+///
+/// Returns whether the frame version was rewritten. Frames of version IEEE
+/// 802.15.4-2003 and 2006 are left unchanged.
+///
+/// * `mpdu` - MPDU to be sent (without FCS)
+///
+/// # Errors
+///
+/// Returns the first feature of the frame that IEEE 802.15.4-2006 cannot
+/// express. The frame is left unchanged in that case.
+pub fn pin(mpdu: &mut [u8]) -> Result<bool, ZigbeeWarning> {
+    let mut frame_control = FrameControl::new(mpdu)?;
+    match frame_control.frame_version() {
+        FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => return Ok(false),
+        FrameVersion::Ieee802154 => {}
+        FrameVersion::Unknown => return Err(ZigbeeWarning::FrameVersion),
+    }
+
+    if !matches!(
+        frame_control.frame_type(),
+        FrameType::Beacon | FrameType::Data | FrameType::Ack | FrameType::MacCommand
+    ) {
+        return Err(ZigbeeWarning::FrameType);
+    }
+    if frame_control.information_elements_present() {
+        return Err(ZigbeeWarning::InformationElements);
+    }
+    if frame_control.sequence_number_suppression() {
+        return Err(ZigbeeWarning::SequenceNumberSuppression);
+    }
+    if frame_control.security_enabled() {
+        return Err(ZigbeeWarning::Security);
+    }
+
+    let pan_id_compression = match (
+        frame_control.dst_addressing_mode(),
+        frame_control.src_addressing_mode(),
+        frame_control.pan_id_compression(),
+    ) {
+        // IEEE 802.15.4-2015 omits the source PAN ID of frames between
+        // extended addresses without PAN ID compression.
+        (AddressingMode::Extended, AddressingMode::Extended, false) => true,
+        (AddressingMode::Extended, AddressingMode::Extended, true) => {
+            return Err(ZigbeeWarning::PanIdCompression)
+        }
+        (AddressingMode::Absent, _, true) | (_, AddressingMode::Absent, true) => {
+            return Err(ZigbeeWarning::PanIdCompression)
+        }
+        (_, _, pan_id_compression) => pan_id_compression,
+    };
+
+    frame_control.set_pan_id_compression(pan_id_compression);
+    frame_control.set_frame_version(FRAME_VERSION);
+    Ok(true)
+}
+
+/// Build the Imm-Ack of a received frame, see the module documentation.
+///
+/// Returns `None` if the frame doesn't request an acknowledgement or requests
+/// an Enh-Ack, which Zigbee devices never do.
+///
+/// * `mpdu` - Received MPDU (without FCS)
+pub fn ack_frame(mpdu: &[u8]) -> Result<Option<AckFrame>, FrameError> {
+    if FrameControl::new(mpdu)?.frame_version() == FrameVersion::Ieee802154 {
+        return Ok(None);
+    }
+    ack::ack_frame(mpdu, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return a data frame of version IEEE 802.15.4-2015 with the given
+    /// addressing modes and PAN ID compression, requesting an ACK.
+    fn frame(dst: u8, src: u8, pan_id_compression: bool) -> [u8; 2] {
+        let frame_control = 0x0021
+            | ((pan_id_compression as u16) << 6)
+            | ((dst as u16) << 10)
+            | (0b10 << 12)
+            | ((src as u16) << 14);
+        frame_control.to_le_bytes()
+    }
+
+    #[test]
+    fn pin_frames() {
+        let mut mpdu = frame(0b10, 0b10, true);
+        assert_eq!(pin(&mut mpdu), Ok(true));
+        let frame_control = FrameControl::new(&mpdu[..]).unwrap();
+        assert_eq!(frame_control.frame_version(), FRAME_VERSION);
+        assert!(frame_control.pan_id_compression());
+        assert!(frame_control.ack_request());
+        assert_eq!(pin(&mut mpdu), Ok(false));
+
+        // The destination PAN ID only.
+        let mut mpdu = frame(0b11, 0b11, false);
+        assert_eq!(pin(&mut mpdu), Ok(true));
+        assert!(FrameControl::new(&mpdu[..]).unwrap().pan_id_compression());
+
+        // No PAN ID at all.
+        let mut mpdu = frame(0b11, 0b11, true);
+        assert_eq!(pin(&mut mpdu), Err(ZigbeeWarning::PanIdCompression));
+        assert_eq!(mpdu, frame(0b11, 0b11, true));
+        let mut mpdu = frame(0b10, 0b00, true);
+        assert_eq!(pin(&mut mpdu), Err(ZigbeeWarning::PanIdCompression));
+    }
+
+    #[test]
+    fn reject_later_features() {
+        let with = |bits: u16| {
+            let frame_control = u16::from_le_bytes(frame(0b10, 0b10, true)) | bits;
+            frame_control.to_le_bytes()
+        };
+        assert_eq!(
+            pin(&mut with(1 << 9)),
+            Err(ZigbeeWarning::InformationElements)
+        );
+        assert_eq!(
+            pin(&mut with(1 << 8)),
+            Err(ZigbeeWarning::SequenceNumberSuppression)
+        );
+        assert_eq!(pin(&mut with(1 << 3)), Err(ZigbeeWarning::Security));
+        assert_eq!(pin(&mut with(0b100)), Err(ZigbeeWarning::FrameType));
+        assert_eq!(pin(&mut with(0b11 << 12)), Err(ZigbeeWarning::FrameVersion));
+        assert!(matches!(pin(&mut [0x41]), Err(ZigbeeWarning::Malformed(_))));
+    }
+
+    #[test]
+    fn imm_ack_only() {
+        // Data frame of version IEEE 802.15.4-2006 requesting an ACK.
+        let mpdu = [0x61, 0x98, 0x2a, 0xcd, 0xab, 0x02, 0x00, 0x01, 0x00];
+        let ack = ack_frame(&mpdu).unwrap().unwrap();
+        assert_eq!(&ack[..], &[0x02, 0x10, 0x2a]);
+
+        let mut mpdu = mpdu;
+        mpdu[1] = 0xa8;
+        assert_eq!(ack_frame(&mpdu), Ok(None));
+    }
+}
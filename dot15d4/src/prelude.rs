@@ -0,0 +1,45 @@
+//! The stable, user-facing API of this crate.
+//!
+//! Integrators should import everything they need from here:
+//!
+//! ```ignore
+//! use dot15d4::prelude::*;
+//! ```
+//!
+//! Items that are reachable via other paths but not re-exported from the
+//! prelude are considered internal. They may change between releases without
+//! notice.
+//!
+//! The prelude is versioned. [`prelude`](self) re-exports the latest version.
+//! Breaking changes to the prelude will be introduced in a new version module
+//! so that integrators can pin an older version while migrating.
+
+/// Version 1 of the prelude.
+pub mod v1 {
+    pub use crate::{
+        driver::{
+            frame::{Address, AddressingMode, FrameType, FrameVersion},
+            link_quality::LinkQuality,
+            tasks::{RadioDriver, TaskOff},
+            DriverConfig, RadioDriverApi,
+        },
+        export::RngCore,
+        mac::{
+            frame::{
+                mpdu::MpduFrame,
+                repr::{IeListRepr, IeRepr, MpduRepr, SeqNrRepr},
+            },
+            primitives::{
+                AckType, BeaconNotifyIndication, BeaconRequest, DataConfirm, DataIndication,
+                DataRequest, MacIndication, MacRequest, SetRequestAttribute, TxOptions,
+                TxParameters,
+            },
+            MacBufferAllocator, MacIndicationChannel, MacIndicationReceiver, MacIndicationSender,
+            MacRequestChannel, MacRequestReceiver, MacRequestSender, MAC_BUFFER_SIZE,
+            MAC_NUM_REQUIRED_BUFFERS,
+        },
+        Device,
+    };
+}
+
+pub use v1::*;
@@ -0,0 +1,422 @@
+//! [`smoltcp`] integration.
+//!
+//! [`SmoltcpDevice`] exposes the MAC data service as a [`Device`] of the
+//! IEEE 802.15.4 medium so that 6LoWPAN and IPv6 run directly on top of the
+//! MAC. smoltcp builds and parses the MAC header itself, the device exchanges
+//! MPDUs without FCS with it. The MAC fills in the sequence number and
+//! acknowledges frames.
+//!
+//! smoltcp addresses IEEE 802.15.4 devices in big-endian notation while this
+//! crate keeps addressing fields in on-air byte order, see
+//! [`address_to_smoltcp()`], [`address_from_smoltcp()`] and the PAN ID
+//! counterparts.
+//!
+//! smoltcp polls devices synchronously. The device never blocks: if no frame
+//! has been indicated by the MAC, no buffer is free or no request can be
+//! sent, it returns no token and the application polls the interface again
+//! later, e.g. when [`Interface::poll_delay()`] expires. Indications other
+//! than data frames are dropped.
+//!
+//! [`Interface::poll_delay()`]: smoltcp::iface::Interface::poll_delay
+
+use core::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem,
+    num::NonZero,
+    task::{Context, Poll, Waker},
+};
+
+use smoltcp::{
+    iface::Config,
+    phy::{self, ChecksumCapabilities, Device, DeviceCapabilities, Medium},
+    time::Instant,
+    wire::{HardwareAddress, Ieee802154Address, Ieee802154Pan},
+};
+
+use crate::{
+    driver::{
+        constants::{FCS_LEN, PHY_MAX_PACKET_SIZE_127},
+        frame::{
+            Address, ExtendedAddress, PanId, RadioFrame, RadioFrameRepr, RadioFrameSized,
+            RadioFrameUnsized, ShortAddress,
+        },
+        DriverConfig,
+    },
+    mac::{
+        frame::mpdu::MpduFrame,
+        primitives::{DataRequest, MacIndication, MacRequest},
+        MacBufferAllocator, MacIndicationReceiver, MacRequestSender,
+    },
+    util::{
+        allocator::IntoBuffer,
+        frame::Frame,
+        sync::{ConsumerToken, RequestToken, ResponseToken},
+    },
+};
+
+/// Converts the given address to a smoltcp address.
+pub fn address_to_smoltcp<Bytes: AsRef<[u8]>>(address: &Address<Bytes>) -> Ieee802154Address {
+    match address {
+        Address::Absent => Ieee802154Address::Absent,
+        Address::Short(address) => Ieee802154Address::Short(address.into_be_bytes()),
+        Address::Extended(address) => Ieee802154Address::Extended(address.into_be_bytes()),
+    }
+}
+
+/// Converts the given smoltcp address to an address.
+pub fn address_from_smoltcp(address: Ieee802154Address) -> Address<heapless::Vec<u8, 8>> {
+    // smoltcp keeps addresses in big-endian notation.
+    let le_bytes = |be_bytes: &[u8]| be_bytes.iter().rev().copied().collect();
+    match address {
+        Ieee802154Address::Absent => Address::Absent,
+        Ieee802154Address::Short(be_bytes) => {
+            Address::Short(ShortAddress::new(le_bytes(&be_bytes)))
+        }
+        Ieee802154Address::Extended(be_bytes) => {
+            Address::Extended(ExtendedAddress::new(le_bytes(&be_bytes)))
+        }
+    }
+}
+
+/// Converts the given PAN ID to a smoltcp PAN ID.
+pub fn pan_id_to_smoltcp<Bytes: AsRef<[u8]>>(pan_id: &PanId<Bytes>) -> Ieee802154Pan {
+    Ieee802154Pan(pan_id.into_u16())
+}
+
+/// Converts the given smoltcp PAN ID to a PAN ID.
+pub fn pan_id_from_smoltcp(pan_id: Ieee802154Pan) -> PanId<[u8; 2]> {
+    PanId::from_u16(pan_id.0)
+}
+
+/// The MAC data service as a smoltcp [`Device`], see the module
+/// documentation.
+pub struct SmoltcpDevice<'device, RadioDriverImpl: DriverConfig> {
+    buffer_allocator: MacBufferAllocator,
+    request_sender: MacRequestSender<'device>,
+    indication_receiver: MacIndicationReceiver<'device>,
+    consumer_token: RefCell<ConsumerToken>,
+    pan_id: PanId<[u8; 2]>,
+    driver: PhantomData<RadioDriverImpl>,
+}
+
+impl<'device, RadioDriverImpl: DriverConfig> SmoltcpDevice<'device, RadioDriverImpl> {
+    const RADIO_FRAME_REPR: RadioFrameRepr<RadioDriverImpl, RadioFrameUnsized> =
+        RadioFrameRepr::<RadioDriverImpl, RadioFrameUnsized>::new();
+
+    // Note: smoltcp only tells the frame length when consuming the token, so
+    //       we always allocate the max buffer size.
+    const BUFFER_LENGTH: usize = Self::RADIO_FRAME_REPR.max_buffer_length() as usize;
+
+    /// Creates a device on top of the given MAC channels.
+    ///
+    /// * `pan_id` - The PAN the interface is part of, see
+    ///   [`SmoltcpDevice::interface_config()`]
+    pub fn new(
+        buffer_allocator: MacBufferAllocator,
+        request_sender: MacRequestSender<'device>,
+        indication_receiver: MacIndicationReceiver<'device>,
+        pan_id: PanId<[u8; 2]>,
+    ) -> Self {
+        let consumer_token = indication_receiver
+            .try_allocate_consumer_token()
+            .expect("consumer slot");
+        Self {
+            buffer_allocator,
+            request_sender,
+            indication_receiver,
+            consumer_token: RefCell::new(consumer_token),
+            pan_id,
+            driver: PhantomData,
+        }
+    }
+
+    /// Return the PAN ID of the device.
+    pub fn pan_id(&self) -> PanId<[u8; 2]> {
+        self.pan_id
+    }
+
+    /// Return the configuration of a smoltcp interface on this device.
+    ///
+    /// smoltcp fills in the source and destination PAN IDs of outgoing frames
+    /// and drops incoming frames of other PANs based on the configuration. The
+    /// caller sets the random seed.
+    ///
+    /// * `address` - The extended address of the radio, e.g.
+    ///   [`RadioDriverApi::ieee802154_address()`](crate::driver::RadioDriverApi::ieee802154_address)
+    pub fn interface_config(&self, address: [u8; 8]) -> Config {
+        let mut config = Config::new(HardwareAddress::Ieee802154(Ieee802154Address::Extended(
+            address,
+        )));
+        config.pan_id = Some(pan_id_to_smoltcp(&self.pan_id));
+        config
+    }
+
+    fn rx_token(&self, cx: &mut Context) -> Option<RxToken<'_, 'device>> {
+        // smoltcp only consumes data frames: drop any other indication and
+        // poll for the next one.
+        let (response_token, mpdu) = loop {
+            let (response_token, indication) = match self.indication_receiver.poll_wait_for_request(
+                cx,
+                &mut self.consumer_token.borrow_mut(),
+                &(),
+            ) {
+                Poll::Ready(request) => request,
+                Poll::Pending => return None,
+            };
+
+            match indication {
+                MacIndication::McpsData(data_indication) => {
+                    break (response_token, data_indication.mpdu)
+                }
+                MacIndication::MlmeBeaconNotify(beacon_notify_indication) => {
+                    self.indication_receiver.received(response_token, ());
+                    // Safety: Indications are allocated from the same
+                    //         allocator that the device was given.
+                    unsafe {
+                        self.buffer_allocator
+                            .deallocate_buffer(beacon_notify_indication.mpdu.into_buffer());
+                    }
+                }
+            }
+        };
+        Some(RxToken {
+            indication_receiver: &self.indication_receiver,
+            radio_frame: mpdu.into_radio_frame::<RadioDriverImpl>(),
+            response_token,
+            buffer_allocator: &self.buffer_allocator,
+        })
+    }
+
+    fn tx_token(&self, cx: &mut Context) -> Option<TxToken<'_, 'device>> {
+        // Safety: Always allocate the buffer before trying to allocate a
+        //         request token to avoid deadlock.
+        let buffer = self
+            .buffer_allocator
+            .try_allocate_buffer(Self::BUFFER_LENGTH)
+            .ok()?;
+
+        let request_token = match self.request_sender.poll_allocate_request_token(cx) {
+            Poll::Ready(request_token) => request_token,
+            Poll::Pending => {
+                // Safety: The buffer was allocated from the same allocator it
+                //         is now de-allocated from.
+                unsafe {
+                    self.buffer_allocator.deallocate_buffer(buffer);
+                }
+                return None;
+            }
+        };
+
+        Some(TxToken {
+            request_sender: &self.request_sender,
+            radio_frame: Some(RadioFrame::<RadioFrameUnsized>::new::<RadioDriverImpl>(
+                buffer,
+            )),
+            request_token: Some(request_token),
+            buffer_allocator: &self.buffer_allocator,
+        })
+    }
+}
+
+impl<'device, RadioDriverImpl: DriverConfig> Device for SmoltcpDevice<'device, RadioDriverImpl> {
+    type RxToken<'token>
+        = RxToken<'token, 'device>
+    where
+        Self: 'token;
+    type TxToken<'token>
+        = TxToken<'token, 'device>
+    where
+        Self: 'token;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let tx_token = self.tx_token(&mut cx)?;
+        let rx_token = self.rx_token(&mut cx)?;
+        Some((rx_token, tx_token))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        self.tx_token(&mut Context::from_waker(Waker::noop()))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.medium = Medium::Ieee802154;
+        caps.max_transmission_unit = PHY_MAX_PACKET_SIZE_127 - FCS_LEN;
+        caps.max_burst_size = Some(1);
+        // The radio appends and checks the FCS, smoltcp never sees it. IP, UDP
+        // and ICMP checksums are computed and verified by smoltcp.
+        caps.checksum = ChecksumCapabilities::default();
+        caps
+    }
+}
+
+/// Token to transmit a frame, see [`phy::TxToken`].
+pub struct TxToken<'token, 'device> {
+    request_sender: &'token MacRequestSender<'device>,
+    radio_frame: Option<RadioFrame<RadioFrameUnsized>>,
+    request_token: Option<RequestToken>,
+    buffer_allocator: &'token MacBufferAllocator,
+}
+
+impl Drop for TxToken<'_, '_> {
+    fn drop(&mut self) {
+        // Safety: Release the buffer last to avoid deadlock.
+        if let Some(request_token) = self.request_token.take() {
+            self.request_sender.release_request_token(request_token);
+        }
+        if let Some(radio_frame) = self.radio_frame.take() {
+            // Safety: We allocated the buffer ourselves from the same allocator.
+            unsafe {
+                self.buffer_allocator
+                    .deallocate_buffer(radio_frame.into_buffer());
+            }
+        }
+    }
+}
+
+impl phy::TxToken for TxToken<'_, '_> {
+    fn consume<R, F>(mut self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut radio_frame = self
+            .radio_frame
+            .take()
+            .unwrap()
+            .with_size(NonZero::new(len as u16).expect("invalid len"));
+
+        let result = f(radio_frame.sdu_mut());
+
+        // smoltcp requests an ACK for unicast frames in the frame control
+        // field, the MAC only waits for it if told so.
+        let mut data_request = DataRequest::new(MpduFrame::from_radio_frame(radio_frame));
+        let ack_tx = matches!(data_request.dst_addr(), Ok(address) if address.is_unicast());
+        data_request.tx_options().set_ack_tx(ack_tx);
+
+        let request = MacRequest::McpsDataRequest(data_request);
+        self.request_sender
+            .send_request_no_response(self.request_token.take().unwrap(), request);
+
+        // No need to drop a consumed token.
+        mem::forget(self);
+
+        result
+    }
+}
+
+/// Token to receive a frame, see [`phy::RxToken`].
+pub struct RxToken<'token, 'device> {
+    indication_receiver: &'token MacIndicationReceiver<'device>,
+    radio_frame: RadioFrame<RadioFrameSized>,
+    response_token: ResponseToken,
+    buffer_allocator: &'token MacBufferAllocator,
+}
+
+impl phy::RxToken for RxToken<'_, '_> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let result = f(self.radio_frame.sdu_ref());
+        self.indication_receiver.received(self.response_token, ());
+        // Safety: We use the MAC service's allocator to release a buffer
+        //         allocated by the MAC service.
+        unsafe {
+            self.buffer_allocator
+                .deallocate_buffer(self.radio_frame.into_buffer());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mac::{
+        primitives::BeaconNotifyIndication,
+        test_helpers::{mac_buffer_allocator, TestDriverConfig},
+        MacIndicationChannel, MacRequestChannel,
+    };
+
+    #[test]
+    fn addresses() {
+        let short = Address::Short(ShortAddress::new_owned([0x34, 0x12]));
+        assert_eq!(
+            address_to_smoltcp(&short),
+            Ieee802154Address::Short([0x12, 0x34])
+        );
+        let extended = Address::Extended(ExtendedAddress::new_owned([1, 2, 3, 4, 5, 6, 7, 8]));
+        let smoltcp_extended = Ieee802154Address::Extended([8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(address_to_smoltcp(&extended), smoltcp_extended);
+        assert_eq!(
+            address_to_smoltcp(&Address::<[u8; 2]>::Absent),
+            Ieee802154Address::Absent
+        );
+
+        let address = address_from_smoltcp(smoltcp_extended);
+        assert_eq!(address.as_le_bytes(), [1, 2, 3, 4, 5, 6, 7, 8]);
+        let address = address_from_smoltcp(Ieee802154Address::BROADCAST);
+        assert!(address.is_broadcast());
+        assert!(address_from_smoltcp(Ieee802154Address::Absent).is_absent());
+    }
+
+    #[test]
+    fn pan_ids() {
+        let pan_id = PanId::new_owned([0xcd, 0xab]);
+        assert_eq!(pan_id_to_smoltcp(&pan_id), Ieee802154Pan(0xabcd));
+        assert_eq!(pan_id_from_smoltcp(Ieee802154Pan(0xabcd)), pan_id);
+    }
+
+    #[test]
+    fn tokens_are_not_available() {
+        let buffer_allocator = mac_buffer_allocator();
+        let request_channel = MacRequestChannel::new();
+        let indication_channel = MacIndicationChannel::new();
+        let indication_sender = indication_channel.sender();
+        let mut device = SmoltcpDevice::<TestDriverConfig>::new(
+            buffer_allocator,
+            request_channel.sender(),
+            indication_channel.receiver(),
+            PanId::new_owned([0xcd, 0xab]),
+        );
+        let now = Instant::from_micros(0);
+
+        // Beacons are not forwarded to smoltcp.
+        let buffer = buffer_allocator
+            .try_allocate_buffer(SmoltcpDevice::<TestDriverConfig>::BUFFER_LENGTH)
+            .unwrap();
+        let radio_frame = RadioFrame::new::<TestDriverConfig>(buffer)
+            .with_size(NonZero::new(FCS_LEN as u16 + 3).unwrap());
+        let request_token = indication_sender.try_allocate_request_token().unwrap();
+        indication_sender.send_request_no_response(
+            request_token,
+            MacIndication::MlmeBeaconNotify(BeaconNotifyIndication {
+                mpdu: MpduFrame::from_radio_frame(radio_frame),
+                timestamp: 0,
+            }),
+        );
+        assert!(device.receive(now).is_none());
+
+        // No buffer left to send a frame.
+        let buffers = [
+            buffer_allocator
+                .try_allocate_buffer(SmoltcpDevice::<TestDriverConfig>::BUFFER_LENGTH)
+                .unwrap(),
+            buffer_allocator
+                .try_allocate_buffer(SmoltcpDevice::<TestDriverConfig>::BUFFER_LENGTH)
+                .unwrap(),
+        ];
+        assert!(device.transmit(now).is_none());
+
+        for buffer in buffers {
+            // Safety: The buffers were allocated above.
+            unsafe { buffer_allocator.deallocate_buffer(buffer) };
+        }
+        assert!(device.transmit(now).is_some());
+
+        // Applications never drop the device and its consumer token.
+        mem::forget(device);
+    }
+}
@@ -1,16 +1,71 @@
 #![no_main]
 
-use dot15d4_frame::{DataFrame, FrameRepr};
+use dot15d4_driver::frame::{AddressingFields, AddressingRepr, FrameControl, FrameType};
+use dot15d4_frame::{
+    fcs::check_fcs,
+    fields::{BeaconFields, NestedIeRepr, NestedIes},
+};
 
 use libfuzzer_sys::{fuzz_target, Corpus};
 
+const MAX_PHY_PACKET_SIZE: usize = 127;
+const FCS_LEN: usize = 2;
+
 fuzz_target!(|data: &[u8]| -> Corpus {
-    if data.len() > 127 {
+    if data.len() > MAX_PHY_PACKET_SIZE || data.len() < FCS_LEN {
         return Corpus::Reject;
     }
 
-    if let Ok(frame) = DataFrame::new(data) {
-        let _ = FrameRepr::parse(&frame);
+    let (mpdu, fcs) = data.split_at(data.len() - FCS_LEN);
+    let _ = check_fcs(mpdu, fcs);
+
+    let Ok(fc) = FrameControl::new(mpdu) else {
+        return Corpus::Keep;
+    };
+    let mut offset = 2 + !fc.sequence_number_suppression() as usize;
+    let frame_type = fc.frame_type();
+
+    let Ok(addressing) = AddressingRepr::from_frame_control(fc) else {
+        return Corpus::Keep;
+    };
+    if let Some(addressing) = addressing {
+        let Ok(length) = addressing.addressing_fields_length() else {
+            return Corpus::Keep;
+        };
+        let Some(bytes) = mpdu.get(offset..offset + length as usize) else {
+            return Corpus::Keep;
+        };
+        if let Ok(fields) = AddressingFields::new(bytes, addressing) {
+            let _ = (fields.dst_pan_id(), fields.dst_address());
+            let _ = (fields.src_pan_id(), fields.src_address());
+        }
+        offset += length as usize;
+    }
+
+    let Some(rest) = mpdu.get(offset..) else {
+        return Corpus::Keep;
+    };
+
+    if frame_type == FrameType::Beacon {
+        if let Ok(beacon) = BeaconFields::new(rest) {
+            let superframe_specification = beacon.superframe_specification();
+            let _ = superframe_specification.beacon_order();
+            beacon.gts_fields().descriptors().for_each(drop);
+            let pending_addresses = beacon.pending_address_fields();
+            pending_addresses.short_addresses().for_each(drop);
+            pending_addresses.extended_addresses().for_each(drop);
+            let _ = beacon.beacon_payload();
+        }
+    }
+
+    // Interpret the remainder as a nested IE list to exercise the IE readers.
+    for nested_ie in NestedIes::new(rest).with_mtu(MAX_PHY_PACKET_SIZE as u16) {
+        let Ok(nested_ie) = nested_ie else {
+            break;
+        };
+        if let Ok(repr) = NestedIeRepr::parse_or_unknown(nested_ie) {
+            let _ = format!("{repr}");
+        }
     }
 
     Corpus::Keep
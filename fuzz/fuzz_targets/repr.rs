@@ -1,15 +1,192 @@
 #![no_main]
 
-use dot15d4_frame::{DataFrame, FrameRepr};
+use arbitrary::Arbitrary;
+use dot15d4_driver::frame::{
+    Address, AddressingFields, AddressingRepr, ExtendedAddress, FrameControl, FrameType,
+    FrameVersion, PanId, PanIdCompressionRepr, ShortAddress,
+};
+use dot15d4_frame::{
+    mpdu::FrameBuilder,
+    repr::{IeRepr, SecurityRepr},
+};
 
-use libfuzzer_sys::fuzz_target;
+use libfuzzer_sys::{fuzz_target, Corpus};
 
-fuzz_target!(|repr: FrameRepr| {
-    if repr.validate().is_err() {
-        return;
+const MAX_PHY_PACKET_SIZE: usize = 127;
+const FCS_LEN: usize = 2;
+
+#[derive(Debug, Arbitrary)]
+enum FuzzAddress {
+    Short([u8; 2]),
+    Extended([u8; 8]),
+}
+
+impl FuzzAddress {
+    fn as_address(&self) -> Address<&[u8]> {
+        match self {
+            FuzzAddress::Short(bytes) => Address::Short(ShortAddress::new(&bytes[..])),
+            FuzzAddress::Extended(bytes) => Address::Extended(ExtendedAddress::new(&bytes[..])),
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzFrame<'a> {
+    frame_type: FrameType,
+    seq_nr: Option<u8>,
+    ack_request: bool,
+    frame_pending: bool,
+    dst: Option<(u16, FuzzAddress)>,
+    src: Option<(u16, FuzzAddress)>,
+    ies: &'a [u8],
+    payload: &'a [u8],
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    frame: FuzzFrame<'a>,
+    addressing: AddressingRepr,
+    security: SecurityRepr,
+    ie: IeRepr<'a>,
+}
+
+fuzz_target!(|input: Input| -> Corpus {
+    if input.frame.frame_type == FrameType::Unknown {
+        return Corpus::Reject;
+    }
+
+    let _ = input.security.aux_sec_header_length();
+    let _ = input.security.mic_length();
+    if let IeRepr::TschSlotframeAndLinkNestedIe(slotframes) = input.ie {
+        // IEs that cannot fit into a frame would overflow the length
+        // calculation.
+        let links: usize = slotframes.iter().map(|&links| links as usize).sum();
+        if slotframes.len() * 4 + links * 5 > MAX_PHY_PACKET_SIZE {
+            return Corpus::Reject;
+        }
     }
+    let _ = input.ie.length();
 
-    let len = repr.buffer_len();
-    let mut buffer = vec![0; len];
-    repr.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    addressing_round_trip(input.addressing);
+    frame_round_trip(&input.frame)
 });
+
+/// Encodes the addressing representation into a frame control field and
+/// checks that it is derived back from it.
+fn addressing_round_trip(addressing: AddressingRepr) {
+    let Ok(length) = addressing.addressing_fields_length() else {
+        return;
+    };
+
+    let mut fc = [0; 2];
+    let mut frame_control = FrameControl::new_unchecked(&mut fc[..]);
+    frame_control.set_frame_version(match addressing.pan_id_compression_repr() {
+        PanIdCompressionRepr::Legacy => FrameVersion::Ieee802154_2006,
+        PanIdCompressionRepr::Yes | PanIdCompressionRepr::No => FrameVersion::Ieee802154,
+    });
+    frame_control.set_pan_id_compression(addressing.pan_id_compression());
+    frame_control.set_dst_addressing_mode(addressing.dst_addr_mode());
+    frame_control.set_src_addressing_mode(addressing.src_addr_mode());
+
+    let parsed = AddressingRepr::from_frame_control(FrameControl::new_unchecked(&fc[..]));
+    match parsed {
+        Ok(Some(parsed)) => {
+            assert_eq!(parsed.dst_addr_mode(), addressing.dst_addr_mode());
+            assert_eq!(parsed.src_addr_mode(), addressing.src_addr_mode());
+            assert_eq!(
+                parsed.addressing_fields_lengths().ok(),
+                addressing.addressing_fields_lengths().ok()
+            );
+
+            let mut buffer = [0; 2 * (2 + 8)];
+            assert!(AddressingFields::new(&mut buffer[..length as usize], parsed).is_ok());
+        }
+        Ok(None) => assert_eq!(length, 0),
+        // Not every combination of addressing modes and PAN ID compression is
+        // valid.
+        Err(_) => {}
+    }
+}
+
+/// Emits the frame and checks that the emitted fields are parsed back.
+fn frame_round_trip(frame: &FuzzFrame) -> Corpus {
+    let dst = frame
+        .dst
+        .as_ref()
+        .map(|(pan_id, address)| (PanId::from_u16(*pan_id), address.as_address()));
+    let src = frame
+        .src
+        .as_ref()
+        .map(|(pan_id, address)| (PanId::from_u16(*pan_id), address.as_address()));
+
+    let mut builder = FrameBuilder::new(frame.frame_type)
+        .with_ack_request(frame.ack_request)
+        .with_frame_pending(frame.frame_pending);
+    if let Some(seq_nr) = frame.seq_nr {
+        builder = builder.with_sequence_number(seq_nr);
+    }
+    let builder = builder
+        .with_addressing(dst, src)
+        .without_security()
+        .with_ies(frame.ies)
+        .with_payload(frame.payload);
+
+    let mpdu_length = builder.mpdu_length_wo_fcs();
+    if mpdu_length + FCS_LEN > MAX_PHY_PACKET_SIZE {
+        return Corpus::Reject;
+    }
+
+    let mut buffer = [0; MAX_PHY_PACKET_SIZE];
+    assert_eq!(builder.emit(&mut buffer), Ok(mpdu_length));
+    let mpdu = &buffer[..mpdu_length];
+
+    let fc = FrameControl::new(mpdu).unwrap();
+    assert_eq!(fc.frame_type(), frame.frame_type);
+    assert_eq!(fc.frame_version(), builder.frame_version());
+    assert_eq!(fc.ack_request(), frame.ack_request);
+    assert_eq!(fc.frame_pending(), frame.frame_pending);
+    assert_eq!(fc.sequence_number_suppression(), frame.seq_nr.is_none());
+    assert_eq!(fc.information_elements_present(), !frame.ies.is_empty());
+
+    let mut offset = 2;
+    if let Some(seq_nr) = frame.seq_nr {
+        assert_eq!(mpdu[offset], seq_nr);
+        offset += 1;
+    }
+
+    if let Some(addressing) = AddressingRepr::from_frame_control(fc).unwrap() {
+        let length = addressing.addressing_fields_length().unwrap() as usize;
+        let fields = AddressingFields::new(&mpdu[offset..offset + length], addressing).unwrap();
+
+        assert_eq!(
+            fields.dst_address().filter(|address| !address.is_absent()),
+            dst.map(|(_, address)| address)
+        );
+        assert_eq!(
+            fields.src_address().filter(|address| !address.is_absent()),
+            src.map(|(_, address)| address)
+        );
+
+        // PAN IDs may be elided but those that are present must match.
+        if let Some(pan_id) = fields.dst_pan_id() {
+            let expected = dst.or(src).map(|(pan_id, _)| pan_id.into_u16());
+            assert_eq!(Some(pan_id.into_u16()), expected);
+        }
+        if let Some(pan_id) = fields.src_pan_id() {
+            assert_eq!(
+                Some(pan_id.into_u16()),
+                src.map(|(pan_id, _)| pan_id.into_u16())
+            );
+        }
+
+        offset += length;
+    } else {
+        assert!(dst.is_none() && src.is_none());
+    }
+
+    assert_eq!(&mpdu[offset..offset + frame.ies.len()], frame.ies);
+    offset += frame.ies.len();
+    assert_eq!(&mpdu[offset..], frame.payload);
+
+    Corpus::Keep
+}
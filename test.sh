@@ -17,3 +17,21 @@ cargo test -p dot15d4-frame --no-default-features --features=security,ies
 
 echo "TEST: std"
 cargo test --features=std
+
+echo "TEST: linux"
+cargo test -p dot15d4-driver --features=linux
+
+echo "TEST: serial"
+cargo test -p dot15d4-driver --features=serial
+
+echo "TEST: smoltcp"
+cargo test -p dot15d4 --features=std,smoltcp
+
+echo "TEST: thread"
+cargo test -p dot15d4 --features=std,thread
+
+echo "TEST: zigbee"
+cargo test -p dot15d4 --features=std,zigbee
+
+echo "TEST: counters"
+cargo test -p dot15d4 --features=std,counters